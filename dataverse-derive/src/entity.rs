@@ -0,0 +1,227 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, LitStr, parse2};
+
+/// Table-level options from `#[dataverse(...)]` on the struct.
+#[derive(Default)]
+struct EntityAttrs {
+    logical_name: Option<String>,
+    set_name: Option<String>,
+}
+
+/// Field-level options from `#[dataverse(...)]` on a field.
+#[derive(Default)]
+struct FieldAttrs {
+    id: bool,
+    rename: Option<String>,
+    lookup: Option<String>,
+    nav: Option<String>,
+    read_only: bool,
+    skip: bool,
+}
+
+pub fn expand(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = match parse2(input) {
+        Ok(i) => i,
+        Err(e) => return e.to_compile_error(),
+    };
+    match expand_entity(&input) {
+        Ok(tokens) => tokens,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn expand_entity(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "DataverseEntity can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "DataverseEntity can only be derived for structs",
+            ));
+        }
+    };
+
+    let entity_attrs = parse_entity_attrs(&input.attrs)?;
+    let Some(logical_name) = entity_attrs.logical_name else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[dataverse(entity = \"...\")]`",
+        ));
+    };
+
+    let mut columns = Vec::new();
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+    let mut has_id = false;
+
+    for field in fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let attrs = parse_field_attrs(&field.attrs)?;
+
+        if attrs.skip {
+            reads.push(quote! { #ident: ::core::default::Default::default() });
+            continue;
+        }
+
+        if attrs.id {
+            if has_id {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "only one field can be marked `#[dataverse(id)]`",
+                ));
+            }
+            if attrs.lookup.is_some() {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`id` and `lookup` can't be combined",
+                ));
+            }
+            has_id = true;
+
+            // Dataverse primary keys are `<logicalname>id` unless stated otherwise
+            let column = attrs
+                .rename
+                .unwrap_or_else(|| format!("{}id", logical_name));
+            reads.push(quote! {
+                #ident: ::dataverse_lib::model::FromValue::from_field(
+                    #column,
+                    record
+                        .id()
+                        .map(::dataverse_lib::model::Value::Guid)
+                        .as_ref()
+                        .or_else(|| record.get(#column)),
+                )?
+            });
+            writes.push(quote! {
+                if let ::core::option::Option::Some(id) =
+                    ::dataverse_lib::model::AsRecordId::as_record_id(&self.#ident)
+                {
+                    record.set_id(id);
+                }
+            });
+            columns.push(column);
+            continue;
+        }
+
+        let column = attrs.rename.unwrap_or_else(|| ident.to_string());
+        reads.push(quote! {
+            #ident: ::dataverse_lib::model::FromValue::from_field(#column, record.get(#column))?
+        });
+
+        if !attrs.read_only {
+            match attrs.lookup {
+                Some(set_name) => {
+                    let nav = attrs.nav.unwrap_or_else(|| column.clone());
+                    writes.push(quote! {
+                        record.insert(
+                            #nav,
+                            ::dataverse_lib::model::lookup_binding(#set_name, &self.#ident),
+                        );
+                    });
+                }
+                None => {
+                    if attrs.nav.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            ident,
+                            "`nav` only applies to `lookup` fields",
+                        ));
+                    }
+                    writes.push(quote! {
+                        record.insert(
+                            #column,
+                            ::dataverse_lib::model::Value::from(
+                                ::core::clone::Clone::clone(&self.#ident),
+                            ),
+                        );
+                    });
+                }
+            }
+        }
+        columns.push(column);
+    }
+
+    let entity = match entity_attrs.set_name {
+        Some(set_name) => quote! { ::dataverse_lib::model::Entity::set(#set_name) },
+        None => quote! { ::dataverse_lib::model::Entity::logical(#logical_name) },
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::dataverse_lib::model::DataverseEntity for #name #ty_generics #where_clause {
+            const LOGICAL_NAME: &'static str = #logical_name;
+            const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+
+            fn entity() -> ::dataverse_lib::model::Entity {
+                #entity
+            }
+
+            fn from_record(
+                record: &::dataverse_lib::model::Record,
+            ) -> ::core::result::Result<Self, ::dataverse_lib::error::FieldError> {
+                ::core::result::Result::Ok(Self {
+                    #(#reads,)*
+                })
+            }
+
+            fn to_record(&self) -> ::dataverse_lib::model::Record {
+                let mut record = ::dataverse_lib::model::Record::new(Self::entity());
+                #(#writes)*
+                record
+            }
+        }
+    })
+}
+
+fn parse_entity_attrs(attrs: &[Attribute]) -> syn::Result<EntityAttrs> {
+    let mut parsed = EntityAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("dataverse")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("entity") {
+                parsed.logical_name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("set") {
+                parsed.set_name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `entity = \"...\"` or `set = \"...\"`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+fn parse_field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut parsed = FieldAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("dataverse")) {
+        attr.parse_nested_meta(|meta| {
+            let Some(ident) = meta.path.get_ident().map(Ident::to_string) else {
+                return Err(meta.error("expected a field option"));
+            };
+            match ident.as_str() {
+                "id" => parsed.id = true,
+                "read_only" => parsed.read_only = true,
+                "skip" => parsed.skip = true,
+                "rename" => parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value()),
+                "lookup" => parsed.lookup = Some(meta.value()?.parse::<LitStr>()?.value()),
+                "nav" => parsed.nav = Some(meta.value()?.parse::<LitStr>()?.value()),
+                _ => {
+                    return Err(meta.error(format!("unknown field option `{}`", ident)));
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
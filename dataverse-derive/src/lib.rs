@@ -0,0 +1,49 @@
+//! Procedural macros for dataverse-lib.
+//!
+//! Use these through their re-exports in `dataverse_lib::model`.
+
+mod entity;
+mod option_set;
+
+use proc_macro::TokenStream;
+
+/// Derives `DataverseEntity`, mapping a struct to and from a `Record`.
+///
+/// See `dataverse_lib::model::DataverseEntity` for the supported attributes.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(DataverseEntity)]
+/// #[dataverse(entity = "account", set = "accounts")]
+/// struct Account {
+///     #[dataverse(id)]
+///     id: Option<Uuid>,
+///     name: String,
+///     #[dataverse(lookup = "contacts")]
+///     primarycontactid: Option<Uuid>,
+/// }
+/// ```
+#[proc_macro_derive(DataverseEntity, attributes(dataverse))]
+pub fn derive_dataverse_entity(input: TokenStream) -> TokenStream {
+    entity::expand(input.into()).into()
+}
+
+/// Derives option set conversions for a fieldless enum.
+///
+/// Every variant needs an explicit discriminant matching its option value.
+/// Generates `FromValue` and `From<Enum> for Value`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Clone, Copy, DataverseOptionSet)]
+/// enum StatusCode {
+///     Active = 1,
+///     Inactive = 2,
+/// }
+/// ```
+#[proc_macro_derive(DataverseOptionSet)]
+pub fn derive_dataverse_option_set(input: TokenStream) -> TokenStream {
+    option_set::expand(input.into()).into()
+}
@@ -0,0 +1,72 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, parse2};
+
+pub fn expand(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = match parse2(input) {
+        Ok(i) => i,
+        Err(e) => return e.to_compile_error(),
+    };
+    match expand_option_set(&input) {
+        Ok(tokens) => tokens,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn expand_option_set(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "DataverseOptionSet can only be derived for enums",
+        ));
+    };
+
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                "DataverseOptionSet variants can't have fields",
+            ));
+        }
+        // Option values are arbitrary (often 100000000+), so implicit ones are a bug
+        if variant.discriminant.is_none() {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                "DataverseOptionSet variants need an explicit option value, e.g. `Active = 1`",
+            ));
+        }
+        variants.push(&variant.ident);
+    }
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    Ok(quote! {
+        impl ::dataverse_lib::model::FromValue for #name {
+            fn from_value(
+                field: &str,
+                value: &::dataverse_lib::model::Value,
+            ) -> ::core::result::Result<Self, ::dataverse_lib::error::FieldError> {
+                let option = <i32 as ::dataverse_lib::model::FromValue>::from_value(field, value)?;
+                #(
+                    if option == Self::#variants as i32 {
+                        return ::core::result::Result::Ok(Self::#variants);
+                    }
+                )*
+                ::core::result::Result::Err(::dataverse_lib::error::FieldError::invalid_value(
+                    field,
+                    ::std::format!("{} is not a valid {}", option, #name_str),
+                ))
+            }
+        }
+
+        impl ::core::convert::From<#name> for ::dataverse_lib::model::Value {
+            fn from(value: #name) -> Self {
+                ::dataverse_lib::model::Value::OptionSet(
+                    ::dataverse_lib::model::types::OptionSetValue::new(value as i32),
+                )
+            }
+        }
+    })
+}
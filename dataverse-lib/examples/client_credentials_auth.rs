@@ -0,0 +1,42 @@
+//! Client credentials authentication example (service principal, v2.0).
+//!
+//! Run with: cargo run --example client_credentials_auth
+//!
+//! Requires .env file with:
+//! - DATAVERSE_CLIENT_ID
+//! - DATAVERSE_CLIENT_SECRET
+//! - DATAVERSE_TENANT_ID
+//! - DATAVERSE_URL
+
+use std::env;
+
+use dataverse_lib::auth::ClientCredentialsFlow;
+use dataverse_lib::auth::TokenProvider;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = dotenvy::dotenv();
+
+    let client_id = env::var("DATAVERSE_CLIENT_ID").expect("DATAVERSE_CLIENT_ID not set");
+    let client_secret =
+        env::var("DATAVERSE_CLIENT_SECRET").expect("DATAVERSE_CLIENT_SECRET not set");
+    let tenant_id = env::var("DATAVERSE_TENANT_ID").expect("DATAVERSE_TENANT_ID not set");
+    let url = env::var("DATAVERSE_URL").expect("DATAVERSE_URL not set");
+
+    let flow = ClientCredentialsFlow::new(&client_id, &client_secret, &tenant_id);
+
+    println!("Authenticating...\n");
+
+    let token = flow.get_token(&url).await?;
+
+    println!("Authentication successful!");
+    println!("Token expires at: {:?}", token.expires_at);
+
+    let cached = flow.get_token(&url).await?;
+    println!(
+        "\nSecond request reused cached token: {}",
+        cached.access_token == token.access_token
+    );
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! Record access checks
+//!
+//! `RetrievePrincipalAccess` reports which rights a user or team has on a
+//! record, after security roles, ownership, sharing and hierarchy are taken
+//! into account. Checking up front lets an app disable actions the user
+//! can't perform instead of failing when they try.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let access = client
+//!     .principal_access(Owner::User(user_id), Entity::logical("account"), account_id)
+//!     .await?;
+//! if !access.contains(AccessRights::WRITE) {
+//!     // disable editing
+//! }
+//! ```
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::crud::Owner;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+
+bitflags::bitflags! {
+    /// Rights a principal has on a record, as reported by
+    /// `RetrievePrincipalAccess`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct AccessRights: u32 {
+        /// Read the record (`ReadAccess`).
+        const READ = 1;
+        /// Update the record (`WriteAccess`).
+        const WRITE = 2;
+        /// Attach other records to this one (`AppendAccess`).
+        const APPEND = 4;
+        /// Attach this record to others (`AppendToAccess`).
+        const APPEND_TO = 16;
+        /// Create records (`CreateAccess`).
+        const CREATE = 32;
+        /// Delete the record (`DeleteAccess`).
+        const DELETE = 65536;
+        /// Share the record (`ShareAccess`).
+        const SHARE = 262144;
+        /// Assign the record to another owner (`AssignAccess`).
+        const ASSIGN = 524288;
+    }
+}
+
+impl AccessRights {
+    /// Names of the rights in the Web API's `AccessRights` enum.
+    const NAMES: [(&'static str, AccessRights); 8] = [
+        ("ReadAccess", AccessRights::READ),
+        ("WriteAccess", AccessRights::WRITE),
+        ("AppendAccess", AccessRights::APPEND),
+        ("AppendToAccess", AccessRights::APPEND_TO),
+        ("CreateAccess", AccessRights::CREATE),
+        ("DeleteAccess", AccessRights::DELETE),
+        ("ShareAccess", AccessRights::SHARE),
+        ("AssignAccess", AccessRights::ASSIGN),
+    ];
+
+    /// Parses the Web API's comma-separated form, e.g.
+    /// `"ReadAccess, WriteAccess"` or `"None"`.
+    ///
+    /// Names this crate doesn't know are ignored.
+    pub fn from_names(names: &str) -> Self {
+        names
+            .split(',')
+            .map(str::trim)
+            .filter_map(|name| {
+                Self::NAMES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, right)| *right)
+            })
+            .collect()
+    }
+
+    /// Writes the rights in the Web API's comma-separated form.
+    pub fn to_names(self) -> String {
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(_, right)| self.contains(*right))
+            .map(|(name, _)| *name)
+            .collect();
+        if names.is_empty() {
+            return "None".to_string();
+        }
+        names.join(", ")
+    }
+}
+
+/// Response of `RetrievePrincipalAccess`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PrincipalAccessResponse {
+    access_rights: String,
+}
+
+impl DataverseClient {
+    /// Returns the rights a user or team has on a record, using
+    /// `RetrievePrincipalAccess`.
+    ///
+    /// # Arguments
+    ///
+    /// * `principal` - The user or team to check
+    /// * `entity` - The record's entity
+    /// * `id` - The record's id
+    pub async fn principal_access(
+        &self,
+        principal: Owner,
+        entity: impl Into<Entity>,
+        id: Uuid,
+    ) -> Result<AccessRights, Error> {
+        let response: PrincipalAccessResponse = self
+            .function("RetrievePrincipalAccess")
+            .bound(Entity::set(principal.entity_set()), principal.id())
+            .reference_param("Target", entity, id)
+            .execute_as()
+            .await?;
+        Ok(AccessRights::from_names(&response.access_rights))
+    }
+
+    /// Returns the rights the calling user has on a record.
+    pub async fn access(&self, entity: impl Into<Entity>, id: Uuid) -> Result<AccessRights, Error> {
+        let me = self.who_am_i().await?;
+        self.principal_access(Owner::User(me.user_id), entity, id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        let rights = AccessRights::from_names("ReadAccess, WriteAccess, AssignAccess");
+        assert_eq!(
+            rights,
+            AccessRights::READ | AccessRights::WRITE | AccessRights::ASSIGN
+        );
+        assert_eq!(rights.to_names(), "ReadAccess, WriteAccess, AssignAccess");
+        assert_eq!(AccessRights::from_names("None"), AccessRights::empty());
+        assert_eq!(AccessRights::empty().to_names(), "None");
+        assert_eq!(
+            AccessRights::from_names("ReadAccess, FutureAccess"),
+            AccessRights::READ
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_principal_access() {
+        use crate::mock::MockDataverse;
+        use crate::mock::MockEntity;
+        use crate::model::Record;
+
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid"))
+            .entity(MockEntity::new("systemuser", "systemusers", "systemuserid"))
+            .entity(MockEntity::new("team", "teams", "teamid"));
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let team = Uuid::new_v4();
+        mock.grant_access(
+            mock.user_id(),
+            account,
+            AccessRights::READ | AccessRights::WRITE,
+        );
+
+        let access = client
+            .access(Entity::logical("account"), account)
+            .await
+            .unwrap();
+        assert_eq!(access, AccessRights::READ | AccessRights::WRITE);
+        assert!(!access.contains(AccessRights::DELETE));
+
+        let access = client
+            .principal_access(Owner::Team(team), Entity::logical("account"), account)
+            .await
+            .unwrap();
+        assert!(access.is_empty());
+
+        let missing = client
+            .principal_access(
+                Owner::Team(team),
+                Entity::logical("account"),
+                Uuid::new_v4(),
+            )
+            .await;
+        assert!(missing.is_err());
+    }
+}
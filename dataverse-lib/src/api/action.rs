@@ -0,0 +1,399 @@
+//! Web API actions and functions
+//!
+//! Actions (`POST`) and functions (`GET`) are the operations beyond CRUD:
+//! `WinOpportunity`, `QualifyLead`, `WhoAmI`, custom APIs and so on. Each is
+//! unbound, bound to a record, or bound to an entity's collection. Action
+//! parameters are sent as a JSON body; function parameters are passed in the
+//! URL as parameter aliases.
+//!
+//! # Example
+//!
+//! ```ignore
+//! client
+//!     .action("WinOpportunity")
+//!     .record_param(
+//!         "OpportunityClose",
+//!         Record::new("opportunityclose")
+//!             .set("subject", "Won")
+//!             .set("opportunityid", EntityBinding::new("opportunities", opportunity_id)),
+//!     )
+//!     .param("Status", 3)
+//!     .await?;
+//!
+//! let me: WhoAmIResponse = client.function("WhoAmI").execute_as().await?;
+//!
+//! let created = client
+//!     .action("QualifyLead")
+//!     .bound(Entity::logical("lead"), lead_id)
+//!     .param("CreateAccount", true)
+//!     .param("CreateContact", false)
+//!     .param("CreateOpportunity", false)
+//!     .param("Status", 3)
+//!     .execute()
+//!     .await?;
+//! ```
+
+use reqwest::Method;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+use super::crud::RecordKey;
+use super::long_running::PollOptions;
+use super::long_running::operation_location;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+
+/// Namespace of Dataverse's actions and functions, required when bound.
+const NAMESPACE: &str = "Microsoft.Dynamics.CRM";
+
+/// Whether an operation is an action or a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Action,
+    Function,
+}
+
+/// What an action or function is bound to.
+#[derive(Debug, Clone)]
+enum Binding {
+    Unbound,
+    Record(Entity, RecordKey),
+    Collection(Entity),
+}
+
+/// A parameter value, resolved to JSON when the call is made.
+#[derive(Debug, Clone)]
+enum Param {
+    Value(Json),
+    Record(Box<Record>),
+    Reference(Entity, Uuid),
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Calls a Web API action (`POST`).
+    ///
+    /// Unbound unless [`bound`](ClientActionBuilder::bound) or
+    /// [`bound_collection`](ClientActionBuilder::bound_collection) is set.
+    pub fn action(&self, name: impl Into<String>) -> ClientActionBuilder<'_> {
+        ClientActionBuilder::new(self, Kind::Action, name.into())
+    }
+
+    /// Calls a Web API function (`GET`).
+    ///
+    /// Unbound unless [`bound`](ClientActionBuilder::bound) or
+    /// [`bound_collection`](ClientActionBuilder::bound_collection) is set.
+    pub fn function(&self, name: impl Into<String>) -> ClientActionBuilder<'_> {
+        ClientActionBuilder::new(self, Kind::Function, name.into())
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for calling an action or function, bound to a client.
+pub struct ClientActionBuilder<'a> {
+    client: &'a DataverseClient,
+    kind: Kind,
+    name: String,
+    binding: Binding,
+    params: Vec<(String, Param)>,
+    /// How to poll, if the call may run long.
+    long_running: Option<PollOptions>,
+    /// The first parameter that failed to serialize.
+    error: Option<serde_json::Error>,
+}
+
+impl<'a> ClientActionBuilder<'a> {
+    fn new(client: &'a DataverseClient, kind: Kind, name: String) -> Self {
+        Self {
+            client,
+            kind,
+            name,
+            binding: Binding::Unbound,
+            params: Vec::new(),
+            long_running: None,
+            error: None,
+        }
+    }
+
+    /// Binds the call to a record.
+    pub fn bound(mut self, entity: impl Into<Entity>, id: impl Into<RecordKey>) -> Self {
+        self.binding = Binding::Record(entity.into(), id.into());
+        self
+    }
+
+    /// Binds the call to an entity's collection.
+    pub fn bound_collection(mut self, entity: impl Into<Entity>) -> Self {
+        self.binding = Binding::Collection(entity.into());
+        self
+    }
+
+    /// Adds a parameter, serialized to JSON.
+    ///
+    /// Function parameters are written as URL literals: strings are quoted,
+    /// except GUIDs, and anything else is passed as JSON.
+    pub fn param(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => self.params.push((name.into(), Param::Value(value))),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Adds an entity-typed parameter, tagged with the record's `@odata.type`.
+    ///
+    /// The record's entity must be set.
+    pub fn record_param(mut self, name: impl Into<String>, record: Record) -> Self {
+        self.params
+            .push((name.into(), Param::Record(Box::new(record))));
+        self
+    }
+
+    /// Adds a parameter referencing an existing record.
+    pub fn reference_param(
+        mut self,
+        name: impl Into<String>,
+        entity: impl Into<Entity>,
+        id: Uuid,
+    ) -> Self {
+        self.params
+            .push((name.into(), Param::Reference(entity.into(), id)));
+        self
+    }
+
+    /// Waits for the call to complete if it's accepted as a long-running
+    /// operation (`202 Accepted` with a status URL), polling with `options`.
+    pub fn long_running(mut self, options: PollOptions) -> Self {
+        self.long_running = Some(options);
+        self
+    }
+
+    /// Makes the call and returns its response.
+    ///
+    /// Returns `Json::Null` when there is no response body.
+    pub async fn execute(self) -> Result<Json, Error> {
+        if let Some(e) = self.error {
+            return Err(Error::Serialization(e));
+        }
+        let client = self.client;
+
+        let mut params = Vec::with_capacity(self.params.len());
+        for (name, param) in self.params {
+            params.push((name, self.kind.resolve_param(client, param).await?));
+        }
+
+        let name = match self.binding {
+            Binding::Unbound => self.name,
+            _ if self.name.contains('.') => self.name,
+            _ => format!("{}.{}", NAMESPACE, self.name),
+        };
+        let prefix = match &self.binding {
+            Binding::Unbound => String::new(),
+            Binding::Record(entity, key) => {
+                format!(
+                    "{}({})/",
+                    client.resolve_entity(entity).await?,
+                    key.to_path()
+                )
+            }
+            Binding::Collection(entity) => format!("{}/", client.resolve_entity(entity).await?),
+        };
+
+        let response = match self.kind {
+            Kind::Action => {
+                let url = client.build_url(&format!("/{}{}", prefix, name));
+                let body: serde_json::Map<String, Json> = params.into_iter().collect();
+                let body = serde_json::to_string(&body)?;
+                client
+                    .request(Method::POST, &url, client.default_headers(), Some(body))
+                    .await?
+            }
+            Kind::Function => {
+                let url =
+                    client.build_url(&format!("/{}{}", prefix, function_call(&name, &params)));
+                client
+                    .request(Method::GET, &url, client.default_headers(), None)
+                    .await?
+            }
+        };
+
+        if let Some(options) = self.long_running
+            && let Some(location) = operation_location(&response)
+        {
+            return client.poll_operation(location).options(options).await;
+        }
+
+        let body = response.text().await.map_err(ApiError::from)?;
+        if body.trim().is_empty() {
+            return Ok(Json::Null);
+        }
+        let mut json: Json = serde_json::from_str(&body)?;
+        if let Some(object) = json.as_object_mut() {
+            object.remove("@odata.context");
+        }
+        Ok(json)
+    }
+
+    /// Makes the call and deserializes its response.
+    ///
+    /// Use `()` for calls that return nothing.
+    pub async fn execute_as<T: DeserializeOwned>(self) -> Result<T, Error> {
+        Ok(serde_json::from_value(self.execute().await?)?)
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientActionBuilder<'a> {
+    type Output = Result<Json, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+impl Kind {
+    /// Resolves a parameter to JSON, looking up entity metadata as needed.
+    async fn resolve_param(self, client: &DataverseClient, param: Param) -> Result<Json, Error> {
+        match param {
+            Param::Value(value) => Ok(value),
+            Param::Record(record) => {
+                if record.entity().name().is_empty() {
+                    return Err(Error::InvalidOperation(
+                        "record parameters need the record's entity".to_string(),
+                    ));
+                }
+                let logical_name = client.resolve_entity_logical_name(record.entity()).await?;
+                let mut value = serde_json::to_value(&record)?;
+                value["@odata.type"] = Json::String(format!("{}.{}", NAMESPACE, logical_name));
+                Ok(value)
+            }
+            Param::Reference(entity, id) => {
+                let logical_name = client.resolve_entity_logical_name(&entity).await?;
+                let (entity_set, primary_id_attribute) =
+                    client.resolve_entity_core(&logical_name).await?;
+                Ok(match self {
+                    // Functions take references as entity ids
+                    Kind::Function => {
+                        serde_json::json!({ "@odata.id": format!("{}({})", entity_set, id) })
+                    }
+                    Kind::Action => serde_json::json!({
+                        "@odata.type": format!("{}.{}", NAMESPACE, logical_name),
+                        primary_id_attribute: id,
+                    }),
+                })
+            }
+        }
+    }
+}
+
+/// Builds a function call segment, passing parameters as aliases:
+/// `Name(A=@p1,B=@p2)?@p1=...&@p2=...`.
+fn function_call(name: &str, params: &[(String, Json)]) -> String {
+    let arguments: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, (param, _))| format!("{}=@p{}", param, i + 1))
+        .collect();
+    let aliases: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, (_, value))| {
+            format!(
+                "@p{}={}",
+                i + 1,
+                urlencoding::encode(&function_literal(value))
+            )
+        })
+        .collect();
+
+    let mut call = format!("{}({})", name, arguments.join(","));
+    if !aliases.is_empty() {
+        call.push('?');
+        call.push_str(&aliases.join("&"));
+    }
+    call
+}
+
+/// Writes a function parameter value as a URL literal.
+fn function_literal(value: &Json) -> String {
+    match value {
+        Json::String(s) if Uuid::parse_str(s).is_ok() => s.clone(),
+        Json::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_function_call() {
+        assert_eq!(function_call("WhoAmI", &[]), "WhoAmI()");
+
+        let id = Uuid::nil();
+        let params = vec![
+            ("Name".to_string(), json!("O'Neil")),
+            ("Id".to_string(), json!(id)),
+            ("EntityNames".to_string(), json!(["account"])),
+        ];
+        assert_eq!(
+            function_call("Test", &params),
+            format!(
+                "Test(Name=@p1,Id=@p2,EntityNames=@p3)?@p1=%27O%27%27Neil%27&@p2={}&@p3=%5B%22account%22%5D",
+                id
+            )
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_function_and_bound_action() {
+        use crate::WhoAmIResponse;
+        use crate::mock::MockDataverse;
+        use crate::mock::MockEntity;
+
+        let mock = MockDataverse::new().entity(MockEntity::new("email", "emails", "activityid"));
+        let client = mock.client();
+
+        let me: WhoAmIResponse = client.function("WhoAmI").execute_as().await.unwrap();
+        assert_eq!(me.user_id, mock.user_id());
+
+        let email = mock.insert(
+            Entity::set("emails"),
+            Record::new("email").set("subject", "Hello"),
+        );
+        let response = client
+            .action("SendEmail")
+            .bound(Entity::logical("email"), email)
+            .param("IssueSend", false)
+            .await
+            .unwrap();
+        assert_eq!(response, json!({ "Subject": "Hello" }));
+        let request = mock.requests().pop().unwrap();
+        assert_eq!(
+            request.url.path(),
+            format!(
+                "/api/data/v9.2/emails({})/Microsoft.Dynamics.CRM.SendEmail",
+                email
+            )
+        );
+        let email = mock.record(Entity::set("emails"), email).unwrap();
+        assert_eq!(email.get_int("statuscode").unwrap(), Some(3));
+    }
+}
@@ -66,11 +66,24 @@ impl AggregateType {
             AggregateType::Max => "max",
         }
     }
+
+    /// Parses a FetchXML `aggregate` attribute value.
+    pub(crate) fn from_fetchxml(s: &str) -> Option<Self> {
+        match s {
+            "count" => Some(AggregateType::Count),
+            "countcolumn" => Some(AggregateType::CountDistinct),
+            "sum" => Some(AggregateType::Sum),
+            "avg" => Some(AggregateType::Avg),
+            "min" => Some(AggregateType::Min),
+            "max" => Some(AggregateType::Max),
+            _ => None,
+        }
+    }
 }
 
 /// An aggregate column specification.
 #[derive(Debug, Clone)]
-struct AggregateColumn {
+pub(crate) struct AggregateColumn {
     /// The field to aggregate.
     field: String,
     /// The alias for the result.
@@ -83,7 +96,7 @@ struct AggregateColumn {
 
 /// A group by column specification.
 #[derive(Debug, Clone)]
-struct GroupByColumn {
+pub(crate) struct GroupByColumn {
     /// The field to group by.
     field: String,
     /// The alias for the result.
@@ -92,6 +105,66 @@ struct GroupByColumn {
     date_grouping: Option<DateGrouping>,
 }
 
+impl AggregateColumn {
+    pub(crate) fn new(
+        field: impl Into<String>,
+        alias: impl Into<String>,
+        aggregate_type: AggregateType,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            alias: alias.into(),
+            aggregate_type,
+            distinct: aggregate_type == AggregateType::CountDistinct,
+        }
+    }
+
+    /// Renders the column as a FetchXML `<attribute>` element.
+    pub(crate) fn to_fetchxml(&self) -> String {
+        let distinct_attr = if self.distinct {
+            r#" distinct="true""#
+        } else {
+            ""
+        };
+        format!(
+            r#"<attribute name="{}" alias="{}" aggregate="{}"{}/>"#,
+            escape_xml(&self.field),
+            escape_xml(&self.alias),
+            self.aggregate_type.to_fetchxml(),
+            distinct_attr
+        )
+    }
+}
+
+impl GroupByColumn {
+    pub(crate) fn new(
+        field: impl Into<String>,
+        alias: impl Into<String>,
+        date_grouping: Option<DateGrouping>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            alias: alias.into(),
+            date_grouping,
+        }
+    }
+
+    /// Renders the column as a FetchXML `<attribute>` element.
+    pub(crate) fn to_fetchxml(&self) -> String {
+        let date_grouping_attr = self
+            .date_grouping
+            .as_ref()
+            .map(|dg| format!(r#" dategrouping="{}""#, dg.to_fetchxml()))
+            .unwrap_or_default();
+        format!(
+            r#"<attribute name="{}" alias="{}" groupby="true"{}/>"#,
+            escape_xml(&self.field),
+            escape_xml(&self.alias),
+            date_grouping_attr
+        )
+    }
+}
+
 /// Date grouping options for datetime fields.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DateGrouping {
@@ -123,6 +196,20 @@ impl DateGrouping {
             DateGrouping::FiscalYear => "fiscal-year",
         }
     }
+
+    /// Parses a FetchXML `dategrouping` attribute value.
+    pub(crate) fn from_fetchxml(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(DateGrouping::Day),
+            "week" => Some(DateGrouping::Week),
+            "month" => Some(DateGrouping::Month),
+            "quarter" => Some(DateGrouping::Quarter),
+            "year" => Some(DateGrouping::Year),
+            "fiscal-period" => Some(DateGrouping::FiscalPeriod),
+            "fiscal-year" => Some(DateGrouping::FiscalYear),
+            _ => None,
+        }
+    }
 }
 
 /// Builder for constructing aggregation queries.
@@ -169,11 +256,7 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The field to group by
     /// * `alias` - The alias for this field in the results
     pub fn group_by(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.group_by.push(GroupByColumn {
-            field: field.into(),
-            alias: alias.into(),
-            date_grouping: None,
-        });
+        self.group_by.push(GroupByColumn::new(field, alias, None));
         self
     }
 
@@ -192,11 +275,8 @@ impl<'a> AggregateBuilder<'a> {
         alias: impl Into<String>,
         grouping: DateGrouping,
     ) -> Self {
-        self.group_by.push(GroupByColumn {
-            field: field.into(),
-            alias: alias.into(),
-            date_grouping: Some(grouping),
-        });
+        self.group_by
+            .push(GroupByColumn::new(field, alias, Some(grouping)));
         self
     }
 
@@ -207,12 +287,8 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The field to count
     /// * `alias` - The alias for the count in the results
     pub fn count(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::Count,
-            distinct: false,
-        });
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, AggregateType::Count));
         self
     }
 
@@ -223,12 +299,11 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The field to count distinct values
     /// * `alias` - The alias for the count in the results
     pub fn count_distinct(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::CountDistinct,
-            distinct: true,
-        });
+        self.aggregates.push(AggregateColumn::new(
+            field,
+            alias,
+            AggregateType::CountDistinct,
+        ));
         self
     }
 
@@ -239,12 +314,8 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The numeric field to sum
     /// * `alias` - The alias for the sum in the results
     pub fn sum(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::Sum,
-            distinct: false,
-        });
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, AggregateType::Sum));
         self
     }
 
@@ -255,12 +326,8 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The numeric field to average
     /// * `alias` - The alias for the average in the results
     pub fn avg(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::Avg,
-            distinct: false,
-        });
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, AggregateType::Avg));
         self
     }
 
@@ -271,12 +338,8 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The field to find the minimum value
     /// * `alias` - The alias for the minimum in the results
     pub fn min(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::Min,
-            distinct: false,
-        });
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, AggregateType::Min));
         self
     }
 
@@ -287,12 +350,8 @@ impl<'a> AggregateBuilder<'a> {
     /// * `field` - The field to find the maximum value
     /// * `alias` - The alias for the maximum in the results
     pub fn max(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
-        self.aggregates.push(AggregateColumn {
-            field: field.into(),
-            alias: alias.into(),
-            aggregate_type: AggregateType::Max,
-            distinct: false,
-        });
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, AggregateType::Max));
         self
     }
 
@@ -336,37 +395,12 @@ impl<'a> AggregateBuilder<'a> {
 
         // Group by columns
         for group in &self.group_by {
-            let date_grouping_attr = group
-                .date_grouping
-                .as_ref()
-                .map(|dg| format!(r#" dategrouping="{}""#, dg.to_fetchxml()))
-                .unwrap_or_default();
-
-            entity_content.push_str(&format!(
-                r#"<attribute name="{}" alias="{}" groupby="true"{}/>
-"#,
-                escape_xml(&group.field),
-                escape_xml(&group.alias),
-                date_grouping_attr
-            ));
+            entity_content.push_str(&group.to_fetchxml());
         }
 
         // Aggregate columns
         for agg in &self.aggregates {
-            let distinct_attr = if agg.distinct {
-                r#" distinct="true""#
-            } else {
-                ""
-            };
-
-            entity_content.push_str(&format!(
-                r#"<attribute name="{}" alias="{}" aggregate="{}"{}/>
-"#,
-                escape_xml(&agg.field),
-                escape_xml(&agg.alias),
-                agg.aggregate_type.to_fetchxml(),
-                distinct_attr
-            ));
+            entity_content.push_str(&agg.to_fetchxml());
         }
 
         // Filter
@@ -0,0 +1,427 @@
+//! Notes (annotations) and their attachments
+//!
+//! A note attaches text and optionally a file to a record. Attachments up to
+//! the block size (4 MiB) are sent inline as the base64 `documentbody`;
+//! larger ones go through the `InitializeAnnotationBlocksUpload`,
+//! `UploadBlock` and `CommitAnnotationBlocksUpload` actions one block at a
+//! time, so they can be streamed from disk. Reading works the same way with
+//! [`NoteAttachmentBlocks`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let file = tokio::fs::File::open("contract.pdf").await?;
+//! let size = file.metadata().await?.len();
+//! let note_id = client
+//!     .create_note(Entity::logical("account"), account_id)
+//!     .subject("Signed contract")
+//!     .attachment_stream("contract.pdf", "application/pdf", size, ReaderStream::new(file))
+//!     .await?;
+//!
+//! let mut blocks = client.note_attachment_blocks(note_id);
+//! let mut out = tokio::fs::File::create("copy.pdf").await?;
+//! while let Some(block) = blocks.next(&client).await {
+//!     out.write_all(&block?).await?;
+//! }
+//! ```
+
+use std::io;
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use futures::Stream;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::file::ChunkReader;
+use super::file::ProgressFn;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::types::EntityBinding;
+use crate::stream::DEFAULT_FILE_CHUNK_SIZE;
+use crate::stream::NoteAttachmentBlocks;
+
+/// `@odata.type` of the `Target` parameter of the annotation block actions.
+pub(crate) const ANNOTATION_TYPE: &str = "Microsoft.Dynamics.CRM.annotation";
+
+/// A note's attached file.
+#[derive(Debug, Clone)]
+pub struct NoteAttachment {
+    /// The attachment's file name.
+    pub file_name: Option<String>,
+    /// The attachment's MIME type.
+    pub mime_type: Option<String>,
+    /// The attachment's content.
+    pub data: Bytes,
+}
+
+/// Response of `InitializeAnnotationBlocksUpload`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct UploadSession {
+    file_continuation_token: String,
+}
+
+/// Response of `CommitAnnotationBlocksUpload`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CommitUploadResponse {
+    annotation_id: Uuid,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Creates a note regarding a record, optionally with an attachment.
+    ///
+    /// # Arguments
+    ///
+    /// * `regarding` - The entity of the record the note is about
+    /// * `regarding_id` - The id of the record the note is about
+    pub fn create_note(
+        &self,
+        regarding: Entity,
+        regarding_id: Uuid,
+    ) -> ClientCreateNoteBuilder<'_> {
+        ClientCreateNoteBuilder {
+            client: self,
+            regarding,
+            regarding_id,
+            subject: None,
+            text: None,
+            attachment: None,
+            block_size: DEFAULT_FILE_CHUNK_SIZE,
+            progress: None,
+        }
+    }
+
+    /// Reads a note's attachment into memory.
+    ///
+    /// Returns `None` if the note has no attachment. Use
+    /// [`note_attachment_blocks`](Self::note_attachment_blocks) for
+    /// attachments too large to hold in memory.
+    pub async fn note_attachment(&self, note_id: Uuid) -> Result<Option<NoteAttachment>, Error> {
+        let note = self
+            .retrieve(Entity::logical("annotation"), note_id)
+            .select(&["filename", "mimetype", "documentbody"])
+            .await?
+            .into_inner();
+        let data = match note.get_string("documentbody") {
+            Ok(Some(body)) => STANDARD.decode(body).map_err(|e| {
+                Error::Api(ApiError::Parse {
+                    message: format!("Invalid note document body: {}", e),
+                    body: None,
+                })
+            })?,
+            _ => return Ok(None),
+        };
+        Ok(Some(NoteAttachment {
+            file_name: note
+                .get_string("filename")
+                .ok()
+                .flatten()
+                .map(str::to_string),
+            mime_type: note
+                .get_string("mimetype")
+                .ok()
+                .flatten()
+                .map(str::to_string),
+            data: data.into(),
+        }))
+    }
+
+    /// Returns an async iterator over a note's attachment, one block at a time.
+    ///
+    /// See [`NoteAttachmentBlocks`].
+    pub fn note_attachment_blocks(&self, note_id: Uuid) -> NoteAttachmentBlocks {
+        NoteAttachmentBlocks::new(note_id)
+    }
+}
+
+// =============================================================================
+// Create
+// =============================================================================
+
+/// An attachment waiting to be sent.
+struct PendingAttachment<'a> {
+    file_name: String,
+    mime_type: String,
+    size: u64,
+    stream: BoxStream<'a, io::Result<Bytes>>,
+}
+
+/// Builder for creating a note, bound to a client.
+pub struct ClientCreateNoteBuilder<'a> {
+    client: &'a DataverseClient,
+    regarding: Entity,
+    regarding_id: Uuid,
+    subject: Option<String>,
+    text: Option<String>,
+    attachment: Option<PendingAttachment<'a>>,
+    block_size: usize,
+    progress: Option<ProgressFn>,
+}
+
+impl<'a> ClientCreateNoteBuilder<'a> {
+    /// Sets the note's title.
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Sets the note's text.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Attaches a file held in memory.
+    pub fn attachment(
+        self,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+        data: impl Into<Bytes>,
+    ) -> Self {
+        let data = data.into();
+        let size = data.len() as u64;
+        let stream = futures::stream::once(async move { Ok(data) }).boxed();
+        self.attachment_stream(file_name, mime_type, size, stream)
+    }
+
+    /// Attaches a file read from a stream, for files too large to hold in
+    /// memory.
+    ///
+    /// `size` must be the stream's total length; creating the note fails if
+    /// the stream ends early.
+    pub fn attachment_stream(
+        mut self,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+        size: u64,
+        stream: impl Stream<Item = io::Result<Bytes>> + Send + 'a,
+    ) -> Self {
+        self.attachment = Some(PendingAttachment {
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            size,
+            stream: stream.boxed(),
+        });
+        self
+    }
+
+    /// Sets the block size in bytes.
+    ///
+    /// Attachments up to this size are sent inline with the note. Defaults
+    /// to 4 MiB, the largest block Dataverse accepts.
+    pub fn block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes.max(1);
+        self
+    }
+
+    /// Calls `f` with `(bytes sent, total bytes)` after each block.
+    pub fn on_progress(mut self, f: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    async fn execute(self) -> Result<Uuid, Error> {
+        let client = self.client;
+        let logical_name = client.resolve_entity_logical_name(&self.regarding).await?;
+        let entity_set = client.resolve_entity(&self.regarding).await?;
+
+        let mut note = Record::new("annotation").set(
+            format!("objectid_{}", logical_name),
+            EntityBinding::new(entity_set, self.regarding_id),
+        );
+        if let Some(subject) = self.subject {
+            note.insert("subject", subject);
+        }
+        if let Some(text) = self.text {
+            note.insert("notetext", text);
+        }
+
+        let Some(attachment) = self.attachment else {
+            return client
+                .create(Entity::logical("annotation"), note)
+                .await?
+                .id();
+        };
+        note.insert("filename", attachment.file_name);
+        note.insert("mimetype", attachment.mime_type);
+
+        let size = attachment.size;
+        let mut reader = ChunkReader::new(attachment.stream);
+        let report = |sent: u64| {
+            if let Some(progress) = &self.progress {
+                progress(sent, size);
+            }
+        };
+
+        if size <= self.block_size as u64 {
+            let data = reader.read(size as usize).await?;
+            note.insert("documentbody", STANDARD.encode(&data));
+            let id = client
+                .create(Entity::logical("annotation"), note)
+                .await?
+                .id()?;
+            report(size);
+            return Ok(id);
+        }
+
+        let mut target = serde_json::to_value(&note)?;
+        target["@odata.type"] = json!(ANNOTATION_TYPE);
+        let session: UploadSession = client
+            .action("InitializeAnnotationBlocksUpload")
+            .param("Target", &target)
+            .execute_as()
+            .await?;
+
+        // Block ids must all be the same length
+        let mut block_ids = Vec::new();
+        let mut sent = 0;
+        while sent < size {
+            let len = (size - sent).min(self.block_size as u64);
+            let block = reader.read(len as usize).await?;
+            let block_id = STANDARD.encode(format!("{:08}", block_ids.len()));
+            client
+                .action("UploadBlock")
+                .param("BlockId", &block_id)
+                .param("BlockData", STANDARD.encode(&block))
+                .param("FileContinuationToken", &session.file_continuation_token)
+                .execute()
+                .await?;
+            block_ids.push(block_id);
+            sent += len;
+            report(sent);
+        }
+
+        let committed: CommitUploadResponse = client
+            .action("CommitAnnotationBlocksUpload")
+            .param("Target", target)
+            .param("BlockList", block_ids)
+            .param("FileContinuationToken", session.file_continuation_token)
+            .execute_as()
+            .await?;
+        Ok(committed.annotation_id)
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientCreateNoteBuilder<'a> {
+    type Output = Result<Uuid, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid"))
+            .entity(MockEntity::new("annotation", "annotations", "annotationid"))
+    }
+
+    #[tokio::test]
+    async fn test_inline_attachment() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+
+        let note_id = client
+            .create_note(Entity::logical("account"), account)
+            .subject("Contract")
+            .text("Signed copy attached")
+            .attachment("contract.txt", "text/plain", &b"hello"[..])
+            .await
+            .unwrap();
+
+        let note = mock.record(Entity::set("annotations"), note_id).unwrap();
+        assert_eq!(note.get_string("subject").unwrap(), Some("Contract"));
+        assert_eq!(
+            note.lookup_info("objectid_account")
+                .and_then(|info| info.id),
+            Some(account)
+        );
+
+        let attachment = client.note_attachment(note_id).await.unwrap().unwrap();
+        assert_eq!(attachment.file_name.as_deref(), Some("contract.txt"));
+        assert_eq!(attachment.mime_type.as_deref(), Some("text/plain"));
+        assert_eq!(&attachment.data[..], b"hello");
+
+        let plain = client
+            .create_note(Entity::logical("account"), account)
+            .text("No attachment")
+            .await
+            .unwrap();
+        assert!(client.note_attachment(plain).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_upload_and_download() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        let pieces: Vec<io::Result<Bytes>> = data
+            .chunks(77)
+            .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+            .collect();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let progress = sent.clone();
+        let note_id = client
+            .create_note(Entity::logical("account"), account)
+            .attachment_stream(
+                "big.bin",
+                "application/octet-stream",
+                data.len() as u64,
+                futures::stream::iter(pieces),
+            )
+            .block_size(300)
+            .on_progress(move |sent, total| progress.lock().unwrap().push((sent, total)))
+            .await
+            .unwrap();
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![(300, 1000), (600, 1000), (900, 1000), (1000, 1000)]
+        );
+        let note = mock.record(Entity::set("annotations"), note_id).unwrap();
+        assert_eq!(
+            note.lookup_info("objectid_account")
+                .and_then(|info| info.id),
+            Some(account)
+        );
+
+        let mut blocks = client.note_attachment_blocks(note_id).block_size(400);
+        let mut lengths = Vec::new();
+        let mut downloaded = Vec::new();
+        while let Some(block) = blocks.next(&client).await {
+            let block = block.unwrap();
+            lengths.push(block.len());
+            downloaded.extend_from_slice(&block);
+        }
+        assert_eq!(lengths, vec![400, 400, 200]);
+        assert_eq!(downloaded, data);
+        assert_eq!(blocks.size(), Some(1000));
+        assert_eq!(blocks.file_name(), Some("big.bin"));
+    }
+}
@@ -1 +0,0 @@
-//! Long-running operation polling
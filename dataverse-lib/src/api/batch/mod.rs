@@ -15,19 +15,25 @@
 //!     .add(Op::delete(entity, id))
 //!     .execute()
 //!     .await?;
+//!
+//! // Retry items that failed with transient errors
+//! let results = results.retry_failed(&client).await?;
 //! ```
 
 pub mod multipart;
 pub mod response;
+pub mod retry;
 
 pub use response::BatchItemResult;
 pub use response::BatchOperationError;
 pub use response::BatchResults;
 pub use response::OperationResult as BatchOperationResult;
+pub use retry::BatchRetryPolicy;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::api::crud::Impersonation;
 use crate::api::crud::Operation;
 use crate::api::crud::OperationKind;
 use crate::error::Error;
@@ -51,6 +57,8 @@ pub struct BatchOptions {
     pub suppress_duplicate_detection: bool,
     /// Request the record in responses for create/update operations.
     pub return_record: bool,
+    /// Run operations as another user.
+    pub impersonate: Option<Impersonation>,
 }
 
 impl BatchOptions {
@@ -195,6 +203,14 @@ impl Batch {
         self
     }
 
+    /// Run all operations as another user.
+    ///
+    /// Operations with their own impersonation run as that user instead.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
     // -------------------------------------------------------------------------
     // Adding items
     // -------------------------------------------------------------------------
@@ -237,12 +253,23 @@ impl Batch {
 
     /// Validates the batch.
     ///
-    /// Returns an error if the batch exceeds the maximum of 1000 operations.
+    /// Returns an error if the batch exceeds the maximum of 1000 operations,
+    /// or an operation's custom query options or headers are invalid or set
+    /// by the batch already.
     pub fn validate(&self) -> Result<(), Error> {
         let count = self.operation_count();
         if count > 1000 {
             return Err(Error::BatchSizeExceeded { count, max: 1000 });
         }
+        for item in &self.items {
+            let operations = match item {
+                BatchItem::Operation(op) => std::slice::from_ref(op),
+                BatchItem::Changeset(cs) => cs.operations.as_slice(),
+            };
+            for op in operations {
+                multipart::check_operation(op, &self.options)?;
+            }
+        }
         Ok(())
     }
 
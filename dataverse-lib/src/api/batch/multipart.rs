@@ -7,6 +7,16 @@ use super::BatchItem;
 use super::BatchOptions;
 use crate::api::crud::Operation;
 use crate::api::crud::OperationOptions;
+use crate::api::crud::partitioned_record;
+use crate::api::crud::push_partition;
+use crate::api::crud::ref_body;
+use crate::api::crud::ref_path;
+use crate::api::crud::update_body;
+use crate::api::execute::SESSION_TOKEN_REQUEST_HEADER;
+use crate::api::passthrough::append_query_options;
+use crate::api::passthrough::check_headers;
+use crate::api::passthrough::check_query_options;
+use crate::error::Error;
 use crate::model::Entity;
 
 /// Generates a unique boundary string.
@@ -62,25 +72,24 @@ pub fn build_batch_body(batch: &Batch, base_url: &str, batch_boundary: &str) ->
 
 /// Builds a single operation's HTTP request.
 fn build_operation_request(op: &Operation, base_url: &str, batch_options: &BatchOptions) -> String {
-    let (method, url_path, body_opt, op_options) = operation_parts(op, base_url);
+    let (method, mut url_path, body_opt, op_options) = operation_parts(op, base_url);
+    append_query_options(&mut url_path, &op_options.query_options);
 
     let mut request = String::new();
 
     // Request line
     request.push_str(&format!("{} {} HTTP/1.1\r\n", method, url_path));
 
-    // Headers - merge batch options with per-operation options
-    let merged_headers = merge_headers(batch_options, op_options);
-    for (name, value) in merged_headers {
+    // Headers, then the operation's custom ones (checked by `check_operation`)
+    let headers = operation_headers(batch_options, op_options, body_opt.as_deref());
+    let custom = op_options
+        .headers
+        .iter()
+        .map(|(n, v)| (n.as_str(), v.clone()));
+    for (name, value) in headers.into_iter().chain(custom) {
         request.push_str(&format!("{}: {}\r\n", name, value));
     }
 
-    // Content headers for body
-    if let Some(ref body) = body_opt {
-        request.push_str("Content-Type: application/json\r\n");
-        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
-    }
-
     request.push_str("\r\n");
 
     // Body
@@ -91,6 +100,60 @@ fn build_operation_request(op: &Operation, base_url: &str, batch_options: &Batch
     request
 }
 
+/// Checks that an operation's custom query options and headers are valid
+/// and don't conflict with what the batch sends for it.
+pub(crate) fn check_operation(op: &Operation, batch_options: &BatchOptions) -> Result<(), Error> {
+    if let Operation::Update {
+        record, options, ..
+    } = op
+    {
+        update_body(record, options)?;
+    }
+    let (_, url, body, op_options) = operation_parts(op, "");
+    check_query_options(&url, &op_options.query_options)?;
+    let headers = operation_headers(batch_options, op_options, body.as_deref());
+    check_headers(headers.iter().map(|(name, _)| *name), &op_options.headers)?;
+    Ok(())
+}
+
+/// Returns the headers of an operation's request.
+fn operation_headers(
+    batch_options: &BatchOptions,
+    op_options: &OperationOptions,
+    body: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    // Merge batch options with per-operation options
+    let mut headers: Vec<(&'static str, String)> = merge_headers(batch_options, op_options)
+        .into_iter()
+        .map(|(name, value)| (name, value.to_string()))
+        .collect();
+
+    // Concurrency - If-None-Match wins, as in a direct request
+    if let Some(ref etag) = op_options.if_match
+        && !op_options.if_none_match
+    {
+        headers.push(("If-Match", etag.clone()));
+    }
+
+    // Impersonation - per-operation overrides batch default
+    if let Some(impersonation) = op_options.impersonate.or(batch_options.impersonate) {
+        let (name, value) = impersonation.header();
+        headers.push((name, value));
+    }
+
+    if let Some(token) = &op_options.session_token {
+        headers.push((SESSION_TOKEN_REQUEST_HEADER, token.clone()));
+    }
+
+    // Content headers for body
+    if let Some(body) = body {
+        headers.push(("Content-Type", "application/json".to_string()));
+        headers.push(("Content-Length", body.len().to_string()));
+    }
+
+    headers
+}
+
 /// Extracts parts needed to build the HTTP request from an Operation.
 fn operation_parts<'a>(
     op: &'a Operation,
@@ -116,7 +179,7 @@ fn operation_parts<'a>(
             options,
         } => {
             let entity_set = resolve_entity_set(entity);
-            let mut url = format!("{}/{}({})", base_url, entity_set, id);
+            let mut url = format!("{}/{}({})", base_url, entity_set, id.to_path());
 
             let mut params = Vec::new();
             if !select.is_empty() {
@@ -131,6 +194,7 @@ fn operation_parts<'a>(
                 url.push('?');
                 url.push_str(&params.join("&"));
             }
+            push_partition(&mut url, options);
 
             ("GET", url, None, options)
         }
@@ -142,8 +206,9 @@ fn operation_parts<'a>(
             options,
         } => {
             let entity_set = resolve_entity_set(entity);
-            let url = format!("{}/{}({})", base_url, entity_set, id);
-            let body = serde_json::to_string(record).unwrap_or_default();
+            let mut url = format!("{}/{}({})", base_url, entity_set, id.to_path());
+            push_partition(&mut url, options);
+            let body = update_body(record, options).unwrap_or_default();
             ("PATCH", url, Some(body), options)
         }
 
@@ -153,7 +218,8 @@ fn operation_parts<'a>(
             options,
         } => {
             let entity_set = resolve_entity_set(entity);
-            let url = format!("{}/{}({})", base_url, entity_set, id);
+            let mut url = format!("{}/{}({})", base_url, entity_set, id.to_path());
+            push_partition(&mut url, options);
             ("DELETE", url, None, options)
         }
 
@@ -164,8 +230,10 @@ fn operation_parts<'a>(
             options,
         } => {
             let entity_set = resolve_entity_set(entity);
-            let url = format!("{}/{}({})", base_url, entity_set, id);
-            let body = serde_json::to_string(record).unwrap_or_default();
+            let mut url = format!("{}/{}({})", base_url, entity_set, id.to_path());
+            push_partition(&mut url, options);
+            let record = partitioned_record(record.clone(), options);
+            let body = serde_json::to_string(&record).unwrap_or_default();
             ("PATCH", url, Some(body), options)
         }
 
@@ -179,11 +247,12 @@ fn operation_parts<'a>(
         } => {
             let entity_set = resolve_entity_set(entity);
             let target_set = resolve_entity_set(target_entity);
-            let url = format!("{}/{}({})/{}/$ref", base_url, entity_set, id, relationship);
-            let body = format!(
-                "{{\"@odata.id\":\"{}/{}({})\"}}",
-                base_url, target_set, target_id
+            let url = format!(
+                "{}/{}",
+                base_url,
+                ref_path(entity_set, *id, relationship, None)
             );
+            let body = ref_body(base_url, target_set, *target_id);
             ("POST", url, Some(body), options)
         }
 
@@ -196,8 +265,9 @@ fn operation_parts<'a>(
         } => {
             let entity_set = resolve_entity_set(entity);
             let url = format!(
-                "{}/{}({})/{}({})/$ref",
-                base_url, entity_set, id, relationship, target_id
+                "{}/{}",
+                base_url,
+                ref_path(entity_set, *id, relationship, Some(*target_id))
             );
             ("DELETE", url, None, options)
         }
@@ -212,11 +282,12 @@ fn operation_parts<'a>(
         } => {
             let entity_set = resolve_entity_set(entity);
             let target_set = resolve_entity_set(target_entity);
-            let url = format!("{}/{}({})/{}/$ref", base_url, entity_set, id, nav_property);
-            let body = format!(
-                "{{\"@odata.id\":\"{}/{}({})\"}}",
-                base_url, target_set, target_id
+            let url = format!(
+                "{}/{}",
+                base_url,
+                ref_path(entity_set, *id, nav_property, None)
             );
+            let body = ref_body(base_url, target_set, *target_id);
             ("PUT", url, Some(body), options)
         }
 
@@ -227,7 +298,11 @@ fn operation_parts<'a>(
             options,
         } => {
             let entity_set = resolve_entity_set(entity);
-            let url = format!("{}/{}({})/{}/$ref", base_url, entity_set, id, nav_property);
+            let url = format!(
+                "{}/{}",
+                base_url,
+                ref_path(entity_set, *id, nav_property, None)
+            );
             ("DELETE", url, None, options)
         }
     }
@@ -283,16 +358,6 @@ fn merge_headers(
         headers.push(("Prefer", "return=representation"));
     }
 
-    // Concurrency headers
-    if let Some(ref etag) = op_options.if_match {
-        // Note: We can't return dynamic strings here, so this is a simplification
-        // The actual implementation might need a different approach
-        if etag == "*" {
-            headers.push(("If-Match", "*"));
-        }
-        // For non-wildcard ETags, we'd need a different approach
-    }
-
     if op_options.if_none_match {
         headers.push(("If-None-Match", "*"));
     }
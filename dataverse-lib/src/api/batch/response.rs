@@ -2,6 +2,7 @@
 
 use uuid::Uuid;
 
+use super::Batch;
 use super::BatchItemKinds;
 use crate::api::crud::OperationKind;
 use crate::error::Error;
@@ -73,6 +74,7 @@ impl std::error::Error for BatchOperationError {}
 
 /// Result of a single batch item (operation or changeset).
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum BatchItemResult {
     /// Result of a standalone operation.
     Operation(Result<OperationResult, BatchOperationError>),
@@ -87,7 +89,9 @@ pub enum BatchItemResult {
 /// Result of a batch execution.
 #[derive(Debug)]
 pub struct BatchResults {
-    results: Vec<BatchItemResult>,
+    pub(super) results: Vec<BatchItemResult>,
+    /// The batch these are the results of, for retrying failed items.
+    pub(super) batch: Option<Batch>,
 }
 
 impl BatchResults {
@@ -137,7 +141,10 @@ impl BatchResults {
             }
         }
 
-        Ok(BatchResults { results })
+        Ok(BatchResults {
+            results,
+            batch: None,
+        })
     }
 
     /// Returns the number of items in the batch result.
@@ -183,6 +190,13 @@ impl BatchResults {
         }
     }
 
+    /// Attaches the batch these are the results of, so failed items can be
+    /// retried.
+    pub(crate) fn with_batch(mut self, batch: Batch) -> Self {
+        self.batch = Some(batch);
+        self
+    }
+
     /// Returns the results as a vector.
     pub fn into_vec(self) -> Vec<BatchItemResult> {
         self.results
@@ -265,13 +279,30 @@ fn parse_operation_response(
     let parts: Vec<&str> = status_line.split_whitespace().collect();
     let status: u16 = parts.get(1).and_then(|s| s.parse().ok()).unwrap_or(500);
 
-    // Find where headers end and body begins (empty line)
-    let body_start = response.find("\r\n\r\n").or_else(|| response.find("\n\n"));
-    let body = body_start.map(|idx| response[idx..].trim()).unwrap_or("");
+    // Find where the HTTP headers end and body begins (empty line after the
+    // status line; the part's own MIME headers come before it)
+    let http = response
+        .find("HTTP/1.1")
+        .map(|idx| &response[idx..])
+        .unwrap_or(response);
+    let body_start = http.find("\r\n\r\n").or_else(|| http.find("\n\n"));
+    let body = body_start.map(|idx| http[idx..].trim()).unwrap_or("");
+
+    // The id of a created or upserted record, e.g. `OData-EntityId: .../accounts(<id>)`
+    let entity_id = lines
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("OData-EntityId"))
+        .and_then(|(_, value)| {
+            let value = value.trim();
+            let start = value.rfind('(')? + 1;
+            let end = value.rfind(')')?;
+            Uuid::parse_str(value.get(start..end)?).ok()
+        });
 
     // Check for success (2xx status codes)
     if (200..300).contains(&status) {
-        let result = parse_success_response(status, body, kind)?;
+        let result = parse_success_response(status, body, kind, entity_id)?;
         Ok(Ok(result))
     } else {
         let error = parse_error_response(status, body, response);
@@ -284,21 +315,25 @@ fn parse_success_response(
     status: u16,
     body: &str,
     kind: Option<OperationKind>,
+    entity_id: Option<Uuid>,
 ) -> Result<OperationResult, Error> {
+    if kind == Some(OperationKind::Upsert) {
+        return parse_upsert_response(status, body, entity_id);
+    }
     match status {
         201 => {
             // Created - try to extract ID from response or OData-EntityId header
             if body.is_empty() {
-                // No body, need to extract ID from headers (simplified)
                 Ok(OperationResult::Created {
-                    id: Uuid::nil(),
+                    id: entity_id.unwrap_or(Uuid::nil()),
                     record: None,
                 })
             } else {
                 // Parse JSON body
-                let record: Record = serde_json::from_str(body).map_err(|e| {
+                let mut record: Record = serde_json::from_str(body).map_err(|e| {
                     Error::InvalidOperation(format!("Failed to parse created record: {}", e))
                 })?;
+                record.mark_loaded();
                 let id = record.id().unwrap_or(Uuid::nil());
                 Ok(OperationResult::Created {
                     id,
@@ -311,26 +346,27 @@ fn parse_success_response(
             if body.is_empty() {
                 Ok(OperationResult::Updated { record: None })
             } else {
-                let record: Record = serde_json::from_str(body).map_err(|e| {
+                let mut record: Record = serde_json::from_str(body).map_err(|e| {
                     Error::InvalidOperation(format!("Failed to parse record: {}", e))
                 })?;
+                record.mark_loaded();
                 Ok(OperationResult::Retrieved(record))
             }
         }
         204 => {
             // No Content — use the operation kind to return the correct variant.
             match kind {
+                // Creates without `return=representation` only send the id
+                Some(OperationKind::Create) => Ok(OperationResult::Created {
+                    id: entity_id.unwrap_or(Uuid::nil()),
+                    record: None,
+                }),
                 Some(OperationKind::Associate) => Ok(OperationResult::Associated),
                 Some(OperationKind::Disassociate) => Ok(OperationResult::Disassociated),
                 Some(OperationKind::SetLookup) => Ok(OperationResult::LookupSet),
                 Some(OperationKind::ClearLookup) => Ok(OperationResult::LookupCleared),
                 Some(OperationKind::Update) => Ok(OperationResult::Updated { record: None }),
                 Some(OperationKind::Delete) => Ok(OperationResult::Deleted),
-                Some(OperationKind::Upsert) => Ok(OperationResult::Upserted {
-                    created: false,
-                    id: Uuid::nil(),
-                    record: None,
-                }),
                 // No kind available — fall back to legacy behavior
                 _ => Ok(OperationResult::Deleted),
             }
@@ -339,6 +375,34 @@ fn parse_success_response(
     }
 }
 
+/// Parses a successful upsert response.
+///
+/// Dataverse answers 201 (created) or 200 (updated) when the record is
+/// returned, and 204 either way when it isn't; 204 is reported as updated.
+fn parse_upsert_response(
+    status: u16,
+    body: &str,
+    entity_id: Option<Uuid>,
+) -> Result<OperationResult, Error> {
+    let record = if body.is_empty() {
+        None
+    } else {
+        let mut record: Record = serde_json::from_str(body).map_err(|e| {
+            Error::InvalidOperation(format!("Failed to parse upserted record: {}", e))
+        })?;
+        record.mark_loaded();
+        Some(record)
+    };
+    let id = entity_id
+        .or_else(|| record.as_ref().and_then(Record::id))
+        .unwrap_or(Uuid::nil());
+    Ok(OperationResult::Upserted {
+        created: status == 201,
+        id,
+        record,
+    })
+}
+
 /// Parses an error response.
 fn parse_error_response(status: u16, body: &str, full_response: &str) -> BatchOperationError {
     // Try to parse OData error format
@@ -0,0 +1,287 @@
+//! Selective retry of failed batch items.
+
+use std::time::Duration;
+
+use super::Batch;
+use super::BatchItem;
+use super::response::BatchItemResult;
+use super::response::BatchOperationError;
+use super::response::BatchResults;
+use crate::DataverseClient;
+use crate::error::Error;
+
+/// Dataverse error codes for service protection limits, which are transient.
+const SERVICE_PROTECTION_CODES: [&str; 3] = [
+    // Number of requests exceeded
+    "0x80072322",
+    // Combined execution time exceeded
+    "0x80072321",
+    // Concurrent requests exceeded
+    "0x80072326",
+];
+
+/// Which failed batch items to retry, and how often.
+///
+/// By default, items that failed with a service protection error, a 429 or
+/// a 5xx status are retried up to 3 times, waiting 1 second before the first
+/// retry and doubling the delay each time.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use dataverse_lib::api::BatchRetryPolicy;
+///
+/// let policy = BatchRetryPolicy::default()
+///     .max_retries(5)
+///     .initial_delay(Duration::from_millis(500))
+///     .error_code("0x80040251");
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchRetryPolicy {
+    /// Maximum number of retry batches.
+    pub max_retries: u32,
+    /// Delay before the first retry (doubles each attempt).
+    pub initial_delay: Duration,
+    /// Maximum delay between retries.
+    pub max_delay: Duration,
+    /// HTTP statuses worth retrying.
+    pub statuses: Vec<u16>,
+    /// Dataverse error codes worth retrying, whatever their status.
+    pub error_codes: Vec<String>,
+}
+
+impl Default for BatchRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            statuses: vec![429, 500, 502, 503, 504],
+            error_codes: SERVICE_PROTECTION_CODES.map(String::from).to_vec(),
+        }
+    }
+}
+
+impl BatchRetryPolicy {
+    /// Sets the maximum number of retry batches.
+    pub fn max_retries(mut self, n: u32) -> Self {
+        self.max_retries = n;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Sets the maximum delay between retries.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Also retries items that failed with this HTTP status.
+    pub fn status(mut self, status: u16) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Also retries items that failed with this Dataverse error code.
+    pub fn error_code(mut self, code: impl Into<String>) -> Self {
+        self.error_codes.push(code.into());
+        self
+    }
+
+    /// Returns whether an item that failed with this error should be retried.
+    pub fn is_retryable(&self, error: &BatchOperationError) -> bool {
+        self.statuses.contains(&error.status)
+            || error.error_code.as_ref().is_some_and(|code| {
+                self.error_codes
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(code))
+            })
+    }
+}
+
+impl BatchResults {
+    /// Retries the failed items with the default [`BatchRetryPolicy`].
+    ///
+    /// See [`retry_failed_with`](Self::retry_failed_with).
+    pub async fn retry_failed(self, client: &DataverseClient) -> Result<BatchResults, Error> {
+        self.retry_failed_with(client, &BatchRetryPolicy::default())
+            .await
+    }
+
+    /// Retries the items that failed with a retryable error, in new batches
+    /// with the same options, until they succeed or `policy` gives up.
+    ///
+    /// A failed changeset was rolled back as a whole, so all of its
+    /// operations are sent again, as a changeset; items that succeeded are
+    /// never sent again. Items that didn't run because the batch stopped at a
+    /// retryable failure are sent along with it.
+    ///
+    /// The returned results are in the order of the original batch, with
+    /// each retried item's latest result. Results that weren't returned by
+    /// [`DataverseClient::execute_batch`] have nothing to retry and are
+    /// returned as is.
+    pub async fn retry_failed_with(
+        mut self,
+        client: &DataverseClient,
+        policy: &BatchRetryPolicy,
+    ) -> Result<BatchResults, Error> {
+        let mut delay = policy.initial_delay;
+
+        for _ in 0..policy.max_retries {
+            let Some((batch, indices)) = self.retry_batch(policy) else {
+                break;
+            };
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(policy.max_delay);
+
+            let retried = client.execute_batch(batch).await?;
+            self.merge(retried, &indices);
+        }
+
+        Ok(self)
+    }
+
+    /// Builds a batch of the items to retry, with their indices in the
+    /// original batch.
+    fn retry_batch(&self, policy: &BatchRetryPolicy) -> Option<(Batch, Vec<usize>)> {
+        let source = self.batch.as_ref()?;
+        let mut indices = Vec::new();
+        for (i, result) in self.results.iter().enumerate() {
+            let error = match result {
+                BatchItemResult::Operation(Err(e)) | BatchItemResult::Changeset(Err(e)) => e,
+                _ => continue,
+            };
+            if policy.is_retryable(error) {
+                indices.push(i);
+            }
+        }
+
+        // Without continue-on-error, the batch stops at the first failure and
+        // the rest never runs
+        if self.results.len() < source.items.len() && !indices.is_empty() {
+            indices.extend(self.results.len()..source.items.len());
+        }
+        if indices.is_empty() {
+            return None;
+        }
+
+        let items: Vec<BatchItem> = indices.iter().map(|&i| source.items[i].clone()).collect();
+        let batch = Batch {
+            items,
+            options: source.options.clone(),
+        };
+        Some((batch, indices))
+    }
+
+    /// Puts the results of a retry batch in place of the items it retried.
+    fn merge(&mut self, retried: BatchResults, indices: &[usize]) {
+        for (&i, result) in indices.iter().zip(retried.results) {
+            if i < self.results.len() {
+                self.results[i] = result;
+            } else {
+                // Items retried after the original batch stopped are in order
+                self.results.push(result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::api::batch::BatchOperationResult;
+    use crate::api::crud::Op;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    fn error(status: u16, code: Option<&str>) -> BatchOperationError {
+        BatchOperationError {
+            content_id: None,
+            status,
+            error_code: code.map(String::from),
+            message: "failed".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let policy = BatchRetryPolicy::default();
+        assert!(policy.is_retryable(&error(429, None)));
+        assert!(policy.is_retryable(&error(503, None)));
+        assert!(policy.is_retryable(&error(400, Some("0x80072322"))));
+        assert!(!policy.is_retryable(&error(404, Some("0x80040217"))));
+        assert!(policy.status(404).is_retryable(&error(404, None)));
+    }
+
+    #[test]
+    fn test_retry_batch_selects_failed_items() {
+        let accounts = Entity::set("accounts");
+        let batch = Batch::new()
+            .continue_on_error()
+            .add(Op::create(accounts.clone(), Record::new("account")))
+            .add(Op::delete(accounts.clone(), Uuid::new_v4()))
+            .changeset(|cs| {
+                cs.add(Op::create(accounts.clone(), Record::new("account")));
+                cs.add(Op::create(accounts.clone(), Record::new("account")));
+            });
+        let results = BatchResults {
+            results: vec![
+                BatchItemResult::Operation(Ok(BatchOperationResult::Deleted)),
+                BatchItemResult::Operation(Err(error(404, Some("0x80040217")))),
+                BatchItemResult::Changeset(Err(error(503, None))),
+            ],
+            batch: Some(batch),
+        };
+
+        // The changeset is retried whole
+        let (retry, indices) = results.retry_batch(&BatchRetryPolicy::default()).unwrap();
+        assert_eq!(indices, [2]);
+        assert_eq!(retry.operation_count(), 2);
+        assert!(retry.options.continue_on_error);
+        assert!(matches!(retry.items()[0], BatchItem::Changeset(_)));
+
+        let none = BatchRetryPolicy {
+            statuses: Vec::new(),
+            ..Default::default()
+        };
+        assert!(results.retry_batch(&none).is_none());
+    }
+
+    #[test]
+    fn test_retry_batch_includes_items_that_never_ran() {
+        let accounts = Entity::set("accounts");
+        let batch = Batch::new()
+            .add(Op::delete(accounts.clone(), Uuid::new_v4()))
+            .add(Op::delete(accounts.clone(), Uuid::new_v4()))
+            .add(Op::delete(accounts.clone(), Uuid::new_v4()));
+        let mut results = BatchResults {
+            results: vec![
+                BatchItemResult::Operation(Ok(BatchOperationResult::Deleted)),
+                BatchItemResult::Operation(Err(error(429, None))),
+            ],
+            batch: Some(batch),
+        };
+
+        let (_, indices) = results.retry_batch(&BatchRetryPolicy::default()).unwrap();
+        assert_eq!(indices, [1, 2]);
+
+        let retried = BatchResults {
+            results: vec![
+                BatchItemResult::Operation(Ok(BatchOperationResult::Deleted)),
+                BatchItemResult::Operation(Ok(BatchOperationResult::Deleted)),
+            ],
+            batch: None,
+        };
+        results.merge(retried, &indices);
+        assert_eq!(results.len(), 3);
+        assert!(results.all_succeeded());
+    }
+}
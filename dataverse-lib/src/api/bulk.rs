@@ -0,0 +1,488 @@
+//! Bulk operation messages
+//!
+//! `CreateMultiple`, `UpdateMultiple` and `UpsertMultiple` write many records
+//! of one table in a single request. They're considerably faster than
+//! `$batch` for homogeneous writes because Dataverse processes the targets
+//! together instead of as separate operations.
+//!
+//! Records are sent in chunks of [`DEFAULT_CHUNK_SIZE`] (configurable with
+//! [`chunk_size`](ClientBulkBuilder::chunk_size)). Each chunk is
+//! transactional: if one of its records fails, none of them are written.
+//! Failed chunks don't stop the others; their errors are collected in
+//! [`BulkResults`] along with the rows they cover.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let records: Vec<Record> = rows
+//!     .iter()
+//!     .map(|row| Record::new("contact").set("fullname", &row.name))
+//!     .collect();
+//!
+//! let results = client
+//!     .create_multiple(Entity::logical("contact"), records)
+//!     .bypass_plugins()
+//!     .await?;
+//! for error in results.errors() {
+//!     eprintln!("rows {:?} failed: {}", error.rows, error.error);
+//! }
+//! let ids = results.into_result()?;
+//! ```
+
+use std::ops::Range;
+
+use futures::StreamExt;
+use futures::stream;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+use super::crud::Impersonation;
+use super::crud::OperationOptions;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::telemetry::InSpan;
+use crate::telemetry::span;
+
+/// Namespace of the bulk messages.
+const NAMESPACE: &str = "Microsoft.Dynamics.CRM";
+
+/// Default number of records sent per request.
+///
+/// Microsoft recommends 100 to 1,000 records per request for standard
+/// tables and 100 for elastic tables; smaller requests are less likely to
+/// hit the two-minute request timeout.
+pub const DEFAULT_CHUNK_SIZE: usize = 100;
+
+/// A bulk message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkMessage {
+    /// `CreateMultiple`.
+    Create,
+    /// `UpdateMultiple`; every record needs an id.
+    Update,
+    /// `UpsertMultiple`; records with an id are updated if they exist.
+    Upsert,
+}
+
+impl BulkMessage {
+    /// Returns the message's action name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BulkMessage::Create => "CreateMultiple",
+            BulkMessage::Update => "UpdateMultiple",
+            BulkMessage::Upsert => "UpsertMultiple",
+        }
+    }
+}
+
+/// Response of `CreateMultiple` and `UpsertMultiple`.
+#[derive(Debug, Default, Deserialize)]
+struct BulkResponse {
+    #[serde(rename = "Ids", default)]
+    ids: Vec<Uuid>,
+}
+
+// =============================================================================
+// Results
+// =============================================================================
+
+/// A chunk of records that failed.
+#[derive(Debug)]
+pub struct BulkError {
+    /// Indices of the chunk's records in the input.
+    pub rows: Range<usize>,
+    /// Why the chunk failed.
+    pub error: Error,
+}
+
+/// Results of a bulk operation.
+#[derive(Debug)]
+pub struct BulkResults {
+    ids: Vec<Option<Uuid>>,
+    errors: Vec<BulkError>,
+}
+
+impl BulkResults {
+    /// Returns the id of each input record, `None` where its chunk failed.
+    pub fn ids(&self) -> &[Option<Uuid>] {
+        &self.ids
+    }
+
+    /// Returns the failed chunks, in input order.
+    pub fn errors(&self) -> &[BulkError] {
+        &self.errors
+    }
+
+    /// Returns whether every record was written.
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the indices of the records that weren't written.
+    pub fn failed_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.errors.iter().flat_map(|e| e.rows.clone())
+    }
+
+    /// Returns the ids of all records, or the first chunk's error if any
+    /// failed.
+    pub fn into_result(self) -> Result<Vec<Uuid>, Error> {
+        if let Some(error) = self.errors.into_iter().next() {
+            return Err(error.error);
+        }
+        Ok(self.ids.into_iter().flatten().collect())
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Creates records with `CreateMultiple`.
+    ///
+    /// Records with an id are created with it.
+    pub fn create_multiple(
+        &self,
+        entity: Entity,
+        records: impl IntoIterator<Item = Record>,
+    ) -> ClientBulkBuilder<'_> {
+        ClientBulkBuilder::new(self, BulkMessage::Create, entity, records)
+    }
+
+    /// Updates records with `UpdateMultiple`.
+    ///
+    /// Every record needs an id.
+    pub fn update_multiple(
+        &self,
+        entity: Entity,
+        records: impl IntoIterator<Item = Record>,
+    ) -> ClientBulkBuilder<'_> {
+        ClientBulkBuilder::new(self, BulkMessage::Update, entity, records)
+    }
+
+    /// Creates or updates records with `UpsertMultiple`.
+    ///
+    /// Records with an id are updated if they exist and created with it
+    /// otherwise; records without one are created.
+    pub fn upsert_multiple(
+        &self,
+        entity: Entity,
+        records: impl IntoIterator<Item = Record>,
+    ) -> ClientBulkBuilder<'_> {
+        ClientBulkBuilder::new(self, BulkMessage::Upsert, entity, records)
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for a bulk operation, bound to a client.
+pub struct ClientBulkBuilder<'a> {
+    client: &'a DataverseClient,
+    message: BulkMessage,
+    entity: Entity,
+    records: Vec<Record>,
+    chunk_size: usize,
+    concurrency: usize,
+    options: OperationOptions,
+}
+
+impl<'a> ClientBulkBuilder<'a> {
+    fn new(
+        client: &'a DataverseClient,
+        message: BulkMessage,
+        entity: Entity,
+        records: impl IntoIterator<Item = Record>,
+    ) -> Self {
+        Self {
+            client,
+            message,
+            entity,
+            records: records.into_iter().collect(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            concurrency: 1,
+            options: OperationOptions::default(),
+        }
+    }
+
+    /// Sets the number of records sent per request.
+    ///
+    /// Defaults to [`DEFAULT_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Sets how many chunks are sent at once.
+    ///
+    /// Requests still go through the client's rate and concurrency limiters.
+    /// Defaults to 1.
+    pub fn concurrency(mut self, chunks: usize) -> Self {
+        self.concurrency = chunks.max(1);
+        self
+    }
+
+    /// Skip custom plugin execution.
+    pub fn bypass_plugins(mut self) -> Self {
+        self.options.bypass_plugins = true;
+        self
+    }
+
+    /// Skip Power Automate flows.
+    pub fn bypass_flows(mut self) -> Self {
+        self.options.bypass_flows = true;
+        self
+    }
+
+    /// Run the operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Skip synchronous business logic.
+    pub fn bypass_sync_logic(mut self) -> Self {
+        self.options.bypass_sync_logic = true;
+        self
+    }
+
+    /// Skip duplicate detection rules.
+    pub fn suppress_duplicate_detection(mut self) -> Self {
+        self.options.suppress_duplicate_detection = true;
+        self
+    }
+
+    /// Sends the records and collects the results of every chunk.
+    ///
+    /// Fails without sending anything if the entity can't be resolved or,
+    /// for `UpdateMultiple`, a record has no id. Errors from individual
+    /// chunks are returned in [`BulkResults::errors`].
+    pub async fn execute(self) -> Result<BulkResults, Error> {
+        let span = span!(
+            "dataverse.bulk",
+            message = self.message.name(),
+            records = self.records.len()
+        );
+        self.execute_inner().in_span(span).await
+    }
+
+    async fn execute_inner(self) -> Result<BulkResults, Error> {
+        let client = self.client;
+        if self.message == BulkMessage::Update
+            && let Some(row) = self.records.iter().position(|r| r.id().is_none())
+        {
+            return Err(Error::InvalidOperation(format!(
+                "UpdateMultiple needs an id for every record (row {} has none)",
+                row
+            )));
+        }
+
+        let logical_name = client.resolve_entity_logical_name(&self.entity).await?;
+        let (entity_set, primary_id_attribute) = client.resolve_entity_core(&logical_name).await?;
+        let url = client.build_url(&format!(
+            "/{}/{}.{}",
+            entity_set,
+            NAMESPACE,
+            self.message.name()
+        ));
+        let odata_type = format!("{}.{}", NAMESPACE, logical_name);
+
+        let mut headers = client.default_headers();
+        client.apply_options_headers(&mut headers, &self.options);
+
+        // Records keep their own ids unless the response returns them
+        let mut ids: Vec<Option<Uuid>> = self.records.iter().map(Record::id).collect();
+        let mut errors = Vec::new();
+
+        let chunks: Vec<(Range<usize>, Result<String, Error>)> = self
+            .records
+            .chunks(self.chunk_size)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let start = i * self.chunk_size;
+                let body = chunk
+                    .iter()
+                    .map(|record| target(record, &odata_type, &primary_id_attribute))
+                    .collect::<Result<Vec<_>, Error>>()
+                    .map(|targets| serde_json::json!({ "Targets": targets }).to_string());
+                (start..start + chunk.len(), body)
+            })
+            .collect();
+        let url = url.as_str();
+        let mut responses = stream::iter(chunks)
+            .map(|(rows, body)| {
+                let headers = headers.clone();
+                async move {
+                    let result = match body {
+                        Ok(body) => send_chunk(client, url, headers, body).await,
+                        Err(e) => Err(e),
+                    };
+                    (rows, result)
+                }
+            })
+            .buffered(self.concurrency);
+
+        while let Some((rows, result)) = responses.next().await {
+            match result {
+                Ok(response) if response.ids.len() == rows.len() => {
+                    for (slot, id) in ids[rows].iter_mut().zip(response.ids) {
+                        *slot = Some(id);
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    ids[rows.clone()].fill(None);
+                    errors.push(BulkError { rows, error });
+                }
+            }
+        }
+
+        Ok(BulkResults { ids, errors })
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientBulkBuilder<'a> {
+    type Output = Result<BulkResults, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+/// Serializes a record as a bulk target, tagged with its `@odata.type` and
+/// with its id as the primary key.
+fn target(record: &Record, odata_type: &str, primary_id_attribute: &str) -> Result<Json, Error> {
+    let mut value = serde_json::to_value(record)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert("@odata.type".to_string(), Json::from(odata_type));
+        if let Some(id) = record.id() {
+            object.insert(primary_id_attribute.to_string(), Json::from(id.to_string()));
+        }
+    }
+    Ok(value)
+}
+
+/// Sends one chunk and parses the returned ids, if any.
+async fn send_chunk(
+    client: &DataverseClient,
+    url: &str,
+    headers: reqwest::header::HeaderMap,
+    body: String,
+) -> Result<BulkResponse, Error> {
+    let response = client
+        .request(Method::POST, url, headers, Some(body))
+        .await?;
+    let body = response.text().await.map_err(ApiError::from)?;
+    if body.trim().is_empty() {
+        return Ok(BulkResponse::default());
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        let id = Uuid::new_v4();
+        let mut record = Record::new("account").set("name", "Contoso");
+        record.set_id(id);
+        let value = target(&record, "Microsoft.Dynamics.CRM.account", "accountid").unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "@odata.type": "Microsoft.Dynamics.CRM.account",
+                "accountid": id.to_string(),
+                "name": "Contoso",
+            })
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_bulk_messages() {
+        use crate::mock::MockDataverse;
+        use crate::mock::MockEntity;
+
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+
+        let records = (0..5).map(|i| Record::new("account").set("name", format!("Account {}", i)));
+        let results = client
+            .create_multiple(Entity::logical("account"), records)
+            .chunk_size(2)
+            .await
+            .unwrap();
+        assert!(results.is_success());
+        let ids = results.into_result().unwrap();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(mock.records(Entity::set("accounts")).len(), 5);
+        let requests = mock.requests();
+        let bulk: Vec<_> = requests
+            .iter()
+            .filter(|r| r.url.path().ends_with("CreateMultiple"))
+            .collect();
+        assert_eq!(bulk.len(), 3);
+        assert_eq!(
+            bulk[0].url.path(),
+            "/api/data/v9.2/accounts/Microsoft.Dynamics.CRM.CreateMultiple"
+        );
+
+        // The second chunk updates a record that doesn't exist and is rolled back
+        let mut updates: Vec<Record> = ids
+            .iter()
+            .map(|id| {
+                let mut record = Record::new("account").set("name", "Renamed");
+                record.set_id(*id);
+                record
+            })
+            .collect();
+        updates[3].set_id(Uuid::new_v4());
+        let results = client
+            .update_multiple(Entity::logical("account"), updates)
+            .chunk_size(2)
+            .await
+            .unwrap();
+        assert_eq!(results.errors().len(), 1);
+        assert_eq!(results.errors()[0].rows, 2..4);
+        assert_eq!(results.failed_rows().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(results.ids()[0], Some(ids[0]));
+        assert_eq!(results.ids()[2], None);
+        let renamed = mock
+            .records(Entity::set("accounts"))
+            .into_iter()
+            .filter(|r| r.get_string("name").unwrap() == Some("Renamed"))
+            .count();
+        assert_eq!(renamed, 3);
+
+        let new_id = Uuid::new_v4();
+        let mut existing = Record::new("account").set("name", "Upserted");
+        existing.set_id(ids[0]);
+        let mut created = Record::new("account").set("name", "New");
+        created.set_id(new_id);
+        let ids = client
+            .upsert_multiple(Entity::set("accounts"), [existing, created])
+            .await
+            .unwrap()
+            .into_result()
+            .unwrap();
+        assert_eq!(ids[1], new_id);
+        assert_eq!(mock.records(Entity::set("accounts")).len(), 6);
+
+        let error = client
+            .update_multiple(Entity::set("accounts"), [Record::new("account")])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidOperation(_)));
+    }
+}
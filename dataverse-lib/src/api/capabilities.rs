@@ -0,0 +1,200 @@
+//! Server version and capability detection
+//!
+//! [`DataverseClient::server_version`] asks the environment for its version
+//! with `RetrieveVersion`, once per client. [`DataverseClient::capabilities`]
+//! turns it into the features the environment supports, so callers can
+//! fall back (e.g. to `$batch` instead of `CreateMultiple`) on older
+//! on-premises or sovereign-cloud environments that lag behind the public
+//! cloud.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let capabilities = client.capabilities().await?;
+//! if capabilities.bulk_messages {
+//!     client.create_multiple(Entity::logical("contact"), records).await?;
+//! } else {
+//!     let mut batch = client.batch();
+//!     for record in records {
+//!         batch = batch.add(Op::create(Entity::logical("contact"), record));
+//!     }
+//!     batch.execute().await?;
+//! }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+
+/// A Dataverse server version, e.g. `9.2.24083.180`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerVersion {
+    /// Major version.
+    pub major: u32,
+    /// Minor version.
+    pub minor: u32,
+    /// Build number.
+    pub build: u32,
+    /// Revision number.
+    pub revision: u32,
+}
+
+impl ServerVersion {
+    /// Creates a version.
+    pub const fn new(major: u32, minor: u32, build: u32, revision: u32) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+            revision,
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )
+    }
+}
+
+impl FromStr for ServerVersion {
+    type Err = Error;
+
+    /// Parses a dotted version; missing parts are 0.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Api(ApiError::parse(format!("Invalid server version '{}'", s)));
+        let mut parts = [0; 4];
+        for (i, part) in s.trim().split('.').enumerate() {
+            let slot = parts.get_mut(i).ok_or_else(invalid)?;
+            *slot = part.parse().map_err(|_| invalid())?;
+        }
+        let [major, minor, build, revision] = parts;
+        Ok(Self::new(major, minor, build, revision))
+    }
+}
+
+/// First version with file and image columns.
+const FILE_COLUMNS: ServerVersion = ServerVersion::new(9, 1, 0, 0);
+
+/// First version with `$apply` aggregation in the Web API.
+const AGGREGATION: ServerVersion = ServerVersion::new(9, 0, 0, 0);
+
+/// First version with `CreateMultiple`, `UpdateMultiple` and `UpsertMultiple`
+/// on standard tables.
+const BULK_MESSAGES: ServerVersion = ServerVersion::new(9, 2, 23043, 0);
+
+/// First version with elastic tables.
+const ELASTIC_TABLES: ServerVersion = ServerVersion::new(9, 2, 23063, 0);
+
+/// Features an environment supports, derived from its [`ServerVersion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The environment's version.
+    pub version: ServerVersion,
+    /// `$apply` aggregation and grouping in OData queries.
+    pub aggregation: bool,
+    /// File and image columns, and their chunked uploads.
+    pub file_columns: bool,
+    /// `CreateMultiple`, `UpdateMultiple` and `UpsertMultiple`.
+    pub bulk_messages: bool,
+    /// Elastic tables, with partitions and session tokens.
+    pub elastic_tables: bool,
+}
+
+impl Capabilities {
+    /// Returns the capabilities of an environment of a given version.
+    pub fn for_version(version: ServerVersion) -> Self {
+        Self {
+            version,
+            aggregation: version >= AGGREGATION,
+            file_columns: version >= FILE_COLUMNS,
+            bulk_messages: version >= BULK_MESSAGES,
+            elastic_tables: version >= ELASTIC_TABLES,
+        }
+    }
+}
+
+/// Response of `RetrieveVersion`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RetrieveVersionResponse {
+    version: String,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Returns the environment's version, using the `RetrieveVersion`
+    /// function.
+    ///
+    /// The version is requested once and then shared by the client and its
+    /// clones.
+    pub async fn server_version(&self) -> Result<ServerVersion, Error> {
+        if let Some(version) = *self.known_server_version() {
+            return Ok(version);
+        }
+        let response: RetrieveVersionResponse =
+            self.function("RetrieveVersion").execute_as().await?;
+        let version = response.version.parse()?;
+        *self.known_server_version() = Some(version);
+        Ok(version)
+    }
+
+    /// Returns the features the environment supports.
+    pub async fn capabilities(&self) -> Result<Capabilities, Error> {
+        Ok(Capabilities::for_version(self.server_version().await?))
+    }
+
+    /// Locks the cached server version.
+    fn known_server_version(&self) -> std::sync::MutexGuard<'_, Option<ServerVersion>> {
+        self.inner
+            .server_version
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_server_version() {
+        let version: ServerVersion = "9.2.24083.180".parse().unwrap();
+        assert_eq!(version, ServerVersion::new(9, 2, 24083, 180));
+        assert_eq!(version.to_string(), "9.2.24083.180");
+        assert_eq!(
+            "8.2".parse::<ServerVersion>().unwrap(),
+            ServerVersion::new(8, 2, 0, 0)
+        );
+        assert!("9.2.x".parse::<ServerVersion>().is_err());
+        assert!("9.2.1.1.1".parse::<ServerVersion>().is_err());
+        assert!("".parse::<ServerVersion>().is_err());
+    }
+
+    #[test]
+    fn test_capabilities_for_version() {
+        let current = Capabilities::for_version(ServerVersion::new(9, 2, 24083, 180));
+        assert!(current.aggregation && current.file_columns);
+        assert!(current.bulk_messages && current.elastic_tables);
+
+        let older = Capabilities::for_version(ServerVersion::new(9, 2, 22121, 0));
+        assert!(older.file_columns);
+        assert!(!older.bulk_messages && !older.elastic_tables);
+
+        let on_premises = Capabilities::for_version(ServerVersion::new(9, 0, 44, 3));
+        assert!(on_premises.aggregation);
+        assert!(!on_premises.file_columns && !on_premises.bulk_messages);
+    }
+}
@@ -0,0 +1,348 @@
+//! Record cloning
+//!
+//! [`DataverseClient::clone_record`] retrieves a record and creates a copy
+//! of it. Only attributes the table's metadata marks as valid for create are
+//! copied, so the primary key, system columns such as `createdon`,
+//! calculated and virtual columns, and file and image columns are left out.
+//!
+//! Lookups point the copy at the same records by default. A
+//! [`LookupPolicy`] drops or remaps them instead, for all lookups or per
+//! attribute. The owner isn't copied unless asked for: the copy belongs to
+//! the caller, or to the owner given with [`owner`](ClientCloneBuilder::owner).
+//!
+//! # Example
+//!
+//! ```ignore
+//! let copy = client
+//!     .clone_record(Entity::logical("opportunity"), template)
+//!     .lookup("parentaccountid", LookupPolicy::Remap(Entity::logical("account"), account))
+//!     .lookup("originatingleadid", LookupPolicy::Drop)
+//!     .set("name", "Seeded opportunity")
+//!     .owner(Owner::Team(sales_team))
+//!     .await?;
+//! ```
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use super::Owner;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::Value;
+use crate::model::metadata::AttributeMetadata;
+use crate::model::metadata::AttributeType;
+use crate::model::types::EntityBinding;
+
+/// What a clone does with a lookup of the source record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupPolicy {
+    /// Point the copy at the same record.
+    Keep,
+    /// Leave the lookup empty.
+    Drop,
+    /// Point the copy at another record.
+    Remap(Entity, Uuid),
+}
+
+/// What a clone does with the source record's owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OwnerPolicy {
+    /// The creating user owns the copy.
+    Caller,
+    /// The copy has the source record's owner.
+    Keep,
+    /// The copy is assigned to this owner.
+    Assign(Owner),
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Creates a copy of a record, returning the copy's id.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The record's entity
+    /// * `id` - The id of the record to copy
+    pub fn clone_record(&self, entity: impl Into<Entity>, id: Uuid) -> ClientCloneBuilder<'_> {
+        ClientCloneBuilder {
+            client: self,
+            entity: entity.into(),
+            id,
+            lookups: LookupPolicy::Keep,
+            overrides: HashMap::new(),
+            owner: OwnerPolicy::Caller,
+            fields: Vec::new(),
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for cloning a record, bound to a client.
+pub struct ClientCloneBuilder<'a> {
+    client: &'a DataverseClient,
+    entity: Entity,
+    id: Uuid,
+    lookups: LookupPolicy,
+    overrides: HashMap<String, LookupPolicy>,
+    owner: OwnerPolicy,
+    fields: Vec<(String, Value)>,
+}
+
+impl<'a> ClientCloneBuilder<'a> {
+    /// Sets the policy for lookups without their own. Defaults to
+    /// [`LookupPolicy::Keep`].
+    pub fn lookups(mut self, policy: LookupPolicy) -> Self {
+        self.lookups = policy;
+        self
+    }
+
+    /// Sets the policy for one lookup attribute.
+    pub fn lookup(mut self, attribute: impl Into<String>, policy: LookupPolicy) -> Self {
+        self.overrides.insert(attribute.into(), policy);
+        self
+    }
+
+    /// Gives the copy the source record's owner.
+    pub fn keep_owner(mut self) -> Self {
+        self.owner = OwnerPolicy::Keep;
+        self
+    }
+
+    /// Assigns the copy to a user or team.
+    pub fn owner(mut self, owner: Owner) -> Self {
+        self.owner = OwnerPolicy::Assign(owner);
+        self
+    }
+
+    /// Sets a field of the copy, replacing the copied value.
+    pub fn set(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.push((field.into(), value.into()));
+        self
+    }
+
+    async fn execute(self) -> Result<Uuid, Error> {
+        let client = self.client;
+        let metadata = client.metadata().entity(self.entity.clone()).await?;
+        let source = client
+            .retrieve(Entity::set(&metadata.entity_set_name), self.id)
+            .await?
+            .into_inner();
+
+        let mut copy = Record::new(metadata.logical_name.as_str());
+        for attribute in metadata.attributes.iter().filter(|a| is_copied(a)) {
+            let name = &attribute.logical_name;
+            let value = match source.get(name) {
+                Some(Value::Null) | None => continue,
+                Some(value) => value,
+            };
+            if !attribute.is_lookup() {
+                copy.insert(name.as_str(), value.clone());
+                continue;
+            }
+
+            let policy = if attribute.attribute_type == AttributeType::Owner {
+                match self.owner {
+                    OwnerPolicy::Caller => LookupPolicy::Drop,
+                    OwnerPolicy::Keep => LookupPolicy::Keep,
+                    OwnerPolicy::Assign(_) => continue,
+                }
+            } else {
+                self.overrides.get(name).unwrap_or(&self.lookups).clone()
+            };
+            let binding = match policy {
+                LookupPolicy::Drop => continue,
+                LookupPolicy::Keep => {
+                    let Value::EntityReference(reference) = value else {
+                        continue;
+                    };
+                    let target = match reference.entity.name() {
+                        "" => match attribute.targets.as_slice() {
+                            [target] => target.as_str(),
+                            _ => {
+                                log::warn!("Not copying '{}': the lookup's table is unknown", name);
+                                continue;
+                            }
+                        },
+                        target => target,
+                    };
+                    let entity_set = client.resolve_entity_set_name(target).await?;
+                    EntityBinding::new(entity_set, reference.id)
+                }
+                LookupPolicy::Remap(entity, id) => {
+                    EntityBinding::new(client.resolve_entity(&entity).await?, id)
+                }
+            };
+            copy.insert(name.as_str(), binding);
+        }
+
+        if let OwnerPolicy::Assign(owner) = self.owner {
+            copy.insert("ownerid", owner.binding());
+        }
+        for (field, value) in self.fields {
+            copy.insert(field, value);
+        }
+
+        client
+            .create(Entity::set(&metadata.entity_set_name), copy)
+            .await?
+            .id()
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientCloneBuilder<'a> {
+    type Output = Result<Uuid, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+/// Returns `true` for attributes that can be set on a new record.
+fn is_copied(attribute: &AttributeMetadata) -> bool {
+    attribute.is_valid_for_create
+        && !attribute.is_primary_id
+        && attribute.attribute_of.is_none()
+        && !attribute.is_image()
+        && !matches!(
+            attribute.attribute_type,
+            AttributeType::Virtual
+                | AttributeType::ManagedProperty
+                | AttributeType::EntityName
+                | AttributeType::PartyList
+                | AttributeType::File
+        )
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(MockEntity::new("systemuser", "systemusers", "systemuserid"))
+            .entity(MockEntity::new("team", "teams", "teamid"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .attribute("createdon", AttributeType::DateTime)
+                    .read_only("createdon")
+                    .attribute("ownerid", AttributeType::Owner)
+                    .lookup("parentcustomerid", "account")
+                    .lookup("preferredsystemuserid", "systemuser"),
+            )
+    }
+
+    fn lookup(record: &Record, field: &str) -> Option<Uuid> {
+        record
+            .get_entity_reference(field)
+            .ok()
+            .flatten()
+            .map(|reference| reference.id)
+    }
+
+    #[tokio::test]
+    async fn test_clone_record() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let other = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Fabrikam"),
+        );
+        let user = mock.insert(Entity::set("systemusers"), Record::new("systemuser"));
+        let source = mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact")
+                .set("fullname", "Ada")
+                .set("createdon", "2024-01-01T00:00:00Z")
+                .set("parentcustomerid", EntityBinding::new("accounts", account))
+                .set(
+                    "preferredsystemuserid",
+                    EntityBinding::new("systemusers", user),
+                ),
+        );
+
+        let copy = client
+            .clone_record(Entity::logical("contact"), source)
+            .await
+            .unwrap();
+        assert_ne!(copy, source);
+        let copied = mock.record(Entity::set("contacts"), copy).unwrap();
+        assert_eq!(copied.get_string("fullname").unwrap(), Some("Ada"));
+        assert!(!copied.contains("createdon"));
+        assert_eq!(lookup(&copied, "parentcustomerid"), Some(account));
+        assert_eq!(lookup(&copied, "preferredsystemuserid"), Some(user));
+
+        let copy = client
+            .clone_record(Entity::logical("contact"), source)
+            .lookups(LookupPolicy::Drop)
+            .lookup(
+                "parentcustomerid",
+                LookupPolicy::Remap(Entity::logical("account"), other),
+            )
+            .set("fullname", "Ada (copy)")
+            .await
+            .unwrap();
+        let copied = mock.record(Entity::set("contacts"), copy).unwrap();
+        assert_eq!(copied.get_string("fullname").unwrap(), Some("Ada (copy)"));
+        assert_eq!(lookup(&copied, "parentcustomerid"), Some(other));
+        assert_eq!(lookup(&copied, "preferredsystemuserid"), None);
+    }
+
+    #[tokio::test]
+    async fn test_clone_owner() {
+        let mock = mock();
+        let client = mock.client();
+        let user = mock.insert(Entity::set("systemusers"), Record::new("systemuser"));
+        let team = mock.insert(Entity::set("teams"), Record::new("team"));
+        let source = mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact")
+                .set("fullname", "Ada")
+                .set("ownerid", Owner::User(user).binding()),
+        );
+        let cloned_owner = |copy| {
+            lookup(
+                &mock.record(Entity::set("contacts"), copy).unwrap(),
+                "ownerid",
+            )
+        };
+
+        let copy = client
+            .clone_record(Entity::logical("contact"), source)
+            .await
+            .unwrap();
+        assert_eq!(cloned_owner(copy), None);
+
+        let copy = client
+            .clone_record(Entity::logical("contact"), source)
+            .keep_owner()
+            .await
+            .unwrap();
+        assert_eq!(cloned_owner(copy), Some(user));
+
+        let copy = client
+            .clone_record(Entity::logical("contact"), source)
+            .owner(Owner::Team(team))
+            .await
+            .unwrap();
+        assert_eq!(cloned_owner(copy), Some(team));
+    }
+}
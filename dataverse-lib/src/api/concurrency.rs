@@ -0,0 +1,103 @@
+//! Optimistic concurrency on retrieved records
+//!
+//! Retrieved records carry the ETag of the version they were read at. The
+//! helpers here send it as `If-Match`, so a write only goes through if
+//! nobody changed the record since, and turn the resulting 412 into
+//! [`Error::Concurrency`] with the record's current ETag.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut record = client.retrieve(Entity::logical("account"), id).await?.into_inner();
+//! loop {
+//!     record.insert("creditlimit", record.get_decimal("creditlimit")?.unwrap_or_default() + step);
+//!     match client.update_if_unmodified(&mut record).await {
+//!         Err(Error::Concurrency { .. }) => {
+//!             // Someone else got there first: re-read and try again
+//!             record = client.retrieve(Entity::logical("account"), id).await?.into_inner();
+//!         }
+//!         result => break result?,
+//!     }
+//! }
+//! ```
+
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+
+/// Status Dataverse answers with when `If-Match` doesn't match.
+const PRECONDITION_FAILED: u16 = 412;
+
+/// Returns the entity, id and ETag a conditional write needs.
+fn target(record: &Record) -> Result<(Entity, Uuid, String), Error> {
+    let id = record
+        .id()
+        .ok_or_else(|| Error::InvalidOperation("Record has no id".to_string()))?;
+    let etag = record.etag().ok_or_else(|| {
+        Error::InvalidOperation(format!("Record {} has no ETag; retrieve it first", id))
+    })?;
+    Ok((record.entity().clone(), id, etag.to_string()))
+}
+
+/// Returns `true` if the error is a failed `If-Match`.
+fn is_conflict(error: &Error) -> bool {
+    matches!(error, Error::Api(e) if e.status_code() == Some(PRECONDITION_FAILED))
+}
+
+impl DataverseClient {
+    /// Updates a record only if it hasn't changed since it was retrieved.
+    ///
+    /// Sends the record's changed fields with its ETag as `If-Match`. On
+    /// success the record takes the new ETag and is marked clean. If the
+    /// record was modified in the meantime, fails with
+    /// [`Error::Concurrency`] carrying the current ETag (`None` if the
+    /// record was deleted).
+    pub async fn update_if_unmodified(&self, record: &mut Record) -> Result<(), Error> {
+        let (entity, id, etag) = target(record)?;
+        let result = self
+            .update(entity.clone(), id, record.clone())
+            .if_match(etag)
+            .return_record()
+            .await;
+
+        match result {
+            Ok(updated) => {
+                if let Some(etag) = updated.as_ref().and_then(Record::etag) {
+                    record.set_etag(etag);
+                }
+                record.mark_clean();
+                Ok(())
+            }
+            Err(e) if is_conflict(&e) => Err(self.concurrency_error(entity, id).await),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes a record only if it hasn't changed since it was retrieved.
+    ///
+    /// If the record was modified in the meantime, fails with
+    /// [`Error::Concurrency`] carrying the current ETag.
+    pub async fn delete_if_unmodified(&self, record: &Record) -> Result<(), Error> {
+        let (entity, id, etag) = target(record)?;
+        match self.delete(entity.clone(), id).if_match(etag).await {
+            Err(e) if is_conflict(&e) => Err(self.concurrency_error(entity, id).await),
+            result => result,
+        }
+    }
+
+    /// Builds the error for a lost write, looking up the record's current
+    /// ETag.
+    async fn concurrency_error(&self, entity: Entity, id: Uuid) -> Error {
+        let current_etag = match self.retrieve(entity, id).await {
+            Ok(current) => current.data().etag().map(str::to_string),
+            Err(e) => {
+                log::debug!("Failed to read current ETag of {}: {}", id, e);
+                None
+            }
+        };
+        Error::Concurrency { current_etag }
+    }
+}
@@ -24,13 +24,23 @@ use serde::Serialize;
 use uuid::Uuid;
 
 use crate::api::query::odata::ExpandBuilder;
+use crate::api::query::odata::url::value_to_odata;
+use crate::error::Error;
+use crate::error::FieldValidationError;
 use crate::model::Entity;
 use crate::model::Record;
+use crate::model::Value;
+use crate::model::metadata::EntityKeyMetadata;
+use crate::model::metadata::EntityMetadata;
+use crate::model::types::EntityBinding;
 
 // =============================================================================
 // Operation Options
 // =============================================================================
 
+/// Column holding an elastic table record's partition.
+pub const PARTITION_ID: &str = "partitionid";
+
 /// Options that can be applied to CRUD operations.
 ///
 /// These options control behavior like plugin execution, duplicate detection,
@@ -55,6 +65,22 @@ pub struct OperationOptions {
     pub if_match: Option<String>,
     /// Only succeed if record doesn't exist (If-None-Match: * header).
     pub if_none_match: bool,
+    /// Run the operation as another user.
+    pub impersonate: Option<Impersonation>,
+    /// Partition of an elastic table record (`partitionId` query parameter).
+    pub partition_id: Option<String>,
+    /// Session token for consistent elastic table reads
+    /// (`MSCRM.SessionToken` header).
+    pub session_token: Option<String>,
+    /// Send every field of an updated record, not just the changed ones.
+    pub full_payload: bool,
+    /// Extra query options appended to the request URL, as name and value.
+    pub query_options: Vec<(String, String)>,
+    /// Extra request headers, as name and value.
+    pub headers: Vec<(String, String)>,
+    /// Check the record against the entity's attribute metadata before
+    /// sending it.
+    pub validate: bool,
 }
 
 impl OperationOptions {
@@ -82,6 +108,222 @@ impl OperationOptions {
     }
 }
 
+/// Appends the `partitionId` query parameter, if the operation has a partition.
+pub(crate) fn push_partition(url: &mut String, options: &OperationOptions) {
+    if let Some(partition) = &options.partition_id {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str("partitionId=");
+        url.push_str(&urlencoding::encode(partition));
+    }
+}
+
+/// Serializes the body of an update: the record's changed fields, or all of
+/// them if the operation asks for the full payload.
+///
+/// Fails if nothing changed, rather than sending an empty update.
+pub(crate) fn update_body(record: &Record, options: &OperationOptions) -> Result<String, Error> {
+    let body = if options.full_payload {
+        serde_json::to_string(record)
+    } else if record.has_changes() {
+        serde_json::to_string(&record.changes())
+    } else {
+        return Err(Error::InvalidOperation(
+            "Record has no changed fields to update".to_string(),
+        ));
+    };
+    body.map_err(Error::Serialization)
+}
+
+/// Writes the operation's partition to an upserted record's `partitionid`,
+/// unless the record sets it itself.
+pub(crate) fn partitioned_record(record: Record, options: &OperationOptions) -> Record {
+    match &options.partition_id {
+        Some(partition) if !record.contains(PARTITION_ID) => {
+            record.set(PARTITION_ID, partition.clone())
+        }
+        _ => record,
+    }
+}
+
+// =============================================================================
+// Impersonation
+// =============================================================================
+
+/// A user to run requests as.
+///
+/// The authenticated user needs the `prvActOnBehalfOfAnotherUser` privilege.
+/// Records created while impersonating are owned by the impersonated user.
+///
+/// A bare [`Uuid`] converts to [`Impersonation::ObjectId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Impersonation {
+    /// The user's Microsoft Entra ID object ID (`CallerObjectId` header).
+    ObjectId(Uuid),
+    /// The user's `systemuserid` (`MSCRMCallerID` header).
+    SystemUserId(Uuid),
+}
+
+impl Impersonation {
+    /// All header names used for impersonation.
+    pub(crate) const HEADERS: [&'static str; 2] = ["CallerObjectId", "MSCRMCallerID"];
+
+    /// Returns the header name and value for this impersonation.
+    pub(crate) fn header(&self) -> (&'static str, String) {
+        match self {
+            Impersonation::ObjectId(id) => ("CallerObjectId", id.to_string()),
+            Impersonation::SystemUserId(id) => ("MSCRMCallerID", id.to_string()),
+        }
+    }
+}
+
+impl From<Uuid> for Impersonation {
+    fn from(id: Uuid) -> Self {
+        Impersonation::ObjectId(id)
+    }
+}
+
+// =============================================================================
+// Ownership and state
+// =============================================================================
+
+/// A user or team, as a record's owner or a principal whose access is
+/// checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Owner {
+    /// A user (`systemuser`).
+    User(Uuid),
+    /// A team (`team`).
+    Team(Uuid),
+}
+
+impl Owner {
+    /// Returns the owner's entity set name.
+    pub fn entity_set(&self) -> &'static str {
+        match self {
+            Owner::User(_) => "systemusers",
+            Owner::Team(_) => "teams",
+        }
+    }
+
+    /// Returns the owner's id.
+    pub fn id(&self) -> Uuid {
+        match self {
+            Owner::User(id) | Owner::Team(id) => *id,
+        }
+    }
+
+    /// Returns the `ownerid` binding for this owner.
+    pub(crate) fn binding(&self) -> EntityBinding {
+        EntityBinding::new(self.entity_set(), self.id())
+    }
+}
+
+/// Builds the update payload assigning a record to `owner`.
+pub(crate) fn assign_record(entity: &Entity, owner: Owner) -> Record {
+    Record::new(entity.clone()).set("ownerid", owner.binding())
+}
+
+/// Builds the update payload setting a record's state and status.
+pub(crate) fn state_record(entity: &Entity, state: i32, status: i32) -> Record {
+    Record::new(entity.clone())
+        .set("statecode", state)
+        .set("statuscode", status)
+}
+
+/// Checks a state and status against an entity's state and status option
+/// sets.
+///
+/// The status must exist and belong to the state. Entities without state or
+/// status metadata pass.
+pub(crate) fn check_state(metadata: &EntityMetadata, state: i32, status: i32) -> Result<(), Error> {
+    let mut errors = Vec::new();
+    if let Some(attribute) = metadata.state_attribute("statecode")
+        && !attribute
+            .option_set
+            .options
+            .iter()
+            .any(|o| o.value == state)
+    {
+        errors.push(FieldValidationError::new(
+            "statecode",
+            format!(
+                "{} is not a valid state of {}",
+                state, metadata.logical_name
+            ),
+        ));
+    }
+    if let Some(attribute) = metadata.status_attribute("statuscode") {
+        match attribute
+            .option_set
+            .options
+            .iter()
+            .find(|o| o.value == status)
+        {
+            None => errors.push(FieldValidationError::new(
+                "statuscode",
+                format!(
+                    "{} is not a valid status of {}",
+                    status, metadata.logical_name
+                ),
+            )),
+            Some(option) if option.state != state => errors.push(FieldValidationError::new(
+                "statuscode",
+                format!(
+                    "status {} belongs to state {}, not {}",
+                    status, option.state, state
+                ),
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Validation {
+        message: format!("Invalid state/status for {}", metadata.logical_name),
+        errors,
+    })
+}
+
+/// Checks that an alternate key's columns match one of an entity's active
+/// keys.
+///
+/// Primary keys, and entities without alternate keys, pass.
+pub(crate) fn check_alternate_key(
+    entity: &str,
+    keys: &[EntityKeyMetadata],
+    key: &RecordKey,
+) -> Result<(), Error> {
+    let RecordKey::Alternate(values) = key else {
+        return Ok(());
+    };
+    let columns: Vec<&str> = values.iter().map(|(column, _)| column.as_str()).collect();
+    if keys.is_empty() || keys.iter().any(|k| k.is_active() && k.matches(&columns)) {
+        return Ok(());
+    }
+
+    let message = match keys.iter().find(|k| k.matches(&columns)) {
+        Some(k) => format!(
+            "Alternate key {} of {} is not active ({:?})",
+            k.logical_name, entity, k.entity_key_index_status
+        ),
+        None => format!(
+            "No alternate key of {} has the columns {}; keys are: {}",
+            entity,
+            columns.join(", "),
+            keys.iter()
+                .map(|k| format!("{} ({})", k.logical_name, k.key_attributes.join(", ")))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    };
+    Err(Error::Validation {
+        message,
+        errors: Vec::new(),
+    })
+}
+
 // =============================================================================
 // Operation Kind
 // =============================================================================
@@ -119,6 +361,136 @@ impl From<&Operation> for OperationKind {
     }
 }
 
+// =============================================================================
+// Record keys
+// =============================================================================
+
+/// Identifies a single record, by primary key or by alternate key.
+///
+/// Alternate keys are defined on the table in Dataverse and address records
+/// by the values of one or more columns, as `accounts(accountnumber='A-1')`.
+/// A `Uuid` converts into a primary key, so anywhere that takes
+/// `impl Into<RecordKey>` also takes an id.
+///
+/// # Example
+///
+/// ```
+/// use dataverse_lib::api::RecordKey;
+///
+/// let key = RecordKey::alternate([("accountnumber", "A-1"), ("name", "O'Neil & Co")]);
+/// assert_eq!(key.to_path(), "accountnumber='A-1',name='O%27%27Neil%20%26%20Co'");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordKey {
+    /// The record's primary key.
+    Id(Uuid),
+    /// Alternate key column values, in the key's column order.
+    Alternate(Vec<(String, Value)>),
+}
+
+impl RecordKey {
+    /// Creates an alternate key from column/value pairs.
+    ///
+    /// Lookup columns in a key are given as `_field_value` with a `Uuid`.
+    pub fn alternate<K, V>(values: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        Self::Alternate(
+            values
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    /// Returns the primary key, if this is one.
+    pub fn id(&self) -> Option<Uuid> {
+        match self {
+            Self::Id(id) => Some(*id),
+            Self::Alternate(_) => None,
+        }
+    }
+
+    /// Returns the URL-encoded key as it appears between the parentheses in
+    /// `entityset(...)`.
+    ///
+    /// Alternate key values are written as OData literals (strings quoted,
+    /// with quotes doubled) and percent-encoded.
+    pub fn to_path(&self) -> String {
+        match self {
+            Self::Id(id) => id.to_string(),
+            Self::Alternate(values) => values
+                .iter()
+                .map(|(key, value)| {
+                    let literal = match value {
+                        // Encode the string's contents, keeping its quotes literal
+                        Value::String(s) => {
+                            format!("'{}'", urlencoding::encode(&s.replace('\'', "''")))
+                        }
+                        other => urlencoding::encode(&value_to_odata(other)).into_owned(),
+                    };
+                    format!("{}={}", key, literal)
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+impl From<Uuid> for RecordKey {
+    fn from(id: Uuid) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl std::fmt::Display for RecordKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Alternate(values) => {
+                let parts: Vec<String> = values
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value_to_odata(value)))
+                    .collect();
+                write!(f, "{}", parts.join(","))
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Relationship references
+// =============================================================================
+
+/// Returns the `$ref` path of a navigation property, relative to the service
+/// root: `accounts(<id>)/contact_customer_accounts/$ref`.
+///
+/// With `target_id`, addresses one member of a collection-valued property
+/// (`.../contact_customer_accounts(<target>)/$ref`), as disassociate needs.
+pub(crate) fn ref_path(entity_set: &str, id: Uuid, nav: &str, target_id: Option<Uuid>) -> String {
+    match target_id {
+        Some(target_id) => format!("{}({})/{}({})/$ref", entity_set, id, nav, target_id),
+        None => format!("{}({})/{}/$ref", entity_set, id, nav),
+    }
+}
+
+/// Returns the body of a `$ref` request pointing at a record.
+///
+/// # Arguments
+///
+/// * `service_root` - Absolute or relative service root, without a trailing slash
+/// * `target_set` - Entity set of the referenced record
+/// * `target_id` - ID of the referenced record
+pub(crate) fn ref_body(service_root: &str, target_set: &str, target_id: Uuid) -> String {
+    serde_json::json!({
+        "@odata.id": format!("{}/{}({})", service_root, target_set, target_id)
+    })
+    .to_string()
+}
+
 // =============================================================================
 // Operation enum
 // =============================================================================
@@ -136,10 +508,10 @@ pub enum Operation {
         options: OperationOptions,
     },
 
-    /// Retrieve a record by ID.
+    /// Retrieve a record by ID or alternate key.
     Retrieve {
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         select: Vec<String>,
         expand: Vec<ExpandBuilder>,
         options: OperationOptions,
@@ -148,7 +520,7 @@ pub enum Operation {
     /// Update an existing record.
     Update {
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         record: Record,
         options: OperationOptions,
     },
@@ -156,14 +528,14 @@ pub enum Operation {
     /// Delete a record.
     Delete {
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         options: OperationOptions,
     },
 
     /// Upsert (create or update) a record.
     Upsert {
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         record: Record,
         options: OperationOptions,
     },
@@ -222,6 +594,21 @@ impl Operation {
         }
     }
 
+    /// Returns the entity this operation targets.
+    pub fn entity(&self) -> &Entity {
+        match self {
+            Operation::Create { entity, .. } => entity,
+            Operation::Retrieve { entity, .. } => entity,
+            Operation::Update { entity, .. } => entity,
+            Operation::Delete { entity, .. } => entity,
+            Operation::Upsert { entity, .. } => entity,
+            Operation::Associate { entity, .. } => entity,
+            Operation::Disassociate { entity, .. } => entity,
+            Operation::SetLookup { entity, .. } => entity,
+            Operation::ClearLookup { entity, .. } => entity,
+        }
+    }
+
     /// Returns the content-id for this operation, if set.
     pub fn content_id(&self) -> Option<&str> {
         match self {
@@ -268,10 +655,10 @@ impl Op {
     }
 
     /// Creates a new Retrieve operation builder.
-    pub fn retrieve(entity: Entity, id: Uuid) -> RetrieveBuilder {
+    pub fn retrieve(entity: Entity, id: impl Into<RecordKey>) -> RetrieveBuilder {
         RetrieveBuilder {
             entity,
-            id,
+            id: id.into(),
             select: Vec::new(),
             expand: Vec::new(),
             options: OperationOptions::default(),
@@ -279,29 +666,50 @@ impl Op {
     }
 
     /// Creates a new Update operation builder.
-    pub fn update(entity: Entity, id: Uuid, record: Record) -> UpdateBuilder {
+    pub fn update(entity: Entity, id: impl Into<RecordKey>, record: Record) -> UpdateBuilder {
         UpdateBuilder {
             entity,
-            id,
+            id: id.into(),
             record,
             options: OperationOptions::default(),
         }
     }
 
+    /// Creates an Update operation assigning a record to a user or team.
+    pub fn assign(entity: Entity, id: impl Into<RecordKey>, owner: Owner) -> UpdateBuilder {
+        let record = assign_record(&entity, owner);
+        Op::update(entity, id, record)
+    }
+
+    /// Creates an Update operation setting a record's `statecode` and
+    /// `statuscode`.
+    ///
+    /// Unlike [`DataverseClient::set_state`](crate::DataverseClient::set_state),
+    /// the values aren't checked against metadata.
+    pub fn set_state(
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        state: i32,
+        status: i32,
+    ) -> UpdateBuilder {
+        let record = state_record(&entity, state, status);
+        Op::update(entity, id, record)
+    }
+
     /// Creates a new Delete operation builder.
-    pub fn delete(entity: Entity, id: Uuid) -> DeleteBuilder {
+    pub fn delete(entity: Entity, id: impl Into<RecordKey>) -> DeleteBuilder {
         DeleteBuilder {
             entity,
-            id,
+            id: id.into(),
             options: OperationOptions::default(),
         }
     }
 
     /// Creates a new Upsert operation builder.
-    pub fn upsert(entity: Entity, id: Uuid, record: Record) -> UpsertBuilder {
+    pub fn upsert(entity: Entity, id: impl Into<RecordKey>, record: Record) -> UpsertBuilder {
         UpsertBuilder {
             entity,
-            id,
+            id: id.into(),
             record,
             options: OperationOptions::default(),
         }
@@ -417,6 +825,28 @@ impl CreateBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -449,7 +879,7 @@ impl From<CreateBuilder> for Operation {
 #[derive(Debug, Clone)]
 pub struct RetrieveBuilder {
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     select: Vec<String>,
     expand: Vec<ExpandBuilder>,
     options: OperationOptions,
@@ -485,6 +915,46 @@ impl RetrieveBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
+    /// Reads with session consistency from an elastic table, seeing every
+    /// write made before the session token was returned.
+    ///
+    /// See [`DataverseClient::session_token`](crate::DataverseClient::session_token).
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.options.session_token = Some(token.into());
+        self
+    }
+
     /// Builds the operation.
     pub fn build(self) -> Operation {
         Operation::Retrieve {
@@ -507,7 +977,7 @@ impl From<RetrieveBuilder> for Operation {
 #[derive(Debug, Clone)]
 pub struct UpdateBuilder {
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     record: Record,
     options: OperationOptions,
 }
@@ -537,6 +1007,12 @@ impl UpdateBuilder {
         self
     }
 
+    /// Send every field of the record, not just the changed ones.
+    pub fn full_payload(mut self) -> Self {
+        self.options.full_payload = true;
+        self
+    }
+
     /// Skip custom plugin execution.
     pub fn bypass_plugins(mut self) -> Self {
         self.options.bypass_plugins = true;
@@ -549,6 +1025,37 @@ impl UpdateBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -582,7 +1089,7 @@ impl From<UpdateBuilder> for Operation {
 #[derive(Debug, Clone)]
 pub struct DeleteBuilder {
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     options: OperationOptions,
 }
 
@@ -611,6 +1118,37 @@ impl DeleteBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -637,7 +1175,7 @@ impl From<DeleteBuilder> for Operation {
 #[derive(Debug, Clone)]
 pub struct UpsertBuilder {
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     record: Record,
     options: OperationOptions,
 }
@@ -673,6 +1211,12 @@ impl UpsertBuilder {
         self
     }
 
+    /// Only update if the record exists and its ETag matches.
+    pub fn if_match(mut self, etag: impl Into<String>) -> Self {
+        self.options.if_match = Some(etag.into());
+        self
+    }
+
     /// Skip custom plugin execution.
     pub fn bypass_plugins(mut self) -> Self {
         self.options.bypass_plugins = true;
@@ -685,6 +1229,37 @@ impl UpsertBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// The partition is also written to the record's `partitionid`, so a
+    /// record that doesn't exist yet is created in it.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -744,6 +1319,28 @@ impl AssociateBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -804,6 +1401,28 @@ impl DisassociateBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -864,6 +1483,28 @@ impl SetLookupBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -923,6 +1564,28 @@ impl ClearLookupBuilder {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -958,6 +1621,7 @@ impl From<ClearLookupBuilder> for Operation {
 
 /// Result of a create operation.
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum CreateResult {
     /// Only the ID was returned (default).
     Id(Uuid),
@@ -0,0 +1,279 @@
+//! Typed custom API calls
+//!
+//! A custom API (or custom action) is described once as a request type
+//! implementing [`CustomApi`]: its fields are the request parameters, and
+//! the trait names the API, what it's bound to and the response type. Calls
+//! then go through the action subsystem with parameters checked by the
+//! compiler and the response deserialized.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use dataverse_lib::api::{CustomApi, CustomApiBinding};
+//!
+//! #[derive(Serialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct CalculateDiscount {
+//!     quantity: i32,
+//!     promo_code: Option<String>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct CalculateDiscountResponse {
+//!     discount: f64,
+//! }
+//!
+//! impl CustomApi for CalculateDiscount {
+//!     const NAME: &'static str = "contoso_CalculateDiscount";
+//!     const BINDING: CustomApiBinding = CustomApiBinding::Entity("product");
+//!     type Response = CalculateDiscountResponse;
+//! }
+//!
+//! let response = client
+//!     .invoke(CalculateDiscount { quantity: 10, promo_code: None })
+//!     .bound(product_id)
+//!     .await?;
+//! println!("{}", response.discount);
+//! ```
+
+use std::future::Future;
+use std::future::IntoFuture;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as Json;
+
+use super::crud::RecordKey;
+use super::long_running::PollOptions;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+
+/// What a custom API is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomApiBinding {
+    /// Not bound; called at the service root.
+    Unbound,
+    /// Bound to a record of the entity with this logical name.
+    Entity(&'static str),
+    /// Bound to the collection of the entity with this logical name.
+    EntityCollection(&'static str),
+}
+
+/// The signature of a custom API.
+///
+/// The implementing type is the request: it must serialize to a JSON object
+/// whose fields are the API's request parameters (or to nothing, for APIs
+/// without parameters). Fields serialized as `null` are left out, so
+/// optional parameters can be `Option`s.
+pub trait CustomApi: Serialize {
+    /// The API's unique name.
+    const NAME: &'static str;
+
+    /// What the API is bound to.
+    const BINDING: CustomApiBinding = CustomApiBinding::Unbound;
+
+    /// Whether the API is a function (`GET`) rather than an action (`POST`).
+    const IS_FUNCTION: bool = false;
+
+    /// The API's response properties. Use `()` for APIs without any.
+    type Response: DeserializeOwned;
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Calls a custom API with a typed request.
+    ///
+    /// APIs bound to a record also need the record's key, given with
+    /// [`bound`](ClientCustomApiBuilder::bound).
+    pub fn invoke<A: CustomApi>(&self, request: A) -> ClientCustomApiBuilder<'_, A> {
+        ClientCustomApiBuilder {
+            client: self,
+            request,
+            key: None,
+            long_running: None,
+            _response: PhantomData,
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for calling a custom API, bound to a client.
+pub struct ClientCustomApiBuilder<'a, A: CustomApi> {
+    client: &'a DataverseClient,
+    request: A,
+    key: Option<RecordKey>,
+    long_running: Option<PollOptions>,
+    _response: PhantomData<fn() -> A::Response>,
+}
+
+impl<'a, A: CustomApi> ClientCustomApiBuilder<'a, A> {
+    /// Sets the record an entity-bound API is called on.
+    pub fn bound(mut self, key: impl Into<RecordKey>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Waits for the call to complete if the API runs as a long-running
+    /// operation, polling with `options`.
+    pub fn long_running(mut self, options: PollOptions) -> Self {
+        self.long_running = Some(options);
+        self
+    }
+
+    /// Returns the request parameters, leaving out nulls.
+    fn parameters(&self) -> Result<Vec<(String, Json)>, Error> {
+        match serde_json::to_value(&self.request)? {
+            Json::Null => Ok(Vec::new()),
+            Json::Object(fields) => Ok(fields
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .collect()),
+            _ => Err(Error::InvalidOperation(format!(
+                "request of custom API {} must serialize to an object",
+                A::NAME
+            ))),
+        }
+    }
+
+    /// Makes the call and deserializes its response.
+    pub async fn execute(self) -> Result<A::Response, Error> {
+        let params = self.parameters()?;
+        let mut builder = if A::IS_FUNCTION {
+            self.client.function(A::NAME)
+        } else {
+            self.client.action(A::NAME)
+        };
+
+        builder = match (A::BINDING, self.key) {
+            (CustomApiBinding::Unbound, None) => builder,
+            (CustomApiBinding::Entity(entity), Some(key)) => {
+                builder.bound(Entity::logical(entity), key)
+            }
+            (CustomApiBinding::EntityCollection(entity), None) => {
+                builder.bound_collection(Entity::logical(entity))
+            }
+            (CustomApiBinding::Entity(entity), None) => {
+                return Err(Error::InvalidOperation(format!(
+                    "custom API {} is bound to a {} record; set it with bound()",
+                    A::NAME,
+                    entity
+                )));
+            }
+            (_, Some(_)) => {
+                return Err(Error::InvalidOperation(format!(
+                    "custom API {} isn't bound to a record",
+                    A::NAME
+                )));
+            }
+        };
+
+        for (name, value) in params {
+            builder = builder.param(name, value);
+        }
+        if let Some(options) = self.long_running {
+            builder = builder.long_running(options);
+        }
+        builder.execute_as().await
+    }
+}
+
+impl<'a, A> IntoFuture for ClientCustomApiBuilder<'a, A>
+where
+    A: CustomApi + Send + 'a,
+{
+    type Output = Result<A::Response, Error>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use serde::Deserialize;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::WhoAmIResponse;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+
+    #[derive(Serialize)]
+    struct WhoAmI;
+
+    impl CustomApi for WhoAmI {
+        const NAME: &'static str = "WhoAmI";
+        const IS_FUNCTION: bool = true;
+        type Response = WhoAmIResponse;
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct SendEmail {
+        issue_send: bool,
+        tracking_token: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct SendEmailResponse {
+        subject: String,
+    }
+
+    impl CustomApi for SendEmail {
+        const NAME: &'static str = "SendEmail";
+        const BINDING: CustomApiBinding = CustomApiBinding::Entity("email");
+        type Response = SendEmailResponse;
+    }
+
+    #[tokio::test]
+    async fn test_invoke_typed_custom_api() {
+        let mock = MockDataverse::new().entity(MockEntity::new("email", "emails", "activityid"));
+        let client = mock.client();
+
+        let me = client.invoke(WhoAmI).await.unwrap();
+        assert_eq!(me.user_id, mock.user_id());
+
+        let email = mock.insert(
+            Entity::set("emails"),
+            Record::new("email").set("subject", "Hello"),
+        );
+        let send = || SendEmail {
+            issue_send: false,
+            tracking_token: None,
+        };
+
+        // Bound APIs need a record
+        assert!(matches!(
+            client.invoke(send()).await,
+            Err(Error::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            client.invoke(WhoAmI).bound(Uuid::nil()).await,
+            Err(Error::InvalidOperation(_))
+        ));
+
+        let response = client.invoke(send()).bound(email).await.unwrap();
+        assert_eq!(response.subject, "Hello");
+        let request = mock.requests().pop().unwrap();
+        assert_eq!(
+            request.url.path(),
+            format!(
+                "/api/data/v9.2/emails({})/Microsoft.Dynamics.CRM.SendEmail",
+                email
+            )
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Word documents and Excel exports
+//!
+//! [`DataverseClient::export_word_document`] fills a Word document template
+//! with records, using `ExportWordDocument`, and
+//! [`DataverseClient::export_to_excel`] exports a view's rows to a workbook,
+//! using `ExportToExcel`. Both come back as [`FileContent`], like file column
+//! downloads. Templates for a table are listed with
+//! [`DataverseClient::document_templates`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let templates = client.document_templates(Entity::logical("account")).await?;
+//! let template = templates
+//!     .iter()
+//!     .find(|t| t.document_type == DocumentType::Word)
+//!     .unwrap();
+//! let document = client
+//!     .export_word_document(template.id, Entity::logical("account"), &[account])
+//!     .await?;
+//! tokio::fs::write("account.docx", &document.data).await?;
+//!
+//! let workbook = client.export_to_excel(ViewReference::System(active_accounts)).await?;
+//! ```
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::file::FileContent;
+use super::query::Filter;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::model::DataverseOptionSet;
+use crate::model::Entity;
+
+/// The kind of document a template produces (`documenttype`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum DocumentType {
+    /// A Microsoft Excel workbook.
+    Excel = 1,
+    /// A Microsoft Word document.
+    Word = 2,
+}
+
+/// A document template (`documenttemplate` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "documenttemplate", set = "documenttemplates")]
+pub struct DocumentTemplate {
+    /// The template's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The template's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The template's description.
+    #[dataverse(read_only)]
+    pub description: Option<String>,
+    /// The kind of document the template produces.
+    #[dataverse(rename = "documenttype", read_only)]
+    pub document_type: DocumentType,
+    /// Logical name of the table the template is for.
+    #[dataverse(rename = "associatedentitytypecode", read_only)]
+    pub entity_logical_name: Option<String>,
+    /// The template's language code (e.g. `1033`).
+    #[dataverse(rename = "languagecode", read_only)]
+    pub language_code: Option<i32>,
+}
+
+/// A view to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViewReference {
+    /// A system view (`savedquery`).
+    System(Uuid),
+    /// A personal view (`userquery`).
+    Personal(Uuid),
+}
+
+impl ViewReference {
+    /// Returns the view's entity.
+    fn entity(&self) -> Entity {
+        match self {
+            ViewReference::System(_) => Entity::logical("savedquery"),
+            ViewReference::Personal(_) => Entity::logical("userquery"),
+        }
+    }
+
+    /// Returns the view's id.
+    pub fn id(&self) -> Uuid {
+        match self {
+            ViewReference::System(id) | ViewReference::Personal(id) => *id,
+        }
+    }
+}
+
+/// Response of `ExportWordDocument`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExportWordDocumentResponse {
+    word_file: String,
+}
+
+/// Response of `ExportToExcel`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExportToExcelResponse {
+    excel_file: String,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Lists the document templates of a table.
+    pub async fn document_templates(
+        &self,
+        entity: impl Into<Entity>,
+    ) -> Result<Vec<DocumentTemplate>, Error> {
+        let logical_name = self.resolve_entity_logical_name(&entity.into()).await?;
+        let mut pages = self
+            .query(DocumentTemplate::entity())
+            .filter(Filter::eq("associatedentitytypecode", logical_name))
+            .into_typed::<DocumentTemplate>()
+            .into_async_iter(self);
+        let mut templates = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            templates.extend(page?);
+        }
+        Ok(templates)
+    }
+
+    /// Generates a Word document from a template for one or more records of
+    /// the template's table.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The Word document template's id
+    /// * `entity` - The records' entity
+    /// * `ids` - The records to fill the template with
+    pub async fn export_word_document(
+        &self,
+        template: Uuid,
+        entity: impl Into<Entity>,
+        ids: &[Uuid],
+    ) -> Result<FileContent, Error> {
+        if ids.is_empty() {
+            return Err(Error::InvalidOperation(
+                "A Word document needs at least one record".to_string(),
+            ));
+        }
+        let metadata = self.metadata().entity(entity.into()).await?;
+        let records: Vec<String> = ids.iter().map(|id| format!("{{{}}}", id)).collect();
+        let response: ExportWordDocumentResponse = self
+            .action("ExportWordDocument")
+            .param("EntityTypeCode", metadata.object_type_code)
+            .reference_param("SelectedTemplate", DocumentTemplate::entity(), template)
+            .param("SelectedRecords", json!(records).to_string())
+            .execute_as()
+            .await?;
+        decode_file("Word document", &response.word_file)
+    }
+
+    /// Exports the rows of a view to an Excel workbook.
+    pub async fn export_to_excel(&self, view: ViewReference) -> Result<FileContent, Error> {
+        let definition = self
+            .retrieve(view.entity(), view.id())
+            .select(&["fetchxml", "layoutxml"])
+            .await?
+            .into_inner();
+        let fetch_xml = definition.get_string("fetchxml")?.unwrap_or_default();
+        let layout_xml = definition.get_string("layoutxml")?.unwrap_or_default();
+        let response: ExportToExcelResponse = self
+            .action("ExportToExcel")
+            .reference_param("View", view.entity(), view.id())
+            .param("FetchXml", fetch_xml)
+            .param("LayoutXml", layout_xml)
+            .param("QueryApi", "")
+            .param(
+                "QueryParameters",
+                json!({ "Arguments": { "Count": 0, "IsReadOnly": true, "Keys": [], "Values": [] } }),
+            )
+            .execute_as()
+            .await?;
+        decode_file("Excel workbook", &response.excel_file)
+    }
+}
+
+/// Decodes a base64 file returned by an action.
+fn decode_file(kind: &str, data: &str) -> Result<FileContent, Error> {
+    let data = STANDARD.decode(data).map_err(|e| {
+        Error::Api(ApiError::Parse {
+            message: format!("Invalid {}: {}", kind, e),
+            body: None,
+        })
+    })?;
+    Ok(FileContent {
+        file_name: None,
+        data: data.into(),
+    })
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+    use crate::model::metadata::AttributeType;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new(
+                    "documenttemplate",
+                    "documenttemplates",
+                    "documenttemplateid",
+                )
+                .primary_name("name")
+                .attribute("documenttype", AttributeType::Picklist)
+                .attribute("associatedentitytypecode", AttributeType::EntityName)
+                .attribute("content", AttributeType::Memo),
+            )
+            .entity(
+                MockEntity::new("savedquery", "savedqueries", "savedqueryid")
+                    .primary_name("name")
+                    .attribute("fetchxml", AttributeType::Memo)
+                    .attribute("layoutxml", AttributeType::Memo),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_export_word_document() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let template = mock.insert(
+            Entity::set("documenttemplates"),
+            Record::new("documenttemplate")
+                .set("name", "Account summary")
+                .set("documenttype", 2)
+                .set("associatedentitytypecode", "account")
+                .set("content", STANDARD.encode(b"PK docx")),
+        );
+        mock.insert(
+            Entity::set("documenttemplates"),
+            Record::new("documenttemplate")
+                .set("name", "Contact list")
+                .set("documenttype", 1)
+                .set("associatedentitytypecode", "contact"),
+        );
+
+        let templates = client
+            .document_templates(Entity::set("accounts"))
+            .await
+            .unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].id, template);
+        assert_eq!(templates[0].document_type, DocumentType::Word);
+
+        let document = client
+            .export_word_document(template, Entity::logical("account"), &[account])
+            .await
+            .unwrap();
+        assert_eq!(&document.data[..], b"PK docx");
+
+        let err = client
+            .export_word_document(template, Entity::logical("account"), &[Uuid::new_v4()])
+            .await;
+        assert!(err.is_err());
+        let err = client
+            .export_word_document(template, Entity::logical("account"), &[])
+            .await;
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_export_to_excel() {
+        let mock = mock();
+        let client = mock.client();
+        let fetch_xml =
+            r#"<fetch><entity name="account"><attribute name="name"/></entity></fetch>"#;
+        let view = mock.insert(
+            Entity::set("savedqueries"),
+            Record::new("savedquery")
+                .set("name", "Active Accounts")
+                .set("fetchxml", fetch_xml)
+                .set(
+                    "layoutxml",
+                    r#"<grid><row><cell name="name"/></row></grid>"#,
+                ),
+        );
+
+        let workbook = client
+            .export_to_excel(ViewReference::System(view))
+            .await
+            .unwrap();
+        assert_eq!(&workbook.data[..], fetch_xml.as_bytes());
+        assert!(
+            client
+                .export_to_excel(ViewReference::System(Uuid::new_v4()))
+                .await
+                .is_err()
+        );
+    }
+}
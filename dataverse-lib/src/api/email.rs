@@ -0,0 +1,350 @@
+//! Composing and sending emails
+//!
+//! An email is an `email` activity whose sender and recipients are
+//! `activityparty` records, with attachments stored as
+//! `activitymimeattachment` records. [`DataverseClient::send_email`] creates
+//! all of them and calls the `SendEmail` action in one go.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let email_id = client
+//!     .send_email()
+//!     .from(EmailParty::record(Entity::logical("systemuser"), user_id))
+//!     .to(EmailParty::record(Entity::logical("contact"), contact_id))
+//!     .cc("billing@contoso.com")
+//!     .subject("Your invoice")
+//!     .body("<p>Please find the invoice attached.</p>")
+//!     .regarding(Entity::logical("account"), account_id)
+//!     .attachment("invoice.pdf", "application/pdf", pdf)
+//!     .await?;
+//! ```
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::Value;
+use crate::model::types::EntityBinding;
+
+/// `participationtypemask` of an email's sender.
+const PARTY_FROM: i32 = 1;
+/// `participationtypemask` of an email's To recipients.
+const PARTY_TO: i32 = 2;
+/// `participationtypemask` of an email's Cc recipients.
+const PARTY_CC: i32 = 3;
+/// `participationtypemask` of an email's Bcc recipients.
+const PARTY_BCC: i32 = 4;
+
+/// A sender or recipient of an email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailParty {
+    /// A record with an email address (a user, contact, account, ...).
+    Record {
+        /// The record's entity.
+        entity: Entity,
+        /// The record's id.
+        id: Uuid,
+    },
+    /// A plain email address that isn't resolved to a record.
+    Address(String),
+}
+
+impl EmailParty {
+    /// Creates a party for a record.
+    pub fn record(entity: impl Into<Entity>, id: Uuid) -> Self {
+        Self::Record {
+            entity: entity.into(),
+            id,
+        }
+    }
+
+    /// Creates a party for a plain email address.
+    pub fn address(address: impl Into<String>) -> Self {
+        Self::Address(address.into())
+    }
+}
+
+impl From<&str> for EmailParty {
+    fn from(address: &str) -> Self {
+        Self::address(address)
+    }
+}
+
+impl From<String> for EmailParty {
+    fn from(address: String) -> Self {
+        Self::Address(address)
+    }
+}
+
+/// A file to attach to an email.
+#[derive(Debug, Clone)]
+struct EmailAttachment {
+    file_name: String,
+    mime_type: String,
+    data: Bytes,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Composes an email to create and send in one call.
+    ///
+    /// Awaiting the builder creates the email with its parties and
+    /// attachments, sends it with the `SendEmail` action and returns its id.
+    pub fn send_email(&self) -> ClientSendEmailBuilder<'_> {
+        ClientSendEmailBuilder {
+            client: self,
+            parties: Vec::new(),
+            subject: None,
+            body: None,
+            regarding: None,
+            attachments: Vec::new(),
+            issue_send: true,
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for composing and sending an email, bound to a client.
+pub struct ClientSendEmailBuilder<'a> {
+    client: &'a DataverseClient,
+    /// Parties with their `participationtypemask`.
+    parties: Vec<(i32, EmailParty)>,
+    subject: Option<String>,
+    body: Option<String>,
+    regarding: Option<(Entity, Uuid)>,
+    attachments: Vec<EmailAttachment>,
+    issue_send: bool,
+}
+
+impl<'a> ClientSendEmailBuilder<'a> {
+    /// Sets the sender. Defaults to the calling user.
+    pub fn from(mut self, party: impl Into<EmailParty>) -> Self {
+        self.parties.retain(|(mask, _)| *mask != PARTY_FROM);
+        self.parties.push((PARTY_FROM, party.into()));
+        self
+    }
+
+    /// Adds a To recipient.
+    pub fn to(mut self, party: impl Into<EmailParty>) -> Self {
+        self.parties.push((PARTY_TO, party.into()));
+        self
+    }
+
+    /// Adds a Cc recipient.
+    pub fn cc(mut self, party: impl Into<EmailParty>) -> Self {
+        self.parties.push((PARTY_CC, party.into()));
+        self
+    }
+
+    /// Adds a Bcc recipient.
+    pub fn bcc(mut self, party: impl Into<EmailParty>) -> Self {
+        self.parties.push((PARTY_BCC, party.into()));
+        self
+    }
+
+    /// Sets the subject.
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Sets the body (HTML).
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sets the record the email is about.
+    pub fn regarding(mut self, entity: impl Into<Entity>, id: Uuid) -> Self {
+        self.regarding = Some((entity.into(), id));
+        self
+    }
+
+    /// Attaches a file.
+    pub fn attachment(
+        mut self,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+        data: impl Into<Bytes>,
+    ) -> Self {
+        self.attachments.push(EmailAttachment {
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            data: data.into(),
+        });
+        self
+    }
+
+    /// Sets whether Dataverse actually sends the email (default `true`).
+    ///
+    /// With `false` the email is only marked as sent, for tracking mail sent
+    /// some other way.
+    pub fn issue_send(mut self, issue_send: bool) -> Self {
+        self.issue_send = issue_send;
+        self
+    }
+
+    async fn execute(self) -> Result<Uuid, Error> {
+        let client = self.client;
+
+        let mut parties = Vec::new();
+        for (mask, party) in self.parties {
+            let record = Record::new("activityparty").set("participationtypemask", mask);
+            let record = match party {
+                EmailParty::Record { entity, id } => {
+                    let logical_name = client.resolve_entity_logical_name(&entity).await?;
+                    let entity_set = client.resolve_entity(&entity).await?;
+                    record.set(
+                        format!("partyid_{}", logical_name),
+                        EntityBinding::new(entity_set, id),
+                    )
+                }
+                EmailParty::Address(address) => record.set("addressused", address),
+            };
+            parties.push(record);
+        }
+
+        let mut email = Record::new("email").set("email_activity_parties", Value::Records(parties));
+        if let Some(subject) = self.subject {
+            email.insert("subject", subject);
+        }
+        if let Some(body) = self.body {
+            email.insert("description", body);
+        }
+        if let Some((entity, id)) = self.regarding {
+            let logical_name = client.resolve_entity_logical_name(&entity).await?;
+            let entity_set = client.resolve_entity(&entity).await?;
+            email.insert(
+                format!("regardingobjectid_{}", logical_name),
+                EntityBinding::new(entity_set, id),
+            );
+        }
+        let email_id = client.create(Entity::logical("email"), email).await?.id()?;
+
+        for attachment in self.attachments {
+            let record = Record::new("activitymimeattachment")
+                .set(
+                    "objectid_activitypointer",
+                    EntityBinding::new("activitypointers", email_id),
+                )
+                .set("objecttypecode", "email")
+                .set("filename", attachment.file_name)
+                .set("mimetype", attachment.mime_type)
+                .set("body", STANDARD.encode(&attachment.data));
+            client
+                .create(Entity::logical("activitymimeattachment"), record)
+                .await?;
+        }
+
+        client
+            .action("SendEmail")
+            .bound(Entity::logical("email"), email_id)
+            .param("IssueSend", self.issue_send)
+            .execute()
+            .await?;
+        Ok(email_id)
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientSendEmailBuilder<'a> {
+    type Output = Result<Uuid, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    #[tokio::test]
+    async fn test_send_email_with_attachment() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid"))
+            .entity(MockEntity::new("contact", "contacts", "contactid"))
+            .entity(MockEntity::new("email", "emails", "activityid"))
+            .entity(MockEntity::new(
+                "activitymimeattachment",
+                "activitymimeattachments",
+                "activitymimeattachmentid",
+            ));
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let contact = mock.insert(Entity::set("contacts"), Record::new("contact"));
+
+        let email_id = client
+            .send_email()
+            .to(EmailParty::record(Entity::logical("contact"), contact))
+            .cc("billing@contoso.com")
+            .subject("Your invoice")
+            .body("<p>Attached.</p>")
+            .regarding(Entity::logical("account"), account)
+            .attachment("invoice.txt", "text/plain", &b"total: 42"[..])
+            .await
+            .unwrap();
+
+        let email = mock.record(Entity::set("emails"), email_id).unwrap();
+        assert_eq!(email.get_string("subject").unwrap(), Some("Your invoice"));
+        assert_eq!(
+            email.get_string("description").unwrap(),
+            Some("<p>Attached.</p>")
+        );
+        assert_eq!(email.get_int("statuscode").unwrap(), Some(6));
+        assert_eq!(
+            email
+                .lookup_info("regardingobjectid_account")
+                .and_then(|info| info.id),
+            Some(account)
+        );
+        let Some(Value::Records(parties)) = email.get("email_activity_parties") else {
+            panic!("email has no parties");
+        };
+        assert_eq!(parties.len(), 2);
+        assert_eq!(
+            parties[0].get_int("participationtypemask").unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            parties[0].annotation("partyid_contact", "odata.bind"),
+            Some(format!("/contacts({})", contact).as_str())
+        );
+        assert_eq!(
+            parties[1].get_int("participationtypemask").unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            parties[1].get_string("addressused").unwrap(),
+            Some("billing@contoso.com")
+        );
+
+        let attachments = mock.records(Entity::set("activitymimeattachments"));
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(
+            attachments[0]
+                .lookup_info("objectid_activitypointer")
+                .and_then(|info| info.id),
+            Some(email_id)
+        );
+        assert_eq!(
+            attachments[0].get_string("body").unwrap(),
+            Some(STANDARD.encode(b"total: 42").as_str())
+        );
+    }
+}
@@ -2,13 +2,21 @@
 //!
 //! This module contains the HTTP execution logic for CRUD operations.
 
+use std::future::Future;
+use std::pin::Pin;
 use std::time::Duration;
+use std::time::Instant;
 
+use bytes::Bytes;
+use chrono::Utc;
 use reqwest::Method;
 use reqwest::StatusCode;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
-use serde_json::json;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use uuid::Uuid;
 
 use super::aggregate::AggregateBuilder;
@@ -19,22 +27,81 @@ use super::batch::multipart::build_batch_body;
 use super::batch::multipart::generate_boundary;
 use super::batch::response::extract_boundary;
 use super::crud::CreateResult;
+use super::crud::Impersonation;
 use super::crud::Operation;
+use super::crud::OperationKind;
 use super::crud::OperationOptions;
+use super::crud::Owner;
+use super::crud::RecordKey;
 use super::crud::UpsertResult;
+use super::crud::assign_record;
+use super::crud::check_alternate_key;
+use super::crud::check_state;
+use super::crud::partitioned_record;
+use super::crud::push_partition;
+use super::crud::ref_body;
+use super::crud::ref_path;
+use super::crud::state_record;
+use super::crud::update_body;
 use super::metadata::MetadataClient;
+use super::metadata::entity::cached_entity_metadata;
 use super::metadata::entity::fetch_entity_core;
+use super::metadata::key::cached_entity_keys;
+use super::passthrough::apply_headers;
+use super::passthrough::push_query_options;
 use super::query::fetchxml::FetchBuilder;
+use super::query::fetchxml::parse_fetchxml;
 use super::query::odata::ExpandBuilder;
 use super::query::odata::QueryBuilder;
 use super::query::odata::RelatedQueryBuilder;
 use super::query::odata::url::build_select_expand_params;
 use crate::DataverseClient;
+use crate::cache::CachedValue;
 use crate::error::ApiError;
+use crate::error::DataverseErrorDetail;
 use crate::error::Error;
+use crate::error::RawResponse;
+use crate::middleware::RequestInfo;
 use crate::model::Entity;
 use crate::model::Record;
+use crate::model::metadata::WriteKind;
+use crate::response::CacheStatus;
+use crate::response::RequestMetadata;
 use crate::response::Response;
+use crate::telemetry::CORRELATION_ID_HEADER;
+use crate::telemetry::InSpan;
+use crate::telemetry::REQUEST_ID_HEADER;
+use crate::telemetry::Span;
+use crate::telemetry::span;
+
+/// Response header carrying the session token of a write to an elastic table.
+const SESSION_TOKEN_HEADER: &str = "x-ms-session-token";
+
+/// Request header asking for a read consistent with a session token.
+pub(crate) const SESSION_TOKEN_REQUEST_HEADER: &str = "MSCRM.SessionToken";
+
+/// Cache key prefix for records cached by conditional retrieves.
+pub const RECORD_CACHE_PREFIX: &str = "record:";
+
+/// A record cached by a conditional retrieve, with the ETag to revalidate it.
+#[derive(Serialize, Deserialize)]
+struct CachedRecord {
+    etag: String,
+    /// The response body, as returned by Dataverse.
+    body: String,
+}
+
+/// Builds the cache key of a conditional retrieve from its URL and
+/// impersonated user.
+fn record_cache_key(url: &str, impersonate: Option<Impersonation>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    if let Some(impersonation) = impersonate {
+        let (name, value) = impersonation.header();
+        hasher.update(format!("\n{}: {}", name, value).as_bytes());
+    }
+    format!("{}{:x}", RECORD_CACHE_PREFIX, hasher.finalize())
+}
 
 impl DataverseClient {
     /// Executes any operation.
@@ -51,6 +118,11 @@ impl DataverseClient {
     /// ```
     pub async fn execute(&self, operation: impl Into<Operation>) -> Result<OperationResult, Error> {
         let operation = operation.into();
+        let span = crud_span(OperationKind::from(&operation), operation.entity());
+        self.execute_operation(operation).in_span(span).await
+    }
+
+    async fn execute_operation(&self, operation: Operation) -> Result<OperationResult, Error> {
         match operation {
             Operation::Create {
                 entity,
@@ -68,7 +140,7 @@ impl DataverseClient {
                 options,
             } => {
                 let result = self
-                    .execute_retrieve(entity, id, select, expand, options)
+                    .execute_retrieve(entity, id, select, expand, options, false)
                     .await?;
                 Ok(OperationResult::Retrieve(result))
             }
@@ -170,6 +242,10 @@ impl DataverseClient {
         record: Record,
         options: OperationOptions,
     ) -> Result<CreateResult, Error> {
+        if options.validate {
+            self.validate_record(&entity, &record, WriteKind::Create)
+                .await?;
+        }
         let entity_set = self.resolve_entity(&entity).await?;
         let mut url = format!("/{}", entity_set);
 
@@ -184,16 +260,19 @@ impl DataverseClient {
             }
         }
 
-        let full_url = self.build_url(&url);
+        let mut full_url = self.build_url(&url);
+        push_query_options(&mut full_url, &options.query_options)?;
         let body = serde_json::to_string(&record).map_err(Error::Serialization)?;
 
+        apply_headers(&mut headers, &options.headers)?;
         let response = self
             .request(Method::POST, &full_url, headers, Some(body))
             .await?;
 
         if options.return_record {
             // 201 Created with body
-            let record: Record = response.json().await.map_err(ApiError::from)?;
+            let mut record: Record = response.json().await.map_err(ApiError::from)?;
+            record.mark_loaded();
             Ok(CreateResult::Record(record))
         } else {
             // 204 No Content with OData-EntityId header
@@ -202,12 +281,7 @@ impl DataverseClient {
                 .get("OData-EntityId")
                 .and_then(|v| v.to_str().ok())
                 .and_then(extract_guid_from_entity_id)
-                .ok_or_else(|| {
-                    Error::Api(ApiError::Parse {
-                        message: "Missing or invalid OData-EntityId header".to_string(),
-                        body: None,
-                    })
-                })?;
+                .ok_or_else(missing_entity_id)?;
             Ok(CreateResult::Id(entity_id))
         }
     }
@@ -215,13 +289,15 @@ impl DataverseClient {
     async fn execute_retrieve(
         &self,
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         select: Vec<String>,
         expand: Vec<ExpandBuilder>,
         options: OperationOptions,
+        conditional: bool,
     ) -> Result<Response<Record>, Error> {
+        self.check_record_key(&entity, &id).await?;
         let entity_set = self.resolve_entity(&entity).await?;
-        let mut url = format!("/{}({})", entity_set, id);
+        let mut url = format!("/{}({})", entity_set, id.to_path());
 
         // Build query parameters using shared helper
         let query_params = build_select_expand_params(&select, &expand);
@@ -229,8 +305,10 @@ impl DataverseClient {
             url.push('?');
             url.push_str(&query_params);
         }
+        push_partition(&mut url, &options);
 
-        let full_url = self.build_url(&url);
+        let mut full_url = self.build_url(&url);
+        push_query_options(&mut full_url, &options.query_options)?;
         let mut headers = self.default_headers();
         headers.insert(
             "Prefer",
@@ -238,21 +316,109 @@ impl DataverseClient {
         );
         self.apply_options_headers(&mut headers, &options);
 
+        // Revalidate the cached copy, if any, with its ETag. Custom headers
+        // can change the representation, so those reads aren't cached
+        let cache = self
+            .cache()
+            .filter(|_| conditional && options.headers.is_empty());
+        let cache_key = record_cache_key(&full_url, options.impersonate.or(self.impersonation()));
+        let mut cached = None;
+        if let Some(cache) = cache
+            && let Some(value) = cache.get(&cache_key).await
+        {
+            match crate::cache::deserialize::<CachedRecord>(&value.data) {
+                Ok(record) => {
+                    if let Ok(etag) = HeaderValue::from_str(&record.etag) {
+                        headers.insert("If-None-Match", etag);
+                    }
+                    cached = Some((record, value));
+                }
+                Err(e) => {
+                    log::warn!("Failed to deserialize cached record: {}", e);
+                    cache.remove(&cache_key).await;
+                }
+            }
+        }
+
+        apply_headers(&mut headers, &options.headers)?;
         let response = self.request(Method::GET, &full_url, headers, None).await?;
-        let record: Record = response.json().await.map_err(ApiError::from)?;
+        let request = response.extensions().get::<RequestMetadata>().cloned();
+        let ttl = self.inner.cache_config.record_ttl;
+
+        let (body, mut status) = match cached {
+            Some((record, value)) if response.status() == StatusCode::NOT_MODIFIED => {
+                // Keep the unchanged copy for another TTL
+                let expires_at = Utc::now() + chrono::Duration::from_std(ttl).unwrap_or_default();
+                if let Some(cache) = cache {
+                    let value = CachedValue::new(value.data, value.created_at, expires_at);
+                    cache.set(&cache_key, value).await;
+                }
+                let status = CacheStatus::Hit {
+                    cached_at: value.created_at,
+                    expires_at,
+                };
+                (record.body, status)
+            }
+            _ => {
+                let body = response.text().await.map_err(ApiError::from)?;
+                (body, CacheStatus::None)
+            }
+        };
+
+        let mut record: Record = serde_json::from_str(&body).map_err(Error::Serialization)?;
+        record.mark_loaded();
+        if let Some(cache) = cache
+            && status.is_none()
+            && !ttl.is_zero()
+            && let Some(etag) = record.etag()
+        {
+            let entry = CachedRecord {
+                etag: etag.to_string(),
+                body,
+            };
+            match crate::cache::serialize(&entry) {
+                Ok(bytes) => {
+                    let value = CachedValue::with_ttl(bytes, ttl);
+                    status = CacheStatus::Miss {
+                        cached_at: value.created_at,
+                        expires_at: value.expires_at,
+                    };
+                    cache.set(&cache_key, value).await;
+                }
+                Err(e) => log::warn!("Failed to serialize record for cache: {}", e),
+            }
+        }
 
-        Ok(Response::fresh(record))
+        // The response body doesn't say which record it is
+        record.set_entity(entity);
+        if let (None, Some(id)) = (record.id(), id.id()) {
+            record.set_id(id);
+        }
+
+        let mut response = Response::fresh(record).with_request(request);
+        response.cache = status;
+        Ok(response)
     }
 
     async fn execute_update(
         &self,
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         record: Record,
         options: OperationOptions,
     ) -> Result<Option<Record>, Error> {
+        if options.validate {
+            let changes = if options.full_payload {
+                record.clone()
+            } else {
+                record.changes()
+            };
+            self.validate_record(&entity, &changes, WriteKind::Update)
+                .await?;
+        }
+        self.check_record_key(&entity, &id).await?;
         let entity_set = self.resolve_entity(&entity).await?;
-        let mut url = format!("/{}({})", entity_set, id);
+        let mut url = format!("/{}({})", entity_set, id.to_path());
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
@@ -270,16 +436,20 @@ impl DataverseClient {
                 url.push_str(&format!("?$select={}", options.select.join(",")));
             }
         }
+        push_partition(&mut url, &options);
 
-        let full_url = self.build_url(&url);
-        let body = serde_json::to_string(&record).map_err(Error::Serialization)?;
+        let mut full_url = self.build_url(&url);
+        push_query_options(&mut full_url, &options.query_options)?;
+        let body = update_body(&record, &options)?;
 
+        apply_headers(&mut headers, &options.headers)?;
         let response = self
             .request(Method::PATCH, &full_url, headers, Some(body))
             .await?;
 
         if options.return_record {
-            let record: Record = response.json().await.map_err(ApiError::from)?;
+            let mut record: Record = response.json().await.map_err(ApiError::from)?;
+            record.mark_loaded();
             Ok(Some(record))
         } else {
             Ok(None)
@@ -289,11 +459,15 @@ impl DataverseClient {
     async fn execute_delete(
         &self,
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         options: OperationOptions,
     ) -> Result<(), Error> {
+        self.check_record_key(&entity, &id).await?;
         let entity_set = self.resolve_entity(&entity).await?;
-        let url = self.build_url(&format!("/{}({})", entity_set, id));
+        let mut url = format!("/{}({})", entity_set, id.to_path());
+        push_partition(&mut url, &options);
+        let mut url = self.build_url(&url);
+        push_query_options(&mut url, &options.query_options)?;
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
@@ -304,6 +478,7 @@ impl DataverseClient {
             headers.insert("If-Match", header_value);
         }
 
+        apply_headers(&mut headers, &options.headers)?;
         self.request(Method::DELETE, &url, headers, None).await?;
         Ok(())
     }
@@ -311,12 +486,23 @@ impl DataverseClient {
     async fn execute_upsert(
         &self,
         entity: Entity,
-        id: Uuid,
+        id: RecordKey,
         record: Record,
         options: OperationOptions,
     ) -> Result<UpsertResult, Error> {
+        if options.validate {
+            let kind = if options.if_none_match {
+                WriteKind::Create
+            } else if options.if_match.is_some() {
+                WriteKind::Update
+            } else {
+                WriteKind::Upsert
+            };
+            self.validate_record(&entity, &record, kind).await?;
+        }
+        self.check_record_key(&entity, &id).await?;
         let entity_set = self.resolve_entity(&entity).await?;
-        let mut url = format!("/{}({})", entity_set, id);
+        let mut url = format!("/{}({})", entity_set, id.to_path());
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
@@ -329,47 +515,88 @@ impl DataverseClient {
             headers.insert("If-Match", header_value);
         }
 
-        if options.return_record {
+        // Dataverse answers 204 whether it created or updated, so an
+        // unconditional upsert asks for the record back (just its primary
+        // key) to tell the two apart
+        let ambiguous = !options.if_none_match && options.if_match.is_none();
+        let select = if options.return_record {
+            options.select.clone()
+        } else if ambiguous {
+            self.upsert_probe_select(&entity).await
+        } else {
+            Vec::new()
+        };
+        if options.return_record || ambiguous {
             headers.insert("Prefer", HeaderValue::from_static("return=representation"));
-            // Add $select query parameter when options.select is non-empty
-            if !options.select.is_empty() {
-                url.push_str(&format!("?$select={}", options.select.join(",")));
+            if !select.is_empty() {
+                url.push_str(&format!("?$select={}", select.join(",")));
             }
         }
+        push_partition(&mut url, &options);
 
-        let full_url = self.build_url(&url);
+        let mut full_url = self.build_url(&url);
+        push_query_options(&mut full_url, &options.query_options)?;
+        let record = partitioned_record(record, &options);
         let body = serde_json::to_string(&record).map_err(Error::Serialization)?;
 
+        apply_headers(&mut headers, &options.headers)?;
         let response = self
             .request(Method::PATCH, &full_url, headers, Some(body))
             .await?;
 
-        let status = response.status();
-        if status == StatusCode::CREATED {
-            // New record created
-            if options.return_record {
-                let record: Record = response.json().await.map_err(ApiError::from)?;
-                Ok(UpsertResult::Created(CreateResult::Record(record)))
-            } else {
-                let entity_id = response
-                    .headers()
-                    .get("OData-EntityId")
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(extract_guid_from_entity_id)
-                    .unwrap_or(id); // Fall back to provided id
-                Ok(UpsertResult::Created(CreateResult::Id(entity_id)))
+        // With an alternate key, the record's id only comes back in the header
+        let entity_id = response
+            .headers()
+            .get("OData-EntityId")
+            .and_then(|v| v.to_str().ok())
+            .and_then(extract_guid_from_entity_id)
+            .or(id.id()); // Fall back to provided id
+
+        let created = match response.status() {
+            StatusCode::CREATED => true,
+            StatusCode::OK => false,
+            // No representation: only a conditional upsert says which it was
+            _ => options.if_none_match,
+        };
+
+        let returned = if options.return_record && response.status() != StatusCode::NO_CONTENT {
+            let mut record: Record = response.json().await.map_err(ApiError::from)?;
+            record.mark_loaded();
+            if let (None, Some(id)) = (record.id(), entity_id) {
+                record.set_id(id);
             }
+            Some(record)
         } else {
-            // Existing record updated
-            if options.return_record {
-                let record: Record = response.json().await.map_err(ApiError::from)?;
-                Ok(UpsertResult::Updated {
-                    id,
-                    record: Some(record),
-                })
-            } else {
-                Ok(UpsertResult::Updated { id, record: None })
+            None
+        };
+
+        if created {
+            match returned {
+                Some(record) => Ok(UpsertResult::Created(CreateResult::Record(record))),
+                None => {
+                    let entity_id = entity_id.ok_or_else(missing_entity_id)?;
+                    Ok(UpsertResult::Created(CreateResult::Id(entity_id)))
+                }
             }
+        } else {
+            let entity_id = entity_id.ok_or_else(missing_entity_id)?;
+            Ok(UpsertResult::Updated {
+                id: entity_id,
+                record: returned,
+            })
+        }
+    }
+
+    /// Returns the `$select` for an unconditional upsert's representation:
+    /// the primary key, or nothing (every column) if metadata can't say.
+    async fn upsert_probe_select(&self, entity: &Entity) -> Vec<String> {
+        let logical_name = match self.resolve_entity_logical_name(entity).await {
+            Ok(name) => name,
+            Err(_) => return Vec::new(),
+        };
+        match fetch_entity_core(self, &logical_name, false).await {
+            Ok(core) => vec![core.primary_id_attribute],
+            Err(_) => Vec::new(),
         }
     }
 
@@ -386,17 +613,19 @@ impl DataverseClient {
         let target_set = self.resolve_entity(&target_entity).await?;
 
         // The relationship name is used as the collection-valued navigation property
-        // The actual URL format is: POST /{entity}({id})/{relationship}/$ref
-        let url = self.build_url(&format!("/{}({})/{}/$ref", entity_set, id, relationship));
+        let mut url = self.build_url(&format!(
+            "/{}",
+            ref_path(&entity_set, id, relationship, None)
+        ));
+        push_query_options(&mut url, &options.query_options)?;
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
 
-        let body = json!({
-            "@odata.id": self.build_url(&format!("/{}({})", target_set, target_id))
-        });
+        let body = ref_body(&self.build_url(""), &target_set, target_id);
 
-        self.request(Method::POST, &url, headers, Some(body.to_string()))
+        apply_headers(&mut headers, &options.headers)?;
+        self.request(Method::POST, &url, headers, Some(body))
             .await?;
         Ok(())
     }
@@ -410,14 +639,16 @@ impl DataverseClient {
         options: OperationOptions,
     ) -> Result<(), Error> {
         let entity_set = self.resolve_entity(&entity).await?;
-        let url = self.build_url(&format!(
-            "/{}({})/{}({})/$ref",
-            entity_set, id, relationship, target_id
+        let mut url = self.build_url(&format!(
+            "/{}",
+            ref_path(&entity_set, id, relationship, Some(target_id))
         ));
+        push_query_options(&mut url, &options.query_options)?;
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
 
+        apply_headers(&mut headers, &options.headers)?;
         self.request(Method::DELETE, &url, headers, None).await?;
         Ok(())
     }
@@ -433,17 +664,19 @@ impl DataverseClient {
     ) -> Result<(), Error> {
         let entity_set = self.resolve_entity(&entity).await?;
         let target_set = self.resolve_entity(&target_entity).await?;
-        let url = self.build_url(&format!("/{}({})/{}/$ref", entity_set, id, nav_property));
+        let mut url = self.build_url(&format!(
+            "/{}",
+            ref_path(&entity_set, id, nav_property, None)
+        ));
+        push_query_options(&mut url, &options.query_options)?;
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
 
-        let body = json!({
-            "@odata.id": self.build_url(&format!("/{}({})", target_set, target_id))
-        });
+        let body = ref_body(&self.build_url(""), &target_set, target_id);
 
-        self.request(Method::PUT, &url, headers, Some(body.to_string()))
-            .await?;
+        apply_headers(&mut headers, &options.headers)?;
+        self.request(Method::PUT, &url, headers, Some(body)).await?;
         Ok(())
     }
 
@@ -455,11 +688,16 @@ impl DataverseClient {
         options: OperationOptions,
     ) -> Result<(), Error> {
         let entity_set = self.resolve_entity(&entity).await?;
-        let url = self.build_url(&format!("/{}({})/{}/$ref", entity_set, id, nav_property));
+        let mut url = self.build_url(&format!(
+            "/{}",
+            ref_path(&entity_set, id, nav_property, None)
+        ));
+        push_query_options(&mut url, &options.query_options)?;
 
         let mut headers = self.default_headers();
         self.apply_options_headers(&mut headers, &options);
 
+        apply_headers(&mut headers, &options.headers)?;
         self.request(Method::DELETE, &url, headers, None).await?;
         Ok(())
     }
@@ -472,7 +710,7 @@ impl DataverseClient {
     ///
     /// - For `Entity::Set`, returns the name directly.
     /// - For `Entity::Logical`, fetches metadata to resolve the entity set name.
-    async fn resolve_entity(&self, entity: &Entity) -> Result<String, Error> {
+    pub(crate) async fn resolve_entity(&self, entity: &Entity) -> Result<String, Error> {
         match entity {
             Entity::Set(name) => Ok(name.clone()),
             Entity::Logical(logical_name) => self.resolve_entity_set_name(logical_name).await,
@@ -548,7 +786,7 @@ impl DataverseClient {
         )
     }
 
-    fn default_headers(&self) -> HeaderMap {
+    pub(crate) fn default_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert("OData-MaxVersion", HeaderValue::from_static("4.0"));
         headers.insert("OData-Version", HeaderValue::from_static("4.0"));
@@ -557,12 +795,24 @@ impl DataverseClient {
         headers
     }
 
-    fn apply_options_headers(&self, headers: &mut HeaderMap, options: &OperationOptions) {
+    pub(crate) fn apply_options_headers(
+        &self,
+        headers: &mut HeaderMap,
+        options: &OperationOptions,
+    ) {
         for (name, value) in options.bypass_headers() {
             if let Ok(header_value) = HeaderValue::from_str(value) {
                 headers.insert(name, header_value);
             }
         }
+        if let Some(impersonation) = &options.impersonate {
+            apply_impersonation(headers, impersonation);
+        }
+        if let Some(token) = &options.session_token
+            && let Ok(value) = HeaderValue::from_str(token)
+        {
+            headers.insert(SESSION_TOKEN_REQUEST_HEADER, value);
+        }
     }
 
     /// Makes an HTTP request with rate limiting and retry logic.
@@ -575,9 +825,79 @@ impl DataverseClient {
         headers: impl Into<Option<HeaderMap>>,
         body: Option<String>,
     ) -> Result<reqwest::Response, Error> {
-        let headers = headers.into().unwrap_or_default();
+        self.request_bytes(method, url, headers, body.map(Bytes::from))
+            .await
+    }
+
+    /// Makes an HTTP request with a binary body, with rate limiting and retry
+    /// logic.
+    pub(crate) async fn request_bytes(
+        &self,
+        method: Method,
+        url: &str,
+        headers: impl Into<Option<HeaderMap>>,
+        body: Option<Bytes>,
+    ) -> Result<reqwest::Response, Error> {
+        let span = span!(
+            "dataverse.request",
+            method = %method,
+            url,
+            retries = tracing::field::Empty,
+            status = tracing::field::Empty,
+        );
+        let observed = method.clone();
+        let result = self
+            .observe(
+                &observed,
+                url,
+                self.request_with_retry(method, url, headers.into(), body)
+                    .in_span(span.clone()),
+            )
+            .await;
+        // The final status, after any retries
+        let status = match &result {
+            Ok(response) => Some(response.status().as_u16()),
+            Err(e) => e.status_code(),
+        };
+        if let Some(status) = status {
+            span.record("status", status);
+        }
+        result
+    }
+
+    async fn request_with_retry(
+        &self,
+        method: Method,
+        url: &str,
+        headers: Option<HeaderMap>,
+        body: Option<Bytes>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut headers = headers.unwrap_or_default();
+
+        // Client-level impersonation, unless the request impersonates someone itself
+        if let Some(impersonation) = &self.inner.impersonation
+            && !Impersonation::HEADERS
+                .iter()
+                .any(|name| headers.contains_key(*name))
+        {
+            apply_impersonation(&mut headers, impersonation);
+        }
+
+        // Correlation id, the same across retries, unless the caller set one
+        if !headers.contains_key(CORRELATION_ID_HEADER)
+            && let Ok(value) = HeaderValue::from_str(&Uuid::new_v4().to_string())
+        {
+            headers.insert(CORRELATION_ID_HEADER, value);
+        }
+        let correlation_id = headers
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let started = Instant::now();
+
         // Acquire concurrency permit (held for entire request lifecycle including retries)
         let _permit = self.inner.concurrency_limiter.acquire().await;
+        let mut waited = started.elapsed();
 
         let retry_config = &self.inner.retry_config;
         let mut attempts = 0;
@@ -585,61 +905,76 @@ impl DataverseClient {
 
         loop {
             // Acquire rate limit slot
+            let wait_started = Instant::now();
             self.inner.rate_limiter.acquire().await;
+            waited += wait_started.elapsed();
 
             // Send request
             let result = self
-                .send_request_inner(method.clone(), url, headers.clone(), body.clone())
+                .send_request_inner(method.clone(), url, headers.clone(), body.clone(), attempts)
                 .await;
 
             match result {
-                Ok(response) => {
+                Ok(mut response) => {
                     let status = response.status();
+                    self.inner.rate_limiter.observe(response.headers()).await;
 
                     // Handle 429 Too Many Requests
                     if status.as_u16() == 429 {
+                        let retry_after = parse_retry_after(&response);
+                        for t in &self.inner.telemetry {
+                            t.on_rate_limited(&method, url, retry_after);
+                        }
                         if !retry_config.retry_on_429 || attempts >= retry_config.max_retries {
-                            let retry_after = parse_retry_after(&response);
                             return Err(Error::RateLimit { retry_after });
                         }
 
-                        let wait = parse_retry_after(&response).unwrap_or(delay);
+                        let wait = retry_after.unwrap_or(delay);
+                        self.report_retry(&method, url, attempts + 1, wait);
                         tokio::time::sleep(wait).await;
+                        waited += wait;
                         attempts += 1;
+                        Span::current().record("retries", attempts);
                         continue;
                     }
 
                     // Handle 5xx server errors
                     if status.is_server_error() {
                         if !retry_config.retry_on_5xx || attempts >= retry_config.max_retries {
-                            let status_code = status.as_u16();
-                            let body = response.text().await.unwrap_or_default();
-                            return Err(Error::Api(ApiError::Http {
-                                status: status_code,
-                                message: body,
-                                code: None,
-                                inner: None,
-                            }));
+                            return Err(self
+                                .http_error(&method, url, &headers, body.as_ref(), response)
+                                .await);
                         }
 
+                        self.report_retry(&method, url, attempts + 1, delay);
                         tokio::time::sleep(delay).await;
                         delay = (delay * 2).min(retry_config.max_delay);
                         attempts += 1;
+                        Span::current().record("retries", attempts);
                         continue;
                     }
 
-                    // Success or client error (4xx except 429)
-                    if status.is_success() {
+                    // Success or client error (4xx except 429); 304 only
+                    // answers a conditional retrieve
+                    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+                        self.record_session_token(&response);
+                        let metadata = RequestMetadata {
+                            service_request_id: response
+                                .headers()
+                                .get(REQUEST_ID_HEADER)
+                                .and_then(|v| v.to_str().ok())
+                                .map(String::from),
+                            correlation_id,
+                            retries: attempts,
+                            rate_limit_wait: waited,
+                            duration: started.elapsed(),
+                        };
+                        response.extensions_mut().insert(metadata);
                         return Ok(response);
                     } else {
-                        let status_code = status.as_u16();
-                        let body = response.text().await.unwrap_or_default();
-                        return Err(Error::Api(ApiError::Http {
-                            status: status_code,
-                            message: body,
-                            code: None,
-                            inner: None,
-                        }));
+                        return Err(self
+                            .http_error(&method, url, &headers, body.as_ref(), response)
+                            .await);
                     }
                 }
                 Err(e) => {
@@ -650,9 +985,11 @@ impl DataverseClient {
                         && retry_config.retry_on_network
                         && attempts < retry_config.max_retries
                     {
+                        self.report_retry(&method, url, attempts + 1, delay);
                         tokio::time::sleep(delay).await;
                         delay = (delay * 2).min(retry_config.max_delay);
                         attempts += 1;
+                        Span::current().record("retries", attempts);
                         continue;
                     }
 
@@ -662,44 +999,158 @@ impl DataverseClient {
         }
     }
 
+    /// Tells telemetry a request is about to be retried.
+    fn report_retry(&self, method: &Method, url: &str, attempt: u32, delay: Duration) {
+        for t in &self.inner.telemetry {
+            t.on_retry(method, url, attempt, delay);
+        }
+    }
+
+    /// Turns an error response into an [`ApiError::Http`], keeping the raw
+    /// request and response in debug mode and the plugin's trace when a
+    /// plugin failed.
+    ///
+    /// Boxed, as fetching plugin traces makes requests that come through here
+    /// too.
+    fn http_error<'a>(
+        &'a self,
+        method: &'a Method,
+        url: &'a str,
+        headers: &'a HeaderMap,
+        body: Option<&'a Bytes>,
+        response: reqwest::Response,
+    ) -> Pin<Box<dyn Future<Output = Error> + Send + 'a>> {
+        Box::pin(async move {
+            let status = response.status().as_u16();
+            let response_headers = response.headers().clone();
+            let bytes = response.bytes().await.unwrap_or_default();
+
+            let raw = self.inner.debug.as_ref().map(|debug| {
+                let request_body = body.map(|body| debug.body(body));
+                let (response_body, truncated) = debug.body(&bytes);
+                Box::new(RawResponse {
+                    method: method.to_string(),
+                    url: url.to_string(),
+                    request_headers: debug.headers(headers),
+                    truncated: truncated || request_body.as_ref().is_some_and(|(_, t)| *t),
+                    request_body: request_body.map(|(body, _)| body),
+                    status,
+                    headers: debug.headers(&response_headers),
+                    body: response_body,
+                })
+            });
+
+            let message = String::from_utf8_lossy(&bytes).into_owned();
+            let mut detail = DataverseErrorDetail::parse(&message);
+            if let (Some(detail), Some(max)) = (&mut detail, self.inner.plugin_traces)
+                && detail.is_plugin_error()
+                && let Some(request_id) = response_headers
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| Uuid::parse_str(v).ok())
+            {
+                match self.plugin_traces(request_id, max).await {
+                    Ok(traces) => detail.plugin_traces = traces,
+                    Err(e) => log::warn!("Failed to fetch plugin traces: {}", e),
+                }
+            }
+
+            Error::Api(ApiError::Http {
+                status,
+                message,
+                code: detail.as_ref().map(|d| d.code.clone()),
+                inner: detail.map(Box::new),
+                raw,
+            })
+        })
+    }
+
+    /// Checks an alternate key against the entity's keys, when they are
+    /// cached.
+    async fn check_record_key(&self, entity: &Entity, key: &RecordKey) -> Result<(), Error> {
+        if !matches!(key, RecordKey::Alternate(_)) || !self.has_cache() {
+            return Ok(());
+        }
+        let logical_name = self.resolve_entity_logical_name(entity).await?;
+        match cached_entity_keys(self, &logical_name).await {
+            Some(keys) => check_alternate_key(&logical_name, &keys, key),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a write payload against the entity's attribute metadata,
+    /// failing with [`Error::Validation`] before anything is sent.
+    async fn validate_record(
+        &self,
+        entity: &Entity,
+        record: &Record,
+        kind: WriteKind,
+    ) -> Result<(), Error> {
+        let metadata = self.metadata().entity(entity.clone()).await?;
+        let errors = metadata.validate_record(record, kind);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Validation {
+            message: format!("Invalid {} record", metadata.logical_name),
+            errors,
+        })
+    }
+
+    /// Remembers the session token of a response from an elastic table.
+    fn record_session_token(&self, response: &reqwest::Response) {
+        if let Some(token) = response
+            .headers()
+            .get(SESSION_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self
+                .inner
+                .session_token
+                .lock()
+                .unwrap_or_else(|p| p.into_inner()) = Some(token.to_string());
+        }
+    }
+
     /// Inner request method without retry logic.
     async fn send_request_inner(
         &self,
         method: Method,
         url: &str,
         headers: HeaderMap,
-        body: Option<String>,
+        body: Option<Bytes>,
+        attempt: u32,
     ) -> Result<reqwest::Response, Error> {
-        let token = self
-            .inner
-            .token_provider
-            .get_token(&self.inner.base_url)
-            .await?;
+        let token = self.access_token().await?;
 
-        let mut request = self
-            .inner
-            .http_client
-            .request(method, url)
-            .headers(headers)
-            .bearer_auth(&token.access_token);
+        let mut request = RequestInfo::new(method, url, headers)?;
+        request.attempt = attempt;
 
-        if let Some(timeout) = self.inner.timeout {
-            request = request.timeout(timeout);
-        }
-
-        if let Some(body) = body {
-            request = request.body(body);
-        }
-
-        request
-            .send()
+        self.send(request, body, &token.access_token)
             .await
             .map_err(|e| Error::Api(ApiError::from(e)))
     }
 }
 
+/// Sets the impersonation header, replacing any other impersonation header.
+pub(crate) fn apply_impersonation(headers: &mut HeaderMap, impersonation: &Impersonation) {
+    for name in Impersonation::HEADERS {
+        headers.remove(name);
+    }
+    let (name, value) = impersonation.header();
+    if let Ok(header_value) = HeaderValue::from_str(&value) {
+        headers.insert(name, header_value);
+    }
+}
+
+/// Creates the span for a CRUD operation.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn crud_span(kind: OperationKind, entity: &Entity) -> Span {
+    span!("dataverse.crud", operation = ?kind, entity = %entity)
+}
+
 /// Parses the Retry-After header value (seconds).
-fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+pub(super) fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
     response
         .headers()
         .get("Retry-After")?
@@ -752,6 +1203,14 @@ impl OperationResult {
     }
 }
 
+/// Error for a create/upsert response without a usable `OData-EntityId` header.
+fn missing_entity_id() -> Error {
+    Error::Api(ApiError::Parse {
+        message: "Missing or invalid OData-EntityId header".to_string(),
+        body: None,
+    })
+}
+
 /// Extracts a GUID from an OData-EntityId header value.
 ///
 /// The header looks like: `https://org.crm.dynamics.com/api/data/v9.2/accounts(00000000-0000-0000-0000-000000000001)`
@@ -804,19 +1263,24 @@ impl DataverseClient {
     ///     .select(&["name", "revenue"])
     ///     .await?;
     /// ```
-    pub fn retrieve(&self, entity: Entity, id: Uuid) -> ClientRetrieveBuilder<'_> {
+    pub fn retrieve(&self, entity: Entity, id: impl Into<RecordKey>) -> ClientRetrieveBuilder<'_> {
         ClientRetrieveBuilder {
             client: self,
             entity,
-            id,
+            id: id.into(),
             select: Vec::new(),
             expand: Vec::new(),
             options: OperationOptions::default(),
+            conditional: false,
         }
     }
 
     /// Updates an existing record.
     ///
+    /// Only the record's changed fields are sent (see
+    /// [`Record::changes`]); a record with none fails with
+    /// [`Error::InvalidOperation`] unless the full payload is requested.
+    ///
     /// Returns a builder that can be configured and executed.
     ///
     /// # Example
@@ -824,11 +1288,16 @@ impl DataverseClient {
     /// ```ignore
     /// client.update(Entity::set("accounts"), id, record).await?;
     /// ```
-    pub fn update(&self, entity: Entity, id: Uuid, record: Record) -> ClientUpdateBuilder<'_> {
+    pub fn update(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        record: Record,
+    ) -> ClientUpdateBuilder<'_> {
         ClientUpdateBuilder {
             client: self,
             entity,
-            id,
+            id: id.into(),
             record,
             options: OperationOptions::default(),
         }
@@ -843,18 +1312,20 @@ impl DataverseClient {
     /// ```ignore
     /// client.delete(Entity::set("accounts"), id).await?;
     /// ```
-    pub fn delete(&self, entity: Entity, id: Uuid) -> ClientDeleteBuilder<'_> {
+    pub fn delete(&self, entity: Entity, id: impl Into<RecordKey>) -> ClientDeleteBuilder<'_> {
         ClientDeleteBuilder {
             client: self,
             entity,
-            id,
+            id: id.into(),
             options: OperationOptions::default(),
         }
     }
 
     /// Upserts (creates or updates) a record.
     ///
-    /// Returns a builder that can be configured and executed.
+    /// Returns a builder that can be configured and executed. Use
+    /// `if_none_match()` to only create, or `if_match_any()` / `if_match(etag)`
+    /// to only update; the other case then fails with 412 or 404.
     ///
     /// # Example
     ///
@@ -863,12 +1334,20 @@ impl DataverseClient {
     /// if result.is_created() {
     ///     println!("Created new record");
     /// }
+    ///
+    /// // Insert-only
+    /// client.upsert(Entity::set("accounts"), id, record).if_none_match().await?;
     /// ```
-    pub fn upsert(&self, entity: Entity, id: Uuid, record: Record) -> ClientUpsertBuilder<'_> {
+    pub fn upsert(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        record: Record,
+    ) -> ClientUpsertBuilder<'_> {
         ClientUpsertBuilder {
             client: self,
             entity,
-            id,
+            id: id.into(),
             record,
             options: OperationOptions::default(),
         }
@@ -987,6 +1466,54 @@ impl DataverseClient {
         }
     }
 
+    /// Assigns a record to a user or team by updating its `ownerid`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// client.assign(Entity::logical("account"), account_id, Owner::Team(team_id)).await?;
+    /// ```
+    pub fn assign(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        owner: Owner,
+    ) -> ClientUpdateBuilder<'_> {
+        let record = assign_record(&entity, owner);
+        self.update(entity, id, record)
+    }
+
+    /// Sets a record's `statecode` and `statuscode`.
+    ///
+    /// When the entity's metadata is cached, the status is checked to exist
+    /// and belong to the state before the update is sent, failing with
+    /// [`Error::Validation`] otherwise. Without cached metadata the values go
+    /// to Dataverse unchecked.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Deactivate an account (Inactive / Inactive)
+    /// client.set_state(Entity::logical("account"), account_id, 1, 2).await?;
+    /// ```
+    pub async fn set_state(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        state: i32,
+        status: i32,
+    ) -> Result<(), Error> {
+        if self.has_cache() {
+            let logical_name = self.resolve_entity_logical_name(&entity).await?;
+            if let Some(metadata) = cached_entity_metadata(self, &logical_name).await {
+                check_state(&metadata, state, status)?;
+            }
+        }
+        let record = state_record(&entity, state, status);
+        self.update(entity, id, record).await?;
+        Ok(())
+    }
+
     /// Creates an OData query for the specified entity.
     ///
     /// Returns a builder that can be configured and executed.
@@ -1047,6 +1574,28 @@ impl DataverseClient {
         FetchBuilder::new(self, entity)
     }
 
+    /// Parses an existing FetchXML query into a [`FetchBuilder`].
+    ///
+    /// The parsed builder can be inspected, modified (extra filters, paging)
+    /// and executed like any other fetch. Conditions without a typed
+    /// [`Filter`](crate::api::query::Filter) form are kept verbatim as
+    /// `Filter::Raw`.
+    ///
+    /// Returns [`Error::InvalidOperation`] if the XML is malformed or uses
+    /// elements the builder can't represent.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut pages = client.fetch_xml(&view_fetchxml)?
+    ///     .and_filter(Filter::eq("statecode", 0))
+    ///     .page_size(500)
+    ///     .into_async_iter();
+    /// ```
+    pub fn fetch_xml(&self, xml: &str) -> Result<FetchBuilder<'_>, Error> {
+        parse_fetchxml(self, xml)
+    }
+
     /// Creates an aggregation query for the specified entity.
     ///
     /// Returns a builder that can be configured and executed.
@@ -1078,17 +1627,19 @@ impl DataverseClient {
         AggregateBuilder::new(self, entity)
     }
 
-    /// Queries records related through a navigation property.
+    /// Queries records related through a 1:N or N:N relationship.
     ///
-    /// Returns a builder that can be configured and executed.
+    /// `relationship` is the relationship's schema name or the navigation
+    /// property name on `entity`. Returns a builder that can be configured
+    /// and executed.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// // Query contacts related to an account
+    /// // Query the leads of an account's N:N relationship
     /// let mut pages = client.related(
     ///     Entity::set("accounts"), account_id,
-    ///     "contact_customer_accounts",
+    ///     "accountleads_association",
     /// )
     /// .select(&["fullname", "emailaddress1"])
     /// .filter(Filter::eq("statecode", 0))
@@ -1106,9 +1657,9 @@ impl DataverseClient {
         &self,
         entity: Entity,
         id: Uuid,
-        nav_property: impl Into<String>,
+        relationship: impl Into<String>,
     ) -> RelatedQueryBuilder {
-        RelatedQueryBuilder::new(self.clone(), entity, id, nav_property)
+        RelatedQueryBuilder::new(self.clone(), entity, id, relationship)
     }
 
     /// Creates a batch request builder.
@@ -1161,6 +1712,11 @@ impl DataverseClient {
     /// For building batches fluently, use the [`batch()`](Self::batch) method instead.
     /// This method is useful when you have a pre-constructed `Batch` object.
     pub async fn execute_batch(&self, batch: Batch) -> Result<BatchResults, Error> {
+        let span = span!("dataverse.batch", operations = batch.operation_count());
+        self.execute_batch_inner(batch).in_span(span).await
+    }
+
+    async fn execute_batch_inner(&self, batch: Batch) -> Result<BatchResults, Error> {
         batch.validate()?;
 
         let batch_boundary = generate_boundary("batch");
@@ -1212,7 +1768,7 @@ impl DataverseClient {
         let response_body = response.text().await.map_err(ApiError::from)?;
 
         let kinds = batch.operation_kinds();
-        BatchResults::parse(&response_body, &response_boundary, &kinds)
+        Ok(BatchResults::parse(&response_body, &response_boundary, &kinds)?.with_batch(batch))
     }
 }
 
@@ -1253,6 +1809,28 @@ impl<'a> ClientCreateBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1264,6 +1842,16 @@ impl<'a> ClientCreateBuilder<'a> {
         self.options.suppress_duplicate_detection = true;
         self
     }
+
+    /// Checks the record against the entity's attribute metadata (fetched
+    /// once and cached) before sending it, failing with
+    /// [`Error::Validation`] listing every invalid field.
+    ///
+    /// See [`EntityMetadata::validate_record`](crate::model::metadata::EntityMetadata::validate_record).
+    pub fn validate(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
 }
 
 impl<'a> std::future::IntoFuture for ClientCreateBuilder<'a> {
@@ -1272,11 +1860,15 @@ impl<'a> std::future::IntoFuture for ClientCreateBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_create(self.entity, self.record, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::Create, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_create(self.entity, self.record, self.options)
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1294,10 +1886,11 @@ impl<'a> From<ClientCreateBuilder<'a>> for Operation {
 pub struct ClientRetrieveBuilder<'a> {
     client: &'a DataverseClient,
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     select: Vec<String>,
     expand: Vec<ExpandBuilder>,
     options: OperationOptions,
+    conditional: bool,
 }
 
 impl<'a> ClientRetrieveBuilder<'a> {
@@ -1318,6 +1911,20 @@ impl<'a> ClientRetrieveBuilder<'a> {
         self
     }
 
+    /// Caches the record with its ETag and revalidates it on later
+    /// conditional retrieves.
+    ///
+    /// When a cached copy exists, it is sent as `If-None-Match`; if the
+    /// record hasn't changed, Dataverse answers `304 Not Modified` and the
+    /// cached copy is returned as a [`CacheStatus::Hit`]. Otherwise the
+    /// fresh record replaces it, as a [`CacheStatus::Miss`]. Copies are kept
+    /// for [`CacheConfig::record_ttl`](crate::cache::CacheConfig::record_ttl)
+    /// after they were last validated. Does nothing without a cache.
+    pub fn conditional(mut self) -> Self {
+        self.conditional = true;
+        self
+    }
+
     /// Skip custom plugin execution.
     pub fn bypass_plugins(mut self) -> Self {
         self.options.bypass_plugins = true;
@@ -1329,6 +1936,46 @@ impl<'a> ClientRetrieveBuilder<'a> {
         self.options.bypass_flows = true;
         self
     }
+
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
+    /// Reads with session consistency from an elastic table, seeing every
+    /// write made before the session token was returned.
+    ///
+    /// See [`DataverseClient::session_token`](crate::DataverseClient::session_token).
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.options.session_token = Some(token.into());
+        self
+    }
 }
 
 impl<'a> std::future::IntoFuture for ClientRetrieveBuilder<'a> {
@@ -1337,11 +1984,22 @@ impl<'a> std::future::IntoFuture for ClientRetrieveBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_retrieve(self.entity, self.id, self.select, self.expand, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::Retrieve, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_retrieve(
+                        self.entity,
+                        self.id,
+                        self.select,
+                        self.expand,
+                        self.options,
+                        self.conditional,
+                    )
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1361,7 +2019,7 @@ impl<'a> From<ClientRetrieveBuilder<'a>> for Operation {
 pub struct ClientUpdateBuilder<'a> {
     client: &'a DataverseClient,
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     record: Record,
     options: OperationOptions,
 }
@@ -1385,6 +2043,12 @@ impl<'a> ClientUpdateBuilder<'a> {
         self
     }
 
+    /// Send every field of the record, not just the changed ones.
+    pub fn full_payload(mut self) -> Self {
+        self.options.full_payload = true;
+        self
+    }
+
     /// Skip custom plugin execution.
     pub fn bypass_plugins(mut self) -> Self {
         self.options.bypass_plugins = true;
@@ -1397,6 +2061,37 @@ impl<'a> ClientUpdateBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1408,6 +2103,16 @@ impl<'a> ClientUpdateBuilder<'a> {
         self.options.suppress_duplicate_detection = true;
         self
     }
+
+    /// Checks the record against the entity's attribute metadata (fetched
+    /// once and cached) before sending it, failing with
+    /// [`Error::Validation`] listing every invalid field.
+    ///
+    /// See [`EntityMetadata::validate_record`](crate::model::metadata::EntityMetadata::validate_record).
+    pub fn validate(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
 }
 
 impl<'a> std::future::IntoFuture for ClientUpdateBuilder<'a> {
@@ -1416,11 +2121,15 @@ impl<'a> std::future::IntoFuture for ClientUpdateBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_update(self.entity, self.id, self.record, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::Update, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_update(self.entity, self.id, self.record, self.options)
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1439,7 +2148,7 @@ impl<'a> From<ClientUpdateBuilder<'a>> for Operation {
 pub struct ClientDeleteBuilder<'a> {
     client: &'a DataverseClient,
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     options: OperationOptions,
 }
 
@@ -1462,6 +2171,37 @@ impl<'a> ClientDeleteBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// Records in elastic tables are addressed by id and partition; without
+    /// it, Dataverse looks in the default partition.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1475,11 +2215,15 @@ impl<'a> std::future::IntoFuture for ClientDeleteBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_delete(self.entity, self.id, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::Delete, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_delete(self.entity, self.id, self.options)
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1497,7 +2241,7 @@ impl<'a> From<ClientDeleteBuilder<'a>> for Operation {
 pub struct ClientUpsertBuilder<'a> {
     client: &'a DataverseClient,
     entity: Entity,
-    id: Uuid,
+    id: RecordKey,
     record: Record,
     options: OperationOptions,
 }
@@ -1527,6 +2271,12 @@ impl<'a> ClientUpsertBuilder<'a> {
         self
     }
 
+    /// Only update if the record exists and its ETag matches.
+    pub fn if_match(mut self, etag: impl Into<String>) -> Self {
+        self.options.if_match = Some(etag.into());
+        self
+    }
+
     /// Skip custom plugin execution.
     pub fn bypass_plugins(mut self) -> Self {
         self.options.bypass_plugins = true;
@@ -1539,6 +2289,37 @@ impl<'a> ClientUpsertBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the partition of an elastic table record.
+    ///
+    /// The partition is also written to the record's `partitionid`, so a
+    /// record that doesn't exist yet is created in it.
+    pub fn partition_id(mut self, partition: impl Into<String>) -> Self {
+        self.options.partition_id = Some(partition.into());
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1550,6 +2331,16 @@ impl<'a> ClientUpsertBuilder<'a> {
         self.options.suppress_duplicate_detection = true;
         self
     }
+
+    /// Checks the record against the entity's attribute metadata (fetched
+    /// once and cached) before sending it, failing with
+    /// [`Error::Validation`] listing every invalid field.
+    ///
+    /// See [`EntityMetadata::validate_record`](crate::model::metadata::EntityMetadata::validate_record).
+    pub fn validate(mut self) -> Self {
+        self.options.validate = true;
+        self
+    }
 }
 
 impl<'a> std::future::IntoFuture for ClientUpsertBuilder<'a> {
@@ -1558,11 +2349,15 @@ impl<'a> std::future::IntoFuture for ClientUpsertBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_upsert(self.entity, self.id, self.record, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::Upsert, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_upsert(self.entity, self.id, self.record, self.options)
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1601,6 +2396,28 @@ impl<'a> ClientAssociateBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1620,18 +2437,22 @@ impl<'a> std::future::IntoFuture for ClientAssociateBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_associate(
-                    self.entity,
-                    self.id,
-                    &self.relationship,
-                    self.target_entity,
-                    self.target_id,
-                    self.options,
-                )
-                .await
-        })
+        let span = crud_span(OperationKind::Associate, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_associate(
+                        self.entity,
+                        self.id,
+                        &self.relationship,
+                        self.target_entity,
+                        self.target_id,
+                        self.options,
+                    )
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1671,6 +2492,28 @@ impl<'a> ClientDisassociateBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1690,17 +2533,21 @@ impl<'a> std::future::IntoFuture for ClientDisassociateBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_disassociate(
-                    self.entity,
-                    self.id,
-                    &self.relationship,
-                    self.target_id,
-                    self.options,
-                )
-                .await
-        })
+        let span = crud_span(OperationKind::Disassociate, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_disassociate(
+                        self.entity,
+                        self.id,
+                        &self.relationship,
+                        self.target_id,
+                        self.options,
+                    )
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1740,6 +2587,28 @@ impl<'a> ClientSetLookupBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1759,18 +2628,22 @@ impl<'a> std::future::IntoFuture for ClientSetLookupBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_set_lookup(
-                    self.entity,
-                    self.id,
-                    &self.nav_property,
-                    self.target_entity,
-                    self.target_id,
-                    self.options,
-                )
-                .await
-        })
+        let span = crud_span(OperationKind::SetLookup, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_set_lookup(
+                        self.entity,
+                        self.id,
+                        &self.nav_property,
+                        self.target_entity,
+                        self.target_id,
+                        self.options,
+                    )
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1809,6 +2682,28 @@ impl<'a> ClientClearLookupBuilder<'a> {
         self
     }
 
+    /// Run this operation as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.options.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover to the request URL.
+    ///
+    /// The request fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a request header.
+    ///
+    /// The request fails if the builder already sets the header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Skip synchronous business logic.
     pub fn bypass_sync_logic(mut self) -> Self {
         self.options.bypass_sync_logic = true;
@@ -1828,11 +2723,15 @@ impl<'a> std::future::IntoFuture for ClientClearLookupBuilder<'a> {
         std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
 
     fn into_future(self) -> Self::IntoFuture {
-        Box::pin(async move {
-            self.client
-                .execute_clear_lookup(self.entity, self.id, &self.nav_property, self.options)
-                .await
-        })
+        let span = crud_span(OperationKind::ClearLookup, &self.entity);
+        Box::pin(
+            async move {
+                self.client
+                    .execute_clear_lookup(self.entity, self.id, &self.nav_property, self.options)
+                    .await
+            }
+            .in_span(span),
+        )
     }
 }
 
@@ -1893,6 +2792,14 @@ impl<'a> ClientBatchBuilder<'a> {
         self
     }
 
+    /// Run all operations as another user.
+    ///
+    /// Operations with their own impersonation run as that user instead.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.batch = self.batch.impersonate(user);
+        self
+    }
+
     /// Adds a standalone operation to the batch.
     pub fn add(mut self, op: impl Into<Operation>) -> Self {
         self.batch = self.batch.add(op);
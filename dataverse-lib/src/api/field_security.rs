@@ -0,0 +1,489 @@
+//! Field-level security
+//!
+//! Columns with field security enabled (`IsSecured` in attribute metadata)
+//! are hidden from everyone except users a field security profile grants
+//! access to, either directly or through a team. Individual values can also
+//! be shared per record, which Dataverse stores as
+//! `principalobjectattributeaccess` rows.
+//!
+//! [`DataverseClient::secured_field_access`] puts the pieces together for
+//! the calling user, so an app can hide or disable columns up front instead
+//! of showing empty values or failing on save.
+//!
+//! # Example
+//!
+//! ```ignore
+//! for field in client.secured_field_access(Entity::logical("contact")).await? {
+//!     if !field.access.contains(FieldAccess::READ) {
+//!         // hide field.attribute.logical_name
+//!     }
+//! }
+//! ```
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use super::query::Filter;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::model::DataverseOptionSet;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::metadata::AttributeMetadata;
+use crate::model::types::EntityReference;
+
+bitflags::bitflags! {
+    /// What a principal may do with a secured column.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct FieldAccess: u8 {
+        /// See the column's value.
+        const READ = 1;
+        /// Set the column when creating a record.
+        const CREATE = 2;
+        /// Change the column's value.
+        const UPDATE = 4;
+    }
+}
+
+/// Whether a field permission grants an operation (`canread`, `cancreate`,
+/// `canupdate`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum FieldPermissionType {
+    /// The operation isn't allowed.
+    NotAllowed = 0,
+    /// The operation is allowed.
+    Allowed = 4,
+}
+
+/// A field security profile (`fieldsecurityprofile` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "fieldsecurityprofile", set = "fieldsecurityprofiles")]
+pub struct FieldSecurityProfile {
+    /// The profile's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The profile's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The profile's description.
+    #[dataverse(read_only)]
+    pub description: Option<String>,
+}
+
+/// Access a field security profile grants to one column
+/// (`fieldpermission` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "fieldpermission", set = "fieldpermissions")]
+pub struct FieldPermission {
+    /// The permission's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The profile granting the permission.
+    #[dataverse(rename = "fieldsecurityprofileid", read_only)]
+    pub profile_id: Uuid,
+    /// Logical name of the column's table.
+    #[dataverse(rename = "entityname", read_only)]
+    pub entity_name: String,
+    /// Logical name of the column.
+    #[dataverse(rename = "attributelogicalname", read_only)]
+    pub attribute_logical_name: String,
+    /// Whether the column can be read.
+    #[dataverse(rename = "canread", read_only)]
+    pub can_read: FieldPermissionType,
+    /// Whether the column can be set on create.
+    #[dataverse(rename = "cancreate", read_only)]
+    pub can_create: FieldPermissionType,
+    /// Whether the column can be updated.
+    #[dataverse(rename = "canupdate", read_only)]
+    pub can_update: FieldPermissionType,
+}
+
+impl FieldPermission {
+    /// Returns the access this permission grants.
+    pub fn access(&self) -> FieldAccess {
+        let mut access = FieldAccess::empty();
+        access.set(
+            FieldAccess::READ,
+            self.can_read == FieldPermissionType::Allowed,
+        );
+        access.set(
+            FieldAccess::CREATE,
+            self.can_create == FieldPermissionType::Allowed,
+        );
+        access.set(
+            FieldAccess::UPDATE,
+            self.can_update == FieldPermissionType::Allowed,
+        );
+        access
+    }
+}
+
+/// A secured column value shared with a user or team on one record
+/// (`principalobjectattributeaccess` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(
+    entity = "principalobjectattributeaccess",
+    set = "principalobjectattributeaccessset"
+)]
+pub struct FieldShare {
+    /// The share's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The user or team the value is shared with.
+    #[dataverse(rename = "principalid", read_only)]
+    pub principal: EntityReference,
+    /// The record whose value is shared.
+    #[dataverse(rename = "objectid", read_only)]
+    pub object_id: Uuid,
+    /// Metadata id of the shared column.
+    #[dataverse(rename = "attributeid", read_only)]
+    pub attribute_id: Uuid,
+    /// Whether the principal can read the value.
+    #[dataverse(rename = "readaccess", read_only)]
+    pub read_access: bool,
+    /// Whether the principal can update the value.
+    #[dataverse(rename = "updateaccess", read_only)]
+    pub update_access: bool,
+}
+
+impl FieldShare {
+    /// Returns the access this share grants.
+    pub fn access(&self) -> FieldAccess {
+        let mut access = FieldAccess::empty();
+        access.set(FieldAccess::READ, self.read_access);
+        access.set(FieldAccess::UPDATE, self.update_access);
+        access
+    }
+}
+
+/// A secured column with the calling user's access to it.
+#[derive(Debug, Clone)]
+pub struct SecuredField {
+    /// The column's metadata.
+    pub attribute: AttributeMetadata,
+    /// What the user's field security profiles allow on the column.
+    pub access: FieldAccess,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Lists the field security profiles in the environment.
+    pub async fn field_security_profiles(&self) -> Result<Vec<FieldSecurityProfile>, Error> {
+        let mut pages = self
+            .query(FieldSecurityProfile::entity())
+            .into_typed::<FieldSecurityProfile>()
+            .into_async_iter(self);
+        let mut profiles = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            profiles.extend(page?);
+        }
+        Ok(profiles)
+    }
+
+    /// Lists the column permissions of a field security profile.
+    pub async fn field_permissions(&self, profile: Uuid) -> Result<Vec<FieldPermission>, Error> {
+        self.query_field_permissions(Filter::eq("_fieldsecurityprofileid_value", profile))
+            .await
+    }
+
+    /// Lists the field security profiles of a user, including those granted
+    /// through the user's teams.
+    pub async fn user_field_security_profiles(
+        &self,
+        user: Uuid,
+    ) -> Result<Vec<FieldSecurityProfile>, Error> {
+        let user_entity = Entity::logical("systemuser");
+        let mut profiles = self
+            .related_records(user_entity.clone(), user, "systemuserprofiles_association")
+            .await?;
+        let teams = self
+            .related_records(user_entity, user, "teammembership_association")
+            .await?;
+        let team_ids = teams
+            .iter()
+            .filter_map(|team| team.get_guid("teamid").ok().flatten());
+        for team in team_ids {
+            let team_profiles = self
+                .related_records(Entity::logical("team"), team, "teamprofiles_association")
+                .await?;
+            profiles.extend(team_profiles);
+        }
+
+        let mut seen = HashSet::new();
+        let mut unique = Vec::new();
+        for record in &profiles {
+            let profile = FieldSecurityProfile::from_record(record)?;
+            if seen.insert(profile.id) {
+                unique.push(profile);
+            }
+        }
+        Ok(unique)
+    }
+
+    /// Lists the secured column values shared on a record, with whom and
+    /// with what access.
+    pub async fn field_shares(&self, id: Uuid) -> Result<Vec<FieldShare>, Error> {
+        let mut pages = self
+            .query(FieldShare::entity())
+            .filter(Filter::eq("_objectid_value", id))
+            .into_typed::<FieldShare>()
+            .into_async_iter(self);
+        let mut shares = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            shares.extend(page?);
+        }
+        Ok(shares)
+    }
+
+    /// Returns a table's secured columns with the calling user's access to
+    /// each, combining the permissions of all the user's field security
+    /// profiles.
+    ///
+    /// Values shared on individual records aren't included; see
+    /// [`field_shares`](Self::field_shares).
+    pub async fn secured_field_access(
+        &self,
+        entity: impl Into<Entity>,
+    ) -> Result<Vec<SecuredField>, Error> {
+        let metadata = self.metadata().entity(entity.into()).await?;
+        let mut fields: Vec<SecuredField> = metadata
+            .attributes
+            .iter()
+            .filter(|attr| attr.is_secured)
+            .map(|attr| SecuredField {
+                attribute: attr.clone(),
+                access: FieldAccess::empty(),
+            })
+            .collect();
+        if fields.is_empty() {
+            return Ok(fields);
+        }
+
+        let me = self.who_am_i().await?;
+        let profiles = self.user_field_security_profiles(me.user_id).await?;
+        if profiles.is_empty() {
+            return Ok(fields);
+        }
+        let filter = Filter::eq("entityname", metadata.logical_name.as_str()).and_also(Filter::or(
+            profiles
+                .iter()
+                .map(|p| Filter::eq("_fieldsecurityprofileid_value", p.id)),
+        ));
+        for permission in self.query_field_permissions(filter).await? {
+            let field = fields
+                .iter_mut()
+                .find(|f| f.attribute.logical_name == permission.attribute_logical_name);
+            if let Some(field) = field {
+                field.access |= permission.access();
+            }
+        }
+        Ok(fields)
+    }
+
+    async fn query_field_permissions(&self, filter: Filter) -> Result<Vec<FieldPermission>, Error> {
+        let mut pages = self
+            .query(FieldPermission::entity())
+            .filter(filter)
+            .into_typed::<FieldPermission>()
+            .into_async_iter(self);
+        let mut permissions = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            permissions.extend(page?);
+        }
+        Ok(permissions)
+    }
+
+    /// Returns all records associated with a record through a relationship.
+    async fn related_records(
+        &self,
+        entity: Entity,
+        id: Uuid,
+        relationship: &str,
+    ) -> Result<Vec<Record>, Error> {
+        let mut pages = self.related(entity, id, relationship).into_async_iter();
+        let mut records = Vec::new();
+        while let Some(page) = pages.next().await {
+            records.extend(page?.into_records());
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::metadata::AttributeType;
+    use crate::model::types::EntityBinding;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .attribute("salary", AttributeType::Money)
+                    .attribute("governmentid", AttributeType::String)
+                    .secured("salary")
+                    .secured("governmentid"),
+            )
+            .entity(MockEntity::new("systemuser", "systemusers", "systemuserid"))
+            .entity(MockEntity::new("team", "teams", "teamid"))
+            .entity(
+                MockEntity::new(
+                    "fieldsecurityprofile",
+                    "fieldsecurityprofiles",
+                    "fieldsecurityprofileid",
+                )
+                .primary_name("name")
+                .attribute("description", AttributeType::Memo),
+            )
+            .entity(
+                MockEntity::new("fieldpermission", "fieldpermissions", "fieldpermissionid")
+                    .lookup("fieldsecurityprofileid", "fieldsecurityprofile")
+                    .attribute("entityname", AttributeType::String)
+                    .attribute("attributelogicalname", AttributeType::String)
+                    .attribute("canread", AttributeType::Picklist)
+                    .attribute("cancreate", AttributeType::Picklist)
+                    .attribute("canupdate", AttributeType::Picklist),
+            )
+            .entity(
+                MockEntity::new(
+                    "principalobjectattributeaccess",
+                    "principalobjectattributeaccessset",
+                    "principalobjectattributeaccessid",
+                )
+                .lookup("principalid", "systemuser")
+                .lookup("objectid", "contact")
+                .attribute("attributeid", AttributeType::Uniqueidentifier)
+                .attribute("readaccess", AttributeType::Boolean)
+                .attribute("updateaccess", AttributeType::Boolean),
+            )
+    }
+
+    fn permission(profile: Uuid, attribute: &str, read: i32, update: i32) -> Record {
+        Record::new("fieldpermission")
+            .set(
+                "fieldsecurityprofileid",
+                EntityBinding::new("fieldsecurityprofiles", profile),
+            )
+            .set("entityname", "contact")
+            .set("attributelogicalname", attribute)
+            .set("canread", read)
+            .set("cancreate", 0)
+            .set("canupdate", update)
+    }
+
+    #[tokio::test]
+    async fn test_secured_field_access() {
+        let mock = mock();
+        let client = mock.client();
+        let me = mock.user_id();
+        mock.insert(
+            Entity::set("systemusers"),
+            Record::new("systemuser").set("systemuserid", me),
+        );
+        let team = mock.insert(Entity::set("teams"), Record::new("team"));
+        let readers = mock.insert(
+            Entity::set("fieldsecurityprofiles"),
+            Record::new("fieldsecurityprofile").set("name", "Readers"),
+        );
+        let payroll = mock.insert(
+            Entity::set("fieldsecurityprofiles"),
+            Record::new("fieldsecurityprofile").set("name", "Payroll"),
+        );
+        let others = mock.insert(
+            Entity::set("fieldsecurityprofiles"),
+            Record::new("fieldsecurityprofile").set("name", "Others"),
+        );
+        for record in [
+            permission(readers, "salary", 4, 0),
+            permission(payroll, "salary", 4, 4),
+            permission(payroll, "governmentid", 0, 0),
+            permission(others, "governmentid", 4, 4),
+        ] {
+            mock.insert(Entity::set("fieldpermissions"), record);
+        }
+
+        let users = Entity::logical("systemuser");
+        let teams = Entity::logical("team");
+        let profiles = Entity::logical("fieldsecurityprofile");
+        client
+            .associate(
+                users.clone(),
+                me,
+                "systemuserprofiles_association",
+                profiles.clone(),
+                readers,
+            )
+            .await
+            .unwrap();
+        client
+            .associate(users, me, "teammembership_association", teams.clone(), team)
+            .await
+            .unwrap();
+        client
+            .associate(teams, team, "teamprofiles_association", profiles, payroll)
+            .await
+            .unwrap();
+
+        let mut names: Vec<_> = client
+            .user_field_security_profiles(me)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|p| p.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Payroll", "Readers"]);
+        assert_eq!(client.field_security_profiles().await.unwrap().len(), 3);
+        assert_eq!(client.field_permissions(payroll).await.unwrap().len(), 2);
+
+        let mut fields = client
+            .secured_field_access(Entity::logical("contact"))
+            .await
+            .unwrap();
+        fields.sort_by(|a, b| a.attribute.logical_name.cmp(&b.attribute.logical_name));
+        let access: Vec<_> = fields
+            .iter()
+            .map(|f| (f.attribute.logical_name.as_str(), f.access))
+            .collect();
+        assert_eq!(
+            access,
+            [
+                ("governmentid", FieldAccess::empty()),
+                ("salary", FieldAccess::READ | FieldAccess::UPDATE),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_field_shares() {
+        let mock = mock();
+        let client = mock.client();
+        let contact = mock.insert(Entity::set("contacts"), Record::new("contact"));
+        let user = mock.insert(Entity::set("systemusers"), Record::new("systemuser"));
+        let attribute = Uuid::new_v4();
+        mock.insert(
+            Entity::set("principalobjectattributeaccessset"),
+            Record::new("principalobjectattributeaccess")
+                .set("principalid", EntityBinding::new("systemusers", user))
+                .set("objectid", EntityBinding::new("contacts", contact))
+                .set("attributeid", attribute)
+                .set("readaccess", true)
+                .set("updateaccess", false),
+        );
+
+        let shares = client.field_shares(contact).await.unwrap();
+        assert_eq!(shares.len(), 1);
+        assert_eq!(shares[0].principal.id, user);
+        assert_eq!(shares[0].attribute_id, attribute);
+        assert_eq!(shares[0].access(), FieldAccess::READ);
+        assert!(client.field_shares(user).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,747 @@
+//! File and image column content
+//!
+//! File and image columns keep their content outside the record: retrieving
+//! the record only returns a reference. Content is uploaded with
+//! `PATCH <record>/<column>` (in chunks for large files), downloaded from
+//! `<record>/<column>/$value` with range requests, and removed with
+//! `DELETE <record>/<column>`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let pdf = tokio::fs::read("contract.pdf").await?;
+//! client
+//!     .upload_file(Entity::set("accounts"), id, "new_contract", "contract.pdf", pdf)
+//!     .on_progress(|sent, total| println!("{sent}/{total}"))
+//!     .await?;
+//!
+//! let file = client.download_file(Entity::set("accounts"), id, "new_contract").await?;
+//! assert_eq!(file.file_name.as_deref(), Some("contract.pdf"));
+//! ```
+
+use std::io;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use bytes::BytesMut;
+use futures::Stream;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use reqwest::Method;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderValue;
+use uuid::Uuid;
+
+use super::crud::RecordKey;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::stream::DEFAULT_FILE_CHUNK_SIZE;
+use crate::stream::FileChunks;
+
+/// Progress callback, called with `(bytes transferred, total bytes)` after
+/// each chunk.
+pub type ProgressFn = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// A downloaded file.
+#[derive(Debug, Clone)]
+pub struct FileContent {
+    /// The file's name, if the server sent it.
+    pub file_name: Option<String>,
+    /// The file's content.
+    pub data: Bytes,
+}
+
+/// Which version of an image column to download.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageSize {
+    /// The 144x144 thumbnail Dataverse always stores.
+    #[default]
+    Thumbnail,
+    /// The original image, when the column can store full images.
+    Full,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Uploads a file to a file or image column, replacing any existing file.
+    ///
+    /// Files larger than the chunk size (4 MiB by default) are sent in chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity the record belongs to
+    /// * `id` - The record's id or alternate key
+    /// * `column` - The file or image column's logical name
+    /// * `file_name` - The name to store the file under
+    /// * `data` - The file's content
+    pub fn upload_file(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+        file_name: impl Into<String>,
+        data: impl Into<Bytes>,
+    ) -> ClientUploadFileBuilder<'_> {
+        let data = data.into();
+        let size = data.len() as u64;
+        let stream = futures::stream::once(async move { Ok(data) }).boxed();
+        ClientUploadFileBuilder::new(self, entity, id.into(), column, file_name, size, stream)
+    }
+
+    /// Uploads a file from a stream of bytes, for files too large to hold in
+    /// memory.
+    ///
+    /// `size` must be the stream's total length; the upload fails if the
+    /// stream ends early. A file can be streamed with
+    /// `tokio_util::io::ReaderStream`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let file = tokio::fs::File::open("backup.zip").await?;
+    /// let size = file.metadata().await?.len();
+    /// client
+    ///     .upload_file_stream(entity, id, "new_backup", "backup.zip", size, ReaderStream::new(file))
+    ///     .await?;
+    /// ```
+    pub fn upload_file_stream<'a>(
+        &'a self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+        file_name: impl Into<String>,
+        size: u64,
+        stream: impl Stream<Item = io::Result<Bytes>> + Send + 'a,
+    ) -> ClientUploadFileBuilder<'a> {
+        ClientUploadFileBuilder::new(
+            self,
+            entity,
+            id.into(),
+            column,
+            file_name,
+            size,
+            stream.boxed(),
+        )
+    }
+
+    /// Downloads the content of a file or image column.
+    ///
+    /// Image columns return the thumbnail unless `full_size()` is set.
+    pub fn download_file(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+    ) -> ClientDownloadFileBuilder<'_> {
+        ClientDownloadFileBuilder {
+            client: self,
+            chunks: self.file_chunks(entity, id, column),
+            progress: None,
+        }
+    }
+
+    /// Downloads an image column, as a thumbnail or at full size.
+    ///
+    /// Columns that can't store full images (`CanStoreFullImage` is false in
+    /// their metadata) only have the thumbnail, which is returned for
+    /// [`ImageSize::Full`] too.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity the record belongs to
+    /// * `id` - The record's id or alternate key
+    /// * `column` - The image column's logical name
+    /// * `size` - Whether to download the thumbnail or the full image
+    pub async fn download_image(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+        size: ImageSize,
+    ) -> Result<FileContent, Error> {
+        let column = column.into();
+        let full_size = match size {
+            ImageSize::Thumbnail => false,
+            ImageSize::Full => self.can_store_full_image(&entity, &column).await?,
+        };
+        let download = self.download_file(entity, id, column);
+        if full_size {
+            download.full_size().await
+        } else {
+            download.await
+        }
+    }
+
+    /// Returns an async iterator over the chunks of a file or image column.
+    ///
+    /// See [`FileChunks`].
+    pub fn file_chunks(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+    ) -> FileChunks {
+        FileChunks::new(entity, id.into(), column.into())
+    }
+
+    /// Deletes the file stored in a file or image column.
+    pub async fn delete_file(
+        &self,
+        entity: Entity,
+        id: impl Into<RecordKey>,
+        column: impl Into<String>,
+    ) -> Result<(), Error> {
+        let url = self.file_url(&entity, &id.into(), &column.into()).await?;
+        self.request(Method::DELETE, &url, None, None).await?;
+        Ok(())
+    }
+
+    /// Returns whether an image column stores full-size images, from metadata.
+    async fn can_store_full_image(&self, entity: &Entity, column: &str) -> Result<bool, Error> {
+        let logical_name = self.resolve_entity_logical_name(entity).await?;
+        let attribute = self.metadata().attribute(&logical_name, column).await?;
+        if !attribute.is_image() {
+            return Err(Error::InvalidOperation(format!(
+                "'{}' is not an image column of '{}'",
+                column, logical_name
+            )));
+        }
+        Ok(attribute.can_store_full_image.unwrap_or(true))
+    }
+
+    /// Returns the URL of a record's file column.
+    async fn file_url(
+        &self,
+        entity: &Entity,
+        key: &RecordKey,
+        column: &str,
+    ) -> Result<String, Error> {
+        let entity_set = self.resolve_entity(entity).await?;
+        Ok(self.build_url(&format!("/{}({})/{}", entity_set, key.to_path(), column)))
+    }
+}
+
+// =============================================================================
+// Record helpers
+// =============================================================================
+
+impl Record {
+    /// Fetches an image column's content for this record.
+    ///
+    /// A thumbnail that came back with the record is decoded without a
+    /// request; anything else is downloaded with
+    /// [`DataverseClient::download_image`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let account = client.retrieve(Entity::logical("account"), id).await?.into_inner();
+    /// let logo = account.fetch_image(&client, "entityimage", ImageSize::Full).await?;
+    /// ```
+    pub async fn fetch_image(
+        &self,
+        client: &DataverseClient,
+        column: &str,
+        size: ImageSize,
+    ) -> Result<FileContent, Error> {
+        if size == ImageSize::Thumbnail
+            && let Ok(Some(thumbnail)) = self.get_image_thumbnail(column)
+        {
+            return Ok(FileContent {
+                file_name: None,
+                data: thumbnail.into(),
+            });
+        }
+        if self.entity().name().is_empty() {
+            return Err(Error::InvalidOperation(
+                "record has no entity to fetch the image from".to_string(),
+            ));
+        }
+        let id = match self.id() {
+            Some(id) => id,
+            None => self.primary_id(client).await?,
+        };
+        client
+            .download_image(self.entity().clone(), id, column, size)
+            .await
+    }
+
+    /// Reads the record's id from its primary id column.
+    async fn primary_id(&self, client: &DataverseClient) -> Result<Uuid, Error> {
+        let logical_name = client.resolve_entity_logical_name(self.entity()).await?;
+        let (_, primary_id_attribute) = client.resolve_entity_core(&logical_name).await?;
+        self.get_guid(&primary_id_attribute)
+            .ok()
+            .flatten()
+            .ok_or_else(|| Error::InvalidOperation("record has no id".to_string()))
+    }
+}
+
+// =============================================================================
+// Upload
+// =============================================================================
+
+/// Builder for file uploads bound to a client.
+pub struct ClientUploadFileBuilder<'a> {
+    client: &'a DataverseClient,
+    entity: Entity,
+    key: RecordKey,
+    column: String,
+    file_name: String,
+    size: u64,
+    stream: BoxStream<'a, io::Result<Bytes>>,
+    chunk_size: Option<usize>,
+    progress: Option<ProgressFn>,
+}
+
+impl<'a> ClientUploadFileBuilder<'a> {
+    fn new(
+        client: &'a DataverseClient,
+        entity: Entity,
+        key: RecordKey,
+        column: impl Into<String>,
+        file_name: impl Into<String>,
+        size: u64,
+        stream: BoxStream<'a, io::Result<Bytes>>,
+    ) -> Self {
+        Self {
+            client,
+            entity,
+            key,
+            column: column.into(),
+            file_name: file_name.into(),
+            size,
+            stream,
+            chunk_size: None,
+            progress: None,
+        }
+    }
+
+    /// Sets the chunk size in bytes.
+    ///
+    /// Files up to this size are sent in a single request. Defaults to 4 MiB,
+    /// or the size the server asks for once a chunked upload has started.
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = Some(bytes.max(1));
+        self
+    }
+
+    /// Calls `f` with `(bytes sent, total bytes)` after each chunk.
+    pub fn on_progress(mut self, f: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    async fn execute(self) -> Result<(), Error> {
+        let url = self
+            .client
+            .file_url(&self.entity, &self.key, &self.column)
+            .await?;
+        let file_name = HeaderValue::from_bytes(self.file_name.as_bytes()).map_err(|_| {
+            Error::InvalidOperation(format!("Invalid file name: {}", self.file_name))
+        })?;
+        let mut reader = ChunkReader::new(self.stream);
+        let size = self.size;
+        let report = |sent: u64| {
+            if let Some(progress) = &self.progress {
+                progress(sent, size);
+            }
+        };
+
+        let chunk_size = self.chunk_size.unwrap_or(DEFAULT_FILE_CHUNK_SIZE);
+        if size <= chunk_size as u64 {
+            let data = reader.read(size as usize).await?;
+            let mut headers = content_headers(&file_name);
+            headers.insert(
+                "Content-Type",
+                HeaderValue::from_static("application/octet-stream"),
+            );
+            self.client
+                .request_bytes(Method::PATCH, &url, headers, Some(data))
+                .await?;
+            report(size);
+            return Ok(());
+        }
+
+        // Start the session; chunks go to the URL it returns
+        let mut headers = content_headers(&file_name);
+        headers.insert("x-ms-transfer-mode", HeaderValue::from_static("chunked"));
+        let response = self
+            .client
+            .request(Method::PATCH, &url, headers, None)
+            .await?;
+        let location = response
+            .headers()
+            .get("Location")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                Error::Api(ApiError::Parse {
+                    message: "Missing Location header for chunked upload".to_string(),
+                    body: None,
+                })
+            })?;
+        let chunk_size = self.chunk_size.unwrap_or_else(|| {
+            response
+                .headers()
+                .get("x-ms-chunk-size")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_FILE_CHUNK_SIZE)
+        });
+
+        let mut sent = 0;
+        while sent < size {
+            let len = (size - sent).min(chunk_size as u64);
+            let chunk = reader.read(len as usize).await?;
+            let range = format!("bytes {}-{}/{}", sent, sent + len - 1, size);
+
+            let mut headers = content_headers(&file_name);
+            headers.insert(
+                "Content-Type",
+                HeaderValue::from_static("application/octet-stream"),
+            );
+            headers.insert(
+                "Content-Range",
+                HeaderValue::from_str(&range).expect("range header is ASCII"),
+            );
+            self.client
+                .request_bytes(Method::PATCH, &location, headers, Some(chunk))
+                .await?;
+
+            sent += len;
+            report(sent);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientUploadFileBuilder<'a> {
+    type Output = Result<(), Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+/// Returns the OData headers plus `x-ms-file-name`.
+fn content_headers(file_name: &HeaderValue) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("OData-MaxVersion", HeaderValue::from_static("4.0"));
+    headers.insert("OData-Version", HeaderValue::from_static("4.0"));
+    headers.insert("x-ms-file-name", file_name.clone());
+    headers
+}
+
+/// Re-slices a byte stream into chunks of the requested sizes.
+pub(crate) struct ChunkReader<'a> {
+    stream: BoxStream<'a, io::Result<Bytes>>,
+    buffer: BytesMut,
+    read: u64,
+}
+
+impl<'a> ChunkReader<'a> {
+    pub(crate) fn new(stream: BoxStream<'a, io::Result<Bytes>>) -> Self {
+        Self {
+            stream,
+            buffer: BytesMut::new(),
+            read: 0,
+        }
+    }
+
+    /// Reads exactly `len` bytes.
+    pub(crate) async fn read(&mut self, len: usize) -> Result<Bytes, Error> {
+        while self.buffer.len() < len {
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None => {
+                    return Err(Error::Io(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "stream ended after {} bytes",
+                            self.read + self.buffer.len() as u64
+                        ),
+                    )));
+                }
+            }
+        }
+        self.read += len as u64;
+        Ok(self.buffer.split_to(len).freeze())
+    }
+}
+
+// =============================================================================
+// Download
+// =============================================================================
+
+/// Builder for file downloads bound to a client.
+pub struct ClientDownloadFileBuilder<'a> {
+    client: &'a DataverseClient,
+    chunks: FileChunks,
+    progress: Option<ProgressFn>,
+}
+
+impl<'a> ClientDownloadFileBuilder<'a> {
+    /// Sets the chunk size in bytes (default 4 MiB).
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunks = self.chunks.chunk_size(bytes);
+        self
+    }
+
+    /// Downloads the full-size image instead of the thumbnail (image columns).
+    pub fn full_size(mut self) -> Self {
+        self.chunks = self.chunks.full_size();
+        self
+    }
+
+    /// Calls `f` with `(bytes received, total bytes)` after each chunk.
+    pub fn on_progress(mut self, f: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    async fn execute(mut self) -> Result<FileContent, Error> {
+        let mut data = BytesMut::new();
+        while let Some(chunk) = self.chunks.next(self.client).await {
+            data.extend_from_slice(&chunk?);
+            if let Some(progress) = &self.progress {
+                let received = self.chunks.position();
+                progress(received, self.chunks.size().unwrap_or(received));
+            }
+        }
+        Ok(FileContent {
+            file_name: self.chunks.file_name().map(str::to_string),
+            data: data.freeze(),
+        })
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientDownloadFileBuilder<'a> {
+    type Output = Result<FileContent, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::metadata::AttributeType;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new().entity(
+            MockEntity::new("account", "accounts", "accountid")
+                .attribute("new_contract", AttributeType::File)
+                .image("entityimage", true)
+                .image("new_badge", false),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_single_request_round_trip() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(Entity::set("accounts"), Record::new("account"));
+
+        client
+            .upload_file(
+                Entity::set("accounts"),
+                id,
+                "new_contract",
+                "a.txt",
+                &b"hello"[..],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.file(Entity::set("accounts"), id, "new_contract"),
+            Some(("a.txt".to_string(), b"hello".to_vec()))
+        );
+
+        let file = client
+            .download_file(Entity::set("accounts"), id, "new_contract")
+            .await
+            .unwrap();
+        assert_eq!(file.file_name.as_deref(), Some("a.txt"));
+        assert_eq!(&file.data[..], b"hello");
+
+        client
+            .delete_file(Entity::set("accounts"), id, "new_contract")
+            .await
+            .unwrap();
+        assert!(
+            client
+                .download_file(Entity::set("accounts"), id, "new_contract")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chunked_upload_and_download() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        // Stream pieces that don't line up with the chunks
+        let pieces: Vec<io::Result<Bytes>> = data
+            .chunks(77)
+            .map(|piece| Ok(Bytes::copy_from_slice(piece)))
+            .collect();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let progress = sent.clone();
+        client
+            .upload_file_stream(
+                Entity::set("accounts"),
+                id,
+                "new_contract",
+                "big.bin",
+                data.len() as u64,
+                futures::stream::iter(pieces),
+            )
+            .chunk_size(300)
+            .on_progress(move |sent, total| progress.lock().unwrap().push((sent, total)))
+            .await
+            .unwrap();
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![(300, 1000), (600, 1000), (900, 1000), (1000, 1000)]
+        );
+        let (_, stored) = mock
+            .file(Entity::set("accounts"), id, "new_contract")
+            .unwrap();
+        assert_eq!(stored, data);
+
+        let mut chunks = client
+            .file_chunks(Entity::set("accounts"), id, "new_contract")
+            .chunk_size(400);
+        let mut lengths = Vec::new();
+        let mut downloaded = Vec::new();
+        while let Some(chunk) = chunks.next(&client).await {
+            let chunk = chunk.unwrap();
+            lengths.push(chunk.len());
+            downloaded.extend_from_slice(&chunk);
+        }
+        assert_eq!(lengths, vec![400, 400, 200]);
+        assert_eq!(downloaded, data);
+        assert_eq!(chunks.size(), Some(1000));
+        assert_eq!(chunks.file_name(), Some("big.bin"));
+    }
+
+    #[tokio::test]
+    async fn test_short_stream_fails() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(Entity::set("accounts"), Record::new("account"));
+
+        let result = client
+            .upload_file_stream(
+                Entity::set("accounts"),
+                id,
+                "new_contract",
+                "short.bin",
+                10,
+                futures::stream::iter([Ok(Bytes::from_static(b"abc"))]),
+            )
+            .chunk_size(4)
+            .await;
+        assert!(matches!(result, Err(Error::Io(_))));
+        assert!(
+            mock.file(Entity::set("accounts"), id, "new_contract")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_image_sizes() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let image: Vec<u8> = (0..40).collect();
+
+        for column in ["entityimage", "new_badge"] {
+            client
+                .upload_file(
+                    Entity::set("accounts"),
+                    id,
+                    column,
+                    "logo.png",
+                    image.clone(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let thumbnail = client
+            .download_image(
+                Entity::set("accounts"),
+                id,
+                "entityimage",
+                ImageSize::Thumbnail,
+            )
+            .await
+            .unwrap();
+        assert_eq!(&thumbnail.data[..], &image[..16]);
+        let full = client
+            .download_image(Entity::set("accounts"), id, "entityimage", ImageSize::Full)
+            .await
+            .unwrap();
+        assert_eq!(&full.data[..], &image[..]);
+
+        // Only the thumbnail is stored, so that's what a full download returns
+        let attribute = client
+            .metadata()
+            .attribute("account", "new_badge")
+            .await
+            .unwrap();
+        assert!(attribute.is_image());
+        assert_eq!(attribute.can_store_full_image, Some(false));
+        let badge = client
+            .download_image(Entity::set("accounts"), id, "new_badge", ImageSize::Full)
+            .await
+            .unwrap();
+        assert_eq!(&badge.data[..], &image[..16]);
+
+        let result = client
+            .download_image(Entity::set("accounts"), id, "new_contract", ImageSize::Full)
+            .await;
+        assert!(matches!(result, Err(Error::InvalidOperation(_))));
+
+        // The record carries the thumbnail; the full image is fetched on demand
+        let record = client
+            .retrieve(Entity::set("accounts"), id)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            record.get_image_thumbnail("entityimage").unwrap(),
+            Some(image[..16].to_vec())
+        );
+        let thumbnail = record
+            .fetch_image(&client, "entityimage", ImageSize::Thumbnail)
+            .await
+            .unwrap();
+        assert_eq!(&thumbnail.data[..], &image[..16]);
+        let full = record
+            .fetch_image(&client, "entityimage", ImageSize::Full)
+            .await
+            .unwrap();
+        assert_eq!(&full.data[..], &image[..]);
+    }
+}
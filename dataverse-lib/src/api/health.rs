@@ -0,0 +1,217 @@
+//! Connection health checks and warm-up
+//!
+//! [`DataverseClient::health_check`] checks that a token can be acquired and
+//! the environment answers a `WhoAmI`, within a strict timeout, for
+//! readiness probes. [`DataverseClient::warm_up`] does the same at startup
+//! to open a connection (DNS, TLS) and then loads the metadata of the
+//! entities the app uses into the cache, so the first real requests don't
+//! pay for either.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // At startup
+//! client
+//!     .warm_up()
+//!     .entities(["account", "contact", "incident"])
+//!     .await?;
+//!
+//! // In a readiness probe
+//! let report = client.health_check().timeout(Duration::from_secs(2)).await?;
+//! println!("token {:?}, WhoAmI {:?}", report.token_latency, report.request_latency);
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream;
+use tokio::time::Instant;
+
+use crate::DataverseClient;
+use crate::WhoAmIResponse;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+
+/// Default time a health check may take.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many entities' metadata a warm-up loads at once.
+const WARM_UP_CONCURRENCY: usize = 4;
+
+/// Outcome of a successful health check.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    /// The calling user, as reported by `WhoAmI`.
+    pub user: WhoAmIResponse,
+    /// Time taken to acquire an access token.
+    pub token_latency: Duration,
+    /// Time taken by the `WhoAmI` request.
+    pub request_latency: Duration,
+}
+
+/// Runs `future`, failing with [`ApiError::Timeout`] after `limit`.
+async fn within<T>(
+    limit: Duration,
+    timeout: Duration,
+    future: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(limit, future)
+        .await
+        .map_err(|_| Error::Api(ApiError::Timeout(timeout)))?
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Checks that a token can be acquired and the environment responds.
+    ///
+    /// Makes a single `WhoAmI` request, without retries or caching, and
+    /// fails with [`ApiError::Timeout`] if the whole check takes longer than
+    /// the [`timeout`](HealthCheckBuilder::timeout) (default 10 seconds).
+    pub fn health_check(&self) -> HealthCheckBuilder<'_> {
+        HealthCheckBuilder {
+            client: self,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Prepares the client for its first requests: runs a health check,
+    /// which opens a connection, then loads the metadata of the given
+    /// entities into the cache.
+    pub fn warm_up(&self) -> WarmUpBuilder<'_> {
+        WarmUpBuilder {
+            client: self,
+            timeout: DEFAULT_TIMEOUT,
+            entities: Vec::new(),
+        }
+    }
+}
+
+// =============================================================================
+// HealthCheckBuilder
+// =============================================================================
+
+/// Builder for a health check, bound to a client.
+pub struct HealthCheckBuilder<'a> {
+    client: &'a DataverseClient,
+    timeout: Duration,
+}
+
+impl<'a> HealthCheckBuilder<'a> {
+    /// Sets how long the whole check may take.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn execute(self) -> Result<HealthReport, Error> {
+        let started = Instant::now();
+        within(self.timeout, self.timeout, self.client.access_token()).await?;
+        let token_latency = started.elapsed();
+
+        let left = self.timeout.saturating_sub(token_latency);
+        let sent = Instant::now();
+        let user = within(left, self.timeout, self.client.connect()).await?;
+
+        Ok(HealthReport {
+            user,
+            token_latency,
+            request_latency: sent.elapsed(),
+        })
+    }
+}
+
+impl<'a> std::future::IntoFuture for HealthCheckBuilder<'a> {
+    type Output = Result<HealthReport, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+// =============================================================================
+// WarmUpBuilder
+// =============================================================================
+
+/// Builder for warming up a client, bound to a client.
+pub struct WarmUpBuilder<'a> {
+    client: &'a DataverseClient,
+    timeout: Duration,
+    entities: Vec<Entity>,
+}
+
+impl<'a> WarmUpBuilder<'a> {
+    /// Sets the timeout of the health check. Loading metadata isn't limited.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Adds an entity whose metadata to load.
+    pub fn entity(mut self, entity: impl Into<Entity>) -> Self {
+        self.entities.push(entity.into());
+        self
+    }
+
+    /// Adds entities whose metadata to load.
+    pub fn entities<E: Into<Entity>>(mut self, entities: impl IntoIterator<Item = E>) -> Self {
+        self.entities.extend(entities.into_iter().map(Into::into));
+        self
+    }
+
+    async fn execute(self) -> Result<HealthReport, Error> {
+        let client = self.client;
+        let report = client.health_check().timeout(self.timeout).await?;
+
+        let mut loads = stream::iter(self.entities)
+            .map(|entity| async move { client.metadata().entity(entity).await })
+            .buffer_unordered(WARM_UP_CONCURRENCY);
+        while let Some(result) = loads.next().await {
+            result?;
+        }
+        Ok(report)
+    }
+}
+
+impl<'a> std::future::IntoFuture for WarmUpBuilder<'a> {
+    type Output = Result<HealthReport, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    #[tokio::test]
+    async fn test_health_check_and_warm_up() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+
+        let report = client.health_check().await.unwrap();
+        assert_eq!(report.user.user_id, mock.user_id());
+
+        let report = client
+            .warm_up()
+            .entity("account")
+            .timeout(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(report.user.user_id, mock.user_id());
+
+        assert!(client.warm_up().entity("nope").await.is_err());
+    }
+}
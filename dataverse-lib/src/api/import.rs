@@ -0,0 +1,611 @@
+//! CSV import
+//!
+//! [`DataverseClient::import_csv`] reads rows from a CSV file, converts each
+//! cell to its column's type using the table's metadata, and creates the
+//! records with `CreateMultiple`. A chunk that fails as a whole is sent again
+//! as a `$batch` of single creates, so the [`ImportReport`] says which rows
+//! failed and why.
+//!
+//! Headers are matched to columns by logical name or display name, or
+//! explicitly with [`map_column`](ClientCsvImportBuilder::map_column). Cells
+//! are read as follows:
+//!
+//! - option sets: the value or the label; multi-select option sets separate
+//!   them with `;`
+//! - lookups: the referenced id, prefixed with the target table as
+//!   `account:<id>` for lookups with more than one target
+//! - dates: RFC 3339, `YYYY-MM-DD HH:MM:SS` (UTC) or `YYYY-MM-DD`
+//! - yes/no: `true`/`false`, `yes`/`no` or `1`/`0`
+//! - the primary key column: the id to create the record with
+//!
+//! Empty cells are left out of the record.
+//!
+//! # Resuming
+//!
+//! The report serializes with serde. Passing it to
+//! [`resume`](ClientCsvImportBuilder::resume) when importing the same file
+//! again skips the rows it created:
+//!
+//! ```ignore
+//! let report = client
+//!     .import_csv(Entity::logical("contact"), File::open("contacts.csv")?)
+//!     .map_column("Name", "fullname")
+//!     .await?;
+//! for row in report.failed() {
+//!     eprintln!("row {}: {:?}", row.row, row.outcome);
+//! }
+//!
+//! // After fixing the data
+//! let report = client
+//!     .import_csv(Entity::logical("contact"), File::open("contacts.csv")?)
+//!     .map_column("Name", "fullname")
+//!     .resume(&report)
+//!     .await?;
+//! ```
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::BatchOperationResult;
+use super::DEFAULT_CHUNK_SIZE;
+use super::Op;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::error::MetadataError;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::Value;
+use crate::model::metadata::AttributeMetadata;
+use crate::model::metadata::AttributeType;
+use crate::model::metadata::EntityMetadata;
+use crate::model::types::EntityBinding;
+use crate::model::types::Money;
+use crate::model::types::MultiSelectOptionSetValue;
+use crate::model::types::OptionSetValue;
+
+// =============================================================================
+// Report
+// =============================================================================
+
+/// What happened to one row of an import.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status", content = "detail")]
+pub enum RowOutcome {
+    /// The record was created with this id.
+    Created(Uuid),
+    /// The row couldn't be read or the record couldn't be created.
+    Failed(String),
+}
+
+/// The outcome of one row of an import.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowResult {
+    /// Index of the row in the file, not counting the header.
+    pub row: usize,
+    /// What happened to it.
+    pub outcome: RowOutcome,
+}
+
+/// Per-row results of an import, in file order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    rows: Vec<RowResult>,
+}
+
+impl ImportReport {
+    /// Returns the result of every row.
+    pub fn rows(&self) -> &[RowResult] {
+        &self.rows
+    }
+
+    /// Returns the rows that were created, with their ids.
+    pub fn created(&self) -> impl Iterator<Item = (usize, Uuid)> + '_ {
+        self.rows.iter().filter_map(|r| match r.outcome {
+            RowOutcome::Created(id) => Some((r.row, id)),
+            RowOutcome::Failed(_) => None,
+        })
+    }
+
+    /// Returns the rows that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &RowResult> + '_ {
+        self.rows
+            .iter()
+            .filter(|r| matches!(r.outcome, RowOutcome::Failed(_)))
+    }
+
+    /// Returns whether every row was created.
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    fn push(&mut self, row: usize, outcome: RowOutcome) {
+        self.rows.push(RowResult { row, outcome });
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Imports records from CSV, creating one record per row.
+    ///
+    /// The first row must be a header. See the [module docs](crate::api::import)
+    /// for how headers and cells are read.
+    pub fn import_csv<'a, R: Read + Send + 'a>(
+        &'a self,
+        entity: impl Into<Entity>,
+        reader: R,
+    ) -> ClientCsvImportBuilder<'a, R> {
+        ClientCsvImportBuilder {
+            client: self,
+            entity: entity.into(),
+            reader,
+            mappings: HashMap::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            resume: HashMap::new(),
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for a CSV import, bound to a client.
+pub struct ClientCsvImportBuilder<'a, R> {
+    client: &'a DataverseClient,
+    entity: Entity,
+    reader: R,
+    mappings: HashMap<String, String>,
+    chunk_size: usize,
+    resume: HashMap<usize, Uuid>,
+}
+
+impl<'a, R: Read + Send + 'a> ClientCsvImportBuilder<'a, R> {
+    /// Reads the column with this header into an attribute.
+    pub fn map_column(mut self, header: impl Into<String>, attribute: impl Into<String>) -> Self {
+        self.mappings.insert(header.into(), attribute.into());
+        self
+    }
+
+    /// Sets the number of rows created per request.
+    ///
+    /// Defaults to [`DEFAULT_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Skips the rows a previous import of the same file created, keeping
+    /// their results in the new report.
+    pub fn resume(mut self, report: &ImportReport) -> Self {
+        self.resume = report.created().collect();
+        self
+    }
+
+    /// Runs the import.
+    ///
+    /// Fails if the table's metadata can't be loaded or a header doesn't
+    /// match a column. Errors in individual rows are returned in the report.
+    pub async fn execute(self) -> Result<ImportReport, Error> {
+        let client = self.client;
+        let metadata = client.metadata().entity(self.entity.clone()).await?;
+        let entity = Entity::set(metadata.entity_set_name.clone());
+
+        let mut reader = csv::Reader::from_reader(self.reader);
+        let headers = reader.headers().map_err(std::io::Error::from)?.clone();
+        let mut columns = Vec::with_capacity(headers.len());
+        for header in &headers {
+            let attribute = self.mappings.get(header).map(String::as_str);
+            columns.push(Column::resolve(client, &metadata, header, attribute).await?);
+        }
+
+        let mut report = ImportReport::default();
+        let mut chunk: Vec<(usize, Record)> = Vec::new();
+        for (row, cells) in reader.records().enumerate() {
+            if let Some(id) = self.resume.get(&row) {
+                report.push(row, RowOutcome::Created(*id));
+                continue;
+            }
+            let record = cells
+                .map_err(|e| e.to_string())
+                .and_then(|cells| to_record(&metadata, &columns, &cells));
+            match record {
+                Ok(record) => chunk.push((row, record)),
+                Err(message) => report.push(row, RowOutcome::Failed(message)),
+            }
+            if chunk.len() == self.chunk_size {
+                create_chunk(client, &entity, std::mem::take(&mut chunk), &mut report).await;
+            }
+        }
+        if !chunk.is_empty() {
+            create_chunk(client, &entity, chunk, &mut report).await;
+        }
+
+        report.rows.sort_by_key(|r| r.row);
+        Ok(report)
+    }
+}
+
+impl<'a, R: Read + Send + 'a> std::future::IntoFuture for ClientCsvImportBuilder<'a, R> {
+    type Output = Result<ImportReport, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+/// Creates a chunk of records with `CreateMultiple`, falling back to single
+/// creates in a `$batch` to find the failing rows if the chunk fails.
+async fn create_chunk(
+    client: &DataverseClient,
+    entity: &Entity,
+    chunk: Vec<(usize, Record)>,
+    report: &mut ImportReport,
+) {
+    let (rows, records): (Vec<usize>, Vec<Record>) = chunk.into_iter().unzip();
+    let results = client
+        .create_multiple(entity.clone(), records.clone())
+        .chunk_size(rows.len())
+        .await;
+    if let Ok(results) = &results
+        && results.is_success()
+    {
+        for (row, id) in rows.into_iter().zip(results.ids()) {
+            match id {
+                Some(id) => report.push(row, RowOutcome::Created(*id)),
+                None => report.push(row, RowOutcome::Failed("no id returned".to_string())),
+            }
+        }
+        return;
+    }
+
+    let mut batch = client.batch().continue_on_error();
+    for record in records {
+        batch = batch.add(Op::create(entity.clone(), record));
+    }
+    let results = match batch.execute().await {
+        Ok(results) => results,
+        Err(e) => {
+            for row in rows {
+                report.push(row, RowOutcome::Failed(e.to_string()));
+            }
+            return;
+        }
+    };
+    for (i, row) in rows.into_iter().enumerate() {
+        let outcome = match results.operation(i) {
+            Some(Ok(BatchOperationResult::Created { id, .. })) => RowOutcome::Created(*id),
+            Some(Err(e)) => RowOutcome::Failed(e.to_string()),
+            _ => RowOutcome::Failed("no result returned".to_string()),
+        };
+        report.push(row, outcome);
+    }
+}
+
+// =============================================================================
+// Columns
+// =============================================================================
+
+/// A CSV column resolved to an attribute.
+struct Column {
+    logical_name: String,
+    /// Entity set of each lookup target, by logical name.
+    targets: Vec<(String, String)>,
+}
+
+impl Column {
+    async fn resolve(
+        client: &DataverseClient,
+        metadata: &EntityMetadata,
+        header: &str,
+        attribute: Option<&str>,
+    ) -> Result<Self, Error> {
+        let attr = match attribute {
+            Some(name) => metadata.attributes.iter().find(|a| a.logical_name == name),
+            None => metadata.attributes.iter().find(|a| {
+                a.logical_name.eq_ignore_ascii_case(header)
+                    || a.display_name
+                        .text()
+                        .is_some_and(|label| label.eq_ignore_ascii_case(header))
+            }),
+        };
+        let Some(attr) = attr else {
+            return Err(Error::Metadata(MetadataError::AttributeNotFound {
+                entity: metadata.logical_name.clone(),
+                attribute: attribute.unwrap_or(header).to_string(),
+            }));
+        };
+
+        let mut targets = Vec::new();
+        if is_lookup(attr.attribute_type) {
+            for target in &attr.targets {
+                let (entity_set, _) = client.resolve_entity_core(target).await?;
+                targets.push((target.clone(), entity_set));
+            }
+        }
+        Ok(Self {
+            logical_name: attr.logical_name.clone(),
+            targets,
+        })
+    }
+}
+
+fn is_lookup(attribute_type: AttributeType) -> bool {
+    matches!(
+        attribute_type,
+        AttributeType::Lookup | AttributeType::Customer | AttributeType::Owner
+    )
+}
+
+/// Builds a record from a row's cells.
+fn to_record(
+    metadata: &EntityMetadata,
+    columns: &[Column],
+    cells: &csv::StringRecord,
+) -> Result<Record, String> {
+    let mut record = Record::new(metadata.logical_name.as_str());
+    for (column, cell) in columns.iter().zip(cells) {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            continue;
+        }
+        let attr = metadata
+            .attributes
+            .iter()
+            .find(|a| a.logical_name == column.logical_name)
+            .expect("columns are resolved from the metadata");
+        let value = coerce(metadata, attr, column, cell)
+            .map_err(|message| format!("{}: {}", column.logical_name, message))?;
+        record.insert(column.logical_name.clone(), value);
+    }
+    Ok(record)
+}
+
+/// Converts a cell to a value of the attribute's type.
+fn coerce(
+    metadata: &EntityMetadata,
+    attr: &AttributeMetadata,
+    column: &Column,
+    cell: &str,
+) -> Result<Value, String> {
+    let invalid = |expected: &str| format!("'{}' isn't {}", cell, expected);
+    let value = match attr.attribute_type {
+        AttributeType::Boolean => match cell.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Value::Bool(true),
+            "false" | "no" | "0" => Value::Bool(false),
+            _ => return Err(invalid("a yes/no value")),
+        },
+        AttributeType::Integer => Value::Int(cell.parse().map_err(|_| invalid("an integer"))?),
+        AttributeType::BigInt => Value::Long(cell.parse().map_err(|_| invalid("an integer"))?),
+        AttributeType::Double => Value::Float(cell.parse().map_err(|_| invalid("a number"))?),
+        AttributeType::Decimal => Value::Decimal(parse_decimal(cell).ok_or(invalid("a number"))?),
+        AttributeType::Money => {
+            Value::Money(Money::new(parse_decimal(cell).ok_or(invalid("an amount"))?))
+        }
+        AttributeType::DateTime => Value::DateTime(parse_datetime(cell).ok_or(invalid("a date"))?),
+        AttributeType::Uniqueidentifier => {
+            Value::Guid(cell.parse().map_err(|_| invalid("a GUID"))?)
+        }
+        AttributeType::Picklist | AttributeType::State | AttributeType::Status => {
+            let value = option_value(metadata, attr, cell).ok_or(invalid("an option"))?;
+            Value::OptionSet(OptionSetValue::new(value))
+        }
+        AttributeType::MultiSelectPicklist => {
+            let values = cell
+                .split(';')
+                .map(|option| option_value(metadata, attr, option.trim()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(invalid("a list of options"))?;
+            Value::MultiOptionSet(MultiSelectOptionSetValue::new(values))
+        }
+        AttributeType::Lookup | AttributeType::Customer | AttributeType::Owner => {
+            lookup_value(column, cell).ok_or(invalid("a record id"))?
+        }
+        AttributeType::Image
+        | AttributeType::File
+        | AttributeType::Virtual
+        | AttributeType::PartyList
+        | AttributeType::ManagedProperty => {
+            return Err(format!(
+                "{:?} columns can't be imported",
+                attr.attribute_type
+            ));
+        }
+        AttributeType::String | AttributeType::Memo | AttributeType::EntityName => {
+            Value::String(cell.to_string())
+        }
+    };
+    Ok(value)
+}
+
+fn parse_decimal(cell: &str) -> Option<Decimal> {
+    cell.parse()
+        .ok()
+        .or_else(|| Decimal::from_scientific(cell).ok())
+}
+
+fn parse_datetime(cell: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(cell) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(cell, "%Y-%m-%d %H:%M:%S") {
+        return Some(datetime.and_utc());
+    }
+    NaiveDate::parse_from_str(cell, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+}
+
+/// Reads an option by value or label.
+fn option_value(metadata: &EntityMetadata, attr: &AttributeMetadata, cell: &str) -> Option<i32> {
+    if let Ok(value) = cell.parse() {
+        return Some(value);
+    }
+    let name = attr.logical_name.as_str();
+    let mut options: Vec<(i32, Option<&str>)> = Vec::new();
+    if let Some(option_set) = attr.option_set.as_ref().or(attr.global_option_set.as_ref()) {
+        options.extend(option_set.options.iter().map(|o| (o.value, o.label.text())));
+    }
+    for picklist in metadata
+        .picklist_attributes
+        .iter()
+        .filter(|p| p.logical_name == name)
+    {
+        let labels = picklist.option_set.options.iter();
+        options.extend(labels.map(|o| (o.value, o.label.text())));
+    }
+    for picklist in metadata
+        .multi_select_picklist_attributes
+        .iter()
+        .filter(|p| p.logical_name == name)
+    {
+        let labels = picklist.option_set.options.iter();
+        options.extend(labels.map(|o| (o.value, o.label.text())));
+    }
+    for state in metadata
+        .state_attributes
+        .iter()
+        .filter(|s| s.logical_name == name)
+    {
+        let labels = state.option_set.options.iter();
+        options.extend(labels.map(|o| (o.value, o.label.text())));
+    }
+    for status in metadata
+        .status_attributes
+        .iter()
+        .filter(|s| s.logical_name == name)
+    {
+        let labels = status.option_set.options.iter();
+        options.extend(labels.map(|o| (o.value, o.label.text())));
+    }
+    options
+        .into_iter()
+        .find(|(_, label)| label.is_some_and(|label| label.eq_ignore_ascii_case(cell)))
+        .map(|(value, _)| value)
+}
+
+/// Reads a lookup as `<id>` or `<target>:<id>`.
+fn lookup_value(column: &Column, cell: &str) -> Option<Value> {
+    let (target, id) = match cell.split_once(':') {
+        Some((target, id)) => (Some(target.trim()), id.trim()),
+        None => (None, cell),
+    };
+    let id: Uuid = id.parse().ok()?;
+    let entity_set = match target {
+        Some(target) => column
+            .targets
+            .iter()
+            .find(|(logical_name, _)| logical_name.eq_ignore_ascii_case(target))
+            .map(|(_, entity_set)| entity_set)?,
+        None => match column.targets.as_slice() {
+            [(_, entity_set)] => entity_set,
+            _ => return None,
+        },
+    };
+    Some(Value::EntityBinding(EntityBinding::new(
+        entity_set.clone(),
+        id,
+    )))
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    #[tokio::test]
+    async fn test_import_csv() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .attribute("numberofchildren", AttributeType::Integer)
+                    .attribute("birthdate", AttributeType::DateTime)
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        let account = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let taken = mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact").set("fullname", "Existing"),
+        );
+
+        let csv = format!(
+            "Name,contactid,NumberOfChildren,birthdate,parentcustomerid\n\
+             Ada,,2,1815-12-10,{account}\n\
+             Grace,,two,,\n\
+             Linus,{taken},,,\n\
+             Margaret,,0,1936-08-17 00:00:00,\n"
+        );
+        let report = client
+            .import_csv(Entity::logical("contact"), csv.as_bytes())
+            .map_column("Name", "fullname")
+            .chunk_size(2)
+            .await
+            .unwrap();
+
+        let created: Vec<usize> = report.created().map(|(row, _)| row).collect();
+        assert_eq!(created, [0, 3]);
+        let failed: Vec<usize> = report.failed().map(|r| r.row).collect();
+        assert_eq!(failed, [1, 2]);
+        assert!(matches!(
+            &report.rows()[1].outcome,
+            RowOutcome::Failed(message) if message.starts_with("numberofchildren:")
+        ));
+
+        let (_, ada) = report.created().next().unwrap();
+        let ada = mock.record(Entity::set("contacts"), ada).unwrap();
+        assert_eq!(ada.get_int("numberofchildren").unwrap(), Some(2));
+        assert_eq!(
+            ada.get_entity_reference("parentcustomerid")
+                .unwrap()
+                .map(|r| r.id),
+            Some(account)
+        );
+
+        // Resuming skips the rows already created
+        let csv = csv.replace("two", "3").replace(&taken.to_string(), "");
+        let resumed = client
+            .import_csv(Entity::logical("contact"), csv.as_bytes())
+            .map_column("Name", "fullname")
+            .resume(&report)
+            .await
+            .unwrap();
+        assert!(resumed.is_success());
+        assert_eq!(resumed.rows()[0], report.rows()[0]);
+        assert_eq!(mock.records(Entity::set("contacts")).len(), 5);
+
+        let json = serde_json::to_string(&resumed).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ImportReport>(&json).unwrap(),
+            resumed
+        );
+
+        let unknown = client
+            .import_csv(Entity::logical("contact"), "nope\n1\n".as_bytes())
+            .await;
+        assert!(matches!(
+            unknown,
+            Err(Error::Metadata(MetadataError::AttributeNotFound { .. }))
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+//! System jobs (async operations)
+//!
+//! Long-running work such as solution imports, bulk deletes and workflows
+//! runs in the background as `asyncoperation` records. [`AsyncOperation`]
+//! maps those records with typed state and status, and
+//! [`DataverseClient::wait_for_completion`] polls one until it finishes.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use dataverse_lib::api::jobs::{AsyncOperation, AsyncOperationStatus};
+//!
+//! let job = client
+//!     .wait_for_completion(job_id, Duration::from_secs(5))
+//!     .timeout(Duration::from_secs(600))
+//!     .await?;
+//! if job.status != AsyncOperationStatus::Succeeded {
+//!     eprintln!("{}", job.error_message().unwrap_or("job failed"));
+//! }
+//!
+//! // Failed jobs
+//! let failed: Vec<AsyncOperation> = client
+//!     .query(AsyncOperation::entity())
+//!     .filter(Filter::eq("statuscode", AsyncOperationStatus::Failed))
+//!     .into_typed::<AsyncOperation>()
+//!     .execute(&client)
+//!     .await?;
+//! ```
+
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::model::DataverseOptionSet;
+
+/// State of an async operation (`statecode`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum AsyncOperationState {
+    /// Waiting to run.
+    Ready = 0,
+    /// Paused, e.g. while waiting for a workflow step.
+    Suspended = 1,
+    /// Running.
+    Locked = 2,
+    /// Finished, successfully or not.
+    Completed = 3,
+}
+
+/// Status of an async operation (`statuscode`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum AsyncOperationStatus {
+    /// Ready, waiting for resources.
+    WaitingForResources = 0,
+    /// Suspended, waiting.
+    Waiting = 10,
+    /// Locked, running.
+    InProgress = 20,
+    /// Locked, pausing.
+    Pausing = 21,
+    /// Locked, being canceled.
+    Canceling = 22,
+    /// Completed successfully.
+    Succeeded = 30,
+    /// Completed with an error.
+    Failed = 31,
+    /// Completed by being canceled.
+    Canceled = 32,
+}
+
+/// A system job (`asyncoperation` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "asyncoperation", set = "asyncoperations")]
+pub struct AsyncOperation {
+    /// The job's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The job's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The kind of job (`operationtype`), e.g. `13` for bulk delete.
+    #[dataverse(rename = "operationtype", read_only)]
+    pub operation_type: Option<i32>,
+    /// The job's state.
+    #[dataverse(rename = "statecode", read_only)]
+    pub state: AsyncOperationState,
+    /// The job's status.
+    #[dataverse(rename = "statuscode", read_only)]
+    pub status: AsyncOperationStatus,
+    /// Technical details of the job's outcome.
+    #[dataverse(read_only)]
+    pub message: Option<String>,
+    /// The outcome as shown to users.
+    #[dataverse(rename = "friendlymessage", read_only)]
+    pub friendly_message: Option<String>,
+    /// When the job was created.
+    #[dataverse(rename = "createdon", read_only)]
+    pub created_on: Option<DateTime<Utc>>,
+    /// When the job started running.
+    #[dataverse(rename = "startedon", read_only)]
+    pub started_on: Option<DateTime<Utc>>,
+    /// When the job finished.
+    #[dataverse(rename = "completedon", read_only)]
+    pub completed_on: Option<DateTime<Utc>>,
+}
+
+impl AsyncOperation {
+    /// Returns whether the job has finished, successfully or not.
+    pub fn is_completed(&self) -> bool {
+        self.state == AsyncOperationState::Completed
+    }
+
+    /// Returns whether the job finished successfully.
+    pub fn succeeded(&self) -> bool {
+        self.status == AsyncOperationStatus::Succeeded
+    }
+
+    /// Returns the job's error, preferring the user-facing message.
+    ///
+    /// `None` unless the job failed.
+    pub fn error_message(&self) -> Option<&str> {
+        if self.status != AsyncOperationStatus::Failed {
+            return None;
+        }
+        self.friendly_message.as_deref().or(self.message.as_deref())
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Retrieves a system job.
+    pub async fn async_operation(&self, id: Uuid) -> Result<AsyncOperation, Error> {
+        let record = self
+            .retrieve(AsyncOperation::entity(), id)
+            .select(AsyncOperation::COLUMNS)
+            .await?
+            .into_inner();
+        Ok(AsyncOperation::from_record(&record)?)
+    }
+
+    /// Polls a system job until it completes, returning its final state.
+    ///
+    /// A job that fails or is canceled still completes; check
+    /// [`AsyncOperation::succeeded`]. Waits indefinitely unless a
+    /// [`timeout`](WaitForCompletionBuilder::timeout) is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The job's id
+    /// * `poll_interval` - How long to wait between polls
+    pub fn wait_for_completion(
+        &self,
+        id: Uuid,
+        poll_interval: Duration,
+    ) -> WaitForCompletionBuilder<'_> {
+        WaitForCompletionBuilder {
+            client: self,
+            id,
+            poll_interval,
+            timeout: None,
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for waiting on a system job, bound to a client.
+pub struct WaitForCompletionBuilder<'a> {
+    client: &'a DataverseClient,
+    id: Uuid,
+    poll_interval: Duration,
+    timeout: Option<Duration>,
+}
+
+impl<'a> WaitForCompletionBuilder<'a> {
+    /// Fails with [`ApiError::Timeout`] if the job hasn't completed within
+    /// `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    async fn execute(self) -> Result<AsyncOperation, Error> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let job = self.client.async_operation(self.id).await?;
+            if job.is_completed() {
+                return Ok(job);
+            }
+            let mut wait = self.poll_interval;
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(Error::Api(ApiError::Timeout(
+                        self.timeout.unwrap_or_default(),
+                    )));
+                }
+                wait = wait.min(left);
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl<'a> std::future::IntoFuture for WaitForCompletionBuilder<'a> {
+    type Output = Result<AsyncOperation, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    fn job(state: AsyncOperationState, status: AsyncOperationStatus) -> Record {
+        Record::new("asyncoperation")
+            .set("name", "Bulk delete")
+            .set("operationtype", 13)
+            .set("statecode", state)
+            .set("statuscode", status)
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_completion() {
+        let mock = MockDataverse::new().entity(MockEntity::new(
+            "asyncoperation",
+            "asyncoperations",
+            "asyncoperationid",
+        ));
+        let client = mock.client();
+        let id = mock.insert(
+            Entity::set("asyncoperations"),
+            job(
+                AsyncOperationState::Ready,
+                AsyncOperationStatus::WaitingForResources,
+            ),
+        );
+
+        let pending = client
+            .wait_for_completion(id, Duration::from_millis(5))
+            .timeout(Duration::from_millis(20))
+            .await;
+        assert!(matches!(pending, Err(Error::Api(ApiError::Timeout(_)))));
+
+        let updater = mock.client();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            updater
+                .update(
+                    Entity::set("asyncoperations"),
+                    id,
+                    Record::new("asyncoperation")
+                        .set("statecode", AsyncOperationState::Completed)
+                        .set("statuscode", AsyncOperationStatus::Failed)
+                        .set("friendlymessage", "Access denied"),
+                )
+                .await
+                .unwrap();
+        });
+        let job = client
+            .wait_for_completion(id, Duration::from_millis(5))
+            .timeout(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(job.id, id);
+        assert_eq!(job.name.as_deref(), Some("Bulk delete"));
+        assert_eq!(job.operation_type, Some(13));
+        assert!(job.is_completed());
+        assert!(!job.succeeded());
+        assert_eq!(job.error_message(), Some("Access denied"));
+    }
+}
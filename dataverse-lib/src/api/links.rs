@@ -0,0 +1,153 @@
+//! Model-driven app deep links
+//!
+//! [`Links`] builds `main.aspx` URLs that open a record, a view or a create
+//! form in the browser, optionally inside a specific app. Get one for the
+//! client's environment with [`DataverseClient::links`], or from any
+//! environment URL with [`Links::new`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let links = client.links().app(sales_hub);
+//! let url = links.record("account", account_id);
+//! let url = links.view("account", ViewReference::System(active_accounts));
+//! let url = links.create("contact", &[("lastname", "Smith"), ("parentcustomerid", &id)]);
+//! ```
+
+use url::form_urlencoded::Serializer;
+use uuid::Uuid;
+
+use super::ViewReference;
+use crate::DataverseClient;
+
+/// `viewtype` of system views (`savedquery`).
+const SYSTEM_VIEW: &str = "1039";
+
+/// `viewtype` of personal views (`userquery`).
+const PERSONAL_VIEW: &str = "4230";
+
+/// Builds deep links into model-driven apps.
+///
+/// Entities are given by logical name. Values are URL-encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Links {
+    base_url: String,
+    app_id: Option<Uuid>,
+}
+
+impl Links {
+    /// Creates a link builder for an environment URL
+    /// (e.g. `https://org.crm.dynamics.com`).
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            app_id: None,
+        }
+    }
+
+    /// Opens links in an app (`appid`). Without one, Dataverse opens the
+    /// user's default app.
+    pub fn app(mut self, app_id: Uuid) -> Self {
+        self.app_id = Some(app_id);
+        self
+    }
+
+    /// Returns a link opening a record's main form.
+    pub fn record(&self, entity: &str, id: Uuid) -> String {
+        self.url(&[
+            ("pagetype", "entityrecord"),
+            ("etn", entity),
+            ("id", &id.to_string()),
+        ])
+    }
+
+    /// Returns a link opening a view of a table.
+    pub fn view(&self, entity: &str, view: ViewReference) -> String {
+        let view_type = match view {
+            ViewReference::System(_) => SYSTEM_VIEW,
+            ViewReference::Personal(_) => PERSONAL_VIEW,
+        };
+        self.url(&[
+            ("pagetype", "entitylist"),
+            ("etn", entity),
+            ("viewid", &view.id().to_string()),
+            ("viewType", view_type),
+        ])
+    }
+
+    /// Returns a link opening a table's create form, with fields prefilled
+    /// from `defaults` (`extraqs`).
+    ///
+    /// Lookups are prefilled with their id; Dataverse resolves the name.
+    pub fn create(&self, entity: &str, defaults: &[(&str, &str)]) -> String {
+        if defaults.is_empty() {
+            return self.url(&[("pagetype", "entityrecord"), ("etn", entity)]);
+        }
+        let extra = Serializer::new(String::new())
+            .extend_pairs(defaults)
+            .finish();
+        self.url(&[
+            ("pagetype", "entityrecord"),
+            ("etn", entity),
+            ("extraqs", &extra),
+        ])
+    }
+
+    /// Builds a `main.aspx` URL.
+    fn url(&self, params: &[(&str, &str)]) -> String {
+        let mut query = Serializer::new(String::new());
+        if let Some(app_id) = self.app_id {
+            query.append_pair("appid", &app_id.to_string());
+        }
+        query.extend_pairs(params);
+        format!("{}/main.aspx?{}", self.base_url, query.finish())
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Returns a builder for deep links into the environment's apps.
+    pub fn links(&self) -> Links {
+        Links::new(self.base_url())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links() {
+        let id = Uuid::from_u128(1);
+        let links = Links::new("https://org.crm.dynamics.com/");
+        assert_eq!(
+            links.record("account", id),
+            "https://org.crm.dynamics.com/main.aspx?pagetype=entityrecord&etn=account\
+             &id=00000000-0000-0000-0000-000000000001"
+        );
+        assert_eq!(
+            links.create("account", &[]),
+            "https://org.crm.dynamics.com/main.aspx?pagetype=entityrecord&etn=account"
+        );
+
+        let links = links.app(Uuid::from_u128(2));
+        assert_eq!(
+            links.view("account", ViewReference::Personal(id)),
+            "https://org.crm.dynamics.com/main.aspx?appid=00000000-0000-0000-0000-000000000002\
+             &pagetype=entitylist&etn=account&viewid=00000000-0000-0000-0000-000000000001\
+             &viewType=4230"
+        );
+        assert_eq!(
+            links.create(
+                "contact",
+                &[("lastname", "O'Neil & Sons"), ("jobtitle", "a=b")]
+            ),
+            "https://org.crm.dynamics.com/main.aspx?appid=00000000-0000-0000-0000-000000000002\
+             &pagetype=entityrecord&etn=contact\
+             &extraqs=lastname%3DO%2527Neil%2B%2526%2BSons%26jobtitle%3Da%253Db"
+        );
+    }
+}
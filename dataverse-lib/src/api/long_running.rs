@@ -0,0 +1,409 @@
+//! Long-running operations
+//!
+//! Some requests don't finish before they're answered: they return
+//! `202 Accepted` with an `Operation-Location` or `Location` header pointing
+//! at a status URL. [`DataverseClient::poll_operation`] polls such a URL
+//! until the operation completes, backing off between polls and reporting
+//! progress where the service provides it. Actions and custom APIs can do
+//! this themselves with
+//! [`long_running`](crate::api::ClientActionBuilder::long_running).
+//!
+//! Status responses count as in progress while they are `202 Accepted` or
+//! carry a `status` of `NotStarted`, `Running` or `InProgress`. A `status` of
+//! `Failed` or `Canceled` fails the poll; anything else is the result.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use dataverse_lib::api::long_running::PollOptions;
+//!
+//! let result = client
+//!     .action("contoso_RebuildIndex")
+//!     .long_running(
+//!         PollOptions::new()
+//!             .timeout(Duration::from_secs(600))
+//!             .on_progress(|p| println!("{:?}% after {} polls", p.percent_complete, p.polls)),
+//!     )
+//!     .await?;
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::Url;
+use serde_json::Value as Json;
+use tokio::time::Instant;
+
+use super::execute::parse_retry_after;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+
+/// Default wait before the first poll.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default upper bound for the wait between polls.
+const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Headers naming an operation's status URL, in order of preference.
+const LOCATION_HEADERS: [&str; 2] = ["Operation-Location", "Location"];
+
+/// Callback receiving progress reports.
+type ProgressCallback = Arc<dyn Fn(&OperationProgress) + Send + Sync>;
+
+/// Progress of a long-running operation, reported after each poll that finds
+/// it still running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationProgress {
+    /// The `status` the service reported, if any.
+    pub status: Option<String>,
+    /// How far along the operation is, from 0 to 100, if reported.
+    pub percent_complete: Option<f64>,
+    /// How many times the status URL has been polled.
+    pub polls: u32,
+    /// Time since polling started.
+    pub elapsed: Duration,
+}
+
+/// How a long-running operation is polled.
+///
+/// The wait between polls starts at the
+/// [`interval`](PollOptions::interval) and doubles up to the
+/// [`max_interval`](PollOptions::max_interval), unless the service sends a
+/// `Retry-After`.
+#[derive(Clone)]
+pub struct PollOptions {
+    interval: Duration,
+    max_interval: Duration,
+    timeout: Option<Duration>,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl PollOptions {
+    /// Creates options polling after 1 second, backing off to 30 seconds,
+    /// without a timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the wait before the first poll.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the upper bound for the wait between polls.
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Fails with [`ApiError::Timeout`] if the operation hasn't completed
+    /// within `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Calls `callback` after each poll that finds the operation running.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&OperationProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            max_interval: DEFAULT_MAX_INTERVAL,
+            timeout: None,
+            on_progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for PollOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollOptions")
+            .field("interval", &self.interval)
+            .field("max_interval", &self.max_interval)
+            .field("timeout", &self.timeout)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+/// Returns the status URL of an accepted long-running operation, or `None`
+/// if the response isn't one.
+pub(crate) fn operation_location(response: &reqwest::Response) -> Option<String> {
+    if response.status() != StatusCode::ACCEPTED {
+        return None;
+    }
+    LOCATION_HEADERS.iter().find_map(|name| {
+        response
+            .headers()
+            .get(*name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    })
+}
+
+/// State of an operation, from a status response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Running,
+    Failed,
+    Done,
+}
+
+/// Classifies a status response by its HTTP status and `status` property.
+fn state(status: StatusCode, body: &Json) -> State {
+    let reported = body
+        .get("status")
+        .and_then(Json::as_str)
+        .map(str::to_ascii_lowercase);
+    match reported.as_deref() {
+        _ if status == StatusCode::ACCEPTED => State::Running,
+        Some("notstarted" | "running" | "inprogress") => State::Running,
+        Some("failed" | "canceled" | "cancelled") => State::Failed,
+        _ => State::Done,
+    }
+}
+
+/// Reads the progress a status response reports.
+fn percent_complete(body: &Json) -> Option<f64> {
+    ["percentComplete", "PercentComplete", "progress"]
+        .iter()
+        .find_map(|name| body.get(*name).and_then(Json::as_f64))
+}
+
+/// Reads the error message of a failed operation.
+fn failure_message(body: &Json) -> String {
+    let error = body.get("error").unwrap_or(body);
+    error
+        .get("message")
+        .and_then(Json::as_str)
+        .map(String::from)
+        .unwrap_or_else(|| body.to_string())
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Polls a long-running operation's status URL until it completes,
+    /// returning the final response body (`Json::Null` if empty).
+    ///
+    /// Relative URLs are resolved against the environment URL.
+    pub fn poll_operation(&self, location: impl Into<String>) -> PollOperationBuilder<'_> {
+        PollOperationBuilder {
+            client: self,
+            location: location.into(),
+            options: PollOptions::default(),
+        }
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for polling a long-running operation, bound to a client.
+pub struct PollOperationBuilder<'a> {
+    client: &'a DataverseClient,
+    location: String,
+    options: PollOptions,
+}
+
+impl<'a> PollOperationBuilder<'a> {
+    /// Replaces all polling options.
+    pub fn options(mut self, options: PollOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the wait before the first poll.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.options = self.options.interval(interval);
+        self
+    }
+
+    /// Sets the upper bound for the wait between polls.
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.options = self.options.max_interval(max_interval);
+        self
+    }
+
+    /// Fails with [`ApiError::Timeout`] if the operation hasn't completed
+    /// within `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.timeout(timeout);
+        self
+    }
+
+    /// Calls `callback` after each poll that finds the operation running.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&OperationProgress) + Send + Sync + 'static,
+    {
+        self.options = self.options.on_progress(callback);
+        self
+    }
+
+    /// Polls until the operation completes.
+    pub async fn execute(self) -> Result<Json, Error> {
+        let client = self.client;
+        let options = self.options;
+        let url = Url::parse(client.base_url())
+            .and_then(|base| base.join(&self.location))
+            .map_err(|e| {
+                Error::InvalidOperation(format!(
+                    "invalid operation location '{}': {}",
+                    self.location, e
+                ))
+            })?;
+
+        let started = Instant::now();
+        let deadline = options.timeout.map(|timeout| started + timeout);
+        let mut interval = options.interval;
+        let mut wait = interval;
+        let mut polls = 0;
+        loop {
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(Error::Api(ApiError::Timeout(
+                        options.timeout.unwrap_or_default(),
+                    )));
+                }
+                wait = wait.min(left);
+            }
+            tokio::time::sleep(wait).await;
+
+            let response = client
+                .request(Method::GET, url.as_str(), client.default_headers(), None)
+                .await?;
+            polls += 1;
+            let status = response.status();
+            let retry_after = parse_retry_after(&response);
+            let body = response.text().await.map_err(ApiError::from)?;
+            let mut body = if body.trim().is_empty() {
+                Json::Null
+            } else {
+                serde_json::from_str(&body)?
+            };
+
+            match state(status, &body) {
+                State::Running => {
+                    if let Some(callback) = &options.on_progress {
+                        callback(&OperationProgress {
+                            status: body.get("status").and_then(Json::as_str).map(String::from),
+                            percent_complete: percent_complete(&body),
+                            polls,
+                            elapsed: started.elapsed(),
+                        });
+                    }
+                    interval = (interval * 2).min(options.max_interval);
+                    wait = retry_after.unwrap_or(interval);
+                }
+                State::Failed => {
+                    return Err(Error::InvalidOperation(format!(
+                        "long-running operation failed: {}",
+                        failure_message(&body)
+                    )));
+                }
+                State::Done => {
+                    if let Some(object) = body.as_object_mut() {
+                        object.remove("@odata.context");
+                    }
+                    return Ok(body);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> std::future::IntoFuture for PollOperationBuilder<'a> {
+    type Output = Result<Json, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_operation_state() {
+        assert_eq!(state(StatusCode::ACCEPTED, &Json::Null), State::Running);
+        assert_eq!(
+            state(StatusCode::OK, &json!({ "status": "Running" })),
+            State::Running
+        );
+        assert_eq!(
+            state(
+                StatusCode::OK,
+                &json!({ "status": "Failed", "error": { "message": "Boom" } })
+            ),
+            State::Failed
+        );
+        assert_eq!(
+            state(StatusCode::OK, &json!({ "status": "Succeeded" })),
+            State::Done
+        );
+        assert_eq!(state(StatusCode::NO_CONTENT, &Json::Null), State::Done);
+
+        assert_eq!(
+            failure_message(&json!({ "status": "Failed", "error": { "message": "Boom" } })),
+            "Boom"
+        );
+        assert_eq!(
+            percent_complete(&json!({ "percentComplete": 40 })),
+            Some(40.0)
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_long_running_action_is_polled() {
+        use std::sync::Mutex;
+
+        use crate::mock::MockDataverse;
+
+        let mock = MockDataverse::new();
+        mock.long_running_action("contoso_Rebuild", 2, json!({ "Rebuilt": 12 }));
+        let client = mock.client();
+
+        // Without polling, the call returns as soon as it's accepted
+        let accepted = client.action("contoso_Rebuild").await.unwrap();
+        assert_eq!(accepted, Json::Null);
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let seen = progress.clone();
+        let result = client
+            .action("contoso_Rebuild")
+            .long_running(
+                PollOptions::new()
+                    .interval(Duration::from_millis(1))
+                    .on_progress(move |p| seen.lock().unwrap().push(p.percent_complete)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "Rebuilt": 12 }));
+        assert_eq!(*progress.lock().unwrap(), [Some(33.0), Some(66.0)]);
+    }
+}
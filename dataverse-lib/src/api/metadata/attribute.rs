@@ -190,6 +190,7 @@ async fn fetch_attribute_metadata_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -229,6 +230,7 @@ async fn fetch_attributes_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
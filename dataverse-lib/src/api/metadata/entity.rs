@@ -208,6 +208,19 @@ impl<'a> IntoFuture for AllEntitiesBuilder<'a> {
 // Internal fetch functions
 // =============================================================================
 
+/// Returns an entity's full metadata if it is already cached, without
+/// fetching it.
+pub(crate) async fn cached_entity_metadata(
+    client: &DataverseClient,
+    logical_name: &str,
+) -> Option<EntityMetadata> {
+    let cache = client.inner.cache.as_ref()?;
+    let cached = cache
+        .get(&format!("{}{}", CACHE_KEY_ENTITY_FULL, logical_name))
+        .await?;
+    cache::deserialize(&cached.data).ok()
+}
+
 /// Fetches minimal entity metadata (EntityCore) - used internally for resolution.
 pub(crate) async fn fetch_entity_core(
     client: &DataverseClient,
@@ -270,6 +283,7 @@ async fn fetch_entity_core_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -376,6 +390,7 @@ async fn fetch_base_entity_metadata(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -412,6 +427,7 @@ async fn fetch_state_attributes(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -453,6 +469,7 @@ async fn fetch_status_attributes(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -494,6 +511,7 @@ async fn fetch_picklist_attributes(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -535,6 +553,7 @@ async fn fetch_multi_select_picklist_attributes(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -572,6 +591,7 @@ async fn fetch_all_entities_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -0,0 +1,149 @@
+//! Entity key (alternate key) metadata builders
+
+use std::future::Future;
+use std::future::IntoFuture;
+use std::pin::Pin;
+
+use reqwest::Method;
+
+use super::CACHE_KEY_KEYS;
+use super::metadata_request;
+use super::metadata_url;
+use crate::DataverseClient;
+use crate::cache::{self, CachedValue};
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::error::MetadataError;
+use crate::model::Entity;
+use crate::model::metadata::EntityKeyMetadata;
+
+// =============================================================================
+// EntityKeysBuilder
+// =============================================================================
+
+/// Builder for fetching the alternate keys of an entity.
+pub struct EntityKeysBuilder<'a> {
+    client: &'a DataverseClient,
+    entity: Entity,
+    bypass_cache: bool,
+}
+
+impl<'a> EntityKeysBuilder<'a> {
+    pub(crate) fn new(client: &'a DataverseClient, entity: Entity) -> Self {
+        Self {
+            client,
+            entity,
+            bypass_cache: false,
+        }
+    }
+
+    /// Bypass the cache and fetch directly from the API.
+    pub fn bypass_cache(mut self) -> Self {
+        self.bypass_cache = true;
+        self
+    }
+
+    /// Execute the request.
+    pub async fn execute(self) -> Result<Vec<EntityKeyMetadata>, Error> {
+        let logical_name = self
+            .client
+            .resolve_entity_logical_name(&self.entity)
+            .await?;
+        let cache_key = format!("{}{}", CACHE_KEY_KEYS, logical_name);
+
+        // Check cache first (unless bypassed)
+        if !self.bypass_cache
+            && let Some(cache) = &self.client.inner.cache
+            && let Some(cached) = cache.get(&cache_key).await
+            && let Ok(keys) = cache::deserialize::<Vec<EntityKeyMetadata>>(&cached.data)
+        {
+            return Ok(keys);
+        }
+
+        // Fetch from API
+        let keys = fetch_keys_from_api(self.client, &logical_name).await?;
+
+        // Cache the result
+        if let Some(cache) = &self.client.inner.cache {
+            let ttl = self.client.inner.cache_config.entity_metadata_ttl;
+            if let Ok(data) = cache::serialize(&keys) {
+                cache
+                    .set(&cache_key, CachedValue::with_ttl(data, ttl))
+                    .await;
+            }
+        }
+
+        Ok(keys)
+    }
+}
+
+impl<'a> IntoFuture for EntityKeysBuilder<'a> {
+    type Output = Result<Vec<EntityKeyMetadata>, Error>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+// =============================================================================
+// Internal fetch functions
+// =============================================================================
+
+/// Returns an entity's alternate keys if they are cached, without fetching.
+pub(crate) async fn cached_entity_keys(
+    client: &DataverseClient,
+    logical_name: &str,
+) -> Option<Vec<EntityKeyMetadata>> {
+    let cache = client.inner.cache.as_ref()?;
+    let cached = cache
+        .get(&format!("{}{}", CACHE_KEY_KEYS, logical_name))
+        .await?;
+    cache::deserialize(&cached.data).ok()
+}
+
+/// Fetches the alternate keys of an entity from the API.
+async fn fetch_keys_from_api(
+    client: &DataverseClient,
+    entity: &str,
+) -> Result<Vec<EntityKeyMetadata>, Error> {
+    let url = metadata_url(
+        client,
+        &format!("EntityDefinitions(LogicalName='{}')/Keys", entity),
+    );
+
+    let response = metadata_request(client, Method::GET, &url).await?;
+
+    if response.status().as_u16() == 404 {
+        return Err(Error::Metadata(MetadataError::EntityNotFound {
+            name: entity.to_string(),
+        }));
+    }
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Api(ApiError::Http {
+            status,
+            message: body,
+            code: None,
+            inner: None,
+            raw: None,
+        }));
+    }
+
+    // The response is wrapped in a "value" array
+    #[derive(serde::Deserialize)]
+    struct Response {
+        value: Vec<EntityKeyMetadata>,
+    }
+
+    let resp: Response = response.json().await.map_err(|e| {
+        Error::Api(ApiError::Parse {
+            message: format!("Failed to parse EntityKeyMetadata list: {}", e),
+            body: None,
+        })
+    })?;
+
+    Ok(resp.value)
+}
@@ -17,10 +17,14 @@
 //!
 //! // Fetch a single relationship
 //! let rel = client.metadata().relationship("contact_customer_accounts").await?;
+//!
+//! // Fetch the alternate keys of an entity
+//! let keys = client.metadata().keys("account").await?;
 //! ```
 
 mod attribute;
 pub(crate) mod entity;
+pub(crate) mod key;
 mod option_set;
 mod relationship;
 
@@ -28,6 +32,7 @@ pub use attribute::AttributeMetadataBuilder;
 pub use attribute::AttributesBuilder;
 pub use entity::AllEntitiesBuilder;
 pub use entity::EntityMetadataBuilder;
+pub use key::EntityKeysBuilder;
 pub use option_set::AllGlobalOptionSetsBuilder;
 pub use option_set::GlobalOptionSetBuilder;
 pub use relationship::RelationshipMetadataBuilder;
@@ -40,7 +45,10 @@ use reqwest::header::HeaderValue;
 use crate::DataverseClient;
 use crate::error::ApiError;
 use crate::error::Error;
+use crate::middleware::RequestInfo;
 use crate::model::Entity;
+use crate::telemetry::InSpan;
+use crate::telemetry::span;
 
 /// Cache key prefix for minimal entity metadata (EntityCore).
 pub const CACHE_KEY_ENTITY_CORE: &str = "entity_core:";
@@ -54,6 +62,9 @@ pub const CACHE_KEY_ATTRIBUTE: &str = "attribute:";
 /// Cache key prefix for all attributes of an entity.
 pub const CACHE_KEY_ATTRIBUTES: &str = "attributes:";
 
+/// Cache key prefix for the alternate keys of an entity.
+pub const CACHE_KEY_KEYS: &str = "keys:";
+
 /// Cache key prefix for single relationship metadata.
 pub const CACHE_KEY_RELATIONSHIP: &str = "relationship:";
 
@@ -149,6 +160,24 @@ impl<'a> MetadataClient<'a> {
         AttributesBuilder::new(self.client, entity.into())
     }
 
+    // === Keys ===
+
+    /// Fetches the alternate keys defined on an entity.
+    ///
+    /// Once an entity's keys are cached, operations addressing its records
+    /// by alternate key check that the columns match an active key before
+    /// sending the request.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let keys = client.metadata().keys("account").await?;
+    /// let active: Vec<_> = keys.iter().filter(|k| k.is_active()).collect();
+    /// ```
+    pub fn keys(&self, entity: impl Into<Entity>) -> EntityKeysBuilder<'a> {
+        EntityKeysBuilder::new(self.client, entity.into())
+    }
+
     // === Relationship ===
 
     /// Fetches metadata for a relationship by schema name.
@@ -199,6 +228,42 @@ impl<'a> MetadataClient<'a> {
         AllGlobalOptionSetsBuilder::new(self.client)
     }
 
+    // === Labels ===
+
+    /// Returns an entity's display name in the client's language.
+    ///
+    /// See [`DataverseClientBuilder::language`](crate::DataverseClientBuilder::language).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let name = client.metadata().display_name("account").await?;
+    /// ```
+    pub async fn display_name(&self, entity: impl Into<Entity>) -> Result<String, Error> {
+        let metadata = self.entity(entity).await?;
+        Ok(metadata.display_name(self.client.language()).to_string())
+    }
+
+    /// Returns the label of a choice attribute's option in the client's
+    /// language, or `None` if the attribute has no such option.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let label = client.metadata().option_label("account", "industrycode", 1).await?;
+    /// ```
+    pub async fn option_label(
+        &self,
+        entity: impl Into<Entity>,
+        attribute: &str,
+        value: i32,
+    ) -> Result<Option<String>, Error> {
+        let metadata = self.entity(entity).await?;
+        Ok(metadata
+            .option_label(attribute, value, self.client.language())
+            .map(str::to_string))
+    }
+
     // === Cache Control ===
 
     /// Invalidates cached metadata for an entity.
@@ -229,6 +294,14 @@ impl<'a> MetadataClient<'a> {
         }
     }
 
+    /// Invalidates the cached alternate keys of an entity.
+    pub async fn invalidate_keys(&self, entity: &str) {
+        if let Some(cache) = &self.client.inner.cache {
+            let cache_key = format!("{}{}", CACHE_KEY_KEYS, entity);
+            cache.remove(&cache_key).await;
+        }
+    }
+
     /// Invalidates cached metadata for a relationship.
     pub async fn invalidate_relationship(&self, schema_name: &str) {
         if let Some(cache) = &self.client.inner.cache {
@@ -268,29 +341,34 @@ pub(crate) async fn metadata_request(
     method: Method,
     url: &str,
 ) -> Result<reqwest::Response, Error> {
-    let token = client
-        .inner
-        .token_provider
-        .get_token(&client.inner.base_url)
-        .await?;
+    let span = span!("dataverse.metadata", method = %method, url);
+    let observed = method.clone();
+    client
+        .observe(
+            &observed,
+            url,
+            metadata_request_inner(client, method, url).in_span(span),
+        )
+        .await
+}
+
+async fn metadata_request_inner(
+    client: &DataverseClient,
+    method: Method,
+    url: &str,
+) -> Result<reqwest::Response, Error> {
+    let token = client.access_token().await?;
 
     let mut headers = HeaderMap::new();
     headers.insert("OData-MaxVersion", HeaderValue::from_static("4.0"));
     headers.insert("OData-Version", HeaderValue::from_static("4.0"));
     headers.insert("Accept", HeaderValue::from_static("application/json"));
 
-    let mut request = client
-        .inner
-        .http_client
-        .request(method, url)
-        .headers(headers)
-        .bearer_auth(&token.access_token);
-
-    if let Some(timeout) = client.inner.timeout {
-        request = request.timeout(timeout);
-    }
-
-    let response = request.send().await.map_err(ApiError::from)?;
+    let request = RequestInfo::new(method, url, headers)?;
+    let response = client
+        .send(request, None, &token.access_token)
+        .await
+        .map_err(ApiError::from)?;
     Ok(response)
 }
 
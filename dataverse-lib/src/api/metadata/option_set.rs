@@ -177,6 +177,7 @@ async fn fetch_global_option_set_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -206,6 +207,7 @@ async fn fetch_all_global_option_sets_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
@@ -182,6 +182,7 @@ async fn fetch_relationship_from_api(
             message: body,
             code: None,
             inner: None,
+            raw: None,
         }));
     }
 
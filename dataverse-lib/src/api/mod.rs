@@ -1,22 +1,65 @@
 //! Web API operations
 
+mod access;
+mod action;
 mod aggregate;
+mod annotation;
 mod association;
-mod async_operation;
 mod audit;
 mod batch;
+mod bulk;
+mod capabilities;
+mod clone;
+mod concurrency;
 mod crud;
+mod custom_api;
+mod documents;
+mod email;
 mod execute;
+mod field_security;
+mod file;
 mod forms;
+mod health;
+pub mod import;
+pub mod jobs;
+mod links;
+pub mod long_running;
 mod metadata;
 mod options;
+mod organization;
+mod passthrough;
+pub(crate) mod plugin_trace;
 pub mod query;
+mod queue;
 pub mod schema;
+pub mod search;
+mod solutions;
+mod statistics;
+mod teams;
 mod views;
 
+pub use access::*;
+pub use action::*;
 pub use aggregate::*;
+pub use annotation::*;
 pub use audit::*;
 pub use batch::*;
+pub use bulk::*;
+pub use capabilities::*;
+pub use clone::*;
 pub use crud::*;
+pub use custom_api::*;
+pub use documents::*;
+pub use email::*;
 pub use execute::*;
+pub use field_security::*;
+pub use file::*;
+pub use health::*;
+pub use links::*;
 pub use metadata::*;
+pub use organization::*;
+pub use plugin_trace::*;
+pub use queue::*;
+pub use solutions::*;
+pub use statistics::*;
+pub use teams::*;
@@ -0,0 +1,289 @@
+//! The calling user and their organization
+//!
+//! `WhoAmI` identifies the calling user, their business unit and the
+//! organization; [`DataverseClient::organization`] adds the organization's
+//! name, version and base currency, and [`DataverseClient::user_time_zone`]
+//! the user's time zone. [`DataverseClient::currency_converter`] combines the
+//! base currency with the exchange rates of the active currencies.
+
+use uuid::Uuid;
+
+use super::query::Filter;
+use crate::DataverseClient;
+use crate::WhoAmIResponse;
+use crate::error::Error;
+use crate::model::Currency;
+use crate::model::CurrencyConverter;
+use crate::model::DateTimeConverter;
+use crate::model::Entity;
+use crate::model::UserTimeZone;
+
+/// Details of the organization (environment) a client is connected to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrganizationDetail {
+    /// The organization's id.
+    pub id: Uuid,
+    /// The organization's name.
+    pub name: Option<String>,
+    /// The Dataverse version (e.g. `9.2.24083.180`).
+    pub version: String,
+    /// The base currency's id.
+    pub base_currency_id: Option<Uuid>,
+    /// The base currency's name.
+    pub base_currency_name: Option<String>,
+    /// The base language code (e.g. `1033`).
+    pub language_code: Option<i32>,
+}
+
+impl DataverseClient {
+    /// Returns the calling user, their business unit and the organization,
+    /// using the `WhoAmI` function.
+    pub async fn who_am_i(&self) -> Result<WhoAmIResponse, Error> {
+        self.function("WhoAmI").execute_as().await
+    }
+
+    /// Returns details of the organization the client is connected to.
+    ///
+    /// Combines `WhoAmI`, `RetrieveVersion` and the `organization` record.
+    pub async fn organization(&self) -> Result<OrganizationDetail, Error> {
+        let who_am_i = self.who_am_i().await?;
+        let version = self.server_version().await?;
+        let organization = self
+            .retrieve(Entity::logical("organization"), who_am_i.organization_id)
+            .select(&["name", "_basecurrencyid_value", "languagecode"])
+            .await?
+            .into_inner();
+        let base_currency = organization.lookup_info("basecurrencyid");
+
+        Ok(OrganizationDetail {
+            id: who_am_i.organization_id,
+            name: organization
+                .get_string("name")
+                .ok()
+                .flatten()
+                .map(str::to_string),
+            version: version.to_string(),
+            base_currency_id: base_currency.as_ref().and_then(|info| info.id),
+            base_currency_name: base_currency.and_then(|info| info.formatted_value),
+            language_code: organization.get_int("languagecode").ok().flatten(),
+        })
+    }
+
+    /// Returns the calling user's time zone, from their `usersettings`.
+    pub async fn user_time_zone(&self) -> Result<UserTimeZone, Error> {
+        let who_am_i = self.who_am_i().await?;
+        let settings = self
+            .retrieve(Entity::set("usersettingscollection"), who_am_i.user_id)
+            .select(&UserTimeZone::COLUMNS)
+            .await?
+            .into_inner();
+        Ok(UserTimeZone::from_user_settings(&settings))
+    }
+
+    /// Returns a converter reading and writing an entity's date/time fields
+    /// in the calling user's time zone, following each attribute's
+    /// `DateTimeBehavior`.
+    pub async fn datetime_converter(&self, entity: Entity) -> Result<DateTimeConverter, Error> {
+        let metadata = self.metadata().entity(entity).await?;
+        let time_zone = self.user_time_zone().await?;
+        Ok(DateTimeConverter::from_metadata(time_zone, &metadata))
+    }
+
+    /// Returns a converter writing an entity's money fields in the records'
+    /// transaction currency, using the exchange rates of the organization's
+    /// active currencies.
+    pub async fn currency_converter(&self, entity: Entity) -> Result<CurrencyConverter, Error> {
+        let metadata = self.metadata().entity(entity).await?;
+        let organization = self.organization().await?;
+        let Some(base_currency) = organization.base_currency_id else {
+            return Err(Error::InvalidOperation(
+                "The organization has no base currency".to_string(),
+            ));
+        };
+
+        let mut converter = CurrencyConverter::from_metadata(base_currency, &metadata);
+        let mut pages = self
+            .query(Entity::logical("transactioncurrency"))
+            .select(&[
+                "transactioncurrencyid",
+                "isocurrencycode",
+                "exchangerate",
+                "currencyprecision",
+            ])
+            .filter(Filter::eq("statecode", 0))
+            .into_async_iter(self);
+        while let Some(page) = pages.next(self).await {
+            for record in page?.records() {
+                let Some(id) = record.id() else {
+                    continue;
+                };
+                converter = converter.currency(Currency {
+                    id,
+                    iso_code: record
+                        .get_string("isocurrencycode")
+                        .ok()
+                        .flatten()
+                        .map(str::to_string),
+                    exchange_rate: record
+                        .get_decimal("exchangerate")
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default(),
+                    precision: record
+                        .get_int("currencyprecision")
+                        .ok()
+                        .flatten()
+                        .and_then(|p| u32::try_from(p).ok()),
+                });
+            }
+        }
+        Ok(converter)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::api::ServerVersion;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+    use crate::model::metadata::AttributeType;
+    use crate::model::types::EntityBinding;
+
+    #[tokio::test]
+    async fn test_organization() {
+        let mock = MockDataverse::new()
+            .entity(
+                MockEntity::new("organization", "organizations", "organizationid")
+                    .primary_name("name")
+                    .attribute("languagecode", AttributeType::Integer)
+                    .lookup("basecurrencyid", "transactioncurrency"),
+            )
+            .entity(MockEntity::new(
+                "transactioncurrency",
+                "transactioncurrencies",
+                "transactioncurrencyid",
+            ));
+        let client = mock.client();
+        let currency = mock.insert(
+            Entity::set("transactioncurrencies"),
+            Record::new("transactioncurrency"),
+        );
+        mock.insert(
+            Entity::set("organizations"),
+            Record::with_id("organization", mock.organization_id())
+                .set("name", "Contoso")
+                .set("languagecode", 1033)
+                .set(
+                    "basecurrencyid",
+                    EntityBinding::new("transactioncurrencies", currency),
+                ),
+        );
+
+        let me = client.who_am_i().await.unwrap();
+        assert_eq!(me.user_id, mock.user_id());
+
+        let organization = client.organization().await.unwrap();
+        assert_eq!(organization.id, mock.organization_id());
+        assert_eq!(organization.name.as_deref(), Some("Contoso"));
+        assert_eq!(organization.version, "9.2.0.0");
+        assert_eq!(organization.base_currency_id, Some(currency));
+        assert_eq!(organization.language_code, Some(1033));
+
+        // The version is requested once per client
+        let capabilities = client.clone().capabilities().await.unwrap();
+        assert_eq!(capabilities.version, ServerVersion::new(9, 2, 0, 0));
+        assert!(capabilities.file_columns);
+        assert!(!capabilities.bulk_messages);
+        let version_requests = mock
+            .requests()
+            .iter()
+            .filter(|r| r.url.path().ends_with("RetrieveVersion()"))
+            .count();
+        assert_eq!(version_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_currency_converter() {
+        let mock = MockDataverse::new()
+            .entity(
+                MockEntity::new("organization", "organizations", "organizationid")
+                    .lookup("basecurrencyid", "transactioncurrency"),
+            )
+            .entity(
+                MockEntity::new(
+                    "transactioncurrency",
+                    "transactioncurrencies",
+                    "transactioncurrencyid",
+                )
+                .attribute("isocurrencycode", AttributeType::String)
+                .attribute("exchangerate", AttributeType::Decimal)
+                .attribute("currencyprecision", AttributeType::Integer)
+                .status(0, 1)
+                .status(1, 2),
+            )
+            .entity(
+                MockEntity::new("opportunity", "opportunities", "opportunityid")
+                    .attribute("estimatedvalue", AttributeType::Money)
+                    .lookup("transactioncurrencyid", "transactioncurrency"),
+            );
+        let client = mock.client();
+        let currency = |code: &str, rate: f64, state: i32| {
+            mock.insert(
+                Entity::set("transactioncurrencies"),
+                Record::new("transactioncurrency")
+                    .set("isocurrencycode", code)
+                    .set("exchangerate", rate)
+                    .set("currencyprecision", 2)
+                    .set("statecode", state),
+            )
+        };
+        let usd = currency("USD", 1.0, 0);
+        let eur = currency("EUR", 0.5, 0);
+        let old = currency("DEM", 2.0, 1);
+        mock.insert(
+            Entity::set("organizations"),
+            Record::with_id("organization", mock.organization_id()).set(
+                "basecurrencyid",
+                EntityBinding::new("transactioncurrencies", usd),
+            ),
+        );
+
+        let money = client
+            .currency_converter(Entity::logical("opportunity"))
+            .await
+            .unwrap();
+        assert_eq!(money.base_currency(), usd);
+        assert_eq!(money.get(eur).unwrap().iso_code.as_deref(), Some("EUR"));
+        assert!(money.get(old).is_none());
+
+        let mut opportunity = Record::new("opportunity").set(
+            "transactioncurrencyid",
+            EntityBinding::new("transactioncurrencies", eur),
+        );
+        money
+            .set(
+                &mut opportunity,
+                "estimatedvalue",
+                rust_decimal::Decimal::from(300),
+                usd,
+            )
+            .unwrap();
+        let id = client
+            .create(Entity::set("opportunities"), opportunity)
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+        let stored = mock.record(Entity::set("opportunities"), id).unwrap();
+        assert_eq!(
+            stored.get_decimal("estimatedvalue").unwrap(),
+            Some(rust_decimal::Decimal::from(150))
+        );
+
+        let mut opportunity = Record::new("opportunity").set("estimatedvalue", 10);
+        money.ensure_currency(&mut opportunity).unwrap();
+        assert_eq!(money.currency_of(&opportunity).unwrap(), Some(usd));
+    }
+}
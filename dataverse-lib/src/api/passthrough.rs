@@ -0,0 +1,170 @@
+//! Query option and header passthrough
+//!
+//! The query and CRUD builders accept extra query options and headers for
+//! OData features they don't cover. These helpers add them to a request,
+//! rejecting ones that would replace what the builder itself sends: a
+//! passthrough can add to a request, but not silently change it.
+
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+
+use crate::error::Error;
+
+/// Headers set by the client for every request, never passed through.
+const RESERVED_HEADERS: [&str; 4] = ["authorization", "content-length", "content-type", "host"];
+
+/// Appends query options to a URL.
+///
+/// Fails if an option is repeated, or the URL already has it.
+pub(crate) fn push_query_options(
+    url: &mut String,
+    options: &[(String, String)],
+) -> Result<(), Error> {
+    check_query_options(url, options)?;
+    append_query_options(url, options);
+    Ok(())
+}
+
+/// Checks that query options can be appended to a URL.
+pub(crate) fn check_query_options(url: &str, options: &[(String, String)]) -> Result<(), Error> {
+    let existing: Vec<&str> = match url.split_once('?') {
+        Some((_, query)) => query
+            .split('&')
+            .map(|pair| pair.split_once('=').map_or(pair, |(name, _)| name))
+            .collect(),
+        None => Vec::new(),
+    };
+    for (i, (name, _)) in options.iter().enumerate() {
+        if name.is_empty() || name.contains(['&', '=', '?', '#']) {
+            return Err(Error::InvalidOperation(format!(
+                "Invalid query option name '{}'",
+                name
+            )));
+        }
+        let conflict = existing.iter().any(|e| e.eq_ignore_ascii_case(name))
+            || options[..i]
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name));
+        if conflict {
+            return Err(Error::InvalidOperation(format!(
+                "Query option '{}' is already set by the request",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Appends query options to a URL, without checking them.
+pub(crate) fn append_query_options(url: &mut String, options: &[(String, String)]) {
+    for (name, value) in options {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(name);
+        url.push('=');
+        url.push_str(&urlencoding::encode(value));
+    }
+}
+
+/// Adds headers to a request.
+///
+/// Fails if a header is invalid, repeated, or already set by the request.
+pub(crate) fn apply_headers(
+    headers: &mut HeaderMap,
+    extra: &[(String, String)],
+) -> Result<(), Error> {
+    let parsed = check_headers(headers.keys().map(HeaderName::as_str), extra)?;
+    for (name, value) in parsed {
+        headers.insert(name, value);
+    }
+    Ok(())
+}
+
+/// Checks that headers can be added to a request that already has
+/// `existing`, returning them parsed.
+pub(crate) fn check_headers<'h>(
+    existing: impl IntoIterator<Item = &'h str>,
+    extra: &[(String, String)],
+) -> Result<Vec<(HeaderName, HeaderValue)>, Error> {
+    let existing: Vec<&str> = existing.into_iter().collect();
+    let mut parsed: Vec<(HeaderName, HeaderValue)> = Vec::with_capacity(extra.len());
+    for (name, value) in extra {
+        let header = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| Error::InvalidOperation(format!("Invalid header name '{}'", name)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| Error::InvalidOperation(format!("Invalid value for header '{}'", name)))?;
+        let conflict = RESERVED_HEADERS.contains(&header.as_str())
+            || existing
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(header.as_str()))
+            || parsed.iter().any(|(n, _)| *n == header);
+        if conflict {
+            return Err(Error::InvalidOperation(format!(
+                "Header '{}' is already set by the request",
+                name
+            )));
+        }
+        parsed.push((header, value));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_options() {
+        let mut url = "https://org/api/data/v9.2/accounts".to_string();
+        push_query_options(&mut url, &pairs(&[("$apply", "groupby((name))")])).unwrap();
+        assert_eq!(
+            url,
+            "https://org/api/data/v9.2/accounts?$apply=groupby%28%28name%29%29"
+        );
+
+        let mut url = "https://org/api/data/v9.2/accounts?$select=name".to_string();
+        push_query_options(&mut url, &pairs(&[("tag", "a b")])).unwrap();
+        assert!(url.ends_with("?$select=name&tag=a%20b"));
+
+        let err = push_query_options(&mut url, &pairs(&[("$SELECT", "accountid")]));
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+        let err = push_query_options(&mut url, &pairs(&[("a", "1"), ("a", "2")]));
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+        let err = push_query_options(&mut url, &pairs(&[("a&b", "1")]));
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn test_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Prefer", HeaderValue::from_static("return=representation"));
+        apply_headers(
+            &mut headers,
+            &pairs(&[("MSCRM.SolutionUniqueName", "core")]),
+        )
+        .unwrap();
+        assert_eq!(headers["mscrm.solutionuniquename"], "core");
+
+        for extra in [
+            pairs(&[("prefer", "odata.maxpagesize=10")]),
+            pairs(&[("Authorization", "Bearer x")]),
+            pairs(&[("X-A", "1"), ("x-a", "2")]),
+            pairs(&[("X-A", "1\r\nX-B: 2")]),
+            pairs(&[("X A", "1")]),
+        ] {
+            let err = apply_headers(&mut headers, &extra);
+            assert!(
+                matches!(err, Err(Error::InvalidOperation(_))),
+                "{:?}",
+                extra
+            );
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Plugin trace logs (`plugintracelog`).
+//!
+//! Reads the trace entries plugins write when plugin tracing is turned on in
+//! the environment, so the failing plugin and its trace text are available
+//! programmatically. The client can attach them to errors caused by plugin
+//! exceptions; see [`DataverseClientBuilder::attach_plugin_traces`].
+//!
+//! [`DataverseClientBuilder::attach_plugin_traces`]: crate::DataverseClientBuilder::attach_plugin_traces
+
+use chrono::DateTime;
+use chrono::Utc;
+use reqwest::Method;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+
+/// Error codes Dataverse returns when a plugin fails.
+pub(crate) const PLUGIN_ERROR_CODES: [&str; 2] = [
+    // InvalidPluginExecutionException thrown by the plugin
+    "0x80040265",
+    // Unexpected exception in the plugin
+    "0x80040266",
+];
+
+/// Columns read from `plugintracelog`.
+const SELECT: &str = "typename,messagename,primaryentity,exceptiondetails,messageblock,\
+correlationid,requestid,depth,performanceexecutionduration,createdon";
+
+/// A `plugintracelog` entry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginTrace {
+    /// The plugin type that ran, e.g. `Contoso.Plugins.ValidateAccount`.
+    #[serde(rename = "typename")]
+    pub type_name: Option<String>,
+    /// The message the plugin ran on, e.g. `Create`.
+    #[serde(rename = "messagename")]
+    pub message_name: Option<String>,
+    /// The logical name of the entity the message was for.
+    #[serde(rename = "primaryentity")]
+    pub primary_entity: Option<String>,
+    /// The exception the plugin threw, if any.
+    #[serde(rename = "exceptiondetails")]
+    pub exception_details: Option<String>,
+    /// The text the plugin wrote to its tracing service.
+    #[serde(rename = "messageblock")]
+    pub message_block: Option<String>,
+    /// The correlation id of the plugin execution.
+    #[serde(rename = "correlationid")]
+    pub correlation_id: Option<Uuid>,
+    /// The id of the request that ran the plugin.
+    #[serde(rename = "requestid")]
+    pub request_id: Option<Uuid>,
+    /// How deep in a chain of plugins this one ran.
+    #[serde(default)]
+    pub depth: Option<i32>,
+    /// How long the plugin ran, in milliseconds.
+    #[serde(rename = "performanceexecutionduration")]
+    pub execution_duration_ms: Option<i32>,
+    /// When the entry was written.
+    #[serde(rename = "createdon")]
+    pub created_on: Option<DateTime<Utc>>,
+}
+
+impl PluginTrace {
+    /// Returns `true` if the plugin threw an exception.
+    pub fn failed(&self) -> bool {
+        self.exception_details
+            .as_deref()
+            .is_some_and(|details| !details.is_empty())
+    }
+}
+
+#[derive(Deserialize)]
+struct PluginTraceResponse {
+    value: Vec<PluginTrace>,
+}
+
+impl DataverseClient {
+    /// Retrieves the plugin trace entries written while handling a request,
+    /// newest first.
+    ///
+    /// `request_id` is the request's `x-ms-service-request-id`. Entries are
+    /// only written when plugin tracing is turned on in the environment.
+    pub async fn plugin_traces(
+        &self,
+        request_id: Uuid,
+        max: usize,
+    ) -> Result<Vec<PluginTrace>, Error> {
+        let path = format!(
+            "/plugintracelogs?$select={}&$filter=requestid eq {}&$orderby=createdon desc&$top={}",
+            SELECT, request_id, max
+        );
+        let url = self.build_url(&path);
+
+        let response = self.request(Method::GET, &url, None, None).await?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Api(ApiError::from(e)))?;
+
+        let parsed: PluginTraceResponse = serde_json::from_str(&body)?;
+        Ok(parsed.value)
+    }
+}
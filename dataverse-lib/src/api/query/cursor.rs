@@ -0,0 +1,253 @@
+//! Resumable pagination cursors
+//!
+//! [`ODataPages::cursor`] and [`FetchXmlPages::cursor`] return a
+//! [`PageCursor`] for the position after the last page returned: the
+//! `@odata.nextLink` (with its `$skiptoken`) or the FetchXML page number and
+//! paging cookie, tied to a hash of the query. Saved after each completed
+//! page, it lets a long export that crashed pick up with the next page by
+//! passing it to [`ODataPages::resume`] or [`FetchXmlPages::resume`] on the
+//! same query.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut pages = client
+//!     .query(Entity::logical("account"))
+//!     .select(&["name"])
+//!     .into_async_iter(&client);
+//! if let Some(cursor) = PageCursor::load("accounts.cursor")? {
+//!     pages = pages.resume(cursor);
+//! }
+//!
+//! while let Some(page) = pages.next(&client).await {
+//!     export(page?.records()).await?;
+//!     if let Some(cursor) = pages.cursor() {
+//!         cursor.save("accounts.cursor")?;
+//!     }
+//! }
+//! ```
+//!
+//! [`ODataPages::cursor`]: super::odata::ODataPages::cursor
+//! [`ODataPages::resume`]: super::odata::ODataPages::resume
+//! [`FetchXmlPages::cursor`]: super::fetchxml::FetchXmlPages::cursor
+//! [`FetchXmlPages::resume`]: super::fetchxml::FetchXmlPages::resume
+
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::error::Error;
+
+/// Position in a paged query, saved to resume it later.
+///
+/// Opaque: only meaningful to the query it was taken from. Serializes with
+/// serde, e.g. as JSON with [`save`](Self::save).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    /// Hash of the query the cursor belongs to.
+    query: String,
+    /// The next page to fetch.
+    position: Position,
+}
+
+/// The next page of a paged query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Position {
+    /// An OData `@odata.nextLink`.
+    NextLink(String),
+    /// A FetchXML page number and the paging cookie of the previous page.
+    Page {
+        number: usize,
+        paging_cookie: Option<String>,
+    },
+    /// All pages have been returned.
+    Done,
+}
+
+impl PageCursor {
+    /// Creates a cursor for a query, given its hash.
+    pub(crate) fn new(query: String, position: Position) -> Self {
+        Self { query, position }
+    }
+
+    /// Returns `true` if the query had no pages left.
+    pub fn is_done(&self) -> bool {
+        self.position == Position::Done
+    }
+
+    /// Reads a cursor saved with [`save`](Self::save), or returns `None` if
+    /// the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the cursor to a file as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Returns the position to resume from, or an error if the cursor was
+    /// taken from a different query.
+    pub(crate) fn position_for(self, query: &str) -> Result<Position, Error> {
+        if self.query != query {
+            return Err(Error::InvalidOperation(
+                "Page cursor belongs to a different query".to_string(),
+            ));
+        }
+        Ok(self.position)
+    }
+}
+
+/// Hashes the parts of a query that decide its pages.
+pub(crate) fn query_hash(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    #[tokio::test]
+    async fn test_resume_odata_pages() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+        for name in ["A", "B", "C"] {
+            mock.insert(
+                Entity::set("accounts"),
+                Record::new("account").set("name", name),
+            );
+        }
+        let query = || {
+            client
+                .query(Entity::logical("account"))
+                .select(&["name"])
+                .page_size(1)
+                .bypass_cache()
+        };
+        let names = |page: crate::api::query::Page| -> Vec<String> {
+            page.records()
+                .iter()
+                .map(|r| r.get_string("name").unwrap().unwrap().to_string())
+                .collect()
+        };
+
+        let mut pages = query().into_async_iter(&client);
+        assert!(pages.cursor().is_none());
+        let first = names(pages.next(&client).await.unwrap().unwrap());
+        let cursor = pages.cursor().unwrap();
+        assert!(!cursor.is_done());
+
+        // A cursor survives a round trip through JSON
+        let json = serde_json::to_string(&cursor).unwrap();
+        let cursor: PageCursor = serde_json::from_str(&json).unwrap();
+
+        let mut resumed = query().into_async_iter(&client).resume(cursor.clone());
+        let mut rest = Vec::new();
+        while let Some(page) = resumed.next(&client).await {
+            rest.extend(names(page.unwrap()));
+        }
+        assert_eq!(first.len() + rest.len(), 3);
+        assert!(!rest.contains(&first[0]));
+
+        let done = resumed.cursor().unwrap();
+        assert!(done.is_done());
+        let mut finished = query().into_async_iter(&client).resume(done);
+        assert!(finished.next(&client).await.is_none());
+
+        // A cursor can't resume another query
+        let mut other = query()
+            .select(&["name", "accountid"])
+            .into_async_iter(&client)
+            .resume(cursor);
+        let err = other.next(&client).await.unwrap();
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+        assert!(other.next(&client).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_fetchxml_pages() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+        for name in ["A", "B", "C"] {
+            mock.insert(
+                Entity::set("accounts"),
+                Record::new("account").set("name", name),
+            );
+        }
+        let fetch = || client.fetch(Entity::logical("account")).page_size(1);
+        let names = |page: crate::api::query::Page| -> Vec<String> {
+            page.records()
+                .iter()
+                .map(|r| r.get_string("name").unwrap().unwrap().to_string())
+                .collect()
+        };
+
+        let mut pages = fetch().into_async_iter();
+        assert!(pages.cursor().is_none());
+        let first = names(pages.next().await.unwrap().unwrap());
+        let cursor = pages.cursor().unwrap();
+        assert!(!cursor.is_done());
+
+        let mut resumed = fetch().into_async_iter().resume(cursor.clone());
+        let mut rest = Vec::new();
+        while let Some(page) = resumed.next().await {
+            rest.extend(names(page.unwrap()));
+        }
+        assert_eq!(rest.len(), 2);
+        assert!(!rest.contains(&first[0]));
+        assert!(resumed.cursor().unwrap().is_done());
+
+        // The page number and cookie go into the resumed request
+        let cursor = PageCursor::new(
+            query_hash(&[&fetch().build_fetchxml()]),
+            Position::Page {
+                number: 3,
+                paging_cookie: Some(r#"<cookie page="2"/>"#.to_string()),
+            },
+        );
+        let mut pages = fetch().into_async_iter().resume(cursor.clone());
+        assert!(pages.next().await.unwrap().is_ok());
+        let request = mock.requests().pop().unwrap();
+        let (_, fetch_xml) = request
+            .url
+            .query_pairs()
+            .find(|(k, _)| k == "fetchXml")
+            .unwrap();
+        assert!(
+            fetch_xml.contains(
+                r#"count="1" page="3" paging-cookie="&lt;cookie page=&quot;2&quot;/&gt;">"#
+            ),
+            "{}",
+            fetch_xml
+        );
+
+        let mut other = client
+            .fetch(Entity::logical("account"))
+            .into_async_iter()
+            .resume(cursor);
+        assert!(matches!(
+            other.next().await,
+            Some(Err(Error::InvalidOperation(_)))
+        ));
+    }
+}
@@ -1,14 +1,23 @@
 //! FetchXML query builder.
 
+use serde::de::DeserializeOwned;
+
 use crate::DataverseClient;
+use crate::api::AggregateType;
+use crate::api::DateGrouping;
+use crate::api::Impersonation;
+use crate::api::aggregate::AggregateColumn;
+use crate::api::aggregate::GroupByColumn;
 use crate::api::query::Filter;
 use crate::api::query::OrderBy;
 use crate::error::Error;
+use crate::model::DataverseEntity;
 use crate::model::Entity;
 use crate::model::Record;
 
 use super::link::LinkEntityBuilder;
 use super::pages::FetchXmlPages;
+use super::typed::TypedFetch;
 use super::xml::attributes_to_fetchxml;
 use super::xml::escape_xml;
 use super::xml::filter_to_fetchxml;
@@ -34,6 +43,25 @@ use super::xml::order_to_fetchxml;
 ///     }
 /// }
 /// ```
+///
+/// # Aggregation
+///
+/// Adding an [`aggregate`](Self::aggregate) or [`group_by`](Self::group_by)
+/// column makes this an aggregate query. Results then hold one record per
+/// group, keyed by the columns' aliases:
+///
+/// ```ignore
+/// let rows = client.fetch(Entity::logical("opportunity"))
+///     .group_by_date("actualclosedate", "month", DateGrouping::Month)
+///     .aggregate("estimatedvalue", "total", AggregateType::Sum)
+///     .aggregate("opportunityid", "won", AggregateType::Count)
+///     .filter(Filter::eq("statecode", 1))
+///     .execute()
+///     .await?;
+/// for row in rows {
+///     println!("{:?}: {:?}", row.get("month"), row.get("total"));
+/// }
+/// ```
 pub struct FetchBuilder<'a> {
     client: &'a DataverseClient,
     entity: Entity,
@@ -43,8 +71,14 @@ pub struct FetchBuilder<'a> {
     top: Option<usize>,
     page_size: Option<usize>,
     distinct: bool,
+    no_lock: bool,
     links: Vec<LinkEntityBuilder>,
+    group_by: Vec<GroupByColumn>,
+    aggregates: Vec<AggregateColumn>,
     include_count: bool,
+    impersonate: Option<Impersonation>,
+    query_options: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
 }
 
 impl<'a> FetchBuilder<'a> {
@@ -59,8 +93,14 @@ impl<'a> FetchBuilder<'a> {
             top: None,
             page_size: None,
             distinct: false,
+            no_lock: false,
             links: Vec::new(),
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
             include_count: false,
+            impersonate: None,
+            query_options: Vec::new(),
+            headers: Vec::new(),
         }
     }
 
@@ -78,6 +118,18 @@ impl<'a> FetchBuilder<'a> {
         self
     }
 
+    /// Adds a filter condition, combined with any existing filter using AND.
+    ///
+    /// Useful for narrowing a query parsed with
+    /// [`DataverseClient::fetch_xml`].
+    pub fn and_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and_also(filter),
+            None => filter,
+        });
+        self
+    }
+
     /// Sets the ordering of results.
     pub fn order_by(mut self, order: OrderBy) -> Self {
         self.order_by = Some(order);
@@ -105,6 +157,15 @@ impl<'a> FetchBuilder<'a> {
         self
     }
 
+    /// Reads without taking shared locks (`no-lock`).
+    ///
+    /// Keeps large reads from blocking, and being blocked by, writes to the
+    /// same table, at the cost of possibly seeing uncommitted changes.
+    pub fn no_lock(mut self) -> Self {
+        self.no_lock = true;
+        self
+    }
+
     /// Adds a link entity (join) to the query.
     ///
     /// # Example
@@ -133,12 +194,94 @@ impl<'a> FetchBuilder<'a> {
         self
     }
 
+    /// Adds an already built link entity to the query.
+    pub(crate) fn with_link(mut self, link: LinkEntityBuilder) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// Adds an aggregate column, making this an aggregate query.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to aggregate
+    /// * `alias` - The key of the value in the results
+    /// * `aggregate_type` - The aggregation to perform
+    pub fn aggregate(
+        mut self,
+        field: impl Into<String>,
+        alias: impl Into<String>,
+        aggregate_type: AggregateType,
+    ) -> Self {
+        self.aggregates
+            .push(AggregateColumn::new(field, alias, aggregate_type));
+        self
+    }
+
+    /// Adds a group by column, making this an aggregate query.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to group by
+    /// * `alias` - The key of the field in the results
+    pub fn group_by(mut self, field: impl Into<String>, alias: impl Into<String>) -> Self {
+        self.group_by.push(GroupByColumn::new(field, alias, None));
+        self
+    }
+
+    /// Adds a group by column on a datetime field, grouped by `grouping`
+    /// (`dategrouping`).
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The datetime field to group by
+    /// * `alias` - The key of the field in the results
+    /// * `grouping` - The date grouping interval
+    pub fn group_by_date(
+        mut self,
+        field: impl Into<String>,
+        alias: impl Into<String>,
+        grouping: DateGrouping,
+    ) -> Self {
+        self.group_by
+            .push(GroupByColumn::new(field, alias, Some(grouping)));
+        self
+    }
+
     /// Includes the total count of matching records in the response.
     pub fn include_count(mut self) -> Self {
         self.include_count = true;
         self
     }
 
+    /// Runs this query as another user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option to every page's URL, after `fetchXml`.
+    ///
+    /// The query fails if the option is `fetchXml` or repeated.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a header to every page request.
+    ///
+    /// The query fails if the builder already sets the header, e.g.
+    /// `Prefer`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Returns whether this is an aggregate query.
+    fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty() || !self.aggregates.is_empty()
+    }
+
     /// Returns the entity logical name.
     fn entity_logical_name(&self) -> &str {
         match &self.entity {
@@ -148,7 +291,7 @@ impl<'a> FetchBuilder<'a> {
     }
 
     /// Builds the FetchXML string.
-    pub(crate) fn build_fetchxml(&self) -> String {
+    pub fn build_fetchxml(&self) -> String {
         let entity_name = self.entity_logical_name();
 
         // Build fetch attributes
@@ -164,6 +307,10 @@ impl<'a> FetchBuilder<'a> {
             fetch_attrs.push(r#"distinct="false""#.to_string());
         }
 
+        if self.no_lock {
+            fetch_attrs.push(r#"no-lock="true""#.to_string());
+        }
+
         if let Some(top) = self.top {
             fetch_attrs.push(format!(r#"top="{}""#, top));
         }
@@ -176,6 +323,10 @@ impl<'a> FetchBuilder<'a> {
             fetch_attrs.push(r#"returntotalrecordcount="true""#.to_string());
         }
 
+        if self.is_aggregate() {
+            fetch_attrs.push(r#"aggregate="true""#.to_string());
+        }
+
         // Build entity content
         let mut entity_content = String::new();
 
@@ -184,6 +335,14 @@ impl<'a> FetchBuilder<'a> {
             entity_content.push_str(&attributes_to_fetchxml(&self.select));
         }
 
+        // Group by and aggregate columns
+        for group in &self.group_by {
+            entity_content.push_str(&group.to_fetchxml());
+        }
+        for aggregate in &self.aggregates {
+            entity_content.push_str(&aggregate.to_fetchxml());
+        }
+
         // Filter
         if let Some(ref filter) = self.filter {
             let filter_xml = filter_to_fetchxml(filter);
@@ -213,11 +372,51 @@ impl<'a> FetchBuilder<'a> {
         )
     }
 
+    /// Returns the selected fields.
+    pub fn select_value(&self) -> &[String] {
+        &self.select
+    }
+
+    /// Returns the filter, if set.
+    pub fn filter_value(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    /// Returns the ordering, if set.
+    pub fn order_by_value(&self) -> Option<&OrderBy> {
+        self.order_by.as_ref()
+    }
+
+    /// Returns the record limit, if set.
+    pub fn top_value(&self) -> Option<usize> {
+        self.top
+    }
+
+    /// Returns whether the query reads without locks.
+    pub fn no_lock_value(&self) -> bool {
+        self.no_lock
+    }
+
     /// Returns the page size, if set.
     pub(crate) fn page_size_value(&self) -> Option<usize> {
         self.page_size
     }
 
+    /// Returns the user this query impersonates, if any.
+    pub(crate) fn impersonate_value(&self) -> Option<Impersonation> {
+        self.impersonate
+    }
+
+    /// Returns the extra query options.
+    pub(crate) fn query_options_value(&self) -> &[(String, String)] {
+        &self.query_options
+    }
+
+    /// Returns the extra headers.
+    pub(crate) fn headers_value(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
     /// Returns a reference to the entity.
     pub(crate) fn entity(&self) -> &Entity {
         &self.entity
@@ -262,7 +461,76 @@ impl<'a> FetchBuilder<'a> {
     }
 
     /// Converts this fetch builder into an async iterator over pages.
-    pub fn into_async_iter(self) -> FetchXmlPages<'a> {
+    pub fn into_async_iter(self) -> FetchXmlPages {
         FetchXmlPages::new(self)
     }
+
+    /// Converts this fetch builder into a query returning `T` instead of records.
+    ///
+    /// Selects `T::COLUMNS` unless `.select()` was called.
+    pub fn into_typed<T: DataverseEntity>(self) -> TypedFetch<'a, T> {
+        TypedFetch::new(self)
+    }
+
+    /// Converts this fetch builder into a query deserializing each record
+    /// into `T` with serde. See [`Record::deserialize_into`] for the fields
+    /// `T` can read.
+    pub fn into_deserialized<T: DeserializeOwned>(self) -> TypedFetch<'a, T> {
+        TypedFetch::deserialized(self)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+
+    #[test]
+    fn test_aggregate_fetchxml() {
+        let client = MockDataverse::new().client();
+        let fetch = client
+            .fetch(Entity::logical("opportunity"))
+            .group_by("ownerid", "owner")
+            .group_by_date("actualclosedate", "month", DateGrouping::Month)
+            .aggregate("estimatedvalue", "total", AggregateType::Sum)
+            .aggregate("customerid", "customers", AggregateType::CountDistinct)
+            .distinct(true)
+            .filter(Filter::eq("statecode", 1i32));
+        assert_eq!(
+            fetch.build_fetchxml(),
+            concat!(
+                r#"<fetch version="1.0" output-format="xml-platform" mapping="logical" distinct="true" aggregate="true">"#,
+                r#"<entity name="opportunity">"#,
+                r#"<attribute name="ownerid" alias="owner" groupby="true"/>"#,
+                r#"<attribute name="actualclosedate" alias="month" groupby="true" dategrouping="month"/>"#,
+                r#"<attribute name="estimatedvalue" alias="total" aggregate="sum"/>"#,
+                r#"<attribute name="customerid" alias="customers" aggregate="countcolumn" distinct="true"/>"#,
+                r#"<filter type="and"><condition attribute="statecode" operator="eq" value="1"/></filter>"#,
+                r#"</entity></fetch>"#,
+            )
+        );
+
+        let plain = client.fetch(Entity::logical("account")).select(&["name"]);
+        assert!(!plain.build_fetchxml().contains("aggregate"));
+    }
+
+    #[test]
+    fn test_fetch_options() {
+        let client = MockDataverse::new().client();
+        let fetch = client
+            .fetch(Entity::logical("account"))
+            .no_lock()
+            .distinct(true)
+            .page_size(250);
+        assert_eq!(
+            fetch.build_fetchxml(),
+            concat!(
+                r#"<fetch version="1.0" output-format="xml-platform" mapping="logical" distinct="true" no-lock="true" count="250">"#,
+                r#"<entity name="account"></entity></fetch>"#,
+            )
+        );
+
+        let fetch = fetch.top(10);
+        assert!(fetch.build_fetchxml().contains(r#"top="10""#));
+    }
 }
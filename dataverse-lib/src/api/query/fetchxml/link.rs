@@ -131,6 +131,12 @@ impl LinkEntityBuilder {
         self
     }
 
+    /// Adds an already built nested link entity.
+    pub(crate) fn with_link(mut self, link: LinkEntityBuilder) -> Self {
+        self.links.push(link);
+        self
+    }
+
     /// Converts this link entity to FetchXML.
     pub fn to_fetchxml(&self) -> String {
         let link_type_str = match self.link_type {
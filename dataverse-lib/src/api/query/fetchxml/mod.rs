@@ -11,6 +11,10 @@
 //! - Paging with cookies for reliable pagination
 //! - Distinct queries
 //!
+//! Existing FetchXML (e.g. from Advanced Find or a saved view) can be parsed
+//! back into a [`FetchBuilder`] with [`DataverseClient::fetch_xml`], then
+//! inspected, modified and re-executed.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -29,13 +33,20 @@
 //!     }
 //! }
 //! ```
+//!
+//! [`DataverseClient::fetch_xml`]: crate::DataverseClient::fetch_xml
 
 mod builder;
 mod link;
 mod pages;
+mod parse;
+mod typed;
 pub(crate) mod xml;
 
 pub use builder::FetchBuilder;
 pub use link::LinkEntityBuilder;
 pub use link::LinkType;
 pub use pages::FetchXmlPages;
+pub(crate) use parse::parse_fetchxml;
+pub use typed::TypedFetch;
+pub use typed::TypedFetchPages;
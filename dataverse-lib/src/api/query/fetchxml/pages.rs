@@ -1,5 +1,8 @@
 //! Async iterator for FetchXML query pagination.
 
+use futures::Stream;
+use futures::TryStreamExt;
+use futures::stream;
 use reqwest::Method;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
@@ -7,10 +10,22 @@ use serde::Deserialize;
 use url::form_urlencoded;
 
 use crate::DataverseClient;
+use crate::api::Impersonation;
+use crate::api::execute::apply_impersonation;
+use crate::api::passthrough::apply_headers;
+use crate::api::passthrough::push_query_options;
 use crate::api::query::Page;
+use crate::api::query::PageCursor;
+use crate::api::query::Position;
+use crate::api::query::query_hash;
 use crate::error::ApiError;
 use crate::error::Error;
+use crate::model::Entity;
 use crate::model::Record;
+use crate::model::loaded_records;
+use crate::stream::Prefetch;
+use crate::telemetry::InSpan;
+use crate::telemetry::span;
 
 use super::builder::FetchBuilder;
 
@@ -32,40 +47,83 @@ use super::builder::FetchBuilder;
 ///     }
 /// }
 /// ```
-pub struct FetchXmlPages<'a> {
-    /// Reference to the client for making requests.
-    client: &'a DataverseClient,
-    /// The entity set name (resolved from entity).
+///
+/// Use [`into_stream`](Self::into_stream) or
+/// [`into_record_stream`](Self::into_record_stream) to compose with
+/// [`StreamExt`](futures::StreamExt) combinators, or [`prefetch`](Self::prefetch)
+/// to fetch pages ahead of a slow consumer.
+///
+/// Save [`cursor`](Self::cursor) after each page to [`resume`](Self::resume)
+/// the query later. See [`PageCursor`].
+pub struct FetchXmlPages {
+    /// Client for making requests.
+    client: DataverseClient,
+    /// The entity being queried.
+    entity: Entity,
+    /// The entity set name (resolved from entity on first call).
     entity_set_name: Option<String>,
     /// The base FetchXML (without paging).
-    base_fetchxml: Option<String>,
+    base_fetchxml: String,
     /// Current page number.
     page_number: usize,
     /// Paging cookie from the last response.
     paging_cookie: Option<String>,
     /// Whether we've exhausted all pages.
     done: bool,
-    /// Whether we need to resolve the entity first.
-    needs_resolution: Option<FetchBuilder<'a>>,
+    /// User to run the query as (overrides the client's impersonation).
+    impersonate: Option<Impersonation>,
+    /// Extra query options for every page.
+    query_options: Vec<(String, String)>,
+    /// Extra headers for every page.
+    headers: Vec<(String, String)>,
+    /// Position after the last page returned.
+    position: Option<Position>,
+    /// Cursor to resume from on the first call.
+    resume: Option<PageCursor>,
 }
 
-impl<'a> FetchXmlPages<'a> {
+impl FetchXmlPages {
     /// Creates a new async iterator from a fetch builder.
-    pub(crate) fn new(builder: FetchBuilder<'a>) -> Self {
-        // Extract values before moving builder
-        let client = builder.client();
-
+    pub(crate) fn new(builder: FetchBuilder<'_>) -> Self {
         Self {
-            client,
+            client: builder.client().clone(),
+            entity: builder.entity().clone(),
             entity_set_name: None,
-            base_fetchxml: None,
+            base_fetchxml: builder.build_fetchxml(),
             page_number: 1,
             paging_cookie: None,
             done: false,
-            needs_resolution: Some(builder),
+            impersonate: builder.impersonate_value(),
+            query_options: builder.query_options_value().to_vec(),
+            headers: builder.headers_value().to_vec(),
+            position: None,
+            resume: None,
         }
     }
 
+    /// Continues from a cursor returned by [`cursor`](Self::cursor) on the
+    /// same query, e.g. in an earlier run, instead of the first page.
+    ///
+    /// The first call to [`next`](Self::next) returns
+    /// [`Error::InvalidOperation`] if the cursor belongs to a different
+    /// query.
+    pub fn resume(mut self, cursor: PageCursor) -> Self {
+        self.resume = Some(cursor);
+        self
+    }
+
+    /// Returns the position after the last page returned, or `None` before
+    /// the first page.
+    pub fn cursor(&self) -> Option<PageCursor> {
+        let position = self.position.clone()?;
+        Some(PageCursor::new(self.query_hash(), position))
+    }
+
+    /// Hashes the query for cursors.
+    fn query_hash(&self) -> String {
+        query_hash(&[&self.base_fetchxml])
+    }
+
     /// Fetches the next page of results.
     ///
     /// Returns `None` when all pages have been consumed.
@@ -74,11 +132,71 @@ impl<'a> FetchXmlPages<'a> {
             return None;
         }
 
-        // Resolve entity and build FetchXML on first call
-        if let Some(builder) = self.needs_resolution.take() {
-            let entity_set_name = match builder.entity() {
-                crate::model::Entity::Set(name) => name.clone(),
-                crate::model::Entity::Logical(logical_name) => {
+        let span = span!(
+            "dataverse.query",
+            kind = "fetchxml",
+            page = self.page_number
+        );
+        self.next_page().in_span(span).await
+    }
+
+    /// Converts this iterator into a [`Stream`] of pages.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Page, Error>> + Send + 'static {
+        stream::unfold(self, |mut pages| async move {
+            let page = pages.next().await?;
+            Some((page, pages))
+        })
+    }
+
+    /// Converts this iterator into a [`Stream`] of records, fetching pages
+    /// as they're consumed.
+    pub fn into_record_stream(self) -> impl Stream<Item = Result<Record, Error>> + Send + 'static {
+        self.into_stream()
+            .map_ok(|page| stream::iter(page.into_records().into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Fetches up to `pages` pages ahead in the background while the
+    /// consumer processes the current one.
+    ///
+    /// Each page needs the paging cookie of the previous one, so pages are
+    /// still requested one at a time, but the requests overlap with the
+    /// consumer's work. See [`Prefetch`].
+    pub fn prefetch(self, pages: usize) -> Prefetch<Page> {
+        Prefetch::spawn(self.into_stream(), pages)
+    }
+
+    async fn next_page(&mut self) -> Option<Result<Page, Error>> {
+        if let Some(cursor) = self.resume.take() {
+            match cursor.position_for(&self.query_hash()) {
+                Ok(Position::Page {
+                    number,
+                    paging_cookie,
+                }) => {
+                    self.page_number = number;
+                    self.paging_cookie = paging_cookie.clone();
+                    self.position = Some(Position::Page {
+                        number,
+                        paging_cookie,
+                    });
+                }
+                Ok(_) => {
+                    self.position = Some(Position::Done);
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        // Resolve the entity set name on first call
+        if self.entity_set_name.is_none() {
+            let entity_set_name = match &self.entity {
+                Entity::Set(name) => name.clone(),
+                Entity::Logical(logical_name) => {
                     match self.client.resolve_entity_set_name(logical_name).await {
                         Ok(name) => name,
                         Err(e) => {
@@ -89,24 +207,26 @@ impl<'a> FetchXmlPages<'a> {
                 }
             };
             self.entity_set_name = Some(entity_set_name);
-            self.base_fetchxml = Some(builder.build_fetchxml());
         }
 
         let entity_set_name = self.entity_set_name.as_ref()?;
-        let base_fetchxml = self.base_fetchxml.as_ref()?;
 
         // Build the FetchXML with paging info
-        let fetchxml = self.build_paged_fetchxml(base_fetchxml);
+        let fetchxml = self.build_paged_fetchxml(&self.base_fetchxml);
 
         // Build URL
         let base_url = self.client.base_url().trim_end_matches('/');
         let api_version = self.client.api_version();
         let encoded_fetchxml: String =
             form_urlencoded::byte_serialize(fetchxml.as_bytes()).collect();
-        let url = format!(
+        let mut url = format!(
             "{}/api/data/{}/{}?fetchXml={}",
             base_url, api_version, entity_set_name, encoded_fetchxml
         );
+        if let Err(e) = push_query_options(&mut url, &self.query_options) {
+            self.done = true;
+            return Some(Err(e));
+        }
 
         // Build headers
         let mut headers = HeaderMap::new();
@@ -117,6 +237,13 @@ impl<'a> FetchXmlPages<'a> {
             "Prefer",
             HeaderValue::from_static("odata.include-annotations=\"*\""),
         );
+        if let Some(impersonation) = &self.impersonate {
+            apply_impersonation(&mut headers, impersonation);
+        }
+        if let Err(e) = apply_headers(&mut headers, &self.headers) {
+            self.done = true;
+            return Some(Err(e));
+        }
 
         // Make request
         let response: reqwest::Response =
@@ -150,7 +277,11 @@ impl<'a> FetchXmlPages<'a> {
         }
 
         if let Some(count) = fetchxml_response.total_record_count {
-            page = page.with_total_count(count);
+            page = page
+                .with_total_count(count)
+                .with_total_count_limit_exceeded(
+                    fetchxml_response.total_record_count_limit_exceeded,
+                );
         }
 
         // Check if more records available
@@ -158,6 +289,15 @@ impl<'a> FetchXmlPages<'a> {
             self.done = true;
         }
 
+        self.position = Some(if self.done {
+            Position::Done
+        } else {
+            Position::Page {
+                number: self.page_number,
+                paging_cookie: self.paging_cookie.clone(),
+            }
+        });
+
         Some(Ok(page))
     }
 
@@ -198,6 +338,7 @@ fn xml_escape_attr(s: &str) -> String {
 #[derive(Debug, Deserialize)]
 struct FetchXmlResponse {
     /// The records in this page.
+    #[serde(deserialize_with = "loaded_records")]
     value: Vec<Record>,
     /// Paging cookie for next page.
     #[serde(rename = "@Microsoft.Dynamics.CRM.fetchxmlpagingcookie")]
@@ -205,7 +346,77 @@ struct FetchXmlResponse {
     /// Total record count (when returntotalrecordcount="true").
     #[serde(rename = "@Microsoft.Dynamics.CRM.totalrecordcount")]
     total_record_count: Option<usize>,
+    /// Whether the total count stopped at the 5000 record limit.
+    #[serde(
+        rename = "@Microsoft.Dynamics.CRM.totalrecordcountlimitexceeded",
+        default
+    )]
+    total_record_count_limit_exceeded: bool,
     /// Whether more records are available.
     #[serde(rename = "@Microsoft.Dynamics.CRM.morerecords")]
     more_records: Option<bool>,
 }
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use futures::StreamExt;
+    use futures::TryStreamExt;
+
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    #[tokio::test]
+    async fn test_stream_pages_with_paging_cookie() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+        let names = ["A", "B", "C", "D", "E"];
+        for name in names {
+            mock.insert(
+                Entity::set("accounts"),
+                Record::new("account").set("name", name),
+            );
+        }
+        let fetch = || {
+            client
+                .fetch(Entity::logical("account"))
+                .select(&["name"])
+                .page_size(2)
+        };
+
+        let pages: Vec<_> = fetch().into_async_iter().into_stream().collect().await;
+        let sizes: Vec<usize> = pages
+            .iter()
+            .map(|page| page.as_ref().unwrap().records().len())
+            .collect();
+        assert_eq!(sizes, [2, 2, 1]);
+
+        // Later pages continue from the previous page's cookie
+        let requests: Vec<String> = mock
+            .requests()
+            .iter()
+            .filter_map(|r| r.url.query_pairs().find(|(k, _)| k == "fetchXml"))
+            .map(|(_, fetch_xml)| fetch_xml.into_owned())
+            .collect();
+        assert_eq!(requests.len(), 3);
+        assert!(!requests[0].contains("paging-cookie"));
+        let cookie = pages[0].as_ref().unwrap().paging_cookie().unwrap();
+        assert!(cookie.contains("last="), "{}", cookie);
+        assert!(requests[1].contains(r#" page="2" paging-cookie="&lt;cookie"#));
+        assert!(requests[2].contains(r#" page="3" paging-cookie="&lt;cookie"#));
+
+        let records: Vec<Record> = fetch()
+            .into_async_iter()
+            .into_record_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        let streamed: Vec<&str> = records
+            .iter()
+            .map(|r| r.get_string("name").unwrap().unwrap())
+            .collect();
+        assert_eq!(streamed, names);
+    }
+}
@@ -0,0 +1,492 @@
+//! FetchXML parsing.
+//!
+//! Turns an existing FetchXML document (e.g. exported from Advanced Find or
+//! a saved view) back into a [`FetchBuilder`].
+
+use std::fmt::Display;
+
+use quick_xml::Reader;
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event;
+
+use crate::DataverseClient;
+use crate::api::AggregateType;
+use crate::api::DateGrouping;
+use crate::api::query::Filter;
+use crate::api::query::OrderBy;
+use crate::error::Error;
+use crate::model::Entity;
+
+use super::builder::FetchBuilder;
+use super::link::LinkEntityBuilder;
+use super::link::LinkType;
+
+/// A parsed XML element.
+#[derive(Debug)]
+struct Element {
+    /// The element name.
+    name: String,
+    /// The unescaped attributes, in document order.
+    attributes: Vec<(String, String)>,
+    /// Child elements.
+    children: Vec<Element>,
+    /// The element's source text, including its children.
+    raw: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart<'_>, reader: &Reader<&[u8]>) -> Result<Self, Error> {
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(invalid)?;
+            let value = attribute
+                .decode_and_unescape_value(reader)
+                .map_err(invalid)?;
+            attributes.push((
+                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                value.into_owned(),
+            ));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+            raw: String::new(),
+        })
+    }
+
+    /// Returns the value of an attribute.
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of a required attribute.
+    fn required(&self, name: &str) -> Result<&str, Error> {
+        self.attribute(name).ok_or_else(|| {
+            invalid(format!(
+                "<{}> is missing the '{}' attribute",
+                self.name, name
+            ))
+        })
+    }
+
+    /// Returns whether a boolean attribute is set.
+    fn flag(&self, name: &str) -> bool {
+        matches!(self.attribute(name), Some("true") | Some("1"))
+    }
+}
+
+/// Content shared by `<entity>` and `<link-entity>` elements.
+#[derive(Default)]
+struct Content {
+    select: Vec<String>,
+    group_by: Vec<(String, String, Option<DateGrouping>)>,
+    aggregates: Vec<(String, String, AggregateType)>,
+    filter: Option<Filter>,
+    order_by: Option<OrderBy>,
+    links: Vec<LinkEntityBuilder>,
+}
+
+impl Content {
+    fn select_refs(&self) -> Vec<&str> {
+        self.select.iter().map(String::as_str).collect()
+    }
+}
+
+fn invalid(message: impl Display) -> Error {
+    Error::InvalidOperation(format!("Invalid FetchXML: {}", message))
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+/// Parses a FetchXML document into a [`FetchBuilder`].
+///
+/// Conditions that don't map onto a [`Filter`] variant (e.g. `in`,
+/// `last-x-days` or cross-entity conditions) are kept verbatim as
+/// [`Filter::Raw`]. Paging attributes (`page`, `paging-cookie`) are ignored
+/// since the builder manages paging itself.
+pub(crate) fn parse_fetchxml<'a>(
+    client: &'a DataverseClient,
+    xml: &str,
+) -> Result<FetchBuilder<'a>, Error> {
+    let fetch = parse_document(xml)?;
+    if fetch.name != "fetch" {
+        return Err(invalid(format!(
+            "expected a <fetch> root element, found <{}>",
+            fetch.name
+        )));
+    }
+    let entity = match fetch.children.as_slice() {
+        [entity] if entity.name == "entity" => entity,
+        _ => return Err(invalid("<fetch> must contain a single <entity> element")),
+    };
+
+    let mut builder = client
+        .fetch(Entity::logical(entity.required("name")?))
+        .distinct(fetch.flag("distinct"));
+    if let Some(top) = fetch.attribute("top") {
+        builder = builder.top(parse_number(top, "top")?);
+    }
+    if let Some(count) = fetch.attribute("count") {
+        builder = builder.page_size(parse_number(count, "count")?);
+    }
+    if fetch.flag("returntotalrecordcount") {
+        builder = builder.include_count();
+    }
+    if fetch.flag("no-lock") {
+        builder = builder.no_lock();
+    }
+
+    let content = parse_content(entity)?;
+    if !content.select.is_empty() {
+        builder = builder.select(&content.select_refs());
+    }
+    for (field, alias, grouping) in content.group_by {
+        builder = match grouping {
+            Some(grouping) => builder.group_by_date(field, alias, grouping),
+            None => builder.group_by(field, alias),
+        };
+    }
+    for (field, alias, aggregate_type) in content.aggregates {
+        builder = builder.aggregate(field, alias, aggregate_type);
+    }
+    if let Some(filter) = content.filter {
+        builder = builder.filter(filter);
+    }
+    if let Some(order) = content.order_by {
+        builder = builder.order_by(order);
+    }
+    for link in content.links {
+        builder = builder.with_link(link);
+    }
+    Ok(builder)
+}
+
+/// Parses an XML document into its root element.
+fn parse_document(xml: &str) -> Result<Element, Error> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut stack: Vec<(Element, usize)> = Vec::new();
+    let mut root = None;
+    loop {
+        let start = reader.buffer_position();
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) => stack.push((Element::new(&e, &reader)?, start)),
+            Event::Empty(e) => {
+                let mut element = Element::new(&e, &reader)?;
+                element.raw = xml[start..reader.buffer_position()].trim().to_string();
+                attach(&mut stack, &mut root, element)?;
+            }
+            Event::End(_) => {
+                let (mut element, start) = stack
+                    .pop()
+                    .ok_or_else(|| invalid("unexpected closing tag"))?;
+                element.raw = xml[start..reader.buffer_position()].trim().to_string();
+                attach(&mut stack, &mut root, element)?;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if let Some((element, _)) = stack.last() {
+        return Err(invalid(format!("<{}> is not closed", element.name)));
+    }
+    root.ok_or_else(|| invalid("document is empty"))
+}
+
+/// Adds a completed element to its parent, or makes it the root.
+fn attach(
+    stack: &mut [(Element, usize)],
+    root: &mut Option<Element>,
+    element: Element,
+) -> Result<(), Error> {
+    match stack.last_mut() {
+        Some((parent, _)) => parent.children.push(element),
+        None if root.is_none() => *root = Some(element),
+        None => return Err(invalid("document has multiple root elements")),
+    }
+    Ok(())
+}
+
+fn parse_number(value: &str, attribute: &str) -> Result<usize, Error> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("'{}' is not a valid {} value", value, attribute)))
+}
+
+/// Parses the children of an `<entity>` or `<link-entity>` element.
+fn parse_content(element: &Element) -> Result<Content, Error> {
+    let mut content = Content::default();
+    let mut filters = Vec::new();
+
+    for child in &element.children {
+        match child.name.as_str() {
+            "attribute" => {
+                let name = child.required("name")?.to_string();
+                if let Some(aggregate) = child.attribute("aggregate") {
+                    let aggregate_type = AggregateType::from_fetchxml(aggregate)
+                        .ok_or_else(|| invalid(format!("unsupported aggregate '{}'", aggregate)))?;
+                    let alias = child.required("alias")?.to_string();
+                    content.aggregates.push((name, alias, aggregate_type));
+                } else if child.flag("groupby") {
+                    let grouping = match child.attribute("dategrouping") {
+                        Some(grouping) => {
+                            Some(DateGrouping::from_fetchxml(grouping).ok_or_else(|| {
+                                invalid(format!("unsupported dategrouping '{}'", grouping))
+                            })?)
+                        }
+                        None => None,
+                    };
+                    let alias = child.required("alias")?.to_string();
+                    content.group_by.push((name, alias, grouping));
+                } else {
+                    content.select.push(name);
+                }
+            }
+            // An empty selection already returns all attributes
+            "all-attributes" => {}
+            "filter" => filters.push(parse_filter(child)?),
+            "order" => {
+                let field = child.required("attribute")?;
+                let descending = child.flag("descending");
+                content.order_by = Some(match (content.order_by.take(), descending) {
+                    (None, false) => OrderBy::asc(field),
+                    (None, true) => OrderBy::desc(field),
+                    (Some(order), false) => order.then_asc(field),
+                    (Some(order), true) => order.then_desc(field),
+                });
+            }
+            "link-entity" => content.links.push(parse_link(child)?),
+            other => {
+                return Err(invalid(format!(
+                    "unsupported element <{}> in <{}>",
+                    other, element.name
+                )));
+            }
+        }
+    }
+
+    content.filter = match filters.len() {
+        0 => None,
+        1 => filters.pop(),
+        _ => Some(Filter::and(filters)),
+    };
+    Ok(content)
+}
+
+/// Parses a `<filter>` element.
+fn parse_filter(element: &Element) -> Result<Filter, Error> {
+    let mut filters = Vec::new();
+    for child in &element.children {
+        match child.name.as_str() {
+            "condition" => filters.push(parse_condition(child)?),
+            "filter" => filters.push(parse_filter(child)?),
+            other => {
+                return Err(invalid(format!(
+                    "unsupported element <{}> in <filter>",
+                    other
+                )));
+            }
+        }
+    }
+
+    match element.attribute("type").unwrap_or("and") {
+        "and" => Ok(Filter::and(filters)),
+        "or" => Ok(Filter::or(filters)),
+        other => Err(invalid(format!("unsupported filter type '{}'", other))),
+    }
+}
+
+/// Parses a `<condition>` element.
+///
+/// Conditions without a matching [`Filter`] variant become [`Filter::Raw`].
+fn parse_condition(element: &Element) -> Result<Filter, Error> {
+    let field = element.required("attribute")?;
+    let operator = element.required("operator")?;
+    let raw = || Filter::Raw(element.raw.clone());
+
+    // Cross-entity and column comparison conditions have no typed form
+    if element.attribute("entityname").is_some() || element.attribute("valueof").is_some() {
+        return Ok(raw());
+    }
+
+    let filter = match (operator, element.attribute("value")) {
+        ("eq", Some(value)) => Filter::eq(field, value),
+        ("ne", Some(value)) => Filter::ne(field, value),
+        ("gt", Some(value)) => Filter::gt(field, value),
+        ("ge", Some(value)) => Filter::ge(field, value),
+        ("lt", Some(value)) => Filter::lt(field, value),
+        ("le", Some(value)) => Filter::le(field, value),
+        ("like", Some(value)) => like_filter(field, value).unwrap_or_else(raw),
+        ("null", None) => Filter::is_null(field),
+        ("not-null", None) => Filter::is_not_null(field),
+        _ => raw(),
+    };
+    Ok(filter)
+}
+
+/// Maps a `like` pattern onto contains, starts-with or ends-with.
+fn like_filter(field: &str, pattern: &str) -> Option<Filter> {
+    let leading = pattern.strip_prefix('%');
+    let trailing = pattern.strip_suffix('%');
+    match (leading.and_then(|p| p.strip_suffix('%')), leading, trailing) {
+        (Some(inner), _, _) if !inner.is_empty() && !inner.contains('%') => {
+            Some(Filter::contains(field, inner))
+        }
+        (None, None, Some(prefix)) if !prefix.contains('%') => {
+            Some(Filter::starts_with(field, prefix))
+        }
+        (None, Some(suffix), None) if !suffix.contains('%') => {
+            Some(Filter::ends_with(field, suffix))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a `<link-entity>` element.
+fn parse_link(element: &Element) -> Result<LinkEntityBuilder, Error> {
+    let link_type = match element.attribute("link-type") {
+        None | Some("inner") => LinkType::Inner,
+        Some("outer") => LinkType::Outer,
+        Some(other) => return Err(invalid(format!("unsupported link-type '{}'", other))),
+    };
+
+    let content = parse_content(element)?;
+    if !content.group_by.is_empty() || !content.aggregates.is_empty() {
+        return Err(invalid(
+            "aggregate columns in <link-entity> are not supported",
+        ));
+    }
+
+    let mut link = LinkEntityBuilder::new(
+        element.required("name")?,
+        element.required("from")?,
+        element.required("to")?,
+    )
+    .link_type(link_type);
+    if let Some(alias) = element.attribute("alias") {
+        link = link.alias(alias);
+    }
+    if !content.select.is_empty() {
+        link = link.select(&content.select_refs());
+    }
+    if let Some(filter) = content.filter {
+        link = link.filter(filter);
+    }
+    if let Some(order) = content.order_by {
+        link = link.order_by(order);
+    }
+    for nested in content.links {
+        link = link.with_link(nested);
+    }
+    Ok(link)
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+
+    #[test]
+    fn test_round_trip() {
+        let client = MockDataverse::new().client();
+        let xml = concat!(
+            r#"<fetch version="1.0" output-format="xml-platform" mapping="logical" distinct="false" top="50">"#,
+            r#"<entity name="account">"#,
+            r#"<attribute name="name"/><attribute name="revenue"/>"#,
+            r#"<filter type="and">"#,
+            r#"<condition attribute="statecode" operator="eq" value="0"/>"#,
+            r#"<condition attribute="name" operator="like" value="%Corp%"/>"#,
+            r#"<filter type="or">"#,
+            r#"<condition attribute="industrycode" operator="in"><value>1</value><value>2</value></condition>"#,
+            r#"<condition attribute="telephone1" operator="not-null"/>"#,
+            r#"</filter></filter>"#,
+            r#"<order attribute="revenue" descending="true"/><order attribute="name" descending="false"/>"#,
+            r#"<link-entity name="contact" from="contactid" to="primarycontactid" link-type="outer" alias="pc">"#,
+            r#"<attribute name="fullname"/>"#,
+            r#"</link-entity></entity></fetch>"#,
+        );
+
+        let builder = client.fetch_xml(xml).unwrap();
+        assert_eq!(builder.build_fetchxml(), xml);
+        assert_eq!(builder.top_value(), Some(50));
+        assert_eq!(builder.select_value(), ["name", "revenue"]);
+        assert!(matches!(
+            builder.filter_value(),
+            Some(Filter::And(filters)) if filters.len() == 3
+        ));
+
+        // Modify and re-render
+        let xml = builder
+            .and_filter(Filter::gt("revenue", 1000i32))
+            .page_size(10)
+            .build_fetchxml();
+        assert!(xml.contains(r#"count="10""#));
+        assert!(
+            xml.contains(r#"<condition attribute="revenue" operator="gt" value="1000"/></filter>"#)
+        );
+    }
+
+    #[test]
+    fn test_no_lock() {
+        let client = MockDataverse::new().client();
+        let xml = r#"<fetch no-lock="true"><entity name="account"/></fetch>"#;
+        let builder = client.fetch_xml(xml).unwrap();
+        assert!(builder.no_lock_value());
+        assert!(builder.build_fetchxml().contains(r#"no-lock="true""#));
+    }
+
+    #[test]
+    fn test_aggregates_and_pretty_printed() {
+        let client = MockDataverse::new().client();
+        let xml = r#"
+            <?xml version="1.0"?>
+            <fetch aggregate="true" page="2" paging-cookie="&lt;cookie/&gt;">
+              <!-- saved view -->
+              <entity name="opportunity">
+                <attribute name="ownerid" alias="owner" groupby="true" />
+                <attribute name="actualclosedate" alias="month" groupby="true" dategrouping="month" />
+                <attribute name="estimatedvalue" alias="total" aggregate="sum" />
+                <filter>
+                  <condition attribute="name" operator="eq" value="A &amp; B" />
+                </filter>
+              </entity>
+            </fetch>
+        "#;
+
+        let fetchxml = client.fetch_xml(xml).unwrap().build_fetchxml();
+        assert!(fetchxml.contains(r#"aggregate="true""#));
+        assert!(!fetchxml.contains("page="));
+        assert!(fetchxml.contains(r#"<attribute name="actualclosedate" alias="month" groupby="true" dategrouping="month"/>"#));
+        assert!(
+            fetchxml
+                .contains(r#"<attribute name="estimatedvalue" alias="total" aggregate="sum"/>"#)
+        );
+        assert!(fetchxml.contains(r#"value="A &amp; B""#));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let client = MockDataverse::new().client();
+        for xml in [
+            "",
+            "<fetch>",
+            "<entity name=\"account\"/>",
+            "<fetch><entity/></fetch>",
+            "<fetch><entity name=\"account\"><unknown/></entity></fetch>",
+            "<fetch><entity name=\"account\"></fetch></entity>",
+        ] {
+            assert!(
+                matches!(client.fetch_xml(xml), Err(Error::InvalidOperation(_))),
+                "{xml}"
+            );
+        }
+    }
+}
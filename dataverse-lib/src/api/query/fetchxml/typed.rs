@@ -0,0 +1,197 @@
+//! Typed FetchXML queries.
+
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use futures::stream;
+use serde::de::DeserializeOwned;
+
+use crate::api::query::typed::FromRecordFn;
+use crate::api::query::typed::deserialize_record;
+use crate::api::query::typed::entity_from_record;
+use crate::api::query::typed::from_records;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::stream::Prefetch;
+
+use super::builder::FetchBuilder;
+use super::pages::FetchXmlPages;
+
+/// A FetchXML query whose results are mapped to a user type.
+///
+/// Created with [`FetchBuilder::into_typed`] for a [`DataverseEntity`], or
+/// [`FetchBuilder::into_deserialized`] for any serde type, which can also
+/// read aliased columns of linked entities.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct Row {
+///     name: String,
+///     #[serde(rename = "pc.fullname")]
+///     contact: Option<String>,
+/// }
+///
+/// let rows: Vec<Row> = client
+///     .fetch(Entity::logical("account"))
+///     .select(&["name"])
+///     .link_entity("contact", "contactid", "primarycontactid", |l| {
+///         l.alias("pc").select(&["fullname"])
+///     })
+///     .into_deserialized::<Row>()
+///     .execute()
+///     .await?;
+/// ```
+pub struct TypedFetch<'a, T> {
+    builder: FetchBuilder<'a>,
+    from_record: FromRecordFn<T>,
+}
+
+impl<'a, T: DataverseEntity> TypedFetch<'a, T> {
+    /// Creates a typed query, selecting `T::COLUMNS` unless the builder
+    /// already has a selection.
+    pub(crate) fn new(builder: FetchBuilder<'a>) -> Self {
+        let builder = if builder.select_value().is_empty() {
+            builder.select(T::COLUMNS)
+        } else {
+            builder
+        };
+        Self {
+            builder,
+            from_record: entity_from_record,
+        }
+    }
+}
+
+impl<'a, T: DeserializeOwned> TypedFetch<'a, T> {
+    /// Creates a query deserializing each record with serde.
+    pub(crate) fn deserialized(builder: FetchBuilder<'a>) -> Self {
+        Self {
+            builder,
+            from_record: deserialize_record,
+        }
+    }
+}
+
+impl<'a, T> TypedFetch<'a, T> {
+    /// Returns the underlying fetch builder.
+    pub fn into_inner(self) -> FetchBuilder<'a> {
+        self.builder
+    }
+
+    /// Executes the query and returns the first page of results.
+    pub async fn execute(self) -> Result<Vec<T>, Error> {
+        let records = self.builder.execute().await?;
+        from_records(self.from_record, &records)
+    }
+
+    /// Executes the query and returns the first matching result.
+    pub async fn first(self) -> Result<Option<T>, Error> {
+        match self.builder.first().await? {
+            Some(record) => Ok(Some((self.from_record)(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts this query into an async iterator over typed pages.
+    pub fn into_async_iter(self) -> TypedFetchPages<T> {
+        TypedFetchPages {
+            pages: self.builder.into_async_iter(),
+            from_record: self.from_record,
+        }
+    }
+}
+
+/// Async iterator that yields pages of typed FetchXML results.
+pub struct TypedFetchPages<T> {
+    pages: FetchXmlPages,
+    from_record: FromRecordFn<T>,
+}
+
+impl<T> TypedFetchPages<T> {
+    /// Fetches the next page of results.
+    ///
+    /// Returns `None` when all pages have been consumed.
+    pub async fn next(&mut self) -> Option<Result<Vec<T>, Error>> {
+        let page = match self.pages.next().await? {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(from_records(self.from_record, page.records()))
+    }
+
+    /// Converts this iterator into a [`Stream`] of typed pages.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<T>, Error>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let from_record = self.from_record;
+        self.pages
+            .into_stream()
+            .map(move |page| page.and_then(|page| from_records(from_record, page.records())))
+    }
+
+    /// Fetches up to `pages` pages ahead in the background while the
+    /// consumer processes the current one. See [`FetchXmlPages::prefetch`].
+    pub fn prefetch(self, pages: usize) -> Prefetch<Vec<T>>
+    where
+        T: Send + 'static,
+    {
+        Prefetch::spawn(self.into_stream(), pages)
+    }
+
+    /// Converts this iterator into a [`Stream`] of typed records, fetching
+    /// pages as they're consumed.
+    pub fn into_record_stream(self) -> impl Stream<Item = Result<T, Error>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        self.into_stream()
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::mock::MockDataverse;
+    use crate::model::DataverseEntity;
+
+    #[derive(Debug, DataverseEntity)]
+    #[dataverse(entity = "contact", set = "contacts")]
+    struct Contact {
+        #[dataverse(id)]
+        id: Option<Uuid>,
+        fullname: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ContactRow {}
+
+    #[test]
+    fn test_typed_fetch_selects_columns() {
+        let client = MockDataverse::new().client();
+
+        let fetch = client
+            .fetch(Contact::entity())
+            .into_typed::<Contact>()
+            .into_inner();
+        assert_eq!(fetch.select_value(), Contact::COLUMNS);
+
+        let fetch = client
+            .fetch(Contact::entity())
+            .select(&["fullname"])
+            .into_typed::<Contact>()
+            .into_inner();
+        assert_eq!(fetch.select_value(), ["fullname"]);
+
+        let fetch = client
+            .fetch(Contact::entity())
+            .into_deserialized::<ContactRow>()
+            .into_inner();
+        assert!(fetch.select_value().is_empty());
+    }
+}
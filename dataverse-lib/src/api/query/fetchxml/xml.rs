@@ -3,6 +3,7 @@
 use crate::api::query::Direction;
 use crate::api::query::Filter;
 use crate::api::query::OrderBy;
+use crate::api::query::datetime_literal;
 use crate::model::Value;
 
 /// Escapes a string for use in XML attribute values.
@@ -17,6 +18,9 @@ pub fn escape_xml(s: &str) -> String {
 /// Converts a `Filter` to FetchXML `<filter>` and `<condition>` elements.
 pub fn filter_to_fetchxml(filter: &Filter) -> String {
     match filter {
+        // FetchXML compares against null with dedicated operators
+        Filter::Eq(field, Value::Null) => filter_to_fetchxml(&Filter::IsNull(field.clone())),
+        Filter::Ne(field, Value::Null) => filter_to_fetchxml(&Filter::IsNotNull(field.clone())),
         Filter::Eq(field, value) => {
             format!(
                 r#"<condition attribute="{}" operator="eq" value="{}"/>"#,
@@ -128,7 +132,7 @@ pub fn value_to_fetchxml(value: &Value) -> String {
         Value::Decimal(d) => d.to_string(),
         Value::String(s) => s.clone(),
         Value::Guid(g) => g.to_string(),
-        Value::DateTime(dt) => dt.to_rfc3339(),
+        Value::DateTime(dt) => datetime_literal(dt),
         Value::Money(m) => m.value().to_string(),
         Value::OptionSet(o) => o.value.to_string(),
         Value::EntityReference(r) => r.id.to_string(),
@@ -174,7 +178,13 @@ pub fn attributes_to_fetchxml(fields: &[String]) -> String {
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
     use super::*;
+    use crate::model::types::Money;
 
     #[test]
     fn test_escape_xml() {
@@ -198,6 +208,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_typed_values() {
+        let created = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+        assert_eq!(
+            filter_to_fetchxml(&Filter::ge("createdon", created)),
+            r#"<condition attribute="createdon" operator="ge" value="2024-01-15T09:30:00Z"/>"#
+        );
+        assert_eq!(
+            filter_to_fetchxml(&Filter::eq("donotemail", true)),
+            r#"<condition attribute="donotemail" operator="eq" value="1"/>"#
+        );
+        assert_eq!(
+            filter_to_fetchxml(&Filter::gt("revenue", Money::new(Decimal::new(150050, 2)))),
+            r#"<condition attribute="revenue" operator="gt" value="1500.50"/>"#
+        );
+        assert_eq!(
+            filter_to_fetchxml(&Filter::eq("parentaccountid", None::<Uuid>)),
+            r#"<condition attribute="parentaccountid" operator="null"/>"#
+        );
+        assert_eq!(
+            filter_to_fetchxml(&Filter::ne("parentaccountid", None::<Uuid>)),
+            r#"<condition attribute="parentaccountid" operator="not-null"/>"#
+        );
+    }
+
     #[test]
     fn test_like_conditions() {
         assert_eq!(
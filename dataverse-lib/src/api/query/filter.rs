@@ -1,8 +1,15 @@
 //! Filter types for OData and FetchXML queries.
 
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::odata::parse_filter;
+use super::odata::parse_filter_url;
+use super::odata::url::filter_to_odata;
+use crate::error::Error;
 use crate::model::Value;
 
 /// A filter condition for querying records.
@@ -34,6 +41,35 @@ use crate::model::Value;
 /// // Negation (OData only)
 /// let filter = Filter::eq("statecode", 0).not();
 /// ```
+///
+/// # Typed values
+///
+/// Comparison values take anything convertible to [`Value`], and are written
+/// as the right literal for each query syntax:
+///
+/// | Type | OData | FetchXML |
+/// |------|-------|----------|
+/// | `&str`, `String` | `'O''Brien'` (quoted, escaped) | `O&apos;Brien` |
+/// | `Uuid`, `EntityReference` | `00000000-...` (unquoted) | `00000000-...` |
+/// | `DateTime<Utc>` | `2024-01-15T09:30:00Z` | `2024-01-15T09:30:00Z` |
+/// | `bool` | `true` / `false` | `1` / `0` |
+/// | `OptionSetValue`, option set enums | `1` | `1` |
+/// | `Money`, `Decimal` | `1500.50` | `1500.50` |
+/// | `None` | `null` | `null` / `not-null` operator |
+///
+/// ```
+/// use chrono::Utc;
+/// use dataverse_lib::api::query::Filter;
+/// use dataverse_lib::model::types::Money;
+/// use uuid::Uuid;
+///
+/// let filter = Filter::and([
+///     Filter::eq("parentcustomerid", Uuid::nil()),
+///     Filter::ge("createdon", Utc::now()),
+///     Filter::eq("donotemail", false),
+///     Filter::gt("revenue", Money::new(1_000_000.into())),
+/// ]);
+/// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Filter {
     /// Equality: `field eq value`
@@ -142,6 +178,65 @@ impl Filter {
         Filter::Raw(filter.into())
     }
 
+    /// Parses an OData `$filter` expression.
+    ///
+    /// Comparisons, `and`, `or`, `not`, and `contains`, `startswith` and
+    /// `endswith` become typed filters; anything else (e.g. `any` lambdas,
+    /// `Microsoft.Dynamics.CRM.*` query functions, column comparisons) is
+    /// kept verbatim as [`Filter::Raw`]. [`to_odata`](Self::to_odata) writes
+    /// the filter back.
+    ///
+    /// Unquoted numbers without a fraction or exponent become `Int` or
+    /// `Long`, those with a fraction `Decimal`, and those with an exponent
+    /// `Float`.
+    ///
+    /// Returns [`Error::InvalidOperation`] if the expression is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dataverse_lib::api::query::Filter;
+    ///
+    /// let filter = Filter::from_odata("statecode eq 0 and startswith(name,'Con')").unwrap();
+    /// assert_eq!(
+    ///     filter,
+    ///     Filter::and([
+    ///         Filter::eq("statecode", 0),
+    ///         Filter::starts_with("name", "Con"),
+    ///     ])
+    /// );
+    /// assert_eq!(filter.to_odata(), "(statecode eq 0 and startswith(name,'Con'))");
+    /// ```
+    pub fn from_odata(filter: &str) -> Result<Self, Error> {
+        parse_filter(filter)
+    }
+
+    /// Parses the `$filter` of an OData URL or query string, e.g. one
+    /// copied from the browser.
+    ///
+    /// Returns `None` if the URL has no `$filter`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dataverse_lib::api::query::Filter;
+    ///
+    /// let url = "https://org.crm.dynamics.com/api/data/v9.2/accounts\
+    ///            ?$select=name&$filter=revenue%20gt%201000";
+    /// assert_eq!(
+    ///     Filter::from_odata_url(url).unwrap(),
+    ///     Some(Filter::gt("revenue", 1000))
+    /// );
+    /// ```
+    pub fn from_odata_url(url: &str) -> Result<Option<Self>, Error> {
+        parse_filter_url(url)
+    }
+
+    /// Writes the filter as an OData `$filter` expression.
+    pub fn to_odata(&self) -> String {
+        filter_to_odata(self)
+    }
+
     /// Combines this filter with another using logical AND.
     ///
     /// # Example
@@ -247,3 +342,9 @@ impl From<Filter> for ODataFilter {
         ODataFilter::Base(filter)
     }
 }
+
+/// Formats a datetime as a filter literal: UTC with a `Z` suffix, and
+/// fractional seconds only when present.
+pub(crate) fn datetime_literal(dt: &DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
@@ -8,20 +8,27 @@
 //! - [`Filter`] - Filter conditions used by both OData and FetchXML
 //! - [`OrderBy`] - Ordering specification for query results
 //! - [`Page`] - A page of query results with pagination info
+//! - [`PageCursor`] - A saved position for resuming a paged query
 //!
 //! # Query Builders
 //!
 //! - [`odata`] - OData query builder (uses `$filter`, `$select`, `$expand`, etc.)
 //! - [`fetchxml`] - FetchXML query builder (uses XML-based query language)
 
+mod cursor;
 pub mod fetchxml;
 mod filter;
 pub mod odata;
 mod order;
 mod page;
+mod typed;
 
+pub use cursor::PageCursor;
+pub(crate) use cursor::Position;
+pub(crate) use cursor::query_hash;
 pub use filter::Filter;
 pub use filter::ODataFilter;
+pub(crate) use filter::datetime_literal;
 pub use order::Direction;
 pub use order::OrderBy;
 pub use page::Page;
@@ -1,22 +1,36 @@
 //! OData query builder.
 
-use std::collections::HashMap;
 use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
 
 use crate::DataverseClient;
+use crate::api::Impersonation;
+use crate::api::execute::apply_impersonation;
+use crate::api::passthrough::apply_headers;
+use crate::api::passthrough::push_query_options;
 use crate::api::query::Filter;
 use crate::api::query::ODataFilter;
 use crate::api::query::OrderBy;
+use crate::error::ApiError;
 use crate::error::Error;
+use crate::model::Annotations;
+use crate::model::DataverseEntity;
 use crate::model::Entity;
 use crate::model::Record;
 
 use super::expand::ExpandBuilder;
 use super::pages::ODataPages;
+use super::typed::TypedQuery;
 use super::url::build_select_expand_params;
 use super::url::odata_filter_to_string;
 use super::url::order_to_odata;
 
+/// Dataverse error code for aggregate queries over more than 50,000 records.
+const AGGREGATE_LIMIT_EXCEEDED: &str = "0x8004e023";
+
 /// Builder for constructing OData queries.
 ///
 /// Use [`DataverseClient::query`] to create a query builder.
@@ -47,7 +61,11 @@ pub struct QueryBuilder {
     page_size: Option<usize>,
     expands: Vec<ExpandBuilder>,
     include_count: bool,
+    annotations: Annotations,
     bypass_cache: bool,
+    impersonate: Option<Impersonation>,
+    query_options: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
 }
 
 impl QueryBuilder {
@@ -62,7 +80,11 @@ impl QueryBuilder {
             page_size: None,
             expands: Vec::new(),
             include_count: false,
+            annotations: Annotations::All,
             bypass_cache: false,
+            impersonate: None,
+            query_options: Vec::new(),
+            headers: Vec::new(),
         }
     }
 
@@ -133,12 +155,23 @@ impl QueryBuilder {
     /// Includes the total count of matching records in the response.
     ///
     /// When enabled, `Page::total_count()` will return the total number of
-    /// records matching the query (not just the current page).
+    /// records matching the query (not just the current page). Dataverse
+    /// stops counting at 5000, reported by `Page::total_count_limit_exceeded()`;
+    /// use [`count`](Self::count) for exact totals.
     pub fn include_count(mut self) -> Self {
         self.include_count = true;
         self
     }
 
+    /// Sets which annotations the server includes with each record.
+    ///
+    /// All annotations are requested by default. Read them back with
+    /// [`Record::get_formatted`] and [`Record::annotation`].
+    pub fn annotations(mut self, annotations: Annotations) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
     /// Bypasses the query cache for this query.
     ///
     /// By default, OData query results are cached using the client's cache
@@ -149,6 +182,32 @@ impl QueryBuilder {
         self
     }
 
+    /// Runs this query as another user.
+    ///
+    /// Results are cached separately per impersonated user.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.impersonate = Some(user.into());
+        self
+    }
+
+    /// Appends a query option the builder doesn't cover, such as a custom
+    /// `$apply`, to the first page's URL.
+    ///
+    /// The query fails if the builder already sets the option.
+    pub fn query_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a header to every page request.
+    ///
+    /// The query fails if the builder already sets the header, e.g.
+    /// `Prefer`, which carries the annotations and page size.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
     /// Transforms lookup field names to OData format (`_fieldname_value`).
     ///
     /// This fetches entity metadata to identify lookup fields and transforms
@@ -171,15 +230,6 @@ impl QueryBuilder {
             .map(|attr| attr.logical_name.clone())
             .collect();
 
-        // Build a mapping from lookup logical name → schema name for nav properties.
-        // In Dataverse OData, navigation property names use the SchemaName (PascalCase),
-        // not the LogicalName (lowercase). e.g., "nrq_projectid" → "nrq_ProjectId".
-        let lookup_nav_names: HashMap<String, String> = attributes
-            .iter()
-            .filter(|attr| attr.is_lookup())
-            .map(|attr| (attr.logical_name.clone(), attr.schema_name.clone()))
-            .collect();
-
         // Transform select fields
         self.select = self
             .select
@@ -197,8 +247,8 @@ impl QueryBuilder {
             self.order_by = Some(transform_order_by(order, &lookup_fields));
         }
 
-        // Transform expand navigation property names from logical to schema names
-        transform_expand_nav_properties(&mut self.expands, &lookup_nav_names);
+        // Transform expand navigation property names and nested options
+        transform_expands(client, &mut self.expands, entity_logical_name).await?;
 
         Ok(())
     }
@@ -260,11 +310,31 @@ impl QueryBuilder {
         self.page_size
     }
 
+    /// Returns the annotations to request.
+    pub(crate) fn annotations_value(&self) -> &Annotations {
+        &self.annotations
+    }
+
     /// Returns whether caching is bypassed for this query.
     pub(crate) fn bypass_cache_value(&self) -> bool {
         self.bypass_cache
     }
 
+    /// Returns the user this query impersonates, if any.
+    pub(crate) fn impersonate_value(&self) -> Option<Impersonation> {
+        self.impersonate
+    }
+
+    /// Returns the extra query options.
+    pub(crate) fn query_options_value(&self) -> &[(String, String)] {
+        &self.query_options
+    }
+
+    /// Returns the extra headers.
+    pub(crate) fn headers_value(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
     /// Returns a reference to the entity.
     pub fn entity(&self) -> &Entity {
         &self.entity
@@ -310,9 +380,13 @@ impl QueryBuilder {
 
     /// Executes a count query and returns the number of matching records.
     ///
-    /// Uses `$apply=aggregate($count as count)` which returns accurate counts
-    /// (the `/$count` endpoint is limited to 5000 on some Dataverse instances).
+    /// Uses `$apply=aggregate($count as count)`, which is exact up to the
+    /// aggregate query limit of 50,000 records (`$count` stops at 5000).
+    /// Past that, unfiltered counts fall back to `RetrieveTotalRecordCount`,
+    /// which reports a snapshot that may lag recent changes, and filtered
+    /// counts page through the matching record ids.
     pub async fn count(mut self, client: &DataverseClient) -> Result<usize, Error> {
+        let fallback = self.clone();
         let entity_set_name = self.resolve_entity_set(client).await?;
 
         // Resolve logical name for lookup field transformation
@@ -329,6 +403,25 @@ impl QueryBuilder {
         self.transform_lookup_fields(client, &entity_logical_name)
             .await?;
 
+        match self.aggregate_count(client, &entity_set_name).await {
+            Err(Error::Api(ref e)) if aggregate_unavailable(e) => {}
+            result => return result,
+        }
+
+        if self.filter.is_none() {
+            return client
+                .total_record_count(Entity::logical(&entity_logical_name))
+                .await;
+        }
+        fallback.paged_count(client, &entity_logical_name).await
+    }
+
+    /// Counts records with `$apply=aggregate($count as count)`.
+    async fn aggregate_count(
+        &self,
+        client: &DataverseClient,
+        entity_set_name: &str,
+    ) -> Result<usize, Error> {
         let base_url = client.base_url().trim_end_matches('/');
         let api_version = client.api_version();
 
@@ -343,6 +436,8 @@ impl QueryBuilder {
             url.push_str(&format!("&$filter={}", urlencoding::encode(&filter_str)));
         }
 
+        push_query_options(&mut url, &self.query_options)?;
+
         log::debug!("[QueryBuilder] Count URL: {}", url);
 
         // Build OData headers
@@ -359,6 +454,10 @@ impl QueryBuilder {
             "Accept",
             reqwest::header::HeaderValue::from_static("application/json"),
         );
+        if let Some(impersonation) = &self.impersonate {
+            apply_impersonation(&mut headers, impersonation);
+        }
+        apply_headers(&mut headers, &self.headers)?;
 
         let response: reqwest::Response = client
             .request(reqwest::Method::GET, &url, Some(headers), None)
@@ -386,14 +485,61 @@ impl QueryBuilder {
         Ok(count as usize)
     }
 
+    /// Counts records by paging through their ids.
+    ///
+    /// Stops after the first page when its `$count` isn't capped.
+    async fn paged_count(
+        self,
+        client: &DataverseClient,
+        entity_logical_name: &str,
+    ) -> Result<usize, Error> {
+        let (_, primary_id_attribute) = client.resolve_entity_core(entity_logical_name).await?;
+        let builder = Self {
+            select: vec![primary_id_attribute],
+            order_by: None,
+            top: None,
+            expands: Vec::new(),
+            include_count: true,
+            ..self
+        };
+
+        let mut pages = builder.into_async_iter(client);
+        let mut count = 0;
+        while let Some(page) = pages.next(client).await {
+            let page = page?;
+            if count == 0
+                && !page.total_count_limit_exceeded()
+                && let Some(total) = page.total_count()
+            {
+                return Ok(total);
+            }
+            count += page.len();
+        }
+        Ok(count)
+    }
+
     /// Converts this query builder into an async iterator over pages.
     pub fn into_async_iter(self, client: &DataverseClient) -> ODataPages {
         ODataPages::new(self, client)
     }
+
+    /// Converts this query builder into a query returning `T` instead of records.
+    ///
+    /// Selects `T::COLUMNS` unless `.select()` was called.
+    pub fn into_typed<T: DataverseEntity>(self) -> TypedQuery<T> {
+        TypedQuery::new(self)
+    }
+
+    /// Converts this query builder into a query deserializing each record
+    /// into `T` with serde. See [`Record::deserialize_into`] for the fields
+    /// `T` can read.
+    pub fn into_deserialized<T: DeserializeOwned>(self) -> TypedQuery<T> {
+        TypedQuery::deserialized(self)
+    }
 }
 
 /// Transforms a field name to OData lookup format if it's a lookup field.
-fn transform_field_name(field: &str, lookup_fields: &HashSet<String>) -> String {
+pub(super) fn transform_field_name(field: &str, lookup_fields: &HashSet<String>) -> String {
     if lookup_fields.contains(field) {
         format!("_{}_value", field)
     } else {
@@ -402,7 +548,10 @@ fn transform_field_name(field: &str, lookup_fields: &HashSet<String>) -> String
 }
 
 /// Transforms field names in an ODataFilter.
-fn transform_odata_filter(filter: &ODataFilter, lookup_fields: &HashSet<String>) -> ODataFilter {
+pub(super) fn transform_odata_filter(
+    filter: &ODataFilter,
+    lookup_fields: &HashSet<String>,
+) -> ODataFilter {
     match filter {
         ODataFilter::Base(f) => ODataFilter::Base(transform_filter(f, lookup_fields)),
         ODataFilter::Not(inner) => {
@@ -460,36 +609,123 @@ fn transform_filter(filter: &Filter, lookup_fields: &HashSet<String>) -> Filter
     }
 }
 
-/// Transforms expand navigation property names from logical to schema names.
+/// Transforms expands against the entity they navigate from.
 ///
-/// In Dataverse OData, navigation property names use the SchemaName (PascalCase),
+/// Navigation property names are renamed from logical to schema names: in
+/// Dataverse OData, navigation property names use the SchemaName (PascalCase),
 /// not the LogicalName (lowercase). For example, a lookup attribute with logical
 /// name `nrq_projectid` has navigation property name `nrq_ProjectId`.
 ///
-/// This only transforms the top-level navigation property names using the provided
-/// entity's lookup metadata. Nested expand contents (select/filter) would need
-/// metadata from the expanded entity and are not transformed here.
-fn transform_expand_nav_properties(
-    expands: &mut [ExpandBuilder],
-    lookup_nav_names: &HashMap<String, String>,
-) {
-    for expand in expands.iter_mut() {
-        let nav = expand.navigation_property();
-        if let Some(schema_name) = lookup_nav_names.get(nav) {
-            if nav != schema_name {
+/// Lookup fields in each expand's own options are transformed with the
+/// expanded entity's metadata, recursing into nested expands. Expands whose
+/// target entity can't be resolved are left as is.
+pub(super) fn transform_expands<'a>(
+    client: &'a DataverseClient,
+    expands: &'a mut [ExpandBuilder],
+    entity_logical_name: &'a str,
+) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>> {
+    Box::pin(async move {
+        if expands.is_empty() {
+            return Ok(());
+        }
+        let attributes = client
+            .metadata()
+            .attributes(Entity::logical(entity_logical_name))
+            .await?;
+
+        for expand in expands.iter_mut() {
+            let nav = expand.navigation_property().to_string();
+            let lookup = attributes
+                .iter()
+                .filter(|attr| attr.is_lookup())
+                .find(|attr| attr.logical_name == nav || attr.schema_name == nav);
+
+            if let Some(attr) = lookup
+                && attr.schema_name != nav
+            {
                 log::debug!(
                     "[QueryBuilder] Transforming expand nav property: {} → {}",
                     nav,
-                    schema_name
+                    attr.schema_name
                 );
-                expand.set_navigation_property(schema_name.clone());
+                expand.set_navigation_property(attr.schema_name.clone());
+            }
+
+            if !expand.has_options() {
+                continue;
             }
+            let target = match lookup {
+                Some(attr) if attr.targets.len() == 1 => Some(attr.targets[0].clone()),
+                _ => expand_target(client, entity_logical_name, &nav).await?,
+            };
+            let Some(target) = target else {
+                log::debug!(
+                    "[QueryBuilder] Couldn't resolve the entity for expand {}; leaving its options as is",
+                    nav
+                );
+                continue;
+            };
+
+            let lookup_fields: HashSet<String> = client
+                .metadata()
+                .attributes(Entity::logical(&target))
+                .await?
+                .iter()
+                .filter(|attr| attr.is_lookup())
+                .map(|attr| attr.logical_name.clone())
+                .collect();
+            expand.transform_lookup_fields(&lookup_fields);
+            transform_expands(client, expand.expands_mut(), &target).await?;
         }
-    }
+        Ok(())
+    })
+}
+
+/// Resolves the entity a navigation property points to from the entity's
+/// relationships.
+async fn expand_target(
+    client: &DataverseClient,
+    entity_logical_name: &str,
+    nav: &str,
+) -> Result<Option<String>, Error> {
+    let metadata = client
+        .metadata()
+        .entity(Entity::logical(entity_logical_name))
+        .await?;
+    let nav = Some(nav);
+
+    let many_to_one = metadata
+        .many_to_one_relationships
+        .iter()
+        .find(|r| r.referencing_entity_navigation_property_name.as_deref() == nav)
+        .map(|r| r.referenced_entity.clone());
+    let one_to_many = || {
+        metadata
+            .one_to_many_relationships
+            .iter()
+            .find(|r| r.referenced_entity_navigation_property_name.as_deref() == nav)
+            .map(|r| r.referencing_entity.clone())
+    };
+    let many_to_many = || {
+        metadata.many_to_many_relationships.iter().find_map(|r| {
+            if r.entity1_logical_name == entity_logical_name
+                && r.entity1_navigation_property_name.as_deref() == nav
+            {
+                Some(r.entity2_logical_name.clone())
+            } else if r.entity2_logical_name == entity_logical_name
+                && r.entity2_navigation_property_name.as_deref() == nav
+            {
+                Some(r.entity1_logical_name.clone())
+            } else {
+                None
+            }
+        })
+    };
+    Ok(many_to_one.or_else(one_to_many).or_else(many_to_many))
 }
 
 /// Transforms field names in an OrderBy.
-fn transform_order_by(order: &OrderBy, lookup_fields: &HashSet<String>) -> OrderBy {
+pub(super) fn transform_order_by(order: &OrderBy, lookup_fields: &HashSet<String>) -> OrderBy {
     OrderBy {
         fields: order
             .fields
@@ -498,3 +734,117 @@ fn transform_order_by(order: &OrderBy, lookup_fields: &HashSet<String>) -> Order
             .collect(),
     }
 }
+
+/// Returns whether an aggregate count failed because aggregation isn't
+/// available for the query, e.g. when it exceeds the 50,000 record limit.
+fn aggregate_unavailable(error: &ApiError) -> bool {
+    match error {
+        ApiError::Http { status: 501, .. } => true,
+        ApiError::Http {
+            code: Some(code), ..
+        } => code.eq_ignore_ascii_case(AGGREGATE_LIMIT_EXCEEDED),
+        _ => false,
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::api::query::Filter;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    #[tokio::test]
+    async fn test_count_past_limit() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let client = mock.client();
+        for i in 0..5003 {
+            let statecode = if i == 0 { 1 } else { 0 };
+            mock.insert(
+                Entity::set("accounts"),
+                Record::new("account").set("statecode", statecode),
+            );
+        }
+
+        // Aggregation is unavailable, so these use the fallbacks
+        let total = client
+            .query(Entity::logical("account"))
+            .count(&client)
+            .await
+            .unwrap();
+        assert_eq!(total, 5003);
+        let active = client
+            .query(Entity::logical("account"))
+            .filter(Filter::eq("statecode", 0i32))
+            .count(&client)
+            .await
+            .unwrap();
+        assert_eq!(active, 5002);
+        let inactive = client
+            .query(Entity::logical("account"))
+            .filter(Filter::eq("statecode", 1i32))
+            .count(&client)
+            .await
+            .unwrap();
+        assert_eq!(inactive, 1);
+
+        let mut pages = client
+            .query(Entity::logical("account"))
+            .include_count()
+            .page_size(100)
+            .into_async_iter(&client);
+        let page = pages.next(&client).await.unwrap().unwrap();
+        assert_eq!(page.len(), 100);
+        assert_eq!(page.total_count(), Some(5000));
+        assert!(page.total_count_limit_exceeded());
+    }
+
+    #[tokio::test]
+    async fn test_nested_expand_lookup_fields() {
+        let mock = MockDataverse::new()
+            .entity(
+                MockEntity::new("account", "accounts", "accountid")
+                    .primary_name("name")
+                    .lookup("primarycontactid", "contact"),
+            )
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        let id = Uuid::nil();
+
+        let mut query =
+            client
+                .query(Entity::logical("contact"))
+                .expand("parentcustomerid", |account| {
+                    account
+                        .select(&["name", "primarycontactid"])
+                        .filter(Filter::ne("primarycontactid", id))
+                        .expand("primarycontactid", |contact| {
+                            contact.select(&["fullname", "parentcustomerid"])
+                        })
+                });
+        query
+            .transform_lookup_fields(&client, "contact")
+            .await
+            .unwrap();
+
+        let url = query.build_url(&client, "contacts");
+        let expand = url.split_once("$expand=").unwrap().1;
+        assert_eq!(
+            urlencoding::decode(expand).unwrap(),
+            format!(
+                "parentcustomerid($select=name,_primarycontactid_value;\
+                 $filter=_primarycontactid_value ne {};\
+                 $expand=primarycontactid($select=fullname,_parentcustomerid_value))",
+                id
+            )
+        );
+    }
+}
@@ -1,19 +1,32 @@
 //! OData $expand builder for nested navigation properties.
 
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde::Serialize;
+
 #[cfg(test)]
 use crate::api::query::Filter;
 use crate::api::query::ODataFilter;
 use crate::api::query::OrderBy;
-use serde::Deserialize;
-use serde::Serialize;
 
+use super::builder::transform_field_name;
+use super::builder::transform_odata_filter;
+use super::builder::transform_order_by;
 use super::url::odata_filter_to_string;
 use super::url::order_to_odata;
 
 /// Builder for constructing OData `$expand` clauses.
 ///
 /// Supports nested query options within the expand, allowing filtering,
-/// selecting, and ordering of related records.
+/// selecting, and ordering of related records. Lookup fields in these options
+/// use logical names, like the top-level query; they're resolved against the
+/// expanded entity when the query runs.
+///
+/// Single-valued navigation properties expand into a nested record
+/// ([`Record::get_record`](crate::model::Record::get_record)), collection-valued
+/// ones into a list of records
+/// ([`Record::get_records`](crate::model::Record::get_records)).
 ///
 /// # Example
 ///
@@ -115,6 +128,30 @@ impl ExpandBuilder {
         &mut self.expands
     }
 
+    /// Returns whether this expand has any nested query options.
+    pub(crate) fn has_options(&self) -> bool {
+        !self.select.is_empty()
+            || self.filter.is_some()
+            || self.order_by.is_some()
+            || !self.expands.is_empty()
+    }
+
+    /// Transforms lookup field names in select, filter and order_by to OData
+    /// format (`_fieldname_value`).
+    pub(crate) fn transform_lookup_fields(&mut self, lookup_fields: &HashSet<String>) {
+        self.select = self
+            .select
+            .iter()
+            .map(|field| transform_field_name(field, lookup_fields))
+            .collect();
+        if let Some(ref filter) = self.filter {
+            self.filter = Some(transform_odata_filter(filter, lookup_fields));
+        }
+        if let Some(ref order) = self.order_by {
+            self.order_by = Some(transform_order_by(order, lookup_fields));
+        }
+    }
+
     /// Converts this expand builder to an OData `$expand` clause.
     ///
     /// Returns the full expand expression including nested options.
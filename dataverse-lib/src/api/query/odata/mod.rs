@@ -25,12 +25,18 @@
 mod builder;
 mod expand;
 mod pages;
+mod parse;
 mod related;
+mod typed;
 pub(crate) mod url;
 
 pub use builder::QueryBuilder;
 pub use expand::ExpandBuilder;
 pub use pages::ODataPages;
 pub use pages::QUERY_CACHE_PREFIX;
+pub(crate) use parse::parse_filter;
+pub(crate) use parse::parse_filter_url;
 pub use related::RelatedPages;
 pub use related::RelatedQueryBuilder;
+pub use typed::TypedPages;
+pub use typed::TypedQuery;
@@ -1,5 +1,8 @@
 //! Async iterator for OData query pagination.
 
+use futures::Stream;
+use futures::TryStreamExt;
+use futures::stream;
 use reqwest::Method;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
@@ -11,12 +14,24 @@ use sha2::Sha256;
 use uuid::Uuid;
 
 use crate::DataverseClient;
+use crate::api::Impersonation;
+use crate::api::execute::apply_impersonation;
+use crate::api::passthrough::apply_headers;
+use crate::api::passthrough::push_query_options;
 use crate::api::query::Page;
+use crate::api::query::PageCursor;
+use crate::api::query::Position;
+use crate::api::query::query_hash;
 use crate::cache::CachedValue;
 use crate::error::ApiError;
 use crate::error::Error;
 use crate::model::Record;
 use crate::model::Value;
+use crate::model::loaded_records;
+use crate::stream::Prefetch;
+use crate::telemetry::InSpan;
+use crate::telemetry::Span;
+use crate::telemetry::span;
 
 use super::builder::QueryBuilder;
 
@@ -42,12 +57,32 @@ pub const QUERY_CACHE_PREFIX: &str = "query:";
 ///     }
 /// }
 /// ```
+///
+/// Use [`into_stream`](Self::into_stream) or
+/// [`into_record_stream`](Self::into_record_stream) to compose with
+/// [`StreamExt`](futures::StreamExt) combinators, or [`prefetch`](Self::prefetch)
+/// to fetch pages ahead of a slow consumer:
+///
+/// ```ignore
+/// client.query(Entity::logical("account"))
+///     .into_async_iter(&client)
+///     .into_record_stream(&client)
+///     .try_for_each_concurrent(8, |account| async move {
+///         process(account).await
+///     })
+///     .await?;
+/// ```
+///
+/// Save [`cursor`](Self::cursor) after each page to [`resume`](Self::resume)
+/// the query later. See [`PageCursor`].
 #[derive(Clone)]
 pub struct ODataPages {
     /// The initial URL (built from query builder).
     initial_url: Option<String>,
     /// Page size preference.
     page_size: Option<usize>,
+    /// Annotations preference (`odata.include-annotations`), if any.
+    annotations: Option<String>,
     /// The next URL to fetch (from @odata.nextLink).
     next_url: Option<String>,
     /// Whether we've exhausted all pages.
@@ -58,25 +93,67 @@ pub struct ODataPages {
     primary_id_attribute: Option<String>,
     /// Whether to bypass the cache for this query.
     bypass_cache: bool,
+    /// User to run the query as (overrides the client's impersonation).
+    impersonate: Option<Impersonation>,
+    /// Extra query options for the first page.
+    query_options: Vec<(String, String)>,
+    /// Extra headers for every page.
+    headers: Vec<(String, String)>,
+    /// Hash of the query, for cursors (set with the initial URL).
+    query_hash: Option<String>,
+    /// Position after the last page returned.
+    position: Option<Position>,
+    /// Cursor to resume from on the first call.
+    resume: Option<PageCursor>,
 }
 
 impl ODataPages {
     /// Creates a new async iterator from a query builder.
     pub(crate) fn new(builder: QueryBuilder, _client: &DataverseClient) -> Self {
         let page_size = builder.page_size_value();
+        let annotations = builder.annotations_value().prefer();
         let bypass_cache = builder.bypass_cache_value();
+        let impersonate = builder.impersonate_value();
+        let query_options = builder.query_options_value().to_vec();
+        let headers = builder.headers_value().to_vec();
 
         Self {
             initial_url: None,
             page_size,
+            annotations,
             next_url: None,
             done: false,
             needs_resolution: Some(builder),
             primary_id_attribute: None,
             bypass_cache,
+            impersonate,
+            query_options,
+            headers,
+            query_hash: None,
+            position: None,
+            resume: None,
         }
     }
 
+    /// Continues from a cursor returned by [`cursor`](Self::cursor) on the
+    /// same query, e.g. in an earlier run, instead of the first page.
+    ///
+    /// The first call to [`next`](Self::next) returns
+    /// [`Error::InvalidOperation`] if the cursor belongs to a different
+    /// query.
+    pub fn resume(mut self, cursor: PageCursor) -> Self {
+        self.resume = Some(cursor);
+        self
+    }
+
+    /// Returns the position after the last page returned, or `None` before
+    /// the first page.
+    pub fn cursor(&self) -> Option<PageCursor> {
+        let query = self.query_hash.clone()?;
+        let position = self.position.clone()?;
+        Some(PageCursor::new(query, position))
+    }
+
     /// Fetches the next page of results.
     ///
     /// Returns `None` when all pages have been consumed.
@@ -85,6 +162,52 @@ impl ODataPages {
             return None;
         }
 
+        let span = span!(
+            "dataverse.query",
+            kind = "odata",
+            cache = tracing::field::Empty
+        );
+        self.next_page(client).in_span(span).await
+    }
+
+    /// Converts this iterator into a [`Stream`] of pages.
+    pub fn into_stream(
+        self,
+        client: &DataverseClient,
+    ) -> impl Stream<Item = Result<Page, Error>> + Send + '_ {
+        stream::unfold(self, move |mut pages| async move {
+            let page = pages.next(client).await?;
+            Some((page, pages))
+        })
+    }
+
+    /// Converts this iterator into a [`Stream`] of records, fetching pages
+    /// as they're consumed.
+    pub fn into_record_stream(
+        self,
+        client: &DataverseClient,
+    ) -> impl Stream<Item = Result<Record, Error>> + Send + '_ {
+        self.into_stream(client)
+            .map_ok(|page| stream::iter(page.into_records().into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Fetches up to `pages` pages ahead in the background while the
+    /// consumer processes the current one.
+    ///
+    /// `@odata.nextLink` paging is sequential, so pages are still requested
+    /// one at a time, but the requests overlap with the consumer's work.
+    /// See [`Prefetch`].
+    pub fn prefetch(self, client: &DataverseClient, pages: usize) -> Prefetch<Page> {
+        let client = client.clone();
+        let source = stream::unfold((self, client), |(mut pages, client)| async move {
+            let page = pages.next(&client).await?;
+            Some((page, (pages, client)))
+        });
+        Prefetch::spawn(source, pages)
+    }
+
+    async fn next_page(&mut self, client: &DataverseClient) -> Option<Result<Page, Error>> {
         // Determine which URL to fetch
         let url = if let Some(mut builder) = self.needs_resolution.take() {
             // First call: resolve entity and build URL
@@ -133,9 +256,32 @@ impl ODataPages {
                 return Some(Err(e));
             }
 
-            let url = builder.build_url(client, &entity_set_name);
+            let mut url = builder.build_url(client, &entity_set_name);
+            if let Err(e) = push_query_options(&mut url, &self.query_options) {
+                self.done = true;
+                return Some(Err(e));
+            }
             self.initial_url = Some(url.clone());
-            url
+            let query = query_hash(&[&url, self.prefer().as_deref().unwrap_or_default()]);
+            self.query_hash = Some(query.clone());
+
+            match self.resume.take().map(|c| c.position_for(&query)) {
+                None => url,
+                Some(Ok(Position::NextLink(next_link))) => {
+                    self.initial_url = None;
+                    self.position = Some(Position::NextLink(next_link.clone()));
+                    next_link
+                }
+                Some(Ok(_)) => {
+                    self.position = Some(Position::Done);
+                    self.done = true;
+                    return None;
+                }
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
         } else if let Some(url) = self.next_url.take() {
             // Subsequent pages: use nextLink
             url
@@ -148,8 +294,14 @@ impl ODataPages {
             return None;
         };
 
-        // Build cache key from URL hash
-        let cache_key = make_cache_key(&url);
+        // Build cache key from URL hash, separately for each impersonated user
+        let impersonate = self.impersonate.or(client.impersonation());
+        let cache_key = make_cache_key(
+            &url,
+            self.annotations.as_deref(),
+            impersonate,
+            &self.headers,
+        );
         log::debug!("[ODataPages] URL for cache key {}: {}", cache_key, url);
 
         // Try cache first
@@ -159,6 +311,7 @@ impl ODataPages {
                     log::debug!("[ODataPages] Cache hit for {}", cache_key);
                     match crate::cache::deserialize::<ODataResponse>(&cached.data) {
                         Ok(odata_response) => {
+                            Span::current().record("cache", "hit");
                             return Some(Ok(self.build_page(odata_response)));
                         }
                         Err(e) => {
@@ -171,30 +324,29 @@ impl ODataPages {
             }
         }
 
+        let cache_status = if self.bypass_cache || client.cache().is_none() {
+            "bypass"
+        } else {
+            "miss"
+        };
+        Span::current().record("cache", cache_status);
+
         // Build headers
         let mut headers = HeaderMap::new();
         headers.insert("OData-MaxVersion", HeaderValue::from_static("4.0"));
         headers.insert("OData-Version", HeaderValue::from_static("4.0"));
         headers.insert("Accept", HeaderValue::from_static("application/json"));
-        headers.insert(
-            "Prefer",
-            HeaderValue::from_static("odata.include-annotations=\"*\""),
-        );
-
-        // Add page size preference if specified (only for first page)
-        if let Some(size) = self.page_size
-            && let Ok(_value) = HeaderValue::from_str(&format!("odata.maxpagesize={}", size))
+        if let Some(prefer) = self.prefer()
+            && let Ok(value) = HeaderValue::from_str(&prefer)
         {
-            // Note: This overwrites the previous Prefer header
-            // In practice, we'd want to combine them, but for simplicity:
-            headers.insert(
-                "Prefer",
-                HeaderValue::from_str(&format!(
-                    "odata.include-annotations=\"*\",odata.maxpagesize={}",
-                    size
-                ))
-                .unwrap_or_else(|_| HeaderValue::from_static("odata.include-annotations=\"*\"")),
-            );
+            headers.insert("Prefer", value);
+        }
+        if let Some(impersonation) = &self.impersonate {
+            apply_impersonation(&mut headers, impersonation);
+        }
+        if let Err(e) = apply_headers(&mut headers, &self.headers) {
+            self.done = true;
+            return Some(Err(e));
         }
 
         // Make request
@@ -241,6 +393,20 @@ impl ODataPages {
         Some(Ok(self.build_page(odata_response)))
     }
 
+    /// Builds the `Prefer` header value from the annotations and page size.
+    fn prefer(&self) -> Option<String> {
+        let preferences: Vec<String> = self
+            .annotations
+            .iter()
+            .cloned()
+            .chain(
+                self.page_size
+                    .map(|size| format!("odata.maxpagesize={}", size)),
+            )
+            .collect();
+        (!preferences.is_empty()).then(|| preferences.join(","))
+    }
+
     /// Builds a `Page` from an `ODataResponse`, populating record IDs and tracking pagination.
     fn build_page(&mut self, odata_response: ODataResponse) -> Page {
         // Populate record IDs from the primary key field
@@ -263,13 +429,17 @@ impl ODataPages {
         let mut page = Page::new(records);
 
         if let Some(count) = odata_response.count {
-            page = page.with_total_count(count);
+            page = page
+                .with_total_count(count)
+                .with_total_count_limit_exceeded(odata_response.count_limit_exceeded);
         }
 
         if let Some(next_link) = odata_response.next_link {
             page = page.with_next_link(next_link.clone());
+            self.position = Some(Position::NextLink(next_link.clone()));
             self.next_url = Some(next_link);
         } else {
+            self.position = Some(Position::Done);
             self.done = true;
         }
 
@@ -277,20 +447,45 @@ impl ODataPages {
     }
 }
 
-/// Builds a cache key from a URL by hashing it with SHA-256.
-fn make_cache_key(url: &str) -> String {
-    let hash = Sha256::digest(url.as_bytes());
-    format!("{}{:x}", QUERY_CACHE_PREFIX, hash)
+/// Builds a cache key from a URL (plus annotations, impersonated user and
+/// extra headers) by hashing it with SHA-256.
+fn make_cache_key(
+    url: &str,
+    annotations: Option<&str>,
+    impersonate: Option<Impersonation>,
+    headers: &[(String, String)],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    // All annotations is the default; leaving it out keeps existing keys valid
+    if let Some(annotations) = annotations.filter(|a| *a != "odata.include-annotations=\"*\"") {
+        hasher.update(format!("\nPrefer: {}", annotations).as_bytes());
+    }
+    if let Some(impersonation) = impersonate {
+        let (name, value) = impersonation.header();
+        hasher.update(format!("\n{}: {}", name, value).as_bytes());
+    }
+    for (name, value) in headers {
+        hasher.update(format!("\n{}: {}", name.to_lowercase(), value).as_bytes());
+    }
+    format!("{}{:x}", QUERY_CACHE_PREFIX, hasher.finalize())
 }
 
 /// OData response structure for collection queries.
 #[derive(Debug, Serialize, Deserialize)]
 struct ODataResponse {
     /// The records in this page.
+    #[serde(deserialize_with = "loaded_records")]
     value: Vec<Record>,
     /// Total count (when $count=true).
     #[serde(rename = "@odata.count")]
     count: Option<usize>,
+    /// Whether the total count stopped at the 5000 record limit.
+    #[serde(
+        rename = "@Microsoft.Dynamics.CRM.totalrecordcountlimitexceeded",
+        default
+    )]
+    count_limit_exceeded: bool,
     /// Link to the next page.
     #[serde(rename = "@odata.nextLink")]
     next_link: Option<String>,
@@ -0,0 +1,524 @@
+//! OData `$filter` parsing.
+//!
+//! Turns an existing `$filter` expression (e.g. a saved filter or a URL
+//! copied from the Maker portal) back into a [`Filter`].
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use url::form_urlencoded;
+use uuid::Uuid;
+
+use crate::api::query::Filter;
+use crate::error::Error;
+use crate::model::Value;
+
+fn invalid(message: impl Display) -> Error {
+    Error::InvalidOperation(format!("Invalid OData filter: {}", message))
+}
+
+// =============================================================================
+// Tokens
+// =============================================================================
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    Open,
+    Close,
+    Comma,
+    /// A string literal, unescaped.
+    Str(String),
+    /// An identifier, keyword, operator or unquoted literal.
+    Word(String),
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    /// Byte offsets of the token in the expression.
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            ',' => TokenKind::Comma,
+            '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if matches!(chars.peek(), Some((_, '\''))) => {
+                            chars.next();
+                            value.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => return Err(invalid("unterminated string literal")),
+                    }
+                }
+                TokenKind::Str(value)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | ',' | '\'') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                TokenKind::Word(word)
+            }
+        };
+        let end = chars.peek().map_or(input.len(), |&(i, _)| i);
+        tokens.push(Token { kind, start, end });
+    }
+    Ok(tokens)
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+/// Parses an OData `$filter` expression into a [`Filter`].
+///
+/// Comparisons, `and`/`or`/`not`, and `contains`, `startswith` and
+/// `endswith` map onto their [`Filter`] variants. Other functions (e.g.
+/// `Microsoft.Dynamics.CRM.In` or `any`/`all` lambdas) and comparisons
+/// against columns are kept verbatim as [`Filter::Raw`].
+pub(crate) fn parse_filter(input: &str) -> Result<Filter, Error> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        input,
+        tokens: &tokens,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Err(invalid("expression is empty"));
+    }
+    let filter = parser.parse_or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(filter),
+        Some(token) => Err(invalid(format!("unexpected '{}'", parser.text(token)))),
+    }
+}
+
+/// Parses the `$filter` of an OData URL or query string.
+///
+/// Returns `None` if it has no `$filter`.
+pub(crate) fn parse_filter_url(url: &str) -> Result<Option<Filter>, Error> {
+    let query = url.split_once('?').map_or(url, |(_, query)| query);
+    let query = query.split_once('#').map_or(query, |(query, _)| query);
+    form_urlencoded::parse(query.as_bytes())
+        .find(|(name, _)| name == "$filter")
+        .map(|(_, filter)| parse_filter(&filter))
+        .transpose()
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Returns the source text of a token.
+    fn text(&self, token: &Token) -> &str {
+        &self.input[token.start..token.end]
+    }
+
+    fn peek(&self) -> Option<&'a TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    fn next(&mut self) -> Result<&'a Token, Error> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of expression"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consumes a keyword if it's next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(TokenKind::Word(w)) if w == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<(), Error> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "expected {}, found '{}'",
+                expected,
+                self.text(token)
+            )))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, Error> {
+        let mut filters = vec![self.parse_and()?];
+        while self.keyword("or") {
+            filters.push(self.parse_and()?);
+        }
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => Filter::Or(filters),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, Error> {
+        let mut filters = vec![self.parse_unary()?];
+        while self.keyword("and") {
+            filters.push(self.parse_unary()?);
+        }
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => Filter::And(filters),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, Error> {
+        if self.keyword("not") {
+            return Ok(Filter::Not(Box::new(self.parse_unary()?)));
+        }
+        let start = self.pos;
+        let token = self.next()?;
+        let word = match &token.kind {
+            TokenKind::Open => {
+                let filter = self.parse_or()?;
+                self.expect(TokenKind::Close, "')'")?;
+                return Ok(filter);
+            }
+            TokenKind::Word(word) => word.clone(),
+            _ => return Err(invalid(format!("unexpected '{}'", self.text(token)))),
+        };
+        if self.peek() == Some(&TokenKind::Open) {
+            return self.parse_function(start, &word);
+        }
+        self.parse_comparison(start, word)
+    }
+
+    /// Parses a function call starting at token `start`.
+    fn parse_function(&mut self, start: usize, name: &str) -> Result<Filter, Error> {
+        let filter: fn(String, String) -> Filter = match name {
+            "contains" => Filter::Contains,
+            "startswith" => Filter::StartsWith,
+            "endswith" => Filter::EndsWith,
+            _ => return self.raw(start),
+        };
+        // Only `name(field,'value')` on its own, e.g. not `contains(tolower(name),'a')`
+        // or `contains(name,'a') eq false`
+        let kinds: Vec<&TokenKind> = self.tokens[self.pos..]
+            .iter()
+            .take(6)
+            .map(|t| &t.kind)
+            .collect();
+        if let [
+            TokenKind::Open,
+            TokenKind::Word(field),
+            TokenKind::Comma,
+            TokenKind::Str(value),
+            TokenKind::Close,
+            rest @ ..,
+        ] = kinds.as_slice()
+            && !matches!(rest.first(), Some(TokenKind::Word(w)) if w != "and" && w != "or")
+        {
+            let filter = filter(field.clone(), value.clone());
+            self.pos += 5;
+            return Ok(filter);
+        }
+        self.raw(start)
+    }
+
+    /// Parses `field op literal`, where `field` has been consumed.
+    fn parse_comparison(&mut self, start: usize, field: String) -> Result<Filter, Error> {
+        let token = self.next()?;
+        let operator: fn(String, Value) -> Filter = match &token.kind {
+            TokenKind::Word(op) => match op.as_str() {
+                "eq" => Filter::Eq,
+                "ne" => Filter::Ne,
+                "gt" => Filter::Gt,
+                "ge" => Filter::Ge,
+                "lt" => Filter::Lt,
+                "le" => Filter::Le,
+                _ => {
+                    return Err(invalid(format!(
+                        "expected a comparison operator after '{}', found '{}'",
+                        field,
+                        self.text(token)
+                    )));
+                }
+            },
+            _ => {
+                return Err(invalid(format!(
+                    "expected a comparison operator after '{}', found '{}'",
+                    field,
+                    self.text(token)
+                )));
+            }
+        };
+        let is_eq = matches!(&token.kind, TokenKind::Word(op) if op == "eq");
+        let is_ne = matches!(&token.kind, TokenKind::Word(op) if op == "ne");
+
+        let token = self.next()?;
+        let value = match &token.kind {
+            TokenKind::Str(value) => Value::String(value.clone()),
+            TokenKind::Word(_) if self.peek() == Some(&TokenKind::Open) => {
+                return self.raw(start);
+            }
+            TokenKind::Word(literal) => match parse_literal(literal) {
+                Some(value) => value,
+                // Compared with another column, or a literal with no Value
+                None => return self.raw(start),
+            },
+            _ => {
+                return Err(invalid(format!(
+                    "expected a value after '{}', found '{}'",
+                    field,
+                    self.text(token)
+                )));
+            }
+        };
+        Ok(match value {
+            Value::Null if is_eq => Filter::IsNull(field),
+            Value::Null if is_ne => Filter::IsNotNull(field),
+            value => operator(field, value),
+        })
+    }
+
+    /// Consumes tokens up to the end of the current term (through balanced
+    /// parentheses), returning them verbatim as [`Filter::Raw`].
+    fn raw(&mut self, start: usize) -> Result<Filter, Error> {
+        let mut depth = 0usize;
+        while let Some(kind) = self.peek() {
+            match kind {
+                TokenKind::Open => depth += 1,
+                TokenKind::Close if depth == 0 => break,
+                TokenKind::Close => depth -= 1,
+                TokenKind::Word(w) if depth == 0 && (w == "and" || w == "or") => break,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        if depth > 0 {
+            return Err(invalid("unbalanced parentheses"));
+        }
+        let start = self.tokens[start].start;
+        let end = self.tokens[self.pos - 1].end;
+        Ok(Filter::Raw(self.input[start..end].to_string()))
+    }
+}
+
+/// Parses an unquoted literal, or returns `None` if it isn't one.
+fn parse_literal(literal: &str) -> Option<Value> {
+    match literal {
+        "null" => return Some(Value::Null),
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Ok(id) = Uuid::parse_str(literal) {
+        return Some(Value::Guid(id));
+    }
+    let first = literal.chars().next()?;
+    if !first.is_ascii_digit() && first != '-' {
+        return None;
+    }
+    if let Ok(n) = literal.parse::<i32>() {
+        return Some(Value::Int(n));
+    }
+    if let Ok(n) = literal.parse::<i64>() {
+        return Some(Value::Long(n));
+    }
+    if literal.contains(['e', 'E'])
+        && let Ok(n) = literal.parse::<f64>()
+    {
+        return Some(Value::Float(n));
+    }
+    if let Ok(d) = Decimal::from_str(literal) {
+        return Some(Value::Decimal(d));
+    }
+    DateTime::parse_from_rfc3339(literal)
+        .ok()
+        .map(|dt| Value::DateTime(dt.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::api::query::odata::url::filter_to_odata;
+
+    #[test]
+    fn test_parse_comparisons() {
+        let id = Uuid::from_u128(7);
+        let cases = [
+            ("name eq 'O''Brien'", Filter::eq("name", "O'Brien")),
+            ("statecode ne 1", Filter::ne("statecode", 1)),
+            (
+                "revenue gt 1500.50",
+                Filter::gt("revenue", Decimal::new(150050, 2)),
+            ),
+            ("count ge 3000000000", Filter::ge("count", 3_000_000_000i64)),
+            ("ratio lt 1.5e3", Filter::lt("ratio", 1500.0)),
+            ("donotemail le false", Filter::le("donotemail", false)),
+            (
+                "_parentcustomerid_value eq 00000000-0000-0000-0000-000000000007",
+                Filter::eq("_parentcustomerid_value", id),
+            ),
+            (
+                "accountid eq a1b2c3d4-0000-0000-0000-000000000001",
+                Filter::eq(
+                    "accountid",
+                    Uuid::parse_str("a1b2c3d4-0000-0000-0000-000000000001").unwrap(),
+                ),
+            ),
+            (
+                "createdon ge 2024-01-15T09:30:00Z",
+                Filter::ge(
+                    "createdon",
+                    Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap(),
+                ),
+            ),
+            (
+                "parentaccountid eq null",
+                Filter::is_null("parentaccountid"),
+            ),
+            (
+                "parentaccountid ne null",
+                Filter::is_not_null("parentaccountid"),
+            ),
+            ("contains(name,'Corp')", Filter::contains("name", "Corp")),
+            ("startswith(name, 'A')", Filter::starts_with("name", "A")),
+            ("endswith(name,'Ltd')", Filter::ends_with("name", "Ltd")),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_filter(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_logical_operators() {
+        assert_eq!(
+            parse_filter("statecode eq 0 and revenue gt 100 or name eq 'A'").unwrap(),
+            Filter::or([
+                Filter::and([Filter::eq("statecode", 0), Filter::gt("revenue", 100)]),
+                Filter::eq("name", "A"),
+            ])
+        );
+        assert_eq!(
+            parse_filter("statecode eq 0 and (revenue gt 100 or not (name eq 'A'))").unwrap(),
+            Filter::and([
+                Filter::eq("statecode", 0),
+                Filter::or([
+                    Filter::gt("revenue", 100),
+                    Filter::Not(Box::new(Filter::eq("name", "A"))),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let filter = Filter::and([
+            Filter::eq("statecode", 0),
+            Filter::or([
+                Filter::contains("name", "it's"),
+                Filter::is_null("telephone1"),
+                Filter::and([Filter::ge("revenue", 10), Filter::lt("revenue", 20)]),
+            ]),
+            Filter::Not(Box::new(Filter::starts_with("name", "X"))),
+        ]);
+        assert_eq!(parse_filter(&filter_to_odata(&filter)).unwrap(), filter);
+    }
+
+    #[test]
+    fn test_parse_raw_terms() {
+        assert_eq!(
+            parse_filter(
+                "Microsoft.Dynamics.CRM.In(PropertyName='statecode',PropertyValues=['0','1']) \
+                 and statecode eq 0"
+            )
+            .unwrap(),
+            Filter::and([
+                Filter::raw(
+                    "Microsoft.Dynamics.CRM.In(PropertyName='statecode',PropertyValues=['0','1'])"
+                ),
+                Filter::eq("statecode", 0),
+            ])
+        );
+        assert_eq!(
+            parse_filter(
+                "contact_customer_accounts/any(c:c.fullname eq 'A') or revenue gt creditlimit"
+            )
+            .unwrap(),
+            Filter::or([
+                Filter::raw("contact_customer_accounts/any(c:c.fullname eq 'A')"),
+                Filter::raw("revenue gt creditlimit"),
+            ])
+        );
+        assert_eq!(
+            parse_filter("(contains(tolower(name),'corp'))").unwrap(),
+            Filter::raw("contains(tolower(name),'corp')")
+        );
+        assert_eq!(
+            parse_filter("contains(name,'a') eq false").unwrap(),
+            Filter::raw("contains(name,'a') eq false")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in [
+            "",
+            "name eq 'unterminated",
+            "name",
+            "name eq",
+            "name like 'a'",
+            "(name eq 'a'",
+            "name eq 'a')",
+            "name eq 'a' and",
+            "Microsoft.Dynamics.CRM.Today(PropertyName='createdon'",
+        ] {
+            assert!(
+                matches!(parse_filter(input), Err(Error::InvalidOperation(_))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_filter_url() {
+        assert_eq!(
+            parse_filter_url(
+                "https://org.crm.dynamics.com/api/data/v9.2/accounts?$select=name\
+                 &$filter=name%20eq%20%27A%26B%27%20and%20revenue%20gt%20100#top"
+            )
+            .unwrap(),
+            Some(Filter::and([
+                Filter::eq("name", "A&B"),
+                Filter::gt("revenue", 100),
+            ]))
+        );
+        assert_eq!(parse_filter_url("$top=5").unwrap(), None);
+    }
+}
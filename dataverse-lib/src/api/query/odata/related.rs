@@ -1,11 +1,17 @@
 //! Related records query builder and pagination.
 
+use std::collections::HashSet;
+
 use reqwest::Method;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
 use serde::Deserialize;
 use uuid::Uuid;
 
+use super::builder::transform_expands;
+use super::builder::transform_field_name;
+use super::builder::transform_odata_filter;
+use super::builder::transform_order_by;
 use super::expand::ExpandBuilder;
 use super::url::build_select_expand_params;
 use super::url::odata_filter_to_string;
@@ -18,20 +24,30 @@ use crate::error::ApiError;
 use crate::error::Error;
 use crate::model::Entity;
 use crate::model::Record;
+use crate::model::loaded_records;
+use crate::model::metadata::EntityMetadata;
+use crate::telemetry::InSpan;
+use crate::telemetry::span;
 
-/// Builder for querying records related through a navigation property.
+/// Builder for querying records related through a collection-valued
+/// navigation property.
 ///
-/// Use [`DataverseClient::related`] to create a related query builder.
+/// Use [`DataverseClient::related`] to create a related query builder. The
+/// relationship can be given by its schema name or by the navigation property
+/// name; for N:N relationships the two can differ, and the navigation property
+/// is looked up from the side of the source entity. Lookup fields in select,
+/// filter and order by clauses are named by logical name, as with
+/// [`QueryBuilder`](super::QueryBuilder).
 ///
 /// # Example
 ///
 /// ```ignore
-/// // Query contacts related to an account
+/// // Query the leads of an account's N:N relationship
 /// let mut pages = client.related(
 ///     Entity::set("accounts"), account_id,
-///     "contact_customer_accounts",
+///     "accountleads_association",
 /// )
-/// .select(&["fullname", "emailaddress1"])
+/// .select(&["fullname", "parentaccountid"])
 /// .filter(Filter::eq("statecode", 0))
 /// .order_by(OrderBy::asc("fullname"))
 /// .into_async_iter();
@@ -177,16 +193,73 @@ impl RelatedQueryBuilder {
         &self.client
     }
 
-    /// Returns the entity.
-    pub(crate) fn entity(&self) -> &Entity {
-        &self.entity
-    }
-
     /// Returns the page size value.
     pub(crate) fn page_size_value(&self) -> Option<usize> {
         self.page_size
     }
 
+    /// Resolves the source entity and the relationship, returning the query
+    /// URL (without base URL).
+    ///
+    /// When the relationship is found in the source entity's metadata, lookup
+    /// field names are transformed with the related entity's metadata.
+    /// Otherwise the name is used as the navigation property as is.
+    async fn resolve(mut self) -> Result<String, Error> {
+        let client = self.client.clone();
+        let logical_name = client.resolve_entity_logical_name(&self.entity).await?;
+        let entity_set_name = match &self.entity {
+            Entity::Set(name) => name.clone(),
+            Entity::Logical(name) => client.resolve_entity_set_name(name).await?,
+        };
+        let metadata = client
+            .metadata()
+            .entity(Entity::logical(&logical_name))
+            .await?;
+
+        match collection_navigation(&metadata, &self.nav_property)? {
+            Some((nav_property, target)) => {
+                self.nav_property = nav_property;
+                self.transform_lookup_fields(&client, &target).await?;
+            }
+            None => log::debug!(
+                "[RelatedQueryBuilder] No relationship '{}' on {}; using it as the navigation property",
+                self.nav_property,
+                logical_name
+            ),
+        }
+        Ok(self.build_url(&entity_set_name))
+    }
+
+    /// Transforms lookup field names to OData format (`_fieldname_value`)
+    /// using the related entity's metadata.
+    async fn transform_lookup_fields(
+        &mut self,
+        client: &DataverseClient,
+        target: &str,
+    ) -> Result<(), Error> {
+        let lookup_fields: HashSet<String> = client
+            .metadata()
+            .attributes(Entity::logical(target))
+            .await?
+            .iter()
+            .filter(|attr| attr.is_lookup())
+            .map(|attr| attr.logical_name.clone())
+            .collect();
+
+        self.select = self
+            .select
+            .iter()
+            .map(|field| transform_field_name(field, &lookup_fields))
+            .collect();
+        if let Some(ref filter) = self.filter {
+            self.filter = Some(transform_odata_filter(filter, &lookup_fields));
+        }
+        if let Some(ref order) = self.order_by {
+            self.order_by = Some(transform_order_by(order, &lookup_fields));
+        }
+        transform_expands(client, &mut self.expands, target).await
+    }
+
     /// Builds the query URL (without base URL).
     ///
     /// The entity_set_name is the resolved entity set name for the source entity.
@@ -282,22 +355,21 @@ impl RelatedPages {
             return None;
         }
 
+        let span = span!("dataverse.query", kind = "related");
+        self.next_page().in_span(span).await
+    }
+
+    async fn next_page(&mut self) -> Option<Result<Page, Error>> {
         // Determine which URL to fetch
         let url = if let Some(builder) = self.needs_resolution.take() {
-            // First call: resolve entity and build URL
-            let entity_set_name = match builder.entity() {
-                Entity::Set(name) => name.clone(),
-                Entity::Logical(logical_name) => {
-                    match self.client.resolve_entity_set_name(logical_name).await {
-                        Ok(name) => name,
-                        Err(e) => {
-                            self.done = true;
-                            return Some(Err(e));
-                        }
-                    }
+            // First call: resolve entity and relationship, and build URL
+            let relative_url = match builder.resolve().await {
+                Ok(url) => url,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
                 }
             };
-            let relative_url = builder.build_url(&entity_set_name);
             let full_url = self.client.build_url(&relative_url);
             self.initial_url = Some(full_url.clone());
             full_url
@@ -371,10 +443,69 @@ impl RelatedPages {
     }
 }
 
+/// Finds a collection-valued relationship of an entity by schema name or
+/// navigation property name, returning its navigation property and the
+/// related entity's logical name.
+///
+/// Fails for many-to-one relationships, whose navigation properties are
+/// single-valued.
+fn collection_navigation(
+    metadata: &EntityMetadata,
+    name: &str,
+) -> Result<Option<(String, String)>, Error> {
+    let logical_name = metadata.logical_name.as_str();
+    let matches = |schema_name: &str, nav: Option<&str>| {
+        schema_name.eq_ignore_ascii_case(name) || nav == Some(name)
+    };
+
+    let many_to_many = metadata
+        .many_to_many_relationships
+        .iter()
+        .find(|r| matches(&r.schema_name, r.navigation_property_for(logical_name)));
+    if let Some(r) = many_to_many
+        && let Some(target) = r.other_entity(logical_name)
+    {
+        let nav = r
+            .navigation_property_for(logical_name)
+            .unwrap_or(&r.schema_name);
+        return Ok(Some((nav.to_string(), target.to_string())));
+    }
+
+    let one_to_many = metadata.one_to_many_relationships.iter().find(|r| {
+        r.referenced_entity == logical_name
+            && matches(
+                &r.schema_name,
+                r.referenced_entity_navigation_property_name.as_deref(),
+            )
+    });
+    if let Some(r) = one_to_many {
+        let nav = r
+            .referenced_entity_navigation_property_name
+            .as_deref()
+            .unwrap_or(&r.schema_name);
+        return Ok(Some((nav.to_string(), r.referencing_entity.clone())));
+    }
+
+    let many_to_one = metadata.many_to_one_relationships.iter().any(|r| {
+        matches(
+            &r.schema_name,
+            r.referencing_entity_navigation_property_name.as_deref(),
+        )
+    });
+    if many_to_one {
+        return Err(Error::InvalidOperation(format!(
+            "Relationship '{}' is single-valued on {}; expand it on a query or retrieve instead",
+            name, logical_name
+        )));
+    }
+    Ok(None)
+}
+
 /// OData response structure for collection queries.
 #[derive(Debug, Deserialize)]
 struct ODataResponse {
     /// The records in this page.
+    #[serde(deserialize_with = "loaded_records")]
     value: Vec<Record>,
     /// Total count (when $count=true).
     #[serde(rename = "@odata.count")]
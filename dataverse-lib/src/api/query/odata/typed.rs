@@ -0,0 +1,383 @@
+//! Typed OData queries.
+
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use futures::stream;
+use serde::de::DeserializeOwned;
+
+use crate::DataverseClient;
+use crate::api::query::typed::FromRecordFn;
+use crate::api::query::typed::deserialize_record;
+use crate::api::query::typed::entity_from_record;
+use crate::api::query::typed::from_records;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::stream::Prefetch;
+
+use super::builder::QueryBuilder;
+use super::pages::ODataPages;
+
+/// An OData query whose results are mapped to a user type.
+///
+/// Created with [`QueryBuilder::into_typed`] for a [`DataverseEntity`], or
+/// [`QueryBuilder::into_deserialized`] for any serde type.
+///
+/// # Example
+///
+/// ```ignore
+/// let accounts: Vec<Account> = client
+///     .query(Account::entity())
+///     .filter(Filter::eq("statecode", 0))
+///     .into_typed::<Account>()
+///     .execute(&client)
+///     .await?;
+/// ```
+pub struct TypedQuery<T> {
+    builder: QueryBuilder,
+    from_record: FromRecordFn<T>,
+}
+
+impl<T: DataverseEntity> TypedQuery<T> {
+    /// Creates a typed query, selecting `T::COLUMNS` unless the builder
+    /// already has a selection.
+    pub(crate) fn new(builder: QueryBuilder) -> Self {
+        let builder = if builder.selected_fields().is_empty() {
+            builder.select(T::COLUMNS)
+        } else {
+            builder
+        };
+        Self {
+            builder,
+            from_record: entity_from_record,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> TypedQuery<T> {
+    /// Creates a query deserializing each record with serde.
+    pub(crate) fn deserialized(builder: QueryBuilder) -> Self {
+        Self {
+            builder,
+            from_record: deserialize_record,
+        }
+    }
+}
+
+impl<T> TypedQuery<T> {
+    /// Returns the underlying query builder.
+    pub fn into_inner(self) -> QueryBuilder {
+        self.builder
+    }
+
+    /// Executes the query and returns the first page of results.
+    pub async fn execute(self, client: &DataverseClient) -> Result<Vec<T>, Error> {
+        let records = self.builder.execute(client).await?;
+        from_records(self.from_record, &records)
+    }
+
+    /// Executes the query and returns the first matching result.
+    pub async fn first(self, client: &DataverseClient) -> Result<Option<T>, Error> {
+        match self.builder.first(client).await? {
+            Some(record) => Ok(Some((self.from_record)(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts this query into an async iterator over typed pages.
+    pub fn into_async_iter(self, client: &DataverseClient) -> TypedPages<T> {
+        TypedPages {
+            pages: self.builder.into_async_iter(client),
+            from_record: self.from_record,
+        }
+    }
+}
+
+/// Async iterator that yields pages of typed query results.
+pub struct TypedPages<T> {
+    pages: ODataPages,
+    from_record: FromRecordFn<T>,
+}
+
+impl<T> TypedPages<T> {
+    /// Fetches the next page of results.
+    ///
+    /// Returns `None` when all pages have been consumed.
+    pub async fn next(&mut self, client: &DataverseClient) -> Option<Result<Vec<T>, Error>> {
+        let page = match self.pages.next(client).await? {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(from_records(self.from_record, page.records()))
+    }
+
+    /// Converts this iterator into a [`Stream`] of typed pages.
+    pub fn into_stream<'a>(
+        self,
+        client: &'a DataverseClient,
+    ) -> impl Stream<Item = Result<Vec<T>, Error>> + Send + 'a
+    where
+        T: Send + 'a,
+    {
+        let from_record = self.from_record;
+        self.pages
+            .into_stream(client)
+            .map(move |page| page.and_then(|page| from_records(from_record, page.records())))
+    }
+
+    /// Fetches up to `pages` pages ahead in the background while the
+    /// consumer processes the current one. See [`ODataPages::prefetch`].
+    pub fn prefetch(self, client: &DataverseClient, pages: usize) -> Prefetch<Vec<T>>
+    where
+        T: Send + 'static,
+    {
+        let client = client.clone();
+        let source = stream::unfold((self, client), |(mut pages, client)| async move {
+            let page = pages.next(&client).await?;
+            Some((page, (pages, client)))
+        });
+        Prefetch::spawn(source, pages)
+    }
+
+    /// Converts this iterator into a [`Stream`] of typed records, fetching
+    /// pages as they're consumed.
+    pub fn into_record_stream<'a>(
+        self,
+        client: &'a DataverseClient,
+    ) -> impl Stream<Item = Result<T, Error>> + Send + 'a
+    where
+        T: Send + 'a,
+    {
+        self.into_stream(client)
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use futures::StreamExt;
+    use futures::TryStreamExt;
+    use serde::Deserialize;
+    use uuid::Uuid;
+
+    use crate::api::query::Filter;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::DataverseEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+
+    #[derive(Debug, PartialEq, DataverseEntity)]
+    #[dataverse(entity = "contact", set = "contacts")]
+    struct Contact {
+        #[dataverse(id)]
+        id: Option<Uuid>,
+        fullname: String,
+        #[dataverse(lookup = "accounts")]
+        parentcustomerid: Option<Uuid>,
+    }
+
+    #[tokio::test]
+    async fn test_typed_query() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+
+        let account_id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+
+        let created = client
+            .create(
+                Contact::entity(),
+                Contact {
+                    id: None,
+                    fullname: "Ada".into(),
+                    parentcustomerid: Some(account_id),
+                }
+                .to_record(),
+            )
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+
+        let contacts = client
+            .query(Contact::entity())
+            .filter(Filter::eq("fullname", "Ada"))
+            .into_typed::<Contact>()
+            .execute(&client)
+            .await
+            .unwrap();
+        assert_eq!(
+            contacts,
+            vec![Contact {
+                id: Some(created),
+                fullname: "Ada".into(),
+                parentcustomerid: Some(account_id),
+            }]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct ContactRow {
+        fullname: String,
+        parentcustomerid: Option<Uuid>,
+        #[serde(rename = "parentcustomerid@Microsoft.Dynamics.CRM.lookuplogicalname")]
+        target: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn test_deserialized_query() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        let account_id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        for (name, account) in [("Ada", Some(account_id)), ("Grace", None)] {
+            let contact = Contact {
+                id: None,
+                fullname: name.into(),
+                parentcustomerid: account,
+            };
+            client
+                .create(Contact::entity(), contact.to_record())
+                .await
+                .unwrap();
+        }
+
+        let mut rows: Vec<ContactRow> = client
+            .query(Contact::entity())
+            .select(&["fullname", "parentcustomerid"])
+            .page_size(1)
+            .into_deserialized::<ContactRow>()
+            .into_async_iter(&client)
+            .into_record_stream(&client)
+            .try_collect()
+            .await
+            .unwrap();
+        rows.sort_by(|a, b| a.fullname.cmp(&b.fullname));
+        assert_eq!(
+            rows,
+            [
+                ContactRow {
+                    fullname: "Ada".into(),
+                    parentcustomerid: Some(account_id),
+                    target: Some("account".into()),
+                },
+                ContactRow {
+                    fullname: "Grace".into(),
+                    parentcustomerid: None,
+                    target: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streams() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        for name in ["Ada", "Grace", "Linus"] {
+            mock.insert(
+                Entity::set("contacts"),
+                Record::new("contact").set("fullname", name),
+            );
+        }
+
+        let pages: Vec<_> = client
+            .query(Contact::entity())
+            .page_size(2)
+            .into_async_iter(&client)
+            .into_stream(&client)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.iter().map(|p| p.len()).collect::<Vec<_>>(), [2, 1]);
+
+        let mut names: Vec<String> = client
+            .query(Contact::entity())
+            .page_size(2)
+            .into_typed::<Contact>()
+            .into_async_iter(&client)
+            .into_record_stream(&client)
+            .map_ok(|contact| contact.fullname)
+            .try_collect()
+            .await
+            .unwrap();
+        names.sort();
+        assert_eq!(names, ["Ada", "Grace", "Linus"]);
+
+        let count = client
+            .query(Contact::entity())
+            .page_size(1)
+            .into_async_iter(&client)
+            .into_record_stream(&client)
+            .take(2)
+            .count()
+            .await;
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        for name in ["Ada", "Grace", "Linus", "Margaret", "Barbara"] {
+            mock.insert(
+                Entity::set("contacts"),
+                Record::new("contact").set("fullname", name),
+            );
+        }
+
+        let mut pages = client
+            .query(Entity::set("contacts"))
+            .page_size(2)
+            .into_async_iter(&client)
+            .prefetch(&client, 2);
+        let mut sizes = Vec::new();
+        while let Some(page) = pages.next().await {
+            sizes.push(page.unwrap().len());
+        }
+        assert_eq!(sizes, [2, 2, 1]);
+
+        let mut names: Vec<String> = client
+            .query(Contact::entity())
+            .page_size(2)
+            .into_typed::<Contact>()
+            .into_async_iter(&client)
+            .prefetch(&client, 3)
+            .map_ok(|contacts| contacts.into_iter().map(|c| c.fullname).collect::<Vec<_>>())
+            .try_concat()
+            .await
+            .unwrap();
+        names.sort();
+        assert_eq!(names, ["Ada", "Barbara", "Grace", "Linus", "Margaret"]);
+    }
+}
@@ -5,6 +5,7 @@ use crate::api::query::Direction;
 use crate::api::query::Filter;
 use crate::api::query::ODataFilter;
 use crate::api::query::OrderBy;
+use crate::api::query::datetime_literal;
 use crate::model::Value;
 
 /// Builds the `$select` and `$expand` query parameters.
@@ -95,7 +96,7 @@ pub fn value_to_odata(value: &Value) -> String {
         Value::Decimal(d) => d.to_string(),
         Value::String(s) => escape_string(s),
         Value::Guid(g) => g.to_string(),
-        Value::DateTime(dt) => dt.to_rfc3339(),
+        Value::DateTime(dt) => datetime_literal(dt),
         Value::Money(m) => m.value().to_string(),
         Value::OptionSet(o) => o.value.to_string(),
         Value::EntityReference(r) => r.id.to_string(),
@@ -138,7 +139,14 @@ pub fn escape_string(s: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
     use super::*;
+    use crate::model::types::Money;
+    use crate::model::types::OptionSetValue;
 
     #[test]
     fn test_simple_filters() {
@@ -186,6 +194,36 @@ mod tests {
         assert_eq!(order_to_odata(&order), "revenue desc,name asc");
     }
 
+    #[test]
+    fn test_typed_values() {
+        let id = Uuid::parse_str("9f3a1c2e-5b4d-4e6f-8a7b-0c1d2e3f4a5b").unwrap();
+        let created = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+        assert_eq!(
+            filter_to_odata(&Filter::eq("accountid", id)),
+            "accountid eq 9f3a1c2e-5b4d-4e6f-8a7b-0c1d2e3f4a5b"
+        );
+        assert_eq!(
+            filter_to_odata(&Filter::ge("createdon", created)),
+            "createdon ge 2024-01-15T09:30:00Z"
+        );
+        assert_eq!(
+            filter_to_odata(&Filter::eq("donotemail", false)),
+            "donotemail eq false"
+        );
+        assert_eq!(
+            filter_to_odata(&Filter::eq("industrycode", OptionSetValue::new(3))),
+            "industrycode eq 3"
+        );
+        assert_eq!(
+            filter_to_odata(&Filter::gt("revenue", Money::new(Decimal::new(150050, 2)))),
+            "revenue gt 1500.50"
+        );
+        assert_eq!(
+            filter_to_odata(&Filter::eq("parentaccountid", None::<Uuid>)),
+            "parentaccountid eq null"
+        );
+    }
+
     #[test]
     fn test_escape_string() {
         assert_eq!(escape_string("O'Brien"), "'O''Brien'");
@@ -35,6 +35,8 @@ pub struct Page {
     paging_cookie: Option<String>,
     /// Total record count (if requested with `$count=true`).
     total_count: Option<usize>,
+    /// Whether the total count stopped at the server's limit.
+    total_count_limit_exceeded: bool,
 }
 
 impl Page {
@@ -45,6 +47,7 @@ impl Page {
             next_link: None,
             paging_cookie: None,
             total_count: None,
+            total_count_limit_exceeded: false,
         }
     }
 
@@ -66,6 +69,12 @@ impl Page {
         self
     }
 
+    /// Marks the total record count as capped by the server's limit.
+    pub fn with_total_count_limit_exceeded(mut self, exceeded: bool) -> Self {
+        self.total_count_limit_exceeded = exceeded;
+        self
+    }
+
     /// Returns a reference to the records in this page.
     pub fn records(&self) -> &[Record] {
         &self.records
@@ -97,6 +106,15 @@ impl Page {
         self.total_count
     }
 
+    /// Returns `true` if the total count stopped at the server's limit
+    /// (5000 records), so the real total is higher.
+    ///
+    /// Use [`QueryBuilder::count`](crate::api::query::odata::QueryBuilder::count)
+    /// for exact counts past the limit.
+    pub fn total_count_limit_exceeded(&self) -> bool {
+        self.total_count_limit_exceeded
+    }
+
     /// Returns `true` if this page has no records.
     pub fn is_empty(&self) -> bool {
         self.records.is_empty()
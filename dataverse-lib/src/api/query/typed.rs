@@ -0,0 +1,26 @@
+//! Conversion of query results into user types, shared by the typed OData
+//! and FetchXML queries.
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::model::Record;
+
+/// Converts a result record into a typed query's item type.
+pub(crate) type FromRecordFn<T> = fn(&Record) -> Result<T, Error>;
+
+pub(crate) fn entity_from_record<T: DataverseEntity>(record: &Record) -> Result<T, Error> {
+    Ok(T::from_record(record)?)
+}
+
+pub(crate) fn deserialize_record<T: DeserializeOwned>(record: &Record) -> Result<T, Error> {
+    Ok(record.deserialize_into()?)
+}
+
+pub(crate) fn from_records<T>(
+    from_record: FromRecordFn<T>,
+    records: &[Record],
+) -> Result<Vec<T>, Error> {
+    records.iter().map(from_record).collect()
+}
@@ -0,0 +1,215 @@
+//! Queue routing
+//!
+//! Queues hold work items such as cases and emails as `queueitem` records.
+//! These helpers wrap the routing messages: `AddToQueue` puts a record in a
+//! queue (or moves it from another one), `PickFromQueue` assigns an item to
+//! a worker, `ReleaseToQueue` hands it back and `RemoveFromQueue` takes it
+//! out of its queue.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let item = client
+//!     .add_to_queue(support_queue, Entity::logical("incident"), case_id)
+//!     .from_queue(triage_queue)
+//!     .await?;
+//!
+//! client.pick_from_queue(item, agent_id, false).await?;
+//! // ...
+//! client.release_to_queue(item).await?;
+//! ```
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+
+/// Response of `AddToQueue`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AddToQueueResponse {
+    queue_item_id: Uuid,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Adds a record to a queue, returning the id of its queue item.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The queue to add the record to
+    /// * `entity` - The record's entity
+    /// * `id` - The record's id
+    pub fn add_to_queue(
+        &self,
+        queue: Uuid,
+        entity: impl Into<Entity>,
+        id: Uuid,
+    ) -> ClientAddToQueueBuilder<'_> {
+        ClientAddToQueueBuilder {
+            client: self,
+            queue,
+            target: (entity.into(), id),
+            source_queue: None,
+            properties: None,
+        }
+    }
+
+    /// Assigns a queue item to a user, who then works on it.
+    ///
+    /// With `remove` the item is also taken out of the queue.
+    pub async fn pick_from_queue(
+        &self,
+        queue_item: Uuid,
+        worker: Uuid,
+        remove: bool,
+    ) -> Result<(), Error> {
+        self.action("PickFromQueue")
+            .param("QueueItemId", queue_item)
+            .param("WorkerId", worker)
+            .param("RemoveQueueItem", remove)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Releases a picked queue item back to its queue, clearing its worker.
+    pub async fn release_to_queue(&self, queue_item: Uuid) -> Result<(), Error> {
+        self.action("ReleaseToQueue")
+            .param("QueueItemId", queue_item)
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a queue item from its queue, leaving the queued record as is.
+    pub async fn remove_from_queue(&self, queue_item: Uuid) -> Result<(), Error> {
+        self.action("RemoveFromQueue")
+            .param("QueueItemId", queue_item)
+            .execute()
+            .await?;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Builder
+// =============================================================================
+
+/// Builder for adding a record to a queue, bound to a client.
+pub struct ClientAddToQueueBuilder<'a> {
+    client: &'a DataverseClient,
+    queue: Uuid,
+    target: (Entity, Uuid),
+    source_queue: Option<Uuid>,
+    properties: Option<Record>,
+}
+
+impl<'a> ClientAddToQueueBuilder<'a> {
+    /// Moves the record from this queue instead of adding another item.
+    pub fn from_queue(mut self, queue: Uuid) -> Self {
+        self.source_queue = Some(queue);
+        self
+    }
+
+    /// Sets fields of the new queue item, such as `workerid@odata.bind`.
+    pub fn properties(mut self, properties: Record) -> Self {
+        self.properties = Some(properties);
+        self
+    }
+
+    async fn execute(self) -> Result<Uuid, Error> {
+        let (entity, id) = self.target;
+        let mut action = self
+            .client
+            .action("AddToQueue")
+            .bound(Entity::logical("queue"), self.queue)
+            .reference_param("Target", entity, id);
+        if let Some(source) = self.source_queue {
+            action = action.reference_param("SourceQueue", Entity::logical("queue"), source);
+        }
+        if let Some(mut properties) = self.properties {
+            properties.set_entity(Entity::logical("queueitem"));
+            action = action.record_param("QueueItemProperties", properties);
+        }
+        let response: AddToQueueResponse = action.execute_as().await?;
+        Ok(response.queue_item_id)
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientAddToQueueBuilder<'a> {
+    type Output = Result<Uuid, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    #[tokio::test]
+    async fn test_queue_routing() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("queue", "queues", "queueid").primary_name("name"))
+            .entity(
+                MockEntity::new("queueitem", "queueitems", "queueitemid")
+                    .lookup("queueid", "queue")
+                    .lookup("objectid", "incident")
+                    .lookup("workerid", "systemuser"),
+            )
+            .entity(MockEntity::new("incident", "incidents", "incidentid"));
+        let client = mock.client();
+        let lookup = |item: &Record, field: &str| {
+            item.get_entity_reference(field)
+                .unwrap()
+                .map(|reference| reference.id)
+        };
+        let triage = mock.insert(
+            Entity::set("queues"),
+            Record::new("queue").set("name", "Triage"),
+        );
+        let support = mock.insert(
+            Entity::set("queues"),
+            Record::new("queue").set("name", "Support"),
+        );
+        let case = mock.insert(Entity::set("incidents"), Record::new("incident"));
+
+        let first = client
+            .add_to_queue(triage, Entity::logical("incident"), case)
+            .await
+            .unwrap();
+        let item = client
+            .add_to_queue(support, Entity::logical("incident"), case)
+            .from_queue(triage)
+            .await
+            .unwrap();
+        assert!(mock.record(Entity::set("queueitems"), first).is_none());
+        let queued = mock.record(Entity::set("queueitems"), item).unwrap();
+        assert_eq!(lookup(&queued, "queueid"), Some(support));
+        assert_eq!(lookup(&queued, "objectid"), Some(case));
+
+        let worker = mock.user_id();
+        client.pick_from_queue(item, worker, false).await.unwrap();
+        let picked = mock.record(Entity::set("queueitems"), item).unwrap();
+        assert_eq!(lookup(&picked, "workerid"), Some(worker));
+
+        client.release_to_queue(item).await.unwrap();
+        let released = mock.record(Entity::set("queueitems"), item).unwrap();
+        assert_eq!(lookup(&released, "workerid"), None);
+
+        client.remove_from_queue(item).await.unwrap();
+        assert!(mock.records(Entity::set("queueitems")).is_empty());
+    }
+}
@@ -0,0 +1,632 @@
+//! Dataverse search (relevance search)
+//!
+//! Wraps the `searchquery`, `searchsuggest` and `searchautocomplete`
+//! actions, which search the indexed columns of all tables enabled for
+//! Dataverse search at once. Search has to be turned on for the
+//! environment.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use dataverse_lib::api::search::SearchEntity;
+//!
+//! let results = client
+//!     .search("contoso")
+//!     .entity(SearchEntity::new("account").select(&["name", "telephone1"]))
+//!     .entity("contact")
+//!     .facet("@search.entityname,count:10")
+//!     .count()
+//!     .top(20)
+//!     .await?;
+//!
+//! for hit in &results.hits {
+//!     println!("{} {} ({})", hit.entity_name, hit.id, hit.score);
+//! }
+//!
+//! let suggestions = client.search_suggest("cont").top(5).await?;
+//! let completion = client.search_autocomplete("cont").await?;
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::future::IntoFuture;
+use std::pin::Pin;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+use serde_json::Value as Json;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::Error;
+
+/// Marker Dataverse puts before a highlighted term.
+pub const HIGHLIGHT_START: &str = "{crmhit}";
+
+/// Marker Dataverse puts after a highlighted term.
+pub const HIGHLIGHT_END: &str = "{/crmhit}";
+
+// =============================================================================
+// Request options
+// =============================================================================
+
+/// A table to search, with the columns to return and search in.
+///
+/// A bare logical name converts into a `SearchEntity` searching the table's
+/// default columns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntity {
+    /// The table's logical name.
+    pub name: String,
+    /// Columns returned with each hit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub select_columns: Vec<String>,
+    /// Columns searched, instead of all indexed ones.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub search_columns: Vec<String>,
+    /// An OData filter on the table's rows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+impl SearchEntity {
+    /// Searches a table by logical name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            select_columns: Vec::new(),
+            search_columns: Vec::new(),
+            filter: None,
+        }
+    }
+
+    /// Sets the columns returned with each hit.
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.select_columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Restricts the search to these columns.
+    pub fn search_columns(mut self, columns: &[&str]) -> Self {
+        self.search_columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Filters the table's rows, e.g. `statecode eq 0`.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+}
+
+impl From<&str> for SearchEntity {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for SearchEntity {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+/// Whether any or all search terms must match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// A row matches if any term does.
+    #[default]
+    Any,
+    /// A row matches only if every term does.
+    All,
+}
+
+/// How the search text is parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryType {
+    /// Simple query syntax: `+`, `|`, `-`, quotes, `*` suffixes.
+    #[default]
+    Simple,
+    /// Full Lucene query syntax: fields, fuzzy and proximity search, regexes.
+    Lucene,
+}
+
+/// Options shared by all search requests.
+#[derive(Debug, Clone, Default)]
+struct SearchParams {
+    search: String,
+    entities: Vec<SearchEntity>,
+    filter: Option<String>,
+    top: Option<usize>,
+    fuzzy: bool,
+}
+
+impl SearchParams {
+    fn new(search: String) -> Self {
+        Self {
+            search,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the action parameters. List-valued parameters are passed as
+    /// JSON strings.
+    fn parameters(&self) -> Vec<(&'static str, Json)> {
+        let mut params = vec![("search", Json::String(self.search.clone()))];
+        if !self.entities.is_empty() {
+            let entities = serde_json::to_string(&self.entities).unwrap_or_default();
+            params.push(("entities", Json::String(entities)));
+        }
+        if let Some(filter) = &self.filter {
+            params.push(("filter", Json::String(filter.clone())));
+        }
+        if let Some(top) = self.top {
+            params.push(("top", Json::from(top)));
+        }
+        if self.fuzzy {
+            params.push(("fuzzy", Json::Bool(true)));
+        }
+        params
+    }
+}
+
+// =============================================================================
+// Results
+// =============================================================================
+
+/// Results of a search query.
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    /// The matching rows, best first.
+    pub hits: Vec<SearchHit>,
+    /// Facet counts, by facet field.
+    pub facets: HashMap<String, Vec<FacetResult>>,
+    /// The total number of matches, if requested.
+    pub count: Option<i64>,
+}
+
+/// A row matching a search query.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SearchHit {
+    /// The row's id.
+    pub id: Uuid,
+    /// The logical name of the row's table.
+    pub entity_name: String,
+    /// The object type code of the row's table.
+    #[serde(default)]
+    pub object_type_code: i32,
+    /// The selected columns of the row.
+    #[serde(default)]
+    pub attributes: Map<String, Json>,
+    /// Matched fragments by column, with matches between
+    /// [`HIGHLIGHT_START`] and [`HIGHLIGHT_END`].
+    #[serde(default)]
+    pub highlights: HashMap<String, Vec<String>>,
+    /// How well the row matches.
+    #[serde(default)]
+    pub score: f64,
+}
+
+/// A facet bucket: a value (or range) and how many hits have it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FacetResult {
+    /// The kind of facet, `Value` or `Range`.
+    #[serde(rename = "Type", default)]
+    pub kind: Option<String>,
+    /// The value, for value facets.
+    #[serde(default)]
+    pub value: Json,
+    /// The start of the range, for range facets.
+    #[serde(default)]
+    pub from: Json,
+    /// The end of the range, for range facets.
+    #[serde(default)]
+    pub to: Json,
+    /// The number of hits in the bucket.
+    #[serde(default)]
+    pub count: i64,
+}
+
+/// A suggested row for search-as-you-type.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Suggestion {
+    /// The matched text, with the match between [`HIGHLIGHT_START`] and
+    /// [`HIGHLIGHT_END`].
+    pub text: String,
+    /// The suggested row's columns.
+    #[serde(default)]
+    pub document: Map<String, Json>,
+}
+
+/// The response body every search action wraps in a JSON string.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SearchResponse<T> {
+    #[serde(default)]
+    error: Option<SearchError>,
+    value: Option<T>,
+    #[serde(default)]
+    facets: HashMap<String, Vec<FacetResult>>,
+    #[serde(default)]
+    count: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SearchError {
+    #[serde(default)]
+    message: String,
+}
+
+/// Unwraps a search action's `response` string.
+fn parse_response<T: DeserializeOwned + Default>(body: Json) -> Result<SearchResponse<T>, Error> {
+    let response = body
+        .get("response")
+        .and_then(Json::as_str)
+        .ok_or_else(|| Error::InvalidOperation("search response has no body".to_string()))?;
+    let response: SearchResponse<T> = serde_json::from_str(response)?;
+    if let Some(error) = &response.error {
+        return Err(Error::InvalidOperation(format!(
+            "search failed: {}",
+            error.message
+        )));
+    }
+    Ok(response)
+}
+
+/// Calls a search action with parameters.
+async fn call(
+    client: &DataverseClient,
+    action: &str,
+    params: Vec<(&'static str, Json)>,
+) -> Result<Json, Error> {
+    let mut builder = client.action(action);
+    for (name, value) in params {
+        builder = builder.param(name, value);
+    }
+    builder.execute().await
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Searches across tables (`searchquery`).
+    pub fn search(&self, text: impl Into<String>) -> SearchQueryBuilder<'_> {
+        SearchQueryBuilder {
+            client: self,
+            params: SearchParams::new(text.into()),
+            facets: Vec::new(),
+            order_by: Vec::new(),
+            skip: None,
+            count: false,
+            mode: SearchMode::default(),
+            query_type: QueryType::default(),
+        }
+    }
+
+    /// Suggests rows matching partial text (`searchsuggest`).
+    pub fn search_suggest(&self, text: impl Into<String>) -> SearchSuggestBuilder<'_> {
+        SearchSuggestBuilder {
+            client: self,
+            params: SearchParams::new(text.into()),
+            order_by: Vec::new(),
+        }
+    }
+
+    /// Completes partial text to a likely search term
+    /// (`searchautocomplete`).
+    pub fn search_autocomplete(&self, text: impl Into<String>) -> SearchAutocompleteBuilder<'_> {
+        SearchAutocompleteBuilder {
+            client: self,
+            params: SearchParams::new(text.into()),
+        }
+    }
+}
+
+// =============================================================================
+// SearchQueryBuilder
+// =============================================================================
+
+/// Builder for a search query.
+pub struct SearchQueryBuilder<'a> {
+    client: &'a DataverseClient,
+    params: SearchParams,
+    facets: Vec<String>,
+    order_by: Vec<String>,
+    skip: Option<usize>,
+    count: bool,
+    mode: SearchMode,
+    query_type: QueryType,
+}
+
+impl<'a> SearchQueryBuilder<'a> {
+    /// Adds a table to search. Without any, all searchable tables are.
+    pub fn entity(mut self, entity: impl Into<SearchEntity>) -> Self {
+        self.params.entities.push(entity.into());
+        self
+    }
+
+    /// Adds a facet, e.g. `@search.entityname,count:10` or
+    /// `createdon,values:2024-01-01|2025-01-01`.
+    pub fn facet(mut self, facet: impl Into<String>) -> Self {
+        self.facets.push(facet.into());
+        self
+    }
+
+    /// Filters rows across all tables, e.g. `statecode eq 0`.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.params.filter = Some(filter.into());
+        self
+    }
+
+    /// Adds a sort, e.g. `createdon desc`. Without any, hits are sorted by
+    /// score.
+    pub fn order_by(mut self, order: impl Into<String>) -> Self {
+        self.order_by.push(order.into());
+        self
+    }
+
+    /// Returns at most `n` hits.
+    pub fn top(mut self, n: usize) -> Self {
+        self.params.top = Some(n);
+        self
+    }
+
+    /// Skips the first `n` hits.
+    pub fn skip(mut self, n: usize) -> Self {
+        self.skip = Some(n);
+        self
+    }
+
+    /// Also returns the total number of matches.
+    pub fn count(mut self) -> Self {
+        self.count = true;
+        self
+    }
+
+    /// Sets whether any or all terms must match.
+    pub fn mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets how the search text is parsed.
+    pub fn query_type(mut self, query_type: QueryType) -> Self {
+        self.query_type = query_type;
+        self
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, Json)> {
+        let mut params = self.params.parameters();
+        if !self.facets.is_empty() {
+            let facets = serde_json::to_string(&self.facets).unwrap_or_default();
+            params.push(("facets", Json::String(facets)));
+        }
+        if !self.order_by.is_empty() {
+            let order_by = serde_json::to_string(&self.order_by).unwrap_or_default();
+            params.push(("orderby", Json::String(order_by)));
+        }
+        if let Some(skip) = self.skip {
+            params.push(("skip", Json::from(skip)));
+        }
+        if self.count {
+            params.push(("count", Json::Bool(true)));
+        }
+        let mut options = Map::new();
+        if self.mode == SearchMode::All {
+            options.insert("searchmode".into(), "all".into());
+        }
+        if self.query_type == QueryType::Lucene {
+            options.insert("querytype".into(), "lucene".into());
+        }
+        if !options.is_empty() {
+            params.push(("options", Json::String(Json::Object(options).to_string())));
+        }
+        params
+    }
+
+    /// Runs the search.
+    pub async fn execute(self) -> Result<SearchResults, Error> {
+        let body = call(self.client, "searchquery", self.parameters()).await?;
+        let response = parse_response::<Vec<SearchHit>>(body)?;
+        Ok(SearchResults {
+            hits: response.value.unwrap_or_default(),
+            facets: response.facets,
+            count: response.count,
+        })
+    }
+}
+
+impl<'a> IntoFuture for SearchQueryBuilder<'a> {
+    type Output = Result<SearchResults, Error>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+// =============================================================================
+// SearchSuggestBuilder
+// =============================================================================
+
+/// Builder for search suggestions.
+pub struct SearchSuggestBuilder<'a> {
+    client: &'a DataverseClient,
+    params: SearchParams,
+    order_by: Vec<String>,
+}
+
+impl<'a> SearchSuggestBuilder<'a> {
+    /// Adds a table to suggest rows from. Without any, all searchable
+    /// tables are.
+    pub fn entity(mut self, entity: impl Into<SearchEntity>) -> Self {
+        self.params.entities.push(entity.into());
+        self
+    }
+
+    /// Filters rows across all tables.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.params.filter = Some(filter.into());
+        self
+    }
+
+    /// Adds a sort, e.g. `createdon desc`.
+    pub fn order_by(mut self, order: impl Into<String>) -> Self {
+        self.order_by.push(order.into());
+        self
+    }
+
+    /// Returns at most `n` suggestions.
+    pub fn top(mut self, n: usize) -> Self {
+        self.params.top = Some(n);
+        self
+    }
+
+    /// Also suggests rows with terms one edit away.
+    pub fn fuzzy(mut self) -> Self {
+        self.params.fuzzy = true;
+        self
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, Json)> {
+        let mut params = self.params.parameters();
+        if !self.order_by.is_empty() {
+            let order_by = serde_json::to_string(&self.order_by).unwrap_or_default();
+            params.push(("orderby", Json::String(order_by)));
+        }
+        params
+    }
+
+    /// Fetches the suggestions.
+    pub async fn execute(self) -> Result<Vec<Suggestion>, Error> {
+        let body = call(self.client, "searchsuggest", self.parameters()).await?;
+        Ok(parse_response::<Vec<Suggestion>>(body)?
+            .value
+            .unwrap_or_default())
+    }
+}
+
+impl<'a> IntoFuture for SearchSuggestBuilder<'a> {
+    type Output = Result<Vec<Suggestion>, Error>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+// =============================================================================
+// SearchAutocompleteBuilder
+// =============================================================================
+
+/// Builder for search autocompletion.
+pub struct SearchAutocompleteBuilder<'a> {
+    client: &'a DataverseClient,
+    params: SearchParams,
+}
+
+impl<'a> SearchAutocompleteBuilder<'a> {
+    /// Adds a table to complete from. Without any, all searchable tables
+    /// are.
+    pub fn entity(mut self, entity: impl Into<SearchEntity>) -> Self {
+        self.params.entities.push(entity.into());
+        self
+    }
+
+    /// Filters rows across all tables.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.params.filter = Some(filter.into());
+        self
+    }
+
+    /// Also completes from terms one edit away.
+    pub fn fuzzy(mut self) -> Self {
+        self.params.fuzzy = true;
+        self
+    }
+
+    /// Fetches the completion, if there is one.
+    pub async fn execute(self) -> Result<Option<String>, Error> {
+        let body = call(self.client, "searchautocomplete", self.params.parameters()).await?;
+        Ok(parse_response::<String>(body)?
+            .value
+            .filter(|text| !text.is_empty()))
+    }
+}
+
+impl<'a> IntoFuture for SearchAutocompleteBuilder<'a> {
+    type Output = Result<Option<String>, Error>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_query_parameters() {
+        let mut params = SearchParams::new("contoso".to_string());
+        params.entities = vec![
+            SearchEntity::new("account")
+                .select(&["name"])
+                .filter("statecode eq 0"),
+            "contact".into(),
+        ];
+        params.top = Some(5);
+        let entities = params
+            .parameters()
+            .into_iter()
+            .find(|(name, _)| *name == "entities")
+            .map(|(_, value)| value);
+        assert_eq!(
+            entities,
+            Some(Json::String(
+                r#"[{"name":"account","selectColumns":["name"],"filter":"statecode eq 0"},{"name":"contact"}]"#
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_query_response() {
+        let inner = json!({
+            "Error": null,
+            "Value": [{
+                "Id": "9ab3a7b6-2b4b-ef11-8409-000d3a1c1a11",
+                "EntityName": "account",
+                "ObjectTypeCode": 1,
+                "Attributes": { "name": "Contoso Ltd" },
+                "Highlights": { "name": ["{crmhit}Contoso{/crmhit} Ltd"] },
+                "Score": 4.2
+            }],
+            "Facets": { "@search.entityname": [{ "Type": "Value", "Value": "account", "Count": 1 }] },
+            "Count": 1
+        });
+        let body = json!({ "response": inner.to_string() });
+        let response = parse_response::<Vec<SearchHit>>(body).unwrap();
+
+        let hits = response.value.unwrap();
+        assert_eq!(hits[0].entity_name, "account");
+        assert_eq!(hits[0].highlights["name"], ["{crmhit}Contoso{/crmhit} Ltd"]);
+        assert_eq!(response.facets["@search.entityname"][0].count, 1);
+        assert_eq!(response.count, Some(1));
+
+        let failed = json!({ "response": r#"{"Error":{"Message":"Search is disabled"}}"# });
+        assert!(parse_response::<Vec<SearchHit>>(failed).is_err());
+    }
+}
@@ -0,0 +1,675 @@
+//! Solution export, import and publishing
+//!
+//! Solutions move customizations between environments. Exports come back as
+//! the solution's zip file; imports run as a system job that
+//! [`ClientImportSolutionBuilder`] tracks until it completes. A solution's
+//! contents are listed as [`SolutionComponent`]s, and [`SolutionDiff`]
+//! compares two component sets.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let zip = client.export_solution("ContosoCore").managed(true).await?;
+//!
+//! let import = target
+//!     .import_solution(zip)
+//!     .overwrite_unmanaged_customizations(true)
+//!     .stage_for_upgrade()
+//!     .publish_customizations()
+//!     .timeout(Duration::from_secs(1800))
+//!     .await?;
+//! println!("imported in job {}", import.job.id);
+//!
+//! // What differs between dev and prod?
+//! let diff = SolutionDiff::new(
+//!     &dev.solution_components("ContosoCore").await?,
+//!     &prod.solution_components("ContosoCore").await?,
+//! );
+//! for component in &diff.added {
+//!     println!("{:?} {}", component.component_type, component.object_id);
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::jobs::AsyncOperation;
+use super::query::Filter;
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::error::FieldError;
+use crate::model::DataverseEntity;
+use crate::model::DataverseOptionSet;
+use crate::model::Entity;
+use crate::model::FromValue;
+use crate::model::Value;
+use crate::model::types::OptionSetValue;
+
+/// Poll interval used while waiting for an import.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Response of `ExportSolution`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ExportSolutionResponse {
+    export_solution_file: String,
+}
+
+/// Response of `ImportSolutionAsync` and `StageAndUpgradeAsync`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ImportSolutionAsyncResponse {
+    async_operation_id: Uuid,
+    #[serde(default)]
+    import_job_key: Option<Uuid>,
+}
+
+/// A solution import that has been started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolutionImportJob {
+    /// The system job running the import.
+    pub async_operation_id: Uuid,
+    /// The `importjob` record holding the import log, if reported.
+    pub import_job_id: Option<Uuid>,
+}
+
+/// A completed solution import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionImport {
+    /// The finished system job.
+    pub job: AsyncOperation,
+    /// The `importjob` record holding the import log, if reported.
+    pub import_job_id: Option<Uuid>,
+}
+
+/// Type of a solution component (`componenttype`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentType {
+    /// A table.
+    Entity,
+    /// A column.
+    Attribute,
+    /// A relationship.
+    Relationship,
+    /// A global choice.
+    OptionSet,
+    /// A table relationship.
+    EntityRelationship,
+    /// A security role.
+    Role,
+    /// A view.
+    SavedQuery,
+    /// A process (workflow, flow, business rule, ...).
+    Workflow,
+    /// A chart.
+    SavedQueryVisualization,
+    /// A form.
+    SystemForm,
+    /// A web resource.
+    WebResource,
+    /// A site map.
+    SiteMap,
+    /// A plug-in type.
+    PluginType,
+    /// A plug-in assembly.
+    PluginAssembly,
+    /// A plug-in step.
+    SdkMessageProcessingStep,
+    /// A canvas app.
+    CanvasApp,
+    /// An environment variable definition.
+    EnvironmentVariableDefinition,
+    /// Any other type, by its code.
+    Other(i32),
+}
+
+impl ComponentType {
+    /// Known types and their codes.
+    const CODES: [(ComponentType, i32); 17] = [
+        (ComponentType::Entity, 1),
+        (ComponentType::Attribute, 2),
+        (ComponentType::Relationship, 3),
+        (ComponentType::OptionSet, 9),
+        (ComponentType::EntityRelationship, 10),
+        (ComponentType::Role, 20),
+        (ComponentType::SavedQuery, 26),
+        (ComponentType::Workflow, 29),
+        (ComponentType::SavedQueryVisualization, 59),
+        (ComponentType::SystemForm, 60),
+        (ComponentType::WebResource, 61),
+        (ComponentType::SiteMap, 62),
+        (ComponentType::PluginType, 90),
+        (ComponentType::PluginAssembly, 91),
+        (ComponentType::SdkMessageProcessingStep, 92),
+        (ComponentType::CanvasApp, 300),
+        (ComponentType::EnvironmentVariableDefinition, 380),
+    ];
+
+    /// Returns the type's `componenttype` code.
+    pub fn code(self) -> i32 {
+        match self {
+            ComponentType::Other(code) => code,
+            known => Self::CODES
+                .iter()
+                .find(|(t, _)| *t == known)
+                .map(|(_, code)| *code)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<i32> for ComponentType {
+    fn from(code: i32) -> Self {
+        Self::CODES
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(t, _)| *t)
+            .unwrap_or(ComponentType::Other(code))
+    }
+}
+
+impl FromValue for ComponentType {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        i32::from_value(field, value).map(ComponentType::from)
+    }
+}
+
+impl From<ComponentType> for Value {
+    fn from(component_type: ComponentType) -> Self {
+        Value::OptionSet(OptionSetValue::new(component_type.code()))
+    }
+}
+
+/// How much of a root component a solution includes
+/// (`rootcomponentbehavior`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum RootComponentBehavior {
+    /// The component and all its subcomponents.
+    IncludeSubcomponents = 0,
+    /// Only the component, with subcomponents added individually.
+    DoNotIncludeSubcomponents = 1,
+    /// Only the component's definition, without its subcomponents or data.
+    IncludeAsShellOnly = 2,
+}
+
+/// A component of a solution (`solutioncomponent` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "solutioncomponent", set = "solutioncomponents")]
+pub struct SolutionComponent {
+    /// The solution component record's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The component's type.
+    #[dataverse(rename = "componenttype", read_only)]
+    pub component_type: ComponentType,
+    /// The id of the component itself (the table, form, role, ...).
+    #[dataverse(rename = "objectid", read_only)]
+    pub object_id: Uuid,
+    /// How much of the component is included, for root components.
+    #[dataverse(rename = "rootcomponentbehavior", read_only)]
+    pub root_component_behavior: Option<RootComponentBehavior>,
+    /// The root component this one was added with, for subcomponents.
+    #[dataverse(rename = "rootsolutioncomponentid", read_only)]
+    pub root_solution_component_id: Option<Uuid>,
+    /// Whether the component is metadata (a table, column, ...) rather than
+    /// a record.
+    #[dataverse(rename = "ismetadata", read_only)]
+    pub is_metadata: Option<bool>,
+}
+
+impl SolutionComponent {
+    /// Returns whether this is a root component, added to the solution
+    /// directly rather than as part of another component.
+    pub fn is_root(&self) -> bool {
+        self.root_solution_component_id.is_none()
+    }
+
+    /// Identifies the component across solutions and environments.
+    fn key(&self) -> (ComponentType, Uuid) {
+        (self.component_type, self.object_id)
+    }
+}
+
+/// Differences between two solutions' components.
+///
+/// Components are matched by type and object id, which solution-aware
+/// components keep across environments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolutionDiff {
+    /// Components only in the second solution.
+    pub added: Vec<SolutionComponent>,
+    /// Components only in the first solution.
+    pub removed: Vec<SolutionComponent>,
+    /// Components in both whose root component behavior differs, as
+    /// `(first, second)`.
+    pub changed: Vec<(SolutionComponent, SolutionComponent)>,
+}
+
+impl SolutionDiff {
+    /// Compares the components of two solutions.
+    pub fn new(from: &[SolutionComponent], to: &[SolutionComponent]) -> Self {
+        let from_keys: HashMap<_, _> = from.iter().map(|c| (c.key(), c)).collect();
+        let to_keys: HashMap<_, _> = to.iter().map(|c| (c.key(), c)).collect();
+
+        let mut diff = SolutionDiff::default();
+        for component in to {
+            match from_keys.get(&component.key()) {
+                None => diff.added.push(component.clone()),
+                Some(old) if old.root_component_behavior != component.root_component_behavior => {
+                    diff.changed.push(((*old).clone(), component.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = from
+            .iter()
+            .filter(|c| !to_keys.contains_key(&c.key()))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Returns whether the solutions have the same components.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Exports a solution, returning its zip file.
+    ///
+    /// Exports unmanaged unless [`managed`](ClientExportSolutionBuilder::managed)
+    /// is set.
+    pub fn export_solution(&self, name: impl Into<String>) -> ClientExportSolutionBuilder<'_> {
+        ClientExportSolutionBuilder {
+            client: self,
+            name: name.into(),
+            managed: false,
+        }
+    }
+
+    /// Imports a solution zip file as a system job and waits for it to
+    /// complete.
+    ///
+    /// Fails if the job doesn't succeed. Use
+    /// [`start`](ClientImportSolutionBuilder::start) to only start the import.
+    pub fn import_solution(&self, file: impl Into<Bytes>) -> ClientImportSolutionBuilder<'_> {
+        ClientImportSolutionBuilder {
+            client: self,
+            file: file.into(),
+            overwrite_unmanaged_customizations: false,
+            publish_workflows: true,
+            stage_for_upgrade: false,
+            publish_customizations: false,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: None,
+        }
+    }
+
+    /// Lists the components of a solution, by its unique name.
+    pub async fn solution_components(
+        &self,
+        unique_name: &str,
+    ) -> Result<Vec<SolutionComponent>, Error> {
+        let solution = self
+            .query(Entity::set("solutions"))
+            .select(&["solutionid"])
+            .filter(Filter::eq("uniquename", unique_name))
+            .first(self)
+            .await?
+            .and_then(|solution| solution.id());
+        let Some(solution_id) = solution else {
+            return Err(Error::InvalidOperation(format!(
+                "Solution '{}' not found",
+                unique_name
+            )));
+        };
+
+        let mut pages = self
+            .query(SolutionComponent::entity())
+            .filter(Filter::eq("_solutionid_value", solution_id))
+            .into_typed::<SolutionComponent>()
+            .into_async_iter(self);
+        let mut components = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            components.extend(page?);
+        }
+        Ok(components)
+    }
+
+    /// Compares the components of two solutions in this environment.
+    ///
+    /// To compare across environments, list each side's components with
+    /// [`solution_components`](Self::solution_components) and use
+    /// [`SolutionDiff::new`].
+    pub async fn diff_solutions(&self, from: &str, to: &str) -> Result<SolutionDiff, Error> {
+        let from = self.solution_components(from).await?;
+        let to = self.solution_components(to).await?;
+        Ok(SolutionDiff::new(&from, &to))
+    }
+
+    /// Publishes all customizations, using `PublishAllXml`.
+    pub async fn publish_all_customizations(&self) -> Result<(), Error> {
+        self.action("PublishAllXml").execute().await?;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Export
+// =============================================================================
+
+/// Builder for exporting a solution, bound to a client.
+pub struct ClientExportSolutionBuilder<'a> {
+    client: &'a DataverseClient,
+    name: String,
+    managed: bool,
+}
+
+impl<'a> ClientExportSolutionBuilder<'a> {
+    /// Sets whether to export the solution as managed (default `false`).
+    pub fn managed(mut self, managed: bool) -> Self {
+        self.managed = managed;
+        self
+    }
+
+    async fn execute(self) -> Result<Bytes, Error> {
+        let response: ExportSolutionResponse = self
+            .client
+            .action("ExportSolution")
+            .param("SolutionName", self.name)
+            .param("Managed", self.managed)
+            .execute_as()
+            .await?;
+        let file = STANDARD
+            .decode(&response.export_solution_file)
+            .map_err(|e| {
+                Error::Api(ApiError::Parse {
+                    message: format!("Invalid solution file: {}", e),
+                    body: None,
+                })
+            })?;
+        Ok(file.into())
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientExportSolutionBuilder<'a> {
+    type Output = Result<Bytes, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+// =============================================================================
+// Import
+// =============================================================================
+
+/// Builder for importing a solution, bound to a client.
+pub struct ClientImportSolutionBuilder<'a> {
+    client: &'a DataverseClient,
+    file: Bytes,
+    overwrite_unmanaged_customizations: bool,
+    publish_workflows: bool,
+    stage_for_upgrade: bool,
+    publish_customizations: bool,
+    poll_interval: Duration,
+    timeout: Option<Duration>,
+}
+
+impl<'a> ClientImportSolutionBuilder<'a> {
+    /// Sets whether unmanaged customizations of the solution's components are
+    /// overwritten (default `false`).
+    pub fn overwrite_unmanaged_customizations(mut self, overwrite: bool) -> Self {
+        self.overwrite_unmanaged_customizations = overwrite;
+        self
+    }
+
+    /// Sets whether the solution's workflows are activated (default `true`).
+    pub fn publish_workflows(mut self, publish: bool) -> Self {
+        self.publish_workflows = publish;
+        self
+    }
+
+    /// Imports a managed solution as an upgrade, with `StageAndUpgradeAsync`.
+    ///
+    /// The new version is staged and applied in one job, removing components
+    /// the new version no longer contains.
+    pub fn stage_for_upgrade(mut self) -> Self {
+        self.stage_for_upgrade = true;
+        self
+    }
+
+    /// Publishes all customizations once the import succeeds.
+    pub fn publish_customizations(mut self) -> Self {
+        self.publish_customizations = true;
+        self
+    }
+
+    /// Sets how often the import job is polled (default 5 seconds).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Fails with [`ApiError::Timeout`] if the import hasn't completed within
+    /// `timeout`. Waits indefinitely by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Starts the import without waiting for it.
+    ///
+    /// Publishing isn't done; track the job with
+    /// [`DataverseClient::wait_for_completion`].
+    pub async fn start(&self) -> Result<SolutionImportJob, Error> {
+        let action = if self.stage_for_upgrade {
+            "StageAndUpgradeAsync"
+        } else {
+            "ImportSolutionAsync"
+        };
+        let response: ImportSolutionAsyncResponse = self
+            .client
+            .action(action)
+            .param("CustomizationFile", STANDARD.encode(&self.file))
+            .param(
+                "OverwriteUnmanagedCustomizations",
+                self.overwrite_unmanaged_customizations,
+            )
+            .param("PublishWorkflows", self.publish_workflows)
+            .execute_as()
+            .await?;
+        Ok(SolutionImportJob {
+            async_operation_id: response.async_operation_id,
+            import_job_id: response.import_job_key,
+        })
+    }
+
+    async fn execute(self) -> Result<SolutionImport, Error> {
+        let started = self.start().await?;
+        let mut wait = self
+            .client
+            .wait_for_completion(started.async_operation_id, self.poll_interval);
+        if let Some(timeout) = self.timeout {
+            wait = wait.timeout(timeout);
+        }
+        let job = wait.await?;
+        if !job.succeeded() {
+            return Err(Error::InvalidOperation(format!(
+                "Solution import {:?}: {}",
+                job.status,
+                job.error_message().unwrap_or("no details")
+            )));
+        }
+
+        if self.publish_customizations {
+            self.client.publish_all_customizations().await?;
+        }
+        Ok(SolutionImport {
+            job,
+            import_job_id: started.import_job_id,
+        })
+    }
+}
+
+impl<'a> std::future::IntoFuture for ClientImportSolutionBuilder<'a> {
+    type Output = Result<SolutionImport, Error>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.execute())
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new().entity(MockEntity::new(
+            "asyncoperation",
+            "asyncoperations",
+            "asyncoperationid",
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_components_and_diff() {
+        use crate::model::Record;
+        use crate::model::types::EntityBinding;
+
+        let mock = MockDataverse::new()
+            .entity(
+                MockEntity::new("solution", "solutions", "solutionid").primary_name("uniquename"),
+            )
+            .entity(
+                MockEntity::new(
+                    "solutioncomponent",
+                    "solutioncomponents",
+                    "solutioncomponentid",
+                )
+                .lookup("solutionid", "solution"),
+            );
+        let client = mock.client();
+        let solution = |name: &str| {
+            mock.insert(
+                Entity::set("solutions"),
+                Record::new("solution").set("uniquename", name),
+            )
+        };
+        let component = |solution: Uuid, component_type: i32, object_id: Uuid, behavior: i32| {
+            mock.insert(
+                Entity::set("solutioncomponents"),
+                Record::new("solutioncomponent")
+                    .set("solutionid", EntityBinding::new("solutions", solution))
+                    .set("componenttype", component_type)
+                    .set("objectid", object_id)
+                    .set("rootcomponentbehavior", behavior),
+            );
+        };
+        let (table, form, role, plugin) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        let v1 = solution("Core_1");
+        component(v1, 1, table, 0);
+        component(v1, 60, form, 0);
+        component(v1, 20, role, 0);
+        let v2 = solution("Core_2");
+        component(v2, 1, table, 2);
+        component(v2, 60, form, 0);
+        component(v2, 12345, plugin, 0);
+
+        let components = client.solution_components("Core_1").await.unwrap();
+        assert_eq!(components.len(), 3);
+        assert!(components.iter().all(SolutionComponent::is_root));
+        assert!(
+            components
+                .iter()
+                .any(|c| c.component_type == ComponentType::Role && c.object_id == role)
+        );
+
+        let diff = client.diff_solutions("Core_1", "Core_2").await.unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].component_type, ComponentType::Other(12345));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].object_id, role);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].1.root_component_behavior,
+            Some(RootComponentBehavior::IncludeAsShellOnly)
+        );
+        assert!(SolutionDiff::new(&components, &components).is_empty());
+
+        assert!(client.solution_components("Missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import() {
+        let source = mock();
+        source.add_solution("ContosoCore", b"PK solution".to_vec());
+        let zip = source
+            .client()
+            .export_solution("ContosoCore")
+            .managed(true)
+            .await
+            .unwrap();
+        assert_eq!(&zip[..], b"PK solution");
+        assert!(source.client().export_solution("Missing").await.is_err());
+
+        let target = mock();
+        let client = target.client();
+        let import = client
+            .import_solution(zip)
+            .stage_for_upgrade()
+            .publish_customizations()
+            .poll_interval(Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert!(import.job.succeeded());
+        assert!(import.import_job_id.is_some());
+        assert_eq!(target.imported_solutions(), vec![b"PK solution".to_vec()]);
+        let paths: Vec<String> = target
+            .requests()
+            .iter()
+            .map(|r| r.url.path().to_string())
+            .collect();
+        assert!(paths.contains(&"/api/data/v9.2/StageAndUpgradeAsync".to_string()));
+        assert_eq!(paths.last().unwrap(), "/api/data/v9.2/PublishAllXml");
+
+        // The mock fails empty solution files
+        let failed = client
+            .import_solution(Bytes::new())
+            .publish_customizations()
+            .poll_interval(Duration::from_millis(1))
+            .await;
+        assert!(matches!(failed, Err(Error::InvalidOperation(_))));
+        assert_eq!(
+            target
+                .requests()
+                .iter()
+                .filter(|r| r.url.path().ends_with("PublishAllXml"))
+                .count(),
+            1
+        );
+    }
+}
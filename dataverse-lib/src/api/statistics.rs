@@ -0,0 +1,177 @@
+//! Table statistics
+//!
+//! Record counts from `RetrieveTotalRecordCount`, which reads a snapshot
+//! Dataverse keeps per table instead of scanning it. The snapshot is
+//! refreshed periodically, so counts can lag recent creates and deletes;
+//! use [`QueryBuilder::count`](crate::api::query::odata::QueryBuilder::count)
+//! for an exact, filtered count.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let counts = client
+//!     .total_record_counts([Entity::logical("account"), Entity::logical("contact")])
+//!     .await?;
+//! println!("{} accounts", counts["account"]);
+//!
+//! for table in client.table_statistics().await? {
+//!     println!("{:>10}  {}", table.record_count, table.logical_name);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::DataverseClient;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+
+/// Tables counted per `RetrieveTotalRecordCount` call, keeping URLs short.
+const COUNT_CHUNK_SIZE: usize = 100;
+
+/// Record count of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStatistics {
+    /// The table's logical name.
+    pub logical_name: String,
+    /// The table's entity set name.
+    pub entity_set_name: String,
+    /// The table's display name, if it has one.
+    pub display_name: Option<String>,
+    /// Number of records in the table's last snapshot.
+    pub record_count: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TotalRecordCountResponse {
+    entity_record_count_collection: RecordCounts,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecordCounts {
+    keys: Vec<String>,
+    values: Vec<i64>,
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Returns the number of records of a table.
+    pub async fn total_record_count(&self, entity: impl Into<Entity>) -> Result<usize, Error> {
+        let logical_name = self.resolve_entity_logical_name(&entity.into()).await?;
+        let mut counts = self
+            .total_record_counts([Entity::logical(&logical_name)])
+            .await?;
+        counts.remove(&logical_name).ok_or_else(|| {
+            Error::Api(ApiError::parse(format!(
+                "RetrieveTotalRecordCount returned no count for '{}'",
+                logical_name
+            )))
+        })
+    }
+
+    /// Returns the number of records of several tables, by logical name.
+    pub async fn total_record_counts<E: Into<Entity>>(
+        &self,
+        entities: impl IntoIterator<Item = E>,
+    ) -> Result<HashMap<String, usize>, Error> {
+        let mut names = Vec::new();
+        for entity in entities {
+            names.push(self.resolve_entity_logical_name(&entity.into()).await?);
+        }
+
+        let mut counts = HashMap::with_capacity(names.len());
+        for chunk in names.chunks(COUNT_CHUNK_SIZE) {
+            let response: TotalRecordCountResponse = self
+                .function("RetrieveTotalRecordCount")
+                .param("EntityNames", chunk)
+                .execute_as()
+                .await?;
+            let collection = response.entity_record_count_collection;
+            for (name, count) in collection.keys.into_iter().zip(collection.values) {
+                counts.insert(name, count.max(0) as usize);
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Returns the record count of every table valid for Advanced Find,
+    /// largest first.
+    pub async fn table_statistics(&self) -> Result<Vec<TableStatistics>, Error> {
+        let tables: Vec<_> = self
+            .metadata()
+            .all_entities()
+            .await?
+            .into_iter()
+            .filter(|table| table.is_valid_for_advanced_find)
+            .collect();
+        let counts = self
+            .total_record_counts(tables.iter().map(|t| Entity::logical(&t.logical_name)))
+            .await?;
+
+        let mut statistics: Vec<TableStatistics> = tables
+            .into_iter()
+            .map(|table| TableStatistics {
+                record_count: counts.get(&table.logical_name).copied().unwrap_or(0),
+                display_name: table.display_name.text().map(str::to_string),
+                logical_name: table.logical_name,
+                entity_set_name: table.entity_set_name,
+            })
+            .collect();
+        statistics.sort_by(|a, b| {
+            b.record_count
+                .cmp(&a.record_count)
+                .then_with(|| a.logical_name.cmp(&b.logical_name))
+        });
+        Ok(statistics)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+
+    #[tokio::test]
+    async fn test_record_counts() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid"))
+            .entity(MockEntity::new("contact", "contacts", "contactid"))
+            .entity(MockEntity::new("lead", "leads", "leadid"));
+        let client = mock.client();
+        for _ in 0..3 {
+            mock.insert(Entity::set("contacts"), Record::new("contact"));
+        }
+        mock.insert(Entity::set("accounts"), Record::new("account"));
+
+        let counts = client
+            .total_record_counts([Entity::logical("account"), Entity::set("contacts")])
+            .await
+            .unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!((counts["account"], counts["contact"]), (1, 3));
+        assert_eq!(
+            client
+                .total_record_count(Entity::logical("lead"))
+                .await
+                .unwrap(),
+            0
+        );
+
+        let statistics = client.table_statistics().await.unwrap();
+        let tables: Vec<(&str, usize)> = statistics
+            .iter()
+            .map(|t| (t.logical_name.as_str(), t.record_count))
+            .collect();
+        assert_eq!(tables, [("contact", 3), ("account", 1), ("lead", 0)]);
+        assert_eq!(statistics[0].entity_set_name, "contacts");
+    }
+}
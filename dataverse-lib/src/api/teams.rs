@@ -0,0 +1,462 @@
+//! Teams, business units and security roles
+//!
+//! Helpers for common admin questions: who is in a team, which teams a user
+//! belongs to, how business units nest, and which security roles a user or
+//! team has. Memberships and role assignments are read from the intersect
+//! tables (`teammembership`, `systemuserroles`, `teamroles`) and the records
+//! they point to are then fetched in chunks.
+//!
+//! Everything goes through [`DataverseClient::query`], so results are cached
+//! like other queries for the client's query TTL; changes to memberships or
+//! roles can take that long to show.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let me = client.who_am_i().await?;
+//! for role in client.security_roles(Owner::User(me.user_id)).await? {
+//!     println!("{}", role.name.unwrap_or_default());
+//! }
+//!
+//! let hierarchy = client.business_unit_hierarchy().await?;
+//! for unit in hierarchy.ancestors(me.business_unit_id) {
+//!     println!("under {}", unit.name.as_deref().unwrap_or_default());
+//! }
+//! ```
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use super::Owner;
+use super::query::Filter;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::DataverseEntity;
+use crate::model::DataverseOptionSet;
+use crate::model::Entity;
+
+/// Records fetched per query when resolving ids, keeping URLs short.
+const ID_CHUNK_SIZE: usize = 50;
+
+/// The kind of a team (`teamtype`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DataverseOptionSet)]
+pub enum TeamType {
+    /// A team that can own records.
+    Owner = 0,
+    /// A team records are shared with, which can't own records.
+    Access = 1,
+    /// A team whose members come from a Microsoft Entra ID security group.
+    SecurityGroup = 2,
+    /// A team whose members come from a Microsoft 365 group.
+    OfficeGroup = 3,
+}
+
+/// A team (`team` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "team", set = "teams")]
+pub struct Team {
+    /// The team's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The team's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The kind of team.
+    #[dataverse(rename = "teamtype", read_only)]
+    pub team_type: Option<TeamType>,
+    /// The business unit the team belongs to.
+    #[dataverse(rename = "businessunitid", read_only)]
+    pub business_unit_id: Option<Uuid>,
+    /// Whether this is the default team of its business unit.
+    #[dataverse(rename = "isdefault", read_only)]
+    pub is_default: Option<bool>,
+}
+
+/// A user (`systemuser` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "systemuser", set = "systemusers")]
+pub struct SystemUser {
+    /// The user's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The user's full name.
+    #[dataverse(rename = "fullname", read_only)]
+    pub full_name: Option<String>,
+    /// The user's sign-in name.
+    #[dataverse(rename = "domainname", read_only)]
+    pub domain_name: Option<String>,
+    /// The business unit the user belongs to.
+    #[dataverse(rename = "businessunitid", read_only)]
+    pub business_unit_id: Option<Uuid>,
+    /// Whether the user is disabled.
+    #[dataverse(rename = "isdisabled", read_only)]
+    pub is_disabled: Option<bool>,
+}
+
+/// A business unit (`businessunit` record).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "businessunit", set = "businessunits")]
+pub struct BusinessUnit {
+    /// The business unit's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The business unit's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The parent business unit, `None` for the root business unit.
+    #[dataverse(rename = "parentbusinessunitid", read_only)]
+    pub parent_id: Option<Uuid>,
+    /// Whether the business unit is disabled.
+    #[dataverse(rename = "isdisabled", read_only)]
+    pub is_disabled: Option<bool>,
+}
+
+/// A security role (`role` record).
+///
+/// Roles are copied to every business unit, so the same role has a record
+/// per business unit, all sharing the root business unit's record as
+/// [`parent_root_role_id`](Self::parent_root_role_id).
+#[derive(Debug, Clone, PartialEq, DataverseEntity)]
+#[dataverse(entity = "role", set = "roles")]
+pub struct SecurityRole {
+    /// The role's id.
+    #[dataverse(id)]
+    pub id: Uuid,
+    /// The role's name.
+    #[dataverse(read_only)]
+    pub name: Option<String>,
+    /// The business unit this copy of the role belongs to.
+    #[dataverse(rename = "businessunitid", read_only)]
+    pub business_unit_id: Option<Uuid>,
+    /// The root business unit's copy of the role.
+    #[dataverse(rename = "parentrootroleid", read_only)]
+    pub parent_root_role_id: Option<Uuid>,
+}
+
+/// The business units of an organization, arranged by parent.
+#[derive(Debug, Clone, Default)]
+pub struct BusinessUnitHierarchy {
+    units: Vec<BusinessUnit>,
+}
+
+impl BusinessUnitHierarchy {
+    /// Arranges business units by parent.
+    pub fn new(units: Vec<BusinessUnit>) -> Self {
+        Self { units }
+    }
+
+    /// Returns all business units.
+    pub fn units(&self) -> &[BusinessUnit] {
+        &self.units
+    }
+
+    /// Returns a business unit by id.
+    pub fn get(&self, id: Uuid) -> Option<&BusinessUnit> {
+        self.units.iter().find(|unit| unit.id == id)
+    }
+
+    /// Returns the root business unit.
+    pub fn root(&self) -> Option<&BusinessUnit> {
+        self.units.iter().find(|unit| unit.parent_id.is_none())
+    }
+
+    /// Returns the business units directly under a business unit.
+    pub fn children(&self, id: Uuid) -> Vec<&BusinessUnit> {
+        self.units
+            .iter()
+            .filter(|unit| unit.parent_id == Some(id))
+            .collect()
+    }
+
+    /// Returns the business units above a business unit, its parent first
+    /// and the root last.
+    pub fn ancestors(&self, id: Uuid) -> Vec<&BusinessUnit> {
+        let mut ancestors: Vec<&BusinessUnit> = Vec::new();
+        let mut parent = self.get(id).and_then(|unit| unit.parent_id);
+        while let Some(unit) = parent.and_then(|id| self.get(id)) {
+            if ancestors.iter().any(|a| a.id == unit.id) {
+                break;
+            }
+            ancestors.push(unit);
+            parent = unit.parent_id;
+        }
+        ancestors
+    }
+
+    /// Returns the business units below a business unit, each followed by
+    /// its own descendants.
+    pub fn descendants(&self, id: Uuid) -> Vec<&BusinessUnit> {
+        let mut descendants = Vec::new();
+        let mut seen = HashSet::from([id]);
+        let mut stack: Vec<&BusinessUnit> = self.children(id).into_iter().rev().collect();
+        while let Some(unit) = stack.pop() {
+            if !seen.insert(unit.id) {
+                continue;
+            }
+            descendants.push(unit);
+            stack.extend(self.children(unit.id).into_iter().rev());
+        }
+        descendants
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Lists the members of a team.
+    pub async fn team_members(&self, team: Uuid) -> Result<Vec<SystemUser>, Error> {
+        let users = self
+            .intersect_ids("teammembership", "teamid", team, "systemuserid")
+            .await?;
+        self.records_by_id("systemuserid", users).await
+    }
+
+    /// Lists the teams a user is a member of.
+    pub async fn user_teams(&self, user: Uuid) -> Result<Vec<Team>, Error> {
+        let teams = self
+            .intersect_ids("teammembership", "systemuserid", user, "teamid")
+            .await?;
+        self.records_by_id("teamid", teams).await
+    }
+
+    /// Returns the organization's business units, arranged by parent.
+    pub async fn business_unit_hierarchy(&self) -> Result<BusinessUnitHierarchy, Error> {
+        let mut pages = self
+            .query(BusinessUnit::entity())
+            .into_typed::<BusinessUnit>()
+            .into_async_iter(self);
+        let mut units = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            units.extend(page?);
+        }
+        Ok(BusinessUnitHierarchy::new(units))
+    }
+
+    /// Lists the security roles of a user or team.
+    ///
+    /// A user's roles include those of the teams the user is a member of.
+    pub async fn security_roles(&self, principal: Owner) -> Result<Vec<SecurityRole>, Error> {
+        let mut roles = match principal {
+            Owner::User(user) => {
+                let mut roles = self
+                    .intersect_ids("systemuserroles", "systemuserid", user, "roleid")
+                    .await?;
+                for team in self
+                    .intersect_ids("teammembership", "systemuserid", user, "teamid")
+                    .await?
+                {
+                    roles.extend(
+                        self.intersect_ids("teamroles", "teamid", team, "roleid")
+                            .await?,
+                    );
+                }
+                roles
+            }
+            Owner::Team(team) => {
+                self.intersect_ids("teamroles", "teamid", team, "roleid")
+                    .await?
+            }
+        };
+        let mut seen = HashSet::new();
+        roles.retain(|role| seen.insert(*role));
+        self.records_by_id("roleid", roles).await
+    }
+
+    /// Returns the `column` ids of an intersect table's rows whose `key` is
+    /// `id`.
+    async fn intersect_ids(
+        &self,
+        intersect: &str,
+        key: &str,
+        id: Uuid,
+        column: &str,
+    ) -> Result<Vec<Uuid>, Error> {
+        let mut pages = self
+            .query(Entity::logical(intersect))
+            .select(&[column])
+            .filter(Filter::eq(key, id))
+            .into_async_iter(self);
+        let mut ids = Vec::new();
+        while let Some(page) = pages.next(self).await {
+            for record in page?.records() {
+                if let Some(id) = record.get_guid(column)? {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Fetches the records of a table by their primary key `id_column`.
+    async fn records_by_id<T: DataverseEntity>(
+        &self,
+        id_column: &str,
+        ids: Vec<Uuid>,
+    ) -> Result<Vec<T>, Error> {
+        let mut records = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(ID_CHUNK_SIZE) {
+            let mut pages = self
+                .query(T::entity())
+                .filter(Filter::or(
+                    chunk.iter().map(|id| Filter::eq(id_column, *id)),
+                ))
+                .into_typed::<T>()
+                .into_async_iter(self);
+            while let Some(page) = pages.next(self).await {
+                records.extend(page?);
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+    use crate::model::metadata::AttributeType;
+    use crate::model::types::EntityBinding;
+
+    fn intersect(logical_name: &str, set: &str, columns: [&str; 2]) -> MockEntity {
+        MockEntity::new(logical_name, set, format!("{}id", logical_name))
+            .attribute(columns[0], AttributeType::Uniqueidentifier)
+            .attribute(columns[1], AttributeType::Uniqueidentifier)
+    }
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(
+                MockEntity::new("businessunit", "businessunits", "businessunitid")
+                    .primary_name("name")
+                    .lookup("parentbusinessunitid", "businessunit"),
+            )
+            .entity(
+                MockEntity::new("systemuser", "systemusers", "systemuserid")
+                    .primary_name("fullname"),
+            )
+            .entity(MockEntity::new("team", "teams", "teamid").primary_name("name"))
+            .entity(MockEntity::new("role", "roles", "roleid").primary_name("name"))
+            .entity(intersect(
+                "teammembership",
+                "teammemberships",
+                ["teamid", "systemuserid"],
+            ))
+            .entity(intersect(
+                "systemuserroles",
+                "systemuserrolescollection",
+                ["systemuserid", "roleid"],
+            ))
+            .entity(intersect(
+                "teamroles",
+                "teamrolescollection",
+                ["teamid", "roleid"],
+            ))
+    }
+
+    fn names<'a>(names: impl IntoIterator<Item = &'a Option<String>>) -> Vec<&'a str> {
+        let mut names: Vec<&str> = names.into_iter().flatten().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_team_members_and_roles() {
+        let mock = mock();
+        let client = mock.client();
+        let insert = |set: &str, record: Record| mock.insert(Entity::set(set), record);
+        let ada = insert(
+            "systemusers",
+            Record::new("systemuser").set("fullname", "Ada"),
+        );
+        let grace = insert(
+            "systemusers",
+            Record::new("systemuser").set("fullname", "Grace"),
+        );
+        let sales = insert("teams", Record::new("team").set("name", "Sales"));
+        let support = insert("teams", Record::new("team").set("name", "Support"));
+        let maker = insert("roles", Record::new("role").set("name", "Maker"));
+        let viewer = insert("roles", Record::new("role").set("name", "Viewer"));
+        for (team, user) in [(sales, ada), (sales, grace), (support, ada)] {
+            insert(
+                "teammemberships",
+                Record::new("teammembership")
+                    .set("teamid", team)
+                    .set("systemuserid", user),
+            );
+        }
+        insert(
+            "systemuserrolescollection",
+            Record::new("systemuserroles")
+                .set("systemuserid", ada)
+                .set("roleid", maker),
+        );
+        for team in [sales, support] {
+            insert(
+                "teamrolescollection",
+                Record::new("teamroles")
+                    .set("teamid", team)
+                    .set("roleid", viewer),
+            );
+        }
+
+        let members = client.team_members(sales).await.unwrap();
+        assert_eq!(
+            names(members.iter().map(|u| &u.full_name)),
+            ["Ada", "Grace"]
+        );
+        let teams = client.user_teams(grace).await.unwrap();
+        assert_eq!(names(teams.iter().map(|t| &t.name)), ["Sales"]);
+
+        let roles = client.security_roles(Owner::User(ada)).await.unwrap();
+        assert_eq!(names(roles.iter().map(|r| &r.name)), ["Maker", "Viewer"]);
+        let roles = client.security_roles(Owner::Team(support)).await.unwrap();
+        assert_eq!(names(roles.iter().map(|r| &r.name)), ["Viewer"]);
+        assert!(
+            client
+                .team_members(Uuid::new_v4())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_business_unit_hierarchy() {
+        let mock = mock();
+        let client = mock.client();
+        let unit = |name: &str, parent: Option<Uuid>| {
+            let mut record = Record::new("businessunit").set("name", name);
+            if let Some(parent) = parent {
+                record = record.set(
+                    "parentbusinessunitid",
+                    EntityBinding::new("businessunits", parent),
+                );
+            }
+            mock.insert(Entity::set("businessunits"), record)
+        };
+        let root = unit("Contoso", None);
+        let europe = unit("Europe", Some(root));
+        let france = unit("France", Some(europe));
+        let americas = unit("Americas", Some(root));
+
+        let hierarchy = client.business_unit_hierarchy().await.unwrap();
+        assert_eq!(hierarchy.units().len(), 4);
+        assert_eq!(hierarchy.root().map(|u| u.id), Some(root));
+        let ids = |units: Vec<&BusinessUnit>| units.iter().map(|u| u.id).collect::<Vec<_>>();
+        assert_eq!(ids(hierarchy.ancestors(france)), [europe, root]);
+        assert_eq!(ids(hierarchy.children(europe)), [france]);
+        let descendants = ids(hierarchy.descendants(root));
+        assert_eq!(descendants.len(), 3);
+        assert!(
+            descendants.iter().position(|id| *id == europe)
+                < descendants.iter().position(|id| *id == france)
+        );
+        assert!(descendants.contains(&americas));
+        assert!(hierarchy.descendants(france).is_empty());
+    }
+}
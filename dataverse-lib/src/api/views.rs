@@ -1 +1,157 @@
 //! Saved queries (system views) and user queries
+//!
+//! [`DataverseClient::quick_find`] runs a table's quick find view, the view
+//! behind search boxes and lookup pickers. The view's FetchXML filters on
+//! its searchable columns with a `{0}` placeholder for the search term, next
+//! to its other filters (e.g. active records only); the term is put in the
+//! placeholder and the view parsed into a [`FetchBuilder`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut pages = client
+//!     .quick_find(Entity::logical("account"), "cont")
+//!     .await?
+//!     .page_size(25)
+//!     .into_async_iter();
+//!
+//! while let Some(page) = pages.next().await {
+//!     for record in page?.records() {
+//!         println!("{:?}", record.get_string("name")?);
+//!     }
+//! }
+//! ```
+
+use super::query::Filter;
+use super::query::fetchxml::FetchBuilder;
+use super::query::fetchxml::xml::escape_xml;
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::model::Entity;
+
+/// `querytype` of quick find views.
+const QUICK_FIND_QUERY_TYPE: i32 = 4;
+
+/// Placeholder for the search term in a quick find view's FetchXML.
+const SEARCH_TERM: &str = "{0}";
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Builds a table's quick find query for a search term.
+    ///
+    /// Like quick find in model-driven apps, the term matches the start of
+    /// any of the view's searchable columns, and `*` is a wildcard: `*son`
+    /// matches `Anderson`. The returned builder has the view's columns,
+    /// filters and ordering, and can be refined and paged like any fetch.
+    ///
+    /// Returns [`Error::InvalidOperation`] if the table has no quick find
+    /// view.
+    pub async fn quick_find(
+        &self,
+        entity: impl Into<Entity>,
+        term: &str,
+    ) -> Result<FetchBuilder<'_>, Error> {
+        let logical_name = self.resolve_entity_logical_name(&entity.into()).await?;
+        let view = self
+            .query(Entity::logical("savedquery"))
+            .select(&["fetchxml"])
+            .filter(Filter::and([
+                Filter::eq("returnedtypecode", logical_name.as_str()),
+                Filter::eq("querytype", QUICK_FIND_QUERY_TYPE),
+                Filter::eq("isquickfindquery", true),
+            ]))
+            .first(self)
+            .await?
+            .ok_or_else(|| {
+                Error::InvalidOperation(format!("'{}' has no quick find view", logical_name))
+            })?;
+        let fetch_xml = view.get_string("fetchxml")?.unwrap_or_default();
+        let pattern = escape_xml(&search_pattern(term));
+        self.fetch_xml(&fetch_xml.replace(SEARCH_TERM, &pattern))
+    }
+}
+
+/// Turns a quick find search term into a `like` pattern.
+fn search_pattern(term: &str) -> String {
+    let mut pattern = term.trim().replace('*', "%");
+    if !pattern.ends_with('%') {
+        pattern.push('%');
+    }
+    pattern
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Record;
+    use crate::model::metadata::AttributeType;
+
+    #[test]
+    fn test_search_pattern() {
+        assert_eq!(search_pattern("cont"), "cont%");
+        assert_eq!(search_pattern(" *son "), "%son%");
+        assert_eq!(search_pattern("a*b*"), "a%b%");
+        assert_eq!(search_pattern(""), "%");
+    }
+
+    #[tokio::test]
+    async fn test_quick_find() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("savedquery", "savedqueries", "savedqueryid")
+                    .primary_name("name")
+                    .attribute("fetchxml", AttributeType::Memo)
+                    .attribute("returnedtypecode", AttributeType::EntityName)
+                    .attribute("querytype", AttributeType::Integer)
+                    .attribute("isquickfindquery", AttributeType::Boolean),
+            );
+        let client = mock.client();
+        mock.insert(
+            Entity::set("savedqueries"),
+            Record::new("savedquery")
+                .set("name", "Quick Find Active Accounts")
+                .set("returnedtypecode", "account")
+                .set("querytype", QUICK_FIND_QUERY_TYPE)
+                .set("isquickfindquery", true)
+                .set(
+                    "fetchxml",
+                    concat!(
+                        r#"<fetch version="1.0" mapping="logical"><entity name="account">"#,
+                        r#"<attribute name="name"/><attribute name="accountnumber"/>"#,
+                        r#"<order attribute="name"/>"#,
+                        r#"<filter type="and">"#,
+                        r#"<condition attribute="statecode" operator="eq" value="0"/>"#,
+                        r#"<filter type="or" isquickfindfields="1">"#,
+                        r#"<condition attribute="name" operator="like" value="{0}"/>"#,
+                        r#"<condition attribute="accountnumber" operator="like" value="{0}"/>"#,
+                        r#"</filter></filter></entity></fetch>"#,
+                    ),
+                ),
+        );
+
+        let builder = client
+            .quick_find(Entity::set("accounts"), "Con & Co*")
+            .await
+            .unwrap();
+        assert_eq!(builder.select_value(), ["name", "accountnumber"]);
+        assert_eq!(
+            builder.filter_value(),
+            Some(&Filter::and([
+                Filter::eq("statecode", "0"),
+                Filter::or([
+                    Filter::starts_with("name", "Con & Co"),
+                    Filter::starts_with("accountnumber", "Con & Co"),
+                ]),
+            ]))
+        );
+
+        let err = client.quick_find(Entity::logical("contact"), "a").await;
+        assert!(matches!(err, Err(Error::InvalidOperation(_))));
+    }
+}
@@ -1,11 +1,14 @@
 //! Automatic token refresh handling.
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use tokio::sync::RwLock;
 
 use super::AccessToken;
+use super::TokenCache;
+use super::TokenCacheKey;
 use super::TokenProvider;
 use crate::error::AuthError;
 
@@ -40,6 +43,7 @@ pub trait AuthFlow: Send + Sync {
 /// - Returns cached token if still valid
 /// - Refreshes using refresh_token when token is expired or expiring soon
 /// - Falls back to full re-authentication if refresh fails
+/// - Optionally persists tokens in a [`TokenCache`] so they survive restarts
 ///
 /// # Example
 ///
@@ -63,6 +67,8 @@ pub struct AutoRefreshTokenProvider<F> {
     token: RwLock<Option<AccessToken>>,
     /// Refresh this many seconds before actual expiry
     refresh_buffer: Duration,
+    /// Persistent cache and the key tokens are stored under
+    cache: Option<(Arc<dyn TokenCache>, TokenCacheKey)>,
 }
 
 impl<F: AuthFlow> AutoRefreshTokenProvider<F> {
@@ -75,6 +81,7 @@ impl<F: AuthFlow> AutoRefreshTokenProvider<F> {
             flow,
             token: RwLock::new(None),
             refresh_buffer: Duration::from_secs(300), // 5 minutes
+            cache: None,
         }
     }
 
@@ -89,14 +96,56 @@ impl<F: AuthFlow> AutoRefreshTokenProvider<F> {
             flow,
             token: RwLock::new(None),
             refresh_buffer,
+            cache: None,
         }
     }
 
+    /// Persists tokens in a [`TokenCache`] under the given key.
+    ///
+    /// When no token is held yet, the cached one is used (and refreshed if
+    /// needed) before falling back to authentication. New tokens are written
+    /// back to the cache. Cache failures are logged and otherwise ignored.
+    pub fn with_token_cache(
+        mut self,
+        cache: impl TokenCache + 'static,
+        key: TokenCacheKey,
+    ) -> Self {
+        self.cache = Some((Arc::new(cache), key));
+        self
+    }
+
     /// Clears the cached token, forcing re-authentication on next request.
+    ///
+    /// Only the in-memory token is cleared; the persistent cache is left as is.
     pub async fn clear_token(&self) {
         let mut token = self.token.write().await;
         *token = None;
     }
+
+    async fn load_cached(&self, resource: &str) -> Option<AccessToken> {
+        let (cache, key) = self.cache.as_ref()?;
+        match cache.load(key, resource).await {
+            Ok(token) => token,
+            Err(e) => {
+                log::warn!(
+                    "AutoRefreshTokenProvider - failed to load cached token: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    async fn save_cached(&self, resource: &str, token: &AccessToken) {
+        if let Some((cache, key)) = &self.cache
+            && let Err(e) = cache.save(key, resource, token).await
+        {
+            log::warn!(
+                "AutoRefreshTokenProvider - failed to save token to cache: {}",
+                e
+            );
+        }
+    }
 }
 
 #[async_trait]
@@ -126,6 +175,19 @@ impl<F: AuthFlow> TokenProvider for AutoRefreshTokenProvider<F> {
             }
         }
 
+        // Nothing held yet: try the persistent cache
+        if token_guard.is_none()
+            && let Some(token) = self.load_cached(resource).await
+        {
+            let buffer =
+                chrono::Duration::from_std(self.refresh_buffer).unwrap_or(chrono::Duration::zero());
+            if !token.expires_within(buffer) {
+                *token_guard = Some(token.clone());
+                return Ok(token);
+            }
+            *token_guard = Some(token);
+        }
+
         // Try to refresh if we have a refresh token
         let new_token = if let Some(ref old_token) = *token_guard {
             if let Some(ref refresh_token) = old_token.refresh_token {
@@ -145,6 +207,7 @@ impl<F: AuthFlow> TokenProvider for AutoRefreshTokenProvider<F> {
             self.flow.authenticate(resource).await?
         };
 
+        self.save_cached(resource, &new_token).await;
         *token_guard = Some(new_token.clone());
         Ok(new_token)
     }
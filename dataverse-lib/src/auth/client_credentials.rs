@@ -0,0 +1,197 @@
+//! Client credentials flow utilities (service principal)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use super::AccessToken;
+use super::TokenProvider;
+use super::auto_refresh::AuthFlow;
+use super::common::TokenExchange;
+use crate::error::AuthError;
+
+/// OAuth2 client credentials flow for service principals.
+///
+/// Authenticates as an application (not a user) with a client ID and client
+/// secret, so it needs no interaction and suits headless jobs. The app must be
+/// registered as an application user in the Dataverse environment.
+///
+/// Client credentials tokens have no refresh token; when a token is about to
+/// expire a new one is requested with the same credentials. The flow is a
+/// [`TokenProvider`] itself and caches tokens per resource, refreshing them
+/// 5 minutes before they expire.
+///
+/// # Example
+///
+/// ```ignore
+/// use dataverse_lib::auth::ClientCredentialsFlow;
+/// use dataverse_lib::DataverseClient;
+///
+/// let flow = ClientCredentialsFlow::new(
+///     "your-client-id",
+///     "your-client-secret",
+///     "your-tenant-id",
+/// );
+///
+/// let client = DataverseClient::builder()
+///     .url("https://org.crm.dynamics.com")
+///     .token_provider(flow)
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct ClientCredentialsFlow {
+    inner: Arc<ClientCredentialsFlowInner>,
+}
+
+struct ClientCredentialsFlowInner {
+    client_id: String,
+    client_secret: String,
+    tenant_id: String,
+    http_client: reqwest::Client,
+    /// Cached tokens by resource
+    tokens: RwLock<HashMap<String, AccessToken>>,
+    /// Refresh this long before actual expiry
+    refresh_buffer: Duration,
+}
+
+impl ClientCredentialsFlow {
+    /// Creates a new client credentials flow.
+    ///
+    /// Uses the v2.0 Azure AD endpoint with an explicit tenant ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The Azure AD application (client) ID
+    /// * `client_secret` - The Azure AD application client secret
+    /// * `tenant_id` - The Azure AD tenant ID or domain
+    pub fn new(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        tenant_id: impl Into<String>,
+    ) -> Self {
+        Self::with_refresh_buffer(
+            client_id,
+            client_secret,
+            tenant_id,
+            Duration::from_secs(300), // 5 minutes
+        )
+    }
+
+    /// Creates a new client credentials flow with a custom refresh buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The Azure AD application (client) ID
+    /// * `client_secret` - The Azure AD application client secret
+    /// * `tenant_id` - The Azure AD tenant ID or domain
+    /// * `refresh_buffer` - How long before expiry to request a new token
+    pub fn with_refresh_buffer(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        tenant_id: impl Into<String>,
+        refresh_buffer: Duration,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ClientCredentialsFlowInner {
+                client_id: client_id.into(),
+                client_secret: client_secret.into(),
+                tenant_id: tenant_id.into(),
+                http_client: reqwest::Client::new(),
+                tokens: RwLock::new(HashMap::new()),
+                refresh_buffer,
+            }),
+        }
+    }
+
+    /// Authenticates using the client ID and secret.
+    ///
+    /// Always requests a new token; use [`TokenProvider::get_token`] to reuse
+    /// cached ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The Dataverse environment URL (e.g., `https://org.crm.dynamics.com`)
+    pub async fn authenticate(&self, resource: &str) -> Result<AccessToken, AuthError> {
+        let exchange = TokenExchange {
+            http_client: &self.inner.http_client,
+            client_id: &self.inner.client_id,
+            tenant_id: &self.inner.tenant_id,
+            resource,
+        };
+        exchange.client_credentials(&self.inner.client_secret).await
+    }
+
+    /// Clears cached tokens, forcing re-authentication on next request.
+    pub async fn clear_tokens(&self) {
+        self.inner.tokens.write().await.clear();
+    }
+
+    fn cached(&self, tokens: &HashMap<String, AccessToken>, resource: &str) -> Option<AccessToken> {
+        let buffer = chrono::Duration::from_std(self.inner.refresh_buffer)
+            .unwrap_or(chrono::Duration::zero());
+        tokens
+            .get(resource)
+            .filter(|token| !token.expires_within(buffer))
+            .cloned()
+    }
+}
+
+impl std::fmt::Debug for ClientCredentialsFlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCredentialsFlow")
+            .field("client_id", &self.inner.client_id)
+            .field("client_secret", &"[REDACTED]")
+            .field("tenant_id", &self.inner.tenant_id)
+            .field("refresh_buffer", &self.inner.refresh_buffer)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TokenProvider for ClientCredentialsFlow {
+    async fn get_token(&self, resource: &str) -> Result<AccessToken, AuthError> {
+        // Fast path: check if we have a valid cached token
+        if let Some(token) = self.cached(&*self.inner.tokens.read().await, resource) {
+            return Ok(token);
+        }
+
+        // Slow path: request a new token
+        let mut tokens = self.inner.tokens.write().await;
+
+        // Double-check after acquiring write lock (another task may have refreshed)
+        if let Some(token) = self.cached(&tokens, resource) {
+            return Ok(token);
+        }
+
+        log::debug!(
+            "ClientCredentialsFlow::get_token - requesting token for '{}'",
+            resource
+        );
+        let token = self.authenticate(resource).await?;
+        tokens.insert(resource.to_string(), token.clone());
+        Ok(token)
+    }
+}
+
+// =============================================================================
+// AuthFlow implementation
+// =============================================================================
+
+#[async_trait]
+impl AuthFlow for ClientCredentialsFlow {
+    async fn authenticate(&self, resource: &str) -> Result<AccessToken, AuthError> {
+        self.authenticate(resource).await
+    }
+
+    /// Client credentials tokens can't be refreshed; requests a new token instead.
+    async fn refresh(
+        &self,
+        resource: &str,
+        _refresh_token: &str,
+    ) -> Result<AccessToken, AuthError> {
+        self.authenticate(resource).await
+    }
+}
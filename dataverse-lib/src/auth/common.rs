@@ -40,6 +40,13 @@ pub(crate) fn scope_from_resource(resource: &str) -> String {
     format!("{}/.default offline_access", resource.trim_end_matches('/'))
 }
 
+/// Build app-only scope string from resource URL.
+///
+/// Client credentials tokens can't be refreshed, so `offline_access` isn't requested.
+pub(crate) fn app_scope_from_resource(resource: &str) -> String {
+    format!("{}/.default", resource.trim_end_matches('/'))
+}
+
 // =============================================================================
 // Token Response Parsing
 // =============================================================================
@@ -251,6 +258,28 @@ impl TokenExchange<'_> {
         self.handle_token_response(response).await
     }
 
+    /// Exchange client credentials for an app-only token.
+    pub async fn client_credentials(&self, client_secret: &str) -> Result<AccessToken, AuthError> {
+        let token_url = token_url_v2(self.tenant_id);
+        let scope = app_scope_from_resource(self.resource);
+
+        let params = [
+            ("grant_type", "client_credentials"),
+            ("client_id", self.client_id),
+            ("client_secret", client_secret),
+            ("scope", &scope),
+        ];
+
+        let response = self
+            .http_client
+            .post(&token_url)
+            .form(&params)
+            .send()
+            .await?;
+
+        self.handle_token_response(response).await
+    }
+
     /// Refresh an access token.
     pub async fn refresh(&self, refresh_token: &str) -> Result<AccessToken, AuthError> {
         let token_url = token_url_v2(self.tenant_id);
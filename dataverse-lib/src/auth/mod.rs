@@ -2,15 +2,18 @@
 
 mod auto_refresh;
 mod browser;
+mod client_credentials;
 pub(crate) mod common;
 mod device_code;
 mod password;
 mod token;
+mod token_cache;
 
 pub use auto_refresh::AuthFlow;
 pub use auto_refresh::AutoRefreshTokenProvider;
 pub use browser::BrowserFlow;
 pub use browser::PendingBrowserAuth;
+pub use client_credentials::ClientCredentialsFlow;
 pub use device_code::DeviceCodeFlow;
 pub use device_code::DeviceCodeInfo;
 pub use device_code::PendingDeviceAuth;
@@ -20,3 +23,6 @@ pub use password::PublicClientPasswordFlow;
 pub use token::AccessToken;
 pub use token::StaticTokenProvider;
 pub use token::TokenProvider;
+pub use token_cache::FileTokenCache;
+pub use token_cache::TokenCache;
+pub use token_cache::TokenCacheKey;
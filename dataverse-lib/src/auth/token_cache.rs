@@ -0,0 +1,479 @@
+//! Persistent token caching in the MSAL cache format
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde_json::Map;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::AccessToken;
+use crate::error::AuthError;
+
+/// Authority host recorded as the `environment` of cached tokens.
+const ENVIRONMENT: &str = "login.microsoftonline.com";
+
+/// Identifies whose tokens a [`TokenCache`] entry holds.
+///
+/// Combined with the resource URL, this determines which cached tokens
+/// belong to an authentication flow.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenCacheKey {
+    /// The Azure AD application (client) ID.
+    pub client_id: String,
+    /// The Azure AD tenant ID or domain.
+    pub tenant_id: String,
+    /// The account the tokens were issued to.
+    ///
+    /// MSAL uses the home account ID (`<object-id>.<tenant-id>`); set it to
+    /// share tokens with other MSAL-based tools. `None` for service principals.
+    pub account: Option<String>,
+}
+
+impl TokenCacheKey {
+    /// Creates a key for tokens issued to an application.
+    pub fn new(client_id: impl Into<String>, tenant_id: impl Into<String>) -> Self {
+        Self {
+            client_id: client_id.into(),
+            tenant_id: tenant_id.into(),
+            account: None,
+        }
+    }
+
+    /// Creates a key for tokens issued to a user account.
+    pub fn for_account(
+        client_id: impl Into<String>,
+        tenant_id: impl Into<String>,
+        account: impl Into<String>,
+    ) -> Self {
+        Self {
+            client_id: client_id.into(),
+            tenant_id: tenant_id.into(),
+            account: Some(account.into()),
+        }
+    }
+
+    fn home_account_id(&self) -> &str {
+        self.account.as_deref().unwrap_or("")
+    }
+}
+
+/// Trait for persisting tokens across sessions.
+///
+/// Used with [`AutoRefreshTokenProvider::with_token_cache`](super::AutoRefreshTokenProvider::with_token_cache)
+/// so that restarting an application doesn't require authenticating again.
+#[async_trait]
+pub trait TokenCache: Send + Sync {
+    /// Loads the cached token for a resource.
+    ///
+    /// Returns `None` if nothing usable is cached. A token whose access token
+    /// is missing or expired may still be returned if it can be refreshed.
+    async fn load(
+        &self,
+        key: &TokenCacheKey,
+        resource: &str,
+    ) -> Result<Option<AccessToken>, AuthError>;
+
+    /// Stores a token for a resource, replacing any previous one.
+    async fn save(
+        &self,
+        key: &TokenCacheKey,
+        resource: &str,
+        token: &AccessToken,
+    ) -> Result<(), AuthError>;
+
+    /// Removes the cached tokens for a resource.
+    async fn remove(&self, key: &TokenCacheKey, resource: &str) -> Result<(), AuthError>;
+}
+
+/// A token cache stored in a JSON file using the MSAL unified cache schema.
+///
+/// Access tokens and refresh tokens are written to the `AccessToken` and
+/// `RefreshToken` sections the MSAL libraries use, so a cache file can be
+/// shared with other MSAL-based tools (given a matching client ID and
+/// [`TokenCacheKey::account`]). Entries written by other tools are preserved.
+///
+/// The file holds secrets and is not encrypted; keep it somewhere only the
+/// current user can read.
+///
+/// # Example
+///
+/// ```ignore
+/// use dataverse_lib::auth::{AutoRefreshTokenProvider, FileTokenCache, PasswordFlow, TokenCacheKey};
+///
+/// let flow = PasswordFlow::new(client_id, client_secret, username, password);
+/// let provider = AutoRefreshTokenProvider::new(flow).with_token_cache(
+///     FileTokenCache::new("~/.cache/dataverse/msal_token_cache.json"),
+///     TokenCacheKey::new(client_id, "common"),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct FileTokenCache {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles within this process
+    lock: Mutex<()>,
+}
+
+impl FileTokenCache {
+    /// Creates a token cache backed by the file at `path`.
+    ///
+    /// The file and its parent directory are created on first save.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the path of the cache file.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<MsalCache, AuthError> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) if contents.trim().is_empty() => Ok(MsalCache::default()),
+            Ok(contents) => MsalCache::parse(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(MsalCache::default()),
+            Err(e) => Err(AuthError::Cache(format!(
+                "failed to read {}: {}",
+                self.path.display(),
+                e
+            ))),
+        }
+    }
+
+    fn write(&self, cache: &MsalCache) -> Result<(), AuthError> {
+        let io_error = |e: std::io::Error| {
+            AuthError::Cache(format!("failed to write {}: {}", self.path.display(), e))
+        };
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        // Write to a sibling file and rename so readers never see a partial cache
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, cache.to_json()).map_err(io_error)?;
+        std::fs::rename(&tmp, &self.path).map_err(io_error)
+    }
+}
+
+#[async_trait]
+impl TokenCache for FileTokenCache {
+    async fn load(
+        &self,
+        key: &TokenCacheKey,
+        resource: &str,
+    ) -> Result<Option<AccessToken>, AuthError> {
+        let _guard = self.lock.lock().await;
+        Ok(self.read()?.token(key, resource))
+    }
+
+    async fn save(
+        &self,
+        key: &TokenCacheKey,
+        resource: &str,
+        token: &AccessToken,
+    ) -> Result<(), AuthError> {
+        let _guard = self.lock.lock().await;
+        let mut cache = self.read()?;
+        cache.insert(key, resource, token);
+        self.write(&cache)
+    }
+
+    async fn remove(&self, key: &TokenCacheKey, resource: &str) -> Result<(), AuthError> {
+        let _guard = self.lock.lock().await;
+        let mut cache = self.read()?;
+        if cache.remove(key, resource) {
+            self.write(&cache)?;
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// MSAL cache schema
+// =============================================================================
+
+/// The contents of an MSAL unified cache file.
+///
+/// Kept as raw JSON so sections and fields this crate doesn't use survive a
+/// save untouched.
+#[derive(Debug, Default)]
+struct MsalCache {
+    root: Map<String, Value>,
+}
+
+impl MsalCache {
+    fn parse(contents: &str) -> Result<Self, AuthError> {
+        match serde_json::from_str(contents) {
+            Ok(Value::Object(root)) => Ok(Self { root }),
+            Ok(_) => Err(AuthError::Cache(
+                "token cache is not a JSON object".to_string(),
+            )),
+            Err(e) => Err(AuthError::Cache(format!("invalid token cache: {}", e))),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let mut root = self.root.clone();
+        for section in [
+            "AccessToken",
+            "RefreshToken",
+            "IdToken",
+            "Account",
+            "AppMetadata",
+        ] {
+            root.entry(section)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        serde_json::to_string_pretty(&Value::Object(root)).unwrap_or_default()
+    }
+
+    fn section(&self, name: &str) -> impl Iterator<Item = &Map<String, Value>> {
+        self.root
+            .get(name)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|entries| entries.values())
+            .filter_map(Value::as_object)
+    }
+
+    fn section_mut(&mut self, name: &str) -> &mut Map<String, Value> {
+        let section = self
+            .root
+            .entry(name)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !section.is_object() {
+            *section = Value::Object(Map::new());
+        }
+        section
+            .as_object_mut()
+            .expect("section was just made an object")
+    }
+
+    /// Finds the cached token for a resource.
+    fn token(&self, key: &TokenCacheKey, resource: &str) -> Option<AccessToken> {
+        let scope = scope(resource);
+
+        let access = self
+            .section("AccessToken")
+            .filter(|entry| {
+                matches_account(entry, key)
+                    && field(entry, "realm").eq_ignore_ascii_case(&key.tenant_id)
+                    && field(entry, "target")
+                        .split_whitespace()
+                        .any(|target| target.eq_ignore_ascii_case(&scope))
+            })
+            .filter_map(|entry| {
+                let expires_on = timestamp(field(entry, "expires_on"))?;
+                Some((field(entry, "secret"), expires_on))
+            })
+            .max_by_key(|(_, expires_on)| *expires_on);
+
+        let refresh = self
+            .section("RefreshToken")
+            .find(|entry| matches_account(entry, key))
+            .map(|entry| field(entry, "secret"))
+            .filter(|secret| !secret.is_empty());
+
+        match (access, refresh) {
+            (Some((secret, expires_on)), Some(refresh)) => {
+                Some(AccessToken::with_refresh(secret, Some(expires_on), refresh))
+            }
+            (Some((secret, expires_on)), None) => {
+                Some(AccessToken::with_expiry(secret, expires_on))
+            }
+            // Only a refresh token: return an already-expired access token so
+            // it's refreshed before use.
+            (None, Some(refresh)) => Some(AccessToken::with_refresh(
+                "",
+                Some(DateTime::UNIX_EPOCH),
+                refresh,
+            )),
+            (None, None) => None,
+        }
+    }
+
+    /// Stores a token, replacing the previous ones for the same key and resource.
+    fn insert(&mut self, key: &TokenCacheKey, resource: &str, token: &AccessToken) {
+        let now = Utc::now().timestamp().to_string();
+
+        if let Some(expires_at) = token.expires_at {
+            let target = scope(resource);
+            let expires_on = expires_at.timestamp().to_string();
+            let entry = serde_json::json!({
+                "home_account_id": key.home_account_id(),
+                "environment": ENVIRONMENT,
+                "credential_type": "AccessToken",
+                "client_id": key.client_id,
+                "secret": token.access_token,
+                "realm": key.tenant_id,
+                "target": target,
+                "cached_at": now,
+                "expires_on": expires_on,
+                "extended_expires_on": expires_on,
+            });
+            self.section_mut("AccessToken").insert(
+                entry_key(key, "AccessToken", &key.tenant_id, &target),
+                entry,
+            );
+        }
+
+        if let Some(refresh_token) = &token.refresh_token {
+            let entry = serde_json::json!({
+                "home_account_id": key.home_account_id(),
+                "environment": ENVIRONMENT,
+                "credential_type": "RefreshToken",
+                "client_id": key.client_id,
+                "secret": refresh_token,
+                "last_modification_time": now,
+            });
+            self.section_mut("RefreshToken")
+                .insert(entry_key(key, "RefreshToken", "", ""), entry);
+        }
+    }
+
+    /// Removes the tokens for a key and resource. Returns `true` if anything
+    /// was removed.
+    fn remove(&mut self, key: &TokenCacheKey, resource: &str) -> bool {
+        let access_key = entry_key(key, "AccessToken", &key.tenant_id, &scope(resource));
+        let refresh_key = entry_key(key, "RefreshToken", "", "");
+        let removed_access = self
+            .section_mut("AccessToken")
+            .remove(&access_key)
+            .is_some();
+        let removed_refresh = self
+            .section_mut("RefreshToken")
+            .remove(&refresh_key)
+            .is_some();
+        removed_access || removed_refresh
+    }
+}
+
+/// The scope tokens for a resource are requested with.
+fn scope(resource: &str) -> String {
+    format!("{}/.default", resource.trim_end_matches('/'))
+}
+
+/// Builds an MSAL cache entry key:
+/// `<home_account_id>-<environment>-<credential_type>-<client_id>-<realm>-<target>`.
+fn entry_key(key: &TokenCacheKey, credential_type: &str, realm: &str, target: &str) -> String {
+    [
+        key.home_account_id(),
+        ENVIRONMENT,
+        credential_type,
+        &key.client_id,
+        realm,
+        target,
+    ]
+    .join("-")
+    .to_lowercase()
+}
+
+fn field<'a>(entry: &'a Map<String, Value>, name: &str) -> &'a str {
+    entry.get(name).and_then(Value::as_str).unwrap_or("")
+}
+
+fn matches_account(entry: &Map<String, Value>, key: &TokenCacheKey) -> bool {
+    field(entry, "client_id").eq_ignore_ascii_case(&key.client_id)
+        && field(entry, "home_account_id") == key.home_account_id()
+        && field(entry, "environment").eq_ignore_ascii_case(ENVIRONMENT)
+}
+
+/// Parses an MSAL timestamp (Unix seconds, stored as a string).
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(value.parse().ok()?, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> TokenCacheKey {
+        TokenCacheKey::for_account("Client-Id", "tenant-id", "oid.tid")
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let expires_at = DateTime::from_timestamp(Utc::now().timestamp() + 3600, 0).unwrap();
+        let token = AccessToken::with_refresh("access", Some(expires_at), "refresh");
+
+        let mut cache = MsalCache::default();
+        cache.insert(&key(), "https://org.crm.dynamics.com/", &token);
+        let cache = MsalCache::parse(&cache.to_json()).unwrap();
+
+        let loaded = cache.token(&key(), "https://org.crm.dynamics.com").unwrap();
+        assert_eq!(loaded.access_token, "access");
+        assert_eq!(loaded.expires_at, Some(expires_at));
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh"));
+        assert!(
+            cache
+                .token(&key(), "https://other.crm.dynamics.com")
+                .is_some_and(|t| t.is_expired())
+        );
+        assert!(
+            cache
+                .token(
+                    &TokenCacheKey::new("Client-Id", "tenant-id"),
+                    "https://org.crm.dynamics.com"
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_msal_schema() {
+        let token = AccessToken::with_refresh("access", Some(DateTime::UNIX_EPOCH), "refresh");
+        let mut cache = MsalCache::default();
+        cache.insert(&key(), "https://org.crm.dynamics.com", &token);
+
+        let json: Value = serde_json::from_str(&cache.to_json()).unwrap();
+        let access = &json["AccessToken"]["oid.tid-login.microsoftonline.com-accesstoken-client-id-tenant-id-https://org.crm.dynamics.com/.default"];
+        assert_eq!(access["credential_type"], "AccessToken");
+        assert_eq!(access["target"], "https://org.crm.dynamics.com/.default");
+        assert_eq!(access["expires_on"], "0");
+        let refresh =
+            &json["RefreshToken"]["oid.tid-login.microsoftonline.com-refreshtoken-client-id--"];
+        assert_eq!(refresh["secret"], "refresh");
+        assert!(json["Account"].is_object());
+    }
+
+    #[test]
+    fn test_preserves_foreign_entries() {
+        let contents = r#"{
+            "Account": { "oid.tid-login.microsoftonline.com-tid": { "username": "user@example.com" } },
+            "AccessToken": {}
+        }"#;
+        let mut cache = MsalCache::parse(contents).unwrap();
+        cache.insert(
+            &key(),
+            "https://org.crm.dynamics.com",
+            &AccessToken::new("access"),
+        );
+
+        let json: Value = serde_json::from_str(&cache.to_json()).unwrap();
+        assert_eq!(
+            json["Account"]["oid.tid-login.microsoftonline.com-tid"]["username"],
+            "user@example.com"
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let token = AccessToken::with_refresh("access", Some(Utc::now()), "refresh");
+        let mut cache = MsalCache::default();
+        cache.insert(&key(), "https://org.crm.dynamics.com", &token);
+
+        assert!(cache.remove(&key(), "https://org.crm.dynamics.com"));
+        assert!(
+            cache
+                .token(&key(), "https://org.crm.dynamics.com")
+                .is_none()
+        );
+        assert!(!cache.remove(&key(), "https://org.crm.dynamics.com"));
+    }
+}
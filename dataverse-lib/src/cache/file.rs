@@ -0,0 +1,367 @@
+//! Filesystem-backed persistent cache implementation.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use chrono::TimeZone;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+use super::CacheEntry;
+use super::CacheProvider;
+use super::CachedValue;
+
+/// Extension of cache entry files.
+const ENTRY_EXTENSION: &str = "entry";
+
+/// Extension of entry files being written.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// A persistent cache storing one file per key in a directory.
+///
+/// An alternative to [`SqliteCache`](super::SqliteCache) for tools that
+/// want metadata to survive restarts without a database. File names are
+/// hashes of the keys, and entries are written to a temporary file first,
+/// so a crash never leaves a half-written entry behind.
+///
+/// With a size cap, the least recently used entries are evicted after each
+/// write until the directory fits, expired ones first.
+///
+/// # Example
+///
+/// ```ignore
+/// use dataverse_lib::cache::FileCache;
+///
+/// let cache = FileCache::open("~/.cache/my-tool")?.max_size(64 * 1024 * 1024);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    max_size: Option<u64>,
+}
+
+/// What is written to an entry file.
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    created_at: i64,
+    expires_at: i64,
+    data: Vec<u8>,
+}
+
+impl FileCache {
+    /// Opens a file cache in the specified directory.
+    ///
+    /// Creates the directory if it doesn't exist.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                dir,
+                max_size: None,
+            }),
+        })
+    }
+
+    /// Caps the total size of the entry files, in bytes.
+    pub fn max_size(self, bytes: u64) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                dir: self.inner.dir.clone(),
+                max_size: Some(bytes),
+            }),
+        }
+    }
+
+    /// Returns the cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.inner.dir
+    }
+
+    /// Returns the number of entries in the cache (including expired ones).
+    pub async fn len(&self) -> usize {
+        self.blocking(|inner| inner.files().len()).await
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Returns the total size of the entry files, in bytes.
+    pub async fn size(&self) -> u64 {
+        self.blocking(|inner| inner.files().iter().map(|f| f.size).sum())
+            .await
+    }
+
+    /// Runs filesystem work off the async runtime.
+    async fn blocking<T, F>(&self, f: F) -> T
+    where
+        T: Default + Send + 'static,
+        F: FnOnce(&Inner) -> T + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || f(&inner))
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// An entry file found in the cache directory.
+struct EntryFile {
+    path: PathBuf,
+    size: u64,
+    used_at: SystemTime,
+}
+
+impl Inner {
+    fn path(&self, key: &str) -> PathBuf {
+        let hash = Sha256::digest(key.as_bytes());
+        let name: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+        self.dir.join(name).with_extension(ENTRY_EXTENSION)
+    }
+
+    fn read(path: &Path) -> Option<FileEntry> {
+        let bytes = fs::read(path).ok()?;
+        super::deserialize(&bytes).ok()
+    }
+
+    fn write(&self, path: &Path, entry: &FileEntry) -> io::Result<()> {
+        let bytes = super::serialize(entry).map_err(io::Error::other)?;
+        let temp = path.with_extension(format!("{}.{}", uuid::Uuid::new_v4(), TEMP_EXTENSION));
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+    }
+
+    /// Lists the entry files, skipping anything else in the directory.
+    fn files(&self) -> Vec<EntryFile> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.filter_map(Result::ok)
+            .filter(|e| {
+                e.path()
+                    .extension()
+                    .is_some_and(|ext| ext == ENTRY_EXTENSION)
+            })
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some(EntryFile {
+                    path: e.path(),
+                    size: meta.len(),
+                    used_at: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .collect()
+    }
+
+    /// Removes the entry files matching `pred`, returning how many.
+    fn remove_where(&self, pred: impl Fn(&FileEntry) -> bool) -> usize {
+        self.files()
+            .into_iter()
+            .filter(|f| Self::read(&f.path).is_some_and(|entry| pred(&entry)))
+            .filter(|f| fs::remove_file(&f.path).is_ok())
+            .count()
+    }
+
+    /// Evicts entries until the directory fits the size cap.
+    fn evict(&self) {
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+        let files = self.files();
+        let mut total: u64 = files.iter().map(|f| f.size).sum();
+        if total <= max_size {
+            return;
+        }
+
+        let now = Utc::now().timestamp();
+        let mut files: Vec<(bool, EntryFile)> = files
+            .into_iter()
+            .map(|f| {
+                let live = Self::read(&f.path).is_some_and(|e| e.expires_at > now);
+                (live, f)
+            })
+            .collect();
+        // Expired (or unreadable) entries go first, then least recently used
+        files.sort_by_key(|(live, f)| (*live, f.used_at));
+
+        for (_, file) in files {
+            if total <= max_size {
+                break;
+            }
+            if fs::remove_file(&file.path).is_ok() {
+                log::debug!("FileCache::evict - removed {}", file.path.display());
+                total = total.saturating_sub(file.size);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CacheProvider for FileCache {
+    async fn get(&self, key: &str) -> Option<CachedValue> {
+        let key = key.to_string();
+        self.blocking(move |inner| {
+            let path = inner.path(&key);
+            let entry = Inner::read(&path)?;
+            // Guard against hash collisions
+            if entry.key != key {
+                return None;
+            }
+            if entry.expires_at <= Utc::now().timestamp() {
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+
+            // Mark as recently used for eviction
+            if let Ok(file) = fs::File::options().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+
+            let created_at = Utc.timestamp_opt(entry.created_at, 0).single()?;
+            let expires_at = Utc.timestamp_opt(entry.expires_at, 0).single()?;
+            Some(CachedValue::new(entry.data, created_at, expires_at))
+        })
+        .await
+    }
+
+    async fn set(&self, key: &str, value: CachedValue) {
+        let entry = FileEntry {
+            key: key.to_string(),
+            created_at: value.created_at.timestamp(),
+            expires_at: value.expires_at.timestamp(),
+            data: value.data,
+        };
+        self.blocking(move |inner| {
+            let path = inner.path(&entry.key);
+            match inner.write(&path, &entry) {
+                Ok(()) => inner.evict(),
+                Err(e) => log::error!("FileCache::set - FAILED for key='{}': {}", entry.key, e),
+            }
+        })
+        .await
+    }
+
+    async fn remove(&self, key: &str) {
+        let key = key.to_string();
+        self.blocking(move |inner| {
+            let _ = fs::remove_file(inner.path(&key));
+        })
+        .await
+    }
+
+    async fn clear(&self) {
+        self.blocking(|inner| {
+            for file in inner.files() {
+                let _ = fs::remove_file(file.path);
+            }
+        })
+        .await
+    }
+
+    async fn clear_by_prefix(&self, prefix: &str) -> usize {
+        let prefix = prefix.to_string();
+        self.blocking(move |inner| inner.remove_where(|entry| entry.key.starts_with(&prefix)))
+            .await
+    }
+
+    async fn gc(&self) -> usize {
+        let now = Utc::now().timestamp();
+        self.blocking(move |inner| inner.remove_where(|entry| entry.expires_at <= now))
+            .await
+    }
+
+    async fn get_all(&self) -> Vec<CacheEntry> {
+        self.blocking(|inner| {
+            inner
+                .files()
+                .iter()
+                .filter_map(|f| Inner::read(&f.path))
+                .filter_map(|entry| {
+                    Some(CacheEntry {
+                        expires_at: Utc.timestamp_opt(entry.expires_at, 0).single()?,
+                        key: entry.key,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn temp_cache() -> FileCache {
+        let dir = std::env::temp_dir().join(format!("dataverse-cache-{}", uuid::Uuid::new_v4()));
+        FileCache::open(dir).unwrap()
+    }
+
+    fn value(size: usize, ttl: Duration) -> CachedValue {
+        CachedValue::with_ttl(vec![0; size], ttl)
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip() {
+        let cache = temp_cache();
+        let hour = Duration::from_secs(3600);
+        cache.set("metadata:account", value(4, hour)).await;
+        cache.set("metadata:contact", value(4, hour)).await;
+        cache.set("query:1", value(4, hour)).await;
+        cache.set("expired", value(4, Duration::ZERO)).await;
+
+        assert_eq!(cache.get("metadata:account").await.unwrap().data.len(), 4);
+        assert!(cache.get("missing").await.is_none());
+        assert!(cache.get("expired").await.is_none());
+        assert_eq!(cache.get_all().await.len(), 3);
+
+        assert_eq!(cache.clear_by_prefix("metadata:").await, 2);
+        assert_eq!(cache.len().await, 1);
+
+        cache.clear().await;
+        assert!(cache.is_empty().await);
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[tokio::test]
+    async fn test_size_capped_eviction() {
+        let cache = temp_cache();
+        let hour = Duration::from_secs(3600);
+        cache.set("a", value(100, hour)).await;
+        let entry_size = cache.size().await;
+        let cache = cache.max_size(entry_size * 2);
+
+        cache.set("b", value(100, hour)).await;
+        // Make "a" the most recently used
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(cache.get("a").await.is_some());
+        std::thread::sleep(Duration::from_millis(20));
+        cache.set("c", value(100, hour)).await;
+
+        assert_eq!(cache.len().await, 2);
+        assert!(cache.get("a").await.is_some());
+        assert!(cache.get("b").await.is_none());
+        assert!(cache.get("c").await.is_some());
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+}
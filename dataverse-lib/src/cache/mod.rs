@@ -5,10 +5,12 @@
 //! metadata and query result caching.
 
 mod config;
+mod file;
 mod memory;
 mod sqlite;
 
 pub use config::*;
+pub use file::*;
 pub use memory::*;
 pub use sqlite::*;
 
@@ -1,20 +1,33 @@
 //! Main DataverseClient
 
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use reqwest::Client;
 use serde::Deserialize;
 
+use crate::api::Impersonation;
+use crate::api::ServerVersion;
+use crate::auth::AccessToken;
 use crate::auth::TokenProvider;
 use crate::cache::CacheConfig;
 use crate::cache::CacheProvider;
 use crate::cache::InMemoryCache;
 use crate::error::ApiError;
+use crate::error::DebugConfig;
 use crate::error::Error;
+use crate::middleware::Interceptor;
+use crate::middleware::ObservedCache;
+use crate::middleware::RequestInfo;
+use crate::middleware::Telemetry;
+#[cfg(feature = "test-util")]
+use crate::mock::MockDataverse;
 use crate::rate_limit::ConcurrencyLimiter;
 use crate::rate_limit::RateLimiter;
 use crate::rate_limit::RetryConfig;
+use crate::telemetry::InSpan;
+use crate::telemetry::span;
 
 /// The main client for interacting with the Dataverse Web API.
 ///
@@ -39,6 +52,7 @@ pub struct DataverseClient {
     pub(crate) inner: Arc<DataverseClientInner>,
 }
 
+#[derive(Clone)]
 pub(crate) struct DataverseClientInner {
     pub(crate) base_url: String,
     pub(crate) api_version: String,
@@ -50,6 +64,21 @@ pub(crate) struct DataverseClientInner {
     pub(crate) concurrency_limiter: ConcurrencyLimiter,
     pub(crate) rate_limiter: RateLimiter,
     pub(crate) retry_config: RetryConfig,
+    pub(crate) impersonation: Option<Impersonation>,
+    /// Debug mode settings, when raw responses are kept on errors.
+    pub(crate) debug: Option<DebugConfig>,
+    /// How many plugin trace entries to attach to plugin errors, if any.
+    pub(crate) plugin_traces: Option<usize>,
+    /// Preferred language (LCID) for metadata labels.
+    pub(crate) language: Option<i32>,
+    /// Latest session token returned by Dataverse, shared with clones.
+    pub(crate) session_token: Arc<Mutex<Option<String>>>,
+    /// Server version, once requested, shared with clones.
+    pub(crate) server_version: Arc<Mutex<Option<ServerVersion>>>,
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    pub(crate) telemetry: Vec<Arc<dyn Telemetry>>,
+    #[cfg(feature = "test-util")]
+    pub(crate) mock: Option<MockDataverse>,
 }
 
 impl DataverseClient {
@@ -68,23 +97,15 @@ impl DataverseClient {
             self.inner.api_version
         );
 
-        let token = self
-            .inner
-            .token_provider
-            .get_token(&self.inner.base_url)
-            .await?;
-
-        let mut request = self
-            .inner
-            .http_client
-            .get(&url)
-            .bearer_auth(&token.access_token);
-
-        if let Some(timeout) = self.inner.timeout {
-            request = request.timeout(timeout);
-        }
+        let token = self.access_token().await?;
 
-        let response = request.send().await.map_err(ApiError::from)?;
+        let request = RequestInfo::new(reqwest::Method::GET, &url, Default::default())?;
+        let send = async {
+            self.send(request, None, &token.access_token)
+                .await
+                .map_err(|e| Error::from(ApiError::from(e)))
+        };
+        let response = self.observe(&reqwest::Method::GET, &url, send).await?;
 
         if response.status().is_success() {
             let who_am_i: WhoAmIResponse = response.json().await.map_err(ApiError::from)?;
@@ -97,10 +118,22 @@ impl DataverseClient {
                 message: body,
                 code: None,
                 inner: None,
+                raw: None,
             }))
         }
     }
 
+    /// Gets an access token for the environment from the token provider.
+    pub(crate) async fn access_token(&self) -> Result<AccessToken, Error> {
+        let token = self
+            .inner
+            .token_provider
+            .get_token(&self.inner.base_url)
+            .in_span(span!("dataverse.auth"))
+            .await?;
+        Ok(token)
+    }
+
     /// Returns the base URL of the Dataverse environment.
     pub fn base_url(&self) -> &str {
         &self.inner.base_url
@@ -125,6 +158,71 @@ impl DataverseClient {
     pub fn has_cache(&self) -> bool {
         self.inner.cache.is_some()
     }
+
+    /// Returns a client that runs all requests as another user.
+    ///
+    /// The returned client shares the token provider, cache, and rate limits
+    /// with this one. Per-operation impersonation takes precedence.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let as_owner = client.impersonate(owner_object_id);
+    /// as_owner.create(Entity::set("accounts"), record).await?;
+    /// ```
+    pub fn impersonate(&self, user: impl Into<Impersonation>) -> DataverseClient {
+        let mut inner = (*self.inner).clone();
+        inner.impersonation = Some(user.into());
+        DataverseClient {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns the client's concurrency limiter, with its queue wait metrics.
+    pub fn concurrency_limiter(&self) -> &ConcurrencyLimiter {
+        &self.inner.concurrency_limiter
+    }
+
+    /// Returns the user this client impersonates, if any.
+    pub fn impersonation(&self) -> Option<Impersonation> {
+        self.inner.impersonation
+    }
+
+    /// Returns the preferred language (LCID) for metadata labels, if set.
+    pub fn language(&self) -> Option<i32> {
+        self.inner.language
+    }
+
+    /// Returns the session token of the latest write to an elastic table.
+    ///
+    /// Elastic tables are eventually consistent: a read may not see a write
+    /// made just before it. Passing this token to
+    /// [`session_token`](crate::api::ClientRetrieveBuilder::session_token)
+    /// makes the read see every write up to it.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// client.update(Entity::logical("contoso_sensordata"), id, record)
+    ///     .partition_id("device-001")
+    ///     .await?;
+    /// let token = client.session_token();
+    ///
+    /// let mut retrieve = client
+    ///     .retrieve(Entity::logical("contoso_sensordata"), id)
+    ///     .partition_id("device-001");
+    /// if let Some(token) = token {
+    ///     retrieve = retrieve.session_token(token);
+    /// }
+    /// let record = retrieve.await?;
+    /// ```
+    pub fn session_token(&self) -> Option<String> {
+        self.inner
+            .session_token
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
 }
 
 /// Response from the WhoAmI request.
@@ -186,6 +284,14 @@ pub struct DataverseClientBuilder<Url, Provider> {
     concurrency_limiter: Option<ConcurrencyLimiter>,
     rate_limiter: Option<RateLimiter>,
     retry_config: RetryConfig,
+    impersonation: Option<Impersonation>,
+    debug: Option<DebugConfig>,
+    plugin_traces: Option<usize>,
+    language: Option<i32>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    telemetry: Vec<Arc<dyn Telemetry>>,
+    #[cfg(feature = "test-util")]
+    mock: Option<MockDataverse>,
 }
 
 impl DataverseClientBuilder<Missing, Missing> {
@@ -204,6 +310,14 @@ impl DataverseClientBuilder<Missing, Missing> {
             concurrency_limiter: None,
             rate_limiter: None,
             retry_config: RetryConfig::default(),
+            impersonation: None,
+            debug: None,
+            plugin_traces: None,
+            language: None,
+            interceptors: Vec::new(),
+            telemetry: Vec::new(),
+            #[cfg(feature = "test-util")]
+            mock: None,
         }
     }
 }
@@ -236,6 +350,14 @@ impl<P> DataverseClientBuilder<Missing, P> {
             concurrency_limiter: self.concurrency_limiter,
             rate_limiter: self.rate_limiter,
             retry_config: self.retry_config,
+            impersonation: self.impersonation,
+            debug: self.debug,
+            plugin_traces: self.plugin_traces,
+            language: self.language,
+            interceptors: self.interceptors,
+            telemetry: self.telemetry,
+            #[cfg(feature = "test-util")]
+            mock: self.mock,
         }
     }
 }
@@ -259,6 +381,14 @@ impl<U> DataverseClientBuilder<U, Missing> {
             concurrency_limiter: self.concurrency_limiter,
             rate_limiter: self.rate_limiter,
             retry_config: self.retry_config,
+            impersonation: self.impersonation,
+            debug: self.debug,
+            plugin_traces: self.plugin_traces,
+            language: self.language,
+            interceptors: self.interceptors,
+            telemetry: self.telemetry,
+            #[cfg(feature = "test-util")]
+            mock: self.mock,
         }
     }
 }
@@ -327,6 +457,15 @@ impl<U, P> DataverseClientBuilder<U, P> {
         self
     }
 
+    /// Uses a shared concurrency limiter.
+    ///
+    /// Useful when multiple clients connect to the same environment as the
+    /// same user and should share its concurrent request limit.
+    pub fn shared_concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.concurrency_limiter = Some(limiter);
+        self
+    }
+
     /// Sets a custom rate limit.
     ///
     /// Default is 6000 requests per 5 minutes (Dataverse's service protection limit).
@@ -355,6 +494,73 @@ impl<U, P> DataverseClientBuilder<U, P> {
         self.retry_config = RetryConfig::no_retry();
         self
     }
+
+    /// Runs all requests as another user.
+    ///
+    /// Per-operation impersonation takes precedence.
+    pub fn impersonate(mut self, user: impl Into<Impersonation>) -> Self {
+        self.impersonation = Some(user.into());
+        self
+    }
+
+    /// Turns on debug mode: failed calls keep their raw request and
+    /// response, available from [`ApiError::raw_response`].
+    ///
+    /// Meant for diagnosing malformed payloads without a proxy; bodies are
+    /// size-capped and credentials redacted as `config` says.
+    pub fn debug_mode(mut self, config: DebugConfig) -> Self {
+        self.debug = Some(config);
+        self
+    }
+
+    /// Attaches up to `max` plugin trace entries to errors caused by plugin
+    /// exceptions, in their [`DataverseErrorDetail::plugin_traces`].
+    ///
+    /// Costs an extra request per plugin error, and finds nothing unless
+    /// plugin tracing is turned on in the environment.
+    ///
+    /// [`DataverseErrorDetail::plugin_traces`]: crate::error::DataverseErrorDetail::plugin_traces
+    pub fn attach_plugin_traces(mut self, max: usize) -> Self {
+        self.plugin_traces = Some(max);
+        self
+    }
+
+    /// Sets the preferred language for metadata labels, as an LCID (e.g.
+    /// `1036` for French).
+    ///
+    /// Display names and option labels are picked in this language when
+    /// the environment has it, falling back to the user's language. See
+    /// [`Label::localized`](crate::model::metadata::Label::localized).
+    pub fn language(mut self, lcid: i32) -> Self {
+        self.language = Some(lcid);
+        self
+    }
+
+    /// Adds an interceptor that sees every request and response.
+    ///
+    /// Interceptors run in the order they were added. See [`crate::middleware`].
+    pub fn interceptor<I: Interceptor + 'static>(mut self, interceptor: I) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Adds a telemetry sink that is told about requests, retries, rate
+    /// limiting and cache lookups.
+    ///
+    /// See [`Telemetry`].
+    pub fn telemetry<T: Telemetry + 'static>(mut self, telemetry: T) -> Self {
+        self.telemetry.push(Arc::new(telemetry));
+        self
+    }
+
+    /// Serves requests from an in-memory [`MockDataverse`] instead of the network.
+    ///
+    /// See [`crate::mock`].
+    #[cfg(feature = "test-util")]
+    pub fn mock(mut self, mock: MockDataverse) -> Self {
+        self.mock = Some(mock);
+        self
+    }
 }
 
 impl DataverseClientBuilder<Set<String>, Set<Arc<dyn TokenProvider>>> {
@@ -377,6 +583,15 @@ impl DataverseClientBuilder<Set<String>, Set<Arc<dyn TokenProvider>>> {
             self.cache
                 .or_else(|| Some(Arc::new(InMemoryCache::new()) as Arc<dyn CacheProvider>))
         };
+        // Report cache hits and misses to telemetry
+        let cache = match cache {
+            Some(cache) if !self.telemetry.is_empty() => Some(Arc::new(ObservedCache {
+                inner: cache,
+                telemetry: self.telemetry.clone(),
+            })
+                as Arc<dyn CacheProvider>),
+            cache => cache,
+        };
 
         DataverseClient {
             inner: Arc::new(DataverseClientInner {
@@ -390,6 +605,16 @@ impl DataverseClientBuilder<Set<String>, Set<Arc<dyn TokenProvider>>> {
                 concurrency_limiter: self.concurrency_limiter.unwrap_or_default(),
                 rate_limiter: self.rate_limiter.unwrap_or_default(),
                 retry_config: self.retry_config,
+                impersonation: self.impersonation,
+                debug: self.debug,
+                plugin_traces: self.plugin_traces,
+                language: self.language,
+                session_token: Arc::default(),
+                server_version: Arc::default(),
+                interceptors: self.interceptors,
+                telemetry: self.telemetry,
+                #[cfg(feature = "test-util")]
+                mock: self.mock,
             }),
         }
     }
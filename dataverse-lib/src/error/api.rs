@@ -3,6 +3,7 @@
 use std::time::Duration;
 
 use super::DataverseErrorDetail;
+use super::RawResponse;
 
 /// Errors that can occur during API calls.
 #[derive(Debug, thiserror::Error)]
@@ -18,6 +19,8 @@ pub enum ApiError {
         code: Option<String>,
         /// Detailed error information from Dataverse.
         inner: Option<Box<DataverseErrorDetail>>,
+        /// The raw request and response, in debug mode.
+        raw: Option<Box<RawResponse>>,
     },
 
     /// Network error during API call.
@@ -50,6 +53,7 @@ impl ApiError {
             message: message.into(),
             code: None,
             inner: None,
+            raw: None,
         }
     }
 
@@ -64,6 +68,7 @@ impl ApiError {
             message: message.into(),
             code: Some(detail.code.clone()),
             inner: Some(Box::new(detail)),
+            raw: None,
         }
     }
 
@@ -107,6 +112,15 @@ impl ApiError {
         }
     }
 
+    /// Returns the raw request and response of the failed call, kept when
+    /// the client is in debug mode.
+    pub fn raw_response(&self) -> Option<&RawResponse> {
+        match self {
+            Self::Http { raw, .. } => raw.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Returns `true` if this error is potentially retryable.
     pub fn is_retryable(&self) -> bool {
         match self {
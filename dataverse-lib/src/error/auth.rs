@@ -42,4 +42,8 @@ pub enum AuthError {
     /// Failed to start local callback server for browser auth.
     #[error("Failed to start callback server: {0}")]
     CallbackServerFailed(String),
+
+    /// Failed to read or write the token cache.
+    #[error("Token cache error: {0}")]
+    Cache(String),
 }
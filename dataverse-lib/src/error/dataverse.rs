@@ -2,6 +2,9 @@
 
 use std::collections::HashMap;
 
+use crate::api::PluginTrace;
+use crate::api::plugin_trace::PLUGIN_ERROR_CODES;
+
 /// Detailed error information from Dataverse API responses.
 ///
 /// Dataverse returns structured error information that can include
@@ -16,6 +19,9 @@ pub struct DataverseErrorDetail {
     pub inner_error: Option<Box<DataverseErrorDetail>>,
     /// Additional error metadata.
     pub additional_info: HashMap<String, serde_json::Value>,
+    /// Trace entries of the plugin that failed, when the client attaches
+    /// them.
+    pub plugin_traces: Vec<PluginTrace>,
 }
 
 impl DataverseErrorDetail {
@@ -26,9 +32,31 @@ impl DataverseErrorDetail {
             message: message.into(),
             inner_error: None,
             additional_info: HashMap::new(),
+            plugin_traces: Vec::new(),
         }
     }
 
+    /// Parses the body of an OData error response.
+    pub fn parse(body: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(body).ok()?;
+        let error = json.get("error")?.as_object()?;
+        let code = error.get("code")?.as_str()?;
+        let message = error.get("message").and_then(|v| v.as_str()).unwrap_or("");
+
+        let mut detail = Self::new(code, message);
+        detail.additional_info = error
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "code" | "message"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Some(detail)
+    }
+
+    /// Returns `true` if a plugin failed, i.e. threw an exception.
+    pub fn is_plugin_error(&self) -> bool {
+        PLUGIN_ERROR_CODES.iter().any(|code| self.has_code(code))
+    }
+
     /// Returns the innermost error in the chain.
     pub fn innermost(&self) -> &DataverseErrorDetail {
         let mut current = self;
@@ -55,3 +83,30 @@ impl std::fmt::Display for DataverseErrorDetail {
         write!(f, "[{}] {}", self.code, self.message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plugin_error() {
+        let body = r#"{"error":{"code":"0x80040265","message":"Name is required.",
+            "@Microsoft.PowerApps.CDS.TraceText":"Entered ValidateAccount"}}"#;
+        let detail = DataverseErrorDetail::parse(body).unwrap();
+        assert_eq!(detail.code, "0x80040265");
+        assert_eq!(detail.message, "Name is required.");
+        assert!(detail.is_plugin_error());
+        assert_eq!(
+            detail.additional_info["@Microsoft.PowerApps.CDS.TraceText"],
+            "Entered ValidateAccount"
+        );
+
+        let not_found = r#"{"error":{"code":"0x80040217","message":"Not found"}}"#;
+        assert!(
+            !DataverseErrorDetail::parse(not_found)
+                .unwrap()
+                .is_plugin_error()
+        );
+        assert!(DataverseErrorDetail::parse("Bad Gateway").is_none());
+    }
+}
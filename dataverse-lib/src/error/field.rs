@@ -14,6 +14,10 @@ pub enum FieldError {
         expected: &'static str,
         actual: &'static str,
     },
+
+    /// The field has the right type but a value that can't be represented.
+    #[error("Field '{field}' has invalid value: {message}")]
+    InvalidValue { field: String, message: String },
 }
 
 impl FieldError {
@@ -36,4 +40,12 @@ impl FieldError {
             actual,
         }
     }
+
+    /// Creates a new invalid value error.
+    pub fn invalid_value(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::InvalidValue {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
@@ -5,12 +5,16 @@ mod auth;
 mod concurrency;
 mod dataverse;
 mod field;
+mod raw;
+mod sync;
 mod validation;
 
 pub use api::*;
 pub use auth::*;
 pub use dataverse::*;
 pub use field::*;
+pub use raw::*;
+pub use sync::*;
 pub use validation::*;
 
 use std::time::Duration;
@@ -75,6 +79,9 @@ pub enum Error {
     },
 
     /// Concurrency conflict - record was modified by another user.
+    ///
+    /// Carries the record's current ETag when known, so a conflict loop can
+    /// re-read and retry.
     #[error("Concurrency conflict: record was modified")]
     Concurrency { current_etag: Option<String> },
 
@@ -101,6 +108,10 @@ pub enum Error {
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
 
+    /// I/O error reading or writing file content.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Batch size exceeded the maximum allowed.
     #[error("Batch size exceeded: {count} operations (max {max})")]
     BatchSizeExceeded { count: usize, max: usize },
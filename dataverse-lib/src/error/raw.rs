@@ -0,0 +1,131 @@
+//! Raw request/response capture for debugging
+
+/// Headers redacted by default, as they carry credentials.
+const DEFAULT_REDACTED_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// What replaces the value of a redacted header.
+const REDACTED: &str = "[redacted]";
+
+/// Settings for the client's debug mode, which keeps the raw request and
+/// response of failed calls on the error.
+///
+/// Bodies are cut to `max_body_size` bytes, and the values of sensitive
+/// headers are replaced with `[redacted]`.
+///
+/// # Example
+///
+/// ```
+/// use dataverse_lib::error::DebugConfig;
+///
+/// let config = DebugConfig::default()
+///     .max_body_size(4096)
+///     .redact_header("x-api-key");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebugConfig {
+    /// Maximum size of each captured body, in bytes.
+    pub max_body_size: usize,
+    /// Names of the headers whose values are redacted (case-insensitive).
+    pub redacted_headers: Vec<String>,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            max_body_size: 64 * 1024,
+            redacted_headers: DEFAULT_REDACTED_HEADERS.map(String::from).to_vec(),
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Sets the maximum size of each captured body, in bytes.
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Also redacts the value of this header.
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redacted_headers.push(name.into());
+        self
+    }
+
+    /// Returns the headers as name/value pairs, with sensitive values
+    /// redacted.
+    pub(crate) fn headers(&self, headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let redacted = self
+                    .redacted_headers
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(name.as_str()));
+                let value = if redacted {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Returns a body as text, cut to the maximum size.
+    pub(crate) fn body(&self, body: &[u8]) -> (String, bool) {
+        let truncated = body.len() > self.max_body_size;
+        let body = &body[..body.len().min(self.max_body_size)];
+        (String::from_utf8_lossy(body).into_owned(), truncated)
+    }
+}
+
+/// The raw request and response of a failed call, kept in debug mode.
+///
+/// See [`DataverseClientBuilder::debug_mode`](crate::DataverseClientBuilder::debug_mode).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawResponse {
+    /// The request method.
+    pub method: String,
+    /// The request URL.
+    pub url: String,
+    /// The request headers, with sensitive values redacted.
+    pub request_headers: Vec<(String, String)>,
+    /// The request body, if any.
+    pub request_body: Option<String>,
+    /// The response status.
+    pub status: u16,
+    /// The response headers, with sensitive values redacted.
+    pub headers: Vec<(String, String)>,
+    /// The response body.
+    pub body: String,
+    /// Whether either body was cut to the size cap.
+    pub truncated: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderMap;
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_redacts_and_truncates() {
+        let config = DebugConfig::default().max_body_size(4);
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("Accept", HeaderValue::from_static("application/json"));
+
+        let captured = config.headers(&headers);
+        assert!(captured.contains(&("authorization".to_string(), REDACTED.to_string())));
+        assert!(captured.contains(&("accept".to_string(), "application/json".to_string())));
+
+        assert_eq!(config.body(b"{\"a\":1}"), ("{\"a\"".to_string(), true));
+        assert_eq!(config.body(b"{}"), ("{}".to_string(), false));
+    }
+}
@@ -0,0 +1,27 @@
+//! Offline store error types
+
+use super::Error;
+
+/// Errors from the offline store in [`crate::sync`].
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    /// Reading or writing the local database failed.
+    #[error("Offline store error: {0}")]
+    Store(#[from] async_sqlite::Error),
+
+    /// A record couldn't be encoded for the local database.
+    #[error("Failed to encode record: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+
+    /// A record in the local database couldn't be decoded.
+    #[error("Failed to decode record: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+
+    /// The entity isn't mirrored by the store.
+    #[error("Entity '{0}' is not mirrored")]
+    NotMirrored(String),
+
+    /// A request to Dataverse failed.
+    #[error(transparent)]
+    Dataverse(#[from] Error),
+}
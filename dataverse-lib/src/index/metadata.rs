@@ -0,0 +1,410 @@
+//! Full-text search over entity, attribute and relationship metadata
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::api::CACHE_KEY_ALL_ENTITIES;
+use crate::api::CACHE_KEY_ATTRIBUTES;
+use crate::api::CACHE_KEY_ENTITY_FULL;
+use crate::api::CACHE_KEY_RELATIONSHIP;
+use crate::cache;
+use crate::cache::CacheProvider;
+use crate::model::metadata::AttributeMetadata;
+use crate::model::metadata::EntityMetadata;
+use crate::model::metadata::ManyToManyRelationship;
+use crate::model::metadata::OneToManyRelationship;
+use crate::model::metadata::RelationshipMetadata;
+
+/// How much a match in each kind of field counts.
+const DISPLAY_NAME_WEIGHT: f64 = 3.0;
+const LOGICAL_NAME_WEIGHT: f64 = 2.0;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+
+/// How much a term matching only the start of a word counts, against a
+/// whole word.
+const PREFIX_FACTOR: f64 = 0.5;
+
+/// What a [`MetadataMatch`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MetadataKind {
+    /// An entity (table).
+    Entity,
+    /// An attribute (column) of an entity.
+    Attribute,
+    /// A relationship between entities.
+    Relationship,
+}
+
+/// A metadata item found by [`MetadataIndex::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataMatch {
+    /// What was found.
+    pub kind: MetadataKind,
+    /// The logical name of the entity, or of the referenced (first) entity
+    /// for relationships.
+    pub entity: String,
+    /// The logical name of the item (the schema name for relationships).
+    pub name: String,
+    /// The display name in the user's language, if any.
+    pub display_name: Option<String>,
+    /// How well the item matches; higher is better.
+    pub score: f64,
+}
+
+/// An indexed metadata item.
+#[derive(Debug)]
+struct Document {
+    kind: MetadataKind,
+    entity: String,
+    name: String,
+    display_name: Option<String>,
+}
+
+/// A local full-text index over metadata, for fast pickers.
+///
+/// Indexes the display names, logical and schema names, and descriptions of
+/// entities and attributes, and the names of relationships. Names are split
+/// into words on anything that isn't a letter or digit, so `new_customaddress`
+/// is found by `custom` and `Custom Address` by `addr`.
+///
+/// Build it from metadata already fetched, or from whatever the client's
+/// metadata cache holds.
+///
+/// # Example
+///
+/// ```ignore
+/// use dataverse_lib::index::MetadataIndex;
+///
+/// let index = MetadataIndex::from_cache(client.cache().unwrap()).await;
+/// for hit in index.search("custom addr").iter().take(10) {
+///     println!("{:?} {}.{} ({:.1})", hit.kind, hit.entity, hit.name, hit.score);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MetadataIndex {
+    documents: Vec<Document>,
+    /// Word to the documents containing it, with the weight of the field.
+    terms: BTreeMap<String, Vec<(usize, f64)>>,
+    /// Which items are indexed, so each is indexed once.
+    seen: HashSet<(MetadataKind, String, String)>,
+}
+
+impl MetadataIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an index from the entity, attribute and relationship metadata
+    /// in a cache.
+    ///
+    /// Entries that are expired or can't be read are skipped.
+    pub async fn from_cache(cache: &dyn CacheProvider) -> Self {
+        let mut index = Self::new();
+        for entry in cache.get_all().await {
+            let key = entry.key.as_str();
+            let Some(value) = cache.get(key).await else {
+                continue;
+            };
+            if key == CACHE_KEY_ALL_ENTITIES {
+                if let Ok(entities) = cache::deserialize::<Vec<EntityMetadata>>(&value.data) {
+                    entities.iter().for_each(|e| index.add_entity(e));
+                }
+            } else if key.starts_with(CACHE_KEY_ENTITY_FULL) {
+                if let Ok(entity) = cache::deserialize::<EntityMetadata>(&value.data) {
+                    index.add_entity(&entity);
+                }
+            } else if let Some(entity) = key.strip_prefix(CACHE_KEY_ATTRIBUTES) {
+                if let Ok(attributes) = cache::deserialize::<Vec<AttributeMetadata>>(&value.data) {
+                    index.add_attributes(entity, &attributes);
+                }
+            } else if key.starts_with(CACHE_KEY_RELATIONSHIP)
+                && let Ok(relationship) = cache::deserialize::<RelationshipMetadata>(&value.data)
+            {
+                index.add_relationship(&relationship);
+            }
+        }
+        index
+    }
+
+    /// Returns the number of indexed items.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns `true` if nothing is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Indexes an entity, with the attributes and relationships its metadata
+    /// includes.
+    pub fn add_entity(&mut self, entity: &EntityMetadata) {
+        self.add(
+            MetadataKind::Entity,
+            &entity.logical_name,
+            &entity.logical_name,
+            &[
+                (entity.display_name.text(), DISPLAY_NAME_WEIGHT),
+                (entity.display_collection_name.text(), DISPLAY_NAME_WEIGHT),
+                (Some(&entity.logical_name), LOGICAL_NAME_WEIGHT),
+                (Some(&entity.schema_name), LOGICAL_NAME_WEIGHT),
+                (Some(&entity.entity_set_name), LOGICAL_NAME_WEIGHT),
+                (entity.description.text(), DESCRIPTION_WEIGHT),
+            ],
+            entity.display_name.text(),
+        );
+        self.add_attributes(&entity.logical_name, &entity.attributes);
+        for relationship in entity
+            .one_to_many_relationships
+            .iter()
+            .chain(&entity.many_to_one_relationships)
+        {
+            self.add_one_to_many(relationship);
+        }
+        for relationship in &entity.many_to_many_relationships {
+            self.add_many_to_many(relationship);
+        }
+    }
+
+    /// Indexes the attributes of an entity.
+    pub fn add_attributes(&mut self, entity: &str, attributes: &[AttributeMetadata]) {
+        for attribute in attributes {
+            let entity = attribute.entity_logical_name.as_deref().unwrap_or(entity);
+            self.add(
+                MetadataKind::Attribute,
+                entity,
+                &attribute.logical_name,
+                &[
+                    (attribute.display_name.text(), DISPLAY_NAME_WEIGHT),
+                    (Some(&attribute.logical_name), LOGICAL_NAME_WEIGHT),
+                    (Some(&attribute.schema_name), LOGICAL_NAME_WEIGHT),
+                    (attribute.description.text(), DESCRIPTION_WEIGHT),
+                ],
+                attribute.display_name.text(),
+            );
+        }
+    }
+
+    /// Indexes a relationship.
+    pub fn add_relationship(&mut self, relationship: &RelationshipMetadata) {
+        match relationship {
+            RelationshipMetadata::OneToMany(r) => self.add_one_to_many(r),
+            RelationshipMetadata::ManyToMany(r) => self.add_many_to_many(r),
+        }
+    }
+
+    fn add_one_to_many(&mut self, r: &OneToManyRelationship) {
+        self.add(
+            MetadataKind::Relationship,
+            &r.referenced_entity,
+            &r.schema_name,
+            &[
+                (Some(&r.schema_name), LOGICAL_NAME_WEIGHT),
+                (Some(&r.referencing_attribute), DESCRIPTION_WEIGHT),
+                (
+                    r.referenced_entity_navigation_property_name.as_deref(),
+                    DESCRIPTION_WEIGHT,
+                ),
+                (
+                    r.referencing_entity_navigation_property_name.as_deref(),
+                    DESCRIPTION_WEIGHT,
+                ),
+            ],
+            None,
+        );
+    }
+
+    fn add_many_to_many(&mut self, r: &ManyToManyRelationship) {
+        self.add(
+            MetadataKind::Relationship,
+            &r.entity1_logical_name,
+            &r.schema_name,
+            &[
+                (Some(&r.schema_name), LOGICAL_NAME_WEIGHT),
+                (Some(&r.intersect_entity_name), DESCRIPTION_WEIGHT),
+                (
+                    r.entity1_navigation_property_name.as_deref(),
+                    DESCRIPTION_WEIGHT,
+                ),
+                (
+                    r.entity2_navigation_property_name.as_deref(),
+                    DESCRIPTION_WEIGHT,
+                ),
+            ],
+            None,
+        );
+    }
+
+    fn add(
+        &mut self,
+        kind: MetadataKind,
+        entity: &str,
+        name: &str,
+        fields: &[(Option<&str>, f64)],
+        display_name: Option<&str>,
+    ) {
+        if !self
+            .seen
+            .insert((kind, entity.to_string(), name.to_string()))
+        {
+            return;
+        }
+
+        let id = self.documents.len();
+        self.documents.push(Document {
+            kind,
+            entity: entity.to_string(),
+            name: name.to_string(),
+            display_name: display_name.map(String::from),
+        });
+
+        // Keep the best field each word appears in
+        let mut words: HashMap<String, f64> = HashMap::new();
+        for (text, weight) in fields {
+            for word in text.map(tokenize).unwrap_or_default() {
+                let best = words.entry(word).or_default();
+                *best = best.max(*weight);
+            }
+        }
+        for (word, weight) in words {
+            self.terms.entry(word).or_default().push((id, weight));
+        }
+    }
+
+    /// Searches the index, returning the items matching every word of
+    /// `query`, best first.
+    ///
+    /// Each query word matches whole words and the start of words, so
+    /// `custom addr` finds `Custom Address`. Matches in display names count
+    /// more than in logical names, and those more than in descriptions.
+    pub fn search(&self, query: &str) -> Vec<MetadataMatch> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (i, word) in words.iter().enumerate() {
+            let mut best: HashMap<usize, f64> = HashMap::new();
+            for (term, postings) in self.terms.range(word.clone()..) {
+                if !term.starts_with(word.as_str()) {
+                    break;
+                }
+                let factor = if term == word { 1.0 } else { PREFIX_FACTOR };
+                for &(id, weight) in postings {
+                    let score = best.entry(id).or_default();
+                    *score = score.max(weight * factor);
+                }
+            }
+
+            // Every word must match
+            if i == 0 {
+                scores = best;
+            } else {
+                scores.retain(|id, _| best.contains_key(id));
+                for (id, score) in scores.iter_mut() {
+                    *score += best[id];
+                }
+            }
+        }
+
+        let mut matches: Vec<MetadataMatch> = scores
+            .into_iter()
+            .map(|(id, score)| {
+                let doc = &self.documents[id];
+                MetadataMatch {
+                    kind: doc.kind,
+                    entity: doc.entity.clone(),
+                    name: doc.name.clone(),
+                    display_name: doc.display_name.clone(),
+                    score,
+                }
+            })
+            .collect();
+        // Ties go to entities before attributes, then to shorter names
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.kind.cmp(&b.kind))
+                .then(a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.entity.cmp(&b.entity))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        matches
+    }
+}
+
+/// Splits text into lowercase words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::metadata::Label;
+    use crate::model::metadata::LocalizedLabel;
+
+    fn text(s: &str) -> Label {
+        Label {
+            user_localized_label: Some(LocalizedLabel {
+                label: s.to_string(),
+                language_code: 1033,
+            }),
+            localized_labels: Vec::new(),
+        }
+    }
+
+    fn attribute(entity: &str, name: &str, display: &str) -> AttributeMetadata {
+        let mut attribute: AttributeMetadata = serde_json::from_value(serde_json::json!({
+            "MetadataId": uuid::Uuid::new_v4(),
+            "LogicalName": name,
+            "SchemaName": name,
+            "AttributeType": "String",
+            "EntityLogicalName": entity,
+        }))
+        .unwrap();
+        attribute.display_name = text(display);
+        attribute
+    }
+
+    #[test]
+    fn test_search_ranks_matches() {
+        let mut index = MetadataIndex::new();
+        index.add_attributes(
+            "account",
+            &[
+                attribute("account", "new_customaddress", "Custom Address"),
+                attribute("account", "address1_line1", "Address 1: Street 1"),
+                attribute("account", "new_customerid", "Customer"),
+            ],
+        );
+        index.add_attributes(
+            "account",
+            &[attribute(
+                "account",
+                "address1_line1",
+                "Address 1: Street 1",
+            )],
+        );
+        assert_eq!(index.len(), 3);
+
+        let hits = index.search("custom addr");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].name, "new_customaddress");
+        assert_eq!(hits[0].display_name.as_deref(), Some("Custom Address"));
+
+        let hits = index.search("Address");
+        assert_eq!(hits.len(), 2);
+        // Equal matches go to the shorter name
+        assert_eq!(hits[0].name, "address1_line1");
+
+        assert!(index.search("street 2").is_empty());
+        assert!(index.search("  ").is_empty());
+    }
+}
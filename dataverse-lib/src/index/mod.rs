@@ -1,3 +1,8 @@
 //! Environment schema indexing
 
+mod metadata;
+mod records;
 mod schema;
+
+pub use metadata::*;
+pub use records::*;
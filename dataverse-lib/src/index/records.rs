@@ -0,0 +1,501 @@
+//! On-disk index of records for offline search
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::cache;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::Value;
+
+/// How much a term matching only the start of a word counts, against a
+/// whole word.
+const PREFIX_FACTOR: f64 = 0.5;
+
+/// A change to the index, as written to its log.
+#[derive(Serialize, Deserialize)]
+enum LogEntry {
+    Put(Box<Record>),
+    Remove(Entity, Uuid),
+}
+
+/// A record found by [`RecordIndex::search`].
+#[derive(Debug, Clone)]
+pub struct RecordMatch<'a> {
+    /// The record.
+    pub record: &'a Record,
+    /// How well the record matches; higher is better.
+    pub score: f64,
+}
+
+/// A local index of records, for searching cached data offline.
+///
+/// Records are keyed by entity and id, so ingesting a record again replaces
+/// it. Field values (and their formatted values, such as option set labels
+/// and lookup names) are split into lowercase words and indexed per field.
+///
+/// On disk, the index is a log of changes that is replayed when it is
+/// opened; [`compact`](Self::compact) rewrites it with only the current
+/// records. The log is written synchronously, so call it from a blocking
+/// task in async code.
+///
+/// # Example
+///
+/// ```ignore
+/// use dataverse_lib::index::RecordIndex;
+///
+/// let mut index = RecordIndex::open("accounts.idx")?;
+/// let records = client.query(Entity::set("accounts")).collect().await?;
+/// index.ingest(records)?;
+///
+/// for hit in index.search("name:contoso seattle") {
+///     println!("{:?} {:.1}", hit.record.id(), hit.score);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordIndex {
+    path: Option<PathBuf>,
+    /// Indexed records; removed ones leave a hole.
+    records: Vec<Option<Record>>,
+    /// Entity name and id to the position of the record.
+    keys: HashMap<(String, Uuid), usize>,
+    /// Field and word to the records containing that word in that field.
+    terms: HashMap<String, BTreeMap<String, HashSet<usize>>>,
+}
+
+impl RecordIndex {
+    /// Creates an index kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens an index stored at `path`, creating it if it doesn't exist.
+    ///
+    /// A change cut short by a crash is dropped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut index = Self::new();
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut reader = bytes.as_slice();
+        let mut valid = 0;
+        while let Some(entry) = read_entry(&mut reader) {
+            match entry {
+                LogEntry::Put(record) => index.put(*record),
+                LogEntry::Remove(entity, id) => index.take(&entity, id),
+            }
+            valid = bytes.len() - reader.len();
+        }
+        if valid < bytes.len() {
+            log::warn!(
+                "RecordIndex::open - dropping {} bytes of incomplete changes in {}",
+                bytes.len() - valid,
+                path.display()
+            );
+            File::options()
+                .write(true)
+                .open(&path)?
+                .set_len(valid as u64)?;
+        }
+
+        index.path = Some(path);
+        Ok(index)
+    }
+
+    /// Returns the number of indexed records.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if no records are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns an indexed record.
+    pub fn get(&self, entity: &Entity, id: Uuid) -> Option<&Record> {
+        let slot = *self.keys.get(&(entity.name().to_string(), id))?;
+        self.records[slot].as_ref()
+    }
+
+    /// Returns the indexed records.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter().flatten()
+    }
+
+    /// Adds records to the index, replacing those already indexed.
+    ///
+    /// Records without an id can't be looked up again and are skipped.
+    /// Returns how many records were indexed.
+    pub fn ingest(&mut self, records: impl IntoIterator<Item = Record>) -> io::Result<usize> {
+        let entries: Vec<LogEntry> = records
+            .into_iter()
+            .filter(|record| record.id().is_some())
+            .map(|record| LogEntry::Put(Box::new(record)))
+            .collect();
+        self.append(&entries)?;
+
+        let count = entries.len();
+        for entry in entries {
+            if let LogEntry::Put(record) = entry {
+                self.put(*record);
+            }
+        }
+        Ok(count)
+    }
+
+    /// Removes a record from the index, returning whether it was indexed.
+    pub fn remove(&mut self, entity: &Entity, id: Uuid) -> io::Result<bool> {
+        if !self.keys.contains_key(&(entity.name().to_string(), id)) {
+            return Ok(false);
+        }
+        self.append(&[LogEntry::Remove(entity.clone(), id)])?;
+        self.take(entity, id);
+        Ok(true)
+    }
+
+    /// Removes every record of an entity, returning how many.
+    pub fn remove_entity(&mut self, entity: &Entity) -> io::Result<usize> {
+        let ids: Vec<Uuid> = self
+            .keys
+            .keys()
+            .filter(|(name, _)| name == entity.name())
+            .map(|(_, id)| *id)
+            .collect();
+        let entries: Vec<LogEntry> = ids
+            .iter()
+            .map(|id| LogEntry::Remove(entity.clone(), *id))
+            .collect();
+        self.append(&entries)?;
+        for id in &ids {
+            self.take(entity, *id);
+        }
+        Ok(ids.len())
+    }
+
+    /// Rewrites the log on disk with only the current records.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temp = path.with_extension("compact");
+        let mut file = io::BufWriter::new(File::create(&temp)?);
+        for record in self.records.iter().flatten() {
+            write_entry(&mut file, &LogEntry::Put(Box::new(record.clone())))?;
+        }
+        file.into_inner()?.sync_all()?;
+        fs::rename(&temp, path)?;
+
+        // Slots of removed records can go now
+        let records: Vec<Record> = self.records.drain(..).flatten().collect();
+        self.keys.clear();
+        self.terms.clear();
+        for record in records {
+            self.put(record);
+        }
+        Ok(())
+    }
+
+    /// Searches all entities; see [`search_in`](Self::search_in).
+    pub fn search(&self, query: &str) -> Vec<RecordMatch<'_>> {
+        self.search_filtered(query, None)
+    }
+
+    /// Searches the records of an entity, returning those matching every
+    /// term of `query`, best first.
+    ///
+    /// Terms are words, matching any field, or `field:word`, matching one
+    /// field only; each matches whole words and the start of words. For
+    /// example `name:conto address1_city:seattle`.
+    pub fn search_in(&self, entity: &Entity, query: &str) -> Vec<RecordMatch<'_>> {
+        self.search_filtered(query, Some(entity.name()))
+    }
+
+    fn search_filtered(&self, query: &str, entity: Option<&str>) -> Vec<RecordMatch<'_>> {
+        let terms: Vec<(Option<String>, String)> = query
+            .split_whitespace()
+            .flat_map(|term| match term.split_once(':') {
+                Some((field, text)) if !field.is_empty() => tokenize(text)
+                    .into_iter()
+                    .map(|word| (Some(field.to_lowercase()), word))
+                    .collect::<Vec<_>>(),
+                _ => tokenize(term)
+                    .into_iter()
+                    .map(|word| (None, word))
+                    .collect(),
+            })
+            .collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<usize, f64>> = None;
+        for (field, word) in &terms {
+            let mut best: HashMap<usize, f64> = HashMap::new();
+            let fields = self
+                .terms
+                .iter()
+                .filter(|(name, _)| field.as_ref().is_none_or(|f| f == *name));
+            for (_, words) in fields {
+                for (term, slots) in words.range(word.clone()..) {
+                    if !term.starts_with(word.as_str()) {
+                        break;
+                    }
+                    let factor = if term == word { 1.0 } else { PREFIX_FACTOR };
+                    for &slot in slots {
+                        let score = best.entry(slot).or_default();
+                        *score = score.max(factor);
+                    }
+                }
+            }
+
+            // Every term must match
+            scores = Some(match scores {
+                None => best,
+                Some(mut scores) => {
+                    scores.retain(|slot, _| best.contains_key(slot));
+                    for (slot, score) in scores.iter_mut() {
+                        *score += best[slot];
+                    }
+                    scores
+                }
+            });
+        }
+
+        let mut matches: Vec<RecordMatch<'_>> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(slot, score)| {
+                let record = self.records[slot].as_ref()?;
+                let in_entity = entity.is_none_or(|e| record.entity().name() == e);
+                in_entity.then_some(RecordMatch { record, score })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.record.id().cmp(&b.record.id()))
+        });
+        matches
+    }
+
+    /// Appends changes to the log on disk.
+    fn append(&self, entries: &[LogEntry]) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let file = File::options().create(true).append(true).open(path)?;
+        let mut file = io::BufWriter::new(file);
+        for entry in entries {
+            write_entry(&mut file, entry)?;
+        }
+        file.flush()
+    }
+
+    /// Indexes a record in memory, replacing any previous version.
+    fn put(&mut self, record: Record) {
+        let Some(id) = record.id() else {
+            return;
+        };
+        let entity = record.entity().clone();
+        self.take(&entity, id);
+
+        let slot = self.records.len();
+        for (field, words) in fields(&record) {
+            let postings = self.terms.entry(field).or_default();
+            for word in words {
+                postings.entry(word).or_default().insert(slot);
+            }
+        }
+        self.keys.insert((entity.name().to_string(), id), slot);
+        self.records.push(Some(record));
+    }
+
+    /// Removes a record from memory.
+    fn take(&mut self, entity: &Entity, id: Uuid) {
+        let Some(slot) = self.keys.remove(&(entity.name().to_string(), id)) else {
+            return;
+        };
+        let Some(record) = self.records[slot].take() else {
+            return;
+        };
+        for (field, words) in fields(&record) {
+            if let Some(postings) = self.terms.get_mut(&field) {
+                for word in words {
+                    if let Some(slots) = postings.get_mut(&word) {
+                        slots.remove(&slot);
+                        if slots.is_empty() {
+                            postings.remove(&word);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the words of each field of a record, including formatted values.
+fn fields(record: &Record) -> HashMap<String, HashSet<String>> {
+    let mut fields: HashMap<String, HashSet<String>> = HashMap::new();
+    for (name, value) in record.fields() {
+        let Some(text) = value_text(value) else {
+            continue;
+        };
+        fields
+            .entry(name.to_lowercase())
+            .or_default()
+            .extend(tokenize(&text));
+    }
+    for (name, text) in record.formatted_values() {
+        fields
+            .entry(name.to_lowercase())
+            .or_default()
+            .extend(tokenize(text));
+    }
+    fields
+}
+
+/// Returns the searchable text of a value.
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Int(n) => Some(n.to_string()),
+        Value::Long(n) => Some(n.to_string()),
+        Value::Float(n) => Some(n.to_string()),
+        Value::Decimal(n) => Some(n.to_string()),
+        Value::Guid(id) => Some(id.to_string()),
+        Value::DateTime(dt) => Some(dt.format("%Y-%m-%d").to_string()),
+        Value::EntityReference(r) => Some(match &r.name {
+            Some(name) => format!("{} {}", r.id, name),
+            None => r.id.to_string(),
+        }),
+        Value::OptionSet(o) => Some(o.value.to_string()),
+        _ => None,
+    }
+}
+
+/// Splits text into lowercase words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Writes a length-prefixed log entry.
+fn write_entry(writer: &mut impl Write, entry: &LogEntry) -> io::Result<()> {
+    let bytes = cache::serialize(entry).map_err(io::Error::other)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Reads a log entry, or `None` at the end of the log or an incomplete entry.
+fn read_entry(reader: &mut &[u8]) -> Option<LogEntry> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len).ok()?;
+    let len = u32::from_le_bytes(len) as usize;
+    let bytes = reader.get(..len)?;
+    let entry = cache::deserialize(bytes).ok()?;
+    *reader = &reader[len..];
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(name: &str, city: &str) -> Record {
+        Record::with_id(Entity::logical("account"), Uuid::new_v4())
+            .set("name", name)
+            .set("address1_city", city)
+    }
+
+    #[test]
+    fn test_fielded_search_and_updates() {
+        let mut index = RecordIndex::new();
+        let contoso = account("Contoso Ltd", "Seattle");
+        let id = contoso.id().unwrap();
+        index
+            .ingest([
+                contoso,
+                account("Fabrikam", "Seattle"),
+                Record::new("account"),
+            ])
+            .unwrap();
+        assert_eq!(index.len(), 2);
+
+        assert_eq!(index.search("seattle").len(), 2);
+        let hits = index.search("name:conto address1_city:seattle");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].record.id(), Some(id));
+        assert!(index.search("name:seattle").is_empty());
+        assert!(
+            index
+                .search_in(&Entity::logical("contact"), "seattle")
+                .is_empty()
+        );
+
+        // Ingesting again replaces the record and its words
+        let mut moved = account("Contoso Ltd", "Redmond");
+        moved.set_id(id);
+        index.ingest([moved]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("seattle").len(), 1);
+        assert_eq!(index.search("redmond")[0].record.id(), Some(id));
+
+        assert!(index.remove(&Entity::logical("account"), id).unwrap());
+        assert!(index.search("contoso").is_empty());
+    }
+
+    #[test]
+    fn test_persists_changes() {
+        let path = std::env::temp_dir().join(format!("dataverse-index-{}", Uuid::new_v4()));
+        let contoso = account("Contoso", "Seattle");
+        let fabrikam = account("Fabrikam", "Redmond");
+        let contoso_id = contoso.id().unwrap();
+        let fabrikam_id = fabrikam.id().unwrap();
+
+        let mut index = RecordIndex::open(&path).unwrap();
+        index.ingest([contoso, fabrikam]).unwrap();
+        index
+            .remove(&Entity::logical("account"), contoso_id)
+            .unwrap();
+        drop(index);
+
+        // A torn write at the end is dropped
+        let mut file = File::options().append(true).open(&path).unwrap();
+        file.write_all(&[42, 0, 0, 0, 1]).unwrap();
+        drop(file);
+
+        let mut index = RecordIndex::open(&path).unwrap();
+        assert_eq!(index.len(), 1);
+        let record = index.get(&Entity::logical("account"), fabrikam_id).unwrap();
+        assert_eq!(record.get_string("name").unwrap(), Some("Fabrikam"));
+        assert_eq!(index.search("redmond").len(), 1);
+
+        index.compact().unwrap();
+        let index = RecordIndex::open(&path).unwrap();
+        assert_eq!(index.len(), 1);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -2,18 +2,27 @@
 //!
 //! A Rust async client library for Microsoft Dynamics 365 Dataverse Web API (v9.2).
 
+// Lets derive output (`::dataverse_lib::...` paths) compile inside this crate
+extern crate self as dataverse_lib;
+
 pub mod api;
 pub mod auth;
 pub mod cache;
 pub mod error;
 pub mod index;
+pub mod middleware;
+#[cfg(feature = "test-util")]
+pub mod mock;
 pub mod model;
 pub mod rate_limit;
 pub mod response;
 pub mod stream;
+pub mod sync;
 
 mod client;
+mod telemetry;
 
 pub use client::*;
 pub use response::CacheStatus;
+pub use response::RequestMetadata;
 pub use response::Response;
@@ -0,0 +1,437 @@
+//! Request middleware
+//!
+//! Interceptors registered on the client with
+//! [`DataverseClientBuilder::interceptor`](crate::DataverseClientBuilder::interceptor)
+//! see every request the client sends to the Web API and every response (or
+//! transport error) it gets back, including each retry attempt.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use async_trait::async_trait;
+//! use dataverse_lib::middleware::{Interceptor, RequestInfo, ResponseInfo};
+//!
+//! struct CorrelationId(String);
+//!
+//! #[async_trait]
+//! impl Interceptor for CorrelationId {
+//!     async fn on_request(&self, request: &mut RequestInfo) {
+//!         request.headers.insert("x-ms-correlation-request-id", self.0.parse().unwrap());
+//!     }
+//!
+//!     async fn on_response(&self, response: &ResponseInfo<'_>) {
+//!         println!(
+//!             "{} {} -> {:?} in {:?}",
+//!             response.request.method,
+//!             response.request.url,
+//!             response.status(),
+//!             response.elapsed
+//!         );
+//!     }
+//! }
+//!
+//! let client = DataverseClient::builder()
+//!     .url("https://org.crm.dynamics.com")
+//!     .token_provider(provider)
+//!     .interceptor(CorrelationId(job_id))
+//!     .build();
+//! ```
+//!
+//! For metrics, a [`Telemetry`] sink registered with
+//! [`DataverseClientBuilder::telemetry`](crate::DataverseClientBuilder::telemetry)
+//! is told when requests start and end, when they are retried or throttled,
+//! and about every cache lookup:
+//!
+//! ```ignore
+//! use dataverse_lib::middleware::Telemetry;
+//!
+//! struct Metrics { /* prometheus counters and histograms */ }
+//!
+//! impl Telemetry for Metrics {
+//!     fn on_request_end(&self, method: &Method, _url: &str, status: Option<StatusCode>, elapsed: Duration) {
+//!         let status = status.map_or("error".to_string(), |s| s.as_u16().to_string());
+//!         self.requests.with_label_values(&[method.as_str(), &status]).observe(elapsed.as_secs_f64());
+//!     }
+//!
+//!     fn on_rate_limited(&self, _method: &Method, _url: &str, _retry_after: Option<Duration>) {
+//!         self.throttled.inc();
+//!     }
+//! }
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use url::Url;
+
+use crate::DataverseClient;
+use crate::cache::CacheEntry;
+use crate::cache::CacheProvider;
+use crate::cache::CachedValue;
+use crate::error::Error;
+use crate::telemetry::InSpan;
+use crate::telemetry::REQUEST_ID_HEADER;
+use crate::telemetry::span;
+
+/// An outgoing request, as seen by interceptors.
+///
+/// Interceptors may change the method, URL and headers before the request is
+/// sent. The `Authorization` header isn't included; it's added afterwards so
+/// tokens don't end up in logs.
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    /// The HTTP method.
+    pub method: Method,
+    /// The full request URL.
+    pub url: Url,
+    /// The request headers.
+    pub headers: HeaderMap,
+    /// Which attempt this is: `0` for the first, `1` for the first retry, etc.
+    pub attempt: u32,
+}
+
+impl RequestInfo {
+    /// Creates request info for the first attempt at a request.
+    pub(crate) fn new(method: Method, url: &str, headers: HeaderMap) -> Result<Self, Error> {
+        let url = Url::parse(url)
+            .map_err(|e| Error::InvalidOperation(format!("Invalid URL '{}': {}", url, e)))?;
+        Ok(Self {
+            method,
+            url,
+            headers,
+            attempt: 0,
+        })
+    }
+}
+
+/// The result of a request, as seen by interceptors.
+#[derive(Debug)]
+pub struct ResponseInfo<'a> {
+    /// The request as it was sent.
+    pub request: &'a RequestInfo,
+    /// The response, or the error that prevented one.
+    pub outcome: Result<&'a reqwest::Response, &'a reqwest::Error>,
+    /// Time from sending the request until the response headers arrived.
+    pub elapsed: Duration,
+}
+
+impl ResponseInfo<'_> {
+    /// Returns the response status, if a response was received.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.outcome.ok().map(|response| response.status())
+    }
+
+    /// Returns the response headers, if a response was received.
+    pub fn headers(&self) -> Option<&HeaderMap> {
+        self.outcome.ok().map(|response| response.headers())
+    }
+}
+
+/// Hooks called around every request the client sends.
+///
+/// Both methods default to doing nothing. Interceptors run in the order they
+/// were registered.
+#[async_trait]
+pub trait Interceptor: Send + Sync {
+    /// Called before a request is sent.
+    async fn on_request(&self, _request: &mut RequestInfo) {}
+
+    /// Called after a response (or transport error) is received.
+    ///
+    /// Responses that will be retried (429, 5xx) are reported too, before the retry.
+    async fn on_response(&self, _response: &ResponseInfo<'_>) {}
+}
+
+/// Metrics hooks, called synchronously as the client works.
+///
+/// Unlike interceptors, which see each HTTP attempt, a request here is one
+/// call to the Web API including its retries. All methods default to doing
+/// nothing; they should return quickly, as they run on the request path.
+pub trait Telemetry: Send + Sync {
+    /// Called when a request is about to be made, before any rate limiting.
+    fn on_request_start(&self, _method: &Method, _url: &str) {}
+
+    /// Called when a request has finished, after any retries.
+    ///
+    /// `status` is the final HTTP status, or `None` if no response was
+    /// received.
+    fn on_request_end(
+        &self,
+        _method: &Method,
+        _url: &str,
+        _status: Option<StatusCode>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called before a request is retried after a 429, 5xx or network error.
+    ///
+    /// `attempt` is the retry about to be made: `1` for the first retry.
+    fn on_retry(&self, _method: &Method, _url: &str, _attempt: u32, _delay: Duration) {}
+
+    /// Called when Dataverse throttles a request with `429 Too Many Requests`.
+    fn on_rate_limited(&self, _method: &Method, _url: &str, _retry_after: Option<Duration>) {}
+
+    /// Called when a cache lookup finds a value.
+    fn on_cache_hit(&self, _key: &str) {}
+
+    /// Called when a cache lookup finds nothing.
+    fn on_cache_miss(&self, _key: &str) {}
+}
+
+/// Reports a client's cache lookups to its telemetry.
+pub(crate) struct ObservedCache {
+    pub(crate) inner: Arc<dyn CacheProvider>,
+    pub(crate) telemetry: Vec<Arc<dyn Telemetry>>,
+}
+
+#[async_trait]
+impl CacheProvider for ObservedCache {
+    async fn get(&self, key: &str) -> Option<CachedValue> {
+        let value = self.inner.get(key).await;
+        for telemetry in &self.telemetry {
+            match value {
+                Some(_) => telemetry.on_cache_hit(key),
+                None => telemetry.on_cache_miss(key),
+            }
+        }
+        value
+    }
+
+    async fn set(&self, key: &str, value: CachedValue) {
+        self.inner.set(key, value).await
+    }
+
+    async fn remove(&self, key: &str) {
+        self.inner.remove(key).await
+    }
+
+    async fn clear(&self) {
+        self.inner.clear().await
+    }
+
+    async fn clear_by_prefix(&self, prefix: &str) -> usize {
+        self.inner.clear_by_prefix(prefix).await
+    }
+
+    async fn gc(&self) -> usize {
+        self.inner.gc().await
+    }
+
+    async fn get_all(&self) -> Vec<CacheEntry> {
+        self.inner.get_all().await
+    }
+}
+
+impl DataverseClient {
+    /// Runs a request, reporting its start and end to telemetry.
+    pub(crate) async fn observe<F>(
+        &self,
+        method: &Method,
+        url: &str,
+        request: F,
+    ) -> Result<reqwest::Response, Error>
+    where
+        F: Future<Output = Result<reqwest::Response, Error>>,
+    {
+        let telemetry = &self.inner.telemetry;
+        if telemetry.is_empty() {
+            return request.await;
+        }
+
+        for t in telemetry {
+            t.on_request_start(method, url);
+        }
+        let started = Instant::now();
+        let result = request.await;
+        let status = match &result {
+            Ok(response) => Some(response.status()),
+            Err(e) => e.status_code().and_then(|s| StatusCode::from_u16(s).ok()),
+        };
+        for t in telemetry {
+            t.on_request_end(method, url, status, started.elapsed());
+        }
+        result
+    }
+
+    /// Sends a request through the interceptors.
+    ///
+    /// Every HTTP call to the Web API goes through here.
+    pub(crate) async fn send(
+        &self,
+        mut request: RequestInfo,
+        body: Option<Bytes>,
+        access_token: &str,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        for interceptor in &self.inner.interceptors {
+            interceptor.on_request(&mut request).await;
+        }
+
+        let mut builder = self
+            .inner
+            .http_client
+            .request(request.method.clone(), request.url.clone())
+            .headers(request.headers.clone())
+            .bearer_auth(access_token);
+
+        if let Some(timeout) = self.inner.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        #[cfg(feature = "test-util")]
+        let body_ref = body.clone();
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+
+        let span = span!(
+            "dataverse.http",
+            method = %request.method,
+            url = %request.url,
+            attempt = request.attempt,
+            status = tracing::field::Empty,
+            request_id = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        #[cfg(feature = "test-util")]
+        let result = match &self.inner.mock {
+            Some(mock) => Ok(mock.handle(&request, body_ref.as_deref())),
+            None => builder.send().in_span(span.clone()).await,
+        };
+        #[cfg(not(feature = "test-util"))]
+        let result = builder.send().in_span(span.clone()).await;
+        if let Ok(response) = &result {
+            span.record("status", response.status().as_u16());
+            if let Some(request_id) = response
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+            {
+                span.record("request_id", request_id);
+            }
+        }
+
+        if !self.inner.interceptors.is_empty() {
+            let info = ResponseInfo {
+                request: &request,
+                outcome: result.as_ref(),
+                elapsed: started.elapsed(),
+            };
+            for interceptor in &self.inner.interceptors {
+                interceptor.on_response(&info).await;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::auth::StaticTokenProvider;
+    use crate::mock::MOCK_URL;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::Entity;
+    use crate::model::Record;
+    use crate::rate_limit::RetryConfig;
+
+    /// Tags requests with a header and logs what it sees.
+    struct Tag {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Interceptor for Tag {
+        async fn on_request(&self, request: &mut RequestInfo) {
+            let trail = match request.headers.get("x-trail") {
+                Some(trail) => format!("{},{}", trail.to_str().unwrap(), self.name),
+                None => self.name.to_string(),
+            };
+            request.headers.insert("x-trail", trail.parse().unwrap());
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} request", self.name));
+        }
+
+        async fn on_response(&self, response: &ResponseInfo<'_>) {
+            self.log.lock().unwrap().push(format!(
+                "{} response {} {}",
+                self.name,
+                response.request.attempt,
+                response.status().unwrap().as_u16()
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors() {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let tag = |name| Tag {
+            name,
+            log: log.clone(),
+        };
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .no_cache()
+            .retry_config(RetryConfig {
+                initial_delay: Duration::from_millis(1),
+                ..RetryConfig::default()
+            })
+            .interceptor(tag("first"))
+            .interceptor(tag("second"))
+            .mock(mock.clone())
+            .build();
+
+        let id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+
+        mock.fail_next(StatusCode::SERVICE_UNAVAILABLE, 2);
+        client.retrieve(Entity::set("accounts"), id).await.unwrap();
+
+        // Every attempt carries the headers both interceptors added, in order
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        for (attempt, request) in requests.iter().enumerate() {
+            assert_eq!(request.attempt, attempt as u32);
+            assert_eq!(
+                request.headers.get("x-trail").unwrap().to_str().unwrap(),
+                "first,second"
+            );
+        }
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "first request",
+                "second request",
+                "first response 0 503",
+                "second response 0 503",
+                "first request",
+                "second request",
+                "first response 1 503",
+                "second response 1 503",
+                "first request",
+                "second request",
+                "first response 2 200",
+                "second response 2 200",
+            ]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! `$batch` handling for the mock backend.
+
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use url::Url;
+
+use super::MockResponse;
+use super::MockState;
+use crate::api::multipart::generate_boundary;
+use crate::api::response::extract_boundary;
+use crate::middleware::RequestInfo;
+
+/// A part of a multipart body: its MIME headers and content.
+struct Part<'a> {
+    headers: Vec<(&'a str, &'a str)>,
+    content: &'a str,
+}
+
+impl Part<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+}
+
+/// Splits a multipart body into its parts.
+fn parts<'a>(body: &'a str, boundary: &str) -> Vec<Part<'a>> {
+    let marker = format!("--{}", boundary);
+    body.split(marker.as_str())
+        .skip(1)
+        .filter(|chunk| !chunk.starts_with("--"))
+        .map(|chunk| {
+            let chunk = chunk.trim_start_matches("\r\n");
+            let (head, content) = chunk.split_once("\r\n\r\n").unwrap_or((chunk, ""));
+            Part {
+                headers: parse_headers(head),
+                content,
+            }
+        })
+        .collect()
+}
+
+fn parse_headers(head: &str) -> Vec<(&str, &str)> {
+    head.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect()
+}
+
+/// Executes a `$batch` request.
+pub(super) fn execute(
+    state: &mut MockState,
+    headers: &HeaderMap,
+    body: Option<&str>,
+) -> MockResponse {
+    let Some(boundary) = super::header(headers, "Content-Type").and_then(extract_boundary) else {
+        return MockResponse::bad_request("Missing boundary in batch request.");
+    };
+    let continue_on_error =
+        super::header(headers, "Prefer").is_some_and(|p| p.contains("odata.continue-on-error"));
+
+    let response_boundary = generate_boundary("batchresponse");
+    let mut out = String::new();
+
+    for part in parts(body.unwrap_or_default(), &boundary) {
+        let changeset_boundary = part
+            .header("Content-Type")
+            .filter(|ct| ct.starts_with("multipart/mixed"))
+            .and_then(extract_boundary);
+
+        out.push_str(&format!("--{}\r\n", response_boundary));
+        let succeeded = match changeset_boundary {
+            Some(changeset_boundary) => {
+                let (body, succeeded) = execute_changeset(state, part.content, &changeset_boundary);
+                out.push_str(&body);
+                succeeded
+            }
+            None => {
+                let (body, succeeded) = execute_operation(state, &part);
+                out.push_str(&body);
+                succeeded
+            }
+        };
+
+        // Without continue-on-error, the batch stops at the first failure
+        if !succeeded && !continue_on_error {
+            break;
+        }
+    }
+    out.push_str(&format!("--{}--\r\n", response_boundary));
+
+    MockResponse {
+        status: StatusCode::OK,
+        headers: vec![(
+            "Content-Type",
+            format!("multipart/mixed; boundary={}", response_boundary),
+        )],
+        body: Some(out.into_bytes()),
+    }
+}
+
+/// Executes a changeset, rolling back its changes if any operation fails.
+///
+/// Returns the response part and whether every operation succeeded.
+fn execute_changeset(state: &mut MockState, body: &str, boundary: &str) -> (String, bool) {
+    let snapshot = state.store.clone();
+    let response_boundary = generate_boundary("changesetresponse");
+    let mut responses = Vec::new();
+    let mut failed = None;
+
+    for part in parts(body, boundary) {
+        let (response, succeeded) = execute_operation(state, &part);
+        if !succeeded {
+            failed = Some(response);
+            break;
+        }
+        responses.push(response);
+    }
+
+    // A failed changeset only reports the failed operation
+    let succeeded = failed.is_none();
+    if let Some(response) = failed {
+        state.store = snapshot;
+        responses = vec![response];
+    }
+
+    let mut out = format!(
+        "Content-Type: multipart/mixed; boundary={}\r\n\r\n",
+        response_boundary
+    );
+    for response in responses {
+        out.push_str(&format!("--{}\r\n", response_boundary));
+        out.push_str(&response);
+    }
+    out.push_str(&format!("--{}--\r\n", response_boundary));
+    (out, succeeded)
+}
+
+/// Executes one operation from a batch.
+///
+/// Returns the response part and whether the operation succeeded.
+fn execute_operation(state: &mut MockState, part: &Part<'_>) -> (String, bool) {
+    let response = match parse_request(part.content) {
+        Ok((method, url, headers, body)) => {
+            let response = state.dispatch(&method, &url, &headers, body.map(str::as_bytes));
+            state.batch_operations.push(RequestInfo {
+                method,
+                url,
+                headers,
+                attempt: 0,
+            });
+            response
+        }
+        Err(message) => MockResponse::bad_request(message),
+    };
+    let succeeded = response.status.is_success();
+
+    let mut out =
+        String::from("Content-Type: application/http\r\nContent-Transfer-Encoding: binary\r\n");
+    if let Some(content_id) = part.header("Content-ID") {
+        out.push_str(&format!("Content-ID: {}\r\n", content_id));
+    }
+    out.push_str("\r\n");
+    out.push_str(&format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status.as_u16(),
+        response.status.canonical_reason().unwrap_or_default()
+    ));
+    for (name, value) in &response.headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    if let Some(body) = &response.body {
+        out.push_str(&String::from_utf8_lossy(body));
+    }
+    out.push_str("\r\n");
+
+    (out, succeeded)
+}
+
+/// Parses an `application/http` request.
+fn parse_request(content: &str) -> Result<(Method, Url, HeaderMap, Option<&str>), String> {
+    let (head, body) = content.split_once("\r\n\r\n").unwrap_or((content, ""));
+    let mut lines = head.lines();
+
+    let request_line = lines.next().unwrap_or_default();
+    let mut words = request_line.split_whitespace();
+    let (Some(method), Some(url)) = (words.next(), words.next()) else {
+        return Err(format!("Invalid request line: {}", request_line));
+    };
+    let method =
+        Method::from_bytes(method.as_bytes()).map_err(|_| format!("Invalid method: {}", method))?;
+    let url = Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.trim().as_bytes()),
+            HeaderValue::from_str(value.trim()),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+
+    let body = body.trim_end_matches("\r\n");
+    Ok((method, url, headers, (!body.is_empty()).then_some(body)))
+}
@@ -0,0 +1,391 @@
+//! `$filter` and `$orderby` evaluation for the mock backend.
+//!
+//! Supports the expressions [`Filter`](crate::api::query::Filter) generates:
+//! comparisons, `contains`/`startswith`/`endswith`, `and`, `or`, `not` and
+//! parentheses. String comparisons are case-insensitive, like Dataverse's
+//! default collation.
+
+use std::cmp::Ordering;
+
+use chrono::DateTime;
+use serde_json::Map;
+use serde_json::Value as Json;
+
+/// A parsed `$filter` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Compare(String, CompareOp, Literal),
+    Function(StringFunction, String, String),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StringFunction {
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Open,
+    Close,
+    Comma,
+}
+
+impl Expr {
+    /// Parses a `$filter` expression.
+    pub(crate) fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {:?} in $filter", token)),
+        }
+    }
+
+    /// Returns whether a record matches the expression.
+    pub(crate) fn matches(&self, record: &Map<String, Json>) -> bool {
+        match self {
+            Expr::Compare(field, op, literal) => compare(record.get(field), *op, literal),
+            Expr::Function(function, field, needle) => {
+                let Some(value) = record.get(field).and_then(Json::as_str) else {
+                    return false;
+                };
+                let value = value.to_lowercase();
+                let needle = needle.to_lowercase();
+                match function {
+                    StringFunction::Contains => value.contains(&needle),
+                    StringFunction::StartsWith => value.starts_with(&needle),
+                    StringFunction::EndsWith => value.ends_with(&needle),
+                }
+            }
+            Expr::And(left, right) => left.matches(record) && right.matches(record),
+            Expr::Or(left, right) => left.matches(record) || right.matches(record),
+            Expr::Not(inner) => !inner.matches(record),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled quote is an escaped quote
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            text.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string in $filter".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | ',' | '\'') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(word))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(format!(
+                "expected {:?}, found {:?} in $filter",
+                expected, other
+            )),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek_word("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.peek_word("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_word("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                self.expect(Token::Close)?;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) if self.tokens.get(self.pos) == Some(&Token::Open) => {
+                let function = match word.to_lowercase().as_str() {
+                    "contains" => StringFunction::Contains,
+                    "startswith" => StringFunction::StartsWith,
+                    "endswith" => StringFunction::EndsWith,
+                    _ => return Err(format!("unsupported function '{}' in $filter", word)),
+                };
+                self.expect(Token::Open)?;
+                let field = match self.next() {
+                    Some(Token::Word(field)) => field,
+                    other => return Err(format!("expected field, found {:?} in $filter", other)),
+                };
+                self.expect(Token::Comma)?;
+                let needle = match self.next() {
+                    Some(Token::Text(text)) => text,
+                    other => return Err(format!("expected string, found {:?} in $filter", other)),
+                };
+                self.expect(Token::Close)?;
+                Ok(Expr::Function(function, field, needle))
+            }
+            Some(Token::Word(field)) => {
+                let op = match self.next() {
+                    Some(Token::Word(op)) => match op.to_lowercase().as_str() {
+                        "eq" => CompareOp::Eq,
+                        "ne" => CompareOp::Ne,
+                        "gt" => CompareOp::Gt,
+                        "ge" => CompareOp::Ge,
+                        "lt" => CompareOp::Lt,
+                        "le" => CompareOp::Le,
+                        _ => return Err(format!("unsupported operator '{}' in $filter", op)),
+                    },
+                    other => {
+                        return Err(format!("expected operator, found {:?} in $filter", other));
+                    }
+                };
+                let literal = match self.next() {
+                    Some(Token::Text(text)) => Literal::Text(text),
+                    Some(Token::Word(word)) => parse_literal(&word),
+                    other => return Err(format!("expected value, found {:?} in $filter", other)),
+                };
+                Ok(Expr::Compare(field, op, literal))
+            }
+            other => Err(format!("unexpected {:?} in $filter", other)),
+        }
+    }
+}
+
+/// Parses an unquoted literal: `null`, booleans, numbers, GUIDs and dates.
+fn parse_literal(word: &str) -> Literal {
+    match word {
+        "null" => Literal::Null,
+        "true" => Literal::Bool(true),
+        "false" => Literal::Bool(false),
+        _ => word
+            .parse::<f64>()
+            .map(Literal::Number)
+            .unwrap_or_else(|_| Literal::Text(word.to_string())),
+    }
+}
+
+fn compare(value: Option<&Json>, op: CompareOp, literal: &Literal) -> bool {
+    let value = value.unwrap_or(&Json::Null);
+
+    if *literal == Literal::Null || value.is_null() {
+        let both_null = *literal == Literal::Null && value.is_null();
+        return match op {
+            CompareOp::Eq => both_null,
+            CompareOp::Ne => !both_null,
+            _ => false,
+        };
+    }
+
+    let ordering = match literal {
+        Literal::Null => None,
+        Literal::Bool(b) => value.as_bool().map(|v| v.cmp(b)),
+        Literal::Number(n) => value.as_f64().and_then(|v| v.partial_cmp(n)),
+        Literal::Text(text) => value.as_str().map(|v| compare_text(v, text)),
+    };
+
+    let Some(ordering) = ordering else {
+        return op == CompareOp::Ne;
+    };
+
+    match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+    }
+}
+
+/// Compares strings as dates if both are dates, otherwise case-insensitively.
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (
+        DateTime::parse_from_rfc3339(a),
+        DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Compares two field values for `$orderby`. Nulls sort first.
+pub(crate) fn compare_values(a: Option<&Json>, b: Option<&Json>) -> Ordering {
+    let a = a.unwrap_or(&Json::Null);
+    let b = b.unwrap_or(&Json::Null);
+    match (a, b) {
+        (Json::Null, Json::Null) => Ordering::Equal,
+        (Json::Null, _) => Ordering::Less,
+        (_, Json::Null) => Ordering::Greater,
+        (Json::Bool(a), Json::Bool(b)) => a.cmp(b),
+        (Json::Number(a), Json::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Json::String(a), Json::String(b)) => compare_text(a, b),
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::query::Filter;
+    use crate::api::query::odata::url::filter_to_odata;
+
+    fn record(value: Json) -> Map<String, Json> {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn matches(filter: Filter, value: Json) -> bool {
+        Expr::parse(&filter_to_odata(&filter))
+            .unwrap()
+            .matches(&record(value))
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let r = json!({ "name": "Contoso", "revenue": 1000, "active": true });
+        assert!(matches(Filter::eq("name", "contoso"), r.clone()));
+        assert!(matches(Filter::gt("revenue", 500), r.clone()));
+        assert!(!matches(Filter::lt("revenue", 500), r.clone()));
+        assert!(matches(Filter::eq("active", true), r.clone()));
+        assert!(matches(Filter::is_null("missing"), r.clone()));
+        assert!(matches(Filter::is_not_null("name"), r));
+    }
+
+    #[test]
+    fn test_string_functions_and_escaping() {
+        let r = json!({ "name": "O'Brien Ltd" });
+        assert!(matches(Filter::contains("name", "'brien"), r.clone()));
+        assert!(matches(Filter::starts_with("name", "o'b"), r.clone()));
+        assert!(!matches(Filter::ends_with("name", "inc"), r));
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let r = json!({ "statecode": 0, "revenue": 10 });
+        let filter = Filter::eq("statecode", 0).and_also(Filter::gt("revenue", 100));
+        assert!(!matches(filter.clone(), r.clone()));
+        assert!(matches(Filter::Not(Box::new(filter)), r.clone()));
+        assert!(matches(
+            Filter::or([Filter::eq("statecode", 1), Filter::le("revenue", 10)]),
+            r
+        ));
+    }
+
+    #[test]
+    fn test_dates() {
+        let r = json!({ "createdon": "2024-06-01T12:00:00Z" });
+        let filter = "createdon gt 2024-01-01T00:00:00+00:00";
+        assert!(Expr::parse(filter).unwrap().matches(&record(r)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("name eq 'unterminated").is_err());
+        assert!(Expr::parse("name like 'x'").is_err());
+        assert!(Expr::parse("(name eq 'x'").is_err());
+    }
+}
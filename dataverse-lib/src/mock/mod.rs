@@ -0,0 +1,3826 @@
+//! In-memory Dataverse backend for tests
+//!
+//! [`MockDataverse`] answers the Web API requests a [`DataverseClient`] makes
+//! from an in-memory record store, so code built on the client can be unit
+//! tested without a real environment. Requests still go through the client's
+//! normal paths (entity resolution, interceptors, retries, caching), only the
+//! HTTP round trip is replaced.
+//!
+//! Entities must be registered with [`MockEntity`] before use; their canned
+//! metadata is what the client sees when it resolves entity set names,
+//! primary keys and lookup fields.
+//!
+//! Supported:
+//!
+//! - create, retrieve, update, upsert and delete, including `If-Match` /
+//!   `If-None-Match` (`304 Not Modified` on retrieve) and
+//!   `Prefer: return=representation`
+//! - associate/disassociate and set/clear lookup
+//! - OData queries with `$select`, `$filter`, `$orderby`, `$top`, `$count`
+//!   and paging via `odata.maxpagesize`
+//! - related record queries over associated records
+//! - FetchXML queries with `<attribute>` columns, `top`, and paging by
+//!   `count`, `page` and paging cookie
+//! - change tracking with `Prefer: odata.track-changes` and delta links,
+//!   including deleted records
+//! - `x-ms-service-request-id` on every response and `x-ms-session-token` on
+//!   successful writes
+//! - batches, with changesets rolled back when an operation fails
+//! - file and image column uploads and downloads, and note attachment blocks
+//! - `CreateMultiple`, `UpdateMultiple` and `UpsertMultiple`, rolled back
+//!   when a target fails
+//! - the `SendEmail` action, `RetrievePrincipalAccess` and
+//!   `RetrieveTotalRecordCount`
+//! - queue routing with `AddToQueue`, `PickFromQueue`, `ReleaseToQueue` and
+//!   `RemoveFromQueue`
+//! - solution export, import and `PublishAllXml`
+//! - `ExportWordDocument`, returning the template's content as the
+//!   document, and `ExportToExcel`, returning the view's FetchXML as the
+//!   workbook
+//! - long-running actions answered with `202 Accepted` and polled to
+//!   completion
+//! - failing requests on demand with [`MockDataverse::fail_next`], to
+//!   exercise retries
+//! - `WhoAmI`, `RetrieveVersion` and entity/attribute/key metadata,
+//!   including state and status option sets
+//!
+//! FetchXML filters, joins, ordering and aggregation, `$apply` aggregation
+//! and `$expand` aren't supported; those FetchXML and `$apply` requests fail
+//! with `501 Not Implemented` and `$expand` is ignored.
+//!
+//! Requires the `test-util` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use dataverse_lib::mock::{MockDataverse, MockEntity};
+//! use dataverse_lib::model::{Entity, Record};
+//!
+//! let mock = MockDataverse::new()
+//!     .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"));
+//! let client = mock.client();
+//!
+//! let id = client
+//!     .create(Entity::set("accounts"), Record::new("account").set("name", "Contoso"))
+//!     .await?
+//!     .id()?;
+//!
+//! let accounts = client
+//!     .query(Entity::logical("account"))
+//!     .filter(Filter::eq("name", "Contoso"))
+//!     .execute(&client)
+//!     .await?;
+//! assert_eq!(accounts[0].id(), Some(id));
+//! assert_eq!(mock.records(Entity::set("accounts")).len(), 1);
+//! ```
+
+mod batch;
+mod filter;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde_json::Map;
+use serde_json::Value as Json;
+use serde_json::json;
+use url::Url;
+use uuid::Uuid;
+
+use self::filter::Expr;
+use crate::DataverseClient;
+use crate::api::AccessRights;
+use crate::auth::StaticTokenProvider;
+use crate::middleware::RequestInfo;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::metadata::AttributeType;
+
+/// URL of the environment [`MockDataverse::client`] connects to.
+pub const MOCK_URL: &str = "https://mock.crm.dynamics.com";
+
+/// Version `RetrieveVersion` reports.
+const MOCK_VERSION: &str = "9.2.0.0";
+
+/// Content type of file column uploads and downloads.
+const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Chunk size the mock asks chunked uploads to use (Dataverse's is 4 MiB).
+const UPLOAD_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Size of an image's thumbnail. The mock doesn't decode images, so the
+/// thumbnail is the first bytes of the upload.
+const THUMBNAIL_SIZE: usize = 16;
+
+/// Page size used when a query doesn't ask for one (Dataverse's default).
+const DEFAULT_PAGE_SIZE: usize = 5000;
+
+/// Highest `@odata.count` Dataverse reports.
+const COUNT_LIMIT: usize = 5000;
+
+// =============================================================================
+// Canned metadata
+// =============================================================================
+
+/// Metadata for an entity served by [`MockDataverse`].
+///
+/// The primary ID attribute (and primary name attribute, if set) are added as
+/// attributes automatically.
+///
+/// # Example
+///
+/// ```ignore
+/// let contact = MockEntity::new("contact", "contacts", "contactid")
+///     .primary_name("fullname")
+///     .attribute("emailaddress1", AttributeType::String)
+///     .lookup("parentcustomerid", "account");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockEntity {
+    logical_name: String,
+    entity_set_name: String,
+    primary_id_attribute: String,
+    primary_name_attribute: Option<String>,
+    attributes: Vec<MockAttribute>,
+    /// Status reasons as `(state, status)` pairs.
+    statuses: Vec<(i32, i32)>,
+    /// Alternate keys as `(name, columns)` pairs.
+    keys: Vec<(String, Vec<String>)>,
+    /// N:N relationships as `(schema name, target, navigation property)`.
+    many_to_many: Vec<(String, String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct MockAttribute {
+    logical_name: String,
+    attribute_type: AttributeType,
+    targets: Vec<String>,
+    can_store_full_image: bool,
+    is_secured: bool,
+    is_read_only: bool,
+    is_required: bool,
+}
+
+impl MockEntity {
+    /// Creates entity metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `logical_name` - The entity's logical name (e.g., `account`)
+    /// * `entity_set_name` - The entity set name used in URLs (e.g., `accounts`)
+    /// * `primary_id_attribute` - The primary key attribute (e.g., `accountid`)
+    pub fn new(
+        logical_name: impl Into<String>,
+        entity_set_name: impl Into<String>,
+        primary_id_attribute: impl Into<String>,
+    ) -> Self {
+        let primary_id_attribute = primary_id_attribute.into();
+        Self {
+            logical_name: logical_name.into(),
+            entity_set_name: entity_set_name.into(),
+            attributes: vec![MockAttribute {
+                logical_name: primary_id_attribute.clone(),
+                attribute_type: AttributeType::Uniqueidentifier,
+                targets: Vec::new(),
+                can_store_full_image: false,
+                is_secured: false,
+                is_read_only: false,
+                is_required: false,
+            }],
+            primary_id_attribute,
+            primary_name_attribute: None,
+            statuses: Vec::new(),
+            keys: Vec::new(),
+            many_to_many: Vec::new(),
+        }
+    }
+
+    /// Sets the primary name attribute, adding it as a string attribute.
+    pub fn primary_name(mut self, attribute: impl Into<String>) -> Self {
+        let attribute = attribute.into();
+        self.primary_name_attribute = Some(attribute.clone());
+        self.attribute(attribute, AttributeType::String)
+    }
+
+    /// Adds an attribute.
+    ///
+    /// Image attributes added this way can store full images.
+    pub fn attribute(
+        mut self,
+        logical_name: impl Into<String>,
+        attribute_type: AttributeType,
+    ) -> Self {
+        self.attributes.push(MockAttribute {
+            logical_name: logical_name.into(),
+            attribute_type,
+            targets: Vec::new(),
+            can_store_full_image: attribute_type == AttributeType::Image,
+            is_secured: false,
+            is_read_only: false,
+            is_required: false,
+        });
+        self
+    }
+
+    /// Adds an image attribute, choosing whether it keeps full-size images
+    /// or only thumbnails.
+    pub fn image(mut self, logical_name: impl Into<String>, can_store_full_image: bool) -> Self {
+        self.attributes.push(MockAttribute {
+            logical_name: logical_name.into(),
+            attribute_type: AttributeType::Image,
+            targets: Vec::new(),
+            can_store_full_image,
+            is_secured: false,
+            is_read_only: false,
+            is_required: false,
+        });
+        self
+    }
+
+    /// Adds a status reason belonging to `state`, adding the `statecode` and
+    /// `statuscode` attributes on first use.
+    ///
+    /// The first status added for a state is its default status.
+    pub fn status(mut self, state: i32, status: i32) -> Self {
+        if self.statuses.is_empty() {
+            self = self
+                .attribute("statecode", AttributeType::State)
+                .attribute("statuscode", AttributeType::Status);
+        }
+        self.statuses.push((state, status));
+        self
+    }
+
+    /// Adds an alternate key over `columns`, reported as active.
+    pub fn key(mut self, logical_name: impl Into<String>, columns: &[&str]) -> Self {
+        let columns = columns.iter().map(|c| c.to_string()).collect();
+        self.keys.push((logical_name.into(), columns));
+        self
+    }
+
+    /// Adds an N:N relationship with `target` (an entity logical name),
+    /// navigated from this entity through `navigation_property`.
+    ///
+    /// Only this entity's metadata lists the relationship. Associations made
+    /// through the navigation property can be queried from either side.
+    pub fn many_to_many(
+        mut self,
+        schema_name: impl Into<String>,
+        target: impl Into<String>,
+        navigation_property: impl Into<String>,
+    ) -> Self {
+        self.many_to_many.push((
+            schema_name.into(),
+            target.into(),
+            navigation_property.into(),
+        ));
+        self
+    }
+
+    /// Enables field security on an attribute added earlier.
+    pub fn secured(mut self, logical_name: &str) -> Self {
+        if let Some(attr) = self
+            .attributes
+            .iter_mut()
+            .find(|a| a.logical_name == logical_name)
+        {
+            attr.is_secured = true;
+        }
+        self
+    }
+
+    /// Makes an attribute added earlier invalid for create and update, like
+    /// system columns such as `createdon`.
+    pub fn read_only(mut self, logical_name: &str) -> Self {
+        if let Some(attr) = self
+            .attributes
+            .iter_mut()
+            .find(|a| a.logical_name == logical_name)
+        {
+            attr.is_read_only = true;
+        }
+        self
+    }
+
+    /// Makes an attribute added earlier business required.
+    pub fn required(mut self, logical_name: &str) -> Self {
+        if let Some(attr) = self
+            .attributes
+            .iter_mut()
+            .find(|a| a.logical_name == logical_name)
+        {
+            attr.is_required = true;
+        }
+        self
+    }
+
+    /// Returns whether `column` is a file or image attribute.
+    fn is_file_column(&self, column: &str) -> bool {
+        self.attributes.iter().any(|a| {
+            a.logical_name == column
+                && matches!(a.attribute_type, AttributeType::File | AttributeType::Image)
+        })
+    }
+
+    /// Returns `column`'s attribute if it is an image attribute.
+    fn image_column(&self, column: &str) -> Option<&MockAttribute> {
+        self.attributes
+            .iter()
+            .find(|a| a.logical_name == column && a.attribute_type == AttributeType::Image)
+    }
+
+    /// Adds a lookup attribute referencing `target` (an entity logical name).
+    ///
+    /// Queries selecting or filtering on the lookup are rewritten by the client
+    /// to `_<name>_value`, which is how the mock stores lookups.
+    pub fn lookup(mut self, logical_name: impl Into<String>, target: impl Into<String>) -> Self {
+        self.attributes.push(MockAttribute {
+            logical_name: logical_name.into(),
+            attribute_type: AttributeType::Lookup,
+            targets: vec![target.into()],
+            can_store_full_image: false,
+            is_secured: false,
+            is_read_only: false,
+            is_required: false,
+        });
+        self
+    }
+}
+
+/// A registered entity with its generated metadata IDs.
+#[derive(Debug, Clone)]
+struct EntityDef {
+    entity: MockEntity,
+    metadata_id: Uuid,
+    object_type_code: i32,
+}
+
+impl EntityDef {
+    fn metadata_json(&self, with_attributes: bool) -> Json {
+        let entity = &self.entity;
+        let mut json = json!({
+            "MetadataId": self.metadata_id,
+            "LogicalName": entity.logical_name,
+            "EntitySetName": entity.entity_set_name,
+            "SchemaName": entity.logical_name,
+            "PrimaryIdAttribute": entity.primary_id_attribute,
+            "PrimaryNameAttribute": entity.primary_name_attribute,
+            "ObjectTypeCode": self.object_type_code,
+            "IsIntersect": false,
+            "ChangeTrackingEnabled": true,
+            "IsValidForAdvancedFind": true,
+        });
+        if with_attributes {
+            json["Attributes"] = Json::Array(self.attributes_json());
+            json["OneToManyRelationships"] = json!([]);
+            json["ManyToOneRelationships"] = json!([]);
+            json["ManyToManyRelationships"] = Json::Array(self.many_to_many_json());
+        }
+        json
+    }
+
+    fn many_to_many_json(&self) -> Vec<Json> {
+        let entity = &self.entity;
+        entity
+            .many_to_many
+            .iter()
+            .map(|(schema_name, target, nav)| {
+                json!({
+                    "MetadataId": Uuid::new_v4(),
+                    "SchemaName": schema_name,
+                    "Entity1LogicalName": entity.logical_name,
+                    "Entity2LogicalName": target,
+                    "Entity1NavigationPropertyName": nav,
+                    "Entity2NavigationPropertyName": nav,
+                    "IntersectEntityName": schema_name.to_lowercase(),
+                })
+            })
+            .collect()
+    }
+
+    fn keys_json(&self) -> Vec<Json> {
+        let entity = &self.entity;
+        entity
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, (logical_name, columns))| {
+                let metadata_id = self.metadata_id.as_u128().wrapping_sub(i as u128 + 100);
+                json!({
+                    "MetadataId": Uuid::from_u128(metadata_id),
+                    "LogicalName": logical_name,
+                    "SchemaName": logical_name,
+                    "EntityLogicalName": entity.logical_name,
+                    "KeyAttributes": columns,
+                    "EntityKeyIndexStatus": "Active",
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the `statecode` attribute with its options, if the entity has
+    /// status reasons.
+    fn state_json(&self) -> Option<Json> {
+        let statuses = &self.entity.statuses;
+        let mut options: Vec<Json> = Vec::new();
+        for (state, status) in statuses {
+            if !options.iter().any(|o| o["Value"] == *state) {
+                options.push(json!({ "Value": state, "DefaultStatus": status }));
+            }
+        }
+        (!statuses.is_empty()).then(|| self.option_attribute_json("statecode", 1, options))
+    }
+
+    /// Returns the `statuscode` attribute with its options, if the entity has
+    /// status reasons.
+    fn status_json(&self) -> Option<Json> {
+        let statuses = &self.entity.statuses;
+        let options = statuses
+            .iter()
+            .map(|(state, status)| json!({ "Value": status, "State": state }))
+            .collect();
+        (!statuses.is_empty()).then(|| self.option_attribute_json("statuscode", 2, options))
+    }
+
+    fn option_attribute_json(&self, logical_name: &str, offset: u128, options: Vec<Json>) -> Json {
+        let metadata_id = self.metadata_id.as_u128().wrapping_sub(offset);
+        json!({
+            "MetadataId": Uuid::from_u128(metadata_id),
+            "LogicalName": logical_name,
+            "SchemaName": logical_name,
+            "EntityLogicalName": self.entity.logical_name,
+            "OptionSet": { "Options": options },
+        })
+    }
+
+    fn attributes_json(&self) -> Vec<Json> {
+        let entity = &self.entity;
+        entity
+            .attributes
+            .iter()
+            .enumerate()
+            .map(|(i, attr)| {
+                let metadata_id = self.metadata_id.as_u128().wrapping_add(i as u128 + 1);
+                let mut json = json!({
+                    "MetadataId": Uuid::from_u128(metadata_id),
+                    "LogicalName": attr.logical_name,
+                    "SchemaName": attr.logical_name,
+                    "AttributeType": attr.attribute_type,
+                    "EntityLogicalName": entity.logical_name,
+                    "IsPrimaryId": attr.logical_name == entity.primary_id_attribute,
+                    "IsPrimaryName": entity.primary_name_attribute.as_ref() == Some(&attr.logical_name),
+                    "IsValidForCreate": !attr.is_read_only,
+                    "IsValidForRead": true,
+                    "IsValidForUpdate": !attr.is_read_only,
+                    "IsSecured": attr.is_secured,
+                    "RequiredLevel": {
+                        "Value": if attr.is_required { "ApplicationRequired" } else { "None" },
+                    },
+                    "Targets": attr.targets,
+                });
+                if attr.attribute_type == AttributeType::Image {
+                    json["CanStoreFullImage"] = Json::Bool(attr.can_store_full_image);
+                    json["IsPrimaryImage"] = Json::Bool(false);
+                }
+                json
+            })
+            .collect()
+    }
+}
+
+// =============================================================================
+// Record store
+// =============================================================================
+
+#[derive(Debug, Clone)]
+struct StoredRecord {
+    id: Uuid,
+    fields: Map<String, Json>,
+    version: u64,
+}
+
+impl StoredRecord {
+    fn etag(&self) -> String {
+        format!("W/\"{}\"", self.version)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Association {
+    relationship: String,
+    entity_set: String,
+    id: Uuid,
+    target_set: String,
+    target_id: Uuid,
+}
+
+/// Content of a file or image column.
+#[derive(Debug, Clone)]
+struct StoredFile {
+    name: String,
+    data: Vec<u8>,
+}
+
+/// A chunked upload in progress, keyed by its session token.
+#[derive(Debug)]
+struct UploadSession {
+    entity_set: String,
+    id: Uuid,
+    column: String,
+    name: String,
+    data: Vec<u8>,
+}
+
+/// A note attachment block upload in progress, keyed by its continuation token.
+#[derive(Debug, Default)]
+struct NoteUpload {
+    /// Uploaded blocks by block id.
+    blocks: HashMap<String, Vec<u8>>,
+}
+
+/// Records, associations and files, restored as a unit when a changeset fails.
+#[derive(Debug, Clone, Default)]
+struct Store {
+    tables: HashMap<String, Vec<StoredRecord>>,
+    associations: Vec<Association>,
+    /// Keyed by entity set, record id and column.
+    files: HashMap<(String, Uuid, String), StoredFile>,
+    /// Deleted records as `(entity set, id, version)`, for delta queries.
+    deleted: Vec<(String, Uuid, u64)>,
+    version: u64,
+}
+
+/// An unbound action answered as a long-running operation.
+#[derive(Debug, Clone)]
+struct LongRunningAction {
+    polls: u32,
+    result: Json,
+}
+
+/// A long-running operation in progress.
+#[derive(Debug)]
+struct Operation {
+    action: LongRunningAction,
+    polled: u32,
+}
+
+#[derive(Debug)]
+struct MockState {
+    entities: Vec<EntityDef>,
+    store: Store,
+    uploads: HashMap<String, UploadSession>,
+    note_uploads: HashMap<String, NoteUpload>,
+    /// Rights granted with [`MockDataverse::grant_access`], keyed by
+    /// principal and record.
+    access: HashMap<(Uuid, Uuid), AccessRights>,
+    /// Solution files `ExportSolution` serves, by unique name.
+    solutions: HashMap<String, Vec<u8>>,
+    /// Solution files imported, in order.
+    imported_solutions: Vec<Vec<u8>>,
+    /// Long-running actions, by name.
+    long_running_actions: HashMap<String, LongRunningAction>,
+    /// Long-running operations started, by id.
+    operations: HashMap<Uuid, Operation>,
+    requests: Vec<RequestInfo>,
+    /// Operations received in batches, in order.
+    batch_operations: Vec<RequestInfo>,
+    /// Status to answer the next requests with, and how many.
+    failing: Option<(StatusCode, u32)>,
+    user_id: Uuid,
+    business_unit_id: Uuid,
+    organization_id: Uuid,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        Self {
+            entities: Vec::new(),
+            store: Store::default(),
+            uploads: HashMap::new(),
+            note_uploads: HashMap::new(),
+            access: HashMap::new(),
+            solutions: HashMap::new(),
+            imported_solutions: Vec::new(),
+            long_running_actions: HashMap::new(),
+            operations: HashMap::new(),
+            requests: Vec::new(),
+            batch_operations: Vec::new(),
+            failing: None,
+            user_id: Uuid::new_v4(),
+            business_unit_id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+        }
+    }
+}
+
+// =============================================================================
+// MockDataverse
+// =============================================================================
+
+/// An in-memory Dataverse environment.
+///
+/// Cheap to clone; clones share the same store. See the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct MockDataverse {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockDataverse {
+    /// Creates an empty environment with no entities.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an entity.
+    pub fn entity(self, entity: MockEntity) -> Self {
+        {
+            let mut state = self.lock();
+            let object_type_code = 10000 + state.entities.len() as i32;
+            state.entities.push(EntityDef {
+                entity,
+                metadata_id: Uuid::new_v4(),
+                object_type_code,
+            });
+        }
+        self
+    }
+
+    /// Creates a client backed by this environment.
+    ///
+    /// The client has caching and retries disabled so every call reaches the
+    /// store. Use [`DataverseClientBuilder::mock`](crate::DataverseClientBuilder::mock)
+    /// for other settings.
+    pub fn client(&self) -> DataverseClient {
+        DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .no_cache()
+            .no_retry()
+            .mock(self.clone())
+            .build()
+    }
+
+    /// Returns the user ID `WhoAmI` reports.
+    pub fn user_id(&self) -> Uuid {
+        self.lock().user_id
+    }
+
+    /// Returns the organization ID `WhoAmI` reports.
+    pub fn organization_id(&self) -> Uuid {
+        self.lock().organization_id
+    }
+
+    /// Sets the rights `RetrievePrincipalAccess` reports for a user or team
+    /// on a record. Principals have no access unless granted.
+    pub fn grant_access(&self, principal: Uuid, record: Uuid, rights: AccessRights) {
+        self.lock().access.insert((principal, record), rights);
+    }
+
+    /// Adds a solution `ExportSolution` can export, as its zip file.
+    pub fn add_solution(&self, name: impl Into<String>, file: Vec<u8>) {
+        self.lock().solutions.insert(name.into(), file);
+    }
+
+    /// Returns the solution files imported so far, in order.
+    pub fn imported_solutions(&self) -> Vec<Vec<u8>> {
+        self.lock().imported_solutions.clone()
+    }
+
+    /// Registers an unbound action that runs as a long-running operation.
+    ///
+    /// Calling it returns `202 Accepted` with an `Operation-Location`; the
+    /// operation reports itself running for `polls` polls, then completes
+    /// with `result`.
+    pub fn long_running_action(&self, name: impl Into<String>, polls: u32, result: Json) {
+        self.lock()
+            .long_running_actions
+            .insert(name.into(), LongRunningAction { polls, result });
+    }
+
+    /// Inserts a record directly, returning its ID.
+    ///
+    /// The record is stored as if it were created through the Web API, so
+    /// `@odata.bind` lookups are stored as `_<name>_value`. If the record has
+    /// no ID (neither [`Record::id`] nor its primary key field), one is generated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entity isn't registered.
+    pub fn insert(&self, entity: Entity, record: Record) -> Uuid {
+        let mut state = self.lock();
+        let def = state
+            .find_entity(&entity)
+            .unwrap_or_else(|| panic!("MockDataverse: entity '{}' is not registered", entity))
+            .clone();
+        let Ok(Json::Object(fields)) = serde_json::to_value(&record) else {
+            panic!("MockDataverse: record doesn't serialize to a JSON object");
+        };
+        let id = record
+            .id()
+            .or_else(|| primary_id(&def, &fields))
+            .unwrap_or_else(Uuid::new_v4);
+        state.insert(&def, id, fields);
+        id
+    }
+
+    /// Returns a stored record.
+    pub fn record(&self, entity: Entity, id: Uuid) -> Option<Record> {
+        let state = self.lock();
+        let def = state.find_entity(&entity)?;
+        state
+            .table(def)
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| to_record(def, r))
+    }
+
+    /// Returns all stored records of an entity, in insertion order.
+    pub fn records(&self, entity: Entity) -> Vec<Record> {
+        let state = self.lock();
+        let Some(def) = state.find_entity(&entity) else {
+            return Vec::new();
+        };
+        state.table(def).iter().map(|r| to_record(def, r)).collect()
+    }
+
+    /// Returns the IDs of records associated with a record through an N:N relationship.
+    pub fn associated(&self, entity: Entity, id: Uuid, relationship: &str) -> Vec<Uuid> {
+        let state = self.lock();
+        let Some(def) = state.find_entity(&entity) else {
+            return Vec::new();
+        };
+        state
+            .store
+            .associated(&def.entity.entity_set_name, id, relationship)
+            .into_iter()
+            .map(|(_, target_id)| target_id)
+            .collect()
+    }
+
+    /// Returns the name and content of the file stored in a file or image column.
+    pub fn file(&self, entity: Entity, id: Uuid, column: &str) -> Option<(String, Vec<u8>)> {
+        let state = self.lock();
+        let def = state.find_entity(&entity)?;
+        let key = (def.entity.entity_set_name.clone(), id, column.to_string());
+        state
+            .store
+            .files
+            .get(&key)
+            .map(|file| (file.name.clone(), file.data.clone()))
+    }
+
+    /// Returns the requests received so far, oldest first.
+    ///
+    /// A batch counts as one request.
+    pub fn requests(&self) -> Vec<RequestInfo> {
+        self.lock().requests.clone()
+    }
+
+    /// Returns the operations received in batches so far, oldest first.
+    pub fn batch_operations(&self) -> Vec<RequestInfo> {
+        self.lock().batch_operations.clone()
+    }
+
+    /// Answers the next `count` requests with `status` instead of handling
+    /// them, to exercise retries.
+    ///
+    /// A `429 Too Many Requests` comes with `Retry-After: 0`.
+    pub fn fail_next(&self, status: StatusCode, count: u32) {
+        self.lock().failing = (count > 0).then_some((status, count));
+    }
+
+    /// Answers a request from the client.
+    pub(crate) fn handle(&self, request: &RequestInfo, body: Option<&[u8]>) -> reqwest::Response {
+        log::debug!("MockDataverse::handle - {} {}", request.method, request.url);
+        let mut state = self.lock();
+        state.requests.push(request.clone());
+        if let Some((status, count)) = state.failing {
+            state.failing = (count > 1).then_some((status, count - 1));
+            let response = MockResponse::error(status, "0x80072321", "Injected failure.");
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return response
+                    .with_header("Retry-After", "0".to_string())
+                    .into_response();
+            }
+            return response.into_response();
+        }
+        let response = state
+            .dispatch(&request.method, &request.url, &request.headers, body)
+            .with_header("x-ms-service-request-id", Uuid::new_v4().to_string());
+        // Writes return a session token, as elastic tables do
+        if request.method != Method::GET && response.status.is_success() {
+            let token = format!("0:{}", state.store.version);
+            return response
+                .with_header("x-ms-session-token", token)
+                .into_response();
+        }
+        response.into_response()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+// =============================================================================
+// Request handling
+// =============================================================================
+
+/// A response produced by the mock.
+#[derive(Debug)]
+struct MockResponse {
+    status: StatusCode,
+    headers: Vec<(&'static str, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl MockResponse {
+    fn json(status: StatusCode, body: Json) -> Self {
+        Self {
+            status,
+            headers: vec![(
+                "Content-Type",
+                "application/json; odata.metadata=minimal".to_string(),
+            )],
+            body: Some(body.to_string().into_bytes()),
+        }
+    }
+
+    fn no_content() -> Self {
+        Self {
+            status: StatusCode::NO_CONTENT,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    fn error(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        Self::json(
+            status,
+            json!({ "error": { "code": code, "message": message.into() } }),
+        )
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self::error(StatusCode::NOT_FOUND, "0x80040217", message)
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::error(StatusCode::BAD_REQUEST, "0x80048d19", message)
+    }
+
+    fn precondition_failed() -> Self {
+        Self::error(
+            StatusCode::PRECONDITION_FAILED,
+            "0x80060882",
+            "The version of the existing record doesn't match the RowVersion property provided.",
+        )
+    }
+
+    fn not_implemented(message: impl Into<String>) -> Self {
+        Self::error(StatusCode::NOT_IMPLEMENTED, "0x8004d000", message)
+    }
+
+    fn bytes(status: StatusCode, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type", "application/octet-stream".to_string())],
+            body: Some(body),
+        }
+    }
+
+    fn with_header(mut self, name: &'static str, value: String) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    fn into_response(self) -> reqwest::Response {
+        let mut builder = hyper::Response::builder().status(self.status);
+        for (name, value) in self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(self.body.unwrap_or_default())
+            .map(reqwest::Response::from)
+            .expect("MockDataverse: invalid response")
+    }
+}
+
+/// A path segment like `accounts` or `accounts(<id>)`.
+fn parse_segment(segment: &str) -> (&str, Option<&str>) {
+    match segment.find('(') {
+        Some(open) if segment.ends_with(')') => (
+            &segment[..open],
+            Some(&segment[open + 1..segment.len() - 1]),
+        ),
+        _ => (segment, None),
+    }
+}
+
+/// Parses an alternate key like `accountnumber='A-1',revenue=5` into
+/// field/value pairs.
+fn parse_alternate_key(key: &str) -> Option<Vec<(String, Json)>> {
+    let mut pairs = Vec::new();
+    let mut rest = key;
+    while !rest.is_empty() {
+        let (name, after) = rest.split_once('=')?;
+        let (value, after) = match after.strip_prefix('\'') {
+            Some(quoted) => {
+                // `''` is an escaped quote
+                let mut value = String::new();
+                let mut chars = quoted.char_indices().peekable();
+                let end = loop {
+                    let (i, c) = chars.next()?;
+                    if c != '\'' {
+                        value.push(c);
+                    } else if chars.next_if(|(_, c)| *c == '\'').is_some() {
+                        value.push('\'');
+                    } else {
+                        break i + 1;
+                    }
+                };
+                (Json::String(value), &quoted[end..])
+            }
+            None => {
+                let end = after.find(',').unwrap_or(after.len());
+                let raw = &after[..end];
+                let value = serde_json::from_str(raw).unwrap_or_else(|_| Json::String(raw.into()));
+                (value, &after[end..])
+            }
+        };
+        pairs.push((name.trim().to_string(), value));
+        rest = match after.strip_prefix(',') {
+            Some(next) => next,
+            None if after.is_empty() => after,
+            None => return None,
+        };
+    }
+    (!pairs.is_empty()).then_some(pairs)
+}
+
+/// Splits a Web API URL path into the `/api/data/<version>/` prefix and the rest.
+fn split_api_path(url: &Url) -> Option<(String, String)> {
+    let path = urlencoding::decode(url.path()).ok()?.into_owned();
+    let rest = path.strip_prefix("/api/data/")?;
+    let (version, rest) = rest.split_once('/')?;
+    Some((format!("/api/data/{}/", version), rest.to_string()))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn return_representation(headers: &HeaderMap) -> bool {
+    header(headers, "Prefer").is_some_and(|v| v.contains("return=representation"))
+}
+
+fn query_params(url: &Url) -> HashMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+/// Reads an attribute from the start of an XML element, unescaping it.
+fn xml_attr(element: &str, name: &str) -> Option<String> {
+    let (_, rest) = element.split_once(&format!(" {}=\"", name))?;
+    let (value, _) = rest.split_once('"')?;
+    Some(
+        value
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Parses the `$select` parameter.
+fn select_param(params: &HashMap<String, String>) -> Option<Vec<String>> {
+    params
+        .get("$select")
+        .map(|s| s.split(',').map(|f| f.trim().to_string()).collect())
+}
+
+/// Returns the stand-in thumbnail for an image's content.
+fn thumbnail(data: &[u8]) -> &[u8] {
+    &data[..data.len().min(THUMBNAIL_SIZE)]
+}
+
+fn primary_id(def: &EntityDef, fields: &Map<String, Json>) -> Option<Uuid> {
+    fields
+        .get(&def.entity.primary_id_attribute)
+        .and_then(Json::as_str)
+        .and_then(|s| Uuid::parse_str(s).ok())
+}
+
+fn to_json(def: &EntityDef, record: &StoredRecord, select: Option<&[String]>) -> Json {
+    let mut json = Map::new();
+    json.insert("@odata.etag".to_string(), Json::String(record.etag()));
+    for (key, value) in &record.fields {
+        let field = key.split('@').next().unwrap_or(key);
+        let selected = match select {
+            None => true,
+            Some(select) => {
+                field == def.entity.primary_id_attribute || select.iter().any(|s| s == field)
+            }
+        };
+        if selected {
+            json.insert(key.clone(), value.clone());
+        }
+    }
+    Json::Object(json)
+}
+
+fn to_record(def: &EntityDef, stored: &StoredRecord) -> Record {
+    let mut record: Record = serde_json::from_value(to_json(def, stored, None))
+        .expect("MockDataverse: stored record doesn't deserialize");
+    record.set_entity(Entity::logical(&def.entity.logical_name));
+    record.set_id(stored.id);
+    record.mark_loaded();
+    record
+}
+
+impl Store {
+    fn associated(&self, entity_set: &str, id: Uuid, relationship: &str) -> Vec<(String, Uuid)> {
+        self.associations
+            .iter()
+            .filter(|a| a.relationship == relationship)
+            .filter_map(|a| {
+                if a.entity_set == entity_set && a.id == id {
+                    Some((a.target_set.clone(), a.target_id))
+                } else if a.target_set == entity_set && a.target_id == id {
+                    Some((a.entity_set.clone(), a.id))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl MockState {
+    fn find_entity(&self, entity: &Entity) -> Option<&EntityDef> {
+        self.entities.iter().find(|def| match entity {
+            Entity::Logical(name) => def.entity.logical_name == *name,
+            Entity::Set(name) => def.entity.entity_set_name == *name,
+        })
+    }
+
+    fn table(&self, def: &EntityDef) -> &[StoredRecord] {
+        self.store
+            .tables
+            .get(&def.entity.entity_set_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn table_mut(&mut self, def: &EntityDef) -> &mut Vec<StoredRecord> {
+        self.store
+            .tables
+            .entry(def.entity.entity_set_name.clone())
+            .or_default()
+    }
+
+    fn find_record(&self, def: &EntityDef, id: Uuid) -> Option<&StoredRecord> {
+        self.table(def).iter().find(|r| r.id == id)
+    }
+
+    fn find_by_key(&self, def: &EntityDef, pairs: &[(String, Json)]) -> Option<Uuid> {
+        self.table(def)
+            .iter()
+            .find(|r| pairs.iter().all(|(k, v)| r.fields.get(k) == Some(v)))
+            .map(|r| r.id)
+    }
+
+    fn next_version(&mut self) -> u64 {
+        self.store.version += 1;
+        self.store.version
+    }
+
+    /// Converts written fields (`@odata.bind` lookups) to stored fields.
+    fn write_fields(&self, fields: Map<String, Json>) -> Map<String, Json> {
+        let mut stored = Map::new();
+        for (key, value) in fields {
+            if key.starts_with("@odata") {
+                continue;
+            }
+            match key.strip_suffix("@odata.bind") {
+                Some(nav) => {
+                    let target = value.as_str().and_then(|v| self.parse_reference(v));
+                    self.write_lookup(&mut stored, nav, target);
+                }
+                None => {
+                    stored.insert(key, value);
+                }
+            }
+        }
+        stored
+    }
+
+    /// Sets (or clears) a lookup stored as `_<nav>_value`.
+    fn write_lookup(
+        &self,
+        fields: &mut Map<String, Json>,
+        nav: &str,
+        target: Option<(String, Uuid)>,
+    ) {
+        let key = format!("_{}_value", nav.to_lowercase());
+        let annotation = format!("{}@Microsoft.Dynamics.CRM.lookuplogicalname", key);
+        match target {
+            Some((target_set, target_id)) => {
+                fields.insert(key, Json::String(target_id.to_string()));
+                if let Some(def) = self.find_entity(&Entity::set(&target_set)) {
+                    fields.insert(annotation, Json::String(def.entity.logical_name.clone()));
+                }
+            }
+            None => {
+                fields.insert(key, Json::Null);
+                fields.remove(&annotation);
+            }
+        }
+    }
+
+    /// Parses a record reference like `/accounts(<id>)` or a full record URL.
+    fn parse_reference(&self, reference: &str) -> Option<(String, Uuid)> {
+        let segment = reference.trim_end_matches('/').rsplit('/').next()?;
+        let (set, key) = parse_segment(segment);
+        Some((set.to_string(), Uuid::parse_str(key?).ok()?))
+    }
+
+    fn insert(&mut self, def: &EntityDef, id: Uuid, fields: Map<String, Json>) -> &StoredRecord {
+        let mut fields = self.write_fields(fields);
+        fields.insert(
+            def.entity.primary_id_attribute.clone(),
+            Json::String(id.to_string()),
+        );
+        let version = self.next_version();
+        let table = self.table_mut(def);
+        table.push(StoredRecord {
+            id,
+            fields,
+            version,
+        });
+        table.last().expect("just pushed")
+    }
+
+    fn dispatch(
+        &mut self,
+        method: &Method,
+        url: &Url,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) -> MockResponse {
+        let Some((prefix, path)) = split_api_path(url) else {
+            return MockResponse::not_found(format!("Not a Web API URL: {}", url));
+        };
+
+        if path == "$batch" {
+            let body = body.map(String::from_utf8_lossy);
+            return batch::execute(self, headers, body.as_deref());
+        }
+        if path == "WhoAmI" || path == "WhoAmI()" {
+            return MockResponse::json(
+                StatusCode::OK,
+                json!({
+                    "UserId": self.user_id,
+                    "BusinessUnitId": self.business_unit_id,
+                    "OrganizationId": self.organization_id,
+                }),
+            );
+        }
+        if path == "RetrieveVersion()" {
+            return MockResponse::json(StatusCode::OK, json!({ "Version": MOCK_VERSION }));
+        }
+        if path.starts_with("RetrieveTotalRecordCount(") {
+            return self.total_record_count(&path, url);
+        }
+        if path.starts_with("EntityDefinitions") {
+            return self.metadata(&path);
+        }
+        if let Some(response) = self.annotation_action(&path, body) {
+            return response;
+        }
+        if let Some(response) = self.solution_action(&path, body) {
+            return response;
+        }
+        if let Some(response) = self.queue_action(&path, body) {
+            return response;
+        }
+        if let Some(response) = self.document_action(&path, body) {
+            return response;
+        }
+        if let Some(response) = self.long_running(method, url, &prefix, &path) {
+            return response;
+        }
+
+        let segments: Vec<&str> = path.split('/').collect();
+        let (entity_set, key) = parse_segment(segments[0]);
+        let Some(def) = self.find_entity(&Entity::set(entity_set)).cloned() else {
+            return MockResponse::not_found(format!(
+                "Resource not found for the segment '{}'.",
+                entity_set
+            ));
+        };
+
+        // File content goes to file columns as is
+        let raw = body;
+        let body = body.filter(|_| header(headers, "Content-Type") != Some(OCTET_STREAM));
+
+        let mut body = match body.map(serde_json::from_slice::<Json>) {
+            None => None,
+            Some(Ok(Json::Object(fields))) => Some(fields),
+            Some(_) => return MockResponse::bad_request("Request body must be a JSON object."),
+        };
+
+        let id = match key {
+            None => None,
+            Some(key) => match Uuid::parse_str(key) {
+                Ok(id) => Some(id),
+                Err(_) => {
+                    let Some(pairs) = parse_alternate_key(key) else {
+                        return MockResponse::bad_request(format!(
+                            "Invalid key segment: {}",
+                            segments[0]
+                        ));
+                    };
+                    match self.find_by_key(&def, &pairs) {
+                        Some(id) => Some(id),
+                        // Upserting by a key that doesn't exist creates a record with it
+                        None if *method == Method::PATCH && segments.len() == 1 => {
+                            body.get_or_insert_default().extend(pairs);
+                            Some(Uuid::new_v4())
+                        }
+                        None => {
+                            return MockResponse::not_found(format!(
+                                "{} With Key = {} Does Not Exist",
+                                def.entity.logical_name, key
+                            ));
+                        }
+                    }
+                }
+            },
+        };
+
+        match (id, &segments[1..]) {
+            (None, []) => match *method {
+                Method::GET => self.query(&def, self.table(&def).to_vec(), url, headers),
+                Method::POST => self.create(&def, body.unwrap_or_default(), url, &prefix, headers),
+                _ => MockResponse::not_implemented(format!("{} {}", method, path)),
+            },
+            (Some(id), []) => match *method {
+                Method::GET => self.retrieve(&def, id, url, headers),
+                Method::PATCH => {
+                    self.upsert(&def, id, body.unwrap_or_default(), url, &prefix, headers)
+                }
+                Method::DELETE => self.delete(&def, id, headers),
+                _ => MockResponse::not_implemented(format!("{} {}", method, path)),
+            },
+            (Some(id), [column]) if def.entity.is_file_column(column) => match *method {
+                Method::PATCH => self.upload_file(&def, id, column, url, headers, raw),
+                Method::DELETE => self.delete_file(&def, id, column),
+                _ => MockResponse::not_implemented(format!("{} {}", method, path)),
+            },
+            (Some(id), [column, "$value"]) if def.entity.is_file_column(column) => match *method {
+                Method::GET => self.download_file(&def, id, column, url, headers),
+                _ => MockResponse::not_implemented(format!("{} {}", method, path)),
+            },
+            (None, [message])
+                if *method == Method::POST
+                    && matches!(
+                        *message,
+                        "Microsoft.Dynamics.CRM.CreateMultiple"
+                            | "Microsoft.Dynamics.CRM.UpdateMultiple"
+                            | "Microsoft.Dynamics.CRM.UpsertMultiple"
+                    ) =>
+            {
+                self.bulk(&def, message, body.unwrap_or_default(), url, &prefix)
+            }
+            (Some(id), ["Microsoft.Dynamics.CRM.SendEmail"])
+                if *method == Method::POST && def.entity.logical_name == "email" =>
+            {
+                self.send_email(&def, id, body.unwrap_or_default())
+            }
+            (Some(id), ["Microsoft.Dynamics.CRM.AddToQueue"])
+                if *method == Method::POST && def.entity.logical_name == "queue" =>
+            {
+                self.add_to_queue(id, body.unwrap_or_default())
+            }
+            (Some(id), [call])
+                if *method == Method::GET
+                    && call.starts_with("Microsoft.Dynamics.CRM.RetrievePrincipalAccess(") =>
+            {
+                self.principal_access(id, call, url)
+            }
+            (Some(id), [nav]) if *method == Method::GET => {
+                self.related(&def, id, nav, url, headers)
+            }
+            (Some(id), [nav, "$ref"]) => {
+                let target = body
+                    .as_ref()
+                    .and_then(|b| b.get("@odata.id"))
+                    .and_then(Json::as_str)
+                    .and_then(|r| self.parse_reference(r));
+                self.reference(&def, id, nav, method, target)
+            }
+            _ => MockResponse::not_implemented(format!("{} {}", method, path)),
+        }
+    }
+
+    // =========================================================================
+    // CRUD
+    // =========================================================================
+
+    fn entity_id_url(url: &Url, prefix: &str, def: &EntityDef, id: Uuid) -> String {
+        let mut url = url.clone();
+        url.set_query(None);
+        url.set_path(&format!("{}{}({})", prefix, def.entity.entity_set_name, id));
+        url.to_string()
+    }
+
+    fn not_found_record(def: &EntityDef, id: Uuid) -> MockResponse {
+        MockResponse::not_found(format!(
+            "{} With Id = {} Does Not Exist",
+            def.entity.logical_name, id
+        ))
+    }
+
+    fn create(
+        &mut self,
+        def: &EntityDef,
+        fields: Map<String, Json>,
+        url: &Url,
+        prefix: &str,
+        headers: &HeaderMap,
+    ) -> MockResponse {
+        let id = primary_id(def, &fields).unwrap_or_else(Uuid::new_v4);
+        if self.find_record(def, id).is_some() {
+            return MockResponse::error(
+                StatusCode::PRECONDITION_FAILED,
+                "0x80040237",
+                "A record with matching key values already exists.",
+            );
+        }
+        self.created(
+            def,
+            id,
+            fields,
+            url,
+            prefix,
+            headers,
+            StatusCode::NO_CONTENT,
+        )
+    }
+
+    /// Inserts a record and builds the create response.
+    #[allow(clippy::too_many_arguments)]
+    fn created(
+        &mut self,
+        def: &EntityDef,
+        id: Uuid,
+        fields: Map<String, Json>,
+        url: &Url,
+        prefix: &str,
+        headers: &HeaderMap,
+        status: StatusCode,
+    ) -> MockResponse {
+        let entity_id = Self::entity_id_url(url, prefix, def, id);
+        let record = self.insert(def, id, fields);
+        let response = if return_representation(headers) {
+            let select = select_param(&query_params(url));
+            MockResponse::json(StatusCode::CREATED, to_json(def, record, select.as_deref()))
+        } else {
+            MockResponse {
+                status,
+                headers: Vec::new(),
+                body: None,
+            }
+        };
+        response.with_header("OData-EntityId", entity_id)
+    }
+
+    fn retrieve(&self, def: &EntityDef, id: Uuid, url: &Url, headers: &HeaderMap) -> MockResponse {
+        match self.find_record(def, id) {
+            Some(record) if header(headers, "If-None-Match") == Some(&record.etag()) => {
+                MockResponse {
+                    status: StatusCode::NOT_MODIFIED,
+                    headers: Vec::new(),
+                    body: None,
+                }
+            }
+            Some(record) => {
+                let select = select_param(&query_params(url));
+                MockResponse::json(StatusCode::OK, to_json(def, record, select.as_deref()))
+            }
+            None => Self::not_found_record(def, id),
+        }
+    }
+
+    /// Checks `If-Match` / `If-None-Match` against the current record.
+    fn check_preconditions(
+        record: Option<&StoredRecord>,
+        headers: &HeaderMap,
+    ) -> Result<(), MockResponse> {
+        if header(headers, "If-None-Match") == Some("*") && record.is_some() {
+            return Err(MockResponse::precondition_failed());
+        }
+        if let Some(etag) = header(headers, "If-Match") {
+            match record {
+                None => {
+                    return Err(MockResponse::not_found("The record doesn't exist."));
+                }
+                Some(record) if etag != "*" && etag != record.etag() => {
+                    return Err(MockResponse::precondition_failed());
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn upsert(
+        &mut self,
+        def: &EntityDef,
+        id: Uuid,
+        fields: Map<String, Json>,
+        url: &Url,
+        prefix: &str,
+        headers: &HeaderMap,
+    ) -> MockResponse {
+        if let Err(response) = Self::check_preconditions(self.find_record(def, id), headers) {
+            return response;
+        }
+
+        let Some(index) = self.table(def).iter().position(|r| r.id == id) else {
+            // Like Dataverse, only the representation says it was a create
+            return self.created(
+                def,
+                id,
+                fields,
+                url,
+                prefix,
+                headers,
+                StatusCode::NO_CONTENT,
+            );
+        };
+
+        let fields = self.write_fields(fields);
+        let version = self.next_version();
+        let record = &mut self.table_mut(def)[index];
+        for (key, value) in fields {
+            if key != def.entity.primary_id_attribute {
+                record.fields.insert(key, value);
+            }
+        }
+        record.version = version;
+
+        let response = if return_representation(headers) {
+            let select = select_param(&query_params(url));
+            MockResponse::json(StatusCode::OK, to_json(def, record, select.as_deref()))
+        } else {
+            MockResponse::no_content()
+        };
+        response.with_header("OData-EntityId", Self::entity_id_url(url, prefix, def, id))
+    }
+
+    fn delete(&mut self, def: &EntityDef, id: Uuid, headers: &HeaderMap) -> MockResponse {
+        let Some(record) = self.find_record(def, id) else {
+            return Self::not_found_record(def, id);
+        };
+        if let Err(response) = Self::check_preconditions(Some(record), headers) {
+            return response;
+        }
+
+        let entity_set = def.entity.entity_set_name.clone();
+        self.table_mut(def).retain(|r| r.id != id);
+        self.store.associations.retain(|a| {
+            !(a.entity_set == entity_set && a.id == id
+                || a.target_set == entity_set && a.target_id == id)
+        });
+        let version = self.next_version();
+        self.store.deleted.push((entity_set, id, version));
+        MockResponse::no_content()
+    }
+
+    /// Handles `CreateMultiple`, `UpdateMultiple` and `UpsertMultiple`. Like
+    /// Dataverse, nothing is written if any target fails.
+    fn bulk(
+        &mut self,
+        def: &EntityDef,
+        message: &str,
+        params: Map<String, Json>,
+        url: &Url,
+        prefix: &str,
+    ) -> MockResponse {
+        let Some(Json::Array(targets)) = params.get("Targets") else {
+            return MockResponse::bad_request("Targets is required.");
+        };
+        let snapshot = self.store.clone();
+        let headers = HeaderMap::new();
+        let primary_key = def.entity.primary_id_attribute.clone();
+        let mut ids = Vec::new();
+
+        for target in targets {
+            let Json::Object(mut fields) = target.clone() else {
+                self.store = snapshot;
+                return MockResponse::bad_request("Targets must be records.");
+            };
+            fields.remove("@odata.type");
+            let existing = primary_id(def, &fields);
+            let id = existing.unwrap_or_else(Uuid::new_v4);
+            fields.insert(primary_key.clone(), Json::from(id.to_string()));
+
+            let response = match (message, existing) {
+                ("Microsoft.Dynamics.CRM.UpdateMultiple", None) => MockResponse::bad_request(
+                    format!("UpdateMultiple targets need {}.", primary_key),
+                ),
+                ("Microsoft.Dynamics.CRM.UpdateMultiple", Some(id))
+                    if self.find_record(def, id).is_none() =>
+                {
+                    Self::not_found_record(def, id)
+                }
+                ("Microsoft.Dynamics.CRM.CreateMultiple", _) => {
+                    self.create(def, fields, url, prefix, &headers)
+                }
+                _ => self.upsert(def, id, fields, url, prefix, &headers),
+            };
+            if !response.status.is_success() {
+                self.store = snapshot;
+                return response;
+            }
+            ids.push(id);
+        }
+
+        if message == "Microsoft.Dynamics.CRM.UpdateMultiple" {
+            MockResponse::no_content()
+        } else {
+            MockResponse::json(StatusCode::OK, json!({ "Ids": ids }))
+        }
+    }
+
+    /// Handles `$ref` requests: associate, disassociate, set and clear lookup.
+    fn reference(
+        &mut self,
+        def: &EntityDef,
+        id: Uuid,
+        nav: &str,
+        method: &Method,
+        target: Option<(String, Uuid)>,
+    ) -> MockResponse {
+        let Some(index) = self.table(def).iter().position(|r| r.id == id) else {
+            return Self::not_found_record(def, id);
+        };
+        let entity_set = def.entity.entity_set_name.clone();
+        let (nav, nav_key) = parse_segment(nav);
+
+        match (method, nav_key) {
+            // Disassociate: DELETE /set(id)/relationship(target)/$ref
+            (&Method::DELETE, Some(target_id)) => {
+                let Ok(target_id) = Uuid::parse_str(target_id) else {
+                    return MockResponse::bad_request(format!("Invalid ID: {}", target_id));
+                };
+                self.store.associations.retain(|a| {
+                    !(a.relationship == nav
+                        && (a.entity_set == entity_set && a.id == id && a.target_id == target_id
+                            || a.target_set == entity_set
+                                && a.target_id == id
+                                && a.id == target_id))
+                });
+                MockResponse::no_content()
+            }
+            // Clear lookup: DELETE /set(id)/nav/$ref
+            (&Method::DELETE, None) => {
+                let mut fields = std::mem::take(&mut self.table_mut(def)[index].fields);
+                self.write_lookup(&mut fields, nav, None);
+                let version = self.next_version();
+                let record = &mut self.table_mut(def)[index];
+                record.fields = fields;
+                record.version = version;
+                MockResponse::no_content()
+            }
+            (&Method::POST | &Method::PUT, None) => {
+                let Some((target_set, target_id)) = target else {
+                    return MockResponse::bad_request("Missing or invalid @odata.id.");
+                };
+                let Some(target_def) = self.find_entity(&Entity::set(&target_set)).cloned() else {
+                    return MockResponse::not_found(format!(
+                        "Resource not found for the segment '{}'.",
+                        target_set
+                    ));
+                };
+                if self.find_record(&target_def, target_id).is_none() {
+                    return Self::not_found_record(&target_def, target_id);
+                }
+
+                if *method == Method::PUT {
+                    // Set lookup
+                    let mut fields = std::mem::take(&mut self.table_mut(def)[index].fields);
+                    self.write_lookup(&mut fields, nav, Some((target_set, target_id)));
+                    let version = self.next_version();
+                    let record = &mut self.table_mut(def)[index];
+                    record.fields = fields;
+                    record.version = version;
+                } else {
+                    // Associate
+                    let association = Association {
+                        relationship: nav.to_string(),
+                        entity_set,
+                        id,
+                        target_set,
+                        target_id,
+                    };
+                    if !self.store.associations.contains(&association) {
+                        self.store.associations.push(association);
+                    }
+                }
+                MockResponse::no_content()
+            }
+            _ => MockResponse::not_implemented(format!("{} {}/$ref", method, nav)),
+        }
+    }
+
+    // =========================================================================
+    // Queries
+    // =========================================================================
+
+    /// Queries records associated with a record: GET /set(id)/relationship.
+    fn related(
+        &self,
+        def: &EntityDef,
+        id: Uuid,
+        nav: &str,
+        url: &Url,
+        headers: &HeaderMap,
+    ) -> MockResponse {
+        if self.find_record(def, id).is_none() {
+            return Self::not_found_record(def, id);
+        }
+
+        let related = self.store.associated(&def.entity.entity_set_name, id, nav);
+        let Some((target_set, _)) = related.first() else {
+            return MockResponse::json(StatusCode::OK, json!({ "value": [] }));
+        };
+        let Some(target_def) = self.find_entity(&Entity::set(target_set)).cloned() else {
+            return MockResponse::json(StatusCode::OK, json!({ "value": [] }));
+        };
+        let rows = related
+            .iter()
+            .filter_map(|(_, target_id)| self.find_record(&target_def, *target_id))
+            .cloned()
+            .collect();
+        self.query(&target_def, rows, url, headers)
+    }
+
+    fn query(
+        &self,
+        def: &EntityDef,
+        mut rows: Vec<StoredRecord>,
+        url: &Url,
+        headers: &HeaderMap,
+    ) -> MockResponse {
+        let params = query_params(url);
+        if let Some(fetch_xml) = params.get("fetchXml") {
+            return Self::fetch_xml(def, rows, fetch_xml);
+        }
+        if params.contains_key("$apply") {
+            return MockResponse::not_implemented("$apply isn't supported by the mock.");
+        }
+
+        if let Some(filter) = params.get("$filter") {
+            match Expr::parse(filter) {
+                Ok(expr) => rows.retain(|r| expr.matches(&r.fields)),
+                Err(e) => return MockResponse::bad_request(e),
+            }
+        }
+
+        let delta_token = match params.get("$deltatoken").map(|t| t.parse::<u64>()) {
+            Some(Ok(token)) => Some(token),
+            Some(Err(_)) => return MockResponse::bad_request("Invalid $deltatoken."),
+            None => None,
+        };
+        let track_changes = delta_token.is_some()
+            || header(headers, "Prefer").is_some_and(|p| p.contains("odata.track-changes"));
+        if let Some(token) = delta_token {
+            rows.retain(|r| r.version > token);
+        }
+
+        if let Some(order_by) = params.get("$orderby") {
+            let order: Vec<(&str, bool)> = order_by
+                .split(',')
+                .map(|part| {
+                    let mut words = part.split_whitespace();
+                    let field = words.next().unwrap_or_default();
+                    let descending = words.next().is_some_and(|d| d.eq_ignore_ascii_case("desc"));
+                    (field, descending)
+                })
+                .collect();
+            rows.sort_by(|a, b| {
+                order
+                    .iter()
+                    .map(|(field, descending)| {
+                        let ordering =
+                            filter::compare_values(a.fields.get(*field), b.fields.get(*field));
+                        if *descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+
+        let count = rows.len();
+        if let Some(top) = params.get("$top").and_then(|t| t.parse().ok()) {
+            rows.truncate(top);
+        }
+
+        let page_size = header(headers, "Prefer")
+            .and_then(|prefer| {
+                prefer
+                    .split(',')
+                    .find_map(|p| p.trim().strip_prefix("odata.maxpagesize="))
+            })
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_PAGE_SIZE);
+        let skip: usize = params
+            .get("$skiptoken")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let end = (skip + page_size).min(rows.len());
+
+        let select = select_param(&params);
+        let value: Vec<Json> = rows
+            .get(skip..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|r| to_json(def, r, select.as_deref()))
+            .collect();
+
+        let mut body = json!({ "value": value });
+        if params.get("$count").is_some_and(|c| c == "true") {
+            body["@odata.count"] = json!(count.min(COUNT_LIMIT));
+            if count > COUNT_LIMIT {
+                body["@Microsoft.Dynamics.CRM.totalrecordcountlimitexceeded"] = json!(true);
+            }
+        }
+        if end < rows.len() {
+            let mut next = url.clone();
+            next.query_pairs_mut()
+                .clear()
+                .extend_pairs(params.iter().filter(|(k, _)| *k != "$skiptoken"))
+                .append_pair("$skiptoken", &end.to_string());
+            body["@odata.nextLink"] = Json::String(next.to_string());
+        } else if track_changes {
+            if let (Some(token), Json::Array(value)) = (delta_token, &mut body["value"]) {
+                let context = format!(
+                    "{}$metadata#{}/$deletedEntity",
+                    url.as_str()
+                        .split(&def.entity.entity_set_name)
+                        .next()
+                        .unwrap_or_default(),
+                    def.entity.entity_set_name
+                );
+                let deleted = self
+                    .store
+                    .deleted
+                    .iter()
+                    .filter(|(set, _, version)| *set == def.entity.entity_set_name && *version > token)
+                    .map(|(_, id, _)| {
+                        json!({ "@odata.context": context, "id": id, "reason": "deleted" })
+                    });
+                value.extend(deleted);
+            }
+            let mut delta = url.clone();
+            delta
+                .query_pairs_mut()
+                .clear()
+                .extend_pairs(
+                    params
+                        .iter()
+                        .filter(|(k, _)| *k != "$skiptoken" && *k != "$deltatoken"),
+                )
+                .append_pair("$deltatoken", &self.store.version.to_string());
+            body["@odata.deltaLink"] = Json::String(delta.to_string());
+        }
+        MockResponse::json(StatusCode::OK, body)
+    }
+
+    /// Runs a FetchXML query: `count`, `page` and `paging-cookie` paging,
+    /// `top` and `<attribute>` columns. Filters, joins, ordering and
+    /// aggregation aren't supported.
+    fn fetch_xml(def: &EntityDef, mut rows: Vec<StoredRecord>, fetch_xml: &str) -> MockResponse {
+        let unsupported = ["<filter", "<link-entity", "<order", r#"aggregate="true""#];
+        if unsupported.iter().any(|s| fetch_xml.contains(s)) {
+            return MockResponse::not_implemented(
+                "FetchXML filters, joins, ordering and aggregation aren't supported by the mock.",
+            );
+        }
+        let Some((fetch, _)) = fetch_xml.split_once('>') else {
+            return MockResponse::bad_request("Invalid FetchXML.");
+        };
+
+        if let Some(top) = xml_attr(fetch, "top").and_then(|t| t.parse().ok()) {
+            rows.truncate(top);
+        }
+        let count = xml_attr(fetch, "count")
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(DEFAULT_PAGE_SIZE);
+        let page: usize = xml_attr(fetch, "page")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(1);
+
+        // A paging cookie continues after the last record of the previous
+        // page; without one, the page number decides
+        let last = xml_attr(fetch, "paging-cookie")
+            .and_then(|cookie| xml_attr(&cookie, "last"))
+            .and_then(|id| Uuid::parse_str(id.trim_matches(['{', '}'])).ok());
+        let start = match last {
+            Some(last) => rows
+                .iter()
+                .position(|r| r.id == last)
+                .map_or(rows.len(), |i| i + 1),
+            None => (page.max(1) - 1) * count,
+        }
+        .min(rows.len());
+        let end = (start + count).min(rows.len());
+
+        let select: Vec<String> = fetch_xml
+            .split("<attribute ")
+            .skip(1)
+            .filter_map(|a| xml_attr(a, "name"))
+            .collect();
+        let select = (!select.is_empty()).then_some(select);
+        let page_rows = &rows[start..end];
+        let value: Vec<Json> = page_rows
+            .iter()
+            .map(|r| to_json(def, r, select.as_deref()))
+            .collect();
+
+        let mut body = json!({
+            "value": value,
+            "@Microsoft.Dynamics.CRM.morerecords": end < rows.len(),
+        });
+        if let (Some(first), Some(last)) = (page_rows.first(), page_rows.last()) {
+            body["@Microsoft.Dynamics.CRM.fetchxmlpagingcookie"] = json!(format!(
+                r#"<cookie page="{}"><{} last="{{{}}}" first="{{{}}}" /></cookie>"#,
+                page, def.entity.primary_id_attribute, last.id, first.id
+            ));
+        }
+        if xml_attr(fetch, "returntotalrecordcount").is_some_and(|r| r == "true") {
+            body["@Microsoft.Dynamics.CRM.totalrecordcount"] = json!(rows.len().min(COUNT_LIMIT));
+            body["@Microsoft.Dynamics.CRM.totalrecordcountlimitexceeded"] =
+                json!(rows.len() > COUNT_LIMIT);
+        }
+        MockResponse::json(StatusCode::OK, body)
+    }
+
+    /// Answers `RetrieveTotalRecordCount` with the current number of records.
+    fn total_record_count(&self, call: &str, url: &Url) -> MockResponse {
+        let names = call
+            .split_once("EntityNames=")
+            .map(|(_, alias)| alias.trim_end_matches(')'))
+            .and_then(|alias| url.query_pairs().find(|(k, _)| k == alias))
+            .and_then(|(_, v)| serde_json::from_str::<Vec<String>>(&v).ok());
+        let Some(names) = names else {
+            return MockResponse::bad_request("RetrieveTotalRecordCount needs EntityNames.");
+        };
+        let mut values = Vec::with_capacity(names.len());
+        for name in &names {
+            let Some(def) = self.find_entity(&Entity::logical(name)) else {
+                return MockResponse::not_found(format!("Entity '{}' not found.", name));
+            };
+            values.push(self.table(def).len());
+        }
+        MockResponse::json(
+            StatusCode::OK,
+            json!({
+                "EntityRecordCountCollection": {
+                    "Count": names.len(),
+                    "IsReadOnly": false,
+                    "Keys": names,
+                    "Values": values,
+                }
+            }),
+        )
+    }
+
+    // =========================================================================
+    // Files
+    // =========================================================================
+
+    /// Handles `PATCH /set(id)/column`: a single-request upload, the start of
+    /// a chunked upload (`x-ms-transfer-mode: chunked`), or one of its chunks
+    /// (`?sessiontoken=`).
+    fn upload_file(
+        &mut self,
+        def: &EntityDef,
+        id: Uuid,
+        column: &str,
+        url: &Url,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) -> MockResponse {
+        if self.find_record(def, id).is_none() {
+            return Self::not_found_record(def, id);
+        }
+        let params = query_params(url);
+        let file_name = header(headers, "x-ms-file-name")
+            .or(params.get("x-ms-file-name").map(String::as_str))
+            .map(str::to_string);
+
+        if let Some(token) = params.get("sessiontoken") {
+            return self.upload_chunk(def, id, column, token, headers, body.unwrap_or_default());
+        }
+
+        let Some(name) = file_name else {
+            return MockResponse::bad_request("Missing x-ms-file-name header.");
+        };
+
+        if header(headers, "x-ms-transfer-mode") == Some("chunked") {
+            let token = Uuid::new_v4().simple().to_string();
+            let mut location = url.clone();
+            location
+                .query_pairs_mut()
+                .clear()
+                .append_pair("x-ms-file-name", &name)
+                .append_pair("sessiontoken", &token);
+            self.uploads.insert(
+                token,
+                UploadSession {
+                    entity_set: def.entity.entity_set_name.clone(),
+                    id,
+                    column: column.to_string(),
+                    name,
+                    data: Vec::new(),
+                },
+            );
+            return MockResponse {
+                status: StatusCode::OK,
+                headers: Vec::new(),
+                body: None,
+            }
+            .with_header("Location", location.to_string())
+            .with_header("x-ms-chunk-size", UPLOAD_CHUNK_SIZE.to_string())
+            .with_header("Accept-Ranges", "bytes".to_string());
+        }
+
+        let data = body.unwrap_or_default().to_vec();
+        self.store_file(def, id, column, StoredFile { name, data });
+        MockResponse::no_content()
+    }
+
+    /// Appends a chunk to an upload session, storing the file after the last one.
+    fn upload_chunk(
+        &mut self,
+        def: &EntityDef,
+        id: Uuid,
+        column: &str,
+        token: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> MockResponse {
+        let session = self.uploads.get_mut(token).filter(|session| {
+            session.entity_set == def.entity.entity_set_name
+                && session.id == id
+                && session.column == column
+        });
+        let Some(session) = session else {
+            return MockResponse::bad_request("Invalid or expired upload session.");
+        };
+        // Content-Range: bytes <start>-<end>/<total>
+        let range = header(headers, "Content-Range")
+            .and_then(|r| r.strip_prefix("bytes "))
+            .and_then(|r| r.split_once('/'))
+            .and_then(|(span, total)| {
+                let (start, end) = span.split_once('-')?;
+                Some((
+                    start.parse::<usize>().ok()?,
+                    end.parse::<usize>().ok()?,
+                    total.parse::<usize>().ok()?,
+                ))
+            });
+        let Some((start, end, total)) = range else {
+            return MockResponse::bad_request("Missing or invalid Content-Range header.");
+        };
+        if start != session.data.len() || end + 1 - start != body.len() || end >= total {
+            return MockResponse::bad_request(format!(
+                "Unexpected chunk bytes {}-{}/{} after {} bytes.",
+                start,
+                end,
+                total,
+                session.data.len()
+            ));
+        }
+
+        session.data.extend_from_slice(body);
+        if session.data.len() < total {
+            return MockResponse {
+                status: StatusCode::PARTIAL_CONTENT,
+                headers: Vec::new(),
+                body: None,
+            };
+        }
+
+        let session = self.uploads.remove(token).expect("session exists");
+        let file = StoredFile {
+            name: session.name,
+            data: session.data,
+        };
+        self.store_file(def, id, column, file);
+        MockResponse::no_content()
+    }
+
+    /// Stores a file and points the record's column at it.
+    fn store_file(&mut self, def: &EntityDef, id: Uuid, column: &str, file: StoredFile) {
+        let name = file.name.clone();
+        let thumbnail = thumbnail(&file.data).to_vec();
+        let key = (def.entity.entity_set_name.clone(), id, column.to_string());
+        self.store.files.insert(key, file);
+
+        let is_image = def.entity.image_column(column).is_some();
+        let version = self.next_version();
+        if let Some(record) = self.table_mut(def).iter_mut().find(|r| r.id == id) {
+            if is_image {
+                // Image columns return the thumbnail inline, as base64
+                record
+                    .fields
+                    .insert(column.to_string(), Json::String(STANDARD.encode(thumbnail)));
+                record.fields.insert(
+                    format!("{}id", column),
+                    Json::String(Uuid::new_v4().to_string()),
+                );
+                record
+                    .fields
+                    .insert(format!("{}_timestamp", column), Json::from(version));
+            } else {
+                record
+                    .fields
+                    .insert(column.to_string(), Json::String(Uuid::new_v4().to_string()));
+                record
+                    .fields
+                    .insert(format!("{}_name", column), Json::String(name));
+            }
+            record.version = version;
+        }
+    }
+
+    /// Handles `DELETE /set(id)/column`.
+    fn delete_file(&mut self, def: &EntityDef, id: Uuid, column: &str) -> MockResponse {
+        let key = (def.entity.entity_set_name.clone(), id, column.to_string());
+        if self.store.files.remove(&key).is_none() {
+            return MockResponse::not_found(format!(
+                "No file attachment found for attribute: {}",
+                column
+            ));
+        }
+
+        let version = self.next_version();
+        if let Some(record) = self.table_mut(def).iter_mut().find(|r| r.id == id) {
+            record.fields.insert(column.to_string(), Json::Null);
+            record.fields.remove(&format!("{}_name", column));
+            record.fields.remove(&format!("{}id", column));
+            record.fields.remove(&format!("{}_timestamp", column));
+            record.version = version;
+        }
+        MockResponse::no_content()
+    }
+
+    /// Handles `GET /set(id)/column/$value`, honouring a `Range` header.
+    ///
+    /// Image columns return the thumbnail unless `size=full` is asked for.
+    fn download_file(
+        &self,
+        def: &EntityDef,
+        id: Uuid,
+        column: &str,
+        url: &Url,
+        headers: &HeaderMap,
+    ) -> MockResponse {
+        let key = (def.entity.entity_set_name.clone(), id, column.to_string());
+        let Some(file) = self.store.files.get(&key) else {
+            return MockResponse::not_found(format!(
+                "No file attachment found for attribute: {}",
+                column
+            ));
+        };
+        let full_size = query_params(url).get("size").map(String::as_str) == Some("full");
+        let data = match def.entity.image_column(column) {
+            Some(image) if full_size && !image.can_store_full_image => {
+                return MockResponse::bad_request(format!(
+                    "Attribute {} doesn't store full-size images.",
+                    column
+                ));
+            }
+            Some(_) if !full_size => thumbnail(&file.data),
+            _ => &file.data[..],
+        };
+        let size = data.len();
+
+        // Range: bytes=<start>-<end>
+        let range = header(headers, "Range")
+            .and_then(|r| r.strip_prefix("bytes="))
+            .and_then(|r| r.split_once('-'))
+            .and_then(|(start, end)| {
+                Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+            });
+
+        let response = match range {
+            Some((start, end)) if start < size => {
+                let end = end.min(size - 1);
+                MockResponse::bytes(StatusCode::PARTIAL_CONTENT, data[start..=end].to_vec())
+                    .with_header("Content-Range", format!("bytes {}-{}/{}", start, end, size))
+            }
+            Some(_) if size > 0 => {
+                return MockResponse::error(
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    "0x80090001",
+                    "The requested range is not satisfiable.",
+                );
+            }
+            _ => MockResponse::bytes(StatusCode::OK, data.to_vec()),
+        };
+        response
+            .with_header("x-ms-file-name", file.name.clone())
+            .with_header("x-ms-file-size", size.to_string())
+    }
+
+    /// Handles the bound `SendEmail` action: the email is marked as sent, or
+    /// as pending send when `IssueSend` is true.
+    fn send_email(&mut self, def: &EntityDef, id: Uuid, params: Map<String, Json>) -> MockResponse {
+        let issue_send = params
+            .get("IssueSend")
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let version = self.next_version();
+        let Some(record) = self.table_mut(def).iter_mut().find(|r| r.id == id) else {
+            return Self::not_found_record(def, id);
+        };
+        record.fields.insert("statecode".into(), Json::from(1));
+        record.fields.insert(
+            "statuscode".into(),
+            Json::from(if issue_send { 6 } else { 3 }),
+        );
+        record.version = version;
+        let subject = record.fields.get("subject").cloned().unwrap_or(Json::Null);
+        MockResponse::json(StatusCode::OK, json!({ "Subject": subject }))
+    }
+
+    // =========================================================================
+    // Queues
+    // =========================================================================
+
+    /// Handles `AddToQueue`, creating a queue item for the target and, with
+    /// a `SourceQueue`, removing the target's item from that queue. Requires
+    /// the `queueitem` entity to be registered.
+    fn add_to_queue(&mut self, queue: Uuid, params: Map<String, Json>) -> MockResponse {
+        let Some(def) = self.find_entity(&Entity::logical("queueitem")).cloned() else {
+            return MockResponse::not_found("Resource not found for the segment 'queueitem'.");
+        };
+        let Some(target) = params.get("Target").and_then(|t| self.action_reference(t)) else {
+            return MockResponse::bad_request("AddToQueue needs a Target.");
+        };
+        if let Some(source) = params
+            .get("SourceQueue")
+            .and_then(|q| self.action_reference(q))
+        {
+            self.table_mut(&def).retain(|r| {
+                !(r.fields.get("_queueid_value") == Some(&json!(source))
+                    && r.fields.get("_objectid_value") == Some(&json!(target)))
+            });
+        }
+
+        let mut fields = match params.get("QueueItemProperties") {
+            Some(Json::Object(properties)) => properties.clone(),
+            _ => Map::new(),
+        };
+        fields.insert("_queueid_value".into(), json!(queue));
+        fields.insert("_objectid_value".into(), json!(target));
+        let id = Uuid::new_v4();
+        self.insert(&def, id, fields);
+        MockResponse::json(StatusCode::OK, json!({ "QueueItemId": id }))
+    }
+
+    /// Handles `PickFromQueue`, `ReleaseToQueue` and `RemoveFromQueue`.
+    fn queue_action(&mut self, path: &str, body: Option<&[u8]>) -> Option<MockResponse> {
+        if !matches!(path, "PickFromQueue" | "ReleaseToQueue" | "RemoveFromQueue") {
+            return None;
+        }
+        let params = match body.map(serde_json::from_slice::<Json>) {
+            Some(Ok(Json::Object(params))) => params,
+            _ => {
+                return Some(MockResponse::bad_request(
+                    "Request body must be a JSON object.",
+                ));
+            }
+        };
+        let def = self.find_entity(&Entity::logical("queueitem")).cloned()?;
+        let Some(id) = params
+            .get("QueueItemId")
+            .and_then(Json::as_str)
+            .and_then(|id| Uuid::parse_str(id).ok())
+        else {
+            return Some(MockResponse::bad_request(format!(
+                "{} needs a QueueItemId.",
+                path
+            )));
+        };
+        let remove = path == "RemoveFromQueue"
+            || params.get("RemoveQueueItem").and_then(Json::as_bool) == Some(true);
+        let worker = match path {
+            "PickFromQueue" => params.get("WorkerId").cloned().unwrap_or(Json::Null),
+            _ => Json::Null,
+        };
+
+        let version = self.next_version();
+        let table = self.table_mut(&def);
+        let Some(item) = table.iter_mut().find(|r| r.id == id) else {
+            return Some(Self::not_found_record(&def, id));
+        };
+        if remove {
+            table.retain(|r| r.id != id);
+        } else {
+            item.fields.insert("_workerid_value".into(), worker);
+            item.version = version;
+        }
+        Some(MockResponse::no_content())
+    }
+
+    // =========================================================================
+    // Documents
+    // =========================================================================
+
+    /// Handles `ExportWordDocument` and `ExportToExcel`. The mock doesn't
+    /// render anything: a Word document is the template's `content` as is,
+    /// and a workbook is the exported FetchXML.
+    fn document_action(&self, path: &str, body: Option<&[u8]>) -> Option<MockResponse> {
+        if !matches!(path, "ExportWordDocument" | "ExportToExcel") {
+            return None;
+        }
+        let params = match body.map(serde_json::from_slice::<Json>) {
+            Some(Ok(Json::Object(params))) => params,
+            _ => {
+                return Some(MockResponse::bad_request(
+                    "Request body must be a JSON object.",
+                ));
+            }
+        };
+
+        if path == "ExportToExcel" {
+            let view = params.get("View").and_then(|v| self.action_reference(v));
+            let view_def = params["View"]["@odata.type"]
+                .as_str()
+                .and_then(|t| t.strip_prefix("Microsoft.Dynamics.CRM."))
+                .and_then(|name| self.find_entity(&Entity::logical(name)));
+            let (Some(view), Some(view_def)) = (view, view_def) else {
+                return Some(MockResponse::bad_request("ExportToExcel needs a View."));
+            };
+            if self.find_record(view_def, view).is_none() {
+                return Some(Self::not_found_record(view_def, view));
+            }
+            let Some(fetch_xml) = params.get("FetchXml").and_then(Json::as_str) else {
+                return Some(MockResponse::bad_request("ExportToExcel needs FetchXml."));
+            };
+            return Some(MockResponse::json(
+                StatusCode::OK,
+                json!({ "ExcelFile": STANDARD.encode(fetch_xml) }),
+            ));
+        }
+
+        let def = self.find_entity(&Entity::logical("documenttemplate"))?;
+        let Some(template) = params
+            .get("SelectedTemplate")
+            .and_then(|t| self.action_reference(t))
+        else {
+            return Some(MockResponse::bad_request(
+                "ExportWordDocument needs a SelectedTemplate.",
+            ));
+        };
+        let Some(template) = self.find_record(def, template) else {
+            return Some(Self::not_found_record(def, template));
+        };
+        let type_code = params.get("EntityTypeCode").and_then(Json::as_i64);
+        let Some(target) = self
+            .entities
+            .iter()
+            .find(|d| Some(d.object_type_code as i64) == type_code)
+        else {
+            return Some(MockResponse::bad_request(
+                "ExportWordDocument needs a valid EntityTypeCode.",
+            ));
+        };
+        let records = params
+            .get("SelectedRecords")
+            .and_then(Json::as_str)
+            .and_then(|records| serde_json::from_str::<Vec<String>>(records).ok())
+            .unwrap_or_default();
+        for record in &records {
+            let id = Uuid::parse_str(record.trim_matches(['{', '}'])).ok();
+            match id {
+                Some(id) if self.find_record(target, id).is_some() => {}
+                Some(id) => return Some(Self::not_found_record(target, id)),
+                None => {
+                    return Some(MockResponse::bad_request(format!(
+                        "Invalid record id '{}'.",
+                        record
+                    )));
+                }
+            }
+        }
+        let content = template.fields.get("content").cloned().unwrap_or(json!(""));
+        Some(MockResponse::json(
+            StatusCode::OK,
+            json!({ "WordFile": content }),
+        ))
+    }
+
+    /// Reads the id of an entity-typed action parameter, which carries its
+    /// primary key.
+    fn action_reference(&self, param: &Json) -> Option<Uuid> {
+        let logical_name = param["@odata.type"]
+            .as_str()?
+            .strip_prefix("Microsoft.Dynamics.CRM.")?;
+        let def = self.find_entity(&Entity::logical(logical_name))?;
+        param[def.entity.primary_id_attribute.as_str()]
+            .as_str()
+            .and_then(|id| Uuid::parse_str(id).ok())
+    }
+
+    // =========================================================================
+    // Access
+    // =========================================================================
+
+    /// Answers `RetrievePrincipalAccess` from the rights granted with
+    /// [`MockDataverse::grant_access`].
+    fn principal_access(&self, principal: Uuid, call: &str, url: &Url) -> MockResponse {
+        let target = call
+            .split_once("Target=")
+            .map(|(_, alias)| alias.trim_end_matches(')'))
+            .and_then(|alias| url.query_pairs().find(|(k, _)| k == alias))
+            .and_then(|(_, v)| serde_json::from_str::<Json>(&v).ok())
+            .and_then(|v| {
+                v["@odata.id"]
+                    .as_str()
+                    .and_then(|r| self.parse_reference(r))
+            });
+        let Some((entity_set, id)) = target else {
+            return MockResponse::bad_request("RetrievePrincipalAccess needs a Target.");
+        };
+        let Some(def) = self.find_entity(&Entity::set(&entity_set)) else {
+            return MockResponse::not_found(format!(
+                "Resource not found for the segment '{}'.",
+                entity_set
+            ));
+        };
+        if self.find_record(def, id).is_none() {
+            return Self::not_found_record(def, id);
+        }
+        let rights = self
+            .access
+            .get(&(principal, id))
+            .copied()
+            .unwrap_or_default();
+        MockResponse::json(StatusCode::OK, json!({ "AccessRights": rights.to_names() }))
+    }
+
+    // =========================================================================
+    // Solutions
+    // =========================================================================
+
+    /// Handles solution export, import and publishing.
+    ///
+    /// Imports complete immediately as a system job, which fails for empty
+    /// files. Requires the `asyncoperation` entity to be registered.
+    fn solution_action(&mut self, path: &str, body: Option<&[u8]>) -> Option<MockResponse> {
+        if !matches!(
+            path,
+            "ExportSolution" | "ImportSolutionAsync" | "StageAndUpgradeAsync" | "PublishAllXml"
+        ) {
+            return None;
+        }
+        if path == "PublishAllXml" {
+            return Some(MockResponse::no_content());
+        }
+        let params = match body.map(serde_json::from_slice::<Json>) {
+            Some(Ok(Json::Object(params))) => params,
+            _ => {
+                return Some(MockResponse::bad_request(
+                    "Request body must be a JSON object.",
+                ));
+            }
+        };
+
+        if path == "ExportSolution" {
+            let name = params
+                .get("SolutionName")
+                .and_then(Json::as_str)
+                .unwrap_or_default();
+            return Some(match self.solutions.get(name) {
+                Some(file) => MockResponse::json(
+                    StatusCode::OK,
+                    json!({ "ExportSolutionFile": STANDARD.encode(file) }),
+                ),
+                None => MockResponse::not_found(format!("The solution {} was not found.", name)),
+            });
+        }
+
+        let Some(def) = self
+            .find_entity(&Entity::logical("asyncoperation"))
+            .cloned()
+        else {
+            return Some(MockResponse::not_found(
+                "Resource not found for the segment 'asyncoperation'.",
+            ));
+        };
+        let file = params
+            .get("CustomizationFile")
+            .and_then(Json::as_str)
+            .and_then(|file| STANDARD.decode(file).ok())
+            .unwrap_or_default();
+        let mut fields = Map::new();
+        fields.insert("name".into(), Json::from(path));
+        fields.insert("statecode".into(), Json::from(3));
+        if file.is_empty() {
+            fields.insert("statuscode".into(), Json::from(31));
+            fields.insert(
+                "friendlymessage".into(),
+                Json::from("The solution file is invalid."),
+            );
+        } else {
+            fields.insert("statuscode".into(), Json::from(30));
+            self.imported_solutions.push(file);
+        }
+        let id = Uuid::new_v4();
+        self.insert(&def, id, fields);
+        Some(MockResponse::json(
+            StatusCode::OK,
+            json!({ "AsyncOperationId": id, "ImportJobKey": Uuid::new_v4() }),
+        ))
+    }
+
+    // =========================================================================
+    // Long-running operations
+    // =========================================================================
+
+    /// Starts and polls long-running actions registered with
+    /// [`MockDataverse::long_running_action`].
+    fn long_running(
+        &mut self,
+        method: &Method,
+        url: &Url,
+        prefix: &str,
+        path: &str,
+    ) -> Option<MockResponse> {
+        if let Some(action) = self.long_running_actions.get(path)
+            && *method == Method::POST
+        {
+            let id = Uuid::new_v4();
+            let operation = Operation {
+                action: action.clone(),
+                polled: 0,
+            };
+            self.operations.insert(id, operation);
+            let mut location = url.clone();
+            location.set_path(&format!("{}mock_operations({})", prefix, id));
+            location.set_query(None);
+            let response = MockResponse {
+                status: StatusCode::ACCEPTED,
+                headers: Vec::new(),
+                body: None,
+            };
+            return Some(response.with_header("Operation-Location", location.to_string()));
+        }
+
+        let (set, id) = parse_segment(path);
+        if set != "mock_operations" || *method != Method::GET {
+            return None;
+        }
+        let Some(operation) = id
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .and_then(|id| self.operations.get_mut(&id))
+        else {
+            return Some(MockResponse::not_found("Operation not found."));
+        };
+        if operation.polled >= operation.action.polls {
+            return Some(MockResponse::json(
+                StatusCode::OK,
+                operation.action.result.clone(),
+            ));
+        }
+        operation.polled += 1;
+        let percent = operation.polled * 100 / (operation.action.polls + 1);
+        Some(
+            MockResponse::json(
+                StatusCode::ACCEPTED,
+                json!({ "status": "Running", "percentComplete": percent }),
+            )
+            .with_header("Retry-After", "0".to_string()),
+        )
+    }
+
+    // =========================================================================
+    // Note attachments
+    // =========================================================================
+
+    /// Handles the annotation block upload and download actions, or returns
+    /// `None` for any other path.
+    fn annotation_action(&mut self, path: &str, body: Option<&[u8]>) -> Option<MockResponse> {
+        if !matches!(
+            path,
+            "InitializeAnnotationBlocksUpload"
+                | "UploadBlock"
+                | "CommitAnnotationBlocksUpload"
+                | "InitializeAnnotationBlocksDownload"
+                | "DownloadBlock"
+        ) {
+            return None;
+        }
+        let Some(def) = self.find_entity(&Entity::logical("annotation")).cloned() else {
+            return Some(MockResponse::not_found(
+                "Resource not found for the segment 'annotation'.",
+            ));
+        };
+        let params = match body.map(serde_json::from_slice::<Json>) {
+            Some(Ok(Json::Object(params))) => params,
+            _ => {
+                return Some(MockResponse::bad_request(
+                    "Request body must be a JSON object.",
+                ));
+            }
+        };
+        let token = params
+            .get("FileContinuationToken")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+
+        let response = match path {
+            "InitializeAnnotationBlocksUpload" => {
+                let token = Uuid::new_v4().simple().to_string();
+                self.note_uploads
+                    .insert(token.clone(), NoteUpload::default());
+                MockResponse::json(StatusCode::OK, json!({ "FileContinuationToken": token }))
+            }
+            "UploadBlock" => {
+                let Some(upload) = self.note_uploads.get_mut(token) else {
+                    return Some(MockResponse::bad_request(
+                        "Invalid or expired upload session.",
+                    ));
+                };
+                let block_id = params.get("BlockId").and_then(Json::as_str);
+                let data = params
+                    .get("BlockData")
+                    .and_then(Json::as_str)
+                    .and_then(|data| STANDARD.decode(data).ok());
+                let (Some(block_id), Some(data)) = (block_id, data) else {
+                    return Some(MockResponse::bad_request("Missing BlockId or BlockData."));
+                };
+                upload.blocks.insert(block_id.to_string(), data);
+                MockResponse::no_content()
+            }
+            "CommitAnnotationBlocksUpload" => {
+                let Some(mut upload) = self.note_uploads.remove(token) else {
+                    return Some(MockResponse::bad_request(
+                        "Invalid or expired upload session.",
+                    ));
+                };
+                let block_list = params.get("BlockList").and_then(Json::as_array);
+                let mut data = Vec::new();
+                for block_id in block_list.into_iter().flatten() {
+                    let block = block_id.as_str().and_then(|id| upload.blocks.remove(id));
+                    let Some(block) = block else {
+                        return Some(MockResponse::bad_request(format!(
+                            "Block {} wasn't uploaded.",
+                            block_id
+                        )));
+                    };
+                    data.extend(block);
+                }
+                let Some(Json::Object(mut fields)) = params.get("Target").cloned() else {
+                    return Some(MockResponse::bad_request("Missing Target."));
+                };
+                fields.insert("documentbody".into(), Json::String(STANDARD.encode(&data)));
+                fields.insert("filesize".into(), Json::from(data.len()));
+                fields.insert("isdocument".into(), Json::Bool(true));
+                let id = primary_id(&def, &fields).unwrap_or_else(Uuid::new_v4);
+                self.insert(&def, id, fields);
+                MockResponse::json(
+                    StatusCode::OK,
+                    json!({ "AnnotationId": id, "FileSizeInBytes": data.len() }),
+                )
+            }
+            "InitializeAnnotationBlocksDownload" => {
+                let id = params
+                    .get("Target")
+                    .and_then(|target| target.get("annotationid"))
+                    .and_then(Json::as_str)
+                    .and_then(|id| Uuid::parse_str(id).ok());
+                let Some(id) = id else {
+                    return Some(MockResponse::bad_request("Missing Target annotationid."));
+                };
+                // The note's id doubles as the continuation token
+                match self.note_document(&def, id) {
+                    Ok((data, name)) => MockResponse::json(
+                        StatusCode::OK,
+                        json!({
+                            "FileContinuationToken": id.to_string(),
+                            "FileSizeInBytes": data.len(),
+                            "FileName": name,
+                        }),
+                    ),
+                    Err(response) => response,
+                }
+            }
+            _ => {
+                let Ok(id) = Uuid::parse_str(token) else {
+                    return Some(MockResponse::bad_request("Invalid download session."));
+                };
+                let data = match self.note_document(&def, id) {
+                    Ok((data, _)) => data,
+                    Err(response) => return Some(response),
+                };
+                let offset = params.get("Offset").and_then(Json::as_u64).unwrap_or(0) as usize;
+                let len = params
+                    .get("BlockLength")
+                    .and_then(Json::as_u64)
+                    .unwrap_or(0) as usize;
+                let block = data
+                    .get(offset..(offset + len).min(data.len()))
+                    .unwrap_or_default();
+                MockResponse::json(StatusCode::OK, json!({ "Data": STANDARD.encode(block) }))
+            }
+        };
+        Some(response)
+    }
+
+    /// Returns a note's decoded attachment and file name.
+    fn note_document(
+        &self,
+        def: &EntityDef,
+        id: Uuid,
+    ) -> Result<(Vec<u8>, Option<String>), MockResponse> {
+        let Some(record) = self.find_record(def, id) else {
+            return Err(Self::not_found_record(def, id));
+        };
+        let data = record
+            .fields
+            .get("documentbody")
+            .and_then(Json::as_str)
+            .and_then(|body| STANDARD.decode(body).ok());
+        let Some(data) = data else {
+            return Err(MockResponse::bad_request("The note has no attachment."));
+        };
+        let name = record
+            .fields
+            .get("filename")
+            .and_then(Json::as_str)
+            .map(str::to_string);
+        Ok((data, name))
+    }
+
+    // =========================================================================
+    // Metadata
+    // =========================================================================
+
+    fn metadata(&self, path: &str) -> MockResponse {
+        let mut segments = path.split('/');
+        let (_, key) = parse_segment(segments.next().unwrap_or_default());
+
+        let Some(key) = key else {
+            let value: Vec<Json> = self
+                .entities
+                .iter()
+                .map(|def| def.metadata_json(false))
+                .collect();
+            return MockResponse::json(StatusCode::OK, json!({ "value": value }));
+        };
+
+        let logical_name = key
+            .strip_prefix("LogicalName='")
+            .and_then(|k| k.strip_suffix('\''))
+            .unwrap_or(key);
+        let Some(def) = self.find_entity(&Entity::logical(logical_name)) else {
+            return MockResponse::not_found(format!(
+                "Could not find entity with LogicalName '{}'.",
+                logical_name
+            ));
+        };
+
+        match (segments.next(), segments.next()) {
+            (None, _) => MockResponse::json(StatusCode::OK, def.metadata_json(true)),
+            (Some("Keys"), None) => {
+                MockResponse::json(StatusCode::OK, json!({ "value": def.keys_json() }))
+            }
+            (Some(attributes), None) => {
+                let (_, key) = parse_segment(attributes);
+                let attributes = def.attributes_json();
+                match key {
+                    None => MockResponse::json(StatusCode::OK, json!({ "value": attributes })),
+                    Some(key) => {
+                        let name = key
+                            .strip_prefix("LogicalName='")
+                            .and_then(|k| k.strip_suffix('\''))
+                            .unwrap_or(key);
+                        match attributes.into_iter().find(|a| a["LogicalName"] == name) {
+                            Some(attribute) => MockResponse::json(StatusCode::OK, attribute),
+                            None => MockResponse::not_found(format!(
+                                "Could not find attribute with LogicalName '{}'.",
+                                name
+                            )),
+                        }
+                    }
+                }
+            }
+            // Typed attribute casts: state and status, the mock has no picklists
+            (Some(_), Some(cast)) => {
+                let value = match cast {
+                    "Microsoft.Dynamics.CRM.StateAttributeMetadata" => def.state_json(),
+                    "Microsoft.Dynamics.CRM.StatusAttributeMetadata" => def.status_json(),
+                    _ => None,
+                };
+                MockResponse::json(
+                    StatusCode::OK,
+                    json!({ "value": value.into_iter().collect::<Vec<_>>() }),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BatchOperationResult;
+    use crate::api::Impersonation;
+    use crate::api::Op;
+    use crate::api::Owner;
+    use crate::api::RecordKey;
+    use crate::api::query::Filter;
+    use crate::api::query::OrderBy;
+    use crate::cache::InMemoryCache;
+    use crate::error::Error;
+    use crate::model::Value;
+    use crate::model::metadata::validation_codes;
+    use crate::model::types::EntityBinding;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(
+                MockEntity::new("account", "accounts", "accountid")
+                    .primary_name("name")
+                    .attribute("revenue", AttributeType::Money),
+            )
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_crud_round_trip() {
+        let mock = mock();
+        let client = mock.client();
+
+        let id = client
+            .create(
+                Entity::logical("account"),
+                Record::new("account").set("name", "Contoso"),
+            )
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+
+        let record = client
+            .retrieve(Entity::set("accounts"), id)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(record.get_string("name").unwrap(), Some("Contoso"));
+
+        client
+            .update(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Fabrikam"),
+            )
+            .await
+            .unwrap();
+        let stored = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(stored.get_string("name").unwrap(), Some("Fabrikam"));
+
+        client.delete(Entity::set("accounts"), id).await.unwrap();
+        assert!(mock.records(Entity::set("accounts")).is_empty());
+        assert!(client.retrieve(Entity::set("accounts"), id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_sends_only_changed_fields() {
+        let mock = mock();
+        let client = mock.client();
+        let record = Record::new("account")
+            .set("name", "Contoso")
+            .set("telephone1", "555-0100");
+        let id = client
+            .create(Entity::logical("account"), record)
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+
+        let mut stale = client
+            .retrieve(Entity::set("accounts"), id)
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!stale.has_changes());
+
+        // Nothing changed, so nothing is sent
+        let requests = mock.requests().len();
+        let result = client
+            .update(Entity::set("accounts"), id, stale.clone())
+            .await;
+        assert!(matches!(result, Err(Error::InvalidOperation(_))));
+        let result = client
+            .batch()
+            .add(Op::update(Entity::set("accounts"), id, stale.clone()))
+            .execute()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidOperation(_))));
+        assert_eq!(mock.requests().len(), requests);
+
+        // Someone else changes the phone number in the meantime
+        client
+            .update(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("telephone1", "555-0199"),
+            )
+            .await
+            .unwrap();
+
+        stale.insert("name", "Fabrikam");
+        client
+            .update(Entity::set("accounts"), id, stale.clone())
+            .await
+            .unwrap();
+        let stored = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(stored.get_string("name").unwrap(), Some("Fabrikam"));
+        assert_eq!(stored.get_string("telephone1").unwrap(), Some("555-0199"));
+
+        // The full payload overwrites the other change
+        client
+            .update(Entity::set("accounts"), id, stale)
+            .full_payload()
+            .await
+            .unwrap();
+        let stored = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(stored.get_string("telephone1").unwrap(), Some("555-0100"));
+    }
+
+    #[tokio::test]
+    async fn test_elastic_table_partitions_and_session_tokens() {
+        let mock = mock();
+        let client = mock.client();
+        let id = Uuid::new_v4();
+        assert_eq!(client.session_token(), None);
+
+        client
+            .upsert(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Contoso"),
+            )
+            .partition_id("tenant-1")
+            .await
+            .unwrap();
+        let stored = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(stored.get_string("partitionid").unwrap(), Some("tenant-1"));
+        let token = client.session_token().unwrap();
+
+        client
+            .retrieve(Entity::set("accounts"), id)
+            .partition_id("tenant 1")
+            .session_token(token.clone())
+            .await
+            .unwrap();
+        let request = mock.requests().pop().unwrap();
+        assert_eq!(request.url.query(), Some("partitionId=tenant%201"));
+        assert_eq!(
+            request
+                .headers
+                .get("MSCRM.SessionToken")
+                .and_then(|v| v.to_str().ok()),
+            Some(token.as_str())
+        );
+
+        client
+            .delete(Entity::set("accounts"), id)
+            .partition_id("tenant-1")
+            .await
+            .unwrap();
+        let request = mock.requests().pop().unwrap();
+        assert_eq!(request.url.query(), Some("partitionId=tenant-1"));
+    }
+
+    #[tokio::test]
+    async fn test_query_option_and_header_passthrough() {
+        let mock = mock();
+        let client = mock.client();
+        let id = client
+            .create(
+                Entity::set("accounts"),
+                Record::new("account").set("name", "Contoso"),
+            )
+            .query_option("tag", "seed run")
+            .header("MSCRM.SolutionUniqueName", "core")
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+        let request = mock.requests().pop().unwrap();
+        assert_eq!(request.url.query(), Some("tag=seed%20run"));
+        assert_eq!(
+            request
+                .headers
+                .get("MSCRM.SolutionUniqueName")
+                .and_then(|v| v.to_str().ok()),
+            Some("core")
+        );
+
+        let err = client
+            .retrieve(Entity::set("accounts"), id)
+            .select(&["name"])
+            .query_option("$select", "revenue")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidOperation(_)));
+
+        let err = client
+            .query(Entity::logical("account"))
+            .header("Prefer", "odata.maxpagesize=1")
+            .execute(&client)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidOperation(_)));
+
+        let records = client
+            .query(Entity::logical("account"))
+            .query_option("tag", "report")
+            .header("X-Trace", "1")
+            .execute(&client)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        let request = mock.requests().pop().unwrap();
+        assert!(request.url.query().unwrap().ends_with("tag=report"));
+        assert!(request.headers.contains_key("x-trace"));
+    }
+
+    fn caller(request: &RequestInfo) -> (Option<&str>, Option<&str>) {
+        let header = |name| request.headers.get(name).and_then(|v| v.to_str().ok());
+        (header("CallerObjectId"), header("MSCRMCallerID"))
+    }
+
+    #[tokio::test]
+    async fn test_impersonation_headers() {
+        let mock = mock();
+        let owner = Uuid::from_u128(1);
+        let user = Uuid::from_u128(2);
+        let client = mock.client().impersonate(owner);
+        let owner_header = (Some("00000000-0000-0000-0000-000000000001"), None);
+        let user_header = (None, Some("00000000-0000-0000-0000-000000000002"));
+
+        // CRUD
+        let id = client
+            .create(
+                Entity::set("accounts"),
+                Record::new("account").set("name", "Contoso"),
+            )
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), owner_header);
+        client
+            .update(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Contoso Ltd"),
+            )
+            .impersonate(Impersonation::SystemUserId(user))
+            .await
+            .unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), user_header);
+
+        // OData query
+        client
+            .query(Entity::logical("account"))
+            .execute(&client)
+            .await
+            .unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), owner_header);
+        client
+            .query(Entity::logical("account"))
+            .impersonate(Impersonation::SystemUserId(user))
+            .execute(&client)
+            .await
+            .unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), user_header);
+
+        // FetchXML query
+        let fetch = || client.fetch(Entity::logical("account")).select(&["name"]);
+        fetch().execute().await.unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), owner_header);
+        fetch()
+            .impersonate(Impersonation::SystemUserId(user))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(caller(&mock.requests().pop().unwrap()), user_header);
+
+        // Batch: the batch runs as the client's user, operations as their own
+        let result = client
+            .batch()
+            .add(Op::retrieve(Entity::set("accounts"), id))
+            .add(
+                Op::retrieve(Entity::set("accounts"), id)
+                    .impersonate(Impersonation::SystemUserId(user)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        assert!(result.all_succeeded());
+        assert_eq!(caller(&mock.requests().pop().unwrap()), owner_header);
+        let operations = mock.batch_operations();
+        assert_eq!(caller(&operations[0]), (None, None));
+        assert_eq!(caller(&operations[1]), user_header);
+    }
+
+    #[tokio::test]
+    async fn test_alternate_keys() {
+        let mock = mock();
+        let client = mock.client();
+        let key = RecordKey::alternate([("name", "O'Neil & Co")]);
+
+        assert!(
+            client
+                .retrieve(Entity::set("accounts"), key.clone())
+                .await
+                .is_err()
+        );
+
+        let id = client
+            .upsert(
+                Entity::set("accounts"),
+                key.clone(),
+                Record::new("account").set("revenue", 5),
+            )
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+        let stored = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(stored.get_string("name").unwrap(), Some("O'Neil & Co"));
+
+        let record = client
+            .retrieve(Entity::set("accounts"), key.clone())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(record.get_int("revenue").unwrap(), Some(5));
+
+        client.delete(Entity::set("accounts"), key).await.unwrap();
+        assert!(mock.records(Entity::set("accounts")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_alternate_keys_checked_against_metadata() {
+        let mock = MockDataverse::new().entity(
+            MockEntity::new("account", "accounts", "accountid")
+                .primary_name("name")
+                .attribute("accountnumber", AttributeType::String)
+                .key("account_number_key", &["accountnumber"]),
+        );
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .cache(InMemoryCache::new())
+            .no_retry()
+            .mock(mock.clone())
+            .build();
+        mock.insert(
+            Entity::set("accounts"),
+            Record::new("account")
+                .set("name", "Contoso")
+                .set("accountnumber", "A-1"),
+        );
+        let by_name = RecordKey::alternate([("name", "Contoso")]);
+
+        // Unchecked until the keys are known
+        assert!(
+            client
+                .retrieve(Entity::set("accounts"), by_name.clone())
+                .await
+                .is_ok()
+        );
+
+        let keys = client.metadata().keys("account").await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].is_active());
+        assert_eq!(keys[0].key_attributes, ["accountnumber"]);
+
+        let requests = mock.requests().len();
+        let error = client
+            .retrieve(Entity::set("accounts"), by_name)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Validation { .. }));
+        assert_eq!(mock.requests().len(), requests);
+
+        let by_number = RecordKey::alternate([("accountnumber", "A-1")]);
+        assert!(
+            client
+                .retrieve(Entity::set("accounts"), by_number)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upsert_reports_created_or_updated() {
+        let mock = mock();
+        let client = mock.client();
+        let id = Uuid::new_v4();
+
+        let created = client
+            .upsert(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Contoso"),
+            )
+            .await
+            .unwrap();
+        assert!(created.is_created());
+        assert_eq!(created.id().unwrap(), id);
+
+        let updated = client
+            .upsert(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Fabrikam"),
+            )
+            .await
+            .unwrap();
+        assert!(updated.is_updated());
+
+        let missing = client
+            .upsert(
+                Entity::set("accounts"),
+                Uuid::new_v4(),
+                Record::new("account"),
+            )
+            .if_match_any()
+            .await;
+        assert!(missing.is_err());
+        assert_eq!(mock.records(Entity::set("accounts")).len(), 1);
+
+        let etag = mock
+            .record(Entity::set("accounts"), id)
+            .unwrap()
+            .etag()
+            .map(str::to_string);
+        let result = client
+            .batch()
+            .add(
+                Op::upsert(Entity::set("accounts"), id, Record::new("account"))
+                    .if_match(etag.unwrap()),
+            )
+            .add(
+                Op::upsert(
+                    Entity::set("accounts"),
+                    Uuid::new_v4(),
+                    Record::new("account"),
+                )
+                .if_none_match()
+                .return_record(),
+            )
+            .add(
+                Op::upsert(Entity::set("accounts"), id, Record::new("account")).if_match("W/\"0\""),
+            )
+            .continue_on_error()
+            .execute()
+            .await
+            .unwrap();
+        assert!(matches!(
+            result.operation(0),
+            Some(Ok(BatchOperationResult::Upserted { created: false, id: updated, .. })) if *updated == id
+        ));
+        assert!(matches!(
+            result.operation(1),
+            Some(Ok(BatchOperationResult::Upserted { created: true, .. }))
+        ));
+        assert!(matches!(result.operation(2), Some(Err(_))));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_headers() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+
+        let created = client
+            .upsert(Entity::set("accounts"), id, Record::new("account"))
+            .if_none_match()
+            .await;
+        assert!(created.is_err());
+
+        let stale = client
+            .update(Entity::set("accounts"), id, Record::new("account"))
+            .if_match("W/\"0\"")
+            .await;
+        assert!(stale.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete_if_unmodified() {
+        let mock = mock();
+        let client = mock.client();
+        let id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let retrieve = || async {
+            client
+                .retrieve(Entity::set("accounts"), id)
+                .await
+                .unwrap()
+                .into_inner()
+        };
+
+        let mut record = retrieve().await;
+        let mut stale = record.clone();
+        record.insert("name", "Fabrikam");
+        client.update_if_unmodified(&mut record).await.unwrap();
+        assert!(!record.has_changes());
+        let current = retrieve().await.etag().map(str::to_string);
+        assert_eq!(record.etag().map(str::to_string), current);
+
+        stale.insert("name", "Northwind");
+        let error = client.update_if_unmodified(&mut stale).await.unwrap_err();
+        assert!(matches!(error, Error::Concurrency { current_etag } if current_etag == current));
+        assert!(matches!(
+            client.delete_if_unmodified(&stale).await,
+            Err(Error::Concurrency { .. })
+        ));
+
+        client.delete_if_unmodified(&record).await.unwrap();
+        assert!(mock.records(Entity::set("accounts")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_retrieve() {
+        let mock = mock();
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .cache(InMemoryCache::new())
+            .no_retry()
+            .mock(mock.clone())
+            .build();
+        let id = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+
+        let plain = client.retrieve(Entity::set("accounts"), id).await.unwrap();
+        assert!(plain.cache.is_none());
+        let request = plain.request.unwrap();
+        assert!(request.service_request_id.is_some());
+        assert!(request.correlation_id.is_some());
+        assert_eq!(request.retries, 0);
+
+        let first = client
+            .retrieve(Entity::set("accounts"), id)
+            .conditional()
+            .await
+            .unwrap();
+        assert!(first.cache.is_miss());
+
+        let second = client
+            .retrieve(Entity::set("accounts"), id)
+            .conditional()
+            .await
+            .unwrap();
+        assert!(second.is_cached());
+        assert_eq!(second.data().id(), Some(id));
+        assert_eq!(second.data().get_string("name").unwrap(), Some("Contoso"));
+
+        client
+            .update(
+                Entity::set("accounts"),
+                id,
+                Record::new("account").set("name", "Fabrikam"),
+            )
+            .await
+            .unwrap();
+        let changed = client
+            .retrieve(Entity::set("accounts"), id)
+            .conditional()
+            .await
+            .unwrap();
+        assert!(changed.cache.is_miss());
+        assert_eq!(changed.data().get_string("name").unwrap(), Some("Fabrikam"));
+    }
+
+    #[tokio::test]
+    async fn test_debug_mode_keeps_raw_response() {
+        let mock = mock();
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .no_cache()
+            .no_retry()
+            .debug_mode(crate::error::DebugConfig::default().max_body_size(16))
+            .mock(mock.clone())
+            .build();
+
+        let error = client
+            .retrieve(Entity::set("accounts"), Uuid::new_v4())
+            .await
+            .unwrap_err();
+        let Error::Api(error) = error else {
+            panic!("expected an API error, got {:?}", error);
+        };
+        let raw = error.raw_response().unwrap();
+        assert_eq!(raw.method, "GET");
+        assert_eq!(raw.status, 404);
+        assert_eq!(raw.body.len(), 16);
+        assert!(raw.truncated);
+        assert!(
+            raw.headers
+                .iter()
+                .any(|(name, _)| name == "x-ms-service-request-id")
+        );
+
+        let plain = mock.client();
+        let error = plain
+            .retrieve(Entity::set("accounts"), Uuid::new_v4())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Api(e) if e.raw_response().is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_sorts_and_pages() {
+        let mock = mock();
+        let client = mock.client();
+        for (name, revenue) in [("A", 10), ("B", 30), ("C", 20), ("D", 5)] {
+            mock.insert(
+                Entity::set("accounts"),
+                Record::new("account")
+                    .set("name", name)
+                    .set("revenue", revenue),
+            );
+        }
+
+        let mut pages = client
+            .query(Entity::logical("account"))
+            .filter(Filter::ge("revenue", 10))
+            .order_by(OrderBy::desc("revenue"))
+            .page_size(2)
+            .into_async_iter(&client);
+
+        let mut names = Vec::new();
+        while let Some(page) = pages.next(&client).await {
+            for record in page.unwrap().records() {
+                assert!(record.id().is_some());
+                names.push(record.get_string("name").unwrap().unwrap().to_string());
+            }
+        }
+        assert_eq!(names, ["B", "C", "A"]);
+    }
+
+    #[tokio::test]
+    async fn test_lookups() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let contact = mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact").set(
+                "parentcustomerid_account",
+                EntityBinding::new("accounts", account),
+            ),
+        );
+
+        let records = client
+            .query(Entity::logical("contact"))
+            .filter(Filter::eq("_parentcustomerid_account_value", account))
+            .execute(&client)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id(), Some(contact));
+    }
+
+    #[tokio::test]
+    async fn test_related_by_relationship_name() {
+        let mock = MockDataverse::new()
+            .entity(
+                MockEntity::new("account", "accounts", "accountid")
+                    .primary_name("name")
+                    .many_to_many("new_account_contact", "contact", "new_account_contacts"),
+            )
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .lookup("parentcustomerid", "account"),
+            );
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+        for name in ["Grace", "Ada", "Edsger"] {
+            let parent = match name {
+                "Edsger" => Record::new("contact"),
+                _ => Record::new("contact")
+                    .set("parentcustomerid", EntityBinding::new("accounts", account)),
+            };
+            let contact = mock.insert(Entity::set("contacts"), parent.set("fullname", name));
+            client
+                .associate(
+                    Entity::set("accounts"),
+                    account,
+                    "new_account_contacts",
+                    Entity::set("contacts"),
+                    contact,
+                )
+                .await
+                .unwrap();
+        }
+
+        let records = client
+            .related(Entity::logical("account"), account, "new_account_contact")
+            .select(&["fullname", "parentcustomerid"])
+            .filter(Filter::eq("parentcustomerid", account))
+            .order_by(OrderBy::asc("fullname"))
+            .execute()
+            .await
+            .unwrap();
+        let names: Vec<_> = records
+            .iter()
+            .map(|r| r.get_string("fullname").unwrap().unwrap())
+            .collect();
+        assert_eq!(names, ["Ada", "Grace"]);
+        let request = mock.requests().pop().unwrap();
+        assert!(
+            request
+                .url
+                .path()
+                .ends_with(&format!("/accounts({})/new_account_contacts", account))
+        );
+
+        let count = client
+            .related(Entity::set("accounts"), account, "new_account_contacts")
+            .count_only()
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_relationship_references() {
+        let mock = mock();
+        let client = mock.client();
+        let account = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let contact = mock.insert(Entity::set("contacts"), Record::new("contact"));
+
+        client
+            .associate(
+                Entity::set("accounts"),
+                account,
+                "contact_customer_accounts",
+                Entity::set("contacts"),
+                contact,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.associated(
+                Entity::set("accounts"),
+                account,
+                "contact_customer_accounts"
+            ),
+            vec![contact]
+        );
+
+        client
+            .disassociate(
+                Entity::set("accounts"),
+                account,
+                "contact_customer_accounts",
+                contact,
+            )
+            .await
+            .unwrap();
+        assert!(
+            mock.associated(
+                Entity::set("accounts"),
+                account,
+                "contact_customer_accounts"
+            )
+            .is_empty()
+        );
+
+        let result = client
+            .batch()
+            .add(Op::set_lookup(
+                Entity::set("contacts"),
+                contact,
+                "parentcustomerid_account",
+                Entity::set("accounts"),
+                account,
+            ))
+            .execute()
+            .await
+            .unwrap();
+        assert!(result.all_succeeded());
+        let stored = mock.record(Entity::set("contacts"), contact).unwrap();
+        assert_eq!(
+            stored
+                .lookup_info("parentcustomerid_account")
+                .and_then(|info| info.id),
+            Some(account)
+        );
+
+        client
+            .clear_lookup(Entity::set("contacts"), contact, "parentcustomerid_account")
+            .await
+            .unwrap();
+        let stored = mock.record(Entity::set("contacts"), contact).unwrap();
+        assert_eq!(
+            stored
+                .lookup_info("parentcustomerid_account")
+                .and_then(|info| info.id),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_changeset_rolls_back() {
+        let mock = mock();
+        let client = mock.client();
+        let existing = mock.insert(Entity::set("accounts"), Record::new("account"));
+
+        let result = client
+            .batch()
+            .changeset(|cs| {
+                cs.add(Op::create(
+                    Entity::set("accounts"),
+                    Record::new("account").set("name", "New"),
+                ));
+                cs.add(Op::delete(Entity::set("accounts"), Uuid::new_v4()));
+            })
+            .add(Op::delete(Entity::set("accounts"), existing))
+            .continue_on_error()
+            .execute()
+            .await
+            .unwrap();
+
+        assert!(!result.all_succeeded());
+        let remaining = mock.records(Entity::set("accounts"));
+        assert!(
+            remaining.is_empty(),
+            "changeset create should be rolled back"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assign_and_set_state() {
+        let mock = MockDataverse::new().entity(
+            MockEntity::new("account", "accounts", "accountid")
+                .lookup("ownerid", "systemuser")
+                .status(0, 1)
+                .status(1, 2),
+        );
+        let id = mock.insert(Entity::set("accounts"), Record::new("account"));
+        let team = Uuid::new_v4();
+
+        let client = mock.client();
+        client
+            .assign(Entity::logical("account"), id, Owner::Team(team))
+            .await
+            .unwrap();
+        let account = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(
+            account.lookup_info("ownerid").and_then(|info| info.id),
+            Some(team)
+        );
+
+        // Without cached metadata the values aren't checked
+        client
+            .set_state(Entity::logical("account"), id, 0, 2)
+            .await
+            .unwrap();
+
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .cache(InMemoryCache::new())
+            .no_retry()
+            .mock(mock.clone())
+            .build();
+        client
+            .metadata()
+            .entity(Entity::logical("account"))
+            .await
+            .unwrap();
+
+        let err = client
+            .set_state(Entity::logical("account"), id, 0, 2)
+            .await
+            .unwrap_err();
+        let Error::Validation { errors, .. } = err else {
+            panic!("expected a validation error, got {:?}", err);
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "statuscode");
+        assert!(
+            client
+                .set_state(Entity::logical("account"), id, 2, 3)
+                .await
+                .is_err()
+        );
+
+        client
+            .set_state(Entity::logical("account"), id, 1, 2)
+            .await
+            .unwrap();
+        let account = mock.record(Entity::set("accounts"), id).unwrap();
+        assert_eq!(account.get_int("statecode").unwrap(), Some(1));
+        assert_eq!(account.get_int("statuscode").unwrap(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_validated_writes() {
+        let mock = MockDataverse::new().entity(
+            MockEntity::new("account", "accounts", "accountid")
+                .primary_name("name")
+                .required("name")
+                .attribute("createdon", AttributeType::DateTime)
+                .read_only("createdon")
+                .status(0, 1),
+        );
+        let client = mock.client();
+        let posts = || {
+            mock.requests()
+                .iter()
+                .filter(|r| r.method == reqwest::Method::POST)
+                .count()
+        };
+
+        let err = client
+            .create(
+                Entity::logical("account"),
+                Record::new("account")
+                    .set("createdon", chrono::Utc::now())
+                    .set("statuscode", 5)
+                    .set("telephone1", "555"),
+            )
+            .validate()
+            .await
+            .unwrap_err();
+        let Error::Validation { errors, .. } = err else {
+            panic!("expected a validation error, got {:?}", err);
+        };
+        let codes: Vec<(&str, Option<&str>)> = errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.code.as_deref()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("createdon", Some(validation_codes::READ_ONLY)),
+                ("name", Some(validation_codes::REQUIRED)),
+                ("statuscode", Some(validation_codes::INVALID_OPTION)),
+                ("telephone1", Some(validation_codes::UNKNOWN_FIELD)),
+            ]
+        );
+        assert_eq!(posts(), 0);
+
+        let id = client
+            .create(
+                Entity::logical("account"),
+                Record::new("account").set("name", "Contoso"),
+            )
+            .validate()
+            .await
+            .unwrap()
+            .id()
+            .unwrap();
+
+        let err = client
+            .update(
+                Entity::logical("account"),
+                id,
+                Record::new("account").set("name", Value::Null),
+            )
+            .validate()
+            .await;
+        assert!(matches!(err, Err(Error::Validation { .. })));
+        client
+            .update(
+                Entity::logical("account"),
+                id,
+                Record::new("account").set("name", "Fabrikam"),
+            )
+            .validate()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_sees_requests_and_cache_lookups() {
+        use crate::middleware::Telemetry;
+
+        #[derive(Default)]
+        struct Events(Mutex<Vec<String>>);
+
+        impl Telemetry for Arc<Events> {
+            fn on_request_start(&self, method: &Method, _url: &str) {
+                self.0.lock().unwrap().push(format!("start {}", method));
+            }
+
+            fn on_request_end(
+                &self,
+                method: &Method,
+                _url: &str,
+                status: Option<StatusCode>,
+                _elapsed: std::time::Duration,
+            ) {
+                let status = status.map(|s| s.as_u16());
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("end {} {:?}", method, status));
+            }
+
+            fn on_cache_hit(&self, key: &str) {
+                self.0.lock().unwrap().push(format!("hit {}", key));
+            }
+        }
+
+        let events = Arc::new(Events::default());
+        let mock = mock();
+        let client = DataverseClient::builder()
+            .url(MOCK_URL)
+            .token_provider(StaticTokenProvider::new("mock-token"))
+            .cache(InMemoryCache::new())
+            .telemetry(events.clone())
+            .no_retry()
+            .mock(mock.clone())
+            .build();
+
+        let recorded = || events.0.lock().unwrap().clone();
+        client
+            .metadata()
+            .entity(Entity::logical("account"))
+            .await
+            .unwrap();
+        let loaded = recorded();
+        assert!(!loaded.is_empty());
+        assert!(
+            loaded
+                .iter()
+                .all(|e| e == "start GET" || e == "end GET Some(200)")
+        );
+
+        // Served from the cache
+        client
+            .metadata()
+            .entity(Entity::logical("account"))
+            .await
+            .unwrap();
+        let cached = recorded();
+        assert_eq!(cached.len(), loaded.len() + 1);
+        assert!(cached[loaded.len()].starts_with("hit "));
+
+        let missing = client
+            .retrieve(Entity::set("accounts"), Uuid::new_v4())
+            .await;
+        assert!(missing.is_err());
+        assert_eq!(
+            recorded()[cached.len()..],
+            ["start GET", "end GET Some(404)"]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! OData instance annotations
+//!
+//! The Web API attaches annotations to fields as `field@term` keys, e.g.
+//! `statuscode@OData.Community.Display.V1.FormattedValue`. Records keep them
+//! per field; see [`Record::annotation`](super::Record::annotation).
+
+use uuid::Uuid;
+
+/// Display text for a field (option set labels, formatted numbers and dates,
+/// lookup names).
+pub const FORMATTED_VALUE: &str = "OData.Community.Display.V1.FormattedValue";
+
+/// Logical name of the table a lookup points to.
+pub const LOOKUP_LOGICAL_NAME: &str = "Microsoft.Dynamics.CRM.lookuplogicalname";
+
+/// Single-valued navigation property a lookup is read through.
+pub const ASSOCIATED_NAVIGATION_PROPERTY: &str =
+    "Microsoft.Dynamics.CRM.associatednavigationproperty";
+
+/// Which annotations a query asks the server to include.
+///
+/// Sent as `Prefer: odata.include-annotations="..."`. Defaults to all.
+///
+/// # Example
+///
+/// ```
+/// use dataverse_lib::model::Annotations;
+/// use dataverse_lib::model::FORMATTED_VALUE;
+///
+/// let annotations = Annotations::only(&[FORMATTED_VALUE]);
+/// assert_eq!(
+///     annotations.prefer().as_deref(),
+///     Some("odata.include-annotations=\"OData.Community.Display.V1.FormattedValue\"")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Annotations {
+    /// All annotations (`*`).
+    #[default]
+    All,
+    /// No annotations. Lookups still resolve, but without a target table
+    /// or display name.
+    None,
+    /// The listed terms. Wildcards like `Microsoft.Dynamics.CRM.*` are allowed.
+    Only(Vec<String>),
+}
+
+impl Annotations {
+    /// Includes only the given terms.
+    pub fn only(terms: &[&str]) -> Self {
+        Self::Only(terms.iter().map(|t| (*t).to_string()).collect())
+    }
+
+    /// Returns the `Prefer` header preference, or `None` to omit it.
+    pub fn prefer(&self) -> Option<String> {
+        match self {
+            Self::All => Some("odata.include-annotations=\"*\"".to_string()),
+            Self::None => None,
+            Self::Only(terms) if terms.is_empty() => None,
+            Self::Only(terms) => Some(format!("odata.include-annotations=\"{}\"", terms.join(","))),
+        }
+    }
+}
+
+/// What a record knows about a lookup field, from its value and annotations.
+///
+/// Returned by [`Record::lookup_info`](super::Record::lookup_info).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupInfo {
+    /// The referenced record, if the lookup is set and wasn't replaced by an
+    /// expanded record.
+    pub id: Option<Uuid>,
+    /// Logical name of the referenced table.
+    pub logical_name: Option<String>,
+    /// Display name of the referenced record.
+    pub formatted_value: Option<String>,
+    /// Navigation property to read or bind the lookup through.
+    pub navigation_property: Option<String>,
+}
@@ -0,0 +1,311 @@
+//! Currency-aware money conversion
+//!
+//! Money columns hold amounts in the record's transaction currency, the
+//! `transactioncurrencyid` lookup. Dataverse derives the `_base` columns
+//! from the currency's exchange rate, stated in units of the currency per
+//! unit of the organization's base currency.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::Record;
+use super::Value;
+use crate::error::FieldError;
+use crate::model::metadata::AttributeType;
+use crate::model::metadata::EntityMetadata;
+use crate::model::types::EntityBinding;
+use crate::model::types::Money;
+
+/// Logical name of the transaction currency lookup.
+pub const TRANSACTION_CURRENCY: &str = "transactioncurrencyid";
+
+/// Entity set of `transactioncurrency`.
+const CURRENCY_SET: &str = "transactioncurrencies";
+
+/// A currency with its exchange rate (`transactioncurrency` record).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Currency {
+    /// The currency's id.
+    pub id: Uuid,
+    /// The ISO 4217 code (e.g. `EUR`).
+    pub iso_code: Option<String>,
+    /// Units of this currency per unit of the base currency.
+    pub exchange_rate: Decimal,
+    /// Decimal places amounts in this currency are rounded to.
+    pub precision: Option<u32>,
+}
+
+/// Writes money fields in a record's transaction currency, converting
+/// amounts between currencies with the organization's exchange rates.
+///
+/// # Example
+///
+/// ```ignore
+/// let money = client.currency_converter(Entity::logical("opportunity")).await?;
+///
+/// // Quote an amount in euros on a record priced in dollars
+/// let mut opportunity = Record::new("opportunity")
+///     .set("transactioncurrencyid", EntityBinding::new("transactioncurrencies", usd));
+/// money.set(&mut opportunity, "estimatedvalue", dec!(1000), eur)?;
+///
+/// // Records with money fields but no currency get the base currency
+/// money.ensure_currency(&mut opportunity)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CurrencyConverter {
+    base_currency: Uuid,
+    currencies: HashMap<Uuid, Currency>,
+    money_fields: HashSet<String>,
+}
+
+impl CurrencyConverter {
+    /// Creates a converter knowing only the base currency, with no known
+    /// money fields.
+    pub fn new(base_currency: Uuid) -> Self {
+        let base = Currency {
+            id: base_currency,
+            iso_code: None,
+            exchange_rate: Decimal::ONE,
+            precision: None,
+        };
+        Self {
+            base_currency,
+            currencies: HashMap::from([(base_currency, base)]),
+            money_fields: HashSet::new(),
+        }
+    }
+
+    /// Creates a converter knowing the money fields of an entity.
+    ///
+    /// The computed `_base` fields aren't included, since they can't be
+    /// written.
+    pub fn from_metadata(base_currency: Uuid, metadata: &EntityMetadata) -> Self {
+        let mut converter = Self::new(base_currency);
+        converter.money_fields = metadata
+            .attributes
+            .iter()
+            .filter(|a| a.attribute_type == AttributeType::Money && a.attribute_of.is_none())
+            .map(|a| a.logical_name.clone())
+            .collect();
+        converter
+    }
+
+    /// Adds a currency. The base currency's exchange rate is always 1.
+    pub fn currency(mut self, mut currency: Currency) -> Self {
+        if currency.id == self.base_currency {
+            currency.exchange_rate = Decimal::ONE;
+        }
+        self.currencies.insert(currency.id, currency);
+        self
+    }
+
+    /// Marks a field as a money field.
+    pub fn money_field(mut self, field: impl Into<String>) -> Self {
+        self.money_fields.insert(field.into());
+        self
+    }
+
+    /// Returns the organization's base currency.
+    pub fn base_currency(&self) -> Uuid {
+        self.base_currency
+    }
+
+    /// Returns a known currency.
+    pub fn get(&self, currency: Uuid) -> Option<&Currency> {
+        self.currencies.get(&currency)
+    }
+
+    /// Converts an amount between currencies, rounded to the target
+    /// currency's precision.
+    ///
+    /// Returns `None` if either currency is unknown or has no exchange rate.
+    pub fn convert(&self, amount: Decimal, from: Uuid, to: Uuid) -> Option<Decimal> {
+        if from == to {
+            return Some(amount);
+        }
+        let from = self.currencies.get(&from)?;
+        let to = self.currencies.get(&to)?;
+        if from.exchange_rate.is_zero() {
+            return None;
+        }
+        let converted = amount / from.exchange_rate * to.exchange_rate;
+        Some(match to.precision {
+            Some(precision) => converted.round_dp(precision),
+            None => converted,
+        })
+    }
+
+    /// Returns the transaction currency set on a record, if any.
+    pub fn currency_of(&self, record: &Record) -> Result<Option<Uuid>, FieldError> {
+        match record.get(TRANSACTION_CURRENCY) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::EntityReference(reference)) => Ok(Some(reference.id)),
+            Some(Value::EntityBinding(binding)) => Ok(binding.id),
+            Some(other) => Err(FieldError::type_mismatch(
+                TRANSACTION_CURRENCY,
+                "entity_reference",
+                other.type_name(),
+            )),
+        }
+    }
+
+    /// Sets a money field from an amount in `currency`.
+    ///
+    /// Records without a transaction currency are given `currency`. On
+    /// records in another currency, the amount is converted to it.
+    pub fn set(
+        &self,
+        record: &mut Record,
+        field: &str,
+        amount: Decimal,
+        currency: Uuid,
+    ) -> Result<(), FieldError> {
+        self.known(currency)?;
+        let record_currency = match self.currency_of(record)? {
+            Some(record_currency) => self.known(record_currency)?,
+            None => {
+                record.insert(
+                    TRANSACTION_CURRENCY,
+                    EntityBinding::new(CURRENCY_SET, currency),
+                );
+                currency
+            }
+        };
+        let amount = self
+            .convert(amount, currency, record_currency)
+            .ok_or_else(|| FieldError::InvalidValue {
+                field: field.to_string(),
+                message: format!(
+                    "can't convert from currency {} to {}",
+                    currency, record_currency
+                ),
+            })?;
+        record.insert(field, Money::new(amount));
+        Ok(())
+    }
+
+    /// Checks the transaction currency of a record with money fields.
+    ///
+    /// A record setting money fields without a currency is given the base
+    /// currency, which Dataverse would otherwise replace with the user's
+    /// default currency. Fails if the record's currency isn't known.
+    pub fn ensure_currency(&self, record: &mut Record) -> Result<(), FieldError> {
+        if let Some(currency) = self.currency_of(record)? {
+            self.known(currency)?;
+            return Ok(());
+        }
+        let has_money = record.fields().iter().any(|(field, value)| {
+            !matches!(value, Value::Null)
+                && (matches!(value, Value::Money(_)) || self.money_fields.contains(field.as_str()))
+        });
+        if has_money {
+            record.insert(
+                TRANSACTION_CURRENCY,
+                EntityBinding::new(CURRENCY_SET, self.base_currency),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that a currency is known, returning it.
+    fn known(&self, currency: Uuid) -> Result<Uuid, FieldError> {
+        if self.currencies.contains_key(&currency) {
+            Ok(currency)
+        } else {
+            Err(FieldError::InvalidValue {
+                field: TRANSACTION_CURRENCY.to_string(),
+                message: format!("unknown or inactive currency {}", currency),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn euro() -> Currency {
+        Currency {
+            id: Uuid::from_u128(2),
+            iso_code: Some("EUR".to_string()),
+            exchange_rate: Decimal::new(9, 1),
+            precision: Some(2),
+        }
+    }
+
+    fn converter() -> CurrencyConverter {
+        CurrencyConverter::new(Uuid::from_u128(1))
+            .currency(euro())
+            .money_field("estimatedvalue")
+    }
+
+    #[test]
+    fn test_convert() {
+        let money = converter();
+        let (usd, eur) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        assert_eq!(
+            money.convert(Decimal::from(100), usd, eur),
+            Some(Decimal::from(90))
+        );
+        assert_eq!(
+            money.convert(Decimal::from(10), eur, usd),
+            Some(Decimal::from(10) / Decimal::new(9, 1))
+        );
+        assert_eq!(
+            money.convert(Decimal::new(1234, 2), usd, eur),
+            Some(Decimal::new(1111, 2))
+        );
+        assert_eq!(money.convert(Decimal::ONE, usd, Uuid::from_u128(3)), None);
+    }
+
+    #[test]
+    fn test_set_and_ensure_currency() {
+        let money = converter();
+        let (usd, eur) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        let mut record = Record::new("opportunity");
+        money
+            .set(&mut record, "estimatedvalue", Decimal::from(50), eur)
+            .unwrap();
+        assert_eq!(money.currency_of(&record).unwrap(), Some(eur));
+        assert_eq!(
+            record.get_money("estimatedvalue").unwrap(),
+            Some(Money::from_int(50))
+        );
+
+        let mut record = Record::new("opportunity")
+            .set(TRANSACTION_CURRENCY, EntityBinding::new(CURRENCY_SET, usd));
+        money
+            .set(&mut record, "estimatedvalue", Decimal::from(90), eur)
+            .unwrap();
+        assert_eq!(
+            record.get_money("estimatedvalue").unwrap(),
+            Some(Money::from_int(100))
+        );
+        assert!(
+            money
+                .set(
+                    &mut record,
+                    "estimatedvalue",
+                    Decimal::ONE,
+                    Uuid::from_u128(3)
+                )
+                .is_err()
+        );
+
+        let mut record = Record::new("opportunity").set("estimatedvalue", 10);
+        money.ensure_currency(&mut record).unwrap();
+        assert_eq!(money.currency_of(&record).unwrap(), Some(usd));
+        let mut record = Record::new("opportunity").set("name", "Deal");
+        money.ensure_currency(&mut record).unwrap();
+        assert_eq!(money.currency_of(&record).unwrap(), None);
+        let mut record = Record::new("opportunity").set(
+            TRANSACTION_CURRENCY,
+            EntityBinding::new(CURRENCY_SET, Uuid::from_u128(3)),
+        );
+        assert!(money.ensure_currency(&mut record).is_err());
+    }
+}
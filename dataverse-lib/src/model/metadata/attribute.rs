@@ -57,6 +57,11 @@ pub struct AttributeMetadata {
     #[serde(default)]
     pub is_valid_for_update: bool,
 
+    /// Whether field-level security is enabled for this attribute, hiding
+    /// it from users without a field security profile granting access.
+    #[serde(default)]
+    pub is_secured: bool,
+
     /// Whether this attribute is required.
     #[serde(default)]
     pub required_level: RequiredLevel,
@@ -100,6 +105,19 @@ pub struct AttributeMetadata {
     /// For global picklist attributes, the global option set metadata.
     #[serde(default)]
     pub global_option_set: Option<OptionSetMetadata>,
+
+    /// For image attributes, whether the full-size image is stored alongside
+    /// the thumbnail.
+    #[serde(default)]
+    pub can_store_full_image: Option<bool>,
+
+    /// For image attributes, whether this is the entity's primary image.
+    #[serde(default)]
+    pub is_primary_image: Option<bool>,
+
+    /// For file and image attributes, the maximum size in kilobytes.
+    #[serde(default, rename = "MaxSizeInKB")]
+    pub max_size_in_kb: Option<i32>,
 }
 
 impl AttributeMetadata {
@@ -119,10 +137,35 @@ impl AttributeMetadata {
         )
     }
 
+    /// Returns true if this is an image attribute.
+    ///
+    /// The Web API reports image columns as `Virtual`, so image-only metadata
+    /// is checked too.
+    pub fn is_image(&self) -> bool {
+        self.attribute_type == AttributeType::Image || self.can_store_full_image.is_some()
+    }
+
     /// Returns the option set for this attribute (local or global).
     pub fn options(&self) -> Option<&OptionSetMetadata> {
         self.option_set.as_ref().or(self.global_option_set.as_ref())
     }
+
+    /// Returns the attribute's display name in a language (LCID), falling
+    /// back to the logical name.
+    pub fn display_name(&self, language: Option<i32>) -> &str {
+        self.display_name
+            .localized(language)
+            .unwrap_or(&self.logical_name)
+    }
+
+    /// Returns the label of an option in a language (LCID).
+    pub fn option_label(&self, value: i32, language: Option<i32>) -> Option<&str> {
+        self.options()?
+            .options
+            .iter()
+            .find(|o| o.value == value)
+            .and_then(|o| o.label.localized(language))
+    }
 }
 
 /// Attribute type enumeration.
@@ -233,6 +233,50 @@ impl EntityMetadata {
         self.primary_name_attribute.as_deref()
     }
 
+    /// Returns the entity's display name in a language (LCID), falling back
+    /// to the logical name.
+    pub fn display_name(&self, language: Option<i32>) -> &str {
+        self.display_name
+            .localized(language)
+            .unwrap_or(&self.logical_name)
+    }
+
+    /// Returns the label of an option of a choice attribute (picklist,
+    /// multi-select, state or status) in a language (LCID).
+    pub fn option_label(&self, attribute: &str, value: i32, language: Option<i32>) -> Option<&str> {
+        let label = if let Some(a) = self.picklist_attribute(attribute) {
+            a.option_set
+                .options
+                .iter()
+                .find(|o| o.value == value)
+                .map(|o| &o.label)
+        } else if let Some(a) = self.multi_select_picklist_attribute(attribute) {
+            a.option_set
+                .options
+                .iter()
+                .find(|o| o.value == value)
+                .map(|o| &o.label)
+        } else if let Some(a) = self.state_attribute(attribute) {
+            a.option_set
+                .options
+                .iter()
+                .find(|o| o.value == value)
+                .map(|o| &o.label)
+        } else if let Some(a) = self.status_attribute(attribute) {
+            a.option_set
+                .options
+                .iter()
+                .find(|o| o.value == value)
+                .map(|o| &o.label)
+        } else {
+            self.attribute(attribute)
+                .and_then(AttributeMetadata::options)
+                .and_then(|set| set.options.iter().find(|o| o.value == value))
+                .map(|o| &o.label)
+        };
+        label.and_then(|label| label.localized(language))
+    }
+
     /// Finds an attribute by logical name.
     pub fn attribute(&self, logical_name: &str) -> Option<&AttributeMetadata> {
         self.attributes
@@ -375,6 +419,9 @@ impl EntityMetadata {
     }
 }
 
+/// LCID of English, the last-resort label language.
+const ENGLISH: i32 = 1033;
+
 /// A localized label with user-specific and all localized values.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -401,6 +448,24 @@ impl Label {
     pub fn text_or<'a>(&'a self, default: &'a str) -> &'a str {
         self.text().unwrap_or(default)
     }
+
+    /// Returns the label text in a language (LCID), if given.
+    ///
+    /// Falls back to the user's language, then English (1033), then the
+    /// first available label.
+    pub fn localized(&self, language: Option<i32>) -> Option<&str> {
+        let in_language = |lcid: i32| {
+            self.localized_labels
+                .iter()
+                .find(|l| l.language_code == lcid)
+                .map(|l| l.label.as_str())
+        };
+        language
+            .and_then(in_language)
+            .or_else(|| self.user_localized_label.as_ref().map(|l| l.label.as_str()))
+            .or_else(|| in_language(ENGLISH))
+            .or_else(|| self.localized_labels.first().map(|l| l.label.as_str()))
+    }
 }
 
 /// A label localized to a specific language.
@@ -429,3 +494,35 @@ pub enum OwnershipType {
     /// Business-parented.
     BusinessParented,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(user: Option<(&str, i32)>, labels: &[(&str, i32)]) -> Label {
+        let localized = |(label, language_code): (&str, i32)| LocalizedLabel {
+            label: label.to_string(),
+            language_code,
+        };
+        Label {
+            user_localized_label: user.map(localized),
+            localized_labels: labels.iter().copied().map(localized).collect(),
+        }
+    }
+
+    #[test]
+    fn test_localized_label_fallbacks() {
+        let german = label(
+            Some(("Konto", 1031)),
+            &[("Account", 1033), ("Konto", 1031), ("Compte", 1036)],
+        );
+        assert_eq!(german.localized(Some(1036)), Some("Compte"));
+        // Missing languages fall back to the user's language
+        assert_eq!(german.localized(Some(1043)), Some("Konto"));
+        assert_eq!(german.localized(None), Some("Konto"));
+
+        let no_user = label(None, &[("Konto", 1031), ("Account", 1033)]);
+        assert_eq!(no_user.localized(Some(1043)), Some("Account"));
+        assert_eq!(Label::default().localized(Some(1033)), None);
+    }
+}
@@ -0,0 +1,84 @@
+//! Entity key (alternate key) metadata types
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Label;
+
+/// An alternate key defined on an entity.
+///
+/// Alternate keys address records by the values of one or more columns
+/// instead of their id. Dataverse builds an index for each key, and the key
+/// can only be used once the index is [`Active`](EntityKeyIndexStatus::Active).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntityKeyMetadata {
+    /// The unique metadata identifier.
+    pub metadata_id: uuid::Uuid,
+
+    /// The logical name of the key.
+    pub logical_name: String,
+
+    /// The schema name of the key.
+    pub schema_name: String,
+
+    /// Display name of the key.
+    #[serde(default)]
+    pub display_name: Label,
+
+    /// The logical name of the entity the key belongs to.
+    #[serde(default)]
+    pub entity_logical_name: Option<String>,
+
+    /// Logical names of the key's columns.
+    #[serde(default)]
+    pub key_attributes: Vec<String>,
+
+    /// The state of the key's index.
+    #[serde(default)]
+    pub entity_key_index_status: EntityKeyIndexStatus,
+
+    /// Whether this key is part of a managed solution.
+    #[serde(default)]
+    pub is_managed: bool,
+}
+
+impl EntityKeyMetadata {
+    /// Returns `true` if the key's index is built and the key can be used.
+    pub fn is_active(&self) -> bool {
+        self.entity_key_index_status == EntityKeyIndexStatus::Active
+    }
+
+    /// Returns `true` if the key has exactly these columns, in any order.
+    ///
+    /// Lookup columns may be given as `_field_value`, as they are in
+    /// [`RecordKey`](crate::api::RecordKey)s.
+    pub fn matches<S: AsRef<str>>(&self, columns: &[S]) -> bool {
+        let column_name = |column: &str| {
+            column
+                .strip_prefix('_')
+                .and_then(|c| c.strip_suffix("_value"))
+                .unwrap_or(column)
+                .to_string()
+        };
+        let mut columns: Vec<String> = columns.iter().map(|c| column_name(c.as_ref())).collect();
+        let mut key_attributes = self.key_attributes.clone();
+        columns.sort();
+        key_attributes.sort();
+        columns == key_attributes
+    }
+}
+
+/// State of the index backing an alternate key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntityKeyIndexStatus {
+    /// The index is waiting to be built.
+    #[default]
+    Pending,
+    /// The index is being built.
+    InProgress,
+    /// The index is built and the key can be used.
+    Active,
+    /// Building the index failed, usually because of duplicate values.
+    Failed,
+}
@@ -2,18 +2,22 @@
 
 mod attribute;
 mod entity;
+mod key;
 mod multi_select_picklist;
 mod option_set;
 mod picklist;
 mod relationship;
 mod state;
 mod status;
+mod validation;
 
 pub use attribute::*;
 pub use entity::*;
+pub use key::*;
 pub use multi_select_picklist::*;
 pub use option_set::*;
 pub use picklist::*;
 pub use relationship::*;
 pub use state::*;
 pub use status::*;
+pub use validation::*;
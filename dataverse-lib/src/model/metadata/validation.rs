@@ -0,0 +1,338 @@
+//! Write payload validation against attribute metadata
+//!
+//! [`EntityMetadata::validate_record`] checks the fields of a create, update
+//! or upsert payload the way Dataverse would, so a bad payload fails before
+//! it's sent, with an error per field. Each error carries one of the
+//! [`validation_codes`].
+
+use rust_decimal::prelude::ToPrimitive;
+
+use super::AttributeMetadata;
+use super::AttributeType;
+use super::EntityMetadata;
+use super::RequiredLevelValue;
+use crate::error::FieldValidationError;
+use crate::model::Record;
+use crate::model::Value;
+use validation_codes::*;
+
+/// Codes of [`FieldValidationError`]s from [`EntityMetadata::validate_record`].
+pub mod validation_codes {
+    /// The field isn't an attribute of the entity.
+    pub const UNKNOWN_FIELD: &str = "unknown_field";
+
+    /// The field can't be written by the operation.
+    pub const READ_ONLY: &str = "read_only";
+
+    /// The field is required but missing or null.
+    pub const REQUIRED: &str = "required";
+
+    /// The text is longer than the attribute's maximum length.
+    pub const MAX_LENGTH: &str = "max_length";
+
+    /// The number is outside the attribute's range.
+    pub const OUT_OF_RANGE: &str = "out_of_range";
+
+    /// The value isn't one of the choice's options.
+    pub const INVALID_OPTION: &str = "invalid_option";
+}
+
+/// The operation a payload is validated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteKind {
+    /// A create: required fields must be set.
+    Create,
+    /// An update: fields must be valid for update.
+    Update,
+    /// An upsert, which may create or update.
+    Upsert,
+}
+
+impl EntityMetadata {
+    /// Checks a write payload against the entity's attribute metadata.
+    ///
+    /// Checks that every field is an attribute that can be written, that
+    /// required attributes are set (on create) or not cleared, and that
+    /// values respect the attribute's maximum length, range and options.
+    /// Returns the errors sorted by field; an empty list means the payload
+    /// is valid.
+    ///
+    /// Attributes the platform fills in (the primary id, owner, state and
+    /// status) aren't required on create.
+    pub fn validate_record(&self, record: &Record, kind: WriteKind) -> Vec<FieldValidationError> {
+        let mut errors = Vec::new();
+        for (field, value) in record.fields() {
+            // Annotations such as `field@odata.bind`
+            if field.contains('@') {
+                continue;
+            }
+            let Some(attribute) = self.attribute(field) else {
+                errors.push(FieldValidationError::with_code(
+                    field,
+                    format!("not an attribute of {}", self.logical_name),
+                    UNKNOWN_FIELD,
+                ));
+                continue;
+            };
+            if let Some(error) = self.validate_field(attribute, value, kind) {
+                errors.push(error);
+            }
+        }
+
+        if kind == WriteKind::Create {
+            for attribute in &self.attributes {
+                let missing = matches!(
+                    record.fields().get(&attribute.logical_name),
+                    None | Some(Value::Null)
+                );
+                if missing && self.is_required_on_create(attribute) {
+                    errors.push(FieldValidationError::with_code(
+                        &attribute.logical_name,
+                        "is required",
+                        REQUIRED,
+                    ));
+                }
+            }
+        }
+
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        errors.dedup_by(|a, b| a.field == b.field && a.code == b.code);
+        errors
+    }
+
+    /// Checks one field of a payload.
+    fn validate_field(
+        &self,
+        attribute: &AttributeMetadata,
+        value: &Value,
+        kind: WriteKind,
+    ) -> Option<FieldValidationError> {
+        let field = attribute.logical_name.as_str();
+        let writable = match kind {
+            WriteKind::Create => attribute.is_valid_for_create,
+            WriteKind::Update => attribute.is_valid_for_update,
+            WriteKind::Upsert => attribute.is_valid_for_create || attribute.is_valid_for_update,
+        };
+        if !writable && field != self.primary_id_attribute {
+            let operation = match kind {
+                WriteKind::Create => "create",
+                WriteKind::Update => "update",
+                WriteKind::Upsert => "create or update",
+            };
+            return Some(FieldValidationError::with_code(
+                field,
+                format!("can't be set on {}", operation),
+                READ_ONLY,
+            ));
+        }
+
+        match value {
+            Value::Null if is_required(attribute) && kind != WriteKind::Create => {
+                Some(FieldValidationError::with_code(
+                    field,
+                    "is required and can't be cleared",
+                    REQUIRED,
+                ))
+            }
+            Value::String(text) => match attribute.max_length {
+                Some(max)
+                    if matches!(
+                        attribute.attribute_type,
+                        AttributeType::String | AttributeType::Memo
+                    ) && text.chars().count() > max.max(0) as usize =>
+                {
+                    Some(FieldValidationError::with_code(
+                        field,
+                        format!("is longer than {} characters", max),
+                        MAX_LENGTH,
+                    ))
+                }
+                _ => None,
+            },
+            Value::Int(n) if self.option_values(field).is_some() => {
+                self.check_options(field, &[*n])
+            }
+            Value::OptionSet(option) => self.check_options(field, &[option.value]),
+            Value::MultiOptionSet(options) => self.check_options(field, &options.values),
+            _ => {
+                let number = number(value)?;
+                let below = attribute.min_value.is_some_and(|min| number < min);
+                let above = attribute.max_value.is_some_and(|max| number > max);
+                (below || above).then(|| {
+                    FieldValidationError::with_code(
+                        field,
+                        format!(
+                            "must be between {} and {}",
+                            attribute.min_value.unwrap_or(f64::MIN),
+                            attribute.max_value.unwrap_or(f64::MAX)
+                        ),
+                        OUT_OF_RANGE,
+                    )
+                })
+            }
+        }
+    }
+
+    /// Checks that values are options of a choice attribute.
+    fn check_options(&self, field: &str, values: &[i32]) -> Option<FieldValidationError> {
+        let options = self.option_values(field)?;
+        let invalid: Vec<String> = values
+            .iter()
+            .filter(|v| !options.contains(v))
+            .map(i32::to_string)
+            .collect();
+        (!invalid.is_empty()).then(|| {
+            FieldValidationError::with_code(
+                field,
+                format!("{} is not a valid option", invalid.join(", ")),
+                INVALID_OPTION,
+            )
+        })
+    }
+
+    /// Returns the option values of a choice attribute, if its options are
+    /// known.
+    fn option_values(&self, field: &str) -> Option<Vec<i32>> {
+        let values = if let Some(a) = self.picklist_attribute(field) {
+            a.option_set.options.iter().map(|o| o.value).collect()
+        } else if let Some(a) = self.multi_select_picklist_attribute(field) {
+            a.option_set.options.iter().map(|o| o.value).collect()
+        } else if let Some(a) = self.state_attribute(field) {
+            a.option_set.options.iter().map(|o| o.value).collect()
+        } else if let Some(a) = self.status_attribute(field) {
+            a.option_set.options.iter().map(|o| o.value).collect()
+        } else {
+            let set = self.attribute(field)?.options()?;
+            set.options.iter().map(|o| o.value).collect()
+        };
+        Some(values)
+    }
+
+    /// Returns whether a create must set an attribute.
+    fn is_required_on_create(&self, attribute: &AttributeMetadata) -> bool {
+        is_required(attribute)
+            && attribute.is_valid_for_create
+            && attribute.attribute_of.is_none()
+            && attribute.logical_name != self.primary_id_attribute
+            && !matches!(
+                attribute.attribute_type,
+                AttributeType::Owner
+                    | AttributeType::State
+                    | AttributeType::Status
+                    | AttributeType::Virtual
+            )
+    }
+}
+
+/// Returns whether an attribute is system or business required.
+fn is_required(attribute: &AttributeMetadata) -> bool {
+    matches!(
+        attribute.required_level.value,
+        RequiredLevelValue::SystemRequired | RequiredLevelValue::ApplicationRequired
+    )
+}
+
+/// Returns a numeric value as a float.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(f64::from(*n)),
+        Value::Long(n) => Some(*n as f64),
+        Value::Float(n) => Some(*n),
+        Value::Decimal(d) => d.to_f64(),
+        Value::Money(m) => m.value().to_f64(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn metadata() -> EntityMetadata {
+        let attribute = |name: &str, kind: &str, extra: serde_json::Value| {
+            let mut json = json!({
+                "MetadataId": uuid::Uuid::nil(),
+                "LogicalName": name,
+                "SchemaName": name,
+                "AttributeType": kind,
+                "IsValidForCreate": true,
+                "IsValidForUpdate": true,
+            });
+            json.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            json
+        };
+        serde_json::from_value(json!({
+            "LogicalName": "account",
+            "EntitySetName": "accounts",
+            "SchemaName": "Account",
+            "PrimaryIdAttribute": "accountid",
+            "ObjectTypeCode": 1,
+            "MetadataId": uuid::Uuid::nil(),
+            "Attributes": [
+                attribute("accountid", "Uniqueidentifier", json!({ "IsValidForUpdate": false })),
+                attribute("name", "String", json!({
+                    "MaxLength": 5,
+                    "RequiredLevel": { "Value": "SystemRequired" },
+                })),
+                attribute("numberofemployees", "Integer", json!({
+                    "MinValue": 0.0,
+                    "MaxValue": 100.0,
+                })),
+                attribute("modifiedon", "DateTime", json!({
+                    "IsValidForCreate": false,
+                    "IsValidForUpdate": false,
+                })),
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn codes(errors: &[FieldValidationError]) -> Vec<(&str, &str)> {
+        errors
+            .iter()
+            .map(|e| (e.field.as_str(), e.code.as_deref().unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_record() {
+        let metadata = metadata();
+        let record = Record::new("account")
+            .set("name", "Contoso")
+            .set("numberofemployees", 101)
+            .set("parentaccountid@odata.bind", "/accounts(1)");
+        assert_eq!(
+            codes(&metadata.validate_record(&record, WriteKind::Update)),
+            [("name", MAX_LENGTH), ("numberofemployees", OUT_OF_RANGE)]
+        );
+
+        let record = Record::new("account")
+            .set("accountid", uuid::Uuid::nil())
+            .set("modifiedon", Value::Null);
+        assert_eq!(
+            codes(&metadata.validate_record(&record, WriteKind::Create)),
+            [("modifiedon", READ_ONLY), ("name", REQUIRED)]
+        );
+        // The primary id may be sent with an upsert
+        let record = Record::new("account")
+            .set("accountid", uuid::Uuid::nil())
+            .set("name", Value::Null);
+        assert_eq!(
+            codes(&metadata.validate_record(&record, WriteKind::Upsert)),
+            [("name", REQUIRED)]
+        );
+
+        let record = Record::new("account")
+            .set("name", "Fab")
+            .set("numberofemployees", 100);
+        assert!(
+            metadata
+                .validate_record(&record, WriteKind::Create)
+                .is_empty()
+        );
+    }
+}
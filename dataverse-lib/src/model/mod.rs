@@ -1,16 +1,29 @@
 //! Typed models
 
+mod annotation;
+mod currency;
 mod entity;
 pub mod form;
 pub mod metadata;
 mod record;
 mod record_serde;
+mod timezone;
+mod typed;
 pub mod types;
 mod value;
 mod value_serde;
 mod value_type;
 
+pub use annotation::*;
+pub use currency::*;
 pub use entity::*;
 pub use record::*;
+pub use timezone::*;
+pub use typed::*;
 pub use value::*;
 pub use value_type::*;
+
+pub(crate) use record_serde::loaded_records;
+
+pub use dataverse_derive::DataverseEntity;
+pub use dataverse_derive::DataverseOptionSet;
@@ -1,13 +1,22 @@
 //! Dynamic entity record
 
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use chrono::DateTime;
 use chrono::Utc;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use uuid::Uuid;
 
+use super::ASSOCIATED_NAVIGATION_PROPERTY;
 use super::Entity;
+use super::FORMATTED_VALUE;
+use super::LOOKUP_LOGICAL_NAME;
+use super::LookupInfo;
 use super::Value;
 use super::types::EntityBinding;
 use super::types::EntityReference;
@@ -16,6 +25,7 @@ use super::types::ImageReference;
 use super::types::Money;
 use super::types::MultiSelectOptionSetValue;
 use super::types::OptionSetValue;
+use super::types::decimal_from_f64;
 use crate::api::ContentIdRef;
 use crate::error::FieldError;
 
@@ -38,7 +48,16 @@ use crate::error::FieldError;
 /// // Access fields
 /// assert_eq!(record.get_string("name").unwrap(), Some("Contoso"));
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+///
+/// # Change tracking
+///
+/// Records remember which fields were set or inserted since they were
+/// retrieved (or last marked clean), and updates send only those. Records
+/// deserialized outside the client, from JSON or bincode, count every field
+/// as changed until [`mark_clean`](Self::mark_clean) is called. Changes made
+/// through [`fields_mut`](Self::fields_mut) can't be tracked, so after
+/// calling it the whole record is sent again.
+#[derive(Debug, Clone)]
 pub struct Record {
     /// The entity type.
     pub(crate) entity: Entity,
@@ -52,8 +71,27 @@ pub struct Record {
     /// Formatted display values (from @OData.Community.Display.V1.FormattedValue).
     pub(crate) formatted_values: HashMap<String, String>,
 
+    /// Other field annotations, by field then term.
+    pub(crate) annotations: HashMap<String, HashMap<String, String>>,
+
     /// The ETag for concurrency control.
     pub(crate) etag: Option<String>,
+
+    /// Fields changed since the record was loaded, or `None` if changes
+    /// aren't tracked and every field counts as changed.
+    pub(crate) changed: Option<HashSet<String>>,
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        // Change tracking is bookkeeping, not data
+        self.entity == other.entity
+            && self.id == other.id
+            && self.fields == other.fields
+            && self.formatted_values == other.formatted_values
+            && self.annotations == other.annotations
+            && self.etag == other.etag
+    }
 }
 
 impl Record {
@@ -64,7 +102,9 @@ impl Record {
             id: None,
             fields: HashMap::new(),
             formatted_values: HashMap::new(),
+            annotations: HashMap::new(),
             etag: None,
+            changed: Some(HashSet::new()),
         }
     }
 
@@ -75,7 +115,9 @@ impl Record {
             id: Some(id),
             fields: HashMap::new(),
             formatted_values: HashMap::new(),
+            annotations: HashMap::new(),
             etag: None,
+            changed: Some(HashSet::new()),
         }
     }
 
@@ -133,11 +175,102 @@ impl Record {
     }
 
     /// Returns a mutable reference to all fields.
+    ///
+    /// Stops change tracking: every field counts as changed until the
+    /// record is marked clean again.
     pub fn fields_mut(&mut self) -> &mut HashMap<String, Value> {
+        self.changed = None;
         &mut self.fields
     }
 
+    // =========================================================================
+    // Change tracking
+    // =========================================================================
+
+    /// Returns `true` if the field changed since the record was loaded.
+    pub fn is_dirty(&self, field: &str) -> bool {
+        match &self.changed {
+            Some(changed) => changed.contains(field),
+            None => self.fields.contains_key(field),
+        }
+    }
+
+    /// Returns `true` if any field changed since the record was loaded.
+    pub fn has_changes(&self) -> bool {
+        match &self.changed {
+            Some(changed) => !changed.is_empty(),
+            None => !self.fields.is_empty(),
+        }
+    }
+
+    /// Returns the names of the fields changed since the record was loaded.
+    pub fn dirty_fields(&self) -> Vec<&str> {
+        self.fields
+            .keys()
+            .filter(|field| self.is_dirty(field))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Forgets all changes, as if the record was just loaded.
+    pub fn mark_clean(&mut self) {
+        self.changed = Some(HashSet::new());
+    }
+
+    /// Marks every field as changed, so updates send the whole record.
+    pub fn mark_all_dirty(&mut self) {
+        self.changed = None;
+    }
+
+    /// Returns a record holding only the changed fields.
+    ///
+    /// This is what updates send unless asked for the full payload.
+    pub fn changes(&self) -> Record {
+        let mut changes = Record {
+            entity: self.entity.clone(),
+            id: self.id,
+            fields: HashMap::new(),
+            formatted_values: HashMap::new(),
+            annotations: HashMap::new(),
+            etag: self.etag.clone(),
+            changed: None,
+        };
+        for (field, value) in &self.fields {
+            if self.is_dirty(field) {
+                changes.fields.insert(field.clone(), value.clone());
+            }
+        }
+        changes
+    }
+
+    /// Marks a record read from Dataverse clean, along with its expanded
+    /// records.
+    pub(crate) fn mark_loaded(&mut self) {
+        self.mark_clean();
+        for value in self.fields.values_mut() {
+            match value {
+                Value::Record(record) => Arc::make_mut(record).mark_loaded(),
+                Value::Records(records) => records.iter_mut().for_each(Record::mark_loaded),
+                _ => {}
+            }
+        }
+    }
+
+    /// Records a change to a field.
+    fn touch(&mut self, field: &str) {
+        if let Some(changed) = &mut self.changed {
+            changed.insert(field.to_string());
+        }
+    }
+
+    // =========================================================================
+    // Annotations
+    // =========================================================================
+
     /// Returns the formatted value for a field, if available.
+    ///
+    /// For option sets this is the label, for lookups the referenced record's
+    /// name, and for numbers and dates the value in the user's format.
     pub fn get_formatted(&self, field: &str) -> Option<&str> {
         self.formatted_values.get(field).map(|s| s.as_str())
     }
@@ -147,23 +280,102 @@ impl Record {
         &self.formatted_values
     }
 
+    /// Returns an annotation on a field, if present.
+    ///
+    /// Lookup annotations are keyed by the lookup's name (`ownerid`), not
+    /// the raw `_ownerid_value` property.
+    ///
+    /// # Arguments
+    /// * `field` - The field name
+    /// * `term` - The annotation term, e.g. [`LOOKUP_LOGICAL_NAME`]
+    pub fn annotation(&self, field: &str, term: &str) -> Option<&str> {
+        if term == FORMATTED_VALUE {
+            return self.get_formatted(field);
+        }
+        self.annotations
+            .get(field)
+            .and_then(|terms| terms.get(term))
+            .map(|s| s.as_str())
+    }
+
+    /// Sets an annotation on a field.
+    pub fn set_annotation(
+        &mut self,
+        field: impl Into<String>,
+        term: impl Into<String>,
+        value: impl Into<String>,
+    ) {
+        let (field, term) = (field.into(), term.into());
+        if term == FORMATTED_VALUE {
+            self.formatted_values.insert(field, value.into());
+            return;
+        }
+        self.annotations
+            .entry(field)
+            .or_default()
+            .insert(term, value.into());
+    }
+
+    /// Returns what the record knows about a lookup field.
+    ///
+    /// Combines the lookup's value with its annotations. Returns `None` if
+    /// the field isn't a lookup, or is a null lookup (which Dataverse doesn't
+    /// annotate).
+    pub fn lookup_info(&self, field: &str) -> Option<LookupInfo> {
+        let reference = match self.fields.get(field) {
+            Some(Value::EntityReference(r)) => Some(r),
+            _ => None,
+        };
+        let info = LookupInfo {
+            id: reference.map(|r| r.id),
+            logical_name: self
+                .annotation(field, LOOKUP_LOGICAL_NAME)
+                .map(str::to_string)
+                .or_else(|| {
+                    reference
+                        .map(|r| r.entity.name())
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                }),
+            formatted_value: self
+                .get_formatted(field)
+                .map(str::to_string)
+                .or_else(|| reference.and_then(|r| r.name.clone())),
+            navigation_property: self
+                .annotation(field, ASSOCIATED_NAVIGATION_PROPERTY)
+                .map(str::to_string),
+        };
+        // A formatted value alone doesn't make a field a lookup
+        let is_lookup =
+            info.id.is_some() || info.logical_name.is_some() || info.navigation_property.is_some();
+        is_lookup.then_some(info)
+    }
+
     // =========================================================================
     // Setters
     // =========================================================================
 
     /// Sets a field value (builder pattern).
     pub fn set(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
-        self.fields.insert(field.into(), value.into());
+        self.insert(field, value);
         self
     }
 
     /// Inserts a field value.
     pub fn insert(&mut self, field: impl Into<String>, value: impl Into<Value>) {
-        self.fields.insert(field.into(), value.into());
+        let field = field.into();
+        self.touch(&field);
+        self.fields.insert(field, value.into());
     }
 
     /// Removes a field and returns its value.
+    ///
+    /// The field is no longer sent on update; to clear it in Dataverse, set
+    /// it to [`Value::Null`] instead.
     pub fn remove(&mut self, field: &str) -> Option<Value> {
+        if let Some(changed) = &mut self.changed {
+            changed.remove(field);
+        }
         self.fields.remove(field)
     }
 
@@ -192,7 +404,7 @@ impl Record {
         // Format: field@odata.bind = "entity_set/$N"
         let bind_key = format!("{}@odata.bind", field.into());
         let bind_value = format!("{}/{}", entity_set, reference.as_ref_string());
-        self.fields.insert(bind_key, Value::String(bind_value));
+        self.insert(bind_key, Value::String(bind_value));
         self
     }
 
@@ -249,21 +461,35 @@ impl Record {
     }
 
     /// Gets an f64 field value.
+    ///
+    /// Numbers read from the Web API with more digits than `f64` holds are
+    /// kept as decimals; those are rounded to the nearest float.
     pub fn get_float(&self, field: &str) -> Result<Option<f64>, FieldError> {
         match self.fields.get(field) {
             None => Err(FieldError::missing(field)),
             Some(Value::Null) => Ok(None),
             Some(Value::Float(n)) => Ok(Some(*n)),
+            Some(Value::Decimal(d)) => Ok(d.to_f64()),
             Some(other) => Err(FieldError::type_mismatch(field, "float", other.type_name())),
         }
     }
 
     /// Gets a Decimal field value.
+    ///
+    /// Numbers read from the Web API arrive as integers, as floats when `f64`
+    /// holds them exactly, or as decimals otherwise; all are converted
+    /// exactly (see [`decimal_from_f64`]).
     pub fn get_decimal(&self, field: &str) -> Result<Option<Decimal>, FieldError> {
         match self.fields.get(field) {
             None => Err(FieldError::missing(field)),
             Some(Value::Null) => Ok(None),
             Some(Value::Decimal(d)) => Ok(Some(*d)),
+            Some(Value::Money(m)) => Ok(Some(m.0)),
+            Some(Value::Int(n)) => Ok(Some(Decimal::from(*n))),
+            Some(Value::Long(n)) => Ok(Some(Decimal::from(*n))),
+            Some(Value::Float(n)) => decimal_from_f64(*n)
+                .map(Some)
+                .ok_or_else(|| FieldError::invalid_value(field, format!("{} is not a decimal", n))),
             Some(other) => Err(FieldError::type_mismatch(
                 field,
                 "decimal",
@@ -297,11 +523,17 @@ impl Record {
     }
 
     /// Gets a Money field value.
+    ///
+    /// Currency columns read from the Web API arrive as plain numbers; those
+    /// are converted exactly, as with [`get_decimal`](Self::get_decimal).
     pub fn get_money(&self, field: &str) -> Result<Option<Money>, FieldError> {
         match self.fields.get(field) {
+            Some(Value::Money(m)) => Ok(Some(*m)),
+            Some(Value::Int(_) | Value::Long(_) | Value::Float(_) | Value::Decimal(_)) => {
+                self.get_decimal(field).map(|d| d.map(Money))
+            }
             None => Err(FieldError::missing(field)),
             Some(Value::Null) => Ok(None),
-            Some(Value::Money(m)) => Ok(Some(*m)),
             Some(other) => Err(FieldError::type_mismatch(field, "money", other.type_name())),
         }
     }
@@ -388,6 +620,22 @@ impl Record {
         }
     }
 
+    /// Gets the thumbnail of an image column, decoded from the base64 string
+    /// the Web API returns for it.
+    ///
+    /// Only the thumbnail comes back with the record; use
+    /// [`fetch_image`](Self::fetch_image) for the full-size image.
+    pub fn get_image_thumbnail(&self, field: &str) -> Result<Option<Vec<u8>>, FieldError> {
+        match self.fields.get(field) {
+            None => Err(FieldError::missing(field)),
+            Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => STANDARD.decode(s).map(Some).map_err(|e| {
+                FieldError::invalid_value(field, format!("invalid image data: {}", e))
+            }),
+            Some(other) => Err(FieldError::type_mismatch(field, "image", other.type_name())),
+        }
+    }
+
     /// Gets a nested Record field value (from expanded navigation property).
     pub fn get_record(&self, field: &str) -> Result<Option<&Record>, FieldError> {
         match self.fields.get(field) {
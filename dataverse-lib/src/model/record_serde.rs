@@ -8,7 +8,7 @@
 //! - Regular fields serialize normally: `"name": "Contoso"`
 //! - EntityBinding serializes as: `"field@odata.bind": "/entities(guid)"`
 //! - OptionSet serializes as just the value: `"statecode": 0`
-//! - Money serializes as decimal: `"revenue": 1000000.00`
+//! - Money and Decimal serialize as exact JSON numbers: `"revenue": 1000000.00`
 //!
 //! ### Read Format (Deserialization)
 //!
@@ -18,6 +18,12 @@
 //! - Formatted values: `"field@OData.Community.Display.V1.FormattedValue": "Display Text"`
 //! - ETag: `"@odata.etag": "W/\"12345\""`
 //! - Expanded lookups: nested objects parsed as Records with entity from annotation
+//! - Numbers are read from their JSON text: integers become `Int` or `Long`,
+//!   and fractions become `Float` when `f64` holds them exactly, `Decimal`
+//!   otherwise
+//!
+//! Both directions go through `serde_json`'s `RawValue` to keep decimal digits
+//! that `f64` can't hold, so this format is only supported by `serde_json`.
 //!
 //! ## Binary Format (is_human_readable = false)
 //!
@@ -25,22 +31,32 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
 use serde::Deserialize;
 use serde::Deserializer;
 use std::sync::Arc;
 
+use rust_decimal::Decimal;
 use serde::Serialize;
 use serde::Serializer;
+use serde::de::DeserializeOwned;
+use serde::de::Error as _;
 use serde::de::MapAccess;
 use serde::de::Visitor;
+use serde::ser::Error as _;
 use serde::ser::SerializeMap;
+use serde_json::value::RawValue;
 use uuid::Uuid;
 
 use super::Entity;
+use super::FORMATTED_VALUE;
+use super::LOOKUP_LOGICAL_NAME;
 use super::Record;
 use super::Value;
 use super::types::EntityReference;
+use super::types::Money;
+use super::types::decimal_from_f64;
 
 // =============================================================================
 // Binary format helper (for bincode)
@@ -53,6 +69,7 @@ struct BinaryRecord {
     id: Option<Uuid>,
     fields: HashMap<String, Value>,
     formatted_values: HashMap<String, String>,
+    annotations: HashMap<String, HashMap<String, String>>,
     etag: Option<String>,
 }
 
@@ -91,6 +108,12 @@ impl Serialize for Record {
                             .join(",");
                         map.serialize_entry(key, &csv)?;
                     }
+                    // Decimals serialize as JSON numbers with all their digits
+                    Value::Decimal(d) | Value::Money(Money(d)) => {
+                        let number =
+                            RawValue::from_string(d.to_string()).map_err(S::Error::custom)?;
+                        map.serialize_entry(key, &number)?;
+                    }
                     // Null values serialize as JSON null (explicitly clears the field)
                     Value::Null => {
                         map.serialize_entry(key, &())?;
@@ -110,6 +133,7 @@ impl Serialize for Record {
                 id: self.id,
                 fields: self.fields.clone(),
                 formatted_values: self.formatted_values.clone(),
+                annotations: self.annotations.clone(),
                 etag: self.etag.clone(),
             };
             binary.serialize(serializer)
@@ -137,7 +161,9 @@ impl<'de> Deserialize<'de> for Record {
                 id: binary.id,
                 fields: binary.fields,
                 formatted_values: binary.formatted_values,
+                annotations: binary.annotations,
                 etag: binary.etag,
+                changed: None,
             })
         }
     }
@@ -160,11 +186,19 @@ impl<'de> Visitor<'de> for RecordVisitor {
         let mut etag: Option<String> = None;
         let mut formatted_values: HashMap<String, String> = HashMap::new();
         let mut lookup_logical_names: HashMap<String, String> = HashMap::new();
-        let mut raw_fields: HashMap<String, serde_json::Value> = HashMap::new();
+        let mut annotations: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut raw_fields: HashMap<String, Box<RawValue>> = HashMap::new();
 
         // First pass: collect all key-value pairs
         while let Some(key) = map.next_key::<String>()? {
-            let value: serde_json::Value = map.next_value()?;
+            let raw: Box<RawValue> = map.next_value()?;
+            if !key.contains('@') {
+                // Regular field, parsed from its text below
+                raw_fields.insert(key, raw);
+                continue;
+            }
+            let value: serde_json::Value =
+                serde_json::from_str(raw.get()).map_err(M::Error::custom)?;
 
             if key == "@odata.etag" {
                 if let serde_json::Value::String(s) = value {
@@ -184,15 +218,24 @@ impl<'de> Visitor<'de> for RecordVisitor {
                     .strip_suffix("@Microsoft.Dynamics.CRM.lookuplogicalname")
                     .unwrap_or(&key);
                 if let serde_json::Value::String(s) = value {
-                    lookup_logical_names.insert(field_name.to_string(), s);
+                    lookup_logical_names.insert(field_name.to_string(), s.clone());
+                    annotations
+                        .entry(field_name.to_string())
+                        .or_default()
+                        .insert(LOOKUP_LOGICAL_NAME.to_string(), s);
                 }
-            } else if key.contains("@Microsoft.Dynamics.CRM.associatednavigationproperty") {
-                // Skip navigation property annotations
-            } else if key.starts_with("@odata.") || key.starts_with("@Microsoft.") {
-                // Skip other OData annotations
-            } else {
-                // Regular field
-                raw_fields.insert(key, value);
+            } else if key.starts_with('@') {
+                // Skip record-level OData annotations
+            } else if let Some((field_name, term)) = key.split_once('@') {
+                // Any other field annotation, kept as text
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                annotations
+                    .entry(field_name.to_string())
+                    .or_default()
+                    .insert(term.to_string(), value);
             }
         }
 
@@ -201,7 +244,10 @@ impl<'de> Visitor<'de> for RecordVisitor {
         // This way expanded Records take precedence over EntityReferences
         let mut processed_lookups: HashMap<String, Value> = HashMap::new();
 
-        for (key, json_value) in raw_fields {
+        for (key, raw) in raw_fields {
+            let json_value: serde_json::Value =
+                serde_json::from_str(raw.get()).map_err(M::Error::custom)?;
+
             if key.starts_with('_') && key.ends_with("_value") {
                 // This is a lookup field: _fieldname_value
                 // Store for later - expanded object may override
@@ -230,18 +276,18 @@ impl<'de> Visitor<'de> for RecordVisitor {
                             Value::String(guid_str)
                         }
                     }
-                    other => json_value_to_value(other, &lookup_logical_names),
+                    other => json_value_to_value(other, &raw),
                 };
 
                 processed_lookups.insert(clean_key, value);
-            } else if let serde_json::Value::Object(obj) = json_value {
+            } else if json_value.is_object() {
                 // This could be an expanded lookup - check for entity type
                 // The entity type comes from _fieldname_value annotation
                 // For polymorphic lookups, the key may be like "parentcustomerid_account"
                 // but the annotation is on "_parentcustomerid_value"
                 let entity = find_entity_for_expanded_key(&key, &lookup_logical_names);
 
-                let nested_record = json_object_to_record(obj, entity, &lookup_logical_names);
+                let nested_record = json_object_to_record(&raw, entity);
                 record
                     .fields
                     .insert(key.clone(), Value::Record(Arc::new(nested_record)));
@@ -250,44 +296,28 @@ impl<'de> Visitor<'de> for RecordVisitor {
                 if let Some(formatted) = formatted_values.remove(&key) {
                     record.formatted_values.insert(key, formatted);
                 }
-            } else if let serde_json::Value::Array(arr) = json_value {
-                // Could be a collection navigation property (expanded 1:N)
+            } else if json_value
+                .as_array()
+                .is_some_and(|arr| arr.iter().all(serde_json::Value::is_object))
+            {
+                // A collection navigation property (expanded 1:N or N:N), which
+                // may have no related records
                 let entity = find_entity_for_expanded_key(&key, &lookup_logical_names);
 
-                let records: Vec<Record> = arr
-                    .into_iter()
-                    .filter_map(|v| {
-                        if let serde_json::Value::Object(obj) = v {
-                            Some(json_object_to_record(
-                                obj,
-                                entity.clone(),
-                                &lookup_logical_names,
-                            ))
-                        } else {
-                            None
-                        }
-                    })
+                let items: Vec<Box<RawValue>> =
+                    serde_json::from_str(raw.get()).map_err(M::Error::custom)?;
+                let records: Vec<Record> = items
+                    .iter()
+                    .map(|item| json_object_to_record(item, entity.clone()))
                     .collect();
-
-                if !records.is_empty() {
-                    record.fields.insert(key.clone(), Value::Records(records));
-                } else {
-                    // Not a record array, keep as JSON
-                    record.fields.insert(
-                        key.clone(),
-                        json_value_to_value(
-                            serde_json::Value::Array(vec![]), // Empty, original consumed
-                            &lookup_logical_names,
-                        ),
-                    );
-                }
+                record.fields.insert(key.clone(), Value::Records(records));
 
                 if let Some(formatted) = formatted_values.remove(&key) {
                     record.formatted_values.insert(key, formatted);
                 }
             } else {
                 // Regular field
-                let value = json_value_to_value(json_value, &lookup_logical_names);
+                let value = json_value_to_value(json_value, &raw);
                 record.fields.insert(key.clone(), value);
 
                 if let Some(formatted) = formatted_values.remove(&key) {
@@ -317,10 +347,32 @@ impl<'de> Visitor<'de> for RecordVisitor {
             }
         }
 
+        // Annotations on lookups are keyed by the clean field name too
+        for (key, terms) in annotations {
+            let key = match key.strip_prefix('_').and_then(|k| k.strip_suffix("_value")) {
+                Some(clean) => clean.to_string(),
+                None => key,
+            };
+            record.annotations.entry(key).or_default().extend(terms);
+        }
+
+        // Not loaded by the client, so every field counts as changed
+        record.mark_all_dirty();
         Ok(record)
     }
 }
 
+/// Deserializes records read from Dataverse, marked clean so updates send
+/// only later changes.
+pub(crate) fn loaded_records<'de, D>(deserializer: D) -> Result<Vec<Record>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut records = Vec::<Record>::deserialize(deserializer)?;
+    records.iter_mut().for_each(Record::mark_loaded);
+    Ok(records)
+}
+
 /// Find the entity type for an expanded navigation property key.
 ///
 /// For regular lookups, the key is the same as the field name (e.g., "primarycontactid")
@@ -361,24 +413,17 @@ fn find_entity_for_expanded_key(
 }
 
 /// Converts a JSON object to a Record with the given entity type.
-fn json_object_to_record(
-    obj: serde_json::Map<String, serde_json::Value>,
-    entity: Entity,
-    _parent_lookup_names: &HashMap<String, String>,
-) -> Record {
+fn json_object_to_record(raw: &RawValue, entity: Entity) -> Record {
     // Re-parse the object through our normal deserialization
     // This handles nested annotations properly
-    let json_value = serde_json::Value::Object(obj);
-    let mut nested: Record = serde_json::from_value(json_value).unwrap_or_else(|_| Record::new(""));
+    let mut nested: Record = serde_json::from_str(raw.get()).unwrap_or_else(|_| Record::new(""));
     nested.entity = entity;
     nested
 }
 
-/// Converts a serde_json::Value to our Value enum.
-fn json_value_to_value(
-    json: serde_json::Value,
-    lookup_logical_names: &HashMap<String, String>,
-) -> Value {
+/// Converts a serde_json::Value to our Value enum, reading numbers from
+/// their JSON text.
+fn json_value_to_value(json: serde_json::Value, raw: &RawValue) -> Value {
     match json {
         serde_json::Value::Null => Value::Null,
         serde_json::Value::Bool(b) => Value::Bool(b),
@@ -390,7 +435,11 @@ fn json_value_to_value(
                     Value::Long(i)
                 }
             } else if let Some(f) = n.as_f64() {
-                Value::Float(f)
+                // Keep digits f64 would drop, e.g. 28-digit currency amounts
+                match Decimal::from_str(raw.get()) {
+                    Ok(d) if decimal_from_f64(f) != Some(d) => Value::Decimal(d),
+                    _ => Value::Float(f),
+                }
             } else {
                 Value::Json(serde_json::Value::Number(n))
             }
@@ -413,20 +462,121 @@ fn json_value_to_value(
             // Could be collection of records or other array
             Value::Json(serde_json::Value::Array(arr))
         }
-        serde_json::Value::Object(obj) => {
+        serde_json::Value::Object(_) => {
             // Parse as a nested record with unknown entity type
-            let nested =
-                json_object_to_record(obj, Entity::Logical(String::new()), lookup_logical_names);
+            let nested = json_object_to_record(raw, Entity::Logical(String::new()));
             Value::Record(Arc::new(nested))
         }
     }
 }
 
+// =============================================================================
+// Typed deserialization
+// =============================================================================
+
+impl Record {
+    /// Deserializes the record into a serde type.
+    ///
+    /// The type reads from a flat JSON object of the record's fields:
+    /// lookups hold the referenced id, option sets their value and money its
+    /// amount, and expanded records are nested objects. Annotations are
+    /// available as `<field>@<term>`, as in Web API responses, so a lookup's
+    /// name is read with
+    /// `#[serde(rename = "ownerid@OData.Community.Display.V1.FormattedValue")]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dataverse_lib::model::Record;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Row {
+    ///     name: String,
+    ///     #[serde(rename = "statuscode@OData.Community.Display.V1.FormattedValue")]
+    ///     status: Option<String>,
+    /// }
+    ///
+    /// let mut record = Record::new("account").set("name", "Contoso").set("statuscode", 1);
+    /// record.set_formatted("statuscode", "Active");
+    ///
+    /// let row: Row = record.deserialize_into().unwrap();
+    /// assert_eq!(row.name, "Contoso");
+    /// assert_eq!(row.status.as_deref(), Some("Active"));
+    /// ```
+    pub fn deserialize_into<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(serde_json::Value::Object(self.to_json_view()))
+    }
+
+    /// Returns the flat JSON object read by
+    /// [`deserialize_into`](Self::deserialize_into).
+    pub(crate) fn to_json_view(&self) -> serde_json::Map<String, serde_json::Value> {
+        json_view(self)
+    }
+}
+
+/// Builds the flat JSON object read by [`Record::deserialize_into`].
+fn json_view(record: &Record) -> serde_json::Map<String, serde_json::Value> {
+    let mut object = serde_json::Map::new();
+    for (field, value) in &record.fields {
+        object.insert(field.clone(), json_view_value(value));
+    }
+    for (field, terms) in &record.annotations {
+        for (term, value) in terms {
+            object.insert(format!("{field}@{term}"), value.clone().into());
+        }
+    }
+    for (field, value) in &record.formatted_values {
+        object.insert(format!("{field}@{FORMATTED_VALUE}"), value.clone().into());
+    }
+
+    // Lookups built from references rather than responses carry their
+    // target and name on the reference itself
+    for field in record.fields.keys() {
+        let Some(info) = record.lookup_info(field) else {
+            continue;
+        };
+        if let Some(name) = info.logical_name {
+            object
+                .entry(format!("{field}@{LOOKUP_LOGICAL_NAME}"))
+                .or_insert(name.into());
+        }
+        if let Some(name) = info.formatted_value {
+            object
+                .entry(format!("{field}@{FORMATTED_VALUE}"))
+                .or_insert(name.into());
+        }
+    }
+
+    if let Some(etag) = &record.etag {
+        object.insert("@odata.etag".to_string(), etag.clone().into());
+    }
+    object
+}
+
+fn json_view_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::EntityReference(reference) => reference.id.to_string().into(),
+        Value::Record(record) => json_view(record).into(),
+        Value::Records(records) => records
+            .iter()
+            .map(|r| serde_json::Value::Object(json_view(r)))
+            .collect(),
+        Value::Decimal(d) | Value::Money(Money(d)) => d
+            .to_string()
+            .parse::<serde_json::Number>()
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        value => serde_json::to_value(value).unwrap_or(serde_json::Value::Null),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::model::types::EntityBinding;
+    use crate::model::types::Money;
     use crate::model::types::OptionSetValue;
+    use rust_decimal::Decimal;
 
     #[test]
     fn test_serialize_simple_fields() {
@@ -460,6 +610,66 @@ mod tests {
         assert!(json.contains("\"statecode\":0"));
     }
 
+    #[test]
+    fn test_serialize_money() {
+        let record = Record::new("account").set("revenue", Money::new(Decimal::new(100000001, 2)));
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains("\"revenue\":1000000.01"));
+
+        let record = Record::new("account").set("amount", Decimal::new(1234567890123456789, 4));
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains("\"amount\":123456789012345.6789"));
+    }
+
+    #[test]
+    fn test_deserialize_exact_decimals() {
+        let json = r#"{
+            "revenue": 1234567890123456.789,
+            "rate": 0.1,
+            "nested": {"amount": 98765432109876.54321},
+            "lines": [{"amount": 12345678901234567.8}]
+        }"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            record.get_money("revenue").unwrap(),
+            Some(Money::new(Decimal::new(1234567890123456789, 3)))
+        );
+        assert_eq!(
+            record.get_float("revenue").unwrap(),
+            Some(1234567890123456.8)
+        );
+        // Floats that f64 holds exactly stay floats
+        assert_eq!(record.get("rate"), Some(&Value::Float(0.1)));
+
+        let nested = record.get_record("nested").unwrap().unwrap();
+        assert_eq!(
+            nested.get_decimal("amount").unwrap(),
+            Some(Decimal::from_str("98765432109876.54321").unwrap())
+        );
+        let lines = record.get_records("lines").unwrap().unwrap();
+        assert_eq!(
+            lines[0].get_decimal("amount").unwrap(),
+            Some(Decimal::new(123456789012345678, 1))
+        );
+    }
+
+    #[test]
+    fn test_deserialize_money() {
+        let json = r#"{"revenue": 1000000.01, "creditlimit": 5000}"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            record.get_money("revenue").unwrap(),
+            Some(Money::new(Decimal::new(100000001, 2)))
+        );
+        assert_eq!(
+            record.get_decimal("creditlimit").unwrap(),
+            Some(Decimal::new(5000, 0))
+        );
+    }
+
     #[test]
     fn test_deserialize_simple_fields() {
         let json = r#"{"name": "Contoso", "revenue": 1000000}"#;
@@ -494,6 +704,83 @@ mod tests {
         assert_eq!(entity_ref.name, Some("John Smith".to_string()));
     }
 
+    #[test]
+    fn test_deserialize_annotations() {
+        let json = r#"{
+            "statuscode": 1,
+            "statuscode@OData.Community.Display.V1.FormattedValue": "Active",
+            "_ownerid_value": "12345678-1234-1234-1234-123456789012",
+            "_ownerid_value@Microsoft.Dynamics.CRM.lookuplogicalname": "systemuser",
+            "_ownerid_value@Microsoft.Dynamics.CRM.associatednavigationproperty": "owninguser",
+            "_ownerid_value@OData.Community.Display.V1.FormattedValue": "Jane Doe",
+            "contact_customer_accounts@odata.nextLink": "https://example.com/next",
+            "@odata.context": "https://example.com/$metadata#accounts/$entity"
+        }"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+
+        assert_eq!(record.get_formatted("statuscode"), Some("Active"));
+        assert_eq!(
+            record.annotation("statuscode", FORMATTED_VALUE),
+            Some("Active")
+        );
+        assert_eq!(
+            record.annotation("contact_customer_accounts", "odata.nextLink"),
+            Some("https://example.com/next")
+        );
+        assert!(!record.contains("contact_customer_accounts@odata.nextLink"));
+        assert!(!record.contains("@odata.context"));
+
+        let owner = record.lookup_info("ownerid").unwrap();
+        assert_eq!(
+            owner.id,
+            Some(Uuid::parse_str("12345678-1234-1234-1234-123456789012").unwrap())
+        );
+        assert_eq!(owner.logical_name.as_deref(), Some("systemuser"));
+        assert_eq!(owner.formatted_value.as_deref(), Some("Jane Doe"));
+        assert_eq!(owner.navigation_property.as_deref(), Some("owninguser"));
+        assert_eq!(record.lookup_info("statuscode"), None);
+    }
+
+    #[test]
+    fn test_annotations_survive_binary_roundtrip() {
+        let mut record = Record::new("account");
+        record.set_annotation("ownerid", LOOKUP_LOGICAL_NAME, "team");
+
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&record, config).unwrap();
+        let (decoded, _): (Record, _) = bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(
+            decoded.annotation("ownerid", LOOKUP_LOGICAL_NAME),
+            Some("team")
+        );
+    }
+
+    #[test]
+    fn test_deserialized_records_track_changes() {
+        let json = r#"{"name": "Contoso", "revenue": 1000000}"#;
+        let mut record: Record = serde_json::from_str(json).unwrap();
+        assert_eq!(record.dirty_fields().len(), 2);
+
+        record.mark_clean();
+        assert!(!record.has_changes());
+        record.insert("name", "Fabrikam");
+        assert!(record.is_dirty("name"));
+        assert!(!record.is_dirty("revenue"));
+        assert_eq!(
+            serde_json::to_string(&record.changes()).unwrap(),
+            r#"{"name":"Fabrikam"}"#
+        );
+
+        record.fields_mut().remove("revenue");
+        assert_eq!(record.dirty_fields(), vec!["name"]);
+        record.mark_clean();
+        assert!(record.dirty_fields().is_empty());
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&record, config).unwrap();
+        let (decoded, _): (Record, _) = bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(decoded.dirty_fields(), vec!["name"]);
+    }
+
     #[test]
     fn test_deserialize_formatted_value() {
         let json = r#"{
@@ -566,4 +853,132 @@ mod tests {
             Some("Jane Doe")
         );
     }
+
+    #[test]
+    fn test_deserialize_expanded_collection() {
+        let json = r#"{
+            "accountid": "12345678-1234-1234-1234-123456789012",
+            "contact_customer_accounts": [
+                {
+                    "contactid": "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee",
+                    "fullname": "John Smith",
+                    "Contact_Tasks": []
+                }
+            ],
+            "contact_customer_accounts@odata.nextLink": "https://org.crm.dynamics.com/next",
+            "Account_Tasks": [],
+            "tags": ["a", "b"]
+        }"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+
+        let contacts = record
+            .get_records("contact_customer_accounts")
+            .unwrap()
+            .unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(
+            contacts[0].get_string("fullname").unwrap(),
+            Some("John Smith")
+        );
+        assert_eq!(
+            contacts[0]
+                .get_records("Contact_Tasks")
+                .unwrap()
+                .unwrap()
+                .len(),
+            0
+        );
+        assert_eq!(
+            record.annotation("contact_customer_accounts", "odata.nextLink"),
+            Some("https://org.crm.dynamics.com/next")
+        );
+
+        // Empty collections are still record lists; other arrays are kept
+        assert!(
+            record
+                .get_records("Account_Tasks")
+                .unwrap()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(record.get("tags"), Some(Value::Json(_))));
+    }
+
+    #[test]
+    fn test_deserialize_into() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Account {
+            accountid: Uuid,
+            name: String,
+            revenue: Decimal,
+            statuscode: i32,
+            #[serde(rename = "statuscode@OData.Community.Display.V1.FormattedValue")]
+            status: String,
+            #[serde(rename = "ownerid")]
+            owner: Uuid,
+            #[serde(rename = "ownerid@OData.Community.Display.V1.FormattedValue")]
+            owner_name: String,
+            #[serde(rename = "ownerid@Microsoft.Dynamics.CRM.lookuplogicalname")]
+            owner_type: String,
+            primarycontactid: Option<Contact>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Contact {
+            fullname: String,
+        }
+
+        let json = r#"{
+            "@odata.etag": "W/\"1\"",
+            "accountid": "aaaaaaaa-1234-1234-1234-123456789012",
+            "name": "Contoso",
+            "revenue": 1000.5,
+            "statuscode": 1,
+            "statuscode@OData.Community.Display.V1.FormattedValue": "Active",
+            "_ownerid_value": "12345678-1234-1234-1234-123456789012",
+            "_ownerid_value@Microsoft.Dynamics.CRM.lookuplogicalname": "systemuser",
+            "_ownerid_value@OData.Community.Display.V1.FormattedValue": "Jane Doe",
+            "primarycontactid": {"contactid": "bbbbbbbb-1234-1234-1234-123456789012", "fullname": "John Smith"}
+        }"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+
+        let account: Account = record.deserialize_into().unwrap();
+        assert_eq!(
+            account.accountid,
+            record.get_guid("accountid").unwrap().unwrap()
+        );
+        assert_eq!(account.name, "Contoso");
+        assert_eq!(account.revenue, Decimal::new(10005, 1));
+        assert_eq!(account.statuscode, 1);
+        assert_eq!(account.status, "Active");
+        assert_eq!(
+            account.owner,
+            Uuid::parse_str("12345678-1234-1234-1234-123456789012").unwrap()
+        );
+        assert_eq!(account.owner_name, "Jane Doe");
+        assert_eq!(account.owner_type, "systemuser");
+        assert_eq!(account.primarycontactid.unwrap().fullname, "John Smith");
+
+        // References set in code are annotated from the reference
+        let record = Record::new("account").set(
+            "ownerid",
+            EntityReference::with_name(Entity::logical("team"), Uuid::nil(), "Sales"),
+        );
+        let owner: serde_json::Value = record.deserialize_into().unwrap();
+        assert_eq!(owner["ownerid"], Uuid::nil().to_string());
+        assert_eq!(
+            owner["ownerid@OData.Community.Display.V1.FormattedValue"],
+            "Sales"
+        );
+        assert_eq!(
+            owner["ownerid@Microsoft.Dynamics.CRM.lookuplogicalname"],
+            "team"
+        );
+
+        assert!(
+            Record::new("account")
+                .deserialize_into::<Contact>()
+                .is_err()
+        );
+    }
 }
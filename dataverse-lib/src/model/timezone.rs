@@ -0,0 +1,383 @@
+//! User time zones and `DateTimeBehavior`-aware date/time conversion
+
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::NaiveTime;
+use chrono::TimeDelta;
+use chrono::Utc;
+use chrono::Weekday;
+
+use super::Record;
+use super::Value;
+use crate::error::FieldError;
+use crate::model::metadata::DateTimeBehaviorValue;
+use crate::model::metadata::EntityMetadata;
+
+/// Format of date-only values on the wire.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Days of the week as Dataverse numbers them (Sunday is 0).
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Sun,
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+];
+
+/// When a time zone switches between standard and daylight time, e.g. "the
+/// last Sunday of March at 02:00".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionRule {
+    /// The month, `1` to `12`.
+    pub month: u32,
+    /// The week of the month, `1` to `4`, or `5` for the last.
+    pub week: u32,
+    /// The day of the week.
+    pub weekday: Weekday,
+    /// The local time of the switch.
+    pub time: NaiveTime,
+}
+
+impl TransitionRule {
+    /// Returns the local date and time of the switch in a year.
+    fn in_year(&self, year: i32) -> Option<NaiveDateTime> {
+        let date =
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week as u8)
+                // Week 5 means the last, which may be the 4th
+                .or_else(|| {
+                    (self.week >= 5)
+                        .then(|| {
+                            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, 4)
+                        })
+                        .flatten()
+                })?;
+        Some(date.and_time(self.time))
+    }
+}
+
+/// A user's time zone, from their `usersettings` record.
+///
+/// Dataverse describes time zones the way Windows does: a bias from UTC
+/// and, for zones observing daylight saving time, when it starts and ends.
+/// The default is UTC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserTimeZone {
+    /// The Dataverse time zone code (`timezonecode`).
+    pub code: Option<i32>,
+    /// Minutes to add to local time to get UTC (`timezonebias`).
+    pub bias: i32,
+    /// Extra bias during standard time (`timezonestandardbias`).
+    pub standard_bias: i32,
+    /// Extra bias during daylight saving time (`timezonedaylightbias`).
+    pub daylight_bias: i32,
+    /// When daylight saving time starts, in local standard time.
+    pub daylight_start: Option<TransitionRule>,
+    /// When standard time starts again, in local daylight time.
+    pub standard_start: Option<TransitionRule>,
+}
+
+impl UserTimeZone {
+    /// Columns of `usersettings` describing the time zone.
+    pub(crate) const COLUMNS: [&'static str; 14] = [
+        "timezonecode",
+        "timezonebias",
+        "timezonestandardbias",
+        "timezonedaylightbias",
+        "timezonedaylightmonth",
+        "timezonedaylightday",
+        "timezonedaylightdayofweek",
+        "timezonedaylighthour",
+        "timezonedaylightminute",
+        "timezonestandardmonth",
+        "timezonestandardday",
+        "timezonestandarddayofweek",
+        "timezonestandardhour",
+        "timezonestandardminute",
+    ];
+
+    /// Reads the time zone from a `usersettings` record.
+    pub fn from_user_settings(settings: &Record) -> Self {
+        let int = |column: &str| settings.get_int(column).ok().flatten();
+        let rule = |prefix: &str| {
+            let month =
+                int(&format!("timezone{}month", prefix)).filter(|m| (1..=12).contains(m))?;
+            let weekday = int(&format!("timezone{}dayofweek", prefix))?;
+            let time = NaiveTime::from_hms_opt(
+                int(&format!("timezone{}hour", prefix)).unwrap_or(0) as u32,
+                int(&format!("timezone{}minute", prefix)).unwrap_or(0) as u32,
+                0,
+            )?;
+            Some(TransitionRule {
+                month: month as u32,
+                week: int(&format!("timezone{}day", prefix))
+                    .unwrap_or(1)
+                    .clamp(1, 5) as u32,
+                weekday: *WEEKDAYS.get(weekday as usize)?,
+                time,
+            })
+        };
+        Self {
+            code: int("timezonecode"),
+            bias: int("timezonebias").unwrap_or(0),
+            standard_bias: int("timezonestandardbias").unwrap_or(0),
+            daylight_bias: int("timezonedaylightbias").unwrap_or(0),
+            daylight_start: rule("daylight"),
+            standard_start: rule("standard"),
+        }
+    }
+
+    /// Returns `true` if daylight saving time applies at an instant.
+    fn is_daylight(&self, utc: DateTime<Utc>) -> bool {
+        let (Some(daylight), Some(standard)) = (self.daylight_start, self.standard_start) else {
+            return false;
+        };
+        let utc = utc.naive_utc();
+        let year = (utc - self.offset(self.standard_bias)).year();
+        let (Some(starts), Some(ends)) = (daylight.in_year(year), standard.in_year(year)) else {
+            return false;
+        };
+        let starts = starts + self.offset(self.standard_bias);
+        let ends = ends + self.offset(self.daylight_bias);
+        if starts < ends {
+            utc >= starts && utc < ends
+        } else {
+            // Southern hemisphere: daylight time spans the new year
+            utc >= starts || utc < ends
+        }
+    }
+
+    /// Returns the difference between local time and UTC with an extra bias.
+    fn offset(&self, extra_bias: i32) -> TimeDelta {
+        TimeDelta::minutes((self.bias + extra_bias) as i64)
+    }
+
+    /// Converts an instant to local time.
+    pub fn to_local(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        let extra_bias = if self.is_daylight(utc) {
+            self.daylight_bias
+        } else {
+            self.standard_bias
+        };
+        utc.naive_utc() - self.offset(extra_bias)
+    }
+
+    /// Converts a local time to an instant.
+    ///
+    /// Local times repeated when clocks go back resolve to the first;
+    /// those skipped when clocks go forward are taken as standard time.
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let daylight = (local + self.offset(self.daylight_bias)).and_utc();
+        let standard = (local + self.offset(self.standard_bias)).and_utc();
+        if self.is_daylight(daylight) && self.to_local(daylight) == local {
+            daylight
+        } else {
+            standard
+        }
+    }
+}
+
+/// Reads and writes date/time fields of an entity the way their
+/// `DateTimeBehavior` says.
+///
+/// * `UserLocal` values are stored in UTC and shown in the user's time
+///   zone, so they are converted.
+/// * `DateOnly` values are calendar dates, read as midnight and written
+///   without a time.
+/// * `TimeZoneIndependent` values are the same wall-clock time everywhere,
+///   so they are never converted.
+///
+/// Fields without a known behavior are treated as `UserLocal`, Dataverse's
+/// default.
+///
+/// # Example
+///
+/// ```ignore
+/// let dates = client.datetime_converter(Entity::logical("contact")).await?;
+/// let birthday = dates.read(&contact, "birthdate")?;
+/// dates.set(&mut contact, "lastonholdtime", Local::now().naive_local());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DateTimeConverter {
+    time_zone: UserTimeZone,
+    behaviors: HashMap<String, DateTimeBehaviorValue>,
+}
+
+impl DateTimeConverter {
+    /// Creates a converter for a time zone, with no known behaviors.
+    pub fn new(time_zone: UserTimeZone) -> Self {
+        Self {
+            time_zone,
+            behaviors: HashMap::new(),
+        }
+    }
+
+    /// Creates a converter knowing the behavior of every date/time
+    /// attribute of an entity.
+    pub fn from_metadata(time_zone: UserTimeZone, metadata: &EntityMetadata) -> Self {
+        let behaviors = metadata
+            .attributes
+            .iter()
+            .filter_map(|a| {
+                let behavior = a.date_time_behavior.as_ref()?;
+                Some((a.logical_name.clone(), behavior.value))
+            })
+            .collect();
+        Self {
+            time_zone,
+            behaviors,
+        }
+    }
+
+    /// Sets the behavior of a field.
+    pub fn behavior(mut self, field: impl Into<String>, behavior: DateTimeBehaviorValue) -> Self {
+        self.behaviors.insert(field.into(), behavior);
+        self
+    }
+
+    /// Returns the time zone values are converted to and from.
+    pub fn time_zone(&self) -> &UserTimeZone {
+        &self.time_zone
+    }
+
+    /// Returns the behavior of a field.
+    pub fn behavior_of(&self, field: &str) -> DateTimeBehaviorValue {
+        self.behaviors
+            .get(field)
+            .copied()
+            .unwrap_or(DateTimeBehaviorValue::UserLocal)
+    }
+
+    /// Reads a date/time field as the user sees it.
+    pub fn read(&self, record: &Record, field: &str) -> Result<Option<NaiveDateTime>, FieldError> {
+        match record.get(field) {
+            None => Err(FieldError::missing(field)),
+            Some(Value::Null) => Ok(None),
+            Some(Value::DateTime(dt)) => Ok(Some(match self.behavior_of(field) {
+                DateTimeBehaviorValue::UserLocal => self.time_zone.to_local(*dt),
+                _ => dt.naive_utc(),
+            })),
+            Some(Value::String(s)) => NaiveDate::parse_from_str(s, DATE_FORMAT)
+                .map(|date| Some(date.and_time(NaiveTime::MIN)))
+                .map_err(|_| FieldError::type_mismatch(field, "datetime", "string")),
+            Some(other) => Err(FieldError::type_mismatch(
+                field,
+                "datetime",
+                other.type_name(),
+            )),
+        }
+    }
+
+    /// Converts a date/time as the user sees it to the value to write.
+    pub fn write(&self, field: &str, local: NaiveDateTime) -> Value {
+        match self.behavior_of(field) {
+            DateTimeBehaviorValue::UserLocal => Value::DateTime(self.time_zone.to_utc(local)),
+            DateTimeBehaviorValue::DateOnly => {
+                Value::String(local.date().format(DATE_FORMAT).to_string())
+            }
+            DateTimeBehaviorValue::TimeZoneIndependent => Value::DateTime(local.and_utc()),
+        }
+    }
+
+    /// Sets a date/time field from a date/time as the user sees it.
+    pub fn set(&self, record: &mut Record, field: &str, local: NaiveDateTime) {
+        record.insert(field, self.write(field, local));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Central European Time: UTC+1, daylight time from the last Sunday of
+    /// March at 02:00 to the last Sunday of October at 03:00.
+    fn cet() -> UserTimeZone {
+        let settings = Record::new("usersettings")
+            .set("timezonecode", 105)
+            .set("timezonebias", -60)
+            .set("timezonestandardbias", 0)
+            .set("timezonedaylightbias", -60)
+            .set("timezonedaylightmonth", 3)
+            .set("timezonedaylightday", 5)
+            .set("timezonedaylightdayofweek", 0)
+            .set("timezonedaylighthour", 2)
+            .set("timezonestandardmonth", 10)
+            .set("timezonestandardday", 5)
+            .set("timezonestandarddayofweek", 0)
+            .set("timezonestandardhour", 3);
+        UserTimeZone::from_user_settings(&settings)
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_user_local_conversion() {
+        let tz = cet();
+        assert_eq!(tz.code, Some(105));
+        let winter = at("2024-01-15 12:00").and_utc();
+        let summer = at("2024-07-15 12:00").and_utc();
+        assert_eq!(tz.to_local(winter), at("2024-01-15 13:00"));
+        assert_eq!(tz.to_local(summer), at("2024-07-15 14:00"));
+        assert_eq!(tz.to_utc(at("2024-07-15 14:00")), summer);
+        assert_eq!(tz.to_utc(at("2024-01-15 13:00")), winter);
+
+        // Clocks go forward on 31 March 2024 at 01:00 UTC
+        assert_eq!(
+            tz.to_local(at("2024-03-31 00:59").and_utc()),
+            at("2024-03-31 01:59")
+        );
+        assert_eq!(
+            tz.to_local(at("2024-03-31 01:00").and_utc()),
+            at("2024-03-31 03:00")
+        );
+    }
+
+    #[test]
+    fn test_behaviors() {
+        let dates = DateTimeConverter::new(cet())
+            .behavior("birthdate", DateTimeBehaviorValue::DateOnly)
+            .behavior("checkin", DateTimeBehaviorValue::TimeZoneIndependent);
+        let mut record: Record = serde_json::from_str(
+            r#"{
+                "createdon": "2024-07-15T12:00:00Z",
+                "birthdate": "1990-05-01",
+                "checkin": "2024-07-15T09:00:00Z"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dates.read(&record, "createdon").unwrap(),
+            Some(at("2024-07-15 14:00"))
+        );
+        assert_eq!(
+            dates.read(&record, "birthdate").unwrap(),
+            Some(at("1990-05-01 00:00"))
+        );
+        assert_eq!(
+            dates.read(&record, "checkin").unwrap(),
+            Some(at("2024-07-15 09:00"))
+        );
+
+        dates.set(&mut record, "birthdate", at("1991-02-03 10:00"));
+        dates.set(&mut record, "checkin", at("2024-07-16 09:00"));
+        dates.set(&mut record, "createdon", at("2024-07-16 14:00"));
+        assert_eq!(record.get_string("birthdate").unwrap(), Some("1991-02-03"));
+        assert_eq!(
+            record.get_datetime("checkin").unwrap(),
+            Some(at("2024-07-16 09:00").and_utc())
+        );
+        assert_eq!(
+            record.get_datetime("createdon").unwrap(),
+            Some(at("2024-07-16 12:00").and_utc())
+        );
+    }
+}
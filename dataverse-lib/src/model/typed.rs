@@ -0,0 +1,496 @@
+//! Typed entity mapping
+//!
+//! [`DataverseEntity`] maps a Rust struct to and from a [`Record`], usually
+//! via `#[derive(DataverseEntity)]`. [`FromValue`] converts individual field
+//! values, and [`AsRecordId`] extracts the id used for the primary key and
+//! lookup bindings.
+
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use uuid::Uuid;
+
+use super::Entity;
+use super::Record;
+use super::Value;
+use super::types::EntityBinding;
+use super::types::EntityReference;
+use super::types::Money;
+use super::types::MultiSelectOptionSetValue;
+use super::types::OptionSetValue;
+use super::types::decimal_from_f64;
+use crate::error::FieldError;
+
+/// A Rust type mapped to a Dataverse table.
+///
+/// Usually implemented with `#[derive(DataverseEntity)]`:
+///
+/// ```
+/// use dataverse_lib::model::DataverseEntity;
+/// use dataverse_lib::model::DataverseOptionSet;
+/// use uuid::Uuid;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, DataverseOptionSet)]
+/// enum Category {
+///     Preferred = 1,
+///     Standard = 2,
+/// }
+///
+/// #[derive(Debug, DataverseEntity)]
+/// #[dataverse(entity = "account", set = "accounts")]
+/// struct Account {
+///     #[dataverse(id)]
+///     id: Option<Uuid>,
+///     name: String,
+///     #[dataverse(rename = "accountcategorycode")]
+///     category: Option<Category>,
+///     #[dataverse(lookup = "contacts")]
+///     primarycontactid: Option<Uuid>,
+///     #[dataverse(read_only)]
+///     createdon: Option<chrono::DateTime<chrono::Utc>>,
+/// }
+///
+/// let account = Account {
+///     id: None,
+///     name: "Contoso".into(),
+///     category: Some(Category::Preferred),
+///     primarycontactid: None,
+///     createdon: None,
+/// };
+/// let record = account.to_record();
+/// assert_eq!(record.get_string("name").unwrap(), Some("Contoso"));
+/// ```
+///
+/// # Attributes
+///
+/// On the struct:
+/// - `entity = "..."` - the table's logical name (required)
+/// - `set = "..."` - the entity set name; without it, records are addressed by
+///   logical name and the set name is resolved from metadata
+///
+/// On fields:
+/// - `id` - the primary key; column defaults to `<entity>id`, must be a
+///   `Uuid` or `Option<Uuid>`
+/// - `rename = "..."` - the column's logical name (defaults to the field name)
+/// - `lookup = "..."` - a lookup to the given entity set, written as an
+///   `@odata.bind`; the field must implement [`AsRecordId`]
+/// - `nav = "..."` - the navigation property to bind lookups through, when it
+///   differs from the column name
+/// - `read_only` - read from records but never written
+/// - `skip` - not mapped; filled with `Default::default()` when reading
+///
+/// Field types implement [`FromValue`] for reading and `Into<Value>` (plus
+/// `Clone`) for writing. `Option<T>` fields accept null or missing columns.
+pub trait DataverseEntity: Sized {
+    /// The table's logical name.
+    const LOGICAL_NAME: &'static str;
+
+    /// Columns read by [`from_record`](Self::from_record), including the
+    /// primary key.
+    ///
+    /// Typed queries select these when no explicit `select` was given.
+    const COLUMNS: &'static [&'static str];
+
+    /// The entity used to address this table.
+    fn entity() -> Entity;
+
+    /// Builds a value from a record.
+    fn from_record(record: &Record) -> Result<Self, FieldError>;
+
+    /// Builds a record for create/update operations.
+    ///
+    /// Read-only and skipped fields are left out.
+    fn to_record(&self) -> Record;
+}
+
+// =============================================================================
+// FromValue
+// =============================================================================
+
+/// Conversion from a record field value.
+///
+/// Conversions are lenient where the wire format is ambiguous: option sets
+/// arrive as plain integers, money as floats, and text columns holding a GUID
+/// or timestamp are parsed into those types during deserialization.
+pub trait FromValue: Sized {
+    /// Converts a present, non-missing value.
+    ///
+    /// # Arguments
+    /// * `field` - The field name, for error reporting
+    /// * `value` - The value to convert, which may be `Value::Null`
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError>;
+
+    /// Converts a field that may be missing from the record.
+    ///
+    /// Missing fields are an error unless overridden, as `Option<T>` does.
+    fn from_field(field: &str, value: Option<&Value>) -> Result<Self, FieldError> {
+        match value {
+            Some(value) => Self::from_value(field, value),
+            None => Err(FieldError::missing(field)),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(field, value).map(Some),
+        }
+    }
+
+    fn from_field(field: &str, value: Option<&Value>) -> Result<Self, FieldError> {
+        match value {
+            Some(value) => Self::from_value(field, value),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(_field: &str, value: &Value) -> Result<Self, FieldError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromValue for String {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            // Text that happens to look like a GUID or timestamp is parsed as one
+            Value::Guid(g) => Ok(g.to_string()),
+            Value::DateTime(dt) => Ok(dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "string",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            other => Err(FieldError::type_mismatch(field, "bool", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Int(n) => Ok(*n),
+            Value::OptionSet(opt) => Ok(opt.value),
+            Value::Long(n) => i32::try_from(*n)
+                .map_err(|_| FieldError::invalid_value(field, format!("{} overflows i32", n))),
+            other => Err(FieldError::type_mismatch(field, "int", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Long(n) => Ok(*n),
+            Value::Int(n) => Ok(*n as i64),
+            other => Err(FieldError::type_mismatch(field, "long", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Float(n) => Ok(*n),
+            Value::Int(n) => Ok(*n as f64),
+            Value::Long(n) => Ok(*n as f64),
+            Value::Decimal(d) => Ok(d.to_f64().unwrap_or_default()),
+            Value::Money(m) => Ok(m.0.to_f64().unwrap_or_default()),
+            other => Err(FieldError::type_mismatch(field, "float", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for Decimal {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Decimal(d) => Ok(*d),
+            Value::Money(m) => Ok(m.0),
+            Value::Int(n) => Ok(Decimal::from(*n)),
+            Value::Long(n) => Ok(Decimal::from(*n)),
+            Value::Float(n) => decimal_from_f64(*n)
+                .ok_or_else(|| FieldError::invalid_value(field, format!("{} is not a decimal", n))),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "decimal",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+impl FromValue for Money {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Money(m) => Ok(*m),
+            other => Decimal::from_value(field, other)
+                .map(Money)
+                .map_err(|_| FieldError::type_mismatch(field, "money", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for Uuid {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::Guid(g) => Ok(*g),
+            Value::EntityReference(r) => Ok(r.id),
+            Value::String(s) => {
+                Uuid::parse_str(s).map_err(|_| FieldError::type_mismatch(field, "guid", "string"))
+            }
+            other => Err(FieldError::type_mismatch(field, "guid", other.type_name())),
+        }
+    }
+}
+
+impl FromValue for DateTime<Utc> {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::DateTime(dt) => Ok(*dt),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "datetime",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+impl FromValue for EntityReference {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::EntityReference(r) => Ok(r.clone()),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "entity_reference",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+impl FromValue for OptionSetValue {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::OptionSet(opt) => Ok(opt.clone()),
+            Value::Int(n) => Ok(OptionSetValue::new(*n)),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "option_set",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+impl FromValue for MultiSelectOptionSetValue {
+    fn from_value(field: &str, value: &Value) -> Result<Self, FieldError> {
+        match value {
+            Value::MultiOptionSet(opt) => Ok(opt.clone()),
+            // Multi-select choices are returned as a comma-separated string
+            Value::String(s) => s
+                .split(',')
+                .filter(|part| !part.is_empty())
+                .map(|part| part.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map(MultiSelectOptionSetValue::new)
+                .map_err(|_| {
+                    FieldError::invalid_value(field, format!("'{}' is not a list of options", s))
+                }),
+            other => Err(FieldError::type_mismatch(
+                field,
+                "multi_option_set",
+                other.type_name(),
+            )),
+        }
+    }
+}
+
+// =============================================================================
+// AsRecordId
+// =============================================================================
+
+/// Types that identify a record, used for primary keys and lookup bindings.
+pub trait AsRecordId {
+    /// Returns the record id, or `None` if unset.
+    fn as_record_id(&self) -> Option<Uuid>;
+}
+
+impl AsRecordId for Uuid {
+    fn as_record_id(&self) -> Option<Uuid> {
+        Some(*self)
+    }
+}
+
+impl AsRecordId for EntityReference {
+    fn as_record_id(&self) -> Option<Uuid> {
+        Some(self.id)
+    }
+}
+
+impl<T: AsRecordId> AsRecordId for Option<T> {
+    fn as_record_id(&self) -> Option<Uuid> {
+        self.as_ref().and_then(AsRecordId::as_record_id)
+    }
+}
+
+/// Builds the binding written for a lookup field.
+///
+/// An unset lookup becomes a null binding, which clears it on update.
+///
+/// # Arguments
+/// * `set_name` - The target entity set name
+/// * `value` - The lookup's current value
+pub fn lookup_binding(set_name: &str, value: &impl AsRecordId) -> EntityBinding {
+    match value.as_record_id() {
+        Some(id) => EntityBinding::new(set_name, id),
+        None => EntityBinding::null(set_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, super::super::DataverseOptionSet)]
+    enum Category {
+        Preferred = 1,
+        Standard = 2,
+    }
+
+    #[derive(Debug, PartialEq, super::super::DataverseEntity)]
+    #[dataverse(entity = "account", set = "accounts")]
+    struct Account {
+        #[dataverse(id)]
+        id: Option<Uuid>,
+        name: String,
+        revenue: Option<Money>,
+        #[dataverse(rename = "accountcategorycode")]
+        category: Option<Category>,
+        #[dataverse(lookup = "contacts")]
+        primarycontactid: Option<Uuid>,
+        #[dataverse(lookup = "accounts", nav = "parentaccountid")]
+        parentaccountid: Option<EntityReference>,
+        #[dataverse(read_only)]
+        createdon: Option<DateTime<Utc>>,
+        #[dataverse(skip)]
+        dirty: bool,
+    }
+
+    #[test]
+    fn test_metadata() {
+        assert_eq!(Account::LOGICAL_NAME, "account");
+        assert_eq!(Account::entity(), Entity::set("accounts"));
+        assert_eq!(
+            Account::COLUMNS,
+            &[
+                "accountid",
+                "name",
+                "revenue",
+                "accountcategorycode",
+                "primarycontactid",
+                "parentaccountid",
+                "createdon",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_record() {
+        let id = Uuid::new_v4();
+        let contact = Uuid::new_v4();
+        let record = Record::new("account")
+            .set("accountid", id)
+            .set("name", "Contoso")
+            .set("revenue", 1500.5)
+            .set("accountcategorycode", 2)
+            .set("primarycontactid", EntityReference::new("contact", contact))
+            .set("parentaccountid", Value::Null);
+
+        let account = Account::from_record(&record).unwrap();
+        assert_eq!(account.id, Some(id));
+        assert_eq!(account.name, "Contoso");
+        assert_eq!(account.revenue, Some(Money(Decimal::new(15005, 1))));
+        assert_eq!(account.category, Some(Category::Standard));
+        assert_eq!(account.primarycontactid, Some(contact));
+        assert_eq!(account.parentaccountid, None);
+        assert_eq!(account.createdon, None);
+        assert!(!account.dirty);
+    }
+
+    #[test]
+    fn test_from_record_errors() {
+        let missing = Record::new("account");
+        assert!(matches!(
+            Account::from_record(&missing),
+            Err(FieldError::Missing { field }) if field == "name"
+        ));
+
+        let null = Record::new("account").set("name", Value::Null);
+        assert!(matches!(
+            Account::from_record(&null),
+            Err(FieldError::TypeMismatch { actual: "null", .. })
+        ));
+
+        let unknown = Record::new("account")
+            .set("name", "Contoso")
+            .set("accountcategorycode", 7);
+        assert!(matches!(
+            Account::from_record(&unknown),
+            Err(FieldError::InvalidValue { field, .. }) if field == "accountcategorycode"
+        ));
+    }
+
+    #[test]
+    fn test_to_record() {
+        let id = Uuid::new_v4();
+        let contact = Uuid::new_v4();
+        let account = Account {
+            id: Some(id),
+            name: "Contoso".into(),
+            revenue: None,
+            category: Some(Category::Preferred),
+            primarycontactid: Some(contact),
+            parentaccountid: None,
+            createdon: Some(Utc::now()),
+            dirty: true,
+        };
+
+        let record = account.to_record();
+        assert_eq!(record.entity(), &Entity::set("accounts"));
+        assert_eq!(record.id(), Some(id));
+        assert_eq!(record.get("name"), Some(&Value::from("Contoso")));
+        assert_eq!(record.get("revenue"), Some(&Value::Null));
+        assert_eq!(
+            record.get("accountcategorycode"),
+            Some(&Value::OptionSet(OptionSetValue::new(1)))
+        );
+        assert_eq!(
+            record.get("primarycontactid"),
+            Some(&Value::EntityBinding(EntityBinding::new(
+                "contacts", contact
+            )))
+        );
+        assert_eq!(
+            record.get("parentaccountid"),
+            Some(&Value::EntityBinding(EntityBinding::null("accounts")))
+        );
+        assert!(!record.contains("createdon"));
+        assert!(!record.contains("dirty"));
+        assert!(!record.contains("accountid"));
+    }
+}
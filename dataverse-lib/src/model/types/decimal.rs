@@ -0,0 +1,89 @@
+//! Fixed-precision decimal support
+//!
+//! Dataverse decimal and currency columns are exact base-10 values with up to
+//! 28 significant digits. They're held as [`Decimal`] and written to the Web
+//! API as JSON numbers, never through `f64`.
+
+use std::str::FromStr;
+
+pub use rust_decimal::Decimal;
+
+/// Converts a float to the decimal it was written as.
+///
+/// JSON numbers arrive as `f64`. Formatting one gives the shortest string
+/// that round-trips, which is the number the server sent for anything within
+/// `f64`'s ~15 significant digits, so `0.1` becomes `0.1` rather than
+/// `0.1000000000000000055511151231`.
+///
+/// Returns `None` for NaN, infinities, and values outside `Decimal`'s range.
+pub fn decimal_from_f64(value: f64) -> Option<Decimal> {
+    if !value.is_finite() {
+        return None;
+    }
+    Decimal::from_str(&value.to_string()).ok()
+}
+
+/// Serde helpers that write a `Decimal` as its exact digits in a string.
+///
+/// A string is the only lossless form every serializer supports. Reading also
+/// accepts numbers from human-readable formats, which arrive as `f64`; binary
+/// formats read the string form only, since `rust_decimal`'s default deserializer needs
+/// `deserialize_any`, which bincode doesn't support. Record payloads for the
+/// Web API still carry JSON numbers (see `record_serde`).
+pub(crate) mod serde_number {
+    use rust_decimal::Decimal;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        rust_decimal::serde::str::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        if deserializer.is_human_readable() {
+            // Accepts both numbers and strings
+            <Decimal as Deserialize>::deserialize(deserializer)
+        } else {
+            rust_decimal::serde::str::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_from_f64_is_exact() {
+        assert_eq!(decimal_from_f64(0.1), Some(Decimal::new(1, 1)));
+        assert_eq!(decimal_from_f64(1999.99), Some(Decimal::new(199999, 2)));
+        assert_eq!(
+            decimal_from_f64(123456789.0123),
+            Some(Decimal::new(1234567890123, 4))
+        );
+        assert_eq!(decimal_from_f64(f64::NAN), None);
+        assert_eq!(decimal_from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_serialize_as_exact_string() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Wrapper(#[serde(with = "serde_number")] Decimal);
+
+        let value = Decimal::new(1234567890123456789, 4);
+        let json = serde_json::to_string(&Wrapper(value)).unwrap();
+        assert_eq!(json, "\"123456789012345.6789\"");
+
+        let bytes =
+            bincode::serde::encode_to_vec(Wrapper(value), bincode::config::standard()).unwrap();
+        let (Wrapper(decoded), _): (Wrapper, _) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        assert_eq!(decoded, value);
+
+        let Wrapper(parsed) = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, value);
+        let Wrapper(parsed) = serde_json::from_str("1999.99").unwrap();
+        assert_eq!(parsed, Decimal::new(199999, 2));
+    }
+}
@@ -1,6 +1,7 @@
 //! Dataverse data types
 
 mod aliased;
+mod decimal;
 mod entity_ref;
 mod file;
 mod money;
@@ -9,6 +10,7 @@ mod option_set;
 mod relationship;
 
 pub use aliased::*;
+pub use decimal::*;
 pub use entity_ref::*;
 pub use file::*;
 pub use money::*;
@@ -2,7 +2,11 @@
 
 use rust_decimal::Decimal;
 use serde::Deserialize;
+use serde::Deserializer;
 use serde::Serialize;
+use serde::Serializer;
+
+use super::decimal::serde_number;
 
 /// A monetary value represented as a decimal.
 ///
@@ -10,6 +14,9 @@ use serde::Serialize;
 /// in Dataverse. Currency information (which currency) is stored separately
 /// in Dataverse as a lookup field.
 ///
+/// Money serializes as a string of its exact digits, so values never go
+/// through `f64`. Records write it to the Web API as a JSON number.
+///
 /// # Example
 ///
 /// ```
@@ -19,8 +26,7 @@ use serde::Serialize;
 /// let price = Money::new(Decimal::new(1999, 2));  // 19.99
 /// let price = Money::from(Decimal::new(1999, 2));
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-#[serde(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Money(pub Decimal);
 
 impl Money {
@@ -40,6 +46,18 @@ impl Money {
     }
 }
 
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_number::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_number::deserialize(deserializer).map(Self)
+    }
+}
+
 impl From<Decimal> for Money {
     fn from(value: Decimal) -> Self {
         Self(value)
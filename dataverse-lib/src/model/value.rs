@@ -171,6 +171,12 @@ impl From<&str> for Value {
     }
 }
 
+impl From<&String> for Value {
+    fn from(v: &String) -> Self {
+        Value::String(v.clone())
+    }
+}
+
 impl From<Uuid> for Value {
     fn from(v: Uuid) -> Self {
         Value::Guid(v)
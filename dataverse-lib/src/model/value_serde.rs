@@ -29,6 +29,7 @@ use super::types::ImageReference;
 use super::types::Money;
 use super::types::MultiSelectOptionSetValue;
 use super::types::OptionSetValue;
+use super::types::serde_number;
 
 // =============================================================================
 // Serialization helpers
@@ -72,7 +73,7 @@ impl Serialize for Value {
                 Value::Int(v) => serializer.serialize_i32(*v),
                 Value::Long(v) => serializer.serialize_i64(*v),
                 Value::Float(v) => serializer.serialize_f64(*v),
-                Value::Decimal(v) => Serialize::serialize(v, serializer),
+                Value::Decimal(v) => serde_number::serialize(v, serializer),
                 Value::String(v) => serializer.serialize_str(v),
                 Value::Guid(v) => v.serialize(serializer),
                 Value::DateTime(v) => v.serialize(serializer),
@@ -356,4 +357,22 @@ mod tests {
             Value::Decimal(Decimal::new(-42, 0))
         );
     }
+
+    #[test]
+    fn test_bincode_roundtrip_money() {
+        let value = Value::Money(Money::new(Decimal::new(1234567890123456789, 4)));
+        assert_eq!(bincode_roundtrip(&value), value);
+    }
+
+    #[test]
+    fn test_json_decimal_and_money_are_exact_strings() {
+        let value = Value::Decimal(Decimal::new(1234567890123456789, 4));
+        assert_eq!(
+            serde_json::to_value(&value).unwrap(),
+            serde_json::json!("123456789012345.6789")
+        );
+
+        let value = Value::Money(Money::new(Decimal::new(1990, 2)));
+        assert_eq!(serde_json::to_string(&value).unwrap(), "\"19.90\"");
+    }
 }
@@ -1,9 +1,14 @@
 //! Concurrency limiting for simultaneous requests.
 
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use tokio::sync::Semaphore;
 use tokio::sync::SemaphorePermit;
+use tokio::time::Instant;
 
 /// Limits the number of concurrent requests.
 ///
@@ -11,6 +16,14 @@ use tokio::sync::SemaphorePermit;
 /// simultaneous requests. Default limit is 52 (Dataverse's concurrent
 /// request limit per user per web server).
 ///
+/// Clones share the same permits, so every clone of a client draws from one
+/// limit. Clients built separately for the same environment and user can
+/// share one with
+/// [`shared_concurrency_limiter`](crate::DataverseClientBuilder::shared_concurrency_limiter).
+///
+/// The limiter records how long requests queue for a permit; see
+/// [`stats`](Self::stats).
+///
 /// # Example
 ///
 /// ```
@@ -19,11 +32,51 @@ use tokio::sync::SemaphorePermit;
 /// let limiter = ConcurrencyLimiter::new(10);
 /// assert_eq!(limiter.limit(), 10);
 /// assert_eq!(limiter.available(), 10);
+/// assert_eq!(limiter.stats().acquired, 0);
 /// ```
 #[derive(Clone)]
 pub struct ConcurrencyLimiter {
     semaphore: Arc<Semaphore>,
     limit: usize,
+    metrics: Arc<Metrics>,
+}
+
+/// Queue wait counters, updated as permits are acquired.
+#[derive(Default)]
+struct Metrics {
+    acquired: AtomicU64,
+    waited: AtomicU64,
+    queued: AtomicUsize,
+    total_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+}
+
+/// Snapshot of a [`ConcurrencyLimiter`]'s queue wait metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConcurrencyStats {
+    /// Permits acquired so far.
+    pub acquired: u64,
+    /// Permits that weren't available right away.
+    pub waited: u64,
+    /// Requests currently waiting for a permit.
+    pub queued: usize,
+    /// Requests currently holding a permit.
+    pub in_flight: usize,
+    /// Total time spent waiting for permits.
+    pub total_wait: Duration,
+    /// Longest single wait for a permit.
+    pub max_wait: Duration,
+}
+
+impl ConcurrencyStats {
+    /// Returns the average wait per acquired permit, including those that
+    /// didn't wait.
+    pub fn average_wait(&self) -> Duration {
+        if self.acquired == 0 {
+            return Duration::ZERO;
+        }
+        self.total_wait.div_f64(self.acquired as f64)
+    }
 }
 
 impl ConcurrencyLimiter {
@@ -32,6 +85,7 @@ impl ConcurrencyLimiter {
         Self {
             semaphore: Arc::new(Semaphore::new(limit)),
             limit,
+            metrics: Arc::default(),
         }
     }
 
@@ -39,10 +93,28 @@ impl ConcurrencyLimiter {
     ///
     /// The permit is released when dropped.
     pub async fn acquire(&self) -> SemaphorePermit<'_> {
-        self.semaphore
-            .acquire()
-            .await
-            .expect("semaphore should not be closed")
+        let metrics = &self.metrics;
+        if let Ok(permit) = self.semaphore.try_acquire() {
+            metrics.acquired.fetch_add(1, Ordering::Relaxed);
+            return permit;
+        }
+
+        let start = Instant::now();
+        let permit = {
+            // Leaves the queue even if the caller gives up waiting
+            let _queued = Queued::enter(&metrics.queued);
+            self.semaphore
+                .acquire()
+                .await
+                .expect("semaphore should not be closed")
+        };
+
+        let wait = start.elapsed().as_micros() as u64;
+        metrics.acquired.fetch_add(1, Ordering::Relaxed);
+        metrics.waited.fetch_add(1, Ordering::Relaxed);
+        metrics.total_wait_micros.fetch_add(wait, Ordering::Relaxed);
+        metrics.max_wait_micros.fetch_max(wait, Ordering::Relaxed);
+        permit
     }
 
     /// Returns the configured limit.
@@ -54,6 +126,35 @@ impl ConcurrencyLimiter {
     pub fn available(&self) -> usize {
         self.semaphore.available_permits()
     }
+
+    /// Returns the queue wait metrics collected so far, across all clones.
+    pub fn stats(&self) -> ConcurrencyStats {
+        let metrics = &self.metrics;
+        ConcurrencyStats {
+            acquired: metrics.acquired.load(Ordering::Relaxed),
+            waited: metrics.waited.load(Ordering::Relaxed),
+            queued: metrics.queued.load(Ordering::Relaxed),
+            in_flight: self.limit.saturating_sub(self.available()),
+            total_wait: Duration::from_micros(metrics.total_wait_micros.load(Ordering::Relaxed)),
+            max_wait: Duration::from_micros(metrics.max_wait_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Counts a request as queued until dropped.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl<'a> Queued<'a> {
+    fn enter(queued: &'a AtomicUsize) -> Self {
+        queued.fetch_add(1, Ordering::Relaxed);
+        Self(queued)
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl Default for ConcurrencyLimiter {
@@ -61,3 +162,54 @@ impl Default for ConcurrencyLimiter {
         Self::new(52)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let permit = limiter.acquire().await;
+        assert_eq!(limiter.stats().in_flight, 1);
+
+        let clone = limiter.clone();
+        let waiter = tokio::spawn(async move {
+            let _permit = clone.acquire().await;
+        });
+        while limiter.stats().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        drop(permit);
+        waiter.await.unwrap();
+
+        let stats = limiter.stats();
+        assert_eq!(stats.acquired, 2);
+        assert_eq!(stats.waited, 1);
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.max_wait, stats.total_wait);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let _permit = limiter.acquire().await;
+
+        let clone = limiter.clone();
+        let waiter = tokio::spawn(async move {
+            let _permit = clone.acquire().await;
+        });
+        while limiter.stats().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        waiter.abort();
+        assert!(waiter.await.unwrap_err().is_cancelled());
+
+        // A wait given up on leaves the queue and never counts as acquired
+        let stats = limiter.stats();
+        assert_eq!(stats.acquired, 1);
+        assert_eq!(stats.waited, 0);
+        assert_eq!(stats.queued, 0);
+    }
+}
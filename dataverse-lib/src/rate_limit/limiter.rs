@@ -4,9 +4,27 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::Duration;
 
+use reqwest::header::HeaderMap;
 use tokio::sync::Mutex;
 use tokio::time::Instant;
 
+/// Requests left in the user's service protection window.
+pub const BURST_REMAINING_HEADER: &str = "x-ms-ratelimit-burst-remaining-xrm-requests";
+
+/// Execution time (in milliseconds) left in the user's service protection
+/// window.
+pub const TIME_REMAINING_HEADER: &str = "x-ms-ratelimit-time-remaining-xrm-requests";
+
+/// Requests allowed per user in a 5-minute service protection window.
+const SERVER_REQUEST_BUDGET: f64 = 6000.0;
+
+/// Execution time (in milliseconds) allowed per user in a 5-minute service
+/// protection window.
+const SERVER_TIME_BUDGET: f64 = 1_200_000.0;
+
+/// Fraction of the server's budget below which requests are slowed down.
+const ADAPTIVE_THRESHOLD: f64 = 0.2;
+
 /// Sliding window rate limiter.
 ///
 /// Tracks request timestamps and enforces a maximum number of requests
@@ -16,6 +34,12 @@ use tokio::time::Instant;
 /// This limiter is cheap to clone and can be shared across multiple clients
 /// when they use the same user credentials and should share the rate limit quota.
 ///
+/// The limiter also adapts to the service protection headers Dataverse
+/// returns ([`BURST_REMAINING_HEADER`] and [`TIME_REMAINING_HEADER`]). Once
+/// less than a fifth of either budget is left, requests are spaced out, more
+/// so the less is left, so the client slows down before it's throttled with
+/// a 429 instead of after.
+///
 /// # Example
 ///
 /// ```
@@ -42,6 +66,8 @@ struct RateLimiterInner {
 struct RateLimiterState {
     /// Timestamps of recent requests within the window.
     timestamps: VecDeque<Instant>,
+    /// Minimum time between requests, from the service protection headers.
+    pacing: Duration,
 }
 
 impl RateLimiter {
@@ -56,6 +82,7 @@ impl RateLimiter {
             inner: Arc::new(RateLimiterInner {
                 state: Mutex::new(RateLimiterState {
                     timestamps: VecDeque::with_capacity(capacity as usize),
+                    pacing: Duration::ZERO,
                 }),
                 capacity,
                 window,
@@ -82,14 +109,18 @@ impl RateLimiter {
                     }
                 }
 
-                // Check if we have capacity
-                if (state.timestamps.len() as u32) < self.inner.capacity {
+                // Space requests out while the server's budget runs low
+                let last = state.timestamps.back().copied();
+                if let Some(until) = last.map(|last| last + state.pacing)
+                    && until > now
+                {
+                    Some(until - now)
+                } else if (state.timestamps.len() as u32) < self.inner.capacity {
+                    // We have capacity
                     state.timestamps.push_back(now);
                     return;
-                }
-
-                // Calculate wait time until oldest request expires
-                if let Some(&oldest) = state.timestamps.front() {
+                } else if let Some(&oldest) = state.timestamps.front() {
+                    // Calculate wait time until oldest request expires
                     let expires_at = oldest + self.inner.window;
                     if expires_at > now {
                         Some(expires_at - now)
@@ -128,6 +159,22 @@ impl RateLimiter {
             .saturating_sub(state.timestamps.len() as u32)
     }
 
+    /// Adapts the request rate to a response's service protection headers.
+    ///
+    /// Responses without the headers leave the rate unchanged.
+    pub async fn observe(&self, headers: &HeaderMap) {
+        if let Some(pacing) = pacing(headers, self.inner.window, self.inner.capacity) {
+            self.inner.state.lock().await.pacing = pacing;
+        }
+    }
+
+    /// Returns the current minimum time between requests.
+    ///
+    /// Zero unless the server's budget is running low.
+    pub async fn pacing(&self) -> Duration {
+        self.inner.state.lock().await.pacing
+    }
+
     /// Returns the configured capacity.
     pub fn capacity(&self) -> u32 {
         self.inner.capacity
@@ -139,8 +186,77 @@ impl RateLimiter {
     }
 }
 
+/// Computes the time between requests from the service protection headers.
+///
+/// Below [`ADAPTIVE_THRESHOLD`] of either budget, requests are spaced
+/// `threshold / remaining` times further apart than the limiter's own rate
+/// allows, up to a tenth of the window. `None` without either header.
+fn pacing(headers: &HeaderMap, window: Duration, capacity: u32) -> Option<Duration> {
+    let remaining = |name: &str, budget: f64| {
+        let value = headers.get(name)?.to_str().ok()?.replace(',', "");
+        value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|v| (v / budget).max(0.0))
+    };
+    let burst = remaining(BURST_REMAINING_HEADER, SERVER_REQUEST_BUDGET);
+    let time = remaining(TIME_REMAINING_HEADER, SERVER_TIME_BUDGET);
+    let fraction = match (burst, time) {
+        (None, None) => return None,
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) | (None, Some(a)) => a,
+    };
+    if fraction >= ADAPTIVE_THRESHOLD {
+        return Some(Duration::ZERO);
+    }
+
+    let max = window / 10;
+    if fraction <= 0.0 {
+        return Some(max);
+    }
+    let base = window / capacity.max(1);
+    Some(base.mul_f64(ADAPTIVE_THRESHOLD / fraction).min(max))
+}
+
 impl Default for RateLimiter {
     fn default() -> Self {
         Self::new(6000, Duration::from_secs(300))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(burst: &'static str, time: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(BURST_REMAINING_HEADER, HeaderValue::from_static(burst));
+        headers.insert(TIME_REMAINING_HEADER, HeaderValue::from_static(time));
+        headers
+    }
+
+    #[test]
+    fn test_pacing() {
+        let window = Duration::from_secs(300);
+        assert_eq!(pacing(&HeaderMap::new(), window, 6000), None);
+        assert_eq!(
+            pacing(&headers("5999", "1,199,964.04"), window, 6000),
+            Some(Duration::ZERO)
+        );
+
+        // 10% of the requests left: twice the base interval of 50 ms
+        let paced = pacing(&headers("600", "1,199,964.04"), window, 6000).unwrap();
+        assert_eq!(paced, Duration::from_millis(100));
+
+        // The execution time budget counts too
+        let paced = pacing(&headers("5999", "60,000"), window, 6000).unwrap();
+        assert_eq!(paced, Duration::from_millis(200));
+
+        // Nothing left: at most a tenth of the window
+        let paced = pacing(&headers("0", "0"), window, 6000).unwrap();
+        assert_eq!(paced, Duration::from_secs(30));
+    }
+}
@@ -5,5 +5,8 @@ mod limiter;
 mod retry;
 
 pub use concurrency::ConcurrencyLimiter;
+pub use concurrency::ConcurrencyStats;
+pub use limiter::BURST_REMAINING_HEADER;
 pub use limiter::RateLimiter;
+pub use limiter::TIME_REMAINING_HEADER;
 pub use retry::RetryConfig;
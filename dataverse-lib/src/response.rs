@@ -1,5 +1,7 @@
 //! Response wrapper with cache status
 
+use std::time::Duration;
+
 use chrono::DateTime;
 use chrono::Utc;
 
@@ -24,6 +26,8 @@ pub struct Response<T> {
     data: T,
     /// Information about whether this response came from cache.
     pub cache: CacheStatus,
+    /// Details of the HTTP request, if one was made.
+    pub request: Option<RequestMetadata>,
 }
 
 impl<T> Response<T> {
@@ -32,6 +36,7 @@ impl<T> Response<T> {
         Self {
             data,
             cache: CacheStatus::None,
+            request: None,
         }
     }
 
@@ -50,6 +55,7 @@ impl<T> Response<T> {
                 cached_at,
                 expires_at,
             },
+            request: None,
         }
     }
 
@@ -61,9 +67,16 @@ impl<T> Response<T> {
                 cached_at,
                 expires_at,
             },
+            request: None,
         }
     }
 
+    /// Attaches the details of the HTTP request that produced this response.
+    pub fn with_request(mut self, request: Option<RequestMetadata>) -> Self {
+        self.request = request;
+        self
+    }
+
     /// Returns `true` if this response came from the cache.
     pub fn is_cached(&self) -> bool {
         matches!(self.cache, CacheStatus::Hit { .. })
@@ -109,6 +122,7 @@ impl<T> Response<T> {
         Response {
             data: f(self.data),
             cache: self.cache,
+            request: self.request,
         }
     }
 }
@@ -150,3 +164,35 @@ impl CacheStatus {
         matches!(self, Self::None)
     }
 }
+
+/// Details of the HTTP request behind a response, for logging slow calls and
+/// for Microsoft support tickets.
+///
+/// # Example
+///
+/// ```ignore
+/// let response = client.retrieve(Entity::set("accounts"), id).await?;
+/// if let Some(request) = &response.request
+///     && request.duration > Duration::from_secs(5)
+/// {
+///     log::warn!(
+///         "slow retrieve: {:?} (request id {:?}, {} retries)",
+///         request.duration,
+///         request.service_request_id,
+///         request.retries,
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestMetadata {
+    /// The `x-ms-service-request-id` Dataverse assigned to the request.
+    pub service_request_id: Option<String>,
+    /// The `x-ms-correlation-request-id` sent with the request.
+    pub correlation_id: Option<String>,
+    /// How many times the request was retried.
+    pub retries: u32,
+    /// Time spent waiting for the rate and concurrency limiters.
+    pub rate_limit_wait: Duration,
+    /// Total time from the first attempt to the response, including retries.
+    pub duration: Duration,
+}
@@ -0,0 +1,152 @@
+//! Block-wise note attachment downloads
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::DEFAULT_FILE_CHUNK_SIZE;
+use crate::DataverseClient;
+use crate::api::ANNOTATION_TYPE;
+use crate::error::ApiError;
+use crate::error::Error;
+
+/// Response of `InitializeAnnotationBlocksDownload`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DownloadSession {
+    file_continuation_token: String,
+    file_size_in_bytes: u64,
+    #[serde(default)]
+    file_name: Option<String>,
+}
+
+/// Response of `DownloadBlock`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DownloadBlockResponse {
+    data: String,
+}
+
+/// Async iterator over a note's attachment.
+///
+/// Each call to [`next`](Self::next) downloads one block with the
+/// `DownloadBlock` action, so large attachments never have to fit in memory.
+/// Created with [`DataverseClient::note_attachment_blocks`].
+#[derive(Debug, Clone)]
+pub struct NoteAttachmentBlocks {
+    note_id: Uuid,
+    block_size: usize,
+    /// Download session token, set on the first call.
+    token: Option<String>,
+    /// Bytes downloaded so far.
+    offset: u64,
+    /// Total attachment size, known after the first call.
+    size: Option<u64>,
+    file_name: Option<String>,
+    done: bool,
+}
+
+impl NoteAttachmentBlocks {
+    pub(crate) fn new(note_id: Uuid) -> Self {
+        Self {
+            note_id,
+            block_size: DEFAULT_FILE_CHUNK_SIZE,
+            token: None,
+            offset: 0,
+            size: None,
+            file_name: None,
+            done: false,
+        }
+    }
+
+    /// Sets the block size in bytes (default 4 MiB, the largest Dataverse
+    /// allows).
+    pub fn block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes.max(1);
+        self
+    }
+
+    /// Returns the attachment's file name, once the first block has been fetched.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the attachment's size in bytes, once the first block has been
+    /// fetched.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Returns the number of bytes downloaded so far.
+    pub fn position(&self) -> u64 {
+        self.offset
+    }
+
+    /// Fetches the next block of the attachment.
+    ///
+    /// Returns `None` when the whole attachment has been read.
+    pub async fn next(&mut self, client: &DataverseClient) -> Option<Result<Bytes, Error>> {
+        if self.done {
+            return None;
+        }
+        match self.fetch(client).await {
+            Ok(block) => block.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    async fn fetch(&mut self, client: &DataverseClient) -> Result<Option<Bytes>, Error> {
+        let token = match &self.token {
+            Some(token) => token.clone(),
+            None => {
+                let target = json!({
+                    "annotationid": self.note_id,
+                    "@odata.type": ANNOTATION_TYPE,
+                });
+                let session: DownloadSession = client
+                    .action("InitializeAnnotationBlocksDownload")
+                    .param("Target", target)
+                    .execute_as()
+                    .await?;
+                self.size = Some(session.file_size_in_bytes);
+                self.file_name = session.file_name;
+                self.token = Some(session.file_continuation_token.clone());
+                session.file_continuation_token
+            }
+        };
+
+        let size = self.size.unwrap_or_default();
+        if self.offset >= size {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let len = (size - self.offset).min(self.block_size as u64);
+        let response: DownloadBlockResponse = client
+            .action("DownloadBlock")
+            .param("Offset", self.offset)
+            .param("BlockLength", len)
+            .param("FileContinuationToken", token)
+            .execute_as()
+            .await?;
+        let block = STANDARD.decode(&response.data).map_err(|e| {
+            Error::Api(ApiError::Parse {
+                message: format!("Invalid attachment block: {}", e),
+                body: None,
+            })
+        })?;
+
+        if block.is_empty() {
+            self.done = true;
+            return Ok(None);
+        }
+        self.offset += block.len() as u64;
+        Ok(Some(block.into()))
+    }
+}
@@ -0,0 +1,112 @@
+//! Change tracking queries.
+//!
+//! A query sent with `Prefer: odata.track-changes` ends with an
+//! `@odata.deltaLink`. Following that link later returns the records
+//! created or updated since, and `$deletedEntity` entries for the ones
+//! deleted, along with the next delta link.
+
+use reqwest::Method;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderValue;
+use serde::Deserialize;
+use serde_json::Value as Json;
+use serde_json::value::RawValue;
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::api::query::odata::QueryBuilder;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::FromValue;
+use crate::model::Record;
+
+/// A page of changes.
+pub(crate) struct DeltaPage {
+    /// Created or updated records, with their ids.
+    pub records: Vec<(Uuid, Record)>,
+    /// Ids of deleted records.
+    pub deleted: Vec<Uuid>,
+    pub next_link: Option<String>,
+    pub delta_link: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeltaResponse {
+    value: Vec<Box<RawValue>>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
+/// Builds the URL of a query, resolving its lookup columns, for use as
+/// the first tracked request.
+pub(crate) async fn initial_url(
+    client: &DataverseClient,
+    mut query: QueryBuilder,
+    logical_name: &str,
+    entity_set: &str,
+) -> Result<String, Error> {
+    query.transform_lookup_fields(client, logical_name).await?;
+    Ok(query.build_url(client, entity_set))
+}
+
+/// Fetches a page of changes from a tracked query URL, a next link or a
+/// delta link.
+pub(crate) async fn fetch(
+    client: &DataverseClient,
+    url: &str,
+    logical_name: &str,
+    primary_id: &str,
+) -> Result<DeltaPage, Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert("OData-MaxVersion", HeaderValue::from_static("4.0"));
+    headers.insert("OData-Version", HeaderValue::from_static("4.0"));
+    headers.insert("Accept", HeaderValue::from_static("application/json"));
+    headers.insert(
+        "Prefer",
+        HeaderValue::from_static("odata.track-changes,odata.include-annotations=\"*\""),
+    );
+    let response = client.request(Method::GET, url, headers, None).await?;
+    let response: DeltaResponse = response.json().await.map_err(ApiError::from)?;
+
+    let mut records = Vec::new();
+    let mut deleted = Vec::new();
+    for raw in response.value {
+        let item: Json = serde_json::from_str(raw.get())?;
+        let is_deleted = item
+            .get("@odata.context")
+            .and_then(Json::as_str)
+            .is_some_and(|context| context.ends_with("$deletedEntity"));
+        if is_deleted {
+            match item.get("id").and_then(Json::as_str).map(Uuid::parse_str) {
+                Some(Ok(id)) => deleted.push(id),
+                _ => log::warn!("Ignoring deleted entry without an id: {}", item),
+            }
+            continue;
+        }
+
+        let mut record: Record = serde_json::from_str(raw.get())?;
+        let id = record
+            .get(primary_id)
+            .map(|value| Uuid::from_value(primary_id, value))
+            .transpose()?
+            .or(record.id());
+        let Some(id) = id else {
+            log::warn!("Ignoring {} record without '{}'", logical_name, primary_id);
+            continue;
+        };
+        record.set_entity(Entity::logical(logical_name));
+        record.set_id(id);
+        record.mark_loaded();
+        records.push((id, record));
+    }
+
+    Ok(DeltaPage {
+        records,
+        deleted,
+        next_link: response.next_link,
+        delta_link: response.delta_link,
+    })
+}
@@ -0,0 +1,582 @@
+//! Query result export to CSV, JSON Lines and Parquet
+//!
+//! [`DataverseClient::export`] runs an OData or FetchXML query and writes
+//! its results page by page, so only one page is held in memory however
+//! large the result set. Without explicit columns, the table's readable
+//! columns are taken from metadata and selected on the query.
+//!
+//! Parquet output needs the `parquet` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let query = client
+//!     .query(Entity::logical("account"))
+//!     .filter(Filter::eq("statecode", 0));
+//!
+//! let rows = client
+//!     .export(query)
+//!     .columns(&["name", "revenue", "primarycontactid"])
+//!     .formatted_values()
+//!     .to_path("accounts.csv")
+//!     .await?;
+//! ```
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::Map;
+use serde_json::Value as Json;
+
+use crate::DataverseClient;
+use crate::api::query::Page;
+use crate::api::query::fetchxml::FetchBuilder;
+use crate::api::query::fetchxml::FetchXmlPages;
+use crate::api::query::odata::ODataPages;
+use crate::api::query::odata::QueryBuilder;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::FORMATTED_VALUE;
+use crate::model::Record;
+use crate::model::metadata::AttributeType;
+use crate::model::metadata::EntityMetadata;
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+    /// A Parquet file with one row group per page and a nullable string
+    /// column per exported column, holding the same text as CSV cells.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+/// A query whose results can be exported.
+pub enum ExportQuery<'a> {
+    /// An OData query.
+    OData(QueryBuilder),
+    /// A FetchXML query.
+    FetchXml(FetchBuilder<'a>),
+}
+
+impl From<QueryBuilder> for ExportQuery<'_> {
+    fn from(query: QueryBuilder) -> Self {
+        Self::OData(query)
+    }
+}
+
+impl<'a> From<FetchBuilder<'a>> for ExportQuery<'a> {
+    fn from(fetch: FetchBuilder<'a>) -> Self {
+        Self::FetchXml(fetch)
+    }
+}
+
+impl<'a> ExportQuery<'a> {
+    fn entity(&self) -> &Entity {
+        match self {
+            Self::OData(query) => query.entity(),
+            Self::FetchXml(fetch) => fetch.entity(),
+        }
+    }
+
+    fn selected(&self) -> &[String] {
+        match self {
+            Self::OData(query) => query.selected_fields(),
+            Self::FetchXml(fetch) => fetch.select_value(),
+        }
+    }
+
+    fn select(self, columns: &[String]) -> Self {
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        match self {
+            Self::OData(query) => Self::OData(query.select(&columns)),
+            Self::FetchXml(fetch) => Self::FetchXml(fetch.select(&columns)),
+        }
+    }
+
+    fn into_pages(self, client: &DataverseClient) -> Pages {
+        match self {
+            Self::OData(query) => Pages::OData(Box::new(query.into_async_iter(client))),
+            Self::FetchXml(fetch) => Pages::FetchXml(Box::new(fetch.into_async_iter())),
+        }
+    }
+}
+
+enum Pages {
+    OData(Box<ODataPages>),
+    FetchXml(Box<FetchXmlPages>),
+}
+
+impl Pages {
+    async fn next(&mut self, client: &DataverseClient) -> Option<Result<Page, Error>> {
+        match self {
+            Self::OData(pages) => pages.next(client).await,
+            Self::FetchXml(pages) => pages.next().await,
+        }
+    }
+}
+
+/// Returns the columns of a table worth exporting: readable columns holding
+/// their own data, without the names and types derived from lookups, and
+/// without file and image content.
+fn exportable_columns(metadata: &EntityMetadata) -> Vec<String> {
+    metadata
+        .attributes
+        .iter()
+        .filter(|attr| attr.is_valid_for_read && attr.attribute_of.is_none())
+        .filter(|attr| {
+            !matches!(
+                attr.attribute_type,
+                AttributeType::Virtual
+                    | AttributeType::PartyList
+                    | AttributeType::Image
+                    | AttributeType::File
+            )
+        })
+        .map(|attr| attr.logical_name.clone())
+        .collect()
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Exports the results of a query to CSV, JSON Lines or Parquet.
+    ///
+    /// Accepts a [`QueryBuilder`] or a [`FetchBuilder`]. Columns are, in
+    /// order of preference, those given to
+    /// [`columns`](ExportBuilder::columns), those the query selects, or the
+    /// table's readable columns from metadata.
+    pub fn export<'a>(&'a self, query: impl Into<ExportQuery<'a>>) -> ExportBuilder<'a> {
+        ExportBuilder {
+            client: self,
+            query: query.into(),
+            format: ExportFormat::default(),
+            columns: None,
+            formatted_values: false,
+        }
+    }
+}
+
+// =============================================================================
+// ExportBuilder
+// =============================================================================
+
+/// Builder for exporting query results, bound to a client.
+pub struct ExportBuilder<'a> {
+    client: &'a DataverseClient,
+    query: ExportQuery<'a>,
+    format: ExportFormat,
+    columns: Option<Vec<String>>,
+    formatted_values: bool,
+}
+
+impl<'a> ExportBuilder<'a> {
+    /// Sets the file format (default CSV).
+    pub fn format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the columns to export, in order. These are also selected on
+    /// the query.
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(|c| (*c).to_string()).collect());
+        self
+    }
+
+    /// Exports formatted values, such as option set labels and lookup
+    /// names, where the server provides them.
+    ///
+    /// CSV cells then hold the formatted value instead of the raw one; JSON
+    /// Lines objects get a `<column>@OData.Community.Display.V1.FormattedValue`
+    /// entry next to the raw value.
+    pub fn formatted_values(mut self) -> Self {
+        self.formatted_values = true;
+        self
+    }
+
+    /// Writes the results to a file, returning the number of records.
+    pub async fn to_path(self, path: impl AsRef<Path>) -> Result<u64, Error> {
+        let file = File::create(path)?;
+        self.to_writer(BufWriter::new(file)).await
+    }
+
+    /// Writes the results to `writer`, returning the number of records.
+    pub async fn to_writer<W: Write + Send>(self, writer: W) -> Result<u64, Error> {
+        let client = self.client;
+        let columns = match self.columns {
+            Some(columns) => columns,
+            None if !self.query.selected().is_empty() => self.query.selected().to_vec(),
+            None => {
+                let metadata = client
+                    .metadata()
+                    .entity(self.query.entity().clone())
+                    .await?;
+                exportable_columns(&metadata)
+            }
+        };
+
+        let mut sink = match self.format {
+            ExportFormat::Csv => Sink::csv(writer, &columns)?,
+            ExportFormat::JsonLines => Sink::JsonLines(writer),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Sink::parquet(writer, &columns)?,
+        };
+        let mut pages = self.query.select(&columns).into_pages(client);
+        let mut written = 0;
+        while let Some(page) = pages.next(client).await {
+            for record in page?.records() {
+                sink.write(record, &columns, self.formatted_values)?;
+                written += 1;
+            }
+            sink.end_page()?;
+        }
+        sink.finish()?;
+        Ok(written)
+    }
+}
+
+// =============================================================================
+// Writers
+// =============================================================================
+
+enum Sink<W: Write + Send> {
+    Csv(Box<csv::Writer<W>>),
+    JsonLines(W),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<parquet_sink::ParquetSink<W>>),
+}
+
+impl<W: Write + Send> Sink<W> {
+    fn csv(writer: W, columns: &[String]) -> Result<Self, Error> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(columns).map_err(std::io::Error::from)?;
+        Ok(Self::Csv(Box::new(writer)))
+    }
+
+    #[cfg(feature = "parquet")]
+    fn parquet(writer: W, columns: &[String]) -> Result<Self, Error> {
+        let sink = parquet_sink::ParquetSink::new(writer, columns)?;
+        Ok(Self::Parquet(Box::new(sink)))
+    }
+
+    fn write(&mut self, record: &Record, columns: &[String], formatted: bool) -> Result<(), Error> {
+        let view = record.to_json_view();
+        match self {
+            Self::Csv(writer) => {
+                let cells = columns.iter().map(|column| {
+                    let formatted = formatted.then(|| record.get_formatted(column)).flatten();
+                    match formatted {
+                        Some(text) => text.to_string(),
+                        None => csv_cell(view.get(column)),
+                    }
+                });
+                writer.write_record(cells).map_err(std::io::Error::from)?;
+            }
+            Self::JsonLines(writer) => {
+                let mut line = Map::new();
+                for column in columns {
+                    let value = view.get(column).cloned().unwrap_or(Json::Null);
+                    line.insert(column.clone(), value);
+                    let annotation = format!("{column}@{FORMATTED_VALUE}");
+                    if formatted && let Some(text) = view.get(&annotation) {
+                        line.insert(annotation, text.clone());
+                    }
+                }
+                serde_json::to_writer(&mut *writer, &line)?;
+                writer.write_all(b"\n")?;
+            }
+            #[cfg(feature = "parquet")]
+            Self::Parquet(sink) => {
+                let cells = columns.iter().map(|column| {
+                    let formatted = formatted.then(|| record.get_formatted(column)).flatten();
+                    match (formatted, view.get(column)) {
+                        (Some(text), _) => Some(text.to_string()),
+                        (None, None | Some(Json::Null)) => None,
+                        (None, value) => Some(csv_cell(value)),
+                    }
+                });
+                sink.push(cells);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ends a page of records.
+    fn end_page(&mut self) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "parquet")]
+            Self::Parquet(sink) => sink.flush_row_group(),
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            Self::Csv(mut writer) => writer.flush()?,
+            Self::JsonLines(mut writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(sink) => sink.finish()?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use parquet::basic::Compression;
+    use parquet::basic::LogicalType;
+    use parquet::basic::Repetition;
+    use parquet::basic::Type as PhysicalType;
+    use parquet::data_type::ByteArray;
+    use parquet::data_type::ByteArrayType;
+    use parquet::errors::ParquetError;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+
+    use crate::error::Error;
+
+    /// Writes rows as Parquet, buffering one page of cells per column.
+    pub(super) struct ParquetSink<W: Write + Send> {
+        writer: SerializedFileWriter<W>,
+        columns: Vec<Vec<Option<String>>>,
+    }
+
+    impl<W: Write + Send> ParquetSink<W> {
+        pub(super) fn new(writer: W, columns: &[String]) -> Result<Self, Error> {
+            let fields = columns
+                .iter()
+                .map(|column| {
+                    Type::primitive_type_builder(column, PhysicalType::BYTE_ARRAY)
+                        .with_repetition(Repetition::OPTIONAL)
+                        .with_logical_type(Some(LogicalType::String))
+                        .build()
+                        .map(Arc::new)
+                })
+                .collect::<Result<_, _>>()
+                .map_err(parquet_error)?;
+            let schema = Type::group_type_builder("record")
+                .with_fields(fields)
+                .build()
+                .map_err(parquet_error)?;
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let writer = SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(properties))
+                .map_err(parquet_error)?;
+            Ok(Self {
+                writer,
+                columns: vec![Vec::new(); columns.len()],
+            })
+        }
+
+        /// Adds a row, one cell per column.
+        pub(super) fn push(&mut self, cells: impl Iterator<Item = Option<String>>) {
+            for (column, cell) in self.columns.iter_mut().zip(cells) {
+                column.push(cell);
+            }
+        }
+
+        /// Writes the buffered rows as a row group.
+        pub(super) fn flush_row_group(&mut self) -> Result<(), Error> {
+            if self.columns.first().is_none_or(Vec::is_empty) {
+                return Ok(());
+            }
+            let mut row_group = self.writer.next_row_group().map_err(parquet_error)?;
+            for cells in &mut self.columns {
+                let Some(mut column) = row_group.next_column().map_err(parquet_error)? else {
+                    break;
+                };
+                let levels: Vec<i16> = cells.iter().map(|c| i16::from(c.is_some())).collect();
+                let values: Vec<ByteArray> = cells
+                    .drain(..)
+                    .flatten()
+                    .map(|c| ByteArray::from(c.into_bytes()))
+                    .collect();
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)
+                    .map_err(parquet_error)?;
+                column.close().map_err(parquet_error)?;
+            }
+            row_group.close().map_err(parquet_error)?;
+            Ok(())
+        }
+
+        pub(super) fn finish(mut self) -> Result<(), Error> {
+            self.flush_row_group()?;
+            let mut writer = self.writer.into_inner().map_err(parquet_error)?;
+            writer.flush()?;
+            Ok(())
+        }
+    }
+
+    fn parquet_error(e: ParquetError) -> Error {
+        Error::Io(std::io::Error::other(e))
+    }
+}
+
+/// Renders a value as a CSV cell: text as is, nulls as empty cells and
+/// anything structured as JSON.
+fn csv_cell(value: Option<&Json>) -> String {
+    match value {
+        None | Some(Json::Null) => String::new(),
+        Some(Json::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::api::query::OrderBy;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+    use crate::model::types::EntityBinding;
+
+    async fn mock() -> MockDataverse {
+        let mock = MockDataverse::new()
+            .entity(MockEntity::new("account", "accounts", "accountid").primary_name("name"))
+            .entity(
+                MockEntity::new("contact", "contacts", "contactid")
+                    .primary_name("fullname")
+                    .attribute("age", AttributeType::Integer)
+                    .lookup("parentcustomerid", "account"),
+            );
+        let account = mock.insert(
+            Entity::set("accounts"),
+            Record::new("account").set("name", "Contoso"),
+        );
+        let ada = Record::new("contact")
+            .set("fullname", "Ada, Countess")
+            .set("age", 36)
+            .set("parentcustomerid", EntityBinding::new("accounts", account));
+        mock.client()
+            .create(Entity::set("contacts"), ada)
+            .await
+            .unwrap();
+        mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact").set("fullname", "Grace"),
+        );
+        mock
+    }
+
+    #[tokio::test]
+    async fn test_export_csv() {
+        let mock = mock().await;
+        let client = mock.client();
+
+        let mut out = Vec::new();
+        let query = client
+            .query(Entity::logical("contact"))
+            .order_by(OrderBy::asc("fullname"))
+            .page_size(1);
+        let written = client
+            .export(query)
+            .columns(&["fullname", "age"])
+            .to_writer(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "fullname,age\n\"Ada, Countess\",36\nGrace,\n"
+        );
+
+        // Columns come from metadata when the query selects none
+        let mut out = Vec::new();
+        client
+            .export(client.query(Entity::logical("contact")))
+            .to_writer(&mut out)
+            .await
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let header = out.lines().next().unwrap();
+        for column in ["contactid", "fullname", "age", "parentcustomerid"] {
+            assert!(header.split(',').any(|c| c == column), "{header}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_json_lines() {
+        let mock = mock().await;
+        let client = mock.client();
+
+        let mut out = Vec::new();
+        let query = client
+            .query(Entity::logical("contact"))
+            .select(&["fullname", "parentcustomerid"])
+            .order_by(OrderBy::asc("fullname"));
+        client
+            .export(query)
+            .format(ExportFormat::JsonLines)
+            .to_writer(&mut out)
+            .await
+            .unwrap();
+        let lines: Vec<Json> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["fullname"], "Ada, Countess");
+        assert!(lines[0]["parentcustomerid"].is_string());
+        assert_eq!(
+            lines[1],
+            serde_json::json!({ "fullname": "Grace", "parentcustomerid": null })
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_export_parquet() {
+        use parquet::file::reader::FileReader;
+        use parquet::file::reader::SerializedFileReader;
+
+        let mock = mock().await;
+        let client = mock.client();
+
+        let mut out = Vec::new();
+        let query = client
+            .query(Entity::logical("contact"))
+            .order_by(OrderBy::asc("fullname"))
+            .page_size(1);
+        let written = client
+            .export(query)
+            .columns(&["fullname", "age"])
+            .format(ExportFormat::Parquet)
+            .to_writer(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(written, 2);
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(out)).unwrap();
+        // One row group per page
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                r#"{fullname: "Ada, Countess", age: "36"}"#,
+                r#"{fullname: "Grace", age: null}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Chunked file column downloads
+
+use bytes::Bytes;
+use reqwest::Method;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderValue;
+
+use crate::DataverseClient;
+use crate::api::RecordKey;
+use crate::error::ApiError;
+use crate::error::Error;
+use crate::model::Entity;
+
+/// Default size of a file chunk, in bytes (4 MiB, Dataverse's own chunk size).
+pub const DEFAULT_FILE_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Async iterator over the content of a file or image column.
+///
+/// Each call to [`next`](Self::next) downloads one chunk with a `Range`
+/// request, so large files never have to fit in memory. Created with
+/// [`DataverseClient::file_chunks`].
+///
+/// # Example
+///
+/// ```ignore
+/// let mut chunks = client.file_chunks(Entity::set("accounts"), id, "new_contract");
+/// let mut out = tokio::fs::File::create("contract.pdf").await?;
+/// while let Some(chunk) = chunks.next(&client).await {
+///     out.write_all(&chunk?).await?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileChunks {
+    entity: Entity,
+    key: RecordKey,
+    column: String,
+    chunk_size: usize,
+    full_size: bool,
+    /// The `$value` URL, resolved on the first call.
+    url: Option<String>,
+    /// Bytes downloaded so far.
+    offset: u64,
+    /// Total file size, known after the first chunk.
+    size: Option<u64>,
+    file_name: Option<String>,
+    done: bool,
+}
+
+impl FileChunks {
+    pub(crate) fn new(entity: Entity, key: RecordKey, column: String) -> Self {
+        Self {
+            entity,
+            key,
+            column,
+            chunk_size: DEFAULT_FILE_CHUNK_SIZE,
+            full_size: false,
+            url: None,
+            offset: 0,
+            size: None,
+            file_name: None,
+            done: false,
+        }
+    }
+
+    /// Sets the chunk size in bytes (default 4 MiB).
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    /// Downloads the full-size image instead of the thumbnail (image columns).
+    pub fn full_size(mut self) -> Self {
+        self.full_size = true;
+        self
+    }
+
+    /// Returns the file name, once the first chunk has been fetched.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the file size in bytes, once the first chunk has been fetched.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Returns the number of bytes downloaded so far.
+    pub fn position(&self) -> u64 {
+        self.offset
+    }
+
+    /// Fetches the next chunk of the file.
+    ///
+    /// Returns `None` when the whole file has been read.
+    pub async fn next(&mut self, client: &DataverseClient) -> Option<Result<Bytes, Error>> {
+        if self.done {
+            return None;
+        }
+        match self.fetch(client).await {
+            Ok(chunk) => chunk.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    async fn fetch(&mut self, client: &DataverseClient) -> Result<Option<Bytes>, Error> {
+        if self.size.is_some_and(|size| self.offset >= size) {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => {
+                let entity_set = client.resolve_entity(&self.entity).await?;
+                let mut path = format!(
+                    "/{}({})/{}/$value",
+                    entity_set,
+                    self.key.to_path(),
+                    self.column
+                );
+                if self.full_size {
+                    path.push_str("?size=full");
+                }
+                let url = client.build_url(&path);
+                self.url = Some(url.clone());
+                url
+            }
+        };
+
+        let end = self.offset + self.chunk_size as u64 - 1;
+        let mut headers = HeaderMap::new();
+        let range = HeaderValue::from_str(&format!("bytes={}-{}", self.offset, end))
+            .expect("range header is ASCII");
+        headers.insert("Range", range);
+
+        let response = client.request(Method::GET, &url, headers, None).await?;
+        let status = response.status();
+        if self.file_name.is_none() {
+            self.file_name = response
+                .headers()
+                .get("x-ms-file-name")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+        }
+        let size = response
+            .headers()
+            .get("x-ms-file-size")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let chunk = response.bytes().await.map_err(ApiError::from)?;
+
+        // A full response means the server sent the whole file at once
+        if status != StatusCode::PARTIAL_CONTENT || chunk.is_empty() {
+            self.done = true;
+        }
+        self.offset += chunk.len() as u64;
+        self.size = size.or(self.size).or(self.done.then_some(self.offset));
+        Ok((!chunk.is_empty()).then_some(chunk))
+    }
+}
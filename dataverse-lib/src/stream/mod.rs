@@ -1,4 +1,17 @@
-//! Async iterators for paginated results
+//! Async iterators for paginated results, page prefetching, file content and note
+//! attachments, export of query results to files, and change subscriptions
 
+mod annotation;
+pub(crate) mod delta;
+mod export;
 mod fetchxml;
+mod file;
 mod odata;
+mod prefetch;
+mod subscribe;
+
+pub use annotation::*;
+pub use export::*;
+pub use file::*;
+pub use prefetch::*;
+pub use subscribe::*;
@@ -0,0 +1,88 @@
+//! Read-ahead page prefetching
+
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use futures::Stream;
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::Error;
+
+/// Pages fetched in the background ahead of the consumer.
+///
+/// A background task keeps fetching pages while the consumer processes the
+/// current one, holding at most `pages` completed pages in memory. Requests
+/// still go through the client's rate and concurrency limiters, so
+/// prefetching never exceeds the configured request budget. Dropping the
+/// iterator stops the task.
+///
+/// Created with [`ODataPages::prefetch`](crate::api::query::odata::ODataPages::prefetch),
+/// [`FetchXmlPages::prefetch`](crate::api::query::fetchxml::FetchXmlPages::prefetch) or
+/// [`TypedPages::prefetch`](crate::api::query::odata::TypedPages::prefetch). Also
+/// implements [`Stream`].
+///
+/// # Example
+///
+/// ```ignore
+/// let mut pages = client.query(Entity::logical("account"))
+///     .page_size(5000)
+///     .into_async_iter(&client)
+///     .prefetch(&client, 4);
+///
+/// while let Some(page) = pages.next().await {
+///     export(page?.records()).await?;
+/// }
+/// ```
+pub struct Prefetch<T> {
+    receiver: mpsc::Receiver<Result<T, Error>>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Send + 'static> Prefetch<T> {
+    /// Spawns a task that drives `source` up to `pages` items ahead.
+    ///
+    /// The task stops after forwarding the first error.
+    pub(crate) fn spawn<S>(source: S, pages: usize) -> Self
+    where
+        S: Stream<Item = Result<T, Error>> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(pages.max(1));
+        let task = tokio::spawn(async move {
+            let mut source = std::pin::pin!(source);
+            while let Some(item) = source.next().await {
+                let failed = item.is_err();
+                if sender.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Self { receiver, task }
+    }
+}
+
+impl<T> Prefetch<T> {
+    /// Returns the next prefetched page, waiting for it if necessary.
+    ///
+    /// Returns `None` when all pages have been consumed.
+    pub async fn next(&mut self) -> Option<Result<T, Error>> {
+        self.receiver.recv().await
+    }
+}
+
+impl<T> Stream for Prefetch<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for Prefetch<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,624 @@
+//! Polling change subscriptions
+//!
+//! [`DataverseClient::subscribe`] polls a table at an interval and yields a
+//! [`ChangeEvent`] for each record created, updated or deleted since the
+//! previous poll. Tables with change tracking enabled are polled with delta
+//! links, which also report deletes. Filtered subscriptions, and tables
+//! without change tracking, fall back to a `modifiedon` watermark, which
+//! can't see deletes.
+//!
+//! The position reached is a [`SubscriptionCursor`]. Saved with
+//! [`cursor_file`](SubscribeBuilder::cursor_file), or read with
+//! [`Subscription::cursor`] and passed back with
+//! [`resume`](SubscribeBuilder::resume), it lets a restarted process pick
+//! up where it left off. Cursors are saved after the consumer has taken
+//! all events of a poll, so events are delivered at least once.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut changes = client
+//!     .subscribe(Entity::logical("account"), None, Duration::from_secs(30))
+//!     .select(&["name", "statecode"])
+//!     .cursor_file("accounts.cursor")
+//!     .start();
+//!
+//! while let Some(change) = changes.next().await {
+//!     match change? {
+//!         ChangeEvent::Created(record) => println!("created {:?}", record.id()),
+//!         ChangeEvent::Updated(record) => println!("updated {:?}", record.id()),
+//!         ChangeEvent::Deleted(id) => println!("deleted {}", id),
+//!     }
+//! }
+//! ```
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use futures::Stream;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::delta;
+use crate::DataverseClient;
+use crate::api::query::Filter;
+use crate::api::query::OrderBy;
+use crate::api::query::odata::QueryBuilder;
+use crate::error::Error;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::model::metadata::EntityMetadata;
+
+const CREATED_ON: &str = "createdon";
+const MODIFIED_ON: &str = "modifiedon";
+
+/// A change to a record, reported by a [`Subscription`].
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    /// A record was created.
+    Created(Record),
+    /// A record was updated.
+    Updated(Record),
+    /// A record was deleted. Only reported for tables polled with change
+    /// tracking.
+    Deleted(Uuid),
+}
+
+/// Position of a subscription, saved to resume it later.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionCursor {
+    /// Change tracking link for the changes since the last poll.
+    pub delta_link: Option<String>,
+    /// Latest `modifiedon` seen.
+    pub watermark: Option<DateTime<Utc>>,
+}
+
+impl SubscriptionCursor {
+    /// Reads a cursor saved with [`save`](Self::save), or returns `None` if
+    /// the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the cursor to a file as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Returns `true` before the first poll.
+    fn is_empty(&self) -> bool {
+        self.delta_link.is_none() && self.watermark.is_none()
+    }
+}
+
+// =============================================================================
+// Client methods
+// =============================================================================
+
+impl DataverseClient {
+    /// Subscribes to changes of a table's records, polling every
+    /// `interval`.
+    ///
+    /// Without a cursor, the first poll only records the current position,
+    /// so records that already exist aren't reported. A `filter` forces
+    /// `modifiedon` watermark polling, as change tracking can't filter.
+    pub fn subscribe(
+        &self,
+        entity: impl Into<Entity>,
+        filter: impl Into<Option<Filter>>,
+        interval: Duration,
+    ) -> SubscribeBuilder<'_> {
+        SubscribeBuilder {
+            client: self,
+            entity: entity.into(),
+            filter: filter.into(),
+            interval,
+            columns: Vec::new(),
+            cursor: None,
+            cursor_file: None,
+        }
+    }
+}
+
+// =============================================================================
+// SubscribeBuilder
+// =============================================================================
+
+/// Builder for a change subscription, bound to a client.
+pub struct SubscribeBuilder<'a> {
+    client: &'a DataverseClient,
+    entity: Entity,
+    filter: Option<Filter>,
+    interval: Duration,
+    columns: Vec<String>,
+    cursor: Option<SubscriptionCursor>,
+    cursor_file: Option<PathBuf>,
+}
+
+impl<'a> SubscribeBuilder<'a> {
+    /// Sets the columns of reported records. `createdon` and `modifiedon`
+    /// are always included.
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|c| (*c).to_string()).collect();
+        self
+    }
+
+    /// Resumes from a cursor returned by [`Subscription::cursor`].
+    pub fn resume(mut self, cursor: SubscriptionCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Loads the cursor from `path` when starting, if the file exists, and
+    /// saves it there after every poll.
+    pub fn cursor_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cursor_file = Some(path.into());
+        self
+    }
+
+    /// Starts the subscription. Nothing is requested until the first event
+    /// is awaited.
+    pub fn start(self) -> Subscription {
+        let cursor = Arc::new(Mutex::new(self.cursor.unwrap_or_default()));
+        let mut poller = Poller {
+            client: self.client.clone(),
+            entity: self.entity,
+            filter: self.filter,
+            columns: self.columns,
+            cursor_file: self.cursor_file,
+            cursor: cursor.clone(),
+            metadata: None,
+        };
+        let interval = self.interval;
+
+        let stream = async_stream::stream! {
+            if let Err(e) = poller.load_cursor() {
+                yield Err(e);
+                return;
+            }
+            let mut first = true;
+            loop {
+                if !first {
+                    tokio::time::sleep(interval).await;
+                }
+                first = false;
+                match poller.poll().await {
+                    Ok((events, next)) => {
+                        for event in events {
+                            yield Ok(event);
+                        }
+                        if let Err(e) = poller.advance(next) {
+                            yield Err(e);
+                        }
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
+        };
+
+        Subscription {
+            stream: Box::pin(stream),
+            cursor,
+        }
+    }
+}
+
+// =============================================================================
+// Subscription
+// =============================================================================
+
+/// An endless stream of record changes. Also implements [`Stream`].
+///
+/// A failed poll is reported as an error item and retried after the next
+/// interval; stop consuming to end the subscription.
+pub struct Subscription {
+    stream: Pin<Box<dyn Stream<Item = Result<ChangeEvent, Error>> + Send>>,
+    cursor: Arc<Mutex<SubscriptionCursor>>,
+}
+
+impl Subscription {
+    /// Waits for the next change.
+    pub async fn next(&mut self) -> Option<Result<ChangeEvent, Error>> {
+        self.stream.next().await
+    }
+
+    /// Returns the position after the last completed poll.
+    pub fn cursor(&self) -> SubscriptionCursor {
+        self.cursor
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Result<ChangeEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+// =============================================================================
+// Polling
+// =============================================================================
+
+struct Poller {
+    client: DataverseClient,
+    entity: Entity,
+    filter: Option<Filter>,
+    columns: Vec<String>,
+    cursor_file: Option<PathBuf>,
+    cursor: Arc<Mutex<SubscriptionCursor>>,
+    metadata: Option<Arc<EntityMetadata>>,
+}
+
+impl Poller {
+    fn current(&self) -> SubscriptionCursor {
+        self.cursor
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    fn load_cursor(&mut self) -> Result<(), Error> {
+        if let Some(path) = &self.cursor_file
+            && let Some(saved) = SubscriptionCursor::load(path)?
+        {
+            *self.cursor.lock().unwrap_or_else(|p| p.into_inner()) = saved;
+        }
+        Ok(())
+    }
+
+    /// Moves to the position reached by a poll, saving it if configured.
+    fn advance(&mut self, next: SubscriptionCursor) -> Result<(), Error> {
+        if let Some(path) = &self.cursor_file {
+            next.save(path)?;
+        }
+        *self.cursor.lock().unwrap_or_else(|p| p.into_inner()) = next;
+        Ok(())
+    }
+
+    async fn metadata(&mut self) -> Result<Arc<EntityMetadata>, Error> {
+        if let Some(metadata) = &self.metadata {
+            return Ok(metadata.clone());
+        }
+        let metadata = Arc::new(self.client.metadata().entity(self.entity.clone()).await?);
+        self.metadata = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Builds the subscription's query, without the watermark condition.
+    fn query(&self, metadata: &EntityMetadata) -> QueryBuilder {
+        let mut query = self.client.query(Entity::logical(&metadata.logical_name));
+        if !self.columns.is_empty() {
+            let mut columns: Vec<&str> = self.columns.iter().map(String::as_str).collect();
+            for column in [CREATED_ON, MODIFIED_ON] {
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            query = query.select(&columns);
+        }
+        query
+    }
+
+    /// Polls once, returning the changes and the position after them.
+    async fn poll(&mut self) -> Result<(Vec<ChangeEvent>, SubscriptionCursor), Error> {
+        let metadata = self.metadata().await?;
+        let cursor = self.current();
+        let tracked = self.filter.is_none() && metadata.change_tracking_enabled;
+
+        if let Some(link) = &cursor.delta_link {
+            match self.poll_delta(&metadata, link, &cursor).await {
+                Err(e) if !e.is_retryable() => {
+                    log::warn!(
+                        "Delta link for {} failed, changes since the last poll are skipped: {}",
+                        metadata.logical_name,
+                        e
+                    );
+                }
+                result => return result,
+            }
+        } else if !cursor.is_empty() || !tracked {
+            return self.poll_watermark(&metadata, &cursor).await;
+        }
+
+        // First poll, or a delta link that expired: page through the table
+        // for a delta link without reporting anything
+        let query = self.query(&metadata);
+        let url = delta::initial_url(
+            &self.client,
+            query,
+            &metadata.logical_name,
+            &metadata.entity_set_name,
+        )
+        .await?;
+        let mut baseline = SubscriptionCursor::default();
+        self.follow(&metadata, url, &mut baseline).await?;
+        Ok((Vec::new(), baseline))
+    }
+
+    async fn poll_delta(
+        &self,
+        metadata: &EntityMetadata,
+        link: &str,
+        cursor: &SubscriptionCursor,
+    ) -> Result<(Vec<ChangeEvent>, SubscriptionCursor), Error> {
+        let mut next = cursor.clone();
+        let events = self.follow(metadata, link.to_string(), &mut next).await?;
+        Ok((events, next))
+    }
+
+    /// Follows a tracked query's pages, updating the cursor's delta link
+    /// and watermark. Records are classified against the cursor's
+    /// watermark from before the call.
+    async fn follow(
+        &self,
+        metadata: &EntityMetadata,
+        mut url: String,
+        cursor: &mut SubscriptionCursor,
+    ) -> Result<Vec<ChangeEvent>, Error> {
+        let since = cursor.watermark;
+        let mut events = Vec::new();
+        loop {
+            let page = delta::fetch(
+                &self.client,
+                &url,
+                &metadata.logical_name,
+                &metadata.primary_id_attribute,
+            )
+            .await?;
+            for (_, record) in page.records {
+                cursor.watermark = cursor.watermark.max(modified_on(&record));
+                events.push(classify(record, since));
+            }
+            events.extend(page.deleted.into_iter().map(ChangeEvent::Deleted));
+            match page.next_link {
+                Some(next) => url = next,
+                None => {
+                    cursor.delta_link = page.delta_link;
+                    return Ok(events);
+                }
+            }
+        }
+    }
+
+    async fn poll_watermark(
+        &self,
+        metadata: &EntityMetadata,
+        cursor: &SubscriptionCursor,
+    ) -> Result<(Vec<ChangeEvent>, SubscriptionCursor), Error> {
+        let client = &self.client;
+        let mut next = SubscriptionCursor {
+            delta_link: None,
+            watermark: cursor.watermark,
+        };
+
+        let Some(watermark) = cursor.watermark else {
+            // First poll: start from the latest modification
+            let mut query = self
+                .query(metadata)
+                .order_by(OrderBy::desc(MODIFIED_ON))
+                .top(1);
+            if let Some(filter) = &self.filter {
+                query = query.filter(filter.clone());
+            }
+            let latest = query.first(client).await?;
+            next.watermark = Some(
+                latest
+                    .as_ref()
+                    .and_then(modified_on)
+                    .unwrap_or_else(Utc::now),
+            );
+            return Ok((Vec::new(), next));
+        };
+
+        let mut condition = Filter::gt(MODIFIED_ON, watermark);
+        if let Some(filter) = &self.filter {
+            condition = filter.clone().and_also(condition);
+        }
+        let mut pages = self
+            .query(metadata)
+            .filter(condition)
+            .order_by(OrderBy::asc(MODIFIED_ON))
+            .into_async_iter(client);
+        let mut events = Vec::new();
+        while let Some(page) = pages.next(client).await {
+            for record in page?.into_records() {
+                next.watermark = next.watermark.max(modified_on(&record));
+                events.push(classify(record, Some(watermark)));
+            }
+        }
+        Ok((events, next))
+    }
+}
+
+fn modified_on(record: &Record) -> Option<DateTime<Utc>> {
+    record.get_datetime(MODIFIED_ON).ok().flatten()
+}
+
+/// Reports a record as created if it was created after the previous poll.
+fn classify(record: Record, since: Option<DateTime<Utc>>) -> ChangeEvent {
+    let created_on = record.get_datetime(CREATED_ON).ok().flatten();
+    match (created_on, since) {
+        (Some(created_on), Some(since)) if created_on <= since => ChangeEvent::Updated(record),
+        _ => ChangeEvent::Created(record),
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(MockEntity::new("contact", "contacts", "contactid").primary_name("fullname"))
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn contact(name: &str, created: u32, modified: u32) -> Record {
+        Record::new("contact")
+            .set("fullname", name)
+            .set(CREATED_ON, at(created))
+            .set(MODIFIED_ON, at(modified))
+    }
+
+    /// Runs the first poll, which only records the starting position.
+    async fn baseline(subscription: &mut Subscription) {
+        let polled = tokio::time::timeout(Duration::from_millis(100), subscription.next()).await;
+        assert!(polled.is_err(), "baseline reported {:?}", polled);
+    }
+
+    fn describe(event: ChangeEvent) -> String {
+        match event {
+            ChangeEvent::Created(r) => {
+                format!("created {}", r.get_string("fullname").unwrap().unwrap())
+            }
+            ChangeEvent::Updated(r) => {
+                format!("updated {}", r.get_string("fullname").unwrap().unwrap())
+            }
+            ChangeEvent::Deleted(id) => format!("deleted {}", id),
+        }
+    }
+
+    async fn take(subscription: &mut Subscription, n: usize) -> Vec<String> {
+        let mut events = Vec::new();
+        for _ in 0..n {
+            events.push(describe(subscription.next().await.unwrap().unwrap()));
+        }
+        events.sort();
+        events
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_change_tracking() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada", 1, 1));
+        let grace = mock.insert(Entity::set("contacts"), contact("Grace", 1, 2));
+
+        let mut changes = client
+            .subscribe(Entity::logical("contact"), None, Duration::from_millis(10))
+            .select(&["fullname"])
+            .start();
+        baseline(&mut changes).await;
+        assert!(changes.cursor().delta_link.is_some());
+        assert_eq!(changes.cursor().watermark, Some(at(2)));
+
+        client
+            .update(
+                Entity::set("contacts"),
+                ada,
+                Record::new("contact")
+                    .set("fullname", "Ada Lovelace")
+                    .set(MODIFIED_ON, at(3)),
+            )
+            .await
+            .unwrap();
+        client.delete(Entity::set("contacts"), grace).await.unwrap();
+        mock.insert(Entity::set("contacts"), contact("Linus", 4, 4));
+
+        let events = take(&mut changes, 3).await;
+        assert_eq!(
+            events,
+            [
+                "created Linus".to_string(),
+                format!("deleted {}", grace),
+                "updated Ada Lovelace".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_watermark() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada", 1, 1));
+        mock.insert(Entity::set("contacts"), contact("Alan", 1, 2));
+
+        let filter = Filter::or([
+            Filter::eq("fullname", "Ada Lovelace"),
+            Filter::eq("fullname", "Linus"),
+        ]);
+        let mut changes = client
+            .subscribe(
+                Entity::logical("contact"),
+                filter,
+                Duration::from_millis(10),
+            )
+            .start();
+        baseline(&mut changes).await;
+        // Nothing matched yet, so polling starts from now
+        let start = changes.cursor().watermark.unwrap();
+        assert!(changes.cursor().delta_link.is_none());
+
+        let later = (start + chrono::Duration::hours(1)).to_rfc3339();
+        let update = Record::new("contact")
+            .set("fullname", "Ada Lovelace")
+            .set(MODIFIED_ON, later.as_str());
+        client
+            .update(Entity::set("contacts"), ada, update)
+            .await
+            .unwrap();
+        mock.insert(
+            Entity::set("contacts"),
+            Record::new("contact")
+                .set("fullname", "Linus")
+                .set(CREATED_ON, later.as_str())
+                .set(MODIFIED_ON, later.as_str()),
+        );
+
+        let events = take(&mut changes, 2).await;
+        assert_eq!(events, ["created Linus", "updated Ada Lovelace"]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_cursor_file() {
+        let mock = mock();
+        let client = mock.client();
+        mock.insert(Entity::set("contacts"), contact("Ada", 1, 1));
+        let path = std::env::temp_dir().join(format!("dataverse-cursor-{}", Uuid::new_v4()));
+
+        let mut changes = client
+            .subscribe(Entity::logical("contact"), None, Duration::from_millis(10))
+            .cursor_file(&path)
+            .start();
+        baseline(&mut changes).await;
+        drop(changes);
+        let saved = SubscriptionCursor::load(&path).unwrap().unwrap();
+        assert!(saved.delta_link.is_some());
+
+        // A new subscription resumes from the saved cursor
+        mock.insert(Entity::set("contacts"), contact("Grace", 2, 2));
+        let mut changes = client
+            .subscribe(Entity::logical("contact"), None, Duration::from_millis(10))
+            .cursor_file(&path)
+            .start();
+        assert_eq!(take(&mut changes, 1).await, ["created Grace"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,509 @@
+//! SQLite persistence for the offline store.
+
+use std::path::Path;
+
+use async_sqlite::Client;
+use async_sqlite::ClientBuilder;
+use async_sqlite::JournalMode;
+use async_sqlite::rusqlite;
+use async_sqlite::rusqlite::OptionalExtension;
+use uuid::Uuid;
+
+use super::ChangeKind;
+use crate::error::SyncError;
+use crate::model::Entity;
+use crate::model::Record;
+
+/// A mirrored entity.
+#[derive(Debug, Clone)]
+pub(super) struct Mirror {
+    pub logical_name: String,
+    pub entity_set: String,
+    pub primary_id: String,
+    /// Columns to mirror; empty for all.
+    pub columns: Vec<String>,
+    /// Link for the changes since the last sync, once the entity was loaded.
+    pub delta_link: Option<String>,
+}
+
+/// A queued local write.
+#[derive(Debug)]
+pub(super) struct Change {
+    pub seq: i64,
+    pub logical_name: String,
+    pub id: Uuid,
+    pub kind: ChangeKind,
+    /// ETag of the record the write was made against, if it came from
+    /// Dataverse.
+    pub etag: Option<String>,
+    /// The record to create, or the fields to update.
+    pub record: Option<Record>,
+}
+
+/// How a local write changes the outbox.
+pub(super) enum Queue<'a> {
+    /// Queues a new write.
+    Add {
+        kind: ChangeKind,
+        etag: Option<&'a str>,
+        record: Option<&'a Record>,
+    },
+    /// Rewrites a queued write in place, keeping its place in the queue.
+    Replace {
+        seq: i64,
+        kind: ChangeKind,
+        etag: Option<&'a str>,
+        record: Option<&'a Record>,
+    },
+    /// Drops a queued write the new one cancels out.
+    Drop(i64),
+}
+
+/// [`Queue`] with the record serialized, to move onto the connection thread.
+enum RawQueue {
+    Add {
+        kind: ChangeKind,
+        etag: Option<String>,
+        data: Option<Vec<u8>>,
+    },
+    Replace {
+        seq: i64,
+        kind: ChangeKind,
+        etag: Option<String>,
+        data: Option<Vec<u8>>,
+    },
+    Drop(i64),
+}
+
+/// An outbox row as read from SQLite.
+type RawChange = (i64, String, String, String, Option<String>, Option<Vec<u8>>);
+
+/// A record as stored locally.
+struct Row {
+    id: String,
+    etag: Option<String>,
+    data: Vec<u8>,
+}
+
+impl Row {
+    fn new(id: Uuid, record: &Record) -> Result<Self, SyncError> {
+        Ok(Self {
+            id: id.to_string(),
+            etag: record.etag().map(str::to_string),
+            data: crate::cache::serialize(record)?,
+        })
+    }
+}
+
+impl ChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Create => "create",
+            ChangeKind::Update => "update",
+            ChangeKind::Delete => "delete",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "create" => Some(ChangeKind::Create),
+            "update" => Some(ChangeKind::Update),
+            "delete" => Some(ChangeKind::Delete),
+            _ => None,
+        }
+    }
+}
+
+pub(super) struct Db {
+    client: Client,
+}
+
+impl Db {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, SyncError> {
+        let client = ClientBuilder::new()
+            .path(path)
+            .journal_mode(JournalMode::Wal)
+            .open()
+            .await?;
+        Self::init_schema(&client).await?;
+        Ok(Self { client })
+    }
+
+    pub async fn open_in_memory() -> Result<Self, SyncError> {
+        let client = ClientBuilder::new().path(":memory:").open().await?;
+        Self::init_schema(&client).await?;
+        Ok(Self { client })
+    }
+
+    async fn init_schema(client: &Client) -> Result<(), async_sqlite::Error> {
+        client
+            .conn(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS mirrors (
+                        logical_name TEXT PRIMARY KEY,
+                        entity_set TEXT NOT NULL,
+                        primary_id TEXT NOT NULL,
+                        columns TEXT NOT NULL,
+                        delta_link TEXT
+                    );
+                    CREATE TABLE IF NOT EXISTS records (
+                        entity TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        etag TEXT,
+                        data BLOB NOT NULL,
+                        PRIMARY KEY (entity, id)
+                    );
+                    CREATE TABLE IF NOT EXISTS outbox (
+                        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                        entity TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        kind TEXT NOT NULL,
+                        etag TEXT,
+                        data BLOB
+                    );",
+                )
+            })
+            .await
+    }
+
+    // =========================================================================
+    // Mirrors
+    // =========================================================================
+
+    pub async fn mirrors(&self) -> Result<Vec<Mirror>, SyncError> {
+        let mirrors = self
+            .client
+            .conn(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT logical_name, entity_set, primary_id, columns, delta_link
+                     FROM mirrors ORDER BY logical_name",
+                )?;
+                stmt.query_map([], mirror_from_row)?.collect()
+            })
+            .await?;
+        Ok(mirrors)
+    }
+
+    /// Finds the mirror of an entity, by logical or entity set name.
+    pub async fn mirror(&self, entity: &Entity) -> Result<Mirror, SyncError> {
+        let (column, name) = match entity {
+            Entity::Logical(name) => ("logical_name", name.clone()),
+            Entity::Set(name) => ("entity_set", name.clone()),
+        };
+        let sql = format!(
+            "SELECT logical_name, entity_set, primary_id, columns, delta_link
+             FROM mirrors WHERE {} = ?",
+            column
+        );
+        let lookup = name.clone();
+        let mirror = self
+            .client
+            .conn(move |conn| conn.query_row(&sql, [lookup], mirror_from_row).optional())
+            .await?;
+        mirror.ok_or(SyncError::NotMirrored(name))
+    }
+
+    /// Adds or replaces a mirror, dropping its records and delta link.
+    pub async fn save_mirror(&self, mirror: &Mirror) -> Result<(), SyncError> {
+        let mirror = mirror.clone();
+        self.client
+            .conn_mut(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO mirrors
+                     (logical_name, entity_set, primary_id, columns, delta_link)
+                     VALUES (?, ?, ?, ?, NULL)",
+                    rusqlite::params![
+                        mirror.logical_name,
+                        mirror.entity_set,
+                        mirror.primary_id,
+                        mirror.columns.join(","),
+                    ],
+                )?;
+                tx.execute(
+                    "DELETE FROM records WHERE entity = ?",
+                    [&mirror.logical_name],
+                )?;
+                tx.commit()
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_delta_link(
+        &self,
+        logical_name: &str,
+        delta_link: Option<&str>,
+    ) -> Result<(), SyncError> {
+        let logical_name = logical_name.to_string();
+        let delta_link = delta_link.map(str::to_string);
+        self.client
+            .conn(move |conn| {
+                conn.execute(
+                    "UPDATE mirrors SET delta_link = ? WHERE logical_name = ?",
+                    rusqlite::params![delta_link, logical_name],
+                )
+            })
+            .await?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // Records
+    // =========================================================================
+
+    pub async fn record(&self, logical_name: &str, id: Uuid) -> Result<Option<Record>, SyncError> {
+        let logical_name = logical_name.to_string();
+        let data = self
+            .client
+            .conn(move |conn| {
+                conn.query_row(
+                    "SELECT data FROM records WHERE entity = ? AND id = ?",
+                    [logical_name, id.to_string()],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+            })
+            .await?;
+        match data {
+            Some(data) => Ok(Some(crate::cache::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn records(&self, logical_name: &str) -> Result<Vec<Record>, SyncError> {
+        let logical_name = logical_name.to_string();
+        let rows = self
+            .client
+            .conn(move |conn| {
+                let mut stmt = conn.prepare("SELECT data FROM records WHERE entity = ?")?;
+                stmt.query_map([logical_name], |row| row.get::<_, Vec<u8>>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .await?;
+        rows.iter()
+            .map(|data| Ok(crate::cache::deserialize(data)?))
+            .collect()
+    }
+
+    /// Writes and removes records in one transaction.
+    pub async fn apply(
+        &self,
+        logical_name: &str,
+        records: &[(Uuid, Record)],
+        deleted: &[Uuid],
+    ) -> Result<(), SyncError> {
+        let logical_name = logical_name.to_string();
+        let rows = records
+            .iter()
+            .map(|(id, record)| Row::new(*id, record))
+            .collect::<Result<Vec<_>, _>>()?;
+        let deleted: Vec<String> = deleted.iter().map(Uuid::to_string).collect();
+        self.client
+            .conn_mut(move |conn| {
+                let tx = conn.transaction()?;
+                for row in rows {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO records (entity, id, etag, data)
+                         VALUES (?, ?, ?, ?)",
+                        rusqlite::params![logical_name, row.id, row.etag, row.data],
+                    )?;
+                }
+                for id in deleted {
+                    tx.execute(
+                        "DELETE FROM records WHERE entity = ? AND id = ?",
+                        [&logical_name, &id],
+                    )?;
+                }
+                tx.commit()
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn clear_records(&self, logical_name: &str) -> Result<(), SyncError> {
+        let logical_name = logical_name.to_string();
+        self.client
+            .conn(move |conn| conn.execute("DELETE FROM records WHERE entity = ?", [logical_name]))
+            .await?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // Outbox
+    // =========================================================================
+
+    /// Applies a local write and queues it, in one transaction.
+    ///
+    /// `local` is the record's new local state, `None` once deleted.
+    pub async fn write(
+        &self,
+        logical_name: &str,
+        id: Uuid,
+        local: Option<&Record>,
+        queue: Queue<'_>,
+    ) -> Result<(), SyncError> {
+        let logical_name = logical_name.to_string();
+        let local = local.map(|r| Row::new(id, r)).transpose()?;
+        let queue = match queue {
+            Queue::Add { kind, etag, record } => RawQueue::Add {
+                kind,
+                etag: etag.map(str::to_string),
+                data: record.map(crate::cache::serialize).transpose()?,
+            },
+            Queue::Replace {
+                seq,
+                kind,
+                etag,
+                record,
+            } => RawQueue::Replace {
+                seq,
+                kind,
+                etag: etag.map(str::to_string),
+                data: record.map(crate::cache::serialize).transpose()?,
+            },
+            Queue::Drop(seq) => RawQueue::Drop(seq),
+        };
+        let id = id.to_string();
+        self.client
+            .conn_mut(move |conn| {
+                let tx = conn.transaction()?;
+                match local {
+                    Some(row) => tx.execute(
+                        "INSERT OR REPLACE INTO records (entity, id, etag, data)
+                         VALUES (?, ?, ?, ?)",
+                        rusqlite::params![logical_name, row.id, row.etag, row.data],
+                    )?,
+                    None => tx.execute(
+                        "DELETE FROM records WHERE entity = ? AND id = ?",
+                        [&logical_name, &id],
+                    )?,
+                };
+                match queue {
+                    RawQueue::Add { kind, etag, data } => tx.execute(
+                        "INSERT INTO outbox (entity, id, kind, etag, data) VALUES (?, ?, ?, ?, ?)",
+                        rusqlite::params![logical_name, id, kind.as_str(), etag, data],
+                    )?,
+                    RawQueue::Replace {
+                        seq,
+                        kind,
+                        etag,
+                        data,
+                    } => tx.execute(
+                        "UPDATE outbox SET kind = ?, etag = ?, data = ? WHERE seq = ?",
+                        rusqlite::params![kind.as_str(), etag, data, seq],
+                    )?,
+                    RawQueue::Drop(seq) => tx.execute("DELETE FROM outbox WHERE seq = ?", [seq])?,
+                };
+                tx.commit()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the queued writes, oldest first.
+    pub async fn changes(&self) -> Result<Vec<Change>, SyncError> {
+        let rows: Vec<RawChange> = self
+            .client
+            .conn(|conn| {
+                let mut stmt = conn
+                    .prepare("SELECT seq, entity, id, kind, etag, data FROM outbox ORDER BY seq")?;
+                stmt.query_map([], raw_change_from_row)?.collect()
+            })
+            .await?;
+
+        let mut changes = Vec::with_capacity(rows.len());
+        for row in rows {
+            if let Some(change) = change_from_raw(row)? {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Returns the latest queued write to a record.
+    pub async fn queued(&self, logical_name: &str, id: Uuid) -> Result<Option<Change>, SyncError> {
+        let logical_name = logical_name.to_string();
+        let row = self
+            .client
+            .conn(move |conn| {
+                conn.query_row(
+                    "SELECT seq, entity, id, kind, etag, data FROM outbox
+                     WHERE entity = ? AND id = ? ORDER BY seq DESC LIMIT 1",
+                    [logical_name, id.to_string()],
+                    raw_change_from_row,
+                )
+                .optional()
+            })
+            .await?;
+        match row {
+            Some(row) => change_from_raw(row),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn remove_change(&self, seq: i64) -> Result<(), SyncError> {
+        self.client
+            .conn(move |conn| conn.execute("DELETE FROM outbox WHERE seq = ?", [seq]))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn pending(&self) -> Result<usize, SyncError> {
+        let count = self
+            .client
+            .conn(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM outbox", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+            })
+            .await?;
+        Ok(count as usize)
+    }
+}
+
+fn raw_change_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RawChange> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+    ))
+}
+
+/// Decodes an outbox row, `None` if it's malformed.
+fn change_from_raw(row: RawChange) -> Result<Option<Change>, SyncError> {
+    let (seq, logical_name, id, kind, etag, data) = row;
+    let (Ok(id), Some(kind)) = (Uuid::parse_str(&id), ChangeKind::parse(&kind)) else {
+        log::warn!("Skipping malformed queued change {}", seq);
+        return Ok(None);
+    };
+    let record = data
+        .map(|data| crate::cache::deserialize::<Record>(&data))
+        .transpose()?;
+    Ok(Some(Change {
+        seq,
+        logical_name,
+        id,
+        kind,
+        etag,
+        record,
+    }))
+}
+
+fn mirror_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Mirror> {
+    let columns: String = row.get(3)?;
+    Ok(Mirror {
+        logical_name: row.get(0)?,
+        entity_set: row.get(1)?,
+        primary_id: row.get(2)?,
+        columns: columns
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect(),
+        delta_link: row.get(4)?,
+    })
+}
@@ -0,0 +1,676 @@
+//! Offline mirror of Dataverse tables
+//!
+//! [`OfflineStore`] keeps a local SQLite copy of selected tables, so an app
+//! can read them and write to them without a connection. Writes are applied
+//! to the local copy straight away and queued; [`OfflineStore::sync`] sends
+//! the queue to Dataverse once the network is back, then pulls what changed
+//! on the server using change tracking delta links.
+//!
+//! Queued updates and deletes carry the ETag of the version they were made
+//! against. If someone else changed the record in the meantime, Dataverse
+//! rejects the write, the server's version wins and the write is reported
+//! as a conflict in the [`SyncReport`]. Change tracking must be enabled on
+//! mirrored tables.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let store = OfflineStore::open(client.clone(), "offline.db").await?;
+//! store.mirror(Entity::logical("contact"), &["fullname", "emailaddress1"]).await?;
+//! store.sync().await?;
+//!
+//! // Later, possibly offline
+//! let contacts = store.records(Entity::logical("contact")).await?;
+//! store
+//!     .update(Entity::logical("contact"), id, Record::new("contact").set("fullname", "Ada"))
+//!     .await?;
+//!
+//! // Back online
+//! let report = store.sync().await?;
+//! for rejected in &report.rejected {
+//!     eprintln!("{} {}: {}", rejected.entity, rejected.id, rejected.message);
+//! }
+//! ```
+
+mod db;
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::DataverseClient;
+use crate::error::Error;
+use crate::error::SyncError;
+use crate::model::Entity;
+use crate::model::Record;
+use crate::stream::delta;
+
+use db::Change;
+use db::Db;
+use db::Mirror;
+use db::Queue;
+
+/// Status Dataverse answers with when `If-Match` doesn't match.
+const PRECONDITION_FAILED: u16 = 412;
+
+/// Status Dataverse answers with when a record doesn't exist.
+const NOT_FOUND: u16 = 404;
+
+/// Kind of a queued local write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// A record created locally.
+    Create,
+    /// Fields updated locally.
+    Update,
+    /// A record deleted locally.
+    Delete,
+}
+
+/// A local write Dataverse didn't accept.
+///
+/// The record was reloaded from Dataverse, so the local copy no longer
+/// holds the write.
+#[derive(Debug, Clone)]
+pub struct RejectedChange {
+    /// Logical name of the record's table.
+    pub entity: String,
+    /// The record's id.
+    pub id: Uuid,
+    /// What the write did.
+    pub kind: ChangeKind,
+    /// Whether the record changed in Dataverse since the write was made.
+    pub conflict: bool,
+    /// Why the write was rejected.
+    pub message: String,
+}
+
+/// Outcome of [`OfflineStore::sync`].
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    /// Number of local writes sent to Dataverse.
+    pub pushed: usize,
+    /// Local writes Dataverse rejected.
+    pub rejected: Vec<RejectedChange>,
+    /// Number of records created or updated locally from Dataverse.
+    pub pulled: usize,
+    /// Number of records removed locally because they were deleted in
+    /// Dataverse.
+    pub removed: usize,
+}
+
+/// Returns `true` if a write failed for lack of a connection or capacity,
+/// so it should stay queued rather than be rejected.
+fn is_transient(error: &Error) -> bool {
+    matches!(error, Error::Auth(_))
+        || (error.is_retryable() && !matches!(error, Error::Concurrency { .. }))
+}
+
+/// A local mirror of Dataverse tables, readable and writable offline.
+pub struct OfflineStore {
+    client: DataverseClient,
+    db: Db,
+}
+
+impl OfflineStore {
+    /// Opens an offline store at the specified path, creating it if needed.
+    pub async fn open(client: DataverseClient, path: impl AsRef<Path>) -> Result<Self, SyncError> {
+        Ok(Self {
+            client,
+            db: Db::open(path).await?,
+        })
+    }
+
+    /// Opens an in-memory offline store.
+    ///
+    /// Useful for testing. Data is lost when the store is dropped.
+    pub async fn open_in_memory(client: DataverseClient) -> Result<Self, SyncError> {
+        Ok(Self {
+            client,
+            db: Db::open_in_memory().await?,
+        })
+    }
+
+    /// Mirrors a table, loading its records on the next sync.
+    ///
+    /// With no `columns`, all columns are mirrored. Mirroring an already
+    /// mirrored table with other columns reloads it; with the same columns
+    /// it does nothing. Needs a connection to read the table's metadata.
+    pub async fn mirror(
+        &self,
+        entity: impl Into<Entity>,
+        columns: &[&str],
+    ) -> Result<(), SyncError> {
+        let metadata = self.client.metadata().entity(entity.into()).await?;
+        let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+        let existing = self
+            .db
+            .mirror(&Entity::logical(&metadata.logical_name))
+            .await;
+        if existing.is_ok_and(|m| m.columns == columns) {
+            return Ok(());
+        }
+
+        let mirror = Mirror {
+            logical_name: metadata.logical_name.clone(),
+            entity_set: metadata.entity_set_name.clone(),
+            primary_id: metadata.primary_id_attribute.clone(),
+            columns,
+            delta_link: None,
+        };
+        self.db.save_mirror(&mirror).await
+    }
+
+    /// Returns a record from the local copy.
+    pub async fn get(
+        &self,
+        entity: impl Into<Entity>,
+        id: Uuid,
+    ) -> Result<Option<Record>, SyncError> {
+        let mirror = self.db.mirror(&entity.into()).await?;
+        self.db.record(&mirror.logical_name, id).await
+    }
+
+    /// Returns all records of a table from the local copy.
+    pub async fn records(&self, entity: impl Into<Entity>) -> Result<Vec<Record>, SyncError> {
+        let mirror = self.db.mirror(&entity.into()).await?;
+        self.db.records(&mirror.logical_name).await
+    }
+
+    /// Creates a record locally and queues it, returning its id.
+    ///
+    /// The id is generated here unless the record has one, so it stays the
+    /// same once the record reaches Dataverse.
+    pub async fn create(
+        &self,
+        entity: impl Into<Entity>,
+        mut record: Record,
+    ) -> Result<Uuid, SyncError> {
+        let mirror = self.db.mirror(&entity.into()).await?;
+        let id = record.id().unwrap_or_else(Uuid::new_v4);
+        record.set_entity(Entity::logical(&mirror.logical_name));
+        record.set_id(id);
+        record.insert(mirror.primary_id.clone(), id);
+        self.db
+            .write(
+                &mirror.logical_name,
+                id,
+                Some(&record),
+                Queue::Add {
+                    kind: ChangeKind::Create,
+                    etag: None,
+                    record: Some(&record),
+                },
+            )
+            .await?;
+        Ok(id)
+    }
+
+    /// Updates fields of a record locally and queues the update.
+    ///
+    /// An update to a record with a write still queued is folded into that
+    /// write, so it keeps the ETag the first write was made against.
+    ///
+    /// Fails with [`Error::NotFound`] if the record isn't in the local copy.
+    pub async fn update(
+        &self,
+        entity: impl Into<Entity>,
+        id: Uuid,
+        changes: Record,
+    ) -> Result<(), SyncError> {
+        let mirror = self.db.mirror(&entity.into()).await?;
+        let mut local = self.local_record(&mirror, id).await?;
+        for (field, value) in changes.fields() {
+            local.insert(field.clone(), value.clone());
+        }
+        let queued = self.db.queued(&mirror.logical_name, id).await?;
+        let merged;
+        let queue = match &queued {
+            Some(change) if change.kind != ChangeKind::Delete => {
+                let mut record = change
+                    .record
+                    .clone()
+                    .unwrap_or_else(|| Record::new(changes.entity().clone()));
+                for (field, value) in changes.fields() {
+                    record.insert(field.clone(), value.clone());
+                }
+                merged = record;
+                Queue::Replace {
+                    seq: change.seq,
+                    kind: change.kind,
+                    etag: change.etag.as_deref(),
+                    record: Some(&merged),
+                }
+            }
+            _ => Queue::Add {
+                kind: ChangeKind::Update,
+                etag: local.etag(),
+                record: Some(&changes),
+            },
+        };
+        self.db
+            .write(&mirror.logical_name, id, Some(&local), queue)
+            .await
+    }
+
+    /// Deletes a record locally and queues the delete.
+    ///
+    /// A queued update to the record is replaced by the delete, and a
+    /// queued create is dropped, as the record never reached Dataverse.
+    ///
+    /// Fails with [`Error::NotFound`] if the record isn't in the local copy.
+    pub async fn delete(&self, entity: impl Into<Entity>, id: Uuid) -> Result<(), SyncError> {
+        let mirror = self.db.mirror(&entity.into()).await?;
+        let local = self.local_record(&mirror, id).await?;
+        let queued = self.db.queued(&mirror.logical_name, id).await?;
+        let queue = match &queued {
+            Some(change) if change.kind == ChangeKind::Create => Queue::Drop(change.seq),
+            Some(change) if change.kind == ChangeKind::Update => Queue::Replace {
+                seq: change.seq,
+                kind: ChangeKind::Delete,
+                etag: change.etag.as_deref(),
+                record: None,
+            },
+            _ => Queue::Add {
+                kind: ChangeKind::Delete,
+                etag: local.etag(),
+                record: None,
+            },
+        };
+        self.db.write(&mirror.logical_name, id, None, queue).await
+    }
+
+    /// Returns the number of local writes waiting to be sent.
+    pub async fn pending(&self) -> Result<usize, SyncError> {
+        self.db.pending().await
+    }
+
+    /// Sends queued writes to Dataverse, then pulls the changes made there
+    /// since the last sync.
+    ///
+    /// If Dataverse can't be reached, fails with the remaining writes still
+    /// queued. Writes Dataverse rejects are dropped, their records reloaded
+    /// and reported in [`SyncReport::rejected`].
+    pub async fn sync(&self) -> Result<SyncReport, SyncError> {
+        let mut report = SyncReport::default();
+        for change in self.db.changes().await? {
+            self.push(change, &mut report).await?;
+        }
+        for mirror in self.db.mirrors().await? {
+            self.pull(&mirror, &mut report).await?;
+        }
+        Ok(report)
+    }
+
+    async fn local_record(&self, mirror: &Mirror, id: Uuid) -> Result<Record, SyncError> {
+        self.db
+            .record(&mirror.logical_name, id)
+            .await?
+            .ok_or_else(|| {
+                SyncError::Dataverse(Error::NotFound {
+                    entity: mirror.logical_name.clone(),
+                    id,
+                })
+            })
+    }
+
+    /// Sends a queued write, dequeuing it unless Dataverse can't be reached.
+    async fn push(&self, change: Change, report: &mut SyncReport) -> Result<(), SyncError> {
+        let mirror = self
+            .db
+            .mirror(&Entity::logical(&change.logical_name))
+            .await?;
+        match self.send(&mirror, &change).await {
+            Ok(()) => report.pushed += 1,
+            Err(e) if is_transient(&e) => return Err(e.into()),
+            Err(e) => {
+                log::warn!(
+                    "Dataverse rejected queued {:?} of {} {}: {}",
+                    change.kind,
+                    change.logical_name,
+                    change.id,
+                    e
+                );
+                report.rejected.push(RejectedChange {
+                    entity: change.logical_name.clone(),
+                    id: change.id,
+                    kind: change.kind,
+                    conflict: e.status_code() == Some(PRECONDITION_FAILED),
+                    message: e.to_string(),
+                });
+                self.reload(&mirror, change.id).await?;
+            }
+        }
+        self.db.remove_change(change.seq).await
+    }
+
+    async fn send(&self, mirror: &Mirror, change: &Change) -> Result<(), Error> {
+        let entity = Entity::set(&mirror.entity_set);
+        let id = change.id;
+        match change.kind {
+            ChangeKind::Create => {
+                let record = change
+                    .record
+                    .clone()
+                    .unwrap_or_else(|| Record::new(entity.clone()));
+                self.client.create(entity, record).await?;
+            }
+            ChangeKind::Update => {
+                let mut record = change
+                    .record
+                    .clone()
+                    .unwrap_or_else(|| Record::new(entity.clone()));
+                record.mark_all_dirty();
+                let mut update = self.client.update(entity, id, record);
+                if let Some(etag) = &change.etag {
+                    update = update.if_match(etag.clone());
+                }
+                update.await?;
+            }
+            ChangeKind::Delete => {
+                let mut delete = self.client.delete(entity, id);
+                if let Some(etag) = &change.etag {
+                    delete = delete.if_match(etag.clone());
+                }
+                match delete.await {
+                    Err(e) if e.status_code() == Some(NOT_FOUND) => {}
+                    result => result?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the local copy of a record with Dataverse's.
+    async fn reload(&self, mirror: &Mirror, id: Uuid) -> Result<(), SyncError> {
+        let columns: Vec<&str> = mirror.columns.iter().map(String::as_str).collect();
+        let retrieved = self
+            .client
+            .retrieve(Entity::logical(&mirror.logical_name), id)
+            .select(&columns)
+            .await;
+        match retrieved {
+            Ok(response) => {
+                let mut record = response.into_inner();
+                record.set_entity(Entity::logical(&mirror.logical_name));
+                record.set_id(id);
+                self.db
+                    .apply(&mirror.logical_name, &[(id, record)], &[])
+                    .await
+            }
+            Err(e) if e.status_code() == Some(NOT_FOUND) => {
+                self.db.apply(&mirror.logical_name, &[], &[id]).await
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Pulls a mirror's changes, loading it in full the first time or when
+    /// its delta link has expired.
+    async fn pull(&self, mirror: &Mirror, report: &mut SyncReport) -> Result<(), SyncError> {
+        let (mut page, full) = match &mirror.delta_link {
+            Some(link) => {
+                match delta::fetch(&self.client, link, &mirror.logical_name, &mirror.primary_id)
+                    .await
+                {
+                    Ok(page) => (page, false),
+                    Err(e) if !is_transient(&e) => {
+                        log::info!(
+                            "Reloading {} after its delta link failed: {}",
+                            mirror.logical_name,
+                            e
+                        );
+                        (self.fetch_all(mirror).await?, true)
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            None => (self.fetch_all(mirror).await?, true),
+        };
+        if full {
+            self.db.clear_records(&mirror.logical_name).await?;
+        }
+
+        loop {
+            report.pulled += page.records.len();
+            report.removed += page.deleted.len();
+            self.db
+                .apply(&mirror.logical_name, &page.records, &page.deleted)
+                .await?;
+            match page.next_link {
+                Some(next) => {
+                    page = delta::fetch(
+                        &self.client,
+                        &next,
+                        &mirror.logical_name,
+                        &mirror.primary_id,
+                    )
+                    .await?
+                }
+                None => break,
+            }
+        }
+        self.db
+            .set_delta_link(&mirror.logical_name, page.delta_link.as_deref())
+            .await
+    }
+
+    async fn fetch_all(&self, mirror: &Mirror) -> Result<delta::DeltaPage, Error> {
+        let mut query = self.client.query(Entity::logical(&mirror.logical_name));
+        if !mirror.columns.is_empty() {
+            let columns: Vec<&str> = mirror.columns.iter().map(String::as_str).collect();
+            query = query.select(&columns);
+        }
+        let url = delta::initial_url(
+            &self.client,
+            query,
+            &mirror.logical_name,
+            &mirror.entity_set,
+        )
+        .await?;
+        delta::fetch(&self.client, &url, &mirror.logical_name, &mirror.primary_id).await
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDataverse;
+    use crate::mock::MockEntity;
+
+    fn mock() -> MockDataverse {
+        MockDataverse::new()
+            .entity(MockEntity::new("contact", "contacts", "contactid").primary_name("fullname"))
+    }
+
+    fn contact(name: &str) -> Record {
+        Record::new("contact").set("fullname", name)
+    }
+
+    fn name(record: &Record) -> Option<&str> {
+        record.get_string("fullname").ok().flatten()
+    }
+
+    #[tokio::test]
+    async fn test_pull_changes() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada"));
+        let grace = mock.insert(Entity::set("contacts"), contact("Grace"));
+
+        let store = OfflineStore::open_in_memory(client.clone()).await.unwrap();
+        store
+            .mirror(Entity::logical("contact"), &["fullname"])
+            .await
+            .unwrap();
+        let report = store.sync().await.unwrap();
+        assert_eq!(report.pulled, 2);
+        let local = store
+            .get(Entity::set("contacts"), ada)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(name(&local), Some("Ada"));
+        assert!(local.etag().is_some());
+
+        // Only what changed since is pulled
+        client
+            .update(Entity::set("contacts"), ada, contact("Ada Lovelace"))
+            .await
+            .unwrap();
+        client.delete(Entity::set("contacts"), grace).await.unwrap();
+        mock.insert(Entity::set("contacts"), contact("Linus"));
+        let report = store.sync().await.unwrap();
+        assert_eq!((report.pulled, report.removed), (2, 1));
+
+        let mut names: Vec<String> = store
+            .records(Entity::logical("contact"))
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|r| name(r).map(str::to_string))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Ada Lovelace", "Linus"]);
+
+        let report = store.sync().await.unwrap();
+        assert_eq!((report.pulled, report.removed), (0, 0));
+        assert!(matches!(
+            store.records(Entity::logical("account")).await,
+            Err(SyncError::NotMirrored(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_push_changes() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada"));
+        let grace = mock.insert(Entity::set("contacts"), contact("Grace"));
+        let store = OfflineStore::open_in_memory(client.clone()).await.unwrap();
+        store.mirror(Entity::logical("contact"), &[]).await.unwrap();
+        store.sync().await.unwrap();
+
+        let linus = store
+            .create(Entity::logical("contact"), contact("Linus"))
+            .await
+            .unwrap();
+        store
+            .update(Entity::logical("contact"), ada, contact("Ada Lovelace"))
+            .await
+            .unwrap();
+        store
+            .delete(Entity::logical("contact"), grace)
+            .await
+            .unwrap();
+        assert_eq!(store.pending().await.unwrap(), 3);
+        let local = store.get(Entity::logical("contact"), ada).await.unwrap();
+        assert_eq!(local.as_ref().and_then(name), Some("Ada Lovelace"));
+        assert!(
+            store
+                .get(Entity::logical("contact"), grace)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let report = store.sync().await.unwrap();
+        assert_eq!(report.pushed, 3);
+        assert!(report.rejected.is_empty());
+        assert_eq!(store.pending().await.unwrap(), 0);
+        let created = mock.record(Entity::set("contacts"), linus).unwrap();
+        assert_eq!(name(&created), Some("Linus"));
+        let updated = mock.record(Entity::set("contacts"), ada).unwrap();
+        assert_eq!(name(&updated), Some("Ada Lovelace"));
+        assert!(mock.record(Entity::set("contacts"), grace).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_writes() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada"));
+        let grace = mock.insert(Entity::set("contacts"), contact("Grace"));
+        let store = OfflineStore::open_in_memory(client.clone()).await.unwrap();
+        store.mirror(Entity::logical("contact"), &[]).await.unwrap();
+        store.sync().await.unwrap();
+
+        store
+            .update(Entity::logical("contact"), ada, contact("Ada Lovelace"))
+            .await
+            .unwrap();
+        store
+            .update(
+                Entity::logical("contact"),
+                ada,
+                Record::new("contact").set("jobtitle", "Analyst"),
+            )
+            .await
+            .unwrap();
+        store
+            .update(Entity::logical("contact"), grace, contact("Grace Hopper"))
+            .await
+            .unwrap();
+        store
+            .delete(Entity::logical("contact"), grace)
+            .await
+            .unwrap();
+        let linus = store
+            .create(Entity::logical("contact"), contact("Linus"))
+            .await
+            .unwrap();
+        store
+            .update(Entity::logical("contact"), linus, contact("Linus Torvalds"))
+            .await
+            .unwrap();
+        let ken = store
+            .create(Entity::logical("contact"), contact("Ken"))
+            .await
+            .unwrap();
+        store.delete(Entity::logical("contact"), ken).await.unwrap();
+        assert_eq!(store.pending().await.unwrap(), 3);
+
+        let report = store.sync().await.unwrap();
+        assert!(report.rejected.is_empty(), "{:?}", report.rejected);
+        assert_eq!(report.pushed, 3);
+        let updated = mock.record(Entity::set("contacts"), ada).unwrap();
+        assert_eq!(name(&updated), Some("Ada Lovelace"));
+        assert_eq!(
+            updated.get_string("jobtitle").ok().flatten(),
+            Some("Analyst")
+        );
+        assert!(mock.record(Entity::set("contacts"), grace).is_none());
+        let created = mock.record(Entity::set("contacts"), linus).unwrap();
+        assert_eq!(name(&created), Some("Linus Torvalds"));
+        assert!(mock.record(Entity::set("contacts"), ken).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_conflict() {
+        let mock = mock();
+        let client = mock.client();
+        let ada = mock.insert(Entity::set("contacts"), contact("Ada"));
+        let store = OfflineStore::open_in_memory(client.clone()).await.unwrap();
+        store.mirror(Entity::logical("contact"), &[]).await.unwrap();
+        store.sync().await.unwrap();
+
+        store
+            .update(Entity::logical("contact"), ada, contact("Ada (offline)"))
+            .await
+            .unwrap();
+        client
+            .update(Entity::set("contacts"), ada, contact("Ada (server)"))
+            .await
+            .unwrap();
+
+        let report = store.sync().await.unwrap();
+        assert_eq!(report.pushed, 0);
+        assert_eq!(report.rejected.len(), 1);
+        assert!(report.rejected[0].conflict);
+        assert_eq!(report.rejected[0].kind, ChangeKind::Update);
+        let local = store.get(Entity::logical("contact"), ada).await.unwrap();
+        assert_eq!(local.as_ref().and_then(name), Some("Ada (server)"));
+        let server = mock.record(Entity::set("contacts"), ada).unwrap();
+        assert_eq!(name(&server), Some("Ada (server)"));
+    }
+}
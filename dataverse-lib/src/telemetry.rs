@@ -0,0 +1,72 @@
+//! `tracing` instrumentation
+//!
+//! With the `tracing` feature, API calls run inside spans:
+//!
+//! - `dataverse.auth` around token acquisition
+//! - `dataverse.crud` around create/retrieve/update/delete/... operations
+//! - `dataverse.query` around each fetched query page, with its cache status
+//! - `dataverse.metadata` around metadata requests
+//! - `dataverse.batch` around batch requests
+//! - `dataverse.request` around each Web API request, with its status and retry count
+//! - `dataverse.http` around each attempt at a request, with the server's request ID
+//!
+//! Without the feature these helpers compile to nothing.
+
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+/// Placeholder for `tracing::Span` when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub(crate) fn current() -> Self {
+        Span
+    }
+
+    pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+}
+
+/// Creates an info-level span, or a placeholder without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($($args:tt)*) => {
+        tracing::info_span!($($args)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($($args:tt)*) => {
+        $crate::telemetry::Span
+    };
+}
+
+pub(crate) use span;
+
+/// Runs futures inside a span.
+pub(crate) trait InSpan: Future + Sized {
+    #[cfg(feature = "tracing")]
+    fn in_span(self, span: Span) -> tracing::instrument::Instrumented<Self> {
+        tracing::Instrument::instrument(self, span)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn in_span(self, _span: Span) -> Self {
+        self
+    }
+}
+
+impl<F: Future> InSpan for F {}
+
+/// Header Dataverse uses to identify a request in its logs.
+pub(crate) const REQUEST_ID_HEADER: &str = "x-ms-service-request-id";
+
+/// Header the client sends to tie a request to the caller's own logs.
+pub(crate) const CORRELATION_ID_HEADER: &str = "x-ms-correlation-request-id";
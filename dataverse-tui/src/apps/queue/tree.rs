@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use chrono::Utc;
 use dataverse_lib::api::BatchItem;
 use dataverse_lib::api::Operation;
+use dataverse_lib::api::RecordKey;
 use rafter::widgets::TreeItem;
 use tuidom::{Color, Element, Style};
 use uuid::Uuid;
@@ -104,16 +105,16 @@ pub fn format_operation(op: &Operation) -> String {
     match op {
         Operation::Create { entity, .. } => format!("Create {}", entity.set_name()),
         Operation::Retrieve { entity, id, .. } => {
-            format!("Retrieve {} {}", entity.set_name(), short_id(id))
+            format!("Retrieve {} {}", entity.set_name(), short_key(id))
         }
         Operation::Update { entity, id, .. } => {
-            format!("Update {} {}", entity.set_name(), short_id(id))
+            format!("Update {} {}", entity.set_name(), short_key(id))
         }
         Operation::Delete { entity, id, .. } => {
-            format!("Delete {} {}", entity.set_name(), short_id(id))
+            format!("Delete {} {}", entity.set_name(), short_key(id))
         }
         Operation::Upsert { entity, id, .. } => {
-            format!("Upsert {} {}", entity.set_name(), short_id(id))
+            format!("Upsert {} {}", entity.set_name(), short_key(id))
         }
         Operation::Associate {
             entity,
@@ -150,6 +151,14 @@ pub fn format_operation(op: &Operation) -> String {
     }
 }
 
+/// Shorten a record key: the id's first segment, or the alternate key as is.
+fn short_key(key: &RecordKey) -> String {
+    match key.id() {
+        Some(id) => short_id(&id),
+        None => key.to_string(),
+    }
+}
+
 /// Shorten a UUID to first 8 characters.
 fn short_id(id: &Uuid) -> String {
     let s = id.to_string();
@@ -12,6 +12,11 @@ pub fn derive_request(input: TokenStream) -> TokenStream {
     macros::request::expand(input.into()).into()
 }
 
+#[proc_macro_derive(Validatable, attributes(validate))]
+pub fn derive_validatable(input: TokenStream) -> TokenStream {
+    macros::validatable::expand(input.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
     macros::handler::expand(attr.into(), item.into()).into()
@@ -27,6 +32,16 @@ pub fn request_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
     macros::request_handler::expand(attr.into(), item.into()).into()
 }
 
+#[proc_macro_attribute]
+pub fn on_event(attr: TokenStream, item: TokenStream) -> TokenStream {
+    macros::on_event::expand(attr.into(), item.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn on_request(attr: TokenStream, item: TokenStream) -> TokenStream {
+    macros::on_request::expand(attr.into(), item.into()).into()
+}
+
 #[proc_macro]
 pub fn keybinds(input: TokenStream) -> TokenStream {
     macros::keybinds::expand(input.into()).into()
@@ -84,6 +99,11 @@ pub fn context_menu(input: TokenStream) -> TokenStream {
     input
 }
 
+#[proc_macro_attribute]
+pub fn component(attr: TokenStream, item: TokenStream) -> TokenStream {
+    macros::component::expand(attr.into(), item.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn derived(attr: TokenStream, item: TokenStream) -> TokenStream {
     macros::derived::expand(attr.into(), item.into()).into()
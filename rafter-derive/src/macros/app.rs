@@ -15,6 +15,7 @@
 //! - `#[app(singleton, default)]` - singleton with Default-based factory
 //! - `#[app(autostart, default)]` - auto-starts on runtime init
 //! - `#[app(pages)]` - enables page routing (expects `Page` enum in scope)
+//! - `#[app(persist)]` - saves and restores fields marked `#[persist]`
 //!
 //! Note: `singleton`, `autostart`, and `on_panic = Restart` require either
 //! `default` or `factory = ...` to be specified.
@@ -39,6 +40,8 @@ struct AppAttrs {
     default: bool,
     /// Custom factory function path (e.g., `MyApp::new`), must return `Self`
     factory: Option<syn::Path>,
+    /// Whether `#[persist]` fields are saved and restored through the runtime's store
+    persist: bool,
 }
 
 impl AppAttrs {
@@ -52,6 +55,7 @@ impl AppAttrs {
             autostart: false,
             default: false,
             factory: None,
+            persist: false,
         };
 
         if !attr.is_empty() {
@@ -65,6 +69,8 @@ impl AppAttrs {
                     attrs.autostart = true;
                 } else if meta.path.is_ident("pages") {
                     attrs.pages = true;
+                } else if meta.path.is_ident("persist") {
+                    attrs.persist = true;
                 } else if meta.path.is_ident("on_panic") {
                     meta.input.parse::<Token![=]>()?;
                     let ident: Ident = meta.input.parse()?;
@@ -157,7 +163,11 @@ fn transform_field(field: &Field) -> TokenStream {
     let other_attrs: Vec<_> = field
         .attrs
         .iter()
-        .filter(|a| !a.path().is_ident("state") && !a.path().is_ident("widget"))
+        .filter(|a| {
+            !a.path().is_ident("state")
+                && !a.path().is_ident("widget")
+                && !a.path().is_ident("persist")
+        })
         .collect();
 
     let should_wrap = !has_state_skip(&field.attrs)
@@ -419,6 +429,12 @@ fn generate_metadata(name: &Ident, attrs: &AppAttrs, fields: &FieldsNamed) -> To
     let has_pages = attrs.pages;
     let autostart = attrs.autostart;
 
+    let as_persistent = if attrs.persist {
+        quote! { Some(app) }
+    } else {
+        quote! { None }
+    };
+
     // Fields for dirty checking (all non-skipped fields)
     let dirty_fields: Vec<_> = fields
         .named
@@ -497,6 +513,84 @@ fn generate_metadata(name: &Ident, attrs: &AppAttrs, fields: &FieldsNamed) -> To
                 #(app.#wakeup_fields.install_wakeup(sender.clone());)*
                 #page_wakeup
             }
+
+            pub fn as_persistent(app: &#name) -> Option<&dyn rafter::PersistentApp> {
+                #as_persistent
+            }
+        }
+    }
+}
+
+/// Collect the fields marked `#[persist]`, checking they can be persisted.
+fn persisted_fields<'a>(
+    name: &Ident,
+    fields: &'a FieldsNamed,
+    attrs: &AppAttrs,
+) -> syn::Result<Vec<&'a Field>> {
+    let mut persisted = Vec::new();
+
+    for field in &fields.named {
+        let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("persist")) else {
+            continue;
+        };
+        if !attrs.persist {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[persist]` requires `#[app(persist)]`",
+            ));
+        }
+        if has_state_skip(&field.attrs)
+            || is_resource_type(&field.ty)
+            || has_widget_attribute(&field.attrs)
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "only state fields can be persisted, not resources, widgets or `#[state(skip)]` fields",
+            ));
+        }
+        persisted.push(field);
+    }
+
+    if attrs.persist && persisted.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "`#[app(persist)]` requires at least one field marked `#[persist]`",
+        ));
+    }
+
+    Ok(persisted)
+}
+
+/// Generate the `PersistentApp` impl (only if `persist` attribute is set).
+fn generate_persistent_impl(name: &Ident, persisted: &[&Field]) -> TokenStream {
+    if persisted.is_empty() {
+        return quote! {};
+    }
+
+    let idents: Vec<_> = persisted.iter().map(|f| &f.ident).collect();
+    let keys: Vec<_> = idents
+        .iter()
+        .map(|ident| ident.as_ref().map(ToString::to_string))
+        .collect();
+    let types: Vec<_> = persisted.iter().map(|f| &f.ty).collect();
+
+    quote! {
+        impl rafter::PersistentApp for #name {
+            fn save_state(&self) -> Result<String, rafter::PersistError> {
+                let mut state = rafter::PersistedState::new();
+                #(self.#idents.with_ref(|value| state.save(#keys, value))?;)*
+                Ok(state.to_json())
+            }
+
+            fn restore_state(&self, data: &str) -> Result<(), rafter::PersistError> {
+                let mut state = rafter::PersistedState::parse(data)?;
+                #(
+                    if let Some(value) = state.restore::<#types>(#keys)? {
+                        self.#idents.set(value);
+                    }
+                )*
+                Ok(())
+            }
         }
     }
 }
@@ -579,6 +673,11 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
+    let persisted = match persisted_fields(name, fields, &attrs) {
+        Ok(p) => p,
+        Err(e) => return e.to_compile_error(),
+    };
+
     let transformed_fields: Vec<_> = fields.named.iter().map(transform_field).collect();
     let default_impl = generate_default_impl(name, fields, &attrs);
     let clone_impl = generate_clone_impl(name, fields, &attrs);
@@ -586,6 +685,7 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
     let registration = generate_registration(name, &attrs);
     let metadata = generate_metadata(name, &attrs, fields);
     let singleton_methods = generate_singleton_methods(name, &attrs);
+    let persistent_impl = generate_persistent_impl(name, &persisted);
 
     // Generate the __page field if pages is enabled
     let page_field = if attrs.pages {
@@ -633,5 +733,6 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         #registration
         #metadata
         #singleton_methods
+        #persistent_impl
     }
 }
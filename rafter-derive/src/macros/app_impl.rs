@@ -224,11 +224,15 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         // to use existing parse functions (they expect ImplItemFn)
         let reconstructed = reconstruct_method(method);
         if let Ok(impl_item) = syn::parse2::<syn::ImplItemFn>(reconstructed.clone()) {
-            if let Some(event_handler) = parse_event_handler_metadata(&impl_item) {
-                event_handlers.push(event_handler);
+            match parse_event_handler_metadata(&impl_item) {
+                Ok(Some(event_handler)) => event_handlers.push(event_handler),
+                Ok(None) => {}
+                Err(e) => return e.to_compile_error(),
             }
-            if let Some(request_handler) = parse_request_handler_metadata(&impl_item) {
-                request_handlers.push(request_handler);
+            match parse_request_handler_metadata(&impl_item) {
+                Ok(Some(request_handler)) => request_handlers.push(request_handler),
+                Ok(None) => {}
+                Err(e) => return e.to_compile_error(),
             }
             if let Some(watch_method) = parse_watch_metadata(&impl_item) {
                 // Validate: apps can only use AppContext and GlobalContext in watches
@@ -413,7 +417,7 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         quote! {}
     };
 
-    // Generate dirty methods and wakeup installation
+    // Generate dirty methods, wakeup installation and persistence
     let dirty_impl = quote! {
         fn is_dirty(&self) -> bool {
             #metadata_mod::is_dirty(self)
@@ -426,6 +430,10 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         fn install_wakeup(&self, sender: rafter::WakeupSender) {
             #metadata_mod::install_wakeup(self, sender)
         }
+
+        fn as_persistent(&self) -> Option<&dyn rafter::PersistentApp> {
+            #metadata_mod::as_persistent(self)
+        }
     };
 
     // Generate panic_behavior method
@@ -0,0 +1,464 @@
+//! The `#[component]` attribute macro for reusable view functions.
+//!
+//! Turns a free function returning `tuidom::Element` into something `page!`
+//! and `element!` can use as a widget. The function is kept as-is and a
+//! props struct named after it (`user_card` -> `UserCard`) is generated with
+//! one builder method per parameter, so attributes at the call site are
+//! matched by name instead of by position:
+//!
+//! ```ignore
+//! #[component]
+//! fn user_card(name: String, role: Option<String>, #[prop(default = 1)] gap: u16) -> Element {
+//!     element! { ... }
+//! }
+//!
+//! page! {
+//!     user_card (name: "Ada", gap: 2)
+//! }
+//! ```
+//!
+//! Parameters become props as follows:
+//! - `Option<T>` is optional; the setter takes `T`
+//! - `#[prop(default)]` falls back to `Default::default()`
+//! - `#[prop(default = expr)]` falls back to `expr`
+//! - everything else is required
+//!
+//! Setters take `impl Into<T>`, except for primitive types, which are taken
+//! as-is so numeric literals get the prop's type.
+//!
+//! Required props are tracked in the struct's type: each one has a
+//! `rafter::props::Unset`/`Set` parameter, and `build()` requires all of
+//! them to be `Set`, so leaving one out is a compile error naming the prop.
+//! An attribute that doesn't match any prop fails on the missing setter.
+//!
+//! `id` and `style` are handled by the props struct and applied to the
+//! returned element; layout attributes (`width`, `padding`, ...) are applied
+//! by `page!` after `build()`. None of these can be used as prop names.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Expr, FnArg, GenericArgument, GenericParam, Ident, ItemFn, Pat, PathArguments, Type, parse2,
+};
+
+use super::page::generate::{is_widget_layout_attr, snake_to_pascal};
+
+/// How a prop is filled in when the attribute is left out.
+enum PropKind {
+    Required,
+    /// `Option<T>`, holding `T`.
+    Optional(Type),
+    /// `#[prop(default)]` or `#[prop(default = expr)]`.
+    Default(Option<Expr>),
+}
+
+struct Prop {
+    name: Ident,
+    ty: Type,
+    kind: PropKind,
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(attr, "#[component] takes no arguments").to_compile_error();
+    }
+
+    let mut func: ItemFn = match parse2(item) {
+        Ok(f) => f,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    let props = match extract_props(&mut func) {
+        Ok(props) => props,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    if let syn::ReturnType::Default = func.sig.output {
+        return syn::Error::new_spanned(
+            &func.sig,
+            "#[component] function must return tuidom::Element",
+        )
+        .to_compile_error();
+    }
+
+    let props_struct = generate_props_struct(&func, &props);
+
+    quote! {
+        #func
+        #props_struct
+    }
+}
+
+/// Collect the function's parameters as props, removing their `#[prop]` attributes.
+fn extract_props(func: &mut ItemFn) -> syn::Result<Vec<Prop>> {
+    if let Some(asyncness) = &func.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[component] function cannot be async",
+        ));
+    }
+
+    let mut props = Vec::new();
+    for input in func.sig.inputs.iter_mut() {
+        let arg = match input {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "#[component] must be a free function, not a method",
+                ));
+            }
+        };
+
+        let name = match &*arg.pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "#[component] parameters must be plain identifiers",
+                ));
+            }
+        };
+
+        let name_str = name.to_string();
+        if matches!(name_str.as_str(), "id" | "style" | "new" | "build")
+            || is_widget_layout_attr(&name_str)
+        {
+            return Err(syn::Error::new_spanned(
+                &name,
+                format!(
+                    "`{}` is reserved and cannot be used as a prop name",
+                    name_str
+                ),
+            ));
+        }
+
+        if let Type::ImplTrait(_) = &*arg.ty {
+            return Err(syn::Error::new_spanned(
+                &arg.ty,
+                "`impl Trait` props are not supported, use a named generic parameter",
+            ));
+        }
+
+        let mut default = None;
+        let mut remaining = Vec::new();
+        for attr in arg.attrs.drain(..) {
+            if !attr.path().is_ident("prop") {
+                remaining.push(attr);
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    default = Some(if meta.input.peek(syn::Token![=]) {
+                        Some(meta.value()?.parse::<Expr>()?)
+                    } else {
+                        None
+                    });
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown prop option, expected `default`"))
+                }
+            })?;
+        }
+        arg.attrs = remaining;
+
+        let ty = (*arg.ty).clone();
+        let kind = match (default, option_inner(&ty)) {
+            (Some(default), _) => PropKind::Default(default),
+            (None, Some(inner)) => PropKind::Optional(inner.clone()),
+            (None, None) => PropKind::Required,
+        };
+
+        props.push(Prop { name, ty, kind });
+    }
+
+    Ok(props)
+}
+
+/// `T` for a type spelled `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Parameter type of a prop setter.
+///
+/// Primitives are taken as-is so integer and float literals in `page!` infer
+/// the prop's type; everything else accepts `impl Into<T>`.
+fn setter_arg(ty: &Type) -> TokenStream {
+    let primitive = match ty {
+        Type::Path(path) => {
+            path.qself.is_none()
+                && path.path.get_ident().is_some_and(|ident| {
+                    matches!(
+                        ident.to_string().as_str(),
+                        "bool"
+                            | "char"
+                            | "u8"
+                            | "u16"
+                            | "u32"
+                            | "u64"
+                            | "u128"
+                            | "usize"
+                            | "i8"
+                            | "i16"
+                            | "i32"
+                            | "i64"
+                            | "i128"
+                            | "isize"
+                            | "f32"
+                            | "f64"
+                    )
+                })
+        }
+        _ => false,
+    };
+    if primitive {
+        quote! { #ty }
+    } else {
+        quote! { impl ::std::convert::Into<#ty> }
+    }
+}
+
+fn generate_props_struct(func: &ItemFn, props: &[Prop]) -> TokenStream {
+    let fn_name = &func.sig.ident;
+    let vis = &func.vis;
+    let struct_name = format_ident!("{}", snake_to_pascal(&fn_name.to_string()));
+
+    // The function's own generics come first, then one state parameter per required prop
+    let fn_params: Vec<_> = func.sig.generics.params.iter().collect();
+    let fn_args: Vec<TokenStream> = fn_params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(l) => {
+                let lifetime = &l.lifetime;
+                quote! { #lifetime }
+            }
+            GenericParam::Type(t) => {
+                let ident = &t.ident;
+                quote! { #ident }
+            }
+            GenericParam::Const(c) => {
+                let ident = &c.ident;
+                quote! { #ident }
+            }
+        })
+        .collect();
+    let where_clause = &func.sig.generics.where_clause;
+
+    let required: Vec<&Prop> = props
+        .iter()
+        .filter(|p| matches!(p.kind, PropKind::Required))
+        .collect();
+    let states: Vec<Ident> = required
+        .iter()
+        .map(|p| format_ident!("__{}", snake_to_pascal(&p.name.to_string())))
+        .collect();
+    let unset = vec![quote! { rafter::props::Unset }; states.len()];
+
+    // Every prop is stored as an Option until build()
+    let fields: Vec<TokenStream> = props
+        .iter()
+        .map(|p| {
+            let name = &p.name;
+            let ty = match &p.kind {
+                PropKind::Optional(_) => {
+                    let ty = &p.ty;
+                    quote! { #ty }
+                }
+                _ => {
+                    let ty = &p.ty;
+                    quote! { ::std::option::Option<#ty> }
+                }
+            };
+            quote! { #name: #ty }
+        })
+        .collect();
+    let field_names: Vec<&Ident> = props.iter().map(|p| &p.name).collect();
+
+    // One marker trait per required prop, so a missing prop is reported by name
+    let markers: Vec<Ident> = required
+        .iter()
+        .map(|p| {
+            format_ident!(
+                "__{}Requires{}",
+                struct_name,
+                snake_to_pascal(&p.name.to_string())
+            )
+        })
+        .collect();
+    let marker_traits: Vec<TokenStream> = required
+        .iter()
+        .zip(&markers)
+        .map(|(p, marker)| {
+            let message = format!("missing required prop `{}` on `{}`", p.name, fn_name);
+            let label = format!("`{}` is never set", p.name);
+            let note = format!("add `{}: ...` to the element's attributes", p.name);
+            quote! {
+                #[doc(hidden)]
+                #[diagnostic::on_unimplemented(message = #message, label = #label, note = #note)]
+                #vis trait #marker {}
+                impl #marker for rafter::props::Set {}
+            }
+        })
+        .collect();
+
+    let setters: Vec<TokenStream> = props
+        .iter()
+        .map(|p| {
+            let name = &p.name;
+            match &p.kind {
+                PropKind::Required => {
+                    let index = required.iter().position(|r| r.name == p.name).unwrap_or(0);
+                    let arg_ty = setter_arg(&p.ty);
+                    let out_states = states.iter().enumerate().map(|(i, s)| {
+                        if i == index {
+                            quote! { rafter::props::Set }
+                        } else {
+                            quote! { #s }
+                        }
+                    });
+                    let moves = field_names.iter().map(|f| {
+                        if *f == name {
+                            quote! { #f: ::std::option::Option::Some(#name.into()) }
+                        } else {
+                            quote! { #f: self.#f }
+                        }
+                    });
+                    quote! {
+                        pub fn #name(
+                            self,
+                            #name: #arg_ty,
+                        ) -> #struct_name<#(#fn_args,)* #(#out_states),*> {
+                            #struct_name {
+                                #(#moves,)*
+                                __id: self.__id,
+                                __style: self.__style,
+                                __state: ::std::marker::PhantomData,
+                            }
+                        }
+                    }
+                }
+                PropKind::Optional(inner) => {
+                    let arg_ty = setter_arg(inner);
+                    quote! {
+                        pub fn #name(mut self, #name: #arg_ty) -> Self {
+                            self.#name = ::std::option::Option::Some(#name.into());
+                            self
+                        }
+                    }
+                }
+                PropKind::Default(_) => {
+                    let arg_ty = setter_arg(&p.ty);
+                    quote! {
+                        pub fn #name(mut self, #name: #arg_ty) -> Self {
+                            self.#name = ::std::option::Option::Some(#name.into());
+                            self
+                        }
+                    }
+                }
+            }
+        })
+        .collect();
+
+    let args: Vec<TokenStream> = props
+        .iter()
+        .map(|p| {
+            let name = &p.name;
+            match &p.kind {
+                PropKind::Required => quote! {
+                    match self.#name {
+                        ::std::option::Option::Some(value) => value,
+                        ::std::option::Option::None => {
+                            unreachable!("required prop checked at compile time")
+                        }
+                    }
+                },
+                PropKind::Optional(_) => quote! { self.#name },
+                PropKind::Default(None) => quote! { self.#name.unwrap_or_default() },
+                PropKind::Default(Some(expr)) => quote! { self.#name.unwrap_or_else(|| #expr) },
+            }
+        })
+        .collect();
+
+    let doc = format!("Props for the [`{}`] component.", fn_name);
+
+    quote! {
+        #[doc = #doc]
+        #[must_use]
+        #vis struct #struct_name<#(#fn_params,)* #(#states),*> #where_clause {
+            #(#fields,)*
+            __id: ::std::option::Option<::std::string::String>,
+            __style: ::std::option::Option<tuidom::Style>,
+            __state: ::std::marker::PhantomData<fn() -> (#(#states,)*)>,
+        }
+
+        #(#marker_traits)*
+
+        impl<#(#fn_params),*> #struct_name<#(#fn_args,)* #(#unset),*> #where_clause {
+            pub fn new() -> Self {
+                Self {
+                    #(#field_names: ::std::option::Option::None,)*
+                    __id: ::std::option::Option::None,
+                    __style: ::std::option::Option::None,
+                    __state: ::std::marker::PhantomData,
+                }
+            }
+        }
+
+        impl<#(#fn_params),*> ::std::default::Default
+            for #struct_name<#(#fn_args,)* #(#unset),*> #where_clause
+        {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<#(#fn_params,)* #(#states),*> #struct_name<#(#fn_args,)* #(#states),*> #where_clause {
+            #(#setters)*
+
+            pub fn id(mut self, id: impl ::std::convert::Into<::std::string::String>) -> Self {
+                self.__id = ::std::option::Option::Some(id.into());
+                self
+            }
+
+            /// Style merged on top of the style the component sets itself.
+            pub fn style(mut self, style: tuidom::Style) -> Self {
+                self.__style = ::std::option::Option::Some(style);
+                self
+            }
+        }
+
+        impl<#(#fn_params,)* #(#states),*> #struct_name<#(#fn_args,)* #(#states),*> #where_clause {
+            // The state bounds are on the method rather than the impl so a
+            // missing prop reports the marker trait's message
+            pub fn build(
+                self,
+                _registry: &rafter::HandlerRegistry,
+                _handlers: &rafter::WidgetHandlers,
+            ) -> tuidom::Element
+            where
+                #(#states: #markers,)*
+            {
+                let mut element: tuidom::Element = #fn_name(#(#args),*);
+                if let ::std::option::Option::Some(id) = self.__id {
+                    element = element.id(id);
+                }
+                element.style = element.style.merge(&self.__style);
+                element
+            }
+        }
+    }
+}
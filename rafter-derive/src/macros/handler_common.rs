@@ -126,3 +126,41 @@ pub fn expand_message_handler(item: TokenStream, requires_return: bool) -> Token
     // Pass through unchanged
     quote! { #func }
 }
+
+/// Expand a message handler macro that names its type (shared by on_event and
+/// on_request). Checks the attribute and signature, then passes through unchanged.
+///
+/// The message parameter is optional here, since the type is in the attribute.
+pub fn expand_declared_message_handler(
+    attr: TokenStream,
+    item: TokenStream,
+    requires_return: bool,
+) -> TokenStream {
+    let handler_kind = if requires_return {
+        "on_request"
+    } else {
+        "on_event"
+    };
+
+    if let Err(e) = syn::parse2::<syn::Type>(attr) {
+        return syn::Error::new(e.span(), format!("expected `#[{}(Type)]`", handler_kind))
+            .to_compile_error();
+    }
+
+    let func: ItemFn = match syn::parse2(item) {
+        Ok(f) => f,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    if func.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(&func.sig, format!("{} must be async", handler_kind))
+            .to_compile_error();
+    }
+
+    if requires_return && !has_return_type(&func) {
+        return syn::Error::new_spanned(&func.sig, "on_request must have a return type")
+            .to_compile_error();
+    }
+
+    quote! { #func }
+}
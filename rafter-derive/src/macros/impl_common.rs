@@ -3,7 +3,8 @@
 use std::collections::HashMap;
 
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
 use syn::{
     Attribute, Generics, Ident, ImplItem, ImplItemFn, ItemImpl, Path, Signature, Token, Type,
     Visibility,
@@ -139,6 +140,8 @@ const CUSTOM_ATTRS: &[&str] = &[
     "handler",
     "event_handler",
     "request_handler",
+    "on_event",
+    "on_request",
     "page",
     "on_start",
     "on_foreground",
@@ -640,6 +643,10 @@ pub struct EventHandlerMethod {
     pub event_type: String,
     /// Handler context requirements
     pub contexts: HandlerContexts,
+    /// Whether the method takes the event as a parameter
+    pub takes_message: bool,
+    /// Type named in `#[on_event(T)]`, checked against `rafter::Event`
+    pub declared_type: Option<Type>,
 }
 
 /// Information about a request handler method
@@ -650,6 +657,10 @@ pub struct RequestHandlerMethod {
     pub request_type: String,
     /// Handler context requirements
     pub contexts: HandlerContexts,
+    /// Whether the method takes the request as a parameter
+    pub takes_message: bool,
+    /// Type named in `#[on_request(T)]`, checked against `rafter::Request`
+    pub declared_type: Option<Type>,
 }
 
 /// Information about a page method marked with #[page] or #[page(Name)]
@@ -688,48 +699,73 @@ pub fn parse_handler_metadata(method: &ImplItemFn) -> Option<HandlerMethod> {
     None
 }
 
-/// Check if method has #[event_handler] attribute and extract metadata.
-/// Extracts the event type from the method signature (first non-self, non-context parameter).
-pub fn parse_event_handler_metadata(method: &ImplItemFn) -> Option<EventHandlerMethod> {
-    let has_attr = method
-        .attrs
-        .iter()
-        .any(|a| a.path().is_ident("event_handler"));
-
-    if !has_attr {
-        return None;
-    }
-
-    let event_type = extract_message_type(method)?;
-    let contexts = detect_handler_contexts(method);
+/// Check if method has #[event_handler] or #[on_event(T)] and extract metadata.
+///
+/// `#[event_handler]` takes the event type from the method signature (first
+/// non-self, non-context parameter). `#[on_event(T)]` names it explicitly, so
+/// the event parameter can be left out.
+pub fn parse_event_handler_metadata(
+    method: &ImplItemFn,
+) -> syn::Result<Option<EventHandlerMethod>> {
+    let Some((event_type, declared_type)) =
+        parse_message_attr(method, "event_handler", "on_event")?
+    else {
+        return Ok(None);
+    };
 
-    Some(EventHandlerMethod {
+    Ok(Some(EventHandlerMethod {
         name: method.sig.ident.clone(),
         event_type,
-        contexts,
-    })
+        contexts: detect_handler_contexts(method),
+        takes_message: extract_message_type(method).is_some(),
+        declared_type,
+    }))
 }
 
-/// Check if method has #[request_handler] attribute and extract metadata.
-/// Extracts the request type from the method signature (first non-self, non-context parameter).
-pub fn parse_request_handler_metadata(method: &ImplItemFn) -> Option<RequestHandlerMethod> {
-    let has_attr = method
-        .attrs
-        .iter()
-        .any(|a| a.path().is_ident("request_handler"));
-
-    if !has_attr {
-        return None;
-    }
-
-    let request_type = extract_message_type(method)?;
-    let contexts = detect_handler_contexts(method);
+/// Check if method has #[request_handler] or #[on_request(T)] and extract metadata.
+///
+/// Like [`parse_event_handler_metadata`], the request type comes from the
+/// signature or from `#[on_request(T)]`.
+pub fn parse_request_handler_metadata(
+    method: &ImplItemFn,
+) -> syn::Result<Option<RequestHandlerMethod>> {
+    let Some((request_type, declared_type)) =
+        parse_message_attr(method, "request_handler", "on_request")?
+    else {
+        return Ok(None);
+    };
 
-    Some(RequestHandlerMethod {
+    Ok(Some(RequestHandlerMethod {
         name: method.sig.ident.clone(),
         request_type,
-        contexts,
-    })
+        contexts: detect_handler_contexts(method),
+        takes_message: extract_message_type(method).is_some(),
+        declared_type,
+    }))
+}
+
+/// Find the message type for a handler marked with `inferred` (type from the
+/// signature) or `declared(T)` (type from the attribute).
+///
+/// Returns the type as a string for codegen, plus the declared type if any.
+fn parse_message_attr(
+    method: &ImplItemFn,
+    inferred: &str,
+    declared: &str,
+) -> syn::Result<Option<(String, Option<Type>)>> {
+    for attr in &method.attrs {
+        if attr.path().is_ident(declared) {
+            let ty: Type = attr.parse_args().map_err(|_| {
+                syn::Error::new_spanned(attr, format!("expected `#[{}(Type)]`", declared))
+            })?;
+            let ty_str = quote!(#ty).to_string().replace(' ', "");
+            return Ok(Some((ty_str, Some(ty))));
+        }
+        if attr.path().is_ident(inferred) {
+            return Ok(extract_message_type(method).map(|ty| (ty, None)));
+        }
+    }
+    Ok(None)
 }
 
 /// Check if method has #[page] or #[page(Name)] attribute and extract metadata.
@@ -871,6 +907,8 @@ pub fn strip_custom_attrs(impl_block: &mut ItemImpl) {
                     && !a.path().is_ident("handler")
                     && !a.path().is_ident("event_handler")
                     && !a.path().is_ident("request_handler")
+                    && !a.path().is_ident("on_event")
+                    && !a.path().is_ident("on_request")
                     && !a.path().is_ident("page")
             });
             // Remove metadata doc attributes
@@ -1597,19 +1635,44 @@ pub fn generate_event_dispatch(
         .map(|h| {
             let name = &h.name;
             let event_type: syn::Type = syn::parse_str(&h.event_type).unwrap();
+            let event_arg = if h.takes_message {
+                quote! { event, }
+            } else {
+                quote! {}
+            };
             let (call, clones) =
-                generate_event_handler_call_and_clones(name, &h.contexts, context_type);
-
-            quote! {
-                t if t == std::any::TypeId::of::<#event_type>() => {
+                generate_event_handler_call_and_clones(name, &event_arg, &h.contexts, context_type);
+            let check = h
+                .declared_type
+                .as_ref()
+                .map(|ty| assert_message_trait(ty, quote! { rafter::Event }));
+
+            let spawn = quote! {
+                #clones
+                tokio::spawn(async move {
+                    #call
+                });
+                return true;
+            };
+            let body = if h.takes_message {
+                quote! {
                     if let Some(event) = event.downcast_ref::<#event_type>() {
                         let event = event.clone();
-                        #clones
-                        tokio::spawn(async move {
-                            #call
-                        });
-                        return true;
+                        #spawn
                     }
+                }
+            } else {
+                quote! {
+                    if event.downcast_ref::<#event_type>().is_some() {
+                        #spawn
+                    }
+                }
+            };
+
+            quote! {
+                t if t == std::any::TypeId::of::<#event_type>() => {
+                    #check
+                    #body
                     false
                 }
             }
@@ -1692,15 +1755,44 @@ pub fn generate_request_dispatch(
         .map(|h| {
             let name = &h.name;
             let request_type: syn::Type = syn::parse_str(&h.request_type).unwrap();
-            let (call, clones) =
-                generate_request_handler_call_and_clones(name, &h.contexts, context_type);
+            let request_arg = if h.takes_message {
+                quote! { *request, }
+            } else {
+                quote! {}
+            };
+            let (call, clones) = generate_request_handler_call_and_clones(
+                name,
+                &request_arg,
+                &h.contexts,
+                context_type,
+            );
+            // A declared request type also pins the handler's return type to
+            // its `Response`, which the requester downcasts to
+            let (check, response) = match &h.declared_type {
+                Some(ty) => (
+                    Some(assert_message_trait(ty, quote! { rafter::Request })),
+                    {
+                        let call = respan(call, ty.span());
+                        quote_spanned! {ty.span()=>
+                            let response: <#ty as rafter::Request>::Response = #call;
+                        }
+                    },
+                ),
+                None => (None, quote! { let response = #call; }),
+            };
+            let binding = if h.takes_message {
+                quote! { Ok(request) }
+            } else {
+                quote! { Ok(_) }
+            };
 
             quote! {
                 t if t == std::any::TypeId::of::<#request_type>() => {
-                    if let Ok(request) = request.downcast::<#request_type>() {
+                    #check
+                    if let #binding = request.downcast::<#request_type>() {
                         #clones
                         return Some(Box::pin(async move {
-                            let response = #call;
+                            #response
                             Box::new(response) as Box<dyn std::any::Any + Send + Sync>
                         }));
                     }
@@ -1762,16 +1854,17 @@ pub fn generate_request_dispatch(
 /// Generate event handler call expression and clone statements.
 fn generate_event_handler_call_and_clones(
     name: &Ident,
+    event: &TokenStream,
     contexts: &HandlerContexts,
     context_type: DispatchContextType,
 ) -> (TokenStream, TokenStream) {
     match context_type {
         DispatchContextType::App => {
             let call = match (contexts.app_context, contexts.global_context) {
-                (false, false) => quote! { this.#name(event).await; },
-                (true, false) => quote! { this.#name(event, &cx).await; },
-                (false, true) => quote! { this.#name(event, &gx).await; },
-                (true, true) => quote! { this.#name(event, &cx, &gx).await; },
+                (false, false) => quote! { this.#name(#event).await; },
+                (true, false) => quote! { this.#name(#event &cx).await; },
+                (false, true) => quote! { this.#name(#event &gx).await; },
+                (true, true) => quote! { this.#name(#event &cx, &gx).await; },
             };
             let clones = match (contexts.app_context, contexts.global_context) {
                 (false, false) => quote! { let this = self.clone(); },
@@ -1786,9 +1879,9 @@ fn generate_event_handler_call_and_clones(
         DispatchContextType::System => {
             // Systems only have GlobalContext
             let call = if contexts.global_context {
-                quote! { this.#name(event, &gx).await; }
+                quote! { this.#name(#event &gx).await; }
             } else {
-                quote! { this.#name(event).await; }
+                quote! { this.#name(#event).await; }
             };
             let clones = if contexts.global_context {
                 quote! { let this = self.clone(); let gx = gx.clone(); }
@@ -1804,14 +1897,14 @@ fn generate_event_handler_call_and_clones(
                 contexts.global_context,
                 contexts.modal_context,
             ) {
-                (false, false, false) => quote! { this.#name(event).await; },
-                (true, false, false) => quote! { this.#name(event, &cx).await; },
-                (false, true, false) => quote! { this.#name(event, &gx).await; },
-                (true, true, false) => quote! { this.#name(event, &cx, &gx).await; },
-                (false, false, true) => quote! { this.#name(event, &mx).await; },
-                (true, false, true) => quote! { this.#name(event, &cx, &mx).await; },
-                (false, true, true) => quote! { this.#name(event, &gx, &mx).await; },
-                (true, true, true) => quote! { this.#name(event, &cx, &gx, &mx).await; },
+                (false, false, false) => quote! { this.#name(#event).await; },
+                (true, false, false) => quote! { this.#name(#event &cx).await; },
+                (false, true, false) => quote! { this.#name(#event &gx).await; },
+                (true, true, false) => quote! { this.#name(#event &cx, &gx).await; },
+                (false, false, true) => quote! { this.#name(#event &mx).await; },
+                (true, false, true) => quote! { this.#name(#event &cx, &mx).await; },
+                (false, true, true) => quote! { this.#name(#event &gx, &mx).await; },
+                (true, true, true) => quote! { this.#name(#event &cx, &gx, &mx).await; },
             };
             let clones = match (
                 contexts.app_context,
@@ -1840,10 +1933,10 @@ fn generate_event_handler_call_and_clones(
         DispatchContextType::SystemModal => {
             // System modals only have GlobalContext, ModalContext accessed via mx parameter (no AppContext)
             let call = match (contexts.global_context, contexts.modal_context) {
-                (false, false) => quote! { this.#name(event).await; },
-                (true, false) => quote! { this.#name(event, &gx).await; },
-                (false, true) => quote! { this.#name(event, &mx).await; },
-                (true, true) => quote! { this.#name(event, &gx, &mx).await; },
+                (false, false) => quote! { this.#name(#event).await; },
+                (true, false) => quote! { this.#name(#event &gx).await; },
+                (false, true) => quote! { this.#name(#event &mx).await; },
+                (true, true) => quote! { this.#name(#event &gx, &mx).await; },
             };
             let clones = match (contexts.global_context, contexts.modal_context) {
                 (false, false) => quote! { let this = self.clone(); },
@@ -1861,16 +1954,17 @@ fn generate_event_handler_call_and_clones(
 /// Generate request handler call expression and clone statements.
 fn generate_request_handler_call_and_clones(
     name: &Ident,
+    request: &TokenStream,
     contexts: &HandlerContexts,
     context_type: DispatchContextType,
 ) -> (TokenStream, TokenStream) {
     match context_type {
         DispatchContextType::App => {
             let call = match (contexts.app_context, contexts.global_context) {
-                (false, false) => quote! { this.#name(*request).await },
-                (true, false) => quote! { this.#name(*request, &cx).await },
-                (false, true) => quote! { this.#name(*request, &gx).await },
-                (true, true) => quote! { this.#name(*request, &cx, &gx).await },
+                (false, false) => quote! { this.#name(#request).await },
+                (true, false) => quote! { this.#name(#request &cx).await },
+                (false, true) => quote! { this.#name(#request &gx).await },
+                (true, true) => quote! { this.#name(#request &cx, &gx).await },
             };
             let clones = match (contexts.app_context, contexts.global_context) {
                 (false, false) => quote! { let this = self.clone(); },
@@ -1885,9 +1979,9 @@ fn generate_request_handler_call_and_clones(
         DispatchContextType::System => {
             // Systems only have GlobalContext
             let call = if contexts.global_context {
-                quote! { this.#name(*request, &gx).await }
+                quote! { this.#name(#request &gx).await }
             } else {
-                quote! { this.#name(*request).await }
+                quote! { this.#name(#request).await }
             };
             let clones = if contexts.global_context {
                 quote! { let this = self.clone(); let gx = gx.clone(); }
@@ -1900,10 +1994,10 @@ fn generate_request_handler_call_and_clones(
         DispatchContextType::AppModal | DispatchContextType::SystemModal => {
             // Fallback to App-like behavior in case this is ever called
             let call = match (contexts.app_context, contexts.global_context) {
-                (false, false) => quote! { this.#name(*request).await },
-                (true, false) => quote! { this.#name(*request, &cx).await },
-                (false, true) => quote! { this.#name(*request, &gx).await },
-                (true, true) => quote! { this.#name(*request, &cx, &gx).await },
+                (false, false) => quote! { this.#name(#request).await },
+                (true, false) => quote! { this.#name(#request &cx).await },
+                (false, true) => quote! { this.#name(#request &gx).await },
+                (true, true) => quote! { this.#name(#request &cx, &gx).await },
             };
             let clones = match (contexts.app_context, contexts.global_context) {
                 (false, false) => quote! { let this = self.clone(); },
@@ -1918,6 +2012,36 @@ fn generate_request_handler_call_and_clones(
     }
 }
 
+/// Give every token in `tokens` the same span, so type errors in generated
+/// code point at the attribute that caused them.
+fn respan(tokens: TokenStream, span: proc_macro2::Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tree| {
+            if let proc_macro2::TokenTree::Group(group) = &tree {
+                let mut respanned =
+                    proc_macro2::Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                tree = proc_macro2::TokenTree::Group(respanned);
+            } else {
+                tree.set_span(span);
+            }
+            tree
+        })
+        .collect()
+}
+
+/// Assert at compile time that a declared message type implements `trait_path`,
+/// with the error pointing at the type in the attribute.
+fn assert_message_trait(ty: &Type, trait_path: TokenStream) -> TokenStream {
+    quote_spanned! {ty.span()=>
+        {
+            fn __rafter_assert_message<T: #trait_path>() {}
+            __rafter_assert_message::<#ty>();
+        }
+    }
+}
+
 // =============================================================================
 // Handler Wrapper Generation
 // =============================================================================
@@ -1,7 +1,20 @@
 //! The `keybinds!` macro for defining keybind mappings.
 //!
-//! Parses a DSL and generates `Keybinds::add_str` calls.
+//! Parses a DSL and generates `Keybinds::add_binding` calls.
 //! Parsing happens at runtime, errors are logged and skipped.
+//!
+//! ```ignore
+//! keybinds! {
+//!     "ctrl+s" => save,
+//!     "q" | "escape" => quit: "Quit the application",
+//!     "ctrl+k ctrl+s" | ["ctrl+k", "s"] => save_all: "Save all files",
+//! }
+//! ```
+//!
+//! Each entry is one keybind. Key strings separated by `|` are aliases; a
+//! key string with several space-separated chords, or a `[...]` list of
+//! chords, is a sequence. An optional `: "description"` after the handler is
+//! shown in help overlays.
 
 use proc_macro2::TokenStream;
 use quote::quote;
@@ -11,12 +24,33 @@ use syn::{
     parse2,
 };
 
-/// A single keybind entry: "key" | "key2" => handler
+/// A single keybind entry: "key" | "key2" => handler: "description"
 struct KeybindEntry {
     /// Key strings (alternatives separated by |)
     keys: Vec<LitStr>,
     /// Handler name
     handler: Ident,
+    /// Optional description
+    description: Option<LitStr>,
+}
+
+/// A key string, or a `[...]` list of chords joined into one sequence.
+fn parse_keys(input: ParseStream) -> syn::Result<LitStr> {
+    if !input.peek(syn::token::Bracket) {
+        return input.parse();
+    }
+
+    let content;
+    let bracket = syn::bracketed!(content in input);
+    let chords = content.parse_terminated(<LitStr as Parse>::parse, Token![,])?;
+    if chords.is_empty() {
+        return Err(syn::Error::new(
+            bracket.span.join(),
+            "expected at least one key string",
+        ));
+    }
+    let sequence: Vec<String> = chords.iter().map(LitStr::value).collect();
+    Ok(LitStr::new(&sequence.join(" "), bracket.span.join()))
 }
 
 impl Parse for KeybindEntry {
@@ -24,14 +58,12 @@ impl Parse for KeybindEntry {
         let mut keys = Vec::new();
 
         // Parse first key
-        let first: LitStr = input.parse()?;
-        keys.push(first);
+        keys.push(parse_keys(input)?);
 
         // Parse alternatives: | "key2" | "key3"
         while input.peek(Token![|]) {
             input.parse::<Token![|]>()?;
-            let alt: LitStr = input.parse()?;
-            keys.push(alt);
+            keys.push(parse_keys(input)?);
         }
 
         // Parse =>
@@ -40,7 +72,19 @@ impl Parse for KeybindEntry {
         // Parse handler name
         let handler: Ident = input.parse()?;
 
-        Ok(Self { keys, handler })
+        // Parse optional description: handler: "description"
+        let description = if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            keys,
+            handler,
+            description,
+        })
     }
 }
 
@@ -76,12 +120,15 @@ pub fn expand(input: TokenStream) -> TokenStream {
 
     for entry in keybinds.entries {
         let handler_name = entry.handler.to_string();
-
-        for key_lit in &entry.keys {
-            add_calls.push(quote! {
-                __keybinds.add_str(#key_lit, #handler_name);
-            });
-        }
+        let keys = &entry.keys;
+        let description = match &entry.description {
+            Some(description) => quote! { Some(#description) },
+            None => quote! { None },
+        };
+
+        add_calls.push(quote! {
+            __keybinds.add_binding(#handler_name, &[#(#keys),*], #description);
+        });
     }
 
     quote! {
@@ -1,5 +1,6 @@
 pub mod app;
 pub mod app_impl;
+pub mod component;
 pub mod dep_detection;
 pub mod derived;
 pub mod event;
@@ -11,10 +12,13 @@ pub mod impl_common;
 pub mod keybinds;
 pub mod modal;
 pub mod modal_impl;
+pub mod on_event;
+pub mod on_request;
 pub mod page;
 pub mod request;
 pub mod request_handler;
 pub mod system;
 pub mod system_impl;
 pub mod theme;
+pub mod validatable;
 pub mod watch;
@@ -183,7 +183,11 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         // Parse the reconstructed method to extract metadata
         let reconstructed = reconstruct_method(method);
         if let Ok(impl_item) = syn::parse2::<syn::ImplItemFn>(reconstructed.clone()) {
-            if let Some(mut event_handler) = parse_event_handler_metadata(&impl_item) {
+            let event_handler = match parse_event_handler_metadata(&impl_item) {
+                Ok(event_handler) => event_handler,
+                Err(e) => return e.to_compile_error(),
+            };
+            if let Some(mut event_handler) = event_handler {
                 // For system modals, validate that event handlers don't use AppContext
                 if attrs.kind == ModalKindAttr::System && event_handler.contexts.app_context {
                     return syn::Error::new_spanned(
@@ -0,0 +1,9 @@
+//! The `#[on_event(T)]` attribute macro for event subscriber methods.
+
+use proc_macro2::TokenStream;
+
+use super::handler_common::expand_declared_message_handler;
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_declared_message_handler(attr, item, false)
+}
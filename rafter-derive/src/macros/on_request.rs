@@ -0,0 +1,9 @@
+//! The `#[on_request(T)]` attribute macro for request responder methods.
+
+use proc_macro2::TokenStream;
+
+use super::handler_common::expand_declared_message_handler;
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_declared_message_handler(attr, item, true)
+}
@@ -3,13 +3,26 @@
 use syn::{Expr, Ident};
 
 /// Root node of the page! macro - contains a single element or expression.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Page {
+    /// Theme to check color names against, from `#![theme(Path)]`
+    pub theme: Option<syn::Path>,
+    /// Template file the view was loaded from, from `#![template("path")]`
+    pub template: Option<Template>,
     pub root: ViewNode,
 }
 
+/// A template file read at compile time.
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// Absolute path of the file
+    pub path: String,
+    /// File contents when the macro ran
+    pub source: String,
+}
+
 /// A node in the view tree.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ViewNode {
     /// An element (column, row, button, etc.)
     Element(ElementNode),
@@ -26,7 +39,7 @@ pub enum ViewNode {
 }
 
 /// An element node (e.g., `column (padding: 1) style (bg: primary) { ... }`)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ElementNode {
     /// Element name (column, row, button, etc.)
     pub name: Ident,
@@ -45,14 +58,14 @@ pub struct ElementNode {
 }
 
 /// An attribute (key: value pair)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Attr {
     pub name: Ident,
     pub value: AttrValue,
 }
 
 /// A transition attribute (e.g., `bg: 200ms ease_out`)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TransitionAttr {
     /// Property name (bg, fg, all, width, height, etc.)
     pub property: Ident,
@@ -63,7 +76,7 @@ pub struct TransitionAttr {
 }
 
 /// Attribute value types
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AttrValue {
     /// Identifier (e.g., `primary`, `center`)
     Ident(Ident),
@@ -83,7 +96,7 @@ pub enum AttrValue {
 }
 
 /// Else branch of a conditional attribute value
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AttrValueElse {
     /// else { value }
     Else(Box<AttrValue>),
@@ -92,7 +105,7 @@ pub enum AttrValueElse {
 }
 
 /// An if node within a conditional attribute value (for else-if chains)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AttrValueIf {
     pub cond: Expr,
     pub then_value: Box<AttrValue>,
@@ -100,7 +113,7 @@ pub struct AttrValueIf {
 }
 
 /// An inline handler attribute (e.g., `on_click: handler(arg1, cx)`)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HandlerAttr {
     /// Event name (on_click, on_change, etc.)
     pub event: Ident,
@@ -114,7 +127,7 @@ pub struct HandlerAttr {
 ///
 /// Note: Handler arguments are parsed but not currently supported.
 /// Reserved for future handler argument passing support.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum HandlerArg {
     /// A regular expression to capture (cloned at render time)
@@ -124,7 +137,10 @@ pub enum HandlerArg {
 }
 
 /// A for loop node (e.g., `for item in items { ... }`)
-#[derive(Debug)]
+///
+/// A `(key: expr)` after the iterator is applied while parsing: top-level body
+/// elements without an explicit `id` get one derived from the key.
+#[derive(Debug, Clone)]
 pub struct ForNode {
     /// Loop variable pattern
     pub pat: syn::Pat,
@@ -135,7 +151,7 @@ pub struct ForNode {
 }
 
 /// An if/else node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IfNode {
     /// Condition expression
     pub cond: Expr,
@@ -146,7 +162,7 @@ pub struct IfNode {
 }
 
 /// Else branch of an if node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ElseBranch {
     /// else { ... }
     Else(Vec<ViewNode>),
@@ -155,7 +171,7 @@ pub enum ElseBranch {
 }
 
 /// A match expression node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MatchNode {
     /// Expression being matched
     pub expr: Expr,
@@ -164,7 +180,7 @@ pub struct MatchNode {
 }
 
 /// A match arm
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MatchArm {
     /// Pattern to match
     pub pat: syn::Pat,
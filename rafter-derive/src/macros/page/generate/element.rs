@@ -318,7 +318,7 @@ fn generate_widget(elem: &ElementNode, mode: CodegenMode) -> TokenStream {
 }
 
 /// Convert snake_case to PascalCase
-fn snake_to_pascal(s: &str) -> String {
+pub(crate) fn snake_to_pascal(s: &str) -> String {
     s.split('_')
         .map(|word| {
             let mut chars = word.chars();
@@ -343,10 +343,12 @@ fn is_layout_attr(name: &str) -> bool {
             | "max_width"
             | "min_height"
             | "max_height"
+            | "aspect_ratio"
             | "direction"
             | "justify"
             | "align"
             | "wrap"
+            | "align_content"
             | "text_wrap"
             | "flex_grow"
             | "flex_shrink"
@@ -368,7 +370,7 @@ fn is_layout_attr(name: &str) -> bool {
 /// Check if an attribute is a layout attribute for widgets.
 /// Unlike containers, widgets handle id/focusable/clickable/draggable themselves
 /// (needed before build() for handler registration).
-fn is_widget_layout_attr(name: &str) -> bool {
+pub(crate) fn is_widget_layout_attr(name: &str) -> bool {
     matches!(
         name,
         "padding"
@@ -380,10 +382,12 @@ fn is_widget_layout_attr(name: &str) -> bool {
             | "max_width"
             | "min_height"
             | "max_height"
+            | "aspect_ratio"
             | "direction"
             | "justify"
             | "align"
             | "wrap"
+            | "align_content"
             | "text_wrap"
             | "flex_grow"
             | "flex_shrink"
@@ -422,10 +426,12 @@ fn generate_layout_calls(attrs: &[&Attr]) -> Vec<TokenStream> {
             "max_width" => calls.push(quote! { .max_width(#value as u16) }),
             "min_height" => calls.push(quote! { .min_height(#value as u16) }),
             "max_height" => calls.push(quote! { .max_height(#value as u16) }),
+            "aspect_ratio" => calls.push(quote! { .aspect_ratio(#value as f32) }),
             "direction" => calls.push(generate_direction_call(&attr.value)),
-            "justify" => calls.push(generate_justify_call(&attr.value)),
+            "justify" => calls.push(generate_justify_call("justify", &attr.value)),
             "align" => calls.push(generate_align_call(&attr.value)),
             "wrap" => calls.push(generate_wrap_call(&attr.value)),
+            "align_content" => calls.push(generate_justify_call("align_content", &attr.value)),
             "text_wrap" => calls.push(generate_text_wrap_call(&attr.value)),
             "flex_grow" => calls.push(quote! { .flex_grow(#value as u16) }),
             "flex_shrink" => calls.push(quote! { .flex_shrink(#value as u16) }),
@@ -676,8 +682,10 @@ fn generate_direction_call(value: &AttrValue) -> TokenStream {
     }
 }
 
-/// Generate justify call
-fn generate_justify_call(value: &AttrValue) -> TokenStream {
+/// Generate a justify-style call (`justify` or `align_content`)
+fn generate_justify_call(method: &str, value: &AttrValue) -> TokenStream {
+    let method_ident = syn::Ident::new(method, proc_macro2::Span::call_site());
+
     // Handle conditional values
     if is_conditional(value) {
         let justify_value = generate_conditional_attr_value(value, |leaf| match leaf {
@@ -695,27 +703,31 @@ fn generate_justify_call(value: &AttrValue) -> TokenStream {
             }
             _ => generate_attr_value(leaf),
         });
-        return quote! { .justify(#justify_value) };
+        return quote! { .#method_ident(#justify_value) };
     }
 
     match value {
         AttrValue::Ident(ident) => {
             let ident_str = ident.to_string();
             match ident_str.as_str() {
-                "start" => quote! { .justify(tuidom::Justify::Start) },
-                "end" => quote! { .justify(tuidom::Justify::End) },
-                "center" => quote! { .justify(tuidom::Justify::Center) },
+                "start" => quote! { .#method_ident(tuidom::Justify::Start) },
+                "end" => quote! { .#method_ident(tuidom::Justify::End) },
+                "center" => quote! { .#method_ident(tuidom::Justify::Center) },
                 "between" | "space_between" => {
-                    quote! { .justify(tuidom::Justify::SpaceBetween) }
+                    quote! { .#method_ident(tuidom::Justify::SpaceBetween) }
+                }
+                "around" | "space_around" => {
+                    quote! { .#method_ident(tuidom::Justify::SpaceAround) }
                 }
-                "around" | "space_around" => quote! { .justify(tuidom::Justify::SpaceAround) },
-                "evenly" | "space_evenly" => quote! { .justify(tuidom::Justify::SpaceEvenly) },
-                _ => quote! { .justify(#ident) },
+                "evenly" | "space_evenly" => {
+                    quote! { .#method_ident(tuidom::Justify::SpaceEvenly) }
+                }
+                _ => quote! { .#method_ident(#ident) },
             }
         }
         _ => {
             let val = generate_attr_value(value);
-            quote! { .justify(#val) }
+            quote! { .#method_ident(#val) }
         }
     }
 }
@@ -3,8 +3,12 @@
 mod element;
 pub mod handler;
 pub mod style;
+mod template;
+pub mod theme_check;
 pub mod transition;
 
+pub(crate) use element::{is_widget_layout_attr, snake_to_pascal};
+
 use proc_macro2::TokenStream;
 use quote::quote;
 
@@ -89,12 +93,34 @@ pub fn is_conditional(value: &AttrValue) -> bool {
 
 /// Generate code for the entire page (with handler support)
 pub fn generate(page: &Page) -> TokenStream {
-    generate_view_node(&page.root, CodegenMode::Page)
+    generate_root(page, CodegenMode::Page)
 }
 
 /// Generate code for the entire element (without handler support)
 pub fn generate_element(page: &Page) -> TokenStream {
-    generate_view_node(&page.root, CodegenMode::Element)
+    generate_root(page, CodegenMode::Element)
+}
+
+fn generate_root(page: &Page, mode: CodegenMode) -> TokenStream {
+    let root = match &page.template {
+        Some(template) => template::generate(template, &page.root, mode),
+        None => generate_view_node(&page.root, mode),
+    };
+    with_color_checks(page, root)
+}
+
+/// Prefix the generated tree with theme color checks, if the page names a theme
+fn with_color_checks(page: &Page, root: TokenStream) -> TokenStream {
+    let checks = theme_check::generate_color_checks(page);
+    if checks.is_empty() {
+        return root;
+    }
+    quote! {
+        {
+            #checks
+            #root
+        }
+    }
 }
 
 /// Code generation mode
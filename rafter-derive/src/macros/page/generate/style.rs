@@ -12,43 +12,68 @@ use super::{generate_conditional_attr_value, is_conditional};
 /// Generate a single merged style call from style attributes.
 /// Returns None if no style attributes are present.
 pub fn generate_merged_style(attrs: &[&Attr]) -> Option<TokenStream> {
-    if attrs.is_empty() {
-        return None;
-    }
-
-    let style_methods: Vec<_> = attrs
-        .iter()
-        .filter_map(|attr| generate_style_method(attr))
-        .collect();
-
-    if style_methods.is_empty() {
-        return None;
-    }
-
+    let style = generate_style_expr(attrs)?;
     Some(quote! {
-        .style(tuidom::Style::new()#(#style_methods)*)
+        .style(#style)
     })
 }
 
 /// Generate a single merged style_focused call from style attributes.
 /// Returns None if no style attributes are present.
 pub fn generate_merged_style_focused(attrs: &[&Attr]) -> Option<TokenStream> {
-    if attrs.is_empty() {
-        return None;
-    }
+    let style = generate_style_expr(attrs)?;
+    Some(quote! {
+        .style_focused(#style)
+    })
+}
+
+/// Build the `Style` expression for a set of style attributes.
+///
+/// `class` attributes name style bundles: an identifier calls a function
+/// returning `tuidom::Style` (`class: card` -> `card()`), a braced expression
+/// is used as-is. Classes merge in order, then the other attributes apply on
+/// top, so `style (class: card, fg: accent)` is a card with an accent color.
+fn generate_style_expr(attrs: &[&Attr]) -> Option<TokenStream> {
+    let classes: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.name == "class")
+        .map(|attr| generate_class(&attr.value))
+        .collect();
 
     let style_methods: Vec<_> = attrs
         .iter()
         .filter_map(|attr| generate_style_method(attr))
         .collect();
 
-    if style_methods.is_empty() {
+    if classes.is_empty() && style_methods.is_empty() {
         return None;
     }
 
-    Some(quote! {
-        .style_focused(tuidom::Style::new()#(#style_methods)*)
-    })
+    let base = match classes.split_first() {
+        Some((first, rest)) => quote! {
+            (#first)#(.merge(&Some(#rest)))*
+        },
+        None => quote! { tuidom::Style::new() },
+    };
+
+    Some(quote! { #base #(#style_methods)* })
+}
+
+/// Generate the style bundle for a `class` attribute
+fn generate_class(value: &AttrValue) -> TokenStream {
+    if is_conditional(value) {
+        return generate_conditional_attr_value(value, generate_class_leaf);
+    }
+
+    generate_class_leaf(value)
+}
+
+fn generate_class_leaf(value: &AttrValue) -> TokenStream {
+    match value {
+        AttrValue::Ident(ident) => quote! { #ident() },
+        AttrValue::Expr(expr) => quote! { #expr },
+        _ => quote! { tuidom::Style::new() },
+    }
 }
 
 /// Generate a style method call for a single attribute
@@ -78,12 +103,18 @@ fn generate_style_method(attr: &Attr) -> Option<TokenStream> {
                 None
             }
         }
-        "underline" => {
-            if is_true_value(&attr.value) {
-                Some(quote! { .underline() })
-            } else {
-                None
+        "underline" => match &attr.value {
+            // underline: curly / double / dotted / dashed / single
+            AttrValue::Ident(ident) if ident != "true" && ident != "false" => {
+                let style = generate_underline_style(ident);
+                Some(quote! { .underline_style(#style) })
             }
+            value if is_true_value(value) => Some(quote! { .underline() }),
+            _ => None,
+        },
+        "underline_color" => {
+            let color = generate_color(&attr.value);
+            Some(quote! { .underline_color(#color) })
         }
         "dim" => {
             if is_true_value(&attr.value) {
@@ -100,6 +131,17 @@ fn generate_style_method(attr: &Attr) -> Option<TokenStream> {
     }
 }
 
+/// Generate an underline style from an identifier
+fn generate_underline_style(ident: &syn::Ident) -> TokenStream {
+    match ident.to_string().as_str() {
+        "double" => quote! { tuidom::UnderlineStyle::Double },
+        "curly" => quote! { tuidom::UnderlineStyle::Curly },
+        "dotted" => quote! { tuidom::UnderlineStyle::Dotted },
+        "dashed" => quote! { tuidom::UnderlineStyle::Dashed },
+        _ => quote! { tuidom::UnderlineStyle::Single },
+    }
+}
+
 /// Check if an attribute value is truthy (true, or just present)
 fn is_true_value(value: &AttrValue) -> bool {
     match value {
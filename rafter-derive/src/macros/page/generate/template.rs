@@ -0,0 +1,173 @@
+//! Code generation for pages loaded from `#![template("path")]`.
+//!
+//! The template is compiled like an inline page body, and the file is
+//! included as a string so editing it triggers a rebuild.
+//!
+//! With the `hot-reload` feature, a page whose root is a static element is
+//! instead handed to `rafter::hot_reload::page`, which re-reads the file when
+//! it changes and interprets it at runtime. Only static elements can be
+//! interpreted: `column`, `row`, `box` and `text` whose attributes are all
+//! literals, keywords or bare flags, with no handlers or transitions.
+//! Everything else (widgets, loops, conditionals, expressions) is a "hole",
+//! evaluated by the compiled code and spliced into the interpreted tree in
+//! document order.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::macros::page::ast::{Attr, AttrValue, ElementNode, Template, ViewNode};
+
+use super::{CodegenMode, generate_view_node};
+
+/// Elements the runtime interpreter can build.
+const STATIC_ELEMENTS: &[&str] = &["column", "col", "row", "box", "box_", "text"];
+
+/// Layout attributes whose identifier values are keywords, not variables.
+const KEYWORD_ATTRS: &[&str] = &[
+    "direction",
+    "justify",
+    "align",
+    "wrap",
+    "align_content",
+    "text_wrap",
+    "align_self",
+    "overflow",
+    "position",
+];
+
+/// Generate the view for a template-backed page.
+pub fn generate(template: &Template, root: &ViewNode, mode: CodegenMode) -> TokenStream {
+    let path = &template.path;
+    let view = if cfg!(feature = "hot-reload") {
+        generate_reloadable(template, root, mode)
+    } else {
+        None
+    };
+    let view = view.unwrap_or_else(|| generate_view_node(root, mode));
+
+    quote! {
+        {
+            const _: &str = include_str!(#path);
+            #view
+        }
+    }
+}
+
+/// Generate a view that reloads the template at runtime, or `None` if the
+/// root can't be interpreted.
+fn generate_reloadable(
+    template: &Template,
+    root: &ViewNode,
+    mode: CodegenMode,
+) -> Option<TokenStream> {
+    if !is_static(root) {
+        return None;
+    }
+
+    let mut holes = Vec::new();
+    let skeleton = split_holes(root, &mut holes);
+    let skeleton = generate_view_node(&skeleton, mode);
+    let holes = holes.iter().map(|hole| generate_view_node(hole, mode));
+    let path = &template.path;
+    let source = &template.source;
+
+    Some(quote! {
+        {
+            let __holes: Vec<Vec<tuidom::Element>> = vec![
+                #(rafter::IntoPageChildren::into_page_children(#holes)),*
+            ];
+            rafter::hot_reload::page(
+                #path,
+                #source,
+                __holes,
+                |__holes: &mut [Vec<tuidom::Element>]| #skeleton,
+            )
+        }
+    })
+}
+
+/// Copy a static node, moving its dynamic descendants into `holes` and
+/// leaving a placeholder that takes the hole's elements.
+fn split_holes(node: &ViewNode, holes: &mut Vec<ViewNode>) -> ViewNode {
+    let ViewNode::Element(elem) = node else {
+        return node.clone();
+    };
+
+    let children = elem
+        .children
+        .iter()
+        .map(|child| {
+            if is_static(child) {
+                return split_holes(child, holes);
+            }
+            let index = holes.len();
+            holes.push(child.clone());
+            ViewNode::Expr(syn::parse_quote! {
+                std::mem::take(&mut __holes[#index])
+            })
+        })
+        .collect();
+
+    ViewNode::Element(ElementNode {
+        children,
+        ..elem.clone()
+    })
+}
+
+/// Whether the runtime interpreter can build this node.
+///
+/// Must agree with the classification in `rafter::hot_reload`, which finds
+/// the holes in the template file by the same rules.
+fn is_static(node: &ViewNode) -> bool {
+    let ViewNode::Element(elem) = node else {
+        return false;
+    };
+    let name = elem.name.to_string();
+
+    STATIC_ELEMENTS.contains(&name.as_str())
+        && elem.handlers.is_empty()
+        && elem.transition_attrs.is_empty()
+        && (name != "text" || elem.children.is_empty())
+        && elem
+            .layout_attrs
+            .iter()
+            .all(|attr| is_static_attr(attr, true))
+        && elem
+            .style_attrs
+            .iter()
+            .chain(&elem.style_focused_attrs)
+            .all(|attr| is_static_attr(attr, false))
+}
+
+/// Whether an attribute's value is known without running Rust code.
+///
+/// Style identifiers are theme colors or keywords. Layout identifiers are
+/// variables, except for `KEYWORD_ATTRS` and `auto`/`fill` sizes.
+fn is_static_attr(attr: &Attr, layout: bool) -> bool {
+    let name = attr.name.to_string();
+    if name == "class" {
+        return false;
+    }
+    match &attr.value {
+        AttrValue::Ident(ident) => {
+            !layout
+                || KEYWORD_ATTRS.contains(&name.as_str())
+                || (matches!(name.as_str(), "width" | "height")
+                    && (ident == "auto" || ident == "fill"))
+        }
+        AttrValue::Lit(_) | AttrValue::BareFlag => true,
+        AttrValue::Expr(syn::Expr::Tuple(tuple)) => {
+            matches!(tuple.elems.len(), 2 | 4)
+                && tuple.elems.iter().all(|elem| {
+                    matches!(
+                        elem,
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(_),
+                            ..
+                        })
+                    )
+                })
+        }
+        _ => false,
+    }
+}
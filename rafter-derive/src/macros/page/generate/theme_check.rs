@@ -0,0 +1,137 @@
+//! Compile-time color name checks for page! with `#![theme(Path)]`.
+//!
+//! Every theme color used in a style block becomes a reference to a constant
+//! in the theme's companion module (generated by `#[theme]`), so a name the
+//! theme doesn't define is a compile error at the color, with the compiler's
+//! usual "similar name" suggestion.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::Ident;
+
+use crate::macros::page::ast::{
+    Attr, AttrValue, AttrValueElse, ElementNode, ElseBranch, IfNode, Page, ViewNode,
+};
+use crate::macros::theme::colors_module_name;
+
+/// Generate the color checks for a page, or nothing if it names no theme.
+pub fn generate_color_checks(page: &Page) -> TokenStream {
+    let Some(theme) = &page.theme else {
+        return quote! {};
+    };
+
+    let mut module = theme.clone();
+    if let Some(last) = module.segments.last_mut() {
+        last.ident = colors_module_name(&last.ident);
+        last.arguments = syn::PathArguments::None;
+    }
+
+    let mut colors = Vec::new();
+    collect_node(&page.root, &mut colors);
+
+    let checks = colors.iter().map(|color| match color {
+        Ok((segments, span)) => quote_spanned! {*span=>
+            const _: () = #module #(::#segments)*;
+        },
+        Err(e) => e.to_compile_error(),
+    });
+
+    quote! { #(#checks)* }
+}
+
+type ColorRef = syn::Result<(Vec<Ident>, Span)>;
+
+fn collect_node(node: &ViewNode, colors: &mut Vec<ColorRef>) {
+    match node {
+        ViewNode::Element(elem) => collect_element(elem, colors),
+        ViewNode::For(for_node) => collect_nodes(&for_node.body, colors),
+        ViewNode::If(if_node) => collect_if(if_node, colors),
+        ViewNode::Match(match_node) => {
+            for arm in &match_node.arms {
+                collect_nodes(&arm.body, colors);
+            }
+        }
+        ViewNode::Expr(_) | ViewNode::Spread(_) => {}
+    }
+}
+
+fn collect_nodes(nodes: &[ViewNode], colors: &mut Vec<ColorRef>) {
+    for node in nodes {
+        collect_node(node, colors);
+    }
+}
+
+fn collect_if(if_node: &IfNode, colors: &mut Vec<ColorRef>) {
+    collect_nodes(&if_node.then_branch, colors);
+    match &if_node.else_branch {
+        Some(ElseBranch::Else(body)) => collect_nodes(body, colors),
+        Some(ElseBranch::ElseIf(next)) => collect_if(next, colors),
+        None => {}
+    }
+}
+
+fn collect_element(elem: &ElementNode, colors: &mut Vec<ColorRef>) {
+    let attrs = elem.style_attrs.iter().chain(&elem.style_focused_attrs);
+    for attr in attrs.filter(|attr| is_color_attr(attr)) {
+        collect_value(&attr.value, colors);
+    }
+    collect_nodes(&elem.children, colors);
+}
+
+fn is_color_attr(attr: &Attr) -> bool {
+    matches!(
+        attr.name.to_string().as_str(),
+        "bg" | "background" | "fg" | "foreground" | "color" | "underline_color"
+    )
+}
+
+fn collect_value(value: &AttrValue, colors: &mut Vec<ColorRef>) {
+    match value {
+        AttrValue::Ident(ident) => colors.push(Ok((vec![ident.clone()], ident.span()))),
+        AttrValue::Lit(syn::Lit::Str(lit)) => {
+            let name = lit.value();
+            // Hex colors don't come from the theme
+            if name.starts_with('#') {
+                return;
+            }
+            let segments: Option<Vec<Ident>> = name
+                .split('.')
+                .map(|segment| is_ident(segment).then(|| Ident::new(segment, lit.span())))
+                .collect();
+            colors.push(match segments {
+                Some(segments) => Ok((segments, lit.span())),
+                None => Err(syn::Error::new_spanned(
+                    lit,
+                    format!("`{}` is not a valid theme color name", name),
+                )),
+            });
+        }
+        AttrValue::If {
+            then_value,
+            else_branch,
+            ..
+        } => {
+            collect_value(then_value, colors);
+            collect_else(else_branch, colors);
+        }
+        // Expressions produce colors at runtime
+        _ => {}
+    }
+}
+
+fn collect_else(else_branch: &AttrValueElse, colors: &mut Vec<ColorRef>) {
+    match else_branch {
+        AttrValueElse::Else(value) => collect_value(value, colors),
+        AttrValueElse::ElseIf(next) => {
+            collect_value(&next.then_value, colors);
+            collect_else(&next.else_branch, colors);
+        }
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && syn::parse_str::<Ident>(s).is_ok()
+}
@@ -1,9 +1,25 @@
 //! The page! macro for declarative UI definitions.
 //!
 //! Outputs `tuidom::Element` using a builder pattern.
+//!
+//! `for` loops take an optional key, `for item in items (key: item.id) { ... }`.
+//! Top-level elements in a keyed loop body get ids derived from the key, so
+//! they keep focus and widget state when items are inserted, removed or
+//! reordered. The key must implement `Display`.
+//!
+//! `style (class: card, fg: accent)` starts from a named style bundle, a
+//! function returning `tuidom::Style`, and applies the other style attributes
+//! on top. Several `class` attributes merge in order.
+//!
+//! A leading `#![theme(MyTheme)]` checks every theme color used in style
+//! blocks against the `#[theme]` struct `MyTheme` at compile time.
+//!
+//! `#![template("pages/home.page")]` takes the body from a file, relative to
+//! the crate root, instead of the macro input. With rafter's `hot-reload`
+//! feature, edits to the file's static elements apply while the app runs.
 
 pub mod ast;
-mod generate;
+pub(crate) mod generate;
 pub mod parse;
 
 use proc_macro2::TokenStream;
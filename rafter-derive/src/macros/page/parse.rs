@@ -6,14 +6,76 @@ use syn::{Expr, Ident, Pat, Token, braced, parenthesized};
 
 use super::ast::{
     Attr, AttrValue, AttrValueElse, AttrValueIf, ElementNode, ElseBranch, ForNode, HandlerArg,
-    HandlerAttr, IfNode, MatchArm, MatchNode, Page, TransitionAttr, ViewNode,
+    HandlerAttr, IfNode, MatchArm, MatchNode, Page, Template, TransitionAttr, ViewNode,
 };
 
 impl Parse for Page {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut theme = None;
+        let mut template = None;
+        for attr in syn::Attribute::parse_inner(input)? {
+            if attr.path().is_ident("theme") {
+                theme = Some(attr.parse_args::<syn::Path>()?);
+            } else if attr.path().is_ident("template") {
+                template = Some(attr.parse_args::<syn::LitStr>()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr.path(),
+                    "unknown page attribute, expected `#![theme(Path)]` or `#![template(\"path\")]`",
+                ));
+            }
+        }
+
+        if let Some(path) = template {
+            if !input.is_empty() {
+                return Err(input.error("a page with `#![template(...)]` can't have a body"));
+            }
+            let mut page = parse_template(&path)?;
+            page.theme = theme.or(page.theme);
+            return Ok(page);
+        }
+
         let root = parse_view_node(input)?;
-        Ok(Page { root })
+        Ok(Page {
+            theme,
+            template: None,
+            root,
+        })
+    }
+}
+
+/// Read and parse a template file, relative to the crate root.
+///
+/// The file holds what would otherwise be the page! body, including any
+/// `#![theme(...)]`.
+fn parse_template(path: &syn::LitStr) -> syn::Result<Page> {
+    let error = |message: String| syn::Error::new(path.span(), message);
+
+    let root = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| error("CARGO_MANIFEST_DIR is not set".to_string()))?;
+    let full_path = std::path::Path::new(&root).join(path.value());
+    let source = std::fs::read_to_string(&full_path)
+        .map_err(|e| error(format!("can't read template `{}`: {}", path.value(), e)))?;
+    let tokens: proc_macro2::TokenStream = source
+        .parse()
+        .map_err(|e| error(format!("in template `{}`: {}", path.value(), e)))?;
+    let page: Page =
+        syn::parse2(tokens).map_err(|e| error(format!("in template `{}`: {}", path.value(), e)))?;
+
+    if page.template.is_some() {
+        return Err(error(format!(
+            "template `{}` can't name another template",
+            path.value()
+        )));
     }
+
+    Ok(Page {
+        template: Some(Template {
+            path: full_path.to_string_lossy().into_owned(),
+            source,
+        }),
+        ..page
+    })
 }
 
 /// Parse a single view node
@@ -472,16 +534,94 @@ fn parse_for(input: ParseStream) -> syn::Result<ForNode> {
     let pat: Pat = Pat::parse_single(input)?;
     input.parse::<Token![in]>()?;
 
-    // Parse the iterator expression (everything up to the brace)
-    let iter = parse_expr_before_brace(input)?;
+    // Parse the iterator expression (everything up to the brace), with an
+    // optional trailing `(key: expr)`
+    let (iter, key) = parse_for_iter(input)?;
 
     let content;
     braced!(content in input);
-    let body = parse_children(&content)?;
+    let mut body = parse_children(&content)?;
+
+    if let Some(key) = &key {
+        assign_keyed_ids(&mut body, &pat, &iter, key);
+    }
 
     Ok(ForNode { pat, iter, body })
 }
 
+/// Parse `iter_expr` or `iter_expr (key: key_expr)` up to the loop body.
+fn parse_for_iter(input: ParseStream) -> syn::Result<(Expr, Option<Expr>)> {
+    let mut tokens = Vec::new();
+    while !input.is_empty() && !input.peek(Brace) {
+        tokens.push(input.parse::<proc_macro2::TokenTree>()?);
+    }
+
+    // A trailing `(key: ...)` group. Never valid Rust as call arguments, so it
+    // can't be part of the iterator expression.
+    let key = match tokens.last() {
+        Some(proc_macro2::TokenTree::Group(group))
+            if group.delimiter() == proc_macro2::Delimiter::Parenthesis && tokens.len() > 1 =>
+        {
+            syn::parse2::<ForKey>(group.stream())
+                .ok()
+                .map(|for_key| for_key.0)
+        }
+        _ => None,
+    };
+    if key.is_some() {
+        tokens.pop();
+    }
+
+    if tokens.is_empty() {
+        return Err(input.error("expected expression"));
+    }
+
+    let token_stream: proc_macro2::TokenStream = tokens.into_iter().collect();
+    Ok((syn::parse2(token_stream)?, key))
+}
+
+/// `key: expr` inside a for loop's parentheses.
+struct ForKey(Expr);
+
+impl Parse for ForKey {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        if name != "key" {
+            return Err(syn::Error::new(name.span(), "expected `key`"));
+        }
+        input.parse::<Token![:]>()?;
+        Ok(ForKey(input.parse()?))
+    }
+}
+
+/// Give each top-level element in a keyed loop body a stable id built from
+/// the key, unless it already has one. Without this, generated elements get a
+/// fresh id every render and lose focus and widget state.
+fn assign_keyed_ids(body: &mut [ViewNode], pat: &Pat, iter: &Expr, key: &Expr) {
+    use std::hash::{Hash, Hasher};
+
+    // Distinguishes loops from each other; stable for a given source
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    quote::quote!(#pat #iter #key).to_string().hash(&mut hasher);
+    let site = hasher.finish() as u32;
+
+    for (index, node) in body.iter_mut().enumerate() {
+        let ViewNode::Element(elem) = node else {
+            continue;
+        };
+        if elem.layout_attrs.iter().any(|a| a.name == "id") {
+            continue;
+        }
+        let prefix = format!("{}-{:08x}-{}", elem.name, site, index);
+        elem.layout_attrs.push(Attr {
+            name: Ident::new("id", elem.name.span()),
+            value: AttrValue::Expr(syn::parse_quote! {
+                format!("{}:{}", #prefix, #key)
+            }),
+        });
+    }
+}
+
 /// Parse an if statement: `if cond { then } else { else }`
 fn parse_if(input: ParseStream) -> syn::Result<IfNode> {
     input.parse::<Token![if]>()?;
@@ -15,15 +15,12 @@ pub fn expand(input: TokenStream) -> TokenStream {
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     // Find #[response(Type)] attribute
-    let response_type = input.attrs.iter().find_map(|attr| {
-        if attr.path().is_ident("response") {
-            attr.parse_args::<Type>().ok()
-        } else {
-            None
-        }
-    });
+    let response_attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("response"));
 
-    let Some(response_type) = response_type else {
+    let Some(response_attr) = response_attr else {
         return syn::Error::new_spanned(
             &input,
             "#[derive(Request)] requires #[response(Type)] attribute",
@@ -31,6 +28,11 @@ pub fn expand(input: TokenStream) -> TokenStream {
         .to_compile_error();
     };
 
+    let response_type = match response_attr.parse_args::<Type>() {
+        Ok(ty) => ty,
+        Err(e) => return e.to_compile_error(),
+    };
+
     quote! {
         impl #impl_generics rafter::Request for #name #ty_generics #where_clause {
             type Response = #response_type;
@@ -97,15 +97,15 @@ pub fn expand(attr: TokenStream, item: TokenStream) -> TokenStream {
         // For event/request handlers, we need to convert to ImplItemFn temporarily
         let reconstructed = reconstruct_method(method);
         if let Ok(impl_item) = syn::parse2::<syn::ImplItemFn>(reconstructed.clone()) {
-            if method.has_attr("event_handler")
-                && let Some(event_handler) = parse_event_handler_metadata(&impl_item)
-            {
-                event_handlers.push(event_handler);
+            match parse_event_handler_metadata(&impl_item) {
+                Ok(Some(event_handler)) => event_handlers.push(event_handler),
+                Ok(None) => {}
+                Err(e) => return e.to_compile_error(),
             }
-            if method.has_attr("request_handler")
-                && let Some(request_handler) = parse_request_handler_metadata(&impl_item)
-            {
-                request_handlers.push(request_handler);
+            match parse_request_handler_metadata(&impl_item) {
+                Ok(Some(request_handler)) => request_handlers.push(request_handler),
+                Ok(None) => {}
+                Err(e) => return e.to_compile_error(),
             }
             if method.has_attr("watch")
                 && let Some(watch_method) = parse_watch_metadata(&impl_item)
@@ -2,10 +2,56 @@
 //!
 //! Generates a `Theme` implementation for structs with Color fields.
 //! Supports nested groups via the `#[group]` attribute.
+//!
+//! Also generates a hidden companion module listing the theme's color names
+//! as constants (`MyTheme` -> `__my_theme_colors`), with groups as nested
+//! modules. `page!` with `#![theme(MyTheme)]` references these constants for
+//! each color it uses, so a misspelled color fails to compile.
 
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{DeriveInput, Fields, parse2};
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Fields, Ident, Type, Visibility, parse2};
+
+use super::impl_common::to_snake_case;
+
+/// Name of the companion module holding a theme's color names.
+pub fn colors_module_name(theme: &Ident) -> Ident {
+    format_ident!("__{}_colors", to_snake_case(&theme.to_string()))
+}
+
+/// Path to a group type's companion module, from a type path like `ButtonColors`,
+/// as seen from inside the theme's own companion module.
+fn group_colors_path(ty: &Type) -> Option<TokenStream> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let mut path = path.path.clone();
+    let first = path.segments.first()?.ident.to_string();
+    // A sibling type's module is only visible through the glob import, which
+    // can't be re-exported; longer paths resolve their first segment through it
+    let relative = path.leading_colon.is_none() && (path.segments.len() == 1 || first == "super");
+    if first == "self" {
+        path.segments.first_mut()?.ident = format_ident!("super");
+    }
+    let last = path.segments.last_mut()?;
+    last.ident = colors_module_name(&last.ident);
+    last.arguments = syn::PathArguments::None;
+    if relative {
+        Some(quote! { super::#path })
+    } else {
+        Some(quote! { #path })
+    }
+}
+
+/// Visibility for items re-exported from inside the companion module, so
+/// they're reachable wherever the theme struct is.
+fn companion_visibility(vis: &Visibility) -> TokenStream {
+    match vis {
+        Visibility::Public(_) => quote! { pub },
+        Visibility::Restricted(r) if r.path.is_ident("crate") => quote! { pub(crate) },
+        _ => quote! { pub(super) },
+    }
+}
 
 /// Check if a field has the `#[group]` attribute.
 fn has_group_attr(field: &syn::Field) -> bool {
@@ -64,17 +110,32 @@ pub fn expand(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Separate regular fields from group fields
     let mut color_fields = Vec::new();
     let mut group_fields = Vec::new();
+    let mut group_types = Vec::new();
 
     for field in fields.iter() {
         if let Some(ident) = &field.ident {
             if has_group_attr(field) {
                 group_fields.push(ident);
+                group_types.push(&field.ty);
             } else {
                 color_fields.push(ident);
             }
         }
     }
 
+    // Companion module: one constant per color, one re-exported module per group
+    let vis = &input.vis;
+    let colors_module = colors_module_name(name);
+    let inner_vis = companion_visibility(vis);
+    let group_reexports: Vec<_> = group_fields
+        .iter()
+        .zip(&group_types)
+        .filter_map(|(field, ty)| {
+            let path = group_colors_path(ty)?;
+            Some(quote! { #inner_vis use #path as #field; })
+        })
+        .collect();
+
     // Generate match arms for direct color fields
     let color_arms: Vec<_> = color_fields
         .iter()
@@ -135,5 +196,14 @@ pub fn expand(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 self.__theme_resolve(name)
             }
         }
+
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals, unused_imports)]
+        #vis mod #colors_module {
+            use super::*;
+
+            #(pub const #color_fields: () = ();)*
+            #(#group_reexports)*
+        }
     }
 }
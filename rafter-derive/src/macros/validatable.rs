@@ -0,0 +1,259 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::{Data, DeriveInput, Expr, Fields, Ident, LitStr, Token, parse2};
+
+/// A single rule from a `#[validate(...)]` attribute.
+struct Rule {
+    name: Ident,
+    arg: Option<Expr>,
+    message: Option<LitStr>,
+}
+
+/// A field with at least one rule.
+struct ValidatedField {
+    ident: Ident,
+    label: String,
+    rules: Vec<Rule>,
+}
+
+/// Derive macro for validating form structs.
+///
+/// Fields marked with `#[validate(...)]` must implement
+/// `rafter::validation::Validatable`. Generates `validator()`, which builds the
+/// `Validator` chain for those fields in declaration order, and `validate()`,
+/// which runs it.
+///
+/// ```ignore
+/// #[derive(Validatable)]
+/// struct SignupForm {
+///     #[validate(required, min_length = 3)]
+///     username: Input,
+///     #[validate(required, email(message = "That doesn't look like an email"))]
+///     email: Input,
+///     #[validate(checked, label = "Terms")]
+///     accept_terms: Checkbox,
+/// }
+/// ```
+///
+/// Rules take their argument as `rule = value` or `rule(value)`, and a custom
+/// message as `rule(value, message = "...")`. Without one, the message is
+/// built from the field's label, which defaults to the field name in sentence
+/// case (`first_name` -> "First name").
+pub fn expand(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = match parse2(input) {
+        Ok(i) => i,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return syn::Error::new_spanned(
+                    &input.ident,
+                    "Validatable can only be derived for structs with named fields",
+                )
+                .to_compile_error();
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(
+                &input.ident,
+                "Validatable can only be derived for structs",
+            )
+            .to_compile_error();
+        }
+    };
+
+    let mut validated = Vec::new();
+    for field in fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let mut entry = ValidatedField {
+            ident: ident.clone(),
+            label: sentence_case(&ident.to_string()),
+            rules: Vec::new(),
+        };
+        let mut marked = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+            marked = true;
+            if let Err(e) = parse_validate_attr(attr, &mut entry) {
+                return e.to_compile_error();
+            }
+        }
+        if marked {
+            validated.push(entry);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let chain: Vec<TokenStream> = validated
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            let field_name = ident.to_string();
+            let rules = field
+                .rules
+                .iter()
+                .map(|rule| generate_rule(rule, &field.label));
+            quote! {
+                .field(&self.#ident, #field_name)
+                #(#rules)*
+                .finish()
+            }
+        })
+        .collect();
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Build the validator for this form's `#[validate]` fields.
+            pub fn validator(&self) -> rafter::validation::Validator {
+                rafter::validation::Validator::new()
+                    #(#chain)*
+            }
+
+            /// Validate all `#[validate]` fields, setting or clearing their errors.
+            pub fn validate(&self) -> rafter::validation::ValidationResult {
+                self.validator().validate()
+            }
+        }
+    }
+}
+
+fn parse_validate_attr(attr: &syn::Attribute, field: &mut ValidatedField) -> syn::Result<()> {
+    attr.parse_nested_meta(|meta| {
+        let Some(ident) = meta.path.get_ident().cloned() else {
+            return Err(meta.error("expected a validation rule"));
+        };
+
+        if ident == "label" {
+            field.label = meta.value()?.parse::<LitStr>()?.value();
+            return Ok(());
+        }
+
+        let takes_arg = rule_takes_arg(&ident)
+            .ok_or_else(|| syn::Error::new_spanned(&ident, format!("unknown rule `{}`", ident)))?;
+
+        let mut rule = Rule {
+            name: ident.clone(),
+            arg: None,
+            message: None,
+        };
+
+        if meta.input.peek(Token![=]) {
+            rule.arg = Some(meta.value()?.parse()?);
+        } else if meta.input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            parse_rule_args(&content, &mut rule, takes_arg)?;
+        }
+
+        if takes_arg && rule.arg.is_none() {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                format!("`{}` needs a value, e.g. `{} = ...`", ident, ident),
+            ));
+        }
+        if !takes_arg && rule.arg.is_some() {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                format!("`{}` doesn't take a value", ident),
+            ));
+        }
+
+        field.rules.push(rule);
+        Ok(())
+    })
+}
+
+/// Contents of `rule(...)`: an optional value, then an optional `message = "..."`.
+fn parse_rule_args(input: ParseStream, rule: &mut Rule, takes_arg: bool) -> syn::Result<()> {
+    if takes_arg && !(input.peek(Ident) && input.peek2(Token![=])) {
+        rule.arg = Some(input.parse()?);
+        if input.is_empty() {
+            return Ok(());
+        }
+        input.parse::<Token![,]>()?;
+    }
+    while !input.is_empty() {
+        let key: Ident = input.parse()?;
+        if key != "message" {
+            return Err(syn::Error::new_spanned(key, "expected `message = \"...\"`"));
+        }
+        input.parse::<Token![=]>()?;
+        rule.message = Some(input.parse()?);
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a rule takes a value, or `None` for unknown rules.
+///
+/// Each rule maps to the `FieldBuilder` method of the same name.
+fn rule_takes_arg(rule: &Ident) -> Option<bool> {
+    match rule.to_string().as_str() {
+        "required" | "email" | "checked" | "unchecked" | "selected" => Some(false),
+        "min_length" | "max_length" | "pattern" | "contains" => Some(true),
+        _ => None,
+    }
+}
+
+fn generate_rule(rule: &Rule, label: &str) -> TokenStream {
+    let method = &rule.name;
+    let message = match &rule.message {
+        Some(message) => quote! { #message },
+        None => default_message(rule, label),
+    };
+    match &rule.arg {
+        Some(arg) => quote! { .#method(#arg, #message) },
+        None => quote! { .#method(#message) },
+    }
+}
+
+fn default_message(rule: &Rule, label: &str) -> TokenStream {
+    let fixed = |text: String| {
+        let lit = LitStr::new(&text, Span::call_site());
+        quote! { #lit }
+    };
+    let arg = &rule.arg;
+    // The label ends up in a format string for rules that include their value
+    let escaped = label.replace('{', "{{").replace('}', "}}");
+    match rule.name.to_string().as_str() {
+        "required" => fixed(format!("{} is required", label)),
+        "email" => fixed(format!("{} must be a valid email address", label)),
+        "checked" => fixed(format!("{} must be checked", label)),
+        "unchecked" => fixed(format!("{} must not be checked", label)),
+        "selected" => fixed(format!("{} must be selected", label)),
+        "pattern" => fixed(format!("{} has an invalid format", label)),
+        "min_length" => {
+            let text = format!("{} must be at least {{}} characters", escaped);
+            quote! { format!(#text, #arg) }
+        }
+        "max_length" => {
+            let text = format!("{} must be at most {{}} characters", escaped);
+            quote! { format!(#text, #arg) }
+        }
+        "contains" => {
+            let text = format!("{} must contain \"{{}}\"", escaped);
+            quote! { format!(#text, #arg) }
+        }
+        _ => fixed(format!("{} is invalid", label)),
+    }
+}
+
+/// `first_name` -> "First name".
+fn sentence_case(name: &str) -> String {
+    let words = name.trim_start_matches("r#").replace('_', " ");
+    let words = words.trim();
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -4,7 +4,7 @@
 //! Since Element's internals are private, we mainly verify compilation succeeds.
 
 use rafter::widgets::Text;
-use rafter_derive::{element, page};
+use rafter_derive::{component, element, page};
 use tuidom::Element;
 
 // Basic element generation
@@ -331,6 +331,17 @@ fn test_generate_all_text_styles() {
     };
 }
 
+#[test]
+fn test_generate_underline_styles() {
+    let _elem: Element = element! {
+        column {
+            text (content: "curly") style (underline: curly, underline_color: error) {}
+            text (content: "double") style (underline: double) {}
+            text (content: "dotted") style (underline: dotted, underline_color: "#ff0000") {}
+        }
+    };
+}
+
 #[test]
 fn test_generate_color_variants() {
     // Theme variable
@@ -378,3 +389,253 @@ fn test_generate_layout_only() {
         }
     };
 }
+
+// Keyed for loops
+fn child_ids(elem: &Element) -> Vec<String> {
+    match &elem.content {
+        tuidom::Content::Children(children) => children.iter().map(|c| c.id.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn keyed_list(items: &[(u32, &str)]) -> Element {
+    element! {
+        column {
+            for (id, label) in items.iter() (key: id) {
+                row {
+                    text (content: {*label}) {}
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generate_keyed_for_stable_ids() {
+    let first = child_ids(&keyed_list(&[(1, "a"), (2, "b")]));
+    let second = child_ids(&keyed_list(&[(1, "a"), (2, "b")]));
+    assert_eq!(first, second);
+    assert!(first[0].ends_with(":1"));
+    assert!(first[1].ends_with(":2"));
+}
+
+#[test]
+fn test_generate_keyed_for_follows_items() {
+    let before = child_ids(&keyed_list(&[(1, "a"), (2, "b"), (3, "c")]));
+    // Item 2 removed and the rest reordered: ids move with their items
+    let after = child_ids(&keyed_list(&[(3, "c"), (1, "a")]));
+    assert_eq!(after, vec![before[2].clone(), before[0].clone()]);
+}
+
+#[test]
+fn test_generate_keyed_for_keeps_explicit_id() {
+    let items = vec![7, 8];
+    let elem: Element = element! {
+        column {
+            for item in items.iter() (key: item) {
+                row (id: {format!("item-{}", item)}) {}
+                text (content: "second") {}
+            }
+        }
+    };
+    let ids = child_ids(&elem);
+    assert_eq!(ids[0], "item-7");
+    assert!(ids[1].ends_with(":7"));
+    assert_ne!(ids[0], ids[1]);
+}
+
+#[test]
+fn test_generate_unkeyed_for_still_works() {
+    let items = vec![1, 2];
+    let elem: Element = element! {
+        column {
+            for item in items.iter().map(|i| i * 2) {
+                text (content: {item.to_string()}) {}
+            }
+        }
+    };
+    assert_eq!(child_ids(&elem).len(), 2);
+}
+
+// Components
+#[component]
+fn badge(
+    label: String,
+    detail: Option<String>,
+    #[prop(default = 2)] spacing: u16,
+    #[prop(default)] children: Vec<Element>,
+) -> Element {
+    let text = match detail {
+        Some(detail) => format!("{} ({})", label, detail),
+        None => label,
+    };
+    let mut items = vec![Element::text(text)];
+    items.extend(children);
+    Element::row().gap(spacing).children(items)
+}
+
+fn text_content(elem: &Element) -> Option<&str> {
+    match &elem.content {
+        tuidom::Content::Text(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn children(elem: &Element) -> &[Element] {
+    match &elem.content {
+        tuidom::Content::Children(children) => children,
+        _ => &[],
+    }
+}
+
+#[test]
+fn test_generate_component_required_only() {
+    let elem: Element = element! {
+        badge (label: "new")
+    };
+    assert_eq!(text_content(&children(&elem)[0]), Some("new"));
+    assert_eq!(elem.gap, 2);
+}
+
+#[test]
+fn test_generate_component_optional_and_default() {
+    let elem: Element = element! {
+        badge (spacing: 5, detail: "3", label: {String::from("new")})
+    };
+    assert_eq!(text_content(&children(&elem)[0]), Some("new (3)"));
+    assert_eq!(elem.gap, 5);
+}
+
+#[test]
+fn test_generate_component_children_and_id() {
+    let elem: Element = element! {
+        column {
+            badge (label: "tag", id: "badge") style (bold) {
+                text (content: "child") {}
+            }
+        }
+    };
+    let badge = &children(&elem)[0];
+    assert_eq!(badge.id, "badge");
+    assert!(badge.style.text_style.bold);
+    assert_eq!(children(badge).len(), 2);
+}
+
+#[test]
+fn test_generate_component_layout_attrs() {
+    let elem: Element = element! {
+        badge (label: "x", width: fill)
+    };
+    assert_eq!(elem.width, tuidom::Size::Fill);
+}
+
+#[test]
+fn test_generate_component_direct_call() {
+    let elem = badge("x".into(), None, 1, Vec::new());
+    assert_eq!(elem.gap, 1);
+    let built = Badge::new().label("x").build(
+        &rafter::HandlerRegistry::new(),
+        &rafter::WidgetHandlers::new(),
+    );
+    assert_eq!(built.gap, 2);
+}
+
+// Style classes
+fn card() -> tuidom::Style {
+    tuidom::Style::new()
+        .background(tuidom::Color::var("surface"))
+        .foreground(tuidom::Color::var("text"))
+        .border(tuidom::Border::Rounded)
+}
+
+fn emphasis() -> tuidom::Style {
+    tuidom::Style::new().bold()
+}
+
+#[test]
+fn test_generate_style_class() {
+    let elem: Element = element! {
+        column style (class: card) {}
+    };
+    assert_eq!(elem.style.background, card().background);
+    assert_eq!(elem.style.foreground, card().foreground);
+    assert_eq!(elem.style.border, tuidom::Border::Rounded);
+}
+
+#[test]
+fn test_generate_style_class_with_overrides() {
+    let elem: Element = element! {
+        column style (class: card, fg: accent) {}
+    };
+    assert_eq!(elem.style.background, Some(tuidom::Color::var("surface")));
+    assert_eq!(elem.style.foreground, Some(tuidom::Color::var("accent")));
+    assert_eq!(elem.style.border, tuidom::Border::Rounded);
+}
+
+#[test]
+fn test_generate_style_multiple_classes() {
+    let highlighted = true;
+    let elem: Element = element! {
+        column style (class: card, class: if highlighted { emphasis } else { card }) {}
+    };
+    assert!(elem.style.text_style.bold);
+    assert_eq!(elem.style.border, tuidom::Border::Rounded);
+
+    let elem: Element = element! {
+        text (content: "x") style (class: {emphasis().italic()}) {}
+    };
+    assert!(elem.style.text_style.bold);
+    assert!(elem.style.text_style.italic);
+}
+
+// Theme color checks
+mod checked_theme {
+    // Only used for its color names
+    #![allow(dead_code)]
+
+    use rafter::theme;
+    use tuidom::Color;
+
+    #[theme]
+    pub struct PanelColors {
+        pub header: Color,
+    }
+
+    #[theme]
+    pub struct AppTheme {
+        pub surface: Color,
+        pub accent: Color,
+        #[group]
+        pub panel: PanelColors,
+    }
+}
+
+#[test]
+fn test_generate_theme_checked_colors() {
+    let selected = true;
+    let elem: Element = element! {
+        #![theme(checked_theme::AppTheme)]
+        column style (bg: surface, fg: if selected { accent } else { "panel.header" }) {
+            text (content: "hex") style (fg: "#ff0000") {}
+            text (content: "dynamic") style (fg: {tuidom::Color::var("anything")}) {}
+        }
+    };
+    assert_eq!(elem.style.background, Some(tuidom::Color::var("surface")));
+}
+
+#[test]
+fn test_generate_theme_checked_against_rafter_theme() {
+    let _: Element = element! {
+        #![theme(rafter::theme::RafterTheme)]
+        column style (bg: surface, fg: "button.hover") {}
+    };
+}
+
+// Uncomment to see the compile error with its suggestion:
+// #[test]
+// fn test_generate_theme_unknown_color() {
+//     let _: Element = element! {
+//         #![theme(checked_theme::AppTheme)]
+//         column style (bg: acent) {}
+//     };
+// }
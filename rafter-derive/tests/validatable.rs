@@ -0,0 +1,137 @@
+//! Tests for #[derive(Validatable)].
+
+use std::sync::{Arc, Mutex};
+
+use rafter::validation::{ErrorDisplay, Validatable};
+
+/// Minimal validatable widget holding a value and its error.
+#[derive(Clone)]
+struct Field<T> {
+    id: &'static str,
+    value: T,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl<T> Field<T> {
+    fn new(id: &'static str, value: T) -> Self {
+        Self {
+            id,
+            value,
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync> Validatable for Field<T> {
+    type Value = T;
+
+    fn validation_value(&self) -> T {
+        self.value.clone()
+    }
+
+    fn set_error(&self, msg: impl Into<String>) {
+        *self.error.lock().unwrap() = Some(msg.into());
+    }
+
+    fn clear_error(&self) {
+        *self.error.lock().unwrap() = None;
+    }
+
+    fn has_error(&self) -> bool {
+        self.error.lock().unwrap().is_some()
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    fn widget_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn error_display(&self) -> ErrorDisplay {
+        ErrorDisplay::Below
+    }
+
+    fn set_error_display(&self, _display: ErrorDisplay) {}
+}
+
+#[derive(Validatable)]
+struct SignupForm {
+    #[validate(required, min_length = 3)]
+    user_name: Field<String>,
+    #[validate(email(message = "Enter a real email"), label = "E-mail")]
+    email: Field<String>,
+    #[validate(max_length(4))]
+    code: Field<String>,
+    #[validate(checked, label = "Terms")]
+    accept_terms: Field<bool>,
+    #[allow(dead_code)]
+    notes: Field<String>,
+}
+
+fn form(user_name: &str, email: &str, code: &str, accept_terms: bool) -> SignupForm {
+    SignupForm {
+        user_name: Field::new("user-name", user_name.to_string()),
+        email: Field::new("email", email.to_string()),
+        code: Field::new("code", code.to_string()),
+        accept_terms: Field::new("terms", accept_terms),
+        notes: Field::new("notes", String::new()),
+    }
+}
+
+#[test]
+fn test_validatable_valid_form() {
+    let form = form("ada", "ada@example.com", "1234", true);
+    assert!(form.validate().is_valid());
+    assert!(!form.user_name.has_error());
+}
+
+#[test]
+fn test_validatable_default_messages() {
+    let form = form("", "ada@example.com", "12345", false);
+    let result = form.validate();
+    let messages: Vec<_> = result.errors().iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "User name is required",
+            "Code must be at most 4 characters",
+            "Terms must be checked",
+        ]
+    );
+    assert_eq!(result.first_invalid_widget(), Some("user-name"));
+    assert_eq!(result.errors()[0].field_name, "user_name");
+    assert_eq!(
+        form.code.error().as_deref(),
+        Some("Code must be at most 4 characters")
+    );
+}
+
+#[test]
+fn test_validatable_first_failing_rule_wins() {
+    let form = form("ad", "not an email", "", true);
+    let result = form.validate();
+    let messages: Vec<_> = result.errors().iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "User name must be at least 3 characters",
+            "Enter a real email"
+        ]
+    );
+}
+
+#[test]
+fn test_validatable_clears_errors() {
+    let mut form = form("", "", "", true);
+    assert!(form.validate().is_invalid());
+    assert!(form.user_name.has_error());
+
+    form.user_name = Field {
+        value: "ada".to_string(),
+        ..form.user_name.clone()
+    };
+    assert!(form.validate().is_valid());
+    assert!(!form.user_name.has_error());
+}
@@ -4,8 +4,8 @@
 //! - Spawning new app instances with `gx.spawn_and_focus()`
 //! - Different BlurPolicy behaviors (Continue, Sleep, Close)
 //! - Instance discovery with `gx.instances()`
-//! - Pub/Sub events with `gx.publish()` and `#[event_handler]`
-//! - Request/Response with `gx.request()` and `#[request_handler]`
+//! - Pub/Sub events with `gx.publish()`, `#[event_handler]` and `#[on_event]`
+//! - Request/Response with `gx.request()`, `#[request_handler]` and `#[on_request]`
 //! - System keybinds with `#[system]` and `#[system_impl]`
 //! - Global data with `Runtime::data()` and `gx.data::<T>()`
 
@@ -150,8 +150,8 @@ impl Taskbar {
         self.instances.set(gx.instances());
     }
 
-    #[event_handler]
-    async fn on_focus_changed(&self, _event: FocusChanged, gx: &GlobalContext) {
+    #[on_event(FocusChanged)]
+    async fn on_focus_changed(&self, gx: &GlobalContext) {
         info!("[Taskbar] on_focus_changed");
         self.instances.set(gx.instances());
     }
@@ -430,8 +430,8 @@ impl AppB {
         bind("enter", next_app);
     }
 
-    #[request_handler]
-    async fn handle_is_paused(&self, _request: IsPaused) -> bool {
+    #[on_request(IsPaused)]
+    async fn handle_is_paused(&self) -> bool {
         info!("[App B] IsPaused request, responding: false");
         false
     }
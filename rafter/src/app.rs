@@ -14,7 +14,8 @@ use std::sync::OnceLock;
 use tuidom::Element;
 
 use crate::{
-    AppContext, GlobalContext, HandlerRegistry, KeybindClosures, LifecycleHooks, WakeupSender,
+    AppContext, GlobalContext, HandlerRegistry, KeybindClosures, LifecycleHooks, PersistentApp,
+    WakeupSender,
 };
 
 /// App configuration.
@@ -149,6 +150,13 @@ pub trait App: Clone + Send + Sync + 'static {
         let _ = sender;
     }
 
+    /// Get the app's persisted state, if it has any.
+    ///
+    /// Returns `Some` for apps declared with `#[app(persist)]`.
+    fn as_persistent(&self) -> Option<&dyn PersistentApp> {
+        None
+    }
+
     // =========================================================================
     // Watch (generated by macros)
     // =========================================================================
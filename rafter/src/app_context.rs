@@ -225,7 +225,7 @@ impl AppContext {
 
     /// Set the cursor position for a text input element.
     ///
-    /// The position is clamped to the text length.
+    /// The position is a grapheme index, clamped to the text length.
     /// Clears any active selection.
     pub fn set_input_cursor(&self, element_id: impl Into<String>, position: usize) {
         if let Ok(mut inner) = self.inner.write() {
@@ -55,3 +55,17 @@ pub struct FocusChanged {
 }
 
 impl Event for FocusChanged {}
+
+/// Published when the terminal window gains or loses focus.
+///
+/// Only sent by terminals that support focus reporting. This is separate
+/// from focus moving between instances ([`FocusChanged`]): the focused
+/// instance stays the same and `on_foreground` / `on_background` aren't
+/// called. Handle it to e.g. refresh data when the user comes back.
+#[derive(Debug, Clone)]
+pub struct TerminalFocusChanged {
+    /// Whether the terminal window now has focus.
+    pub focused: bool,
+}
+
+impl Event for TerminalFocusChanged {}
@@ -5,6 +5,7 @@
 //! - Instance management (spawn, close, focus)
 //! - Toast notifications
 //! - Theme changes
+//! - System overlay visibility
 //! - Global modals
 //! - Inter-app communication (publish, request)
 //! - Global data
@@ -12,7 +13,7 @@
 //! - Scheduled jobs
 
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
@@ -24,6 +25,7 @@ use crate::handler_context::Handler;
 use crate::instance::{InstanceId, InstanceInfo, RequestError, SpawnError};
 use crate::job::{JobId, Schedule, ScheduledJob};
 use crate::modal::{Modal, ModalContext, ModalEntry};
+use crate::persist::PersistStore;
 use crate::registration::CloneableApp;
 use crate::wakeup::WakeupSender;
 use crate::{App, Event, Request, System, Toast};
@@ -166,6 +168,10 @@ struct GlobalContextInner {
     instance_commands: Vec<InstanceCommand>,
     /// Bounding rect of the currently focused element.
     focused_element_rect: Option<Rect>,
+    /// Systems whose overlays are currently hidden.
+    hidden_overlays: HashSet<TypeId>,
+    /// The terminal window lost focus (focus reporting).
+    terminal_unfocused: bool,
 }
 
 // =============================================================================
@@ -212,6 +218,8 @@ pub struct GlobalContext {
     wakeup_sender: Option<WakeupSender>,
     /// Cursor state for mouse position tracking.
     cursor_state: Arc<RwLock<CursorState>>,
+    /// Store for persisted app state.
+    persist_store: Option<Arc<dyn PersistStore>>,
 }
 
 impl GlobalContext {
@@ -223,6 +231,7 @@ impl GlobalContext {
             data,
             wakeup_sender: None,
             cursor_state,
+            persist_store: None,
         }
     }
 
@@ -240,6 +249,16 @@ impl GlobalContext {
         self.registry = Some(registry);
     }
 
+    /// Set the persisted state store (called by runtime).
+    pub(crate) fn set_persist_store(&mut self, store: Arc<dyn PersistStore>) {
+        self.persist_store = Some(store);
+    }
+
+    /// Get the persisted state store, if one is configured.
+    pub(crate) fn persist_store(&self) -> Option<Arc<dyn PersistStore>> {
+        self.persist_store.clone()
+    }
+
     /// Get the cursor state for internal runtime use.
     pub(crate) fn cursor_state(&self) -> &Arc<RwLock<CursorState>> {
         &self.cursor_state
@@ -288,6 +307,28 @@ impl GlobalContext {
         }
     }
 
+    // =========================================================================
+    // Terminal Focus
+    // =========================================================================
+
+    /// Check if the terminal window has focus.
+    ///
+    /// Returns true if the terminal doesn't report focus changes. Useful to
+    /// skip background polling while the user is in another window.
+    pub fn is_terminal_focused(&self) -> bool {
+        self.inner
+            .read()
+            .map(|inner| !inner.terminal_unfocused)
+            .unwrap_or(true)
+    }
+
+    /// Record a terminal focus change (runtime use).
+    pub(crate) fn set_terminal_focused(&self, focused: bool) {
+        if let Ok(mut inner) = self.inner.write() {
+            inner.terminal_unfocused = !focused;
+        }
+    }
+
     // =========================================================================
     // Shutdown
     // =========================================================================
@@ -478,6 +519,48 @@ impl GlobalContext {
         }
     }
 
+    // =========================================================================
+    // System Overlays
+    // =========================================================================
+
+    /// Show the overlay of system `S` if it was hidden.
+    pub fn show_overlay<S: System>(&self) {
+        self.set_overlay_visible::<S>(true);
+    }
+
+    /// Hide the overlay of system `S`.
+    ///
+    /// The system stays registered and keeps handling keybinds and events.
+    /// Edge overlays give their space back to the app while hidden.
+    pub fn hide_overlay<S: System>(&self) {
+        self.set_overlay_visible::<S>(false);
+    }
+
+    /// Toggle the visibility of the overlay of system `S`.
+    pub fn toggle_overlay<S: System>(&self) {
+        let visible = self.is_overlay_visible::<S>();
+        self.set_overlay_visible::<S>(!visible);
+    }
+
+    /// Check if the overlay of system `S` is visible.
+    pub fn is_overlay_visible<S: System>(&self) -> bool {
+        !self.is_overlay_hidden(TypeId::of::<S>())
+    }
+
+    fn set_overlay_visible<S: System>(&self, visible: bool) {
+        if let Ok(mut inner) = self.inner.write() {
+            let type_id = TypeId::of::<S>();
+            let changed = if visible {
+                inner.hidden_overlays.remove(&type_id)
+            } else {
+                inner.hidden_overlays.insert(type_id)
+            };
+            if changed {
+                self.send_wakeup();
+            }
+        }
+    }
+
     // =========================================================================
     // Global Modal
     // =========================================================================
@@ -799,6 +882,14 @@ impl GlobalContext {
             .unwrap_or(false)
     }
 
+    /// Check if a system's overlay was hidden (runtime use).
+    pub(crate) fn is_overlay_hidden(&self, type_id: TypeId) -> bool {
+        self.inner
+            .read()
+            .map(|inner| inner.hidden_overlays.contains(&type_id))
+            .unwrap_or(false)
+    }
+
     /// Take pending toasts (runtime use).
     pub(crate) fn take_toasts(&self) -> Vec<Toast> {
         self.inner
@@ -855,6 +946,7 @@ impl Default for GlobalContext {
             data: Arc::new(HashMap::new()),
             wakeup_sender: None,
             cursor_state: Arc::new(RwLock::new(CursorState::new())),
+            persist_store: None,
         }
     }
 }
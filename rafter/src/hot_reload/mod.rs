@@ -0,0 +1,160 @@
+//! Hot reload for page templates and theme files.
+//!
+//! `page! { #![template("pages/home.page")] }` compiles the template file into
+//! the page like an inline body. With the `hot-reload` feature, the page also
+//! checks the file whenever it renders. If it was edited, the static parts
+//! (`column`, `row`, `box` and `text` with literal attributes) are rebuilt
+//! from the file, so layout and styling changes show up without recompiling.
+//! Dynamic parts (widgets, loops, conditionals, expressions) still come from
+//! the compiled page, so editing them needs a rebuild; until then the compiled
+//! page is shown and a warning is logged.
+//!
+//! [`Runtime::theme_file`](crate::Runtime) does the same for theme colors,
+//! reloading a [`ThemeFile`] whenever it changes.
+
+mod parse;
+mod template;
+mod theme;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use tuidom::Element;
+
+use template::Template;
+
+pub use theme::{ThemeFile, ThemeFileError};
+
+/// Why a template file couldn't be used.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub(crate) struct TemplateError {
+    message: String,
+}
+
+impl TemplateError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// Build a template-backed page.
+///
+/// Called by `page!` with the `hot-reload` feature. `source` is the template
+/// as it was compiled, `holes` the elements for its dynamic parts in document
+/// order, and `compiled` builds the compiled page from them. If the file on
+/// disk differs from `source` and can be interpreted, it's built instead.
+#[doc(hidden)]
+pub fn page(
+    path: &str,
+    source: &str,
+    mut holes: Vec<Vec<Element>>,
+    compiled: impl FnOnce(&mut [Vec<Element>]) -> Element,
+) -> Element {
+    match current(path, source, holes.len()) {
+        Some(template) => template.build(&mut holes),
+        None => compiled(&mut holes),
+    }
+}
+
+/// A template file as last seen on disk.
+struct Entry {
+    modified: Option<SystemTime>,
+    /// `None` when the compiled page is current, or the file can't be used.
+    template: Option<Arc<Template>>,
+}
+
+fn templates() -> &'static Mutex<HashMap<String, Entry>> {
+    static TEMPLATES: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    TEMPLATES.get_or_init(Default::default)
+}
+
+fn modified(path: impl AsRef<Path>) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// The template to build, reloading it if the file changed.
+fn current(path: &str, source: &str, hole_count: usize) -> Option<Arc<Template>> {
+    let modified = modified(path);
+    let mut templates = templates().lock().unwrap();
+    if let Some(entry) = templates.get(path)
+        && entry.modified == modified
+    {
+        return entry.template.clone();
+    }
+
+    let template = load(path, source, hole_count).map(Arc::new);
+    templates.insert(
+        path.to_string(),
+        Entry {
+            modified,
+            template: template.clone(),
+        },
+    );
+    template
+}
+
+fn load(path: &str, source: &str, hole_count: usize) -> Option<Template> {
+    let edited = match std::fs::read_to_string(path) {
+        Ok(edited) => edited,
+        Err(e) => {
+            log::warn!("template `{}`: {}; showing the compiled page", path, e);
+            return None;
+        }
+    };
+    if edited == source {
+        return None;
+    }
+
+    match Template::load(&edited, source, hole_count) {
+        Ok(template) => {
+            log::info!("reloaded template `{}`", path);
+            Some(template)
+        }
+        Err(e) => {
+            log::warn!("template `{}`: {}; showing the compiled page", path, e);
+            None
+        }
+    }
+}
+
+/// Watch rendered templates and the theme file, redrawing or swapping the
+/// theme when they change.
+#[cfg(feature = "hot-reload")]
+pub(crate) fn spawn_watcher(
+    gx: crate::GlobalContext,
+    theme: Option<(std::path::PathBuf, Arc<dyn tuidom::Theme>)>,
+) {
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+    tokio::spawn(async move {
+        let mut theme_modified = None;
+        loop {
+            if let Some((path, base)) = &theme {
+                let modified = modified(path);
+                if modified != theme_modified {
+                    theme_modified = modified;
+                    match ThemeFile::load(path, Arc::clone(base)) {
+                        Ok(theme) => gx.set_theme(theme),
+                        Err(e) => log::warn!("theme file `{}`: {}", path.display(), e),
+                    }
+                }
+            }
+
+            let changed = templates()
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(path, entry)| modified(path) != entry.modified);
+            if changed && let Some(wakeup) = gx.wakeup_sender() {
+                wakeup.send();
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
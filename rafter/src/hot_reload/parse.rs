@@ -0,0 +1,748 @@
+//! Tokenizer and parser for template files.
+//!
+//! Follows the `page!` grammar closely enough to pick out the static elements
+//! and the holes between them. A hole is kept as its source tokens so a
+//! template can be checked against the holes the page was compiled with.
+//!
+//! Which elements are static must agree with `is_static` in rafter-derive's
+//! template codegen.
+
+use std::fmt;
+
+use super::TemplateError;
+
+/// Elements the interpreter can build.
+const STATIC_ELEMENTS: &[&str] = &["column", "col", "row", "box", "box_", "text"];
+
+/// Layout attributes whose identifier values are keywords, not variables.
+const KEYWORD_ATTRS: &[&str] = &[
+    "direction",
+    "justify",
+    "align",
+    "wrap",
+    "align_content",
+    "text_wrap",
+    "align_self",
+    "overflow",
+    "position",
+];
+
+// =============================================================================
+// Tokens
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    Ident(String),
+    Literal { text: String, value: Literal },
+    Punct(char),
+    Group(Delimiter, Vec<Token>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Delimiter {
+    Paren,
+    Bracket,
+    Brace,
+}
+
+impl Delimiter {
+    fn open(self) -> char {
+        match self {
+            Delimiter::Paren => '(',
+            Delimiter::Bracket => '[',
+            Delimiter::Brace => '{',
+        }
+    }
+
+    fn close(self) -> char {
+        match self {
+            Delimiter::Paren => ')',
+            Delimiter::Bracket => ']',
+            Delimiter::Brace => '}',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Literal {
+    Str(String),
+    Int(u64),
+    Float(f64),
+    Bool(bool),
+    /// Chars, byte strings and suffixed numbers like `200ms`.
+    Other,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => f.write_str(ident),
+            Token::Literal { text, .. } => f.write_str(text),
+            Token::Punct(c) => write!(f, "{}", c),
+            Token::Group(delimiter, tokens) => {
+                write!(f, "{}", delimiter.open())?;
+                for token in tokens {
+                    write!(f, " {}", token)?;
+                }
+                write!(f, " {}", delimiter.close())
+            }
+        }
+    }
+}
+
+enum Lexed {
+    Open(Delimiter),
+    Close(Delimiter),
+    Token(Token),
+}
+
+/// Split a template file into tokens, with delimited groups nested.
+pub(super) fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+    };
+    let mut stack: Vec<(Delimiter, Vec<Token>)> = Vec::new();
+    let mut tokens = Vec::new();
+
+    while let Some(lexed) = lexer.next()? {
+        match lexed {
+            Lexed::Open(delimiter) => stack.push((delimiter, std::mem::take(&mut tokens))),
+            Lexed::Close(delimiter) => {
+                let Some((open, outer)) = stack.pop() else {
+                    return Err(TemplateError::new(format!(
+                        "unexpected `{}`",
+                        delimiter.close()
+                    )));
+                };
+                if open != delimiter {
+                    return Err(TemplateError::new(format!(
+                        "expected `{}`, found `{}`",
+                        open.close(),
+                        delimiter.close()
+                    )));
+                }
+                let inner = std::mem::replace(&mut tokens, outer);
+                tokens.push(Token::Group(delimiter, inner));
+            }
+            Lexed::Token(token) => tokens.push(token),
+        }
+    }
+
+    if let Some((open, _)) = stack.last() {
+        return Err(TemplateError::new(format!("unclosed `{}`", open.open())));
+    }
+    Ok(tokens)
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Lexer {
+    fn peek(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn text(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn next(&mut self) -> Result<Option<Lexed>, TemplateError> {
+        self.skip_trivia()?;
+        let Some(c) = self.peek(0) else {
+            return Ok(None);
+        };
+
+        let lexed = match c {
+            '(' | '[' | '{' | ')' | ']' | '}' => {
+                self.pos += 1;
+                let delimiter = match c {
+                    '(' | ')' => Delimiter::Paren,
+                    '[' | ']' => Delimiter::Bracket,
+                    _ => Delimiter::Brace,
+                };
+                if matches!(c, '(' | '[' | '{') {
+                    Lexed::Open(delimiter)
+                } else {
+                    Lexed::Close(delimiter)
+                }
+            }
+            '"' => {
+                let start = self.pos;
+                let value = self.string()?;
+                Lexed::Token(Token::Literal {
+                    text: self.text(start),
+                    value: Literal::Str(value),
+                })
+            }
+            'r' if self.peek(1) == Some('"')
+                || (self.peek(1) == Some('#') && matches!(self.peek(2), Some('#' | '"'))) =>
+            {
+                let start = self.pos;
+                self.pos += 1;
+                let value = self.raw_string()?;
+                Lexed::Token(Token::Literal {
+                    text: self.text(start),
+                    value: Literal::Str(value),
+                })
+            }
+            'b' if matches!(self.peek(1), Some('"' | '\'')) => {
+                let start = self.pos;
+                self.pos += 1;
+                if self.peek(0) == Some('"') {
+                    self.string()?;
+                } else {
+                    self.char()?;
+                }
+                Lexed::Token(Token::Literal {
+                    text: self.text(start),
+                    value: Literal::Other,
+                })
+            }
+            '\'' if self.peek(1) == Some('\\') || self.peek(2) == Some('\'') => {
+                let start = self.pos;
+                self.char()?;
+                Lexed::Token(Token::Literal {
+                    text: self.text(start),
+                    value: Literal::Other,
+                })
+            }
+            '\'' => {
+                // Lifetime or loop label
+                let start = self.pos;
+                self.pos += 1;
+                self.ident_chars();
+                Lexed::Token(Token::Ident(self.text(start)))
+            }
+            c if c.is_ascii_digit() => Lexed::Token(self.number()),
+            c if c == '_' || c.is_alphabetic() => {
+                let start = self.pos;
+                if c == 'r' && self.peek(1) == Some('#') {
+                    self.pos += 2;
+                }
+                self.ident_chars();
+                Lexed::Token(Token::Ident(self.text(start)))
+            }
+            c => {
+                self.pos += 1;
+                Lexed::Token(Token::Punct(c))
+            }
+        };
+        Ok(Some(lexed))
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<(), TemplateError> {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c.is_whitespace() => self.pos += 1,
+                (Some('/'), Some('/')) => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    let mut depth = 1;
+                    while depth > 0 {
+                        match (self.peek(0), self.peek(1)) {
+                            (None, _) => {
+                                return Err(TemplateError::new("unterminated block comment"));
+                            }
+                            (Some('/'), Some('*')) => {
+                                depth += 1;
+                                self.pos += 2;
+                            }
+                            (Some('*'), Some('/')) => {
+                                depth -= 1;
+                                self.pos += 2;
+                            }
+                            _ => self.pos += 1,
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn ident_chars(&mut self) {
+        while self
+            .peek(0)
+            .is_some_and(|c| c == '_' || c.is_alphanumeric())
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Lex a `"..."` string starting at the opening quote, returning its value.
+    fn string(&mut self) -> Result<String, TemplateError> {
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.peek(0) else {
+                return Err(TemplateError::new("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' => self.escape(&mut value)?,
+                c => value.push(c),
+            }
+        }
+    }
+
+    /// Lex the rest of an escape sequence, after the backslash.
+    fn escape(&mut self, value: &mut String) -> Result<(), TemplateError> {
+        let Some(c) = self.peek(0) else {
+            return Err(TemplateError::new("unterminated string"));
+        };
+        self.pos += 1;
+        match c {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '0' => value.push('\0'),
+            '\\' | '"' | '\'' => value.push(c),
+            'x' => {
+                let hex: String = self.chars.iter().skip(self.pos).take(2).collect();
+                self.pos += 2;
+                let byte = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| TemplateError::new(format!("invalid escape `\\x{}`", hex)))?;
+                value.push(char::from(byte));
+            }
+            'u' => {
+                let start = self.pos;
+                while self.peek(0).is_some_and(|c| c != '}') {
+                    self.pos += 1;
+                }
+                self.pos += 1;
+                let escape = self.text(start);
+                let code = escape
+                    .strip_prefix('{')
+                    .and_then(|s| s.strip_suffix('}'))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| TemplateError::new(format!("invalid escape `\\u{}`", escape)))?;
+                value.push(code);
+            }
+            '\n' => {
+                while self.peek(0).is_some_and(char::is_whitespace) {
+                    self.pos += 1;
+                }
+            }
+            c => return Err(TemplateError::new(format!("invalid escape `\\{}`", c))),
+        }
+        Ok(())
+    }
+
+    /// Lex a raw string after the `r`, returning its value.
+    fn raw_string(&mut self) -> Result<String, TemplateError> {
+        let mut hashes = 0;
+        while self.peek(0) == Some('#') {
+            hashes += 1;
+            self.pos += 1;
+        }
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek(0) {
+                None => return Err(TemplateError::new("unterminated raw string")),
+                Some('"') if (1..=hashes).all(|n| self.peek(n) == Some('#')) => {
+                    let value = self.text(start);
+                    self.pos += 1 + hashes;
+                    return Ok(value);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Lex a `'c'` char literal starting at the opening quote.
+    fn char(&mut self) -> Result<(), TemplateError> {
+        self.pos += 1;
+        loop {
+            match self.peek(0) {
+                None => return Err(TemplateError::new("unterminated char literal")),
+                Some('\\') => self.pos += 2,
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Token {
+        let start = self.pos;
+        let hex = self.peek(0) == Some('0') && matches!(self.peek(1), Some('x' | 'X'));
+        self.number_chars(hex);
+        if !hex && self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            self.number_chars(false);
+        }
+        let text = self.text(start);
+        Token::Literal {
+            value: number_value(&text),
+            text,
+        }
+    }
+
+    fn number_chars(&mut self, hex: bool) {
+        while let Some(c) = self.peek(0) {
+            let exponent_sign =
+                !hex && matches!(c, '+' | '-') && matches!(self.chars[self.pos - 1], 'e' | 'E');
+            if c == '_' || c.is_alphanumeric() || exponent_sign {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+const NUMBER_SUFFIXES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32",
+    "f64",
+];
+
+fn number_value(text: &str) -> Literal {
+    let clean = text.replace('_', "");
+    let (radix, digits) = match clean.get(..2) {
+        Some("0x") => (16, &clean[2..]),
+        Some("0o") => (8, &clean[2..]),
+        Some("0b") => (2, &clean[2..]),
+        _ => (10, clean.as_str()),
+    };
+
+    let is_float_suffix = radix == 10 && (digits.ends_with("f32") || digits.ends_with("f64"));
+    let digits = NUMBER_SUFFIXES
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .filter(|rest| radix != 16 || !rest.is_empty())
+        .unwrap_or(digits);
+
+    if radix == 10 && (is_float_suffix || digits.contains(['.', 'e', 'E'])) {
+        return digits.parse().map_or(Literal::Other, Literal::Float);
+    }
+    u64::from_str_radix(digits, radix).map_or(Literal::Other, Literal::Int)
+}
+
+// =============================================================================
+// Nodes
+// =============================================================================
+
+#[derive(Debug)]
+pub(super) enum Node {
+    Element(Element),
+    /// Content only the compiled page can build, as its source tokens.
+    Hole(String),
+}
+
+#[derive(Debug)]
+pub(super) struct Element {
+    pub name: String,
+    pub layout: Vec<Attr>,
+    pub style: Vec<Attr>,
+    pub style_focused: Vec<Attr>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug)]
+pub(super) struct Attr {
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Debug)]
+pub(super) enum Value {
+    Ident(String),
+    Literal(Literal),
+    /// `(1, 2)` or `(1, 2, 3, 4)` edges.
+    Tuple(Vec<u64>),
+    /// A name with no value, like `focusable`.
+    Flag,
+}
+
+/// Parse a template file into its root node.
+pub(super) fn parse(source: &str) -> Result<Node, TemplateError> {
+    let tokens = tokenize(source)?;
+    let mut cursor = Cursor {
+        tokens: &tokens,
+        pos: 0,
+    };
+
+    // Inner attributes like `#![theme(..)]` only matter to the compiler
+    while cursor.is_punct(0, '#')
+        && cursor.is_punct(1, '!')
+        && cursor.is_group(2, Delimiter::Bracket)
+    {
+        cursor.pos += 3;
+    }
+
+    let root = parse_node(&mut cursor)?;
+    if !cursor.is_empty() {
+        return Err(TemplateError::new(format!(
+            "unexpected `{}` after the root element",
+            cursor.tokens[cursor.pos]
+        )));
+    }
+    Ok(root)
+}
+
+fn parse_node(cursor: &mut Cursor) -> Result<Node, TemplateError> {
+    let start = cursor.pos;
+
+    if (0..3).all(|n| cursor.is_punct(n, '.')) {
+        cursor.pos += 3;
+        skip_expr(cursor)?;
+    } else if cursor.is_ident(0, "for") || cursor.is_ident(0, "match") {
+        skip_to_block(cursor)?;
+    } else if cursor.is_ident(0, "if") {
+        skip_if(cursor)?;
+    } else if cursor.is_group(0, Delimiter::Brace) {
+        cursor.pos += 1;
+    } else if let Some(element) = parse_element(cursor)? {
+        return Ok(Node::Element(element));
+    }
+
+    Ok(Node::Hole(cursor.source(start)))
+}
+
+/// Parse an element, or skip it and return `None` if it isn't static.
+fn parse_element(cursor: &mut Cursor) -> Result<Option<Element>, TemplateError> {
+    let Some(name) = cursor.ident() else {
+        return Err(match cursor.peek(0) {
+            Some(token) => TemplateError::new(format!("expected an element, found `{}`", token)),
+            None => TemplateError::new("expected an element"),
+        });
+    };
+
+    let mut is_static = STATIC_ELEMENTS.contains(&name.as_str());
+
+    let layout = match cursor.group(Delimiter::Paren) {
+        Some(tokens) => parse_attrs(tokens, true),
+        None => Some(Vec::new()),
+    };
+    let style = keyword_attrs(cursor, "style")
+        .map_or(Some(Vec::new()), |tokens| parse_attrs(tokens, false));
+    let style_focused = keyword_attrs(cursor, "style_focused")
+        .map_or(Some(Vec::new()), |tokens| parse_attrs(tokens, false));
+    if keyword_attrs(cursor, "transition").is_some() {
+        is_static = false;
+    }
+
+    // Handlers: `on_x: handler` with optional `(args)`
+    while matches!(cursor.peek(0), Some(Token::Ident(_))) && cursor.is_punct(1, ':') {
+        is_static = false;
+        cursor.pos += 2;
+        cursor.ident();
+        cursor.group(Delimiter::Paren);
+    }
+
+    let children = cursor.group(Delimiter::Brace);
+
+    let (Some(layout), Some(style), Some(style_focused)) = (layout, style, style_focused) else {
+        return Ok(None);
+    };
+    if !is_static || (name == "text" && children.is_some_and(|tokens| !tokens.is_empty())) {
+        return Ok(None);
+    }
+
+    let mut nodes = Vec::new();
+    if let Some(tokens) = children {
+        let mut inner = Cursor { tokens, pos: 0 };
+        while !inner.is_empty() {
+            nodes.push(parse_node(&mut inner)?);
+        }
+    }
+
+    Ok(Some(Element {
+        name,
+        layout,
+        style,
+        style_focused,
+        children: nodes,
+    }))
+}
+
+/// Take `keyword (attrs)` if it's next, returning the attribute tokens.
+fn keyword_attrs<'a>(cursor: &mut Cursor<'a>, keyword: &str) -> Option<&'a [Token]> {
+    if cursor.is_ident(0, keyword) && cursor.is_group(1, Delimiter::Paren) {
+        cursor.pos += 1;
+        return cursor.group(Delimiter::Paren);
+    }
+    None
+}
+
+/// Parse `name: value, flag` attributes, or `None` if any isn't static.
+fn parse_attrs(tokens: &[Token], layout: bool) -> Option<Vec<Attr>> {
+    tokens
+        .split(|token| *token == Token::Punct(','))
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let (Token::Ident(name), rest) = segment.split_first()? else {
+                return None;
+            };
+            let value = match rest {
+                [] => Value::Flag,
+                [Token::Punct(':'), value @ ..] => parse_value(value)?,
+                _ => return None,
+            };
+            let is_static = match &value {
+                _ if name == "class" => false,
+                Value::Ident(ident) => {
+                    !layout
+                        || KEYWORD_ATTRS.contains(&name.as_str())
+                        || (matches!(name.as_str(), "width" | "height")
+                            && (ident == "auto" || ident == "fill"))
+                }
+                _ => true,
+            };
+            is_static.then(|| Attr {
+                name: name.clone(),
+                value,
+            })
+        })
+        .collect()
+}
+
+fn parse_value(tokens: &[Token]) -> Option<Value> {
+    match tokens {
+        [Token::Ident(ident)] => match ident.as_str() {
+            "true" => Some(Value::Literal(Literal::Bool(true))),
+            "false" => Some(Value::Literal(Literal::Bool(false))),
+            "self" => None,
+            _ => Some(Value::Ident(ident.clone())),
+        },
+        [Token::Literal { value, .. }] => Some(Value::Literal(value.clone())),
+        [Token::Group(Delimiter::Paren, inner)] => {
+            let values = inner
+                .split(|token| *token == Token::Punct(','))
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| match segment {
+                    [
+                        Token::Literal {
+                            value: Literal::Int(n),
+                            ..
+                        },
+                    ] => Some(*n),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            matches!(values.len(), 2 | 4).then_some(Value::Tuple(values))
+        }
+        _ => None,
+    }
+}
+
+/// Skip the expression after `...`: a path, with field access, calls and
+/// indexing.
+fn skip_expr(cursor: &mut Cursor) -> Result<(), TemplateError> {
+    if cursor.peek(0).is_none() {
+        return Err(TemplateError::new("expected an expression after `...`"));
+    }
+    cursor.pos += 1;
+
+    loop {
+        if cursor.is_punct(0, '.')
+            && matches!(
+                cursor.peek(1),
+                Some(Token::Ident(_) | Token::Literal { .. })
+            )
+        {
+            cursor.pos += 2;
+        } else if cursor.is_punct(0, ':') && cursor.is_punct(1, ':') {
+            cursor.pos += 3;
+        } else if cursor.is_punct(0, '!') && matches!(cursor.peek(1), Some(Token::Group(..))) {
+            cursor.pos += 2;
+        } else if cursor.is_group(0, Delimiter::Paren)
+            || cursor.is_group(0, Delimiter::Bracket)
+            || cursor.is_punct(0, '?')
+        {
+            cursor.pos += 1;
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+/// Skip tokens through the next brace group.
+fn skip_to_block(cursor: &mut Cursor) -> Result<(), TemplateError> {
+    while !cursor.is_group(0, Delimiter::Brace) {
+        if cursor.peek(0).is_none() {
+            return Err(TemplateError::new("expected `{`"));
+        }
+        cursor.pos += 1;
+    }
+    cursor.pos += 1;
+    Ok(())
+}
+
+/// Skip an `if` and its `else if` / `else` chain.
+fn skip_if(cursor: &mut Cursor) -> Result<(), TemplateError> {
+    skip_to_block(cursor)?;
+    while cursor.is_ident(0, "else") {
+        cursor.pos += 1;
+        skip_to_block(cursor)?;
+    }
+    Ok(())
+}
+
+struct Cursor<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self, n: usize) -> Option<&'a Token> {
+        self.tokens.get(self.pos + n)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn is_ident(&self, n: usize, name: &str) -> bool {
+        matches!(self.peek(n), Some(Token::Ident(ident)) if ident == name)
+    }
+
+    fn is_punct(&self, n: usize, c: char) -> bool {
+        self.peek(n) == Some(&Token::Punct(c))
+    }
+
+    fn is_group(&self, n: usize, delimiter: Delimiter) -> bool {
+        matches!(self.peek(n), Some(Token::Group(d, _)) if *d == delimiter)
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        let Some(Token::Ident(ident)) = self.peek(0) else {
+            return None;
+        };
+        self.pos += 1;
+        Some(ident.clone())
+    }
+
+    fn group(&mut self, delimiter: Delimiter) -> Option<&'a [Token]> {
+        match self.peek(0) {
+            Some(Token::Group(d, tokens)) if *d == delimiter => {
+                self.pos += 1;
+                Some(tokens)
+            }
+            _ => None,
+        }
+    }
+
+    /// The tokens from `start` to here, as text.
+    fn source(&self, start: usize) -> String {
+        self.tokens[start..self.pos]
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
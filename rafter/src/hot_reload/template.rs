@@ -0,0 +1,560 @@
+//! Interpreted templates.
+//!
+//! A parsed template is resolved into builder operations when it's loaded, so
+//! every error shows up once, at load time, and building a frame can't fail.
+
+use tuidom::{
+    Align, Border, Color, Direction, Edges, Element, Justify, Overflow, Position, Size, Style,
+    TextWrap, UnderlineStyle, Wrap,
+};
+
+use super::TemplateError;
+use super::parse::{self, Attr, Literal, Node, Value};
+use crate::widgets::Text;
+use crate::{HandlerRegistry, WidgetHandlers};
+
+/// A template file, resolved and ready to build.
+#[derive(Debug)]
+pub(super) struct Template {
+    root: Resolved,
+}
+
+impl Template {
+    /// Resolve an edited template against the source the page was compiled
+    /// from. The holes must be unchanged, since only the compiled page can
+    /// build them.
+    pub(super) fn load(
+        edited: &str,
+        compiled: &str,
+        hole_count: usize,
+    ) -> Result<Self, TemplateError> {
+        let compiled = parse::parse(compiled)?;
+        let edited = parse::parse(edited)?;
+
+        let mut compiled_holes = Vec::new();
+        collect_holes(&compiled, &mut compiled_holes);
+        if compiled_holes.len() != hole_count {
+            return Err(TemplateError::new(
+                "the compiled page has different holes than the template; rebuild to apply",
+            ));
+        }
+
+        let mut edited_holes = Vec::new();
+        collect_holes(&edited, &mut edited_holes);
+        if let Some((before, after)) = compiled_holes
+            .iter()
+            .zip(&edited_holes)
+            .find(|(before, after)| before != after)
+        {
+            return Err(TemplateError::new(format!(
+                "dynamic content changed from `{}` to `{}`; rebuild to apply",
+                before, after
+            )));
+        }
+        if compiled_holes.len() != edited_holes.len() {
+            return Err(TemplateError::new(
+                "dynamic content was added or removed; rebuild to apply",
+            ));
+        }
+
+        match edited {
+            Node::Element(root) => Ok(Self {
+                root: resolve(root)?,
+            }),
+            Node::Hole(_) => Err(TemplateError::new("the root must be a static element")),
+        }
+    }
+
+    /// Build the element tree, taking each hole's elements in order.
+    pub(super) fn build(&self, holes: &mut [Vec<Element>]) -> Element {
+        self.root.build(&mut holes.iter_mut())
+    }
+}
+
+fn collect_holes<'a>(node: &'a Node, holes: &mut Vec<&'a str>) {
+    match node {
+        Node::Element(element) => {
+            for child in &element.children {
+                collect_holes(child, holes);
+            }
+        }
+        Node::Hole(source) => holes.push(source),
+    }
+}
+
+// =============================================================================
+// Resolution
+// =============================================================================
+
+#[derive(Debug)]
+enum ResolvedNode {
+    Element(Box<Resolved>),
+    Hole,
+}
+
+#[derive(Debug)]
+struct Resolved {
+    kind: Kind,
+    layout: Vec<LayoutOp>,
+    style: Option<Style>,
+    style_focused: Option<Style>,
+    children: Vec<ResolvedNode>,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Column,
+    Row,
+    Box,
+    Text(TextProps),
+}
+
+#[derive(Debug, Default)]
+struct TextProps {
+    content: Option<String>,
+    id: Option<String>,
+    link: Option<String>,
+    ansi: Option<bool>,
+}
+
+/// A layout builder call on `tuidom::Element`.
+#[derive(Debug)]
+enum LayoutOp {
+    Padding(Edges),
+    Margin(Edges),
+    Gap(u16),
+    Width(Size),
+    Height(Size),
+    MinWidth(u16),
+    MaxWidth(u16),
+    MinHeight(u16),
+    MaxHeight(u16),
+    AspectRatio(f32),
+    Direction(Direction),
+    Justify(Justify),
+    Align(Align),
+    Wrap(Wrap),
+    AlignContent(Justify),
+    TextWrap(TextWrap),
+    FlexGrow(u16),
+    FlexShrink(u16),
+    AlignSelf(Align),
+    Overflow(Overflow),
+    Position(Position),
+    Top(i16),
+    Left(i16),
+    Right(i16),
+    Bottom(i16),
+    ZIndex(i16),
+    Id(String),
+    Focusable(bool),
+    Clickable(bool),
+    Draggable(bool),
+}
+
+fn resolve(element: parse::Element) -> Result<Resolved, TemplateError> {
+    let mut kind = match element.name.as_str() {
+        "column" | "col" => Kind::Column,
+        "row" => Kind::Row,
+        "box" | "box_" => Kind::Box,
+        _ => Kind::Text(TextProps::default()),
+    };
+
+    let mut layout = Vec::new();
+    for attr in &element.layout {
+        match &mut kind {
+            Kind::Text(props) if !is_widget_layout_attr(&attr.name) => {
+                resolve_text_prop(props, attr)?;
+            }
+            _ => layout.extend(resolve_layout(attr)?),
+        }
+    }
+
+    let style = resolve_style(&element.style)?;
+    let style_focused = resolve_style(&element.style_focused)?;
+    if matches!(kind, Kind::Text(_)) && style_focused.is_some() {
+        return Err(TemplateError::new("`text` has no focused style"));
+    }
+
+    let children = element
+        .children
+        .into_iter()
+        .map(|child| match child {
+            Node::Element(element) => resolve(element).map(|r| ResolvedNode::Element(Box::new(r))),
+            Node::Hole(_) => Ok(ResolvedNode::Hole),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Resolved {
+        kind,
+        layout,
+        style,
+        style_focused,
+        children,
+    })
+}
+
+/// Layout attributes that apply to a widget's element rather than the widget.
+fn is_widget_layout_attr(name: &str) -> bool {
+    !matches!(name, "id" | "focusable" | "clickable" | "draggable") && is_layout_attr(name)
+}
+
+fn is_layout_attr(name: &str) -> bool {
+    matches!(
+        name,
+        "padding"
+            | "margin"
+            | "gap"
+            | "width"
+            | "height"
+            | "min_width"
+            | "max_width"
+            | "min_height"
+            | "max_height"
+            | "aspect_ratio"
+            | "direction"
+            | "justify"
+            | "align"
+            | "wrap"
+            | "align_content"
+            | "text_wrap"
+            | "flex_grow"
+            | "flex_shrink"
+            | "align_self"
+            | "overflow"
+            | "position"
+            | "top"
+            | "left"
+            | "right"
+            | "bottom"
+            | "z_index"
+            | "id"
+            | "focusable"
+            | "clickable"
+            | "draggable"
+    )
+}
+
+fn resolve_text_prop(props: &mut TextProps, attr: &Attr) -> Result<(), TemplateError> {
+    match (attr.name.as_str(), &attr.value) {
+        ("content", Value::Literal(Literal::Str(s))) => props.content = Some(s.clone()),
+        ("id", Value::Literal(Literal::Str(s))) => props.id = Some(s.clone()),
+        ("link", Value::Literal(Literal::Str(s))) => props.link = Some(s.clone()),
+        ("ansi", Value::Flag) => props.ansi = Some(true),
+        ("ansi", Value::Literal(Literal::Bool(b))) => props.ansi = Some(*b),
+        ("content" | "id" | "link" | "ansi", _) => return Err(invalid(attr)),
+        (name, _) => {
+            return Err(TemplateError::new(format!(
+                "`text` has no `{}` attribute",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a layout attribute. Unknown attributes are ignored, as in `page!`.
+fn resolve_layout(attr: &Attr) -> Result<Option<LayoutOp>, TemplateError> {
+    let value = &attr.value;
+    let op = match attr.name.as_str() {
+        "padding" => LayoutOp::Padding(edges(attr)?),
+        "margin" => LayoutOp::Margin(edges(attr)?),
+        "gap" => LayoutOp::Gap(number(attr)? as u16),
+        "width" => LayoutOp::Width(size(attr)?),
+        "height" => LayoutOp::Height(size(attr)?),
+        "min_width" => LayoutOp::MinWidth(number(attr)? as u16),
+        "max_width" => LayoutOp::MaxWidth(number(attr)? as u16),
+        "min_height" => LayoutOp::MinHeight(number(attr)? as u16),
+        "max_height" => LayoutOp::MaxHeight(number(attr)? as u16),
+        "aspect_ratio" => LayoutOp::AspectRatio(number(attr)? as f32),
+        "direction" => LayoutOp::Direction(match keyword(attr)? {
+            "row" | "horizontal" => Direction::Row,
+            "column" | "col" | "vertical" => Direction::Column,
+            _ => return Err(invalid(attr)),
+        }),
+        "justify" => LayoutOp::Justify(justify(attr)?),
+        "align" => LayoutOp::Align(align(attr)?),
+        "wrap" => LayoutOp::Wrap(match keyword(attr)? {
+            "no_wrap" | "nowrap" => Wrap::NoWrap,
+            "wrap" => Wrap::Wrap,
+            _ => return Err(invalid(attr)),
+        }),
+        "align_content" => LayoutOp::AlignContent(justify(attr)?),
+        "text_wrap" => LayoutOp::TextWrap(match keyword(attr)? {
+            "no_wrap" | "nowrap" => TextWrap::NoWrap,
+            "word_wrap" | "word" => TextWrap::WordWrap,
+            "char_wrap" | "char" => TextWrap::CharWrap,
+            "truncate" => TextWrap::Truncate,
+            _ => return Err(invalid(attr)),
+        }),
+        "flex_grow" => LayoutOp::FlexGrow(number(attr)? as u16),
+        "flex_shrink" => LayoutOp::FlexShrink(number(attr)? as u16),
+        "align_self" => LayoutOp::AlignSelf(align(attr)?),
+        "overflow" => LayoutOp::Overflow(match keyword(attr)? {
+            "visible" => Overflow::Visible,
+            "hidden" => Overflow::Hidden,
+            "scroll" => Overflow::Scroll,
+            "auto" => Overflow::Auto,
+            _ => return Err(invalid(attr)),
+        }),
+        "position" => LayoutOp::Position(match keyword(attr)? {
+            "relative" => Position::Relative,
+            "absolute" => Position::Absolute,
+            _ => return Err(invalid(attr)),
+        }),
+        "top" => LayoutOp::Top(number(attr)? as i16),
+        "left" => LayoutOp::Left(number(attr)? as i16),
+        "right" => LayoutOp::Right(number(attr)? as i16),
+        "bottom" => LayoutOp::Bottom(number(attr)? as i16),
+        "z_index" => LayoutOp::ZIndex(number(attr)? as i16),
+        "id" => match value {
+            Value::Literal(Literal::Str(s)) => LayoutOp::Id(s.clone()),
+            _ => return Err(invalid(attr)),
+        },
+        "focusable" => LayoutOp::Focusable(flag(attr)?),
+        "clickable" => LayoutOp::Clickable(flag(attr)?),
+        "draggable" => LayoutOp::Draggable(flag(attr)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(op))
+}
+
+fn invalid(attr: &Attr) -> TemplateError {
+    TemplateError::new(format!("invalid value for `{}`", attr.name))
+}
+
+fn number(attr: &Attr) -> Result<f64, TemplateError> {
+    match attr.value {
+        Value::Literal(Literal::Int(n)) => Ok(n as f64),
+        Value::Literal(Literal::Float(n)) => Ok(n),
+        _ => Err(invalid(attr)),
+    }
+}
+
+fn keyword(attr: &Attr) -> Result<&str, TemplateError> {
+    match &attr.value {
+        Value::Ident(ident) => Ok(ident),
+        _ => Err(invalid(attr)),
+    }
+}
+
+fn flag(attr: &Attr) -> Result<bool, TemplateError> {
+    match attr.value {
+        Value::Flag => Ok(true),
+        Value::Literal(Literal::Bool(b)) => Ok(b),
+        _ => Err(invalid(attr)),
+    }
+}
+
+fn edges(attr: &Attr) -> Result<Edges, TemplateError> {
+    match &attr.value {
+        Value::Literal(Literal::Int(n)) => Ok(Edges::all(u16::try_from(*n).unwrap_or(0))),
+        Value::Tuple(values) => match values[..] {
+            [vertical, horizontal] => Ok(Edges::new(
+                vertical as u16,
+                horizontal as u16,
+                vertical as u16,
+                horizontal as u16,
+            )),
+            [top, right, bottom, left] => Ok(Edges::new(
+                top as u16,
+                right as u16,
+                bottom as u16,
+                left as u16,
+            )),
+            _ => Err(invalid(attr)),
+        },
+        _ => Err(invalid(attr)),
+    }
+}
+
+fn size(attr: &Attr) -> Result<Size, TemplateError> {
+    match &attr.value {
+        Value::Ident(ident) if ident == "auto" => Ok(Size::Auto),
+        Value::Ident(ident) if ident == "fill" => Ok(Size::Fill),
+        Value::Literal(Literal::Int(n)) => Ok(Size::Fixed(u16::try_from(*n).unwrap_or(0))),
+        Value::Literal(Literal::Str(s)) => Ok(match s.strip_suffix('%') {
+            Some(percent) => Size::Percent(percent.parse().unwrap_or(100.0) / 100.0),
+            None => Size::Fixed(s.parse().unwrap_or(0)),
+        }),
+        _ => Err(invalid(attr)),
+    }
+}
+
+fn justify(attr: &Attr) -> Result<Justify, TemplateError> {
+    Ok(match keyword(attr)? {
+        "start" => Justify::Start,
+        "end" => Justify::End,
+        "center" => Justify::Center,
+        "between" | "space_between" => Justify::SpaceBetween,
+        "around" | "space_around" => Justify::SpaceAround,
+        _ => return Err(invalid(attr)),
+    })
+}
+
+fn align(attr: &Attr) -> Result<Align, TemplateError> {
+    Ok(match keyword(attr)? {
+        "start" => Align::Start,
+        "end" => Align::End,
+        "center" => Align::Center,
+        "stretch" => Align::Stretch,
+        _ => return Err(invalid(attr)),
+    })
+}
+
+/// Resolve style attributes, or `None` if none of them apply.
+fn resolve_style(attrs: &[Attr]) -> Result<Option<Style>, TemplateError> {
+    let mut style = Style::new();
+    let mut applied = false;
+
+    for attr in attrs {
+        style = match attr.name.as_str() {
+            "bg" | "background" => style.background(color(attr)?),
+            "fg" | "foreground" | "color" => style.foreground(color(attr)?),
+            "bold" if is_true(&attr.value) => style.bold(),
+            "italic" if is_true(&attr.value) => style.italic(),
+            "dim" if is_true(&attr.value) => style.dim(),
+            "underline" => match &attr.value {
+                Value::Ident(ident) => style.underline_style(match ident.as_str() {
+                    "double" => UnderlineStyle::Double,
+                    "curly" => UnderlineStyle::Curly,
+                    "dotted" => UnderlineStyle::Dotted,
+                    "dashed" => UnderlineStyle::Dashed,
+                    _ => UnderlineStyle::Single,
+                }),
+                value if is_true(value) => style.underline(),
+                _ => continue,
+            },
+            "underline_color" => style.underline_color(color(attr)?),
+            "border" => style.border(match &attr.value {
+                Value::Ident(ident) => match ident.as_str() {
+                    "single" => Border::Single,
+                    "double" => Border::Double,
+                    "rounded" => Border::Rounded,
+                    "thick" => Border::Thick,
+                    _ => Border::None,
+                },
+                _ => Border::None,
+            }),
+            _ => continue,
+        };
+        applied = true;
+    }
+
+    Ok(applied.then_some(style))
+}
+
+/// Whether a style flag is on: `true`, or present with any other value.
+fn is_true(value: &Value) -> bool {
+    match value {
+        Value::Ident(_) => false,
+        Value::Literal(Literal::Bool(b)) => *b,
+        _ => true,
+    }
+}
+
+fn color(attr: &Attr) -> Result<Color, TemplateError> {
+    match &attr.value {
+        Value::Ident(ident) => Ok(Color::var(ident)),
+        Value::Literal(Literal::Str(s)) if s.starts_with('#') => {
+            Ok(s.parse().unwrap_or_else(|_| Color::var(s)))
+        }
+        Value::Literal(Literal::Str(s)) => Ok(Color::var(s)),
+        _ => Err(invalid(attr)),
+    }
+}
+
+// =============================================================================
+// Building
+// =============================================================================
+
+impl Resolved {
+    fn build<'a>(&self, holes: &mut impl Iterator<Item = &'a mut Vec<Element>>) -> Element {
+        let element = match &self.kind {
+            Kind::Column => Element::col(),
+            Kind::Row => Element::row(),
+            Kind::Box => Element::box_(),
+            Kind::Text(props) => {
+                let mut text = Text::new();
+                if let Some(content) = &props.content {
+                    text = text.content(content);
+                }
+                if let Some(id) = &props.id {
+                    text = text.id(id);
+                }
+                if let Some(link) = &props.link {
+                    text = text.link(link);
+                }
+                if let Some(ansi) = props.ansi {
+                    text = text.ansi(ansi);
+                }
+                if let Some(style) = &self.style {
+                    text = text.style(style.clone());
+                }
+                let element = text.build(&HandlerRegistry::new(), &WidgetHandlers::new());
+                return self.layout.iter().fold(element, LayoutOp::apply);
+            }
+        };
+
+        let mut element = self.layout.iter().fold(element, LayoutOp::apply);
+        if let Some(style) = &self.style {
+            element = element.style(style.clone());
+        }
+        if let Some(style) = &self.style_focused {
+            element = element.style_focused(style.clone());
+        }
+        if self.children.is_empty() {
+            return element;
+        }
+
+        let mut children = Vec::new();
+        for child in &self.children {
+            match child {
+                ResolvedNode::Element(resolved) => children.push(resolved.build(holes)),
+                ResolvedNode::Hole => {
+                    if let Some(hole) = holes.next() {
+                        children.append(hole);
+                    }
+                }
+            }
+        }
+        element.children(children)
+    }
+}
+
+impl LayoutOp {
+    fn apply(element: Element, op: &LayoutOp) -> Element {
+        match op {
+            LayoutOp::Padding(edges) => element.padding(*edges),
+            LayoutOp::Margin(edges) => element.margin(*edges),
+            LayoutOp::Gap(gap) => element.gap(*gap),
+            LayoutOp::Width(size) => element.width(*size),
+            LayoutOp::Height(size) => element.height(*size),
+            LayoutOp::MinWidth(n) => element.min_width(*n),
+            LayoutOp::MaxWidth(n) => element.max_width(*n),
+            LayoutOp::MinHeight(n) => element.min_height(*n),
+            LayoutOp::MaxHeight(n) => element.max_height(*n),
+            LayoutOp::AspectRatio(ratio) => element.aspect_ratio(*ratio),
+            LayoutOp::Direction(direction) => element.direction(*direction),
+            LayoutOp::Justify(justify) => element.justify(*justify),
+            LayoutOp::Align(align) => element.align(*align),
+            LayoutOp::Wrap(wrap) => element.wrap(*wrap),
+            LayoutOp::AlignContent(justify) => element.align_content(*justify),
+            LayoutOp::TextWrap(wrap) => element.text_wrap(*wrap),
+            LayoutOp::FlexGrow(n) => element.flex_grow(*n),
+            LayoutOp::FlexShrink(n) => element.flex_shrink(*n),
+            LayoutOp::AlignSelf(align) => element.align_self(*align),
+            LayoutOp::Overflow(overflow) => element.overflow(*overflow),
+            LayoutOp::Position(position) => element.position(*position),
+            LayoutOp::Top(n) => element.top(*n),
+            LayoutOp::Left(n) => element.left(*n),
+            LayoutOp::Right(n) => element.right(*n),
+            LayoutOp::Bottom(n) => element.bottom(*n),
+            LayoutOp::ZIndex(n) => element.z_index(*n),
+            LayoutOp::Id(id) => element.id(id),
+            LayoutOp::Focusable(b) => element.focusable(*b),
+            LayoutOp::Clickable(b) => element.clickable(*b),
+            LayoutOp::Draggable(b) => element.draggable(*b),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Theme colors loaded from a file.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use tuidom::{Color, Theme};
+
+/// Theme colors read from a file, layered over a base theme.
+///
+/// Each line is `name = color`, where the color is anything `Color` parses:
+/// `#ff8800`, `rgb(255, 136, 0)`, `oklch(0.7, 0.15, 60)`, another color's
+/// name, optionally followed by operations like `| lighten(0.1)`. Blank lines
+/// and lines starting with `//` are skipped. Names the file doesn't define
+/// resolve through the base theme.
+///
+/// ```text
+/// // Warmer accent for this machine
+/// primary = #ff8800
+/// primary.hover = primary | lighten(0.1)
+/// ```
+pub struct ThemeFile {
+    base: Arc<dyn Theme>,
+    colors: HashMap<String, Color>,
+}
+
+/// Error loading a [`ThemeFile`].
+#[derive(Debug, thiserror::Error)]
+pub enum ThemeFileError {
+    #[error("failed to read theme file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+impl ThemeFile {
+    /// Read a theme file.
+    pub fn load(path: impl AsRef<Path>, base: Arc<dyn Theme>) -> Result<Self, ThemeFileError> {
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source, base)
+    }
+
+    /// Parse theme file contents.
+    pub fn parse(source: &str, base: Arc<dyn Theme>) -> Result<Self, ThemeFileError> {
+        let mut colors = HashMap::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let error = |message: String| ThemeFileError::Parse {
+                line: index + 1,
+                message,
+            };
+            let (name, color) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `name = color`".into()))?;
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(error("missing color name".into()));
+            }
+            let color: Color = color.trim().parse().map_err(|e| error(format!("{}", e)))?;
+            colors.insert(name.to_string(), color);
+        }
+
+        Ok(Self { base, colors })
+    }
+}
+
+impl Theme for ThemeFile {
+    fn resolve(&self, name: &str) -> Option<&Color> {
+        self.colors.get(name).or_else(|| self.base.resolve(name))
+    }
+}
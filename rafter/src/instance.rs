@@ -6,7 +6,7 @@ use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
 use tuidom::Element;
@@ -16,6 +16,7 @@ use crate::app::{App, AppConfig};
 use crate::global_context::InstanceQuery;
 use crate::keybinds::KeybindClosures;
 use crate::lifecycle::LifecycleHooks;
+use crate::persist::PersistStore;
 use crate::wakeup::WakeupSender;
 use crate::{AppContext, GlobalContext, HandlerRegistry};
 
@@ -267,6 +268,9 @@ pub trait AnyAppInstance: Send + Sync {
 
     /// Set the sleeping state.
     fn set_sleeping(&self, sleeping: bool);
+
+    /// Save persisted app state to the store, if the app has any.
+    fn save_state(&self);
 }
 
 // =============================================================================
@@ -285,6 +289,8 @@ pub struct AppInstance<A: App> {
     context: RwLock<AppContext>,
     /// Modal stack for this instance.
     modals: RwLock<Vec<Box<dyn crate::runtime::dispatch::AnyModal>>>,
+    /// Store for persisted app state.
+    persist_store: Option<Arc<dyn PersistStore>>,
 }
 
 impl<A: App> AppInstance<A> {
@@ -300,14 +306,32 @@ impl<A: App> AppInstance<A> {
             type_id
         );
         let info = InstanceInfo::new(id, type_id, config.name, title);
+        let persist_store = gx.persist_store();
         let context = AppContext::new(id, gx, config.name);
 
-        Self {
+        let instance = Self {
             app,
             id,
             info: RwLock::new(info),
             context: RwLock::new(context),
             modals: RwLock::new(Vec::new()),
+            persist_store,
+        };
+        instance.restore_state();
+        instance
+    }
+
+    /// Restore persisted app state from the store, if the app has any.
+    fn restore_state(&self) {
+        let (Some(store), Some(app)) = (&self.persist_store, self.app.as_persistent()) else {
+            return;
+        };
+        let name = A::config().name;
+        let result = store
+            .load(name)
+            .and_then(|data| data.map_or(Ok(()), |data| app.restore_state(&data)));
+        if let Err(e) = result {
+            log::warn!("[AppInstance] Failed to restore state for {}: {}", name, e);
         }
     }
 
@@ -444,6 +468,7 @@ impl<A: App> AnyAppInstance for AppInstance<A> {
                     .clone(),
             ),
             modals: RwLock::new(Vec::new()), // Modals don't transfer on clone
+            persist_store: self.persist_store.clone(),
         })
     }
 
@@ -468,6 +493,17 @@ impl<A: App> AnyAppInstance for AppInstance<A> {
             info.is_sleeping = sleeping;
         }
     }
+
+    fn save_state(&self) {
+        let (Some(store), Some(app)) = (&self.persist_store, self.app.as_persistent()) else {
+            return;
+        };
+        let name = A::config().name;
+        let result = app.save_state().and_then(|data| store.save(name, &data));
+        if let Err(e) = result {
+            log::warn!("[AppInstance] Failed to save state for {}: {}", name, e);
+        }
+    }
 }
 
 // =============================================================================
@@ -518,6 +554,7 @@ impl InstanceRegistry {
         let Some(instance) = self.instances.remove(&id) else {
             return false;
         };
+        instance.save_state();
 
         let type_id = AnyAppInstance::type_id(instance.as_ref());
 
@@ -538,6 +575,13 @@ impl InstanceRegistry {
         true
     }
 
+    /// Save the persisted state of every instance (used during shutdown).
+    pub fn save_all_state(&self) {
+        for instance in self.instances.values() {
+            instance.save_state();
+        }
+    }
+
     /// Focus an instance.
     pub fn focus(&mut self, id: InstanceId) -> bool {
         if !self.instances.contains_key(&id) {
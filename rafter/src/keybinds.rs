@@ -92,10 +92,16 @@ pub struct Keybind {
     pub default_keys: String,
     /// Current key sequence (None = disabled).
     pub keys: Option<Vec<KeyCombo>>,
+    /// Original key strings of the alternative sequences (e.g., "q" | "escape").
+    pub default_aliases: Vec<String>,
+    /// Alternative key sequences that trigger the same handler.
+    pub aliases: Vec<Vec<KeyCombo>>,
     /// Handler to invoke.
     pub handler: HandlerId,
     /// Page scope.
     pub scope: KeybindScope,
+    /// Human-readable description for help overlays.
+    pub description: Option<String>,
 }
 
 impl Keybind {
@@ -109,8 +115,11 @@ impl Keybind {
             id: id.into(),
             default_keys: default_keys.into(),
             keys: Some(keys),
+            default_aliases: Vec::new(),
+            aliases: Vec::new(),
             handler: handler.into(),
             scope: KeybindScope::Global,
+            description: None,
         }
     }
 
@@ -119,6 +128,18 @@ impl Keybind {
         self
     }
 
+    /// Add an alternative key sequence.
+    pub fn with_alias(mut self, key_string: impl Into<String>, keys: Vec<KeyCombo>) -> Self {
+        self.default_aliases.push(key_string.into());
+        self.aliases.push(keys);
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
     pub fn with_id_prefix(mut self, prefix: &str) -> Self {
         self.id = format!("{}.{}", prefix, self.id);
         self
@@ -142,15 +163,53 @@ impl Keybind {
         self.keys.as_deref()
     }
 
+    /// All key sequences that trigger this keybind, primary first.
+    /// Empty when disabled.
+    pub fn sequences(&self) -> impl Iterator<Item = &[KeyCombo]> {
+        let aliases = if self.is_enabled() {
+            self.aliases.as_slice()
+        } else {
+            &[]
+        };
+        self.keys
+            .as_deref()
+            .into_iter()
+            .chain(aliases.iter().map(Vec::as_slice))
+    }
+
     pub fn disable(&mut self) {
         self.keys = None;
     }
 
+    /// Replace the keys. Aliases are dropped, since the user chose new keys.
     pub fn set_keys(&mut self, keys: Vec<KeyCombo>) {
         self.keys = Some(keys);
+        self.aliases.clear();
+    }
+
+    /// Restore the default keys and aliases.
+    fn reset(&mut self) -> Result<(), ParseKeyError> {
+        self.keys = Some(parse_key_string(&self.default_keys)?);
+        self.aliases = self
+            .default_aliases
+            .iter()
+            .map(|keys| parse_key_string(keys))
+            .collect::<Result<_, _>>()?;
+        Ok(())
     }
 }
 
+/// Result of matching keys pressed so far against multi-key sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch<'a> {
+    /// The keys complete a sequence.
+    Matched(&'a HandlerId),
+    /// The keys are the start of at least one sequence; wait for more.
+    Pending,
+    /// No sequence starts with these keys.
+    None,
+}
+
 /// Collection of keybinds.
 #[derive(Debug, Clone, Default)]
 pub struct Keybinds {
@@ -169,22 +228,48 @@ impl Keybinds {
     /// Add a keybind from a key string and handler name.
     /// Returns false and logs if parsing fails.
     pub fn add_str(&mut self, key_string: &str, handler: &str) -> bool {
-        match parse_key_string(key_string) {
-            Ok(keys) => {
-                self.binds
-                    .push(Keybind::new(handler, key_string, keys, handler));
-                true
-            }
-            Err(e) => {
-                log::error!(
-                    "Failed to parse keybind '{}' for handler '{}': {}",
-                    key_string,
-                    handler,
-                    e
-                );
-                false
+        self.add_binding(handler, &[key_string], None)
+    }
+
+    /// Add one keybind for `handler` triggered by any of `key_strings`.
+    ///
+    /// The first key string that parses is the primary key, the rest are
+    /// aliases. Unparseable key strings are logged and skipped; returns false
+    /// if none could be parsed.
+    pub fn add_binding(
+        &mut self,
+        handler: &str,
+        key_strings: &[&str],
+        description: Option<&str>,
+    ) -> bool {
+        let mut bind: Option<Keybind> = None;
+        for key_string in key_strings {
+            match parse_key_string(key_string) {
+                Ok(keys) => {
+                    bind = Some(match bind {
+                        Some(bind) => bind.with_alias(*key_string, keys),
+                        None => Keybind::new(handler, *key_string, keys, handler),
+                    });
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to parse keybind '{}' for handler '{}': {}",
+                        key_string,
+                        handler,
+                        e
+                    );
+                }
             }
         }
+
+        let Some(mut bind) = bind else {
+            return false;
+        };
+        if let Some(description) = description {
+            bind = bind.with_description(description);
+        }
+        self.binds.push(bind);
+        true
     }
 
     /// Match a tuidom key event against keybinds.
@@ -208,28 +293,45 @@ impl Keybinds {
 
     /// Look up handler for a single key, respecting page scope.
     pub fn get_single(&self, key: &KeyCombo, current_page: Option<&str>) -> Option<&HandlerId> {
-        // Page-scoped keybinds take priority
-        for bind in &self.binds {
-            if let Some(keys) = &bind.keys
-                && keys.len() == 1
-                && keys[0] == *key
-                && let KeybindScope::Page(page) = &bind.scope
-                && current_page == Some(page.as_str())
-            {
-                return Some(&bind.handler);
-            }
+        match self.match_sequence(std::slice::from_ref(key), current_page) {
+            SequenceMatch::Matched(handler) => Some(handler),
+            _ => None,
         }
-        // Then global keybinds
-        for bind in &self.binds {
-            if let Some(keys) = &bind.keys
-                && keys.len() == 1
-                && keys[0] == *key
-                && bind.scope == KeybindScope::Global
-            {
-                return Some(&bind.handler);
+    }
+
+    /// Match the keys pressed so far against all sequences, respecting page scope.
+    ///
+    /// A complete match wins over longer sequences that start with the same
+    /// keys, and page-scoped keybinds win over global ones.
+    pub fn match_sequence(
+        &self,
+        pending: &[KeyCombo],
+        current_page: Option<&str>,
+    ) -> SequenceMatch<'_> {
+        if pending.is_empty() {
+            return SequenceMatch::None;
+        }
+
+        let mut global = None;
+        let mut prefix = false;
+        for bind in self.active_for(current_page) {
+            for keys in bind.sequences() {
+                if keys == pending {
+                    if matches!(bind.scope, KeybindScope::Page(_)) {
+                        return SequenceMatch::Matched(&bind.handler);
+                    }
+                    global.get_or_insert(&bind.handler);
+                } else if keys.starts_with(pending) {
+                    prefix = true;
+                }
             }
         }
-        None
+
+        match global {
+            Some(handler) => SequenceMatch::Matched(handler),
+            None if prefix => SequenceMatch::Pending,
+            None => SequenceMatch::None,
+        }
     }
 
     pub fn all(&self) -> &[Keybind] {
@@ -297,18 +399,14 @@ impl Keybinds {
             .get_by_id_mut(id)
             .ok_or_else(|| KeybindError::NotFound(id.to_string()))?;
 
-        let keys = parse_key_string(&bind.default_keys)
-            .map_err(|e| KeybindError::ParseError(e.message))?;
-        bind.set_keys(keys);
-        Ok(())
+        bind.reset()
+            .map_err(|e| KeybindError::ParseError(e.message))
     }
 
     /// Reset all keybinds to their defaults.
     pub fn reset_all(&mut self) {
         for bind in &mut self.binds {
-            if let Ok(keys) = parse_key_string(&bind.default_keys) {
-                bind.set_keys(keys);
-            }
+            let _ = bind.reset();
         }
     }
 
@@ -323,7 +421,13 @@ impl Keybinds {
                 } else {
                     None
                 },
+                aliases: if bind.is_enabled() {
+                    bind.default_aliases.clone()
+                } else {
+                    Vec::new()
+                },
                 handler: bind.handler.0.clone(),
+                description: bind.description.clone(),
                 enabled: bind.is_enabled(),
             })
             .collect()
@@ -355,14 +459,29 @@ pub struct KeybindInfo {
     pub id: String,
     /// Current key string (None if disabled).
     pub keys: Option<String>,
+    /// Alternative key strings.
+    pub aliases: Vec<String>,
     /// Handler name.
     pub handler: String,
+    /// Human-readable description, if the keybind has one.
+    pub description: Option<String>,
     /// Whether the keybind is enabled.
     pub enabled: bool,
 }
 
 /// Parse a key string like "ctrl+shift+a" or "gg" into KeyCombo(s).
+///
+/// Chords separated by whitespace form a sequence, e.g. "ctrl+k ctrl+s".
 pub fn parse_key_string(s: &str) -> Result<Vec<KeyCombo>, ParseKeyError> {
+    let trimmed = s.trim();
+    if trimmed.contains(char::is_whitespace) {
+        let mut combos = Vec::new();
+        for chord in trimmed.split_whitespace() {
+            combos.extend(parse_key_string(chord)?);
+        }
+        return Ok(combos);
+    }
+
     // Special case: "+" by itself is the plus key
     if s == "+" {
         return Ok(vec![KeyCombo::new(Key::Char('+'), Modifiers::default())]);
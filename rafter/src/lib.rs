@@ -6,12 +6,15 @@ mod context_menu;
 mod event;
 mod global_context;
 mod handler_context;
+pub mod hot_reload;
 mod instance;
 mod job;
 pub mod keybinds;
 mod lifecycle;
 mod modal;
+mod persist;
 pub mod prelude;
+pub mod props;
 mod registration;
 mod request;
 mod resource;
@@ -34,7 +37,7 @@ pub use context_menu::{
     ContextMenuBuilder, ContextMenuDefinition, ContextMenuRequest, ContextMenuState, MenuOption,
     OpenSubmenu,
 };
-pub use event::{Event, FocusChanged, InstanceClosed, InstanceSpawned};
+pub use event::{Event, FocusChanged, InstanceClosed, InstanceSpawned, TerminalFocusChanged};
 pub use global_context::{
     ArcEvent, DataStore, GlobalContext, GlobalModalRequest, InstanceCommand, InstanceQuery,
     RequestTarget,
@@ -47,12 +50,15 @@ pub use instance::{
 pub use job::JobId;
 pub use keybinds::{
     HandlerId, KeyCombo, Keybind, KeybindClosures, KeybindEntry, KeybindError, KeybindInfo,
-    KeybindScope, Keybinds, ParseKeyError, parse_key_string,
+    KeybindScope, Keybinds, ParseKeyError, SequenceMatch, parse_key_string,
 };
 pub use lifecycle::LifecycleHooks;
 pub use modal::{
     Modal, ModalContext, ModalEntry, ModalKind, ModalPosition, ModalSize, SystemModal,
 };
+pub use persist::{
+    FilePersistStore, MemoryPersistStore, PersistError, PersistStore, PersistedState, PersistentApp,
+};
 pub use registration::{
     AnySystem, AppRegistration, CloneableApp, SystemRegistration, registered_apps,
     registered_systems,
@@ -61,6 +67,8 @@ pub use request::Request;
 pub use resource::{ProgressState, Resource, ResourceError, ResourceState};
 pub use state::State;
 pub use system::{Overlay, OverlayPosition, System};
+#[doc(hidden)]
+pub use theme::__rafter_theme_colors;
 pub use theme::{RafterTheme, default_theme};
 pub use toast::{DEFAULT_TOAST_DURATION, Toast};
 pub use wakeup::{WakeupHandle, WakeupReceiver, WakeupSender, channel as wakeup_channel};
@@ -74,8 +82,9 @@ pub use runtime::{Runtime, RuntimeError};
 
 // Re-export derive macros
 pub use rafter_derive::{
-    Event, Request, app, app_impl, context_menu, derived, element, event_handler, handler,
-    keybinds, modal, modal_impl, page, request_handler, system, system_impl, theme,
+    Event, Request, app, app_impl, component, context_menu, derived, element, event_handler,
+    handler, keybinds, modal, modal_impl, on_event, on_request, page, request_handler, system,
+    system_impl, theme,
 };
 
 // =============================================================================
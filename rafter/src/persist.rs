@@ -0,0 +1,200 @@
+//! App state persistence.
+//!
+//! Apps declared with `#[app(persist)]` save their `#[persist]` fields when
+//! an instance closes (or the runtime shuts down) and restore them when a new
+//! instance opens. State is stored as JSON in the runtime's [`PersistStore`],
+//! keyed by app name, so it only happens when one is configured with
+//! [`Runtime::persist_store`](crate::Runtime::persist_store).
+//!
+//! ```ignore
+//! #[app(persist)]
+//! struct Explorer {
+//!     #[persist]
+//!     query: String,
+//!     #[persist]
+//!     columns: Vec<String>,
+//!     results: Vec<Record>,
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Error saving or restoring app state.
+#[derive(Debug, Error)]
+pub enum PersistError {
+    /// A field's value couldn't be serialized.
+    #[error("failed to save field `{field}`: {source}")]
+    Save {
+        field: &'static str,
+        source: serde_json::Error,
+    },
+    /// A stored field's value doesn't match the field's type.
+    #[error("failed to restore field `{field}`: {source}")]
+    Restore {
+        field: &'static str,
+        source: serde_json::Error,
+    },
+    /// Stored state isn't a JSON object.
+    #[error("invalid persisted state: {0}")]
+    Format(#[source] serde_json::Error),
+    /// The store couldn't be read or written.
+    #[error("persist store error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// An app whose state outlives its instances.
+///
+/// Implemented by `#[app(persist)]`; apps expose it to the runtime through
+/// [`App::as_persistent`](crate::App::as_persistent).
+pub trait PersistentApp: Send + Sync {
+    /// Serialize the persisted fields.
+    fn save_state(&self) -> Result<String, PersistError>;
+
+    /// Restore persisted fields from saved state.
+    ///
+    /// Fields missing from `data` keep their current value, so adding a
+    /// persisted field doesn't invalidate earlier saves.
+    fn restore_state(&self, data: &str) -> Result<(), PersistError>;
+}
+
+/// Storage for persisted app state.
+pub trait PersistStore: Send + Sync {
+    /// Load the state saved under `key`, if any.
+    fn load(&self, key: &str) -> Result<Option<String>, PersistError>;
+
+    /// Save state under `key`, replacing what was there.
+    fn save(&self, key: &str, data: &str) -> Result<(), PersistError>;
+}
+
+/// Stores each app's state in `<dir>/<key>.json`.
+#[derive(Debug, Clone)]
+pub struct FilePersistStore {
+    dir: PathBuf,
+}
+
+impl FilePersistStore {
+    /// Create a store in `dir`. The directory is created on first save.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+impl PersistStore for FilePersistStore {
+    fn load(&self, key: &str) -> Result<Option<String>, PersistError> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, key: &str, data: &str) -> Result<(), PersistError> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(key), data)?;
+        Ok(())
+    }
+}
+
+/// Keeps state in memory, for tests and sessions that shouldn't touch disk.
+#[derive(Debug, Default)]
+pub struct MemoryPersistStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryPersistStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PersistStore for MemoryPersistStore {
+    fn load(&self, key: &str) -> Result<Option<String>, PersistError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .get(key)
+            .cloned())
+    }
+
+    fn save(&self, key: &str, data: &str) -> Result<(), PersistError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert(key.to_string(), data.to_string());
+        Ok(())
+    }
+}
+
+/// Persisted fields being saved or restored, by name.
+///
+/// Used by the code `#[app(persist)]` generates.
+#[derive(Debug, Default)]
+pub struct PersistedState {
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PersistedState {
+    /// Create empty state to save fields into.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse saved state.
+    pub fn parse(data: &str) -> Result<Self, PersistError> {
+        let fields = serde_json::from_str(data).map_err(PersistError::Format)?;
+        Ok(Self { fields })
+    }
+
+    /// Save a field.
+    pub fn save<T: Serialize>(
+        &mut self,
+        field: &'static str,
+        value: &T,
+    ) -> Result<(), PersistError> {
+        let value =
+            serde_json::to_value(value).map_err(|source| PersistError::Save { field, source })?;
+        self.fields.insert(field.to_string(), value);
+        Ok(())
+    }
+
+    /// Take a saved field, or `None` if it wasn't saved.
+    pub fn restore<T: DeserializeOwned>(
+        &mut self,
+        field: &'static str,
+    ) -> Result<Option<T>, PersistError> {
+        self.fields
+            .remove(field)
+            .map(|value| {
+                serde_json::from_value(value)
+                    .map_err(|source| PersistError::Restore { field, source })
+            })
+            .transpose()
+    }
+
+    /// Serialize the saved fields.
+    pub fn to_json(&self) -> String {
+        serde_json::Value::Object(self.fields.clone()).to_string()
+    }
+}
@@ -8,11 +8,13 @@
 pub use crate::app::{App, AppConfig};
 pub use crate::app_context::AppContext;
 pub use crate::context_menu::{ContextMenuBuilder, ContextMenuDefinition};
+pub use crate::event::Event;
 pub use crate::global_context::GlobalContext;
 pub use crate::handler_context::HandlerRegistry;
 pub use crate::job::JobId;
 pub use crate::keybinds::Keybinds;
 pub use crate::modal::{Modal, ModalContext, ModalSize};
+pub use crate::request::Request;
 pub use crate::resource::{ProgressState, Resource, ResourceState};
 pub use crate::runtime::{Runtime, RuntimeError};
 pub use crate::state::State;
@@ -21,7 +23,8 @@ pub use crate::toast::Toast;
 
 // Derive macros
 pub use rafter_derive::{
-    app, app_impl, derived, handler, keybinds, modal, modal_impl, system, system_impl, watch,
+    Event, Request, app, app_impl, component, derived, event_handler, handler, keybinds, modal,
+    modal_impl, on_event, on_request, request_handler, system, system_impl, watch,
 };
 
 // Re-export tuidom Element for page! macro
@@ -0,0 +1,12 @@
+//! Typestate markers for `#[component]` props.
+//!
+//! The props struct generated by `#[component]` carries one of these per
+//! required prop; `build()` is only available once every one is [`Set`].
+
+/// A required prop that has been given a value.
+#[derive(Debug, Clone, Copy)]
+pub struct Set;
+
+/// A required prop that hasn't been given a value yet.
+#[derive(Debug, Clone, Copy)]
+pub struct Unset;
@@ -1291,8 +1291,11 @@ impl<'a> EventDispatcher<'a> {
                 }
             }
 
-            // MouseMove and Resize are not dispatched to widgets
-            Event::MouseMove { .. } | Event::Resize { .. } => {}
+            // MouseMove, Resize and terminal focus are not dispatched to widgets
+            Event::MouseMove { .. }
+            | Event::Resize { .. }
+            | Event::FocusGained
+            | Event::FocusLost => {}
 
             // Change events: pass text via EventData to handler
             Event::Change { target, text } => {
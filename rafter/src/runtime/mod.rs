@@ -26,9 +26,10 @@ use tuidom::{
 
 use enrich::enrich_elements;
 
-use crate::event::{FocusChanged, InstanceClosed, InstanceSpawned};
+use crate::event::{FocusChanged, InstanceClosed, InstanceSpawned, TerminalFocusChanged};
 use crate::global_context::{DataStore, InstanceCommand, InstanceQuery, RequestTarget};
 use crate::instance::{AnyAppInstance, AppInstance, InstanceId, InstanceRegistry, RequestError};
+use crate::persist::PersistStore;
 use crate::registration::{AnySystem, registered_systems};
 use crate::system::System;
 use crate::toast::Toast;
@@ -137,6 +138,11 @@ pub struct Runtime {
     data: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
     /// Systems to install.
     systems: Vec<Box<dyn AnySystem>>,
+    /// Store for persisted app state.
+    persist_store: Option<Arc<dyn PersistStore>>,
+    /// Theme file to watch, over its base theme.
+    #[cfg(feature = "hot-reload")]
+    theme_file: Option<(std::path::PathBuf, Arc<dyn tuidom::Theme>)>,
 }
 
 impl Runtime {
@@ -145,6 +151,9 @@ impl Runtime {
         Ok(Self {
             data: HashMap::new(),
             systems: Vec::new(),
+            persist_store: None,
+            #[cfg(feature = "hot-reload")]
+            theme_file: None,
         })
     }
 
@@ -164,6 +173,27 @@ impl Runtime {
         self
     }
 
+    /// Set the store for `#[app(persist)]` state.
+    ///
+    /// Without a store, persisted apps start fresh and nothing is saved.
+    pub fn persist_store(mut self, store: impl PersistStore + 'static) -> Self {
+        self.persist_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Load theme colors from a file over `base`, reloading it on change.
+    ///
+    /// See [`ThemeFile`](crate::hot_reload::ThemeFile) for the format.
+    #[cfg(feature = "hot-reload")]
+    pub fn theme_file(
+        mut self,
+        path: impl Into<std::path::PathBuf>,
+        base: impl tuidom::Theme + 'static,
+    ) -> Self {
+        self.theme_file = Some((path.into(), Arc::new(base)));
+        self
+    }
+
     /// Run the runtime with an initial app.
     ///
     /// This is the main entry point. The runtime will:
@@ -193,11 +223,17 @@ impl Runtime {
         let cursor_state = Arc::new(RwLock::new(CursorState::new()));
         let mut gx = GlobalContext::new(Arc::clone(&data_store), cursor_state);
         gx.set_wakeup_sender(wakeup_tx.clone());
+        if let Some(store) = self.persist_store.take() {
+            gx.set_persist_store(store);
+        }
 
         // Create registry query wrapper
         let registry_query = RegistryQuery(Arc::clone(&registry));
         gx.set_registry(Arc::new(registry_query));
 
+        #[cfg(feature = "hot-reload")]
+        crate::hot_reload::spawn_watcher(gx.clone(), self.theme_file.take());
+
         // Initialize systems (merge manually added with auto-registered)
         let mut systems: Vec<Box<dyn AnySystem>> = std::mem::take(&mut self.systems);
 
@@ -291,23 +327,29 @@ impl Runtime {
 
         // Run event loop
         let mut wakeup_rx = wakeup_rx;
-        self.event_loop(
-            &mut terminal,
-            &mut focus,
-            &mut scroll,
-            &mut text_inputs,
-            &registry,
-            &mut systems,
-            &gx,
-            &mut wakeup_rx,
-            &mut active_toasts,
-            &mut next_toast_id,
-            &mut global_modals,
-            &mut app_context_menu,
-            &mut global_context_menu,
-            &mut job_scheduler,
-        )
-        .await
+        let result = self
+            .event_loop(
+                &mut terminal,
+                &mut focus,
+                &mut scroll,
+                &mut text_inputs,
+                &registry,
+                &mut systems,
+                &gx,
+                &mut wakeup_rx,
+                &mut active_toasts,
+                &mut next_toast_id,
+                &mut global_modals,
+                &mut app_context_menu,
+                &mut global_context_menu,
+                &mut job_scheduler,
+            )
+            .await;
+
+        // Save persisted state of apps still open at shutdown
+        registry.read().unwrap().save_all_state();
+
+        result
     }
 
     /// The main event loop.
@@ -545,6 +587,7 @@ impl Runtime {
             let mut root = self.build_root_element(
                 registry,
                 systems,
+                gx,
                 active_toasts,
                 global_modals,
                 app_context_menu,
@@ -561,7 +604,7 @@ impl Runtime {
                         // get_data_mut creates entry if not exists, so check if input exists first
                         if text_inputs.get_data(&element_id).is_some() {
                             let data = text_inputs.get_data_mut(&element_id);
-                            data.cursor = position.min(data.text.chars().count());
+                            data.cursor = position.min(tuidom::text::grapheme_count(&data.text));
                             data.clear_selection();
                         }
                     }
@@ -574,6 +617,13 @@ impl Runtime {
 
             // 9. Render (stores layout internally)
             terminal.render(&root)?;
+
+            // 9a. After a resize, restore scroll positions proportionally against
+            // the reflowed layout and redraw with them
+            if scroll.apply_reflow(terminal.layout()) {
+                enrich_elements(&mut root, focus, text_inputs, scroll);
+                terminal.render(&root)?;
+            }
             let t_render = Instant::now();
 
             // 9b. Dispatch on_layout handlers for elements that have them
@@ -708,7 +758,9 @@ impl Runtime {
                 )
             });
 
-            let has_tuidom_anims = terminal.has_active_animations();
+            // Don't drive animation frames while the terminal window is unfocused
+            let has_tuidom_anims = terminal.has_active_animations() && gx.is_terminal_focused();
+            let toast_animating = toast_animating && gx.is_terminal_focused();
             let timeout = if has_tuidom_anims || toast_animating {
                 log::trace!(
                     "[runtime] using animation_timeout: tuidom_anims={}, toast_animating={}",
@@ -878,12 +930,28 @@ impl Runtime {
             // 17. Process focus events (Tab navigation, focus-follows-mouse)
             let events = focus.process_events(&raw_events, &root, layout);
 
-            // 17a. Update cursor position from mouse move events
+            // 17a. Track terminal focus and let apps know, e.g. to refresh
+            // data when the user comes back
+            for event in &events {
+                let focused = match event {
+                    tuidom::Event::FocusGained => true,
+                    tuidom::Event::FocusLost => false,
+                    _ => continue,
+                };
+                if focused == gx.is_terminal_focused() {
+                    continue;
+                }
+                log::debug!("[runtime] Terminal focused: {}", focused);
+                gx.set_terminal_focused(focused);
+                gx.publish(TerminalFocusChanged { focused });
+            }
+
+            // 17b. Update cursor position from mouse move events
             if let Ok(mut cursor_state) = gx.cursor_state().write() {
                 cursor_state.process_events(&events);
             }
 
-            // 17b. Scroll focused element into view
+            // 17c. Scroll focused element into view
             for event in &events {
                 if let tuidom::Event::Focus { target } = event
                     && let Some(change) = scroll_to_element(&root, layout, scroll, target)
@@ -892,7 +960,7 @@ impl Runtime {
                 }
             }
 
-            // 17c. Update focused element rect in GlobalContext
+            // 17d. Update focused element rect in GlobalContext
             let focused_rect = focus.focused().and_then(|id| layout.get(id)).copied();
             gx.set_focused_element_rect(focused_rect);
 
@@ -1156,10 +1224,12 @@ impl Runtime {
     }
 
     /// Build the root element tree.
+    #[allow(clippy::too_many_arguments)]
     fn build_root_element(
         &self,
         registry: &Arc<RwLock<InstanceRegistry>>,
         systems: &[Box<dyn AnySystem>],
+        gx: &GlobalContext,
         active_toasts: &[ActiveToast],
         global_modals: &[Box<dyn AnyModal>],
         app_context_menu: &Option<crate::ContextMenuState>,
@@ -1169,37 +1239,46 @@ impl Runtime {
 
         let mut root = Element::col().width(Size::Fill).height(Size::Fill);
 
+        // Collect visible overlays in z-order (stable sort keeps registration order for ties)
+        let mut overlays: Vec<crate::Overlay> = systems
+            .iter()
+            .filter(|system| !gx.is_overlay_hidden(AnySystem::type_id(system.as_ref())))
+            .filter_map(|system| system.overlay())
+            .filter(|overlay| overlay.visible)
+            .collect();
+        overlays.sort_by_key(|overlay| overlay.z_index);
+
         // Collect edge overlays (top, bottom, left, right)
         let mut top_overlays: Vec<Element> = Vec::new();
         let mut bottom_overlays: Vec<Element> = Vec::new();
         let mut left_overlays: Vec<Element> = Vec::new();
         let mut right_overlays: Vec<Element> = Vec::new();
+        let mut absolute_overlays: Vec<Element> = Vec::new();
 
-        for system in systems {
-            if let Some(overlay) = system.overlay() {
-                match overlay.position {
-                    crate::system::OverlayPosition::Top { height } => {
-                        top_overlays.push(overlay.element.height(Size::Fixed(height)));
-                    }
-                    crate::system::OverlayPosition::Bottom { height } => {
-                        bottom_overlays.push(overlay.element.height(Size::Fixed(height)));
-                    }
-                    crate::system::OverlayPosition::Left { width } => {
-                        left_overlays.push(overlay.element.width(Size::Fixed(width)));
-                    }
-                    crate::system::OverlayPosition::Right { width } => {
-                        right_overlays.push(overlay.element.width(Size::Fixed(width)));
-                    }
-                    crate::system::OverlayPosition::Absolute { x, y, .. } => {
-                        // Absolute overlays rendered later
-                        root = root.child(
-                            overlay
-                                .element
-                                .position(Position::Absolute)
-                                .left(x as i16)
-                                .top(y as i16),
-                        );
-                    }
+        for overlay in overlays {
+            let element = overlay.element.click_through(overlay.click_through);
+            match overlay.position {
+                crate::system::OverlayPosition::Top { height } => {
+                    top_overlays.push(element.height(Size::Fixed(height)));
+                }
+                crate::system::OverlayPosition::Bottom { height } => {
+                    bottom_overlays.push(element.height(Size::Fixed(height)));
+                }
+                crate::system::OverlayPosition::Left { width } => {
+                    left_overlays.push(element.width(Size::Fixed(width)));
+                }
+                crate::system::OverlayPosition::Right { width } => {
+                    right_overlays.push(element.width(Size::Fixed(width)));
+                }
+                crate::system::OverlayPosition::Absolute { x, y, .. } => {
+                    // Absolute overlays are rendered on top of the app content
+                    absolute_overlays.push(
+                        element
+                            .position(Position::Absolute)
+                            .left(x as i16)
+                            .top(y as i16)
+                            .z_index(overlay.z_index),
+                    );
                 }
             }
         }
@@ -1243,6 +1322,11 @@ impl Runtime {
             root = root.child(overlay);
         }
 
+        // Add absolute overlays (after app content so they render above it)
+        for overlay in absolute_overlays {
+            root = root.child(overlay);
+        }
+
         // Add app modals (overlay with dim backdrop)
         // Each modal is rendered in order, stacking on top of each other
         {
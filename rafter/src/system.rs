@@ -44,15 +44,54 @@ impl OverlayPosition {
 }
 
 /// A system overlay with position and content.
+///
+/// Multiple systems can contribute overlays at the same time. `z_index`
+/// controls stacking between them, `click_through` lets mouse input reach
+/// whatever is beneath, and `visible` hides the overlay (including the space
+/// an edge overlay reserves) without unregistering the system.
 #[derive(Debug)]
 pub struct Overlay {
     pub position: OverlayPosition,
     pub element: Element,
+    /// Stacking order. Higher values render on top of lower ones.
+    pub z_index: i16,
+    /// When true, mouse input passes through the overlay.
+    pub click_through: bool,
+    /// When false, the overlay is not rendered.
+    pub visible: bool,
 }
 
 impl Overlay {
     pub fn new(position: OverlayPosition, element: Element) -> Self {
-        Self { position, element }
+        Self {
+            position,
+            element,
+            z_index: 0,
+            click_through: false,
+            visible: true,
+        }
+    }
+
+    /// Set the stacking order relative to other overlays.
+    ///
+    /// Absolute overlays with a higher z-index render on top. Edge overlays
+    /// on the same edge are laid out top to bottom (or left to right) in
+    /// ascending z-index order.
+    pub fn z_index(mut self, z_index: i16) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Let mouse input pass through the overlay to the content beneath.
+    pub fn click_through(mut self, click_through: bool) -> Self {
+        self.click_through = click_through;
+        self
+    }
+
+    /// Set whether the overlay is rendered.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
     }
 
     pub fn top(height: u16, element: Element) -> Self {
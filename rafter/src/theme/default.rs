@@ -76,6 +76,13 @@ pub struct CardColors {
     pub disabled: Color,
 }
 
+#[theme]
+pub struct BannerColors {
+    pub background: Color,
+    pub action: Color,
+    pub focused: Color,
+}
+
 #[theme]
 pub struct CollapsibleColors {
     pub focused: Color,
@@ -138,6 +145,8 @@ pub struct RafterTheme {
     #[group]
     pub card: CardColors,
     #[group]
+    pub banner: BannerColors,
+    #[group]
     pub collapsible: CollapsibleColors,
     #[group]
     pub autocomplete: AutocompleteColors,
@@ -229,6 +238,12 @@ pub fn default_theme() -> RafterTheme {
             disabled: Color::var("surface").darken(0.1),
         },
 
+        banner: BannerColors {
+            background: Color::var("elevated"),
+            action: Color::var("interact"),
+            focused: Color::var("elevated").lighten(0.1),
+        },
+
         collapsible: CollapsibleColors {
             focused: Color::var("surface").lighten(0.1),
         },
@@ -3,6 +3,10 @@
 mod default;
 
 pub use default::{
-    AutocompleteColors, ButtonColors, CardColors, CheckboxColors, CollapsibleColors, InputColors,
-    ListColors, RadioColors, RafterTheme, ScrollbarColors, SelectColors, TextColors, default_theme,
+    AutocompleteColors, BannerColors, ButtonColors, CardColors, CheckboxColors, CollapsibleColors,
+    InputColors, ListColors, RadioColors, RafterTheme, ScrollbarColors, SelectColors, TextColors,
+    default_theme,
 };
+
+#[doc(hidden)]
+pub use default::__rafter_theme_colors;
@@ -20,6 +20,20 @@
 //!     // Proceed with form submission
 //! }
 //! ```
+//!
+//! Forms can derive the same chain from field attributes instead:
+//!
+//! ```ignore
+//! #[derive(Validatable)]
+//! struct SignupForm {
+//!     #[validate(required, min_length = 3)]
+//!     username: Input,
+//!     #[validate(required, email)]
+//!     email: Input,
+//! }
+//!
+//! let result = form.validate();
+//! ```
 
 mod error_display;
 mod result;
@@ -30,3 +44,6 @@ pub use error_display::ErrorDisplay;
 pub use result::{FieldError, ValidationResult};
 pub use validatable::Validatable;
 pub use validator::{FieldBuilder, Validator};
+
+/// Derive macro for form structs, see `rafter_derive::Validatable`.
+pub use rafter_derive::Validatable;
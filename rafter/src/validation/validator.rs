@@ -176,22 +176,23 @@ where
     where
         W2::Value: Clone + Send + 'static,
     {
-        let validator = self.finalize();
+        let validator = self.finish();
         validator.field(widget, name)
     }
 
     /// Finalize and run all synchronous validations.
     pub fn validate(self) -> ValidationResult {
-        self.finalize().validate()
+        self.finish().validate()
     }
 
     /// Finalize and run all validations including async rules.
     pub async fn validate_async(self) -> ValidationResult {
-        self.finalize().validate_async().await
+        self.finish().validate_async().await
     }
 
-    /// Finalize this field and return the validator.
-    fn finalize(self) -> Validator {
+    /// Finish this field and return the validator, e.g. to add fields
+    /// conditionally.
+    pub fn finish(self) -> Validator {
         let widget_id = self.widget.widget_id();
         let name = self.name;
 
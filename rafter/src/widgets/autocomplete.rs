@@ -430,10 +430,10 @@ impl<'a, T: Clone + Eq + Hash + PartialEq + Send + Sync + ToString + 'static>
         let max_label_width = current
             .options
             .iter()
-            .map(|(_, label)| label.chars().count())
+            .map(|(_, label)| tuidom::text::display_width(label))
             .max()
             .unwrap_or(0)
-            .max(placeholder.chars().count());
+            .max(tuidom::text::display_width(&placeholder));
         let min_width = self.width.unwrap_or((max_label_width + 2) as u16);
 
         // Build text input element
@@ -0,0 +1,209 @@
+//! Banner widget - an inline, persistent message bar.
+
+use tuidom::{Color, Edges, Element, Size, Style, Transitions};
+
+use crate::{HandlerRegistry, WidgetHandlers};
+
+/// Banner severity variant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BannerVariant {
+    /// Neutral information: ℹ
+    #[default]
+    Info,
+    /// Positive outcome: ✓
+    Success,
+    /// Something needs attention: ⚠
+    Warning,
+    /// Something went wrong: ✗
+    Error,
+}
+
+impl BannerVariant {
+    /// Icon shown at the start of the banner.
+    pub fn icon(&self) -> &'static str {
+        match self {
+            BannerVariant::Info => "ℹ",
+            BannerVariant::Success => "✓",
+            BannerVariant::Warning => "⚠",
+            BannerVariant::Error => "✗",
+        }
+    }
+
+    /// Theme color used for the icon and accents.
+    pub fn color(&self) -> Color {
+        match self {
+            BannerVariant::Info => Color::var("info"),
+            BannerVariant::Success => Color::var("success"),
+            BannerVariant::Warning => Color::var("warning"),
+            BannerVariant::Error => Color::var("error"),
+        }
+    }
+}
+
+/// A banner widget builder.
+///
+/// Banners are stateless: they stay visible until the app stops rendering
+/// them. Unlike toasts they don't expire, which makes them suitable for
+/// persistent conditions such as "offline mode active".
+///
+/// # Handlers
+///
+/// - `on_action`: the action link was activated (requires `action`)
+/// - `on_dismiss`: the dismiss button was activated (requires `dismissible`)
+///
+/// # Example
+///
+/// ```ignore
+/// // In page! macro:
+/// if self.offline.get() {
+///     banner (message: "Offline mode active", variant: BannerVariant::Warning,
+///             action: "Reconnect", dismissible, id: "offline-banner")
+///         on_action: reconnect()
+///         on_dismiss: hide_offline_banner()
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Banner {
+    id: Option<String>,
+    title: Option<String>,
+    message: Option<String>,
+    variant: BannerVariant,
+    action: Option<String>,
+    dismissible: bool,
+    style: Option<Style>,
+    style_focused: Option<Style>,
+    transitions: Option<Transitions>,
+}
+
+impl Banner {
+    /// Create a new banner builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the banner id.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set a bold title shown before the message.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the banner message.
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Set the banner variant.
+    pub fn variant(mut self, variant: BannerVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Show an action link with the given label. Dispatches `on_action`.
+    pub fn action(mut self, label: impl Into<String>) -> Self {
+        self.action = Some(label.into());
+        self
+    }
+
+    /// Show a dismiss button. Dispatches `on_dismiss`.
+    pub fn dismissible(mut self, dismissible: bool) -> Self {
+        self.dismissible = dismissible;
+        self
+    }
+
+    /// Set the container style.
+    pub fn style(mut self, s: Style) -> Self {
+        self.style = Some(s);
+        self
+    }
+
+    /// Set the style of the action and dismiss controls when focused.
+    pub fn style_focused(mut self, s: Style) -> Self {
+        self.style_focused = Some(s);
+        self
+    }
+
+    /// Set transitions.
+    pub fn transitions(mut self, t: Transitions) -> Self {
+        self.transitions = Some(t);
+        self
+    }
+
+    /// Build the banner element.
+    ///
+    /// Registers `on_action` and `on_dismiss` on the corresponding controls.
+    pub fn build(self, registry: &HandlerRegistry, handlers: &WidgetHandlers) -> Element {
+        let id = self.id.unwrap_or_else(|| "banner".into());
+        let accent = self.variant.color();
+
+        let focused_style = Style::new()
+            .background(Color::var("banner.focused"))
+            .merge(&self.style_focused);
+
+        // Message: optional bold title followed by the text
+        let mut message = Element::row().gap(1).flex_grow(1);
+        if let Some(title) = &self.title {
+            message = message
+                .child(Element::text(title).style(Style::new().foreground(accent.clone()).bold()));
+        }
+        message = message.child(Element::text(self.message.unwrap_or_default()));
+
+        let mut elem = Element::row()
+            .id(&id)
+            .width(Size::Fill)
+            .gap(1)
+            .padding(Edges::symmetric(0, 1))
+            .child(Element::text(self.variant.icon()).style(Style::new().foreground(accent)))
+            .child(message);
+
+        if let Some(label) = &self.action {
+            let action_id = format!("{}-action", id);
+            elem = elem.child(
+                Element::text(label)
+                    .id(&action_id)
+                    .focusable(true)
+                    .clickable(true)
+                    .style(
+                        Style::new()
+                            .foreground(Color::var("banner.action"))
+                            .underline(),
+                    )
+                    .style_focused(focused_style.clone()),
+            );
+            if let Some(handler) = handlers.get("on_action") {
+                registry.register(&action_id, "on_activate", handler.clone());
+            }
+        }
+
+        if self.dismissible {
+            let dismiss_id = format!("{}-dismiss", id);
+            elem = elem.child(
+                Element::text("×")
+                    .id(&dismiss_id)
+                    .focusable(true)
+                    .clickable(true)
+                    .style(Style::new().foreground(Color::var("text.muted")))
+                    .style_focused(focused_style),
+            );
+            if let Some(handler) = handlers.get("on_dismiss") {
+                registry.register(&dismiss_id, "on_activate", handler.clone());
+            }
+        }
+
+        let style = Style::new()
+            .background(Color::var("banner.background"))
+            .merge(&self.style);
+        elem = elem.style(style);
+        if let Some(transitions) = self.transitions {
+            elem = elem.transitions(transitions);
+        }
+
+        elem
+    }
+}
@@ -10,15 +10,96 @@ pub enum CheckboxVariant {
     /// Large checkbox: [x] or [ ]
     #[default]
     Big,
-    /// Small checkbox: ▣ or □
+    /// Small checkbox: ◼ or ◻
     Small,
 }
 
+/// Tri-state checkbox value.
+///
+/// `Indeterminate` is typically used for "select all" headers over a
+/// partially selected list. Only the app sets it (e.g. with
+/// [`CheckState::from_counts`]); activating the checkbox toggles between
+/// `Checked` and `Unchecked`, and checks an indeterminate one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CheckState {
+    #[default]
+    Unchecked,
+    Checked,
+    Indeterminate,
+}
+
+impl CheckState {
+    /// Derive the state of a "select all" header from selection counts.
+    pub fn from_counts(selected: usize, total: usize) -> Self {
+        if selected == 0 {
+            CheckState::Unchecked
+        } else if selected >= total {
+            CheckState::Checked
+        } else {
+            CheckState::Indeterminate
+        }
+    }
+
+    /// Check if the state is `Checked`.
+    pub fn is_checked(&self) -> bool {
+        matches!(self, CheckState::Checked)
+    }
+
+    /// Check if the state is `Indeterminate`.
+    pub fn is_indeterminate(&self) -> bool {
+        matches!(self, CheckState::Indeterminate)
+    }
+}
+
+impl From<bool> for CheckState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
+/// Values that can back a checkbox.
+///
+/// Implemented for `bool` (two-state) and [`CheckState`] (tri-state).
+pub trait CheckboxValue: Clone + Send + Sync + 'static {
+    /// The state to display.
+    fn check_state(&self) -> CheckState;
+
+    /// The value after the user activates the checkbox.
+    fn toggled(&self) -> Self;
+}
+
+impl CheckboxValue for bool {
+    fn check_state(&self) -> CheckState {
+        CheckState::from(*self)
+    }
+
+    fn toggled(&self) -> Self {
+        !*self
+    }
+}
+
+impl CheckboxValue for CheckState {
+    fn check_state(&self) -> CheckState {
+        *self
+    }
+
+    fn toggled(&self) -> Self {
+        match self {
+            CheckState::Checked => CheckState::Unchecked,
+            CheckState::Unchecked | CheckState::Indeterminate => CheckState::Checked,
+        }
+    }
+}
+
 /// Typestate marker: checkbox needs a state reference.
 pub struct NeedsState;
 
 /// Typestate marker: checkbox has a state reference.
-pub struct HasState<'a>(&'a State<bool>);
+pub struct HasState<'a, V: CheckboxValue>(&'a State<V>);
 
 /// A checkbox widget builder.
 ///
@@ -31,6 +112,10 @@ pub struct HasState<'a>(&'a State<bool>);
 /// checkbox (state: self.agree, id: "agree", label: "I agree to terms", small)
 ///     style (fg: primary)
 ///     on_change: agreement_changed()
+///
+/// // Tri-state "select all" header backed by State<CheckState>:
+/// checkbox (state: self.select_all, id: "select-all", label: "All")
+///     on_change: select_all_changed()
 /// ```
 #[derive(Clone, Debug)]
 pub struct Checkbox<S = NeedsState> {
@@ -70,7 +155,9 @@ impl Checkbox<NeedsState> {
     }
 
     /// Set the state reference. Required before calling `build()`.
-    pub fn state(self, s: &State<bool>) -> Checkbox<HasState<'_>> {
+    ///
+    /// Accepts `State<bool>` or `State<CheckState>` for a tri-state checkbox.
+    pub fn state<V: CheckboxValue>(self, s: &State<V>) -> Checkbox<HasState<'_, V>> {
         Checkbox {
             state_marker: HasState(s),
             id: self.id,
@@ -154,31 +241,25 @@ impl<S> Checkbox<S> {
     }
 }
 
-impl<'a> Checkbox<HasState<'a>> {
+impl<'a, V: CheckboxValue> Checkbox<HasState<'a, V>> {
     /// Build the checkbox element.
     ///
     /// Registers the `on_change` handler if provided and not disabled.
+    /// `on_checked` or `on_unchecked` is dispatched after `on_change`
+    /// depending on the new state.
     pub fn build(self, registry: &HandlerRegistry, handlers: &WidgetHandlers) -> Element {
         let state = self.state_marker.0;
-        let checked = state.get();
+        let check_state = state.get().check_state();
         let id = self.id.clone().unwrap_or_else(|| "checkbox".into());
 
         // Build checkbox indicator text
-        let indicator = match self.variant {
-            CheckboxVariant::Big => {
-                if checked {
-                    "[x]"
-                } else {
-                    "[ ]"
-                }
-            }
-            CheckboxVariant::Small => {
-                if checked {
-                    "◼"
-                } else {
-                    "◻"
-                }
-            }
+        let indicator = match (self.variant, check_state) {
+            (CheckboxVariant::Big, CheckState::Checked) => "[x]",
+            (CheckboxVariant::Big, CheckState::Unchecked) => "[ ]",
+            (CheckboxVariant::Big, CheckState::Indeterminate) => "[-]",
+            (CheckboxVariant::Small, CheckState::Checked) => "◼",
+            (CheckboxVariant::Small, CheckState::Unchecked) => "◻",
+            (CheckboxVariant::Small, CheckState::Indeterminate) => "▣",
         };
 
         // Build the checkbox indicator element
@@ -223,27 +304,28 @@ impl<'a> Checkbox<HasState<'a>> {
 
         // Register toggle handler if not disabled
         if !self.disabled {
-            if let Some(on_change) = handlers.get("on_change").cloned() {
-                let state_clone = state.clone();
-                registry.register(
-                    &id,
-                    "on_activate",
-                    std::sync::Arc::new(move |hx| {
-                        state_clone.update(|v| *v = !*v);
-                        on_change(hx);
-                    }),
-                );
-            } else {
-                // Toggle without user callback
-                let state_clone = state.clone();
-                registry.register(
-                    &id,
-                    "on_activate",
-                    std::sync::Arc::new(move |_hx| {
-                        state_clone.update(|v| *v = !*v);
-                    }),
-                );
-            }
+            let state_clone = state.clone();
+            let on_change = handlers.get("on_change").cloned();
+            let on_checked = handlers.get("on_checked").cloned();
+            let on_unchecked = handlers.get("on_unchecked").cloned();
+            registry.register(
+                &id,
+                "on_activate",
+                std::sync::Arc::new(move |hx| {
+                    state_clone.update(|v| *v = v.toggled());
+                    if let Some(ref handler) = on_change {
+                        handler(hx);
+                    }
+                    let state_handler = if state_clone.get().check_state().is_checked() {
+                        &on_checked
+                    } else {
+                        &on_unchecked
+                    };
+                    if let Some(handler) = state_handler {
+                        handler(hx);
+                    }
+                }),
+            );
         }
 
         elem
@@ -5,8 +5,8 @@
 //!
 //! # Widget Categories
 //!
-//! - **Stateless**: `Text`, `Button`, `Card` - no internal state
-//! - **Stateful**: `Checkbox`, `Select`, `RadioGroup`, `Input` - use typestate
+//! - **Stateless**: `Text`, `Button`, `Card`, `Banner` - no internal state
+//! - **Stateful**: `Checkbox`, `Select`, `RadioGroup`, `Input`, `Popover` - use typestate
 //!   pattern to enforce `state()` is called before `build()`
 //!
 //! # Usage
@@ -29,6 +29,7 @@
 //! builder method calls for each prop.
 
 pub mod autocomplete;
+pub mod banner;
 pub mod button;
 pub mod card;
 pub mod checkbox;
@@ -37,6 +38,7 @@ pub mod date_picker;
 pub mod input;
 pub mod list;
 pub mod number_input;
+pub mod popover;
 pub mod radio;
 pub mod scroll;
 pub mod select;
@@ -47,14 +49,16 @@ pub mod tree;
 pub mod virtual_scroller;
 
 pub use autocomplete::{Autocomplete, AutocompleteState};
+pub use banner::{Banner, BannerVariant};
 pub use button::Button;
 pub use card::Card;
-pub use checkbox::{Checkbox, CheckboxVariant};
+pub use checkbox::{CheckState, Checkbox, CheckboxValue, CheckboxVariant};
 pub use collapsible::Collapsible;
 pub use date_picker::{DatePicker, DatePickerState};
 pub use input::Input;
 pub use list::{List, ListItem, ListState};
 pub use number_input::{NumberInput, NumberInputState};
+pub use popover::Popover;
 pub use radio::{RadioGroup, RadioState};
 pub use scroll::{Orientation, ScrollRequest, ScrollState, Scrollbar, ScrollbarStyle};
 pub use select::{Select, SelectState};
@@ -0,0 +1,206 @@
+//! Popover widget - an overlay anchored to another widget.
+
+use std::sync::Arc;
+
+use tuidom::{Color, Element, Placement, Position, Style, Transitions};
+
+use crate::{HandlerRegistry, State, WidgetHandlers};
+
+/// Typestate marker: popover needs a state reference.
+pub struct NeedsState;
+
+/// Typestate marker: popover has a state reference.
+pub struct HasState<'a>(&'a State<bool>);
+
+/// A popover widget builder.
+///
+/// Renders its children in an overlay positioned next to an anchor element
+/// (above/below/left/right). If the preferred side would overflow the screen
+/// the popover flips to the opposite side. The `state` controls whether the
+/// popover is open; clicking outside the popover closes it.
+///
+/// # Handlers
+///
+/// - `on_dismiss`: the popover was closed by clicking outside of it
+///
+/// # Example
+///
+/// ```ignore
+/// // In page! macro:
+/// button (label: "More", id: "more-btn") on_activate: toggle_more()
+/// popover (state: self.more_open, anchor: "more-btn", placement: Placement::Below, id: "more")
+///     on_dismiss: more_closed()
+/// {
+///     button (label: "Rename", id: "rename") on_activate: rename()
+///     button (label: "Delete", id: "delete") on_activate: delete()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Popover<S = NeedsState> {
+    state_marker: S,
+    id: Option<String>,
+    anchor: Option<String>,
+    placement: Placement,
+    offset: u16,
+    children: Vec<Element>,
+    style: Option<Style>,
+    transitions: Option<Transitions>,
+}
+
+impl Default for Popover<NeedsState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Popover<NeedsState> {
+    /// Create a new popover builder.
+    pub fn new() -> Self {
+        Self {
+            state_marker: NeedsState,
+            id: None,
+            anchor: None,
+            placement: Placement::Below,
+            offset: 0,
+            children: Vec::new(),
+            style: None,
+            transitions: None,
+        }
+    }
+
+    /// Set the open state reference. Required before calling `build()`.
+    pub fn state(self, s: &State<bool>) -> Popover<HasState<'_>> {
+        Popover {
+            state_marker: HasState(s),
+            id: self.id,
+            anchor: self.anchor,
+            placement: self.placement,
+            offset: self.offset,
+            children: self.children,
+            style: self.style,
+            transitions: self.transitions,
+        }
+    }
+}
+
+impl<S> Popover<S> {
+    /// Set the popover id.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the id of the element to position against.
+    ///
+    /// The anchor must be rendered before the popover.
+    pub fn anchor(mut self, anchor: impl Into<String>) -> Self {
+        self.anchor = Some(anchor.into());
+        self
+    }
+
+    /// Set the preferred side of the anchor.
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Set the gap between the anchor and the popover, in cells.
+    pub fn offset(mut self, offset: u16) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set the popover content.
+    pub fn children(mut self, children: Vec<Element>) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Add a single child to the popover content.
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Set the popover panel style.
+    pub fn style(mut self, s: Style) -> Self {
+        self.style = Some(s);
+        self
+    }
+
+    /// Set transitions.
+    pub fn transitions(mut self, t: Transitions) -> Self {
+        self.transitions = Some(t);
+        self
+    }
+}
+
+impl<'a> Popover<HasState<'a>> {
+    /// Build the popover element.
+    ///
+    /// When closed this produces an empty out-of-flow element. When open it
+    /// registers a dismiss handler on the full-screen backdrop.
+    pub fn build(self, registry: &HandlerRegistry, handlers: &WidgetHandlers) -> Element {
+        let state = self.state_marker.0;
+        let id = self.id.unwrap_or_else(|| "popover".into());
+
+        if !state.get() {
+            return Element::box_().id(&id).position(Position::Absolute);
+        }
+
+        let mut panel = Element::col()
+            .id(format!("{}-panel", id))
+            .position(Position::Fixed)
+            .z_index(1)
+            .style(
+                Style::new()
+                    .background(Color::var("elevated"))
+                    .merge(&self.style),
+            )
+            .children(self.children);
+        if let Some(anchor) = &self.anchor {
+            panel = panel
+                .anchor(anchor, self.placement)
+                .anchor_offset(self.offset);
+        }
+        if let Some(transitions) = self.transitions {
+            panel = panel.transitions(transitions);
+        }
+
+        // Full-screen transparent backdrop for dismiss on outside click
+        let backdrop_id = format!("{}-backdrop", id);
+        let backdrop = Element::box_()
+            .id(&backdrop_id)
+            .position(Position::Fixed)
+            .left(0)
+            .top(0)
+            .right(0)
+            .bottom(0)
+            .clickable(true);
+
+        let state_clone = state.clone();
+        let on_dismiss = handlers.get("on_dismiss").cloned();
+        registry.register(
+            &backdrop_id,
+            "on_activate",
+            Arc::new(move |hx| {
+                state_clone.set(false);
+                if let Some(ref handler) = on_dismiss {
+                    handler(hx);
+                }
+            }),
+        );
+
+        // interaction_scope keeps focus and clicks inside the popover layer
+        Element::box_()
+            .id(&id)
+            .position(Position::Fixed)
+            .left(0)
+            .top(0)
+            .right(0)
+            .bottom(0)
+            .interaction_scope(true)
+            .child(backdrop)
+            .child(panel)
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use tuidom::{Color, Element, Style, Transitions};
 
+use super::scroll::Orientation;
 use crate::state::State;
 use crate::{HandlerRegistry, WidgetHandlers};
 
@@ -22,7 +23,7 @@ use crate::{HandlerRegistry, WidgetHandlers};
 ///     ("low".to_string(), "Low"),
 ///     ("medium".to_string(), "Medium"),
 ///     ("high".to_string(), "High"),
-/// ]));
+/// ]).with_disabled("high".to_string()));
 /// ```
 #[derive(Clone, Debug)]
 pub struct RadioState<T: Clone> {
@@ -30,6 +31,8 @@ pub struct RadioState<T: Clone> {
     pub value: Option<T>,
     /// Available options as (value, label) pairs.
     pub options: Vec<(T, String)>,
+    /// Options that are shown but cannot be selected.
+    pub disabled: Vec<T>,
 }
 
 impl<T: Clone> Default for RadioState<T> {
@@ -37,6 +40,7 @@ impl<T: Clone> Default for RadioState<T> {
         Self {
             value: None,
             options: Vec::new(),
+            disabled: Vec::new(),
         }
     }
 }
@@ -47,6 +51,7 @@ impl<T: Clone> RadioState<T> {
         Self {
             value: None,
             options: options.into_iter().map(|(v, l)| (v, l.into())).collect(),
+            disabled: Vec::new(),
         }
     }
 
@@ -57,6 +62,31 @@ impl<T: Clone> RadioState<T> {
     }
 }
 
+impl<T: Clone + PartialEq> RadioState<T> {
+    /// Mark an option as disabled.
+    pub fn with_disabled(mut self, value: T) -> Self {
+        self.set_disabled(value, true);
+        self
+    }
+
+    /// Enable or disable a single option.
+    pub fn set_disabled(&mut self, value: T, disabled: bool) {
+        let existing = self.disabled.iter().position(|v| *v == value);
+        match (existing, disabled) {
+            (None, true) => self.disabled.push(value),
+            (Some(i), false) => {
+                self.disabled.remove(i);
+            }
+            _ => {}
+        }
+    }
+
+    /// Check whether an option is disabled.
+    pub fn is_disabled(&self, value: &T) -> bool {
+        self.disabled.contains(value)
+    }
+}
+
 /// Typestate marker: radio group needs a state reference.
 pub struct NeedsState;
 
@@ -74,11 +104,16 @@ pub struct HasState<'a, T: Clone>(&'a State<RadioState<T>>);
 /// radio_group (state: self.priority, id: "priority")
 ///     style (bg: surface)
 ///     on_change: priority_changed()
+///
+/// // Horizontal segmented control where arrow keys cycle through options:
+/// radio_group (state: self.view_mode, id: "view-mode", horizontal, wrap_around)
 /// ```
 #[derive(Clone, Debug)]
 pub struct RadioGroup<S = NeedsState> {
     state_marker: S,
     id: Option<String>,
+    orientation: Orientation,
+    wrap_around: bool,
     disabled: bool,
     style: Option<Style>,
     style_focused: Option<Style>,
@@ -100,6 +135,8 @@ impl RadioGroup<NeedsState> {
         Self {
             state_marker: NeedsState,
             id: None,
+            orientation: Orientation::Vertical,
+            wrap_around: false,
             disabled: false,
             style: None,
             style_focused: None,
@@ -118,6 +155,8 @@ impl RadioGroup<NeedsState> {
         RadioGroup {
             state_marker: HasState(s),
             id: self.id,
+            orientation: self.orientation,
+            wrap_around: self.wrap_around,
             disabled: self.disabled,
             style: self.style,
             style_focused: self.style_focused,
@@ -136,6 +175,30 @@ impl<S> RadioGroup<S> {
         self
     }
 
+    /// Set the layout direction of the options.
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Lay the options out in a single row (segmented control).
+    pub fn horizontal(mut self, horizontal: bool) -> Self {
+        self.orientation = if horizontal {
+            Orientation::Horizontal
+        } else {
+            Orientation::Vertical
+        };
+        self
+    }
+
+    /// Wrap arrow-key navigation from the last option back to the first.
+    ///
+    /// Uses Left/Right for horizontal groups and Up/Down for vertical ones.
+    pub fn wrap_around(mut self, wrap_around: bool) -> Self {
+        self.wrap_around = wrap_around;
+        self
+    }
+
     /// Mark the radio group as disabled.
     pub fn disabled(mut self, disabled: bool) -> Self {
         self.disabled = disabled;
@@ -182,13 +245,17 @@ impl<S> RadioGroup<S> {
 impl<'a, T: Clone + PartialEq + Send + Sync + 'static> RadioGroup<HasState<'a, T>> {
     /// Build the radio group element.
     ///
-    /// Registers handlers for each option if not disabled.
+    /// Registers handlers for each option that is not disabled.
     pub fn build(self, registry: &HandlerRegistry, handlers: &WidgetHandlers) -> Element {
         let state = self.state_marker.0;
         let current = state.get();
         let id = self.id.clone().unwrap_or_else(|| "radio".into());
 
-        let mut container = Element::col();
+        let mut container = match self.orientation {
+            Orientation::Vertical => Element::col(),
+            Orientation::Horizontal => Element::row().gap(2),
+        }
+        .focus_wrap(self.wrap_around);
 
         if let Some(style) = self.style.clone() {
             container = container.style(style);
@@ -200,6 +267,7 @@ impl<'a, T: Clone + PartialEq + Send + Sync + 'static> RadioGroup<HasState<'a, T
         for (i, (value, label)) in current.options.iter().enumerate() {
             let opt_id = format!("{}-opt-{}", id, i);
             let is_selected = current.value.as_ref() == Some(value);
+            let disabled = self.disabled || current.is_disabled(value);
 
             // Radio indicator: ● for selected, ○ for unselected
             let indicator = if is_selected { "●" } else { "○" };
@@ -217,9 +285,9 @@ impl<'a, T: Clone + PartialEq + Send + Sync + 'static> RadioGroup<HasState<'a, T
             let mut opt_row = Element::row()
                 .id(&opt_id)
                 .gap(1)
-                .focusable(!self.disabled)
-                .clickable(!self.disabled)
-                .disabled(self.disabled)
+                .focusable(!disabled)
+                .clickable(!disabled)
+                .disabled(disabled)
                 .children(vec![indicator_elem, label_elem]);
 
             let focused_style = Style::new()
@@ -234,7 +302,7 @@ impl<'a, T: Clone + PartialEq + Send + Sync + 'static> RadioGroup<HasState<'a, T
             container = container.child(opt_row);
 
             // Register option handler
-            if !self.disabled {
+            if !disabled {
                 let state_clone = state.clone();
                 let value_clone = value.clone();
                 let on_change = handlers.get("on_change").cloned();
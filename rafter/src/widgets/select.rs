@@ -347,10 +347,10 @@ impl<'a, T: Clone + Eq + Hash + PartialEq + Send + Sync + 'static> Select<HasSta
             let max_label_width = current
                 .options
                 .iter()
-                .map(|(_, label)| label.chars().count())
+                .map(|(_, label)| tuidom::text::display_width(label))
                 .max()
                 .unwrap_or(0)
-                .max(placeholder.chars().count());
+                .max(tuidom::text::display_width(&placeholder));
             // +2 for arrow and gap
             (max_label_width + 2) as u16
         };
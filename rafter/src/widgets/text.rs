@@ -14,11 +14,19 @@ use crate::{HandlerRegistry, WidgetHandlers};
 /// // In page! macro:
 /// text (content: "Hello world", id: "greeting")
 ///     style (fg: primary)
+///
+/// // Clickable URL in terminals with hyperlink support:
+/// text (content: record.url.clone(), link: record.url.clone())
+///
+/// // Colored output from an external tool:
+/// text (content: self.log.get(), ansi: true)
 /// ```
 #[derive(Clone, Debug, Default)]
 pub struct Text {
     content: Option<String>,
     id: Option<String>,
+    link: Option<String>,
+    ansi: bool,
     style: Option<Style>,
     transitions: Option<Transitions>,
 }
@@ -41,6 +49,18 @@ impl Text {
         self
     }
 
+    /// Make the text a hyperlink to `url`.
+    pub fn link(mut self, url: impl Into<String>) -> Self {
+        self.link = Some(url.into());
+        self
+    }
+
+    /// Interpret SGR escape sequences in the content as styling.
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
     /// Set the style.
     pub fn style(mut self, style: Style) -> Self {
         self.style = Some(style);
@@ -64,6 +84,12 @@ impl Text {
         if let Some(id) = self.id {
             elem = elem.id(id);
         }
+        if let Some(link) = self.link {
+            elem = elem.link(link);
+        }
+        if self.ansi {
+            elem = elem.ansi(true);
+        }
         if let Some(style) = self.style {
             elem = elem.style(style);
         }
@@ -0,0 +1,24 @@
+use rafter::widgets::{CheckState, CheckboxValue};
+
+#[test]
+fn test_check_state_toggle() {
+    assert_eq!(CheckState::Unchecked.toggled(), CheckState::Checked);
+    assert_eq!(CheckState::Checked.toggled(), CheckState::Unchecked);
+    // Activating an indeterminate checkbox checks it
+    assert_eq!(CheckState::Indeterminate.toggled(), CheckState::Checked);
+
+    // The user can't make a checkbox indeterminate
+    let mut state = CheckState::Indeterminate;
+    for _ in 0..4 {
+        state = state.toggled();
+        assert!(!state.is_indeterminate());
+    }
+}
+
+#[test]
+fn test_check_state_from_counts() {
+    assert_eq!(CheckState::from_counts(0, 3), CheckState::Unchecked);
+    assert_eq!(CheckState::from_counts(2, 3), CheckState::Indeterminate);
+    assert_eq!(CheckState::from_counts(3, 3), CheckState::Checked);
+    assert_eq!(CheckState::from_counts(0, 0), CheckState::Unchecked);
+}
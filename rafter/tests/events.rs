@@ -0,0 +1,98 @@
+//! Tests for typed event and request handlers on systems and apps.
+
+use std::any::TypeId;
+
+use rafter::page;
+use rafter::prelude::*;
+use rafter::{FocusChanged, TerminalFocusChanged};
+
+#[derive(Event, Clone)]
+struct Refreshed;
+
+#[derive(Event, Clone)]
+struct Renamed {
+    #[allow(dead_code)]
+    name: String,
+}
+
+#[derive(Request)]
+#[response(u32)]
+struct Count;
+
+#[derive(Request)]
+#[response(bool)]
+struct IsIdle;
+
+#[system]
+struct Tracker {
+    seen: u32,
+}
+
+#[system_impl]
+impl Tracker {
+    #[on_event(Refreshed)]
+    async fn refreshed(&self) {}
+
+    #[on_event(Renamed)]
+    async fn renamed(&self, _event: Renamed, _gx: &GlobalContext) {}
+
+    #[on_request(Count)]
+    async fn count(&self) -> u32 {
+        0
+    }
+
+    #[request_handler]
+    async fn is_idle(&self, _request: IsIdle) -> bool {
+        true
+    }
+}
+
+#[app]
+struct Counter {
+    value: u32,
+}
+
+#[app_impl]
+impl Counter {
+    #[on_event(Refreshed)]
+    async fn refreshed(&self, _cx: &AppContext) {}
+
+    #[on_event(TerminalFocusChanged)]
+    async fn terminal_focus_changed(&self, _event: TerminalFocusChanged) {}
+
+    #[on_request(Count)]
+    async fn count(&self, _request: Count) -> u32 {
+        1
+    }
+
+    fn element(&self) -> Element {
+        page! { text (content: "counter") }
+    }
+}
+
+#[test]
+fn test_system_declared_event_handlers() {
+    let tracker = Tracker::default();
+    assert!(tracker.has_event_handler(TypeId::of::<Refreshed>()));
+    assert!(tracker.has_event_handler(TypeId::of::<Renamed>()));
+}
+
+#[test]
+fn test_system_declared_and_inferred_request_handlers() {
+    let tracker = Tracker::default();
+    assert!(tracker.has_request_handler(TypeId::of::<Count>()));
+    assert!(tracker.has_request_handler(TypeId::of::<IsIdle>()));
+    assert!(!tracker.has_event_handler(TypeId::of::<Count>()));
+}
+
+#[test]
+fn test_app_declared_handlers() {
+    let counter = Counter::new(0);
+    assert!(counter.has_event_handler(TypeId::of::<Refreshed>()));
+    assert!(!counter.has_event_handler(TypeId::of::<Renamed>()));
+    assert!(counter.has_event_handler(TypeId::of::<TerminalFocusChanged>()));
+    // Terminal focus doesn't reuse the instance focus event
+    assert!(!counter.has_event_handler(TypeId::of::<FocusChanged>()));
+    assert!(counter.has_request_handler(TypeId::of::<Count>()));
+    assert!(!counter.has_request_handler(TypeId::of::<IsIdle>()));
+}
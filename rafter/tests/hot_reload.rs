@@ -0,0 +1,139 @@
+//! Tests for page templates and hot reload.
+
+use std::sync::Arc;
+
+use rafter::element;
+use rafter::hot_reload::{ThemeFile, ThemeFileError};
+use rafter::prelude::*;
+use tuidom::{Color, Content, Edges, EmptyTheme, Theme};
+
+fn children(element: &Element) -> &[Element] {
+    match &element.content {
+        Content::Children(children) => children,
+        _ => &[],
+    }
+}
+
+#[test]
+fn test_template_compiles_into_page() {
+    let names = ["Ada", "Grace"];
+    let names = names.iter().map(|name| name.to_string());
+
+    let page = element! { #![template("tests/fixtures/greeting.page")] };
+
+    assert_eq!(page.padding, Edges::all(1));
+    assert_eq!(page.gap, 1);
+    assert_eq!(children(&page).len(), 3);
+}
+
+/// Write a template file unique to this test process.
+fn write_template(name: &str, source: &str) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "rafter-hot-reload-{}-{}.page",
+        std::process::id(),
+        name
+    ));
+    std::fs::write(&path, source).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// Stand-in for the compiled page: a column holding the holes.
+fn compiled(holes: &mut [Vec<Element>]) -> Element {
+    Element::col()
+        .id("compiled")
+        .children(holes.iter_mut().flat_map(std::mem::take))
+}
+
+fn items() -> Vec<Vec<Element>> {
+    vec![vec![Element::box_().id("a"), Element::box_().id("b")]]
+}
+
+const SOURCE: &str = r#"
+column (id: "root", padding: 1) {
+    text (content: "Title")
+    ...items
+}
+"#;
+
+#[test]
+fn test_unchanged_template_uses_compiled_page() {
+    let path = write_template("unchanged", SOURCE);
+
+    let page = rafter::hot_reload::page(&path, SOURCE, items(), compiled);
+
+    assert_eq!(page.id, "compiled");
+}
+
+#[test]
+fn test_edited_template_is_interpreted() {
+    let path = write_template(
+        "edited",
+        r##"
+        column (id: "root", padding: (1, 2), gap: 1) style (bg: "#ff0000") {
+            ...items
+            text (content: "Title") style (bold)
+        }
+        "##,
+    );
+
+    let page = rafter::hot_reload::page(&path, SOURCE, items(), compiled);
+
+    assert_eq!(page.id, "root");
+    assert_eq!(page.padding, Edges::new(1, 2, 1, 2));
+    assert_eq!(page.gap, 1);
+    assert_eq!(page.style.background, Some(Color::rgb(255, 0, 0)));
+    let ids: Vec<_> = children(&page).iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids[..2], ["a", "b"]);
+    assert_eq!(ids.len(), 3);
+}
+
+#[test]
+fn test_changed_holes_fall_back_to_compiled_page() {
+    let path = write_template(
+        "holes",
+        r#"
+        column (id: "root", padding: 2) {
+            text (content: "Title")
+            ...other_items
+        }
+        "#,
+    );
+
+    let page = rafter::hot_reload::page(&path, SOURCE, items(), compiled);
+
+    assert_eq!(page.id, "compiled");
+    assert_eq!(children(&page).len(), 2);
+}
+
+#[test]
+fn test_invalid_template_falls_back_to_compiled_page() {
+    let path = write_template("invalid", "column (padding: 2 {");
+
+    let page = rafter::hot_reload::page(&path, SOURCE, items(), compiled);
+
+    assert_eq!(page.id, "compiled");
+}
+
+#[test]
+fn test_theme_file_overrides_base() {
+    let source = "
+        // Brand colors
+        primary = #ff0000
+        primary.hover = primary | lighten(0.1)
+    ";
+    let theme = ThemeFile::parse(source, Arc::new(EmptyTheme)).unwrap();
+
+    assert_eq!(theme.resolve("primary"), Some(&Color::rgb(255, 0, 0)));
+    assert!(theme.resolve("primary.hover").is_some());
+    assert_eq!(theme.resolve("secondary"), None);
+}
+
+#[test]
+fn test_theme_file_reports_line() {
+    let source = "primary = #ff0000\nsecondary #00ff00\n";
+    let error = ThemeFile::parse(source, Arc::new(EmptyTheme))
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, ThemeFileError::Parse { line: 2, .. }));
+}
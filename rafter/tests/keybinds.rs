@@ -0,0 +1,100 @@
+use rafter::keybinds;
+use rafter::{HandlerId, KeyCombo, SequenceMatch, parse_key_string};
+use tuidom::Key;
+
+fn combo(s: &str) -> KeyCombo {
+    parse_key_string(s).unwrap().remove(0)
+}
+
+fn sequence(s: &str) -> Vec<KeyCombo> {
+    parse_key_string(s).unwrap()
+}
+
+#[test]
+fn test_parse_chord_sequence() {
+    let keys = sequence("ctrl+k ctrl+s");
+    assert_eq!(
+        keys,
+        vec![
+            KeyCombo::key(Key::Char('k')).ctrl(),
+            KeyCombo::key(Key::Char('s')).ctrl(),
+        ]
+    );
+    assert_eq!(sequence("g g"), sequence("gg"));
+    assert_eq!(sequence("space"), vec![KeyCombo::key(Key::Char(' '))]);
+}
+
+#[test]
+fn test_keybinds_aliases_are_one_binding() {
+    let binds = keybinds! {
+        "q" | "escape" => quit: "Quit the application",
+        "ctrl+s" => save,
+    };
+
+    let infos = binds.infos();
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].keys.as_deref(), Some("q"));
+    assert_eq!(infos[0].aliases, vec!["escape".to_string()]);
+    assert_eq!(
+        infos[0].description.as_deref(),
+        Some("Quit the application")
+    );
+    assert_eq!(infos[1].description, None);
+
+    assert_eq!(
+        binds.get_single(&combo("escape"), None),
+        Some(&HandlerId::new("quit"))
+    );
+    assert_eq!(
+        binds.get_single(&combo("q"), None),
+        Some(&HandlerId::new("quit"))
+    );
+}
+
+#[test]
+fn test_keybinds_sequences() {
+    let binds = keybinds! {
+        ["ctrl+k", "ctrl+s"] => save_all,
+        "g g" => top,
+        "g" => go,
+    };
+
+    let ctrl_k = sequence("ctrl+k");
+    assert_eq!(binds.match_sequence(&ctrl_k, None), SequenceMatch::Pending);
+    assert_eq!(
+        binds.match_sequence(&sequence("ctrl+k ctrl+s"), None),
+        SequenceMatch::Matched(&HandlerId::new("save_all"))
+    );
+    assert_eq!(
+        binds.match_sequence(&sequence("ctrl+k x"), None),
+        SequenceMatch::None
+    );
+    // A complete match wins over a longer sequence with the same start
+    assert_eq!(
+        binds.match_sequence(&sequence("g"), None),
+        SequenceMatch::Matched(&HandlerId::new("go"))
+    );
+    assert_eq!(
+        binds.match_sequence(&sequence("g g"), None),
+        SequenceMatch::Matched(&HandlerId::new("top"))
+    );
+}
+
+#[test]
+fn test_override_drops_aliases_and_reset_restores_them() {
+    let mut binds = keybinds! {
+        "q" | "escape" => quit,
+    };
+
+    binds.override_keybind("quit", "ctrl+q").unwrap();
+    assert_eq!(binds.get_single(&combo("escape"), None), None);
+    assert!(binds.get_single(&combo("ctrl+q"), None).is_some());
+
+    binds.reset_keybind("quit").unwrap();
+    assert!(binds.get_single(&combo("escape"), None).is_some());
+    assert_eq!(binds.get_single(&combo("ctrl+q"), None), None);
+
+    binds.disable_keybind("quit").unwrap();
+    assert_eq!(binds.get_single(&combo("escape"), None), None);
+    assert!(binds.infos()[0].aliases.is_empty());
+}
@@ -0,0 +1,112 @@
+//! Tests for `#[app(persist)]` state persistence.
+
+use rafter::page;
+use rafter::prelude::*;
+use rafter::{FilePersistStore, MemoryPersistStore, PersistError, PersistStore};
+
+#[app(persist, default)]
+struct Explorer {
+    #[persist]
+    query: String,
+    #[persist]
+    columns: Vec<String>,
+    results: Vec<String>,
+}
+
+#[app_impl]
+impl Explorer {
+    fn element(&self) -> Element {
+        page! { text (content: "explorer") }
+    }
+}
+
+#[app]
+struct Scratch {
+    note: String,
+}
+
+#[app_impl]
+impl Scratch {
+    fn element(&self) -> Element {
+        page! { text (content: "scratch") }
+    }
+}
+
+#[test]
+fn test_persisted_fields_round_trip() {
+    let app = Explorer::default();
+    app.query.set("accounts".into());
+    app.columns.set(vec!["name".into(), "city".into()]);
+    app.results.set(vec!["Contoso".into()]);
+
+    let data = app.as_persistent().unwrap().save_state().unwrap();
+
+    let restored = Explorer::default();
+    restored
+        .as_persistent()
+        .unwrap()
+        .restore_state(&data)
+        .unwrap();
+    assert_eq!(restored.query.get(), "accounts");
+    assert_eq!(restored.columns.get(), ["name", "city"]);
+    assert!(restored.results.get().is_empty());
+}
+
+#[test]
+fn test_missing_fields_keep_current_value() {
+    let app = Explorer::default();
+    app.query.set("contacts".into());
+
+    app.as_persistent()
+        .unwrap()
+        .restore_state(r#"{"columns": ["email"]}"#)
+        .unwrap();
+
+    assert_eq!(app.query.get(), "contacts");
+    assert_eq!(app.columns.get(), ["email"]);
+}
+
+#[test]
+fn test_mismatched_field_is_an_error() {
+    let app = Explorer::default();
+
+    let error = app
+        .as_persistent()
+        .unwrap()
+        .restore_state(r#"{"query": 42}"#)
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        PersistError::Restore { field: "query", .. }
+    ));
+}
+
+#[test]
+fn test_apps_without_persist_have_no_state() {
+    assert!(Scratch::new(String::new()).as_persistent().is_none());
+}
+
+#[test]
+fn test_memory_store() {
+    let store = MemoryPersistStore::new();
+    assert!(store.load("Explorer").unwrap().is_none());
+
+    store.save("Explorer", "{}").unwrap();
+    assert_eq!(store.load("Explorer").unwrap().as_deref(), Some("{}"));
+}
+
+#[test]
+fn test_file_store() {
+    let dir = std::env::temp_dir().join(format!("rafter-persist-{}", std::process::id()));
+    let store = FilePersistStore::new(&dir);
+    assert!(store.load("Explorer").unwrap().is_none());
+
+    store.save("Explorer", r#"{"query":"accounts"}"#).unwrap();
+    assert_eq!(
+        store.load("Explorer").unwrap().as_deref(),
+        Some(r#"{"query":"accounts"}"#)
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
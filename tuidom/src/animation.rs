@@ -663,8 +663,9 @@ fn lerp_color(from: &Color, to: &Color, t: f32) -> Color {
         dh += 360.0;
     }
     let h = (from_h + dh * t).rem_euclid(360.0);
+    let a = from.opacity() + (to.opacity() - from.opacity()) * t;
 
-    Color::oklch(l, c, h)
+    Color::oklcha(l, c, h, a)
 }
 
 fn color_to_oklch(color: &Color) -> (f32, f32, f32) {
@@ -3,21 +3,32 @@ use crate::types::{Oklch, TextStyle};
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Cell {
     pub char: char,
+    /// Multi-codepoint grapheme cluster shown instead of `char` (emoji
+    /// sequences, combining marks), resolved through
+    /// [`Buffer::grapheme`](super::Buffer::grapheme).
+    pub cluster: Option<u64>,
     pub fg: Oklch,
     /// Background color. `None` means transparent (use terminal default).
     pub bg: Option<Oklch>,
     pub style: TextStyle,
+    /// Underline color. `None` means the terminal draws it in the foreground color.
+    pub underline_color: Option<Oklch>,
     pub wide_continuation: bool,
+    /// Hyperlink target, resolved through [`Buffer::link`](super::Buffer::link).
+    pub link: Option<u64>,
 }
 
 impl Default for Cell {
     fn default() -> Self {
         Self {
             char: ' ',
+            cluster: None,
             fg: Oklch::new(1.0, 0.0, 0.0), // white
             bg: None,                      // transparent
             style: TextStyle::new(),
+            underline_color: None,
             wide_continuation: false,
+            link: None,
         }
     }
 }
@@ -30,6 +41,11 @@ impl Cell {
         }
     }
 
+    pub fn with_cluster(mut self, cluster: Option<u64>) -> Self {
+        self.cluster = cluster;
+        self
+    }
+
     pub fn with_fg(mut self, fg: Oklch) -> Self {
         self.fg = fg;
         self
@@ -44,4 +60,14 @@ impl Cell {
         self.style = style;
         self
     }
+
+    pub fn with_underline_color(mut self, color: Option<Oklch>) -> Self {
+        self.underline_color = color;
+        self
+    }
+
+    pub fn with_link(mut self, link: Option<u64>) -> Self {
+        self.link = link;
+        self
+    }
 }
@@ -1,10 +1,21 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use super::Cell;
+use crate::image::ImagePlacement;
+use crate::layout::Rect;
 
 #[derive(Debug, Clone)]
 pub struct Buffer {
     width: u16,
     height: u16,
     cells: Vec<Cell>,
+    /// Images drawn this frame, in render order.
+    images: Vec<ImagePlacement>,
+    /// Hyperlink targets referenced by cells this frame.
+    links: HashMap<u64, String>,
+    /// Grapheme clusters referenced by cells this frame.
+    graphemes: HashMap<u64, String>,
 }
 
 impl Buffer {
@@ -14,6 +25,9 @@ impl Buffer {
             width,
             height,
             cells,
+            images: Vec::new(),
+            links: HashMap::new(),
+            graphemes: HashMap::new(),
         }
     }
 
@@ -70,5 +84,92 @@ impl Buffer {
         for cell in &mut self.cells {
             *cell = Cell::default();
         }
+        self.images.clear();
+        self.links.clear();
+        self.graphemes.clear();
+    }
+
+    /// Register a hyperlink target and return the id to store in cells.
+    ///
+    /// Ids are derived from the URL, so the same link keeps its id across
+    /// frames and unchanged cells don't show up in the diff. Control
+    /// characters in the URL are percent-encoded so it can't end the
+    /// hyperlink escape sequence early and inject its own.
+    pub fn intern_link(&mut self, url: &str) -> u64 {
+        let url = escape_link(url);
+        let id = hash_str(&url);
+        self.links.entry(id).or_insert(url);
+        id
+    }
+
+    /// Look up the URL for a cell's link id.
+    pub fn link(&self, id: u64) -> Option<&str> {
+        self.links.get(&id).map(String::as_str)
+    }
+
+    /// Register a grapheme cluster and return the id to store in a cell.
+    ///
+    /// Returns `None` for single-codepoint clusters, which fit in `Cell::char`.
+    pub fn intern_grapheme(&mut self, grapheme: &str) -> Option<u64> {
+        let mut chars = grapheme.chars();
+        chars.next()?;
+        chars.next()?;
+        let id = hash_str(grapheme);
+        self.graphemes
+            .entry(id)
+            .or_insert_with(|| grapheme.to_string());
+        Some(id)
+    }
+
+    /// Look up the grapheme cluster for a cell's cluster id.
+    pub fn grapheme(&self, id: u64) -> Option<&str> {
+        self.graphemes.get(&id).map(String::as_str)
     }
+
+    /// Record an image drawn over `placement.rect`.
+    pub fn place_image(&mut self, placement: ImagePlacement) {
+        self.images.push(placement);
+    }
+
+    /// Images drawn this frame.
+    pub fn images(&self) -> &[ImagePlacement] {
+        &self.images
+    }
+
+    /// Mark cells in `rect` as unknown so the next diff rewrites them.
+    pub fn invalidate(&mut self, rect: Rect) {
+        let x0 = rect.x.max(0) as u16;
+        let y0 = rect.y.max(0) as u16;
+        for y in y0..y0.saturating_add(rect.height).min(self.height) {
+            for x in x0..x0.saturating_add(rect.width).min(self.width) {
+                let idx = self.index(x, y);
+                self.cells[idx].char = '\0';
+                self.cells[idx].cluster = None;
+            }
+        }
+    }
+}
+
+/// Stable id for an interned string; equal strings get equal ids across frames.
+/// Percent-encode C0 and C1 control characters (including ESC, BEL and
+/// DEL) in a hyperlink target.
+fn escape_link(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_control() {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn hash_str(s: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    hasher.finish()
 }
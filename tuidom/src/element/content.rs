@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use crate::buffer::Buffer;
+use crate::image::Image;
 use crate::layout::Rect;
 
 #[derive(Default, Clone)]
@@ -27,6 +28,8 @@ pub enum Content {
         /// When Some, display this character instead of the actual text (for passwords).
         mask: Option<char>,
     },
+    /// Image drawn with the terminal's graphics protocol, scaled to the element's rect.
+    Image(Image),
 }
 
 impl Clone for Box<dyn CustomContent> {
@@ -48,6 +51,7 @@ impl std::fmt::Debug for Content {
             Self::TextInput { value, cursor, .. } => {
                 write!(f, "TextInput({value:?}, cursor={cursor})")
             }
+            Self::Image(image) => write!(f, "{image:?}"),
         }
     }
 }
@@ -3,10 +3,11 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use super::{Content, CustomContent};
+use crate::image::Image;
 use crate::transitions::Transitions;
 use crate::types::{
-    Align, Backdrop, Direction, Edges, Justify, Overflow, Position, Size, Style, TextAlign,
-    TextWrap, Wrap,
+    Align, Anchor, Backdrop, Direction, Edges, Justify, Overflow, Placement, Position, Size, Style,
+    TextAlign, TextWrap, Wrap,
 };
 
 static NEXT_ID: AtomicU64 = AtomicU64::new(0);
@@ -31,6 +32,9 @@ pub struct Element {
     pub max_width: Option<u16>,
     pub min_height: Option<u16>,
     pub max_height: Option<u16>,
+    /// Visual width:height ratio. An `Auto` axis is derived from the other one,
+    /// accounting for terminal cells being taller than they are wide.
+    pub aspect_ratio: Option<f32>,
     pub padding: Edges,
     pub margin: Edges,
 
@@ -41,6 +45,9 @@ pub struct Element {
     pub right: Option<i16>,
     pub bottom: Option<i16>,
     pub z_index: i16,
+    /// Out-of-flow elements with an anchor are placed next to the anchor
+    /// element instead of using top/left/right/bottom.
+    pub anchor: Option<Anchor>,
 
     // Flex container
     pub direction: Direction,
@@ -48,6 +55,9 @@ pub struct Element {
     pub justify: Justify,
     pub align: Align,
     pub wrap: Wrap,
+    /// Distribution of wrapped lines along the cross axis.
+    /// Only applies when `wrap` is `Wrap::Wrap`.
+    pub align_content: Justify,
 
     // Flex item
     pub flex_grow: u16,
@@ -69,10 +79,22 @@ pub struct Element {
     pub style: Style,
     pub transitions: Transitions,
     pub backdrop: Backdrop,
+    /// Render this subtree into its own offscreen buffer, then composite it
+    /// into the parent buffer.
+    pub layer: bool,
+    /// Reuse the layer's buffer from the previous frame while this key is
+    /// unchanged. Only meaningful with `layer`.
+    pub layer_key: Option<u64>,
 
     // Text-specific
     pub text_wrap: TextWrap,
     pub text_align: TextAlign,
+    /// URL the text links to. Emitted as an OSC 8 hyperlink on terminals
+    /// that support it; plain text elsewhere.
+    pub link: Option<String>,
+    /// Parse SGR escape sequences in the text into styling instead of
+    /// printing them. Other escape sequences are dropped.
+    pub ansi: bool,
 
     // Interaction
     pub focusable: bool,
@@ -82,6 +104,12 @@ pub struct Element {
     /// When true, this element captures keyboard input (for text fields).
     /// Arrow keys will move cursor instead of focus, etc.
     pub captures_input: bool,
+    /// When true, arrow keys along this container's main axis cycle focus
+    /// between its focusable descendants, wrapping at either end.
+    pub focus_wrap: bool,
+    /// When true, mouse hit testing ignores this element and its descendants,
+    /// so clicks and hovers reach whatever is rendered beneath it.
+    pub click_through: bool,
 
     // State (focused is set by runtime enrichment, disabled is set by user/widgets)
     /// Whether this element is currently focused. Set by runtime enrichment, not by user.
@@ -115,6 +143,7 @@ impl Default for Element {
             max_width: None,
             min_height: None,
             max_height: None,
+            aspect_ratio: None,
             padding: Edges::default(),
             margin: Edges::default(),
             position: Position::Static,
@@ -123,11 +152,13 @@ impl Default for Element {
             right: None,
             bottom: None,
             z_index: 0,
+            anchor: None,
             direction: Direction::Column,
             gap: 0,
             justify: Justify::Start,
             align: Align::Start,
             wrap: Wrap::NoWrap,
+            align_content: Justify::Start,
             flex_grow: 0,
             flex_shrink: 1,
             align_self: None,
@@ -138,13 +169,19 @@ impl Default for Element {
             style: Style::default(),
             transitions: Transitions::default(),
             backdrop: Backdrop::None,
+            layer: false,
+            layer_key: None,
             text_wrap: TextWrap::NoWrap,
             text_align: TextAlign::Left,
+            link: None,
+            ansi: false,
             focusable: false,
             clickable: false,
             draggable: false,
             scrollable: false,
             captures_input: false,
+            focus_wrap: false,
+            click_through: false,
             focused: false,
             disabled: false,
             style_focused: None,
@@ -171,6 +208,18 @@ impl Element {
         }
     }
 
+    /// Create an image element.
+    ///
+    /// The natural size is derived from the image's pixel size; set `width`
+    /// or `height` (optionally with `aspect_ratio`) to scale it.
+    pub fn image(image: Image) -> Self {
+        Self {
+            id: generate_id("image"),
+            content: Content::Image(image),
+            ..Default::default()
+        }
+    }
+
     pub fn col() -> Self {
         Self {
             id: generate_id("col"),
@@ -260,6 +309,17 @@ impl Element {
         self
     }
 
+    /// Keep a visual width:height ratio (e.g. `16.0 / 9.0`).
+    ///
+    /// The ratio describes how the element looks on screen, not its cell count:
+    /// cells are [`CELL_ASPECT`](crate::layout::CELL_ASPECT) times taller than
+    /// wide, so a square (`1.0`) is twice as many columns as rows. Whichever of
+    /// width/height is `Auto` is computed from the other on every layout.
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
     pub fn padding(mut self, padding: Edges) -> Self {
         self.padding = padding;
         self
@@ -301,6 +361,27 @@ impl Element {
         self
     }
 
+    /// Place this element next to the element with the given ID.
+    ///
+    /// Only applies to `Position::Absolute` and `Position::Fixed` elements.
+    /// The anchor must appear earlier in tree order so it is laid out first.
+    pub fn anchor(mut self, id: impl Into<String>, placement: Placement) -> Self {
+        self.anchor = Some(Anchor {
+            id: id.into(),
+            placement,
+            offset: 0,
+        });
+        self
+    }
+
+    /// Set the gap between this element and its anchor.
+    pub fn anchor_offset(mut self, offset: u16) -> Self {
+        if let Some(anchor) = &mut self.anchor {
+            anchor.offset = offset;
+        }
+        self
+    }
+
     // Flex container
     pub fn direction(mut self, direction: Direction) -> Self {
         self.direction = direction;
@@ -327,6 +408,15 @@ impl Element {
         self
     }
 
+    /// Set how wrapped lines are distributed along the cross axis.
+    ///
+    /// Items are aligned within their own line using `align`; this controls
+    /// where the lines themselves go when there is spare cross-axis space.
+    pub fn align_content(mut self, align_content: Justify) -> Self {
+        self.align_content = align_content;
+        self
+    }
+
     // Flex item
     pub fn flex_grow(mut self, flex_grow: u16) -> Self {
         self.flex_grow = flex_grow;
@@ -392,6 +482,26 @@ impl Element {
         self
     }
 
+    /// Render this element and its descendants into an offscreen layer.
+    ///
+    /// Cells the layer doesn't draw stay transparent, so content underneath
+    /// shows through. Has no effect on the root element.
+    pub fn layer(mut self, layer: bool) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Cache the layer between frames: while `key` stays the same, the
+    /// previous frame's pixels are composited without re-rendering the
+    /// subtree. Change the key whenever anything inside the layer changes
+    /// (content, scroll offset, focus); transitions inside a cached layer
+    /// don't animate. Implies `layer(true)`.
+    pub fn layer_key(mut self, key: u64) -> Self {
+        self.layer = true;
+        self.layer_key = Some(key);
+        self
+    }
+
     // Text
     pub fn text_wrap(mut self, text_wrap: TextWrap) -> Self {
         self.text_wrap = text_wrap;
@@ -403,6 +513,19 @@ impl Element {
         self
     }
 
+    /// Make the text a hyperlink to `url`.
+    pub fn link(mut self, url: impl Into<String>) -> Self {
+        self.link = Some(url.into());
+        self
+    }
+
+    /// Interpret SGR escape sequences (colors, bold, ...) in the text, e.g.
+    /// for displaying the output of external tools.
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
     // Interaction
     pub fn focusable(mut self, focusable: bool) -> Self {
         self.focusable = focusable;
@@ -429,6 +552,24 @@ impl Element {
         self
     }
 
+    /// Set whether arrow-key navigation wraps around within this container.
+    ///
+    /// Only keys along the main axis (Left/Right for rows, Up/Down for columns)
+    /// are affected; the cross axis still navigates spatially.
+    pub fn focus_wrap(mut self, wrap: bool) -> Self {
+        self.focus_wrap = wrap;
+        self
+    }
+
+    /// Set whether mouse input passes through this element and its subtree.
+    ///
+    /// Useful for purely informational layers (HUDs, watermarks) drawn on top
+    /// of interactive content.
+    pub fn click_through(mut self, click_through: bool) -> Self {
+        self.click_through = click_through;
+        self
+    }
+
     /// Set whether this element creates an interaction scope.
     ///
     /// When true:
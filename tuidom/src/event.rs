@@ -72,6 +72,10 @@ pub enum Event {
     },
     /// Terminal resized
     Resize { width: u16, height: u16 },
+    /// Terminal window gained focus
+    FocusGained,
+    /// Terminal window lost focus
+    FocusLost,
     /// Text input value changed
     Change { target: String, text: String },
     /// Text input submitted (Enter pressed)
@@ -5,6 +5,7 @@ use crate::event::{Event, Key, Modifiers, NavDirection, ScrollAction};
 use crate::hit::{hit_test_focusable, hit_test_interaction_scope};
 use crate::layout::{LayoutResult, Rect};
 use crate::scroll::{find_scrollable_ancestor, find_scrollable_ancestor_with_type};
+use crate::types::Direction;
 
 /// Find the active interaction scope.
 /// - Always prioritizes the topmost (last in tree order) scope
@@ -326,6 +327,37 @@ impl FocusState {
         }
     }
 
+    /// Move focus within the nearest `focus_wrap` container of the focused element.
+    /// Navigation follows tree order along the container's main axis and wraps
+    /// at either end. Returns None if there is no such container, the direction
+    /// is across the container's axis, or focus would not change.
+    pub fn focus_wrapped(&mut self, direction: NavDirection, root: &Element) -> Option<String> {
+        let current = self.focused.clone()?;
+        let container = find_focus_wrap_ancestor(root, &current)?;
+
+        let forward = match (container.direction, direction) {
+            (Direction::Row, NavDirection::Right) | (Direction::Column, NavDirection::Down) => true,
+            (Direction::Row, NavDirection::Left) | (Direction::Column, NavDirection::Up) => false,
+            _ => return None,
+        };
+
+        let members = collect_focusable(container, None);
+        let idx = members.iter().position(|id| *id == current)?;
+        let len = members.len();
+        let new_idx = if forward {
+            (idx + 1) % len
+        } else {
+            (idx + len - 1) % len
+        };
+
+        let new_focus = members[new_idx].clone();
+        if new_focus == current {
+            return None;
+        }
+        self.focused = Some(new_focus.clone());
+        Some(new_focus)
+    }
+
     /// Process raw crossterm events and produce high-level events.
     /// Focus follows mouse - hovering over a focusable element focuses it.
     pub fn process_events(
@@ -416,6 +448,15 @@ impl FocusState {
 
                         if let Some(dir) = direction {
                             if let Some(old) = self.focused.clone() {
+                                // focus_wrap containers cycle along their main axis
+                                if let Some(new) = self.focus_wrapped(dir, root) {
+                                    events.push(Event::Blur {
+                                        target: old,
+                                        new_target: Some(new.clone()),
+                                    });
+                                    events.push(Event::Focus { target: new });
+                                    continue;
+                                }
                                 if let Some(new) = self.focus_direction(dir, root, layout) {
                                     // For arrow keys in a .scrollable() element, check if navigation
                                     // would leave the scrollable container. If so, emit Key event
@@ -713,6 +754,9 @@ impl FocusState {
                     });
                 }
 
+                CrosstermEvent::FocusGained => events.push(Event::FocusGained),
+                CrosstermEvent::FocusLost => events.push(Event::FocusLost),
+
                 _ => {}
             }
         }
@@ -721,6 +765,37 @@ impl FocusState {
     }
 }
 
+/// Find the nearest ancestor of `target_id` that has `focus_wrap` set.
+fn find_focus_wrap_ancestor<'a>(root: &'a Element, target_id: &str) -> Option<&'a Element> {
+    fn find_recursive<'a>(
+        element: &'a Element,
+        target_id: &str,
+        current: Option<&'a Element>,
+    ) -> Option<Option<&'a Element>> {
+        if element.id == target_id {
+            return Some(current);
+        }
+
+        let wrap = if element.focus_wrap {
+            Some(element)
+        } else {
+            current
+        };
+
+        if let Content::Children(children) = &element.content {
+            for child in children {
+                if let Some(found) = find_recursive(child, target_id, wrap) {
+                    return Some(found);
+                }
+            }
+        }
+
+        None
+    }
+
+    find_recursive(root, target_id, None).flatten()
+}
+
 /// Collect all focusable element IDs in tree order.
 /// If `active_scope` is Some, only collects from within that scope.
 pub fn collect_focusable(element: &Element, active_scope: Option<&str>) -> Vec<String> {
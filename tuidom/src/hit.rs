@@ -189,6 +189,11 @@ fn collect_hits<F>(
 ) where
     F: Fn(&Element) -> bool + Copy,
 {
+    // Click-through subtrees are invisible to hit testing
+    if element.click_through {
+        return;
+    }
+
     let Some(rect) = layout.get(&element.id) else {
         return;
     };
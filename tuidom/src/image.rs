@@ -0,0 +1,489 @@
+//! Terminal image support.
+//!
+//! Images are shown with [`Element::image`](crate::Element::image). During
+//! render the image area is filled with a fallback (half-block approximation
+//! for pixel data, alt text for encoded images) and a placement is recorded in
+//! the buffer. The terminal then draws the real image on top using the
+//! detected [`ImageProtocol`], and removes placements that moved or vanished.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::layout::Rect;
+
+/// Assumed cell size in pixels when the terminal doesn't report one.
+pub const DEFAULT_CELL_PIXELS: (u16, u16) = (8, 16);
+
+/// Maximum payload bytes per Kitty graphics escape sequence.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+static NEXT_IMAGE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Graphics protocol used to draw images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageProtocol {
+    /// Kitty graphics protocol (kitty, WezTerm, Ghostty, Konsole).
+    Kitty,
+    /// Sixel graphics (foot, mlterm, xterm with sixel, Windows Terminal).
+    Sixel,
+    /// iTerm2 inline images (iTerm2, WezTerm).
+    Iterm2,
+    /// No graphics support; only the fallback is rendered.
+    #[default]
+    None,
+}
+
+impl ImageProtocol {
+    /// Detect the supported protocol from the environment.
+    pub fn detect() -> Self {
+        Self::detect_from(|name| std::env::var(name).ok())
+    }
+
+    /// Detect the supported protocol using the given environment lookup.
+    pub fn detect_from(env: impl Fn(&str) -> Option<String>) -> Self {
+        let term = env("TERM").unwrap_or_default();
+        let term_program = env("TERM_PROGRAM").unwrap_or_default();
+
+        if env("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || term_program == "ghostty"
+            || term_program == "WezTerm"
+            || env("KONSOLE_VERSION").is_some()
+        {
+            Self::Kitty
+        } else if term_program == "iTerm.app" {
+            Self::Iterm2
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || env("WT_SESSION").is_some()
+        {
+            Self::Sixel
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Image pixel source.
+#[derive(Clone)]
+pub enum ImageData {
+    /// PNG-encoded bytes. Drawn by Kitty and iTerm2; other terminals show the alt text.
+    Png(Vec<u8>),
+    /// Raw 8-bit RGBA pixels, row-major. Drawn by every protocol and
+    /// approximated with half blocks when graphics aren't available.
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+}
+
+/// An image that can be shown as element content.
+///
+/// Cloning is cheap and keeps the same id, so the terminal can tell an
+/// unchanged image from a new one between frames.
+#[derive(Clone)]
+pub struct Image {
+    id: u32,
+    data: Arc<ImageData>,
+    alt: Option<String>,
+}
+
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (width, height) = self.pixel_size().unwrap_or((0, 0));
+        write!(f, "Image(#{}, {}x{})", self.id, width, height)
+    }
+}
+
+impl Image {
+    fn new(data: ImageData) -> Self {
+        Self {
+            id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+            data: Arc::new(data),
+            alt: None,
+        }
+    }
+
+    /// Create an image from PNG-encoded bytes.
+    pub fn png(bytes: impl Into<Vec<u8>>) -> Self {
+        Self::new(ImageData::Png(bytes.into()))
+    }
+
+    /// Create an image from raw RGBA pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pixels` isn't exactly `width * height * 4` bytes.
+    pub fn rgba(width: u32, height: u32, pixels: impl Into<Vec<u8>>) -> Self {
+        let pixels = pixels.into();
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * 4,
+            "RGBA pixel data doesn't match {}x{}",
+            width,
+            height
+        );
+        Self::new(ImageData::Rgba {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Set the text shown when the image can't be drawn.
+    pub fn alt(mut self, alt: impl Into<String>) -> Self {
+        self.alt = Some(alt.into());
+        self
+    }
+
+    /// Unique id of the image data.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The image pixel data.
+    pub fn data(&self) -> &ImageData {
+        &self.data
+    }
+
+    /// The alt text, if any.
+    pub fn alt_text(&self) -> Option<&str> {
+        self.alt.as_deref()
+    }
+
+    /// Image size in pixels, if known.
+    pub fn pixel_size(&self) -> Option<(u32, u32)> {
+        match self.data.as_ref() {
+            ImageData::Rgba { width, height, .. } => Some((*width, *height)),
+            // IHDR always follows the 8-byte signature: width and height are
+            // the first two big-endian u32s of its data
+            ImageData::Png(bytes) if bytes.len() >= 24 => {
+                let width = u32::from_be_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]);
+                let height = u32::from_be_bytes([bytes[20], bytes[21], bytes[22], bytes[23]]);
+                Some((width, height))
+            }
+            ImageData::Png(_) => None,
+        }
+    }
+
+    /// Natural size in cells for the given cell pixel size.
+    pub fn cell_size(&self, cell_pixels: (u16, u16)) -> (u16, u16) {
+        let (width, height) = self.pixel_size().unwrap_or((0, 0));
+        let cols = width.div_ceil(cell_pixels.0.max(1) as u32);
+        let rows = height.div_ceil(cell_pixels.1.max(1) as u32);
+        (
+            cols.min(u16::MAX as u32) as u16,
+            rows.min(u16::MAX as u32) as u16,
+        )
+    }
+
+    /// Returns true if the protocol can draw this image.
+    pub fn supported_by(&self, protocol: ImageProtocol) -> bool {
+        !matches!(
+            (protocol, self.data.as_ref()),
+            (ImageProtocol::None, _) | (ImageProtocol::Sixel, ImageData::Png(_))
+        )
+    }
+
+    /// Build the escape sequence that draws this image at the cursor,
+    /// scaled to `cols` x `rows` cells.
+    ///
+    /// Returns `None` if the protocol can't draw this image.
+    pub fn encode(
+        &self,
+        protocol: ImageProtocol,
+        cols: u16,
+        rows: u16,
+        cell_pixels: (u16, u16),
+    ) -> Option<String> {
+        if !self.supported_by(protocol) || cols == 0 || rows == 0 {
+            return None;
+        }
+        match protocol {
+            ImageProtocol::Kitty => Some(self.encode_kitty(cols, rows)),
+            ImageProtocol::Iterm2 => Some(self.encode_iterm2(cols, rows)),
+            ImageProtocol::Sixel => {
+                let ImageData::Rgba {
+                    width,
+                    height,
+                    pixels,
+                } = self.data.as_ref()
+                else {
+                    return None;
+                };
+                let target_w = cols as u32 * cell_pixels.0 as u32;
+                let target_h = rows as u32 * cell_pixels.1 as u32;
+                let scaled = scale_rgba(pixels, *width, *height, target_w, target_h);
+                Some(encode_sixel(&scaled, target_w, target_h))
+            }
+            ImageProtocol::None => None,
+        }
+    }
+
+    fn encode_kitty(&self, cols: u16, rows: u16) -> String {
+        let (format, payload) = match self.data.as_ref() {
+            ImageData::Png(bytes) => ("f=100".to_string(), base64(bytes)),
+            ImageData::Rgba {
+                width,
+                height,
+                pixels,
+            } => (format!("f=32,s={},v={}", width, height), base64(pixels)),
+        };
+
+        // Transmit and display in one go; C=1 keeps the cursor in place and
+        // q=2 suppresses responses that would otherwise arrive as input
+        let mut out = String::with_capacity(payload.len() + 128);
+        let chunks: Vec<&str> = payload
+            .as_bytes()
+            .chunks(KITTY_CHUNK_SIZE)
+            .map(|c| std::str::from_utf8(c).unwrap_or_default())
+            .collect();
+        let last = chunks.len().saturating_sub(1);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = if i == last { 0 } else { 1 };
+            if i == 0 {
+                out.push_str(&format!(
+                    "\x1b_Ga=T,{},i={},p=1,c={},r={},C=1,q=2,m={};{}\x1b\\",
+                    format, self.id, cols, rows, more, chunk
+                ));
+            } else {
+                out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+            }
+        }
+        out
+    }
+
+    fn encode_iterm2(&self, cols: u16, rows: u16) -> String {
+        let png = match self.data.as_ref() {
+            ImageData::Png(bytes) => base64(bytes),
+            ImageData::Rgba {
+                width,
+                height,
+                pixels,
+            } => base64(&encode_png(*width, *height, pixels)),
+        };
+        format!(
+            "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{}\x07",
+            cols, rows, png
+        )
+    }
+
+    /// Escape sequence that removes this image's placement (Kitty only).
+    pub fn encode_delete(&self, protocol: ImageProtocol) -> Option<String> {
+        match protocol {
+            ImageProtocol::Kitty => Some(format!("\x1b_Ga=d,d=i,i={},q=2\x1b\\", self.id)),
+            _ => None,
+        }
+    }
+}
+
+/// An image drawn at a position in the buffer.
+#[derive(Debug, Clone)]
+pub struct ImagePlacement {
+    pub image: Image,
+    pub rect: Rect,
+}
+
+impl ImagePlacement {
+    /// Returns true if both placements show the same image in the same place.
+    pub fn same_as(&self, other: &ImagePlacement) -> bool {
+        self.image.id == other.image.id && self.rect == other.rect
+    }
+}
+
+/// Escape sequence that removes every Kitty image placement.
+pub(crate) const KITTY_DELETE_ALL: &str = "\x1b_Ga=d,d=a,q=2\x1b\\";
+
+// =============================================================================
+// Encoding helpers
+// =============================================================================
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+/// Nearest-neighbour scale of RGBA pixels.
+fn scale_rgba(pixels: &[u8], width: u32, height: u32, target_w: u32, target_h: u32) -> Vec<u8> {
+    let mut out = Vec::with_capacity(target_w as usize * target_h as usize * 4);
+    for y in 0..target_h {
+        let src_y = (y as u64 * height as u64 / target_h.max(1) as u64) as usize;
+        for x in 0..target_w {
+            let src_x = (x as u64 * width as u64 / target_w.max(1) as u64) as usize;
+            let i = (src_y * width as usize + src_x) * 4;
+            out.extend_from_slice(&pixels[i..i + 4]);
+        }
+    }
+    out
+}
+
+/// Encode RGBA pixels as a Sixel image using a 6x6x6 color cube.
+/// Pixels with alpha below 50% are left transparent.
+fn encode_sixel(pixels: &[u8], width: u32, height: u32) -> String {
+    let level = |v: u8| (v as u32 * 5 + 127) / 255;
+    let index_of = |i: usize| -> Option<usize> {
+        let p = &pixels[i * 4..i * 4 + 4];
+        if p[3] < 128 {
+            return None;
+        }
+        Some((level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])) as usize)
+    };
+
+    // P2=1: pixels without a color stay transparent
+    let mut out = format!("\x1bP0;1q\"1;1;{};{}", width, height);
+    let mut defined = [false; 216];
+
+    for band in (0..height).step_by(6) {
+        let band_rows = (height - band).min(6);
+
+        // Collect the sixel bitmask per column for every color used in this band
+        let mut colors: Vec<(usize, Vec<u8>)> = Vec::new();
+        for x in 0..width {
+            for dy in 0..band_rows {
+                let i = ((band + dy) * width + x) as usize;
+                let Some(color) = index_of(i) else {
+                    continue;
+                };
+                let pos = match colors.iter().position(|(c, _)| *c == color) {
+                    Some(pos) => pos,
+                    None => {
+                        colors.push((color, vec![0; width as usize]));
+                        colors.len() - 1
+                    }
+                };
+                colors[pos].1[x as usize] |= 1 << dy;
+            }
+        }
+
+        for (n, (color, masks)) in colors.iter().enumerate() {
+            if !defined[*color] {
+                let (r, g, b) = (color / 36, color / 6 % 6, color % 6);
+                out.push_str(&format!("#{};2;{};{};{}", color, r * 20, g * 20, b * 20));
+                defined[*color] = true;
+            }
+            out.push_str(&format!("#{}", color));
+            push_sixel_run_length(&mut out, masks);
+            if n + 1 < colors.len() {
+                out.push('$');
+            }
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+fn push_sixel_run_length(out: &mut String, masks: &[u8]) {
+    let mut i = 0;
+    while i < masks.len() {
+        let mask = masks[i];
+        let run = masks[i..].iter().take_while(|&&m| m == mask).count();
+        let ch = (63 + mask) as char;
+        if run > 3 {
+            out.push_str(&format!("!{}{}", run, ch));
+        } else {
+            for _ in 0..run {
+                out.push(ch);
+            }
+        }
+        i += run;
+    }
+}
+
+/// Encode RGBA pixels as an uncompressed PNG (stored deflate blocks).
+fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    // Raw scanlines, each prefixed with filter type 0
+    let stride = width as usize * 4;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    // zlib stream with stored blocks
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, no interlace
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    push_png_chunk(&mut png, b"IHDR", &ihdr);
+    push_png_chunk(&mut png, b"IDAT", &zlib);
+    push_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn push_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc_input = Vec::with_capacity(4 + data.len());
+    crc_input.extend_from_slice(kind);
+    crc_input.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&crc_input).to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
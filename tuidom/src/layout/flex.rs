@@ -3,8 +3,9 @@ use std::collections::HashMap;
 use super::Rect;
 use crate::animation::AnimationState;
 use crate::element::{Content, Element};
+use crate::text::ansi::strip as strip_ansi;
 use crate::text::{display_width, wrap_chars, wrap_words};
-use crate::types::{Align, Direction, Overflow, Position, Size, TextWrap, Wrap};
+use crate::types::{Align, Anchor, Direction, Overflow, Placement, Position, Size, TextWrap, Wrap};
 
 // =============================================================================
 // Virtualization Constants
@@ -18,6 +19,66 @@ const VIRTUALIZATION_THRESHOLD: usize = 20;
 /// Provides buffer for smooth scrolling without visual pop-in.
 const VIRTUALIZATION_BUFFER: usize = 5;
 
+// =============================================================================
+// Aspect Ratio
+// =============================================================================
+
+/// Height of a terminal cell relative to its width.
+/// Most terminal fonts render cells roughly twice as tall as they are wide.
+pub const CELL_ASPECT: f32 = 2.0;
+
+/// Convert a size on one axis into the other axis for a visual width:height ratio.
+fn aspect_convert(other: u16, ratio: f32, to_width: bool) -> u16 {
+    if ratio <= 0.0 {
+        return other;
+    }
+    let size = if to_width {
+        other as f32 * ratio * CELL_ASPECT
+    } else {
+        other as f32 / (ratio * CELL_ASPECT)
+    };
+    size.round().min(u16::MAX as f32) as u16
+}
+
+/// Derive the `Auto` axis of an aspect-ratio element from the other axis.
+/// If the derived size doesn't fit in `max_width` x `max_height`, the element
+/// is scaled down along both axes instead.
+fn apply_aspect_ratio(
+    element: &Element,
+    width: u16,
+    height: u16,
+    max_width: u16,
+    max_height: u16,
+) -> (u16, u16) {
+    let Some(ratio) = element.aspect_ratio else {
+        return (width, height);
+    };
+
+    if matches!(element.height, Size::Auto) {
+        let derived = aspect_convert(width, ratio, false);
+        if derived <= max_height {
+            (width, derived)
+        } else {
+            (
+                aspect_convert(max_height, ratio, true).min(width),
+                max_height,
+            )
+        }
+    } else if matches!(element.width, Size::Auto) {
+        let derived = aspect_convert(height, ratio, true);
+        if derived <= max_width {
+            (derived, height)
+        } else {
+            (
+                max_width,
+                aspect_convert(max_width, ratio, false).min(height),
+            )
+        }
+    } else {
+        (width, height)
+    }
+}
+
 // =============================================================================
 // Scroll Context for Virtualization
 // =============================================================================
@@ -39,6 +100,8 @@ pub struct LayoutResult {
     /// Content and viewport sizes for scrollable elements
     /// (content_width, content_height, viewport_width, viewport_height)
     content_sizes: HashMap<String, (u16, u16, u16, u16)>,
+    /// The area the root element was laid out in.
+    viewport: Rect,
 }
 
 impl LayoutResult {
@@ -93,10 +156,16 @@ impl LayoutResult {
     pub fn iter_rects(&self) -> impl Iterator<Item = (&String, &Rect)> {
         self.rects.iter()
     }
+
+    /// The area the root element was laid out in.
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
 }
 
 pub fn layout(element: &Element, available: Rect, animation: &AnimationState) -> LayoutResult {
     let mut result = LayoutResult::new();
+    result.viewport = available;
     layout_element(element, available, &mut result, animation, None);
     result
 }
@@ -108,9 +177,23 @@ fn layout_element(
     animation: &AnimationState,
     scroll_ctx: Option<ScrollContext>,
 ) {
-    // Handle absolute positioning
-    if element.position == Position::Absolute {
-        let rect = layout_absolute(element, available);
+    // Handle absolute/fixed positioning
+    if element.position.is_out_of_flow() {
+        let container = if element.position == Position::Fixed {
+            result.viewport
+        } else {
+            available
+        };
+        let anchor_rect = element
+            .anchor
+            .as_ref()
+            .and_then(|anchor| Some((anchor, *result.get(&anchor.id)?)));
+        let rect = match anchor_rect {
+            Some((anchor, anchor_rect)) => {
+                layout_anchored(element, anchor, anchor_rect, container, result.viewport)
+            }
+            None => layout_absolute(element, container),
+        };
         result.insert(element.id.clone(), rect);
         layout_children(element, rect, result, animation, scroll_ctx);
         return;
@@ -123,6 +206,13 @@ fn layout_element(
     // Calculate this element's size within margin-adjusted space
     let width = resolve_size(element.width, after_margin.width, element, true);
     let height = resolve_size(element.height, after_margin.height, element, false);
+    let (width, height) = apply_aspect_ratio(
+        element,
+        width,
+        height,
+        after_margin.width,
+        after_margin.height,
+    );
     let mut rect = Rect::new(after_margin.x, after_margin.y, width, height);
 
     // Handle relative positioning - offset from normal flow position
@@ -171,6 +261,7 @@ fn layout_absolute(element: &Element, container: Rect) -> Rect {
         // Only explicit size or default - use unclamped to allow overflow
         _ => resolve_size_clamped(element.height, container.height, element, false, false),
     };
+    let (width, height) = apply_aspect_ratio(element, width, height, u16::MAX, u16::MAX);
 
     // Determine x position
     let x = match (element.left, element.right) {
@@ -195,6 +286,75 @@ fn layout_absolute(element: &Element, container: Rect) -> Rect {
     Rect::new(x, y, width, height)
 }
 
+/// Layout an element next to its anchor, flipping to the opposite side when
+/// the preferred side would overflow the viewport. The cross axis is aligned
+/// with the anchor's start edge and clamped to stay on screen.
+fn layout_anchored(
+    element: &Element,
+    anchor: &Anchor,
+    anchor_rect: Rect,
+    container: Rect,
+    viewport: Rect,
+) -> Rect {
+    let width = resolve_size_clamped(element.width, container.width, element, true, false);
+    let height = resolve_size_clamped(element.height, container.height, element, false, false);
+    let (x, y) = place_anchored(anchor, anchor_rect, width, height, viewport);
+    Rect::new(x, y, width, height)
+}
+
+/// Position of an element of the given size next to its anchor.
+fn place_anchored(
+    anchor: &Anchor,
+    anchor_rect: Rect,
+    width: u16,
+    height: u16,
+    viewport: Rect,
+) -> (i16, i16) {
+    let offset = anchor.offset as i16;
+
+    let place = |placement: Placement| -> (i16, i16) {
+        match placement {
+            Placement::Below => (anchor_rect.x, anchor_rect.bottom() + offset),
+            Placement::Above => (anchor_rect.x, anchor_rect.y - offset - height as i16),
+            Placement::Right => (anchor_rect.right() + offset, anchor_rect.y),
+            Placement::Left => (anchor_rect.x - offset - width as i16, anchor_rect.y),
+        }
+    };
+    let fits_main_axis = |placement: Placement, (x, y): (i16, i16)| -> bool {
+        match placement {
+            Placement::Above | Placement::Below => {
+                y >= viewport.y && y + height as i16 <= viewport.bottom()
+            }
+            Placement::Left | Placement::Right => {
+                x >= viewport.x && x + width as i16 <= viewport.right()
+            }
+        }
+    };
+
+    let preferred = place(anchor.placement);
+    let (mut x, mut y) = if fits_main_axis(anchor.placement, preferred) {
+        preferred
+    } else {
+        let flipped = anchor.placement.flipped();
+        let alternative = place(flipped);
+        if fits_main_axis(flipped, alternative) {
+            alternative
+        } else {
+            preferred
+        }
+    };
+
+    // Keep the element on screen along the cross axis
+    let max_x = (viewport.right() - width as i16).max(viewport.x);
+    let max_y = (viewport.bottom() - height as i16).max(viewport.y);
+    match anchor.placement {
+        Placement::Above | Placement::Below => x = x.clamp(viewport.x, max_x),
+        Placement::Left | Placement::Right => y = y.clamp(viewport.y, max_y),
+    }
+
+    (x, y)
+}
+
 fn layout_children(
     element: &Element,
     rect: Rect,
@@ -223,11 +383,11 @@ fn layout_children(
     // Separate flow children from absolute children
     let flow_children: Vec<_> = children
         .iter()
-        .filter(|c| c.position != Position::Absolute)
+        .filter(|c| !c.position.is_out_of_flow())
         .collect();
     let absolute_children: Vec<_> = children
         .iter()
-        .filter(|c| c.position == Position::Absolute)
+        .filter(|c| c.position.is_out_of_flow())
         .collect();
 
     // Account for border
@@ -388,21 +548,41 @@ fn layout_children(
     let cross_size = if is_row { inner.height } else { inner.width };
 
     // Split children into lines (for wrapping)
-    let lines = if element.wrap == Wrap::Wrap {
-        split_into_lines(&flow_children, main_size, element.gap, is_row)
+    if element.wrap == Wrap::Wrap {
+        let lines = split_into_lines(&flow_children, main_size, element.gap, is_row);
+
+        // Each line is as thick as its largest item; items align within their line
+        let line_sizes: Vec<u16> = lines
+            .iter()
+            .map(|line| line_cross_size(line, is_row))
+            .collect();
+        let (start_offset, between_gap) =
+            distribute_lines(element.align_content, &line_sizes, cross_size, element.gap);
+
+        let mut cross_offset = start_offset;
+        for (line, &line_size) in lines.iter().zip(&line_sizes) {
+            layout_line(
+                line,
+                element,
+                inner,
+                cross_offset,
+                main_size,
+                cross_offset.saturating_add(line_size),
+                is_row,
+                result,
+                animation,
+                child_scroll_ctx,
+            );
+            cross_offset = cross_offset
+                .saturating_add(line_size)
+                .saturating_add(between_gap);
+        }
     } else {
-        vec![flow_children.clone()]
-    };
-
-    // Layout each line
-    let mut cross_offset = 0u16;
-
-    for line in &lines {
-        let line_cross_size = layout_line(
-            line,
+        layout_line(
+            &flow_children,
             element,
             inner,
-            cross_offset,
+            0,
             main_size,
             cross_size,
             is_row,
@@ -410,7 +590,6 @@ fn layout_children(
             animation,
             child_scroll_ctx,
         );
-        cross_offset += line_cross_size + element.gap;
     }
 
     // Store content size for scrollable elements using natural/unconstrained child sizes
@@ -500,15 +679,33 @@ fn compute_content_size(children: &[&Element], inner: Rect, is_row: bool, gap: u
 /// Apply scroll offset to an element and all its descendants.
 /// Only recurses into children that are present in the layout result
 /// (virtualized off-screen elements won't have their children laid out).
+///
+/// Fixed elements are relative to the viewport and don't scroll. Anchored
+/// elements, including those inside fixed elements, are placed again against
+/// their anchor's scrolled rect, and their descendants move with them.
 fn apply_scroll_offset_recursive(
     element: &Element,
     scroll_x: u16,
     scroll_y: u16,
     result: &mut LayoutResult,
 ) {
+    shift_recursive(element, scroll_x as i16, scroll_y as i16, result);
+}
+
+/// Move an element and its descendants up/left by the given amounts,
+/// re-anchoring anchored elements (see `apply_scroll_offset_recursive`).
+fn shift_recursive(element: &Element, dx: i16, dy: i16, result: &mut LayoutResult) {
+    let (dx, dy) = match &element.anchor {
+        Some(anchor) if element.position.is_out_of_flow() => {
+            anchored_shift(element, anchor, result).unwrap_or((dx, dy))
+        }
+        _ if element.position == Position::Fixed => (0, 0),
+        _ => (dx, dy),
+    };
+
     if let Some(rect) = result.get_mut(&element.id) {
-        rect.x = rect.x - scroll_x as i16;
-        rect.y = rect.y - scroll_y as i16;
+        rect.x -= dx;
+        rect.y -= dy;
     }
 
     if let Content::Children(children) = &element.content {
@@ -516,12 +713,27 @@ fn apply_scroll_offset_recursive(
             // Only recurse if child was laid out (virtualized off-screen children
             // have a rect but their descendants don't)
             if result.get(&child.id).is_some() {
-                apply_scroll_offset_recursive(child, scroll_x, scroll_y, result);
+                shift_recursive(child, dx, dy, result);
             }
         }
     }
 }
 
+/// How far an anchored element has to move up/left to sit next to its
+/// anchor's current rect.
+fn anchored_shift(element: &Element, anchor: &Anchor, result: &LayoutResult) -> Option<(i16, i16)> {
+    let anchor_rect = *result.get(&anchor.id)?;
+    let rect = *result.get(&element.id)?;
+    let (x, y) = place_anchored(
+        anchor,
+        anchor_rect,
+        rect.width,
+        rect.height,
+        result.viewport,
+    );
+    Some((rect.x - x, rect.y - y))
+}
+
 /// Split children into lines based on available main axis space
 fn split_into_lines<'a>(
     children: &[&'a Element],
@@ -533,7 +745,7 @@ fn split_into_lines<'a>(
     let mut current_line_size = 0u16;
 
     for child in children {
-        let child_main = get_base_main_size(child, is_row);
+        let child_main = get_base_main_size(child, is_row, main_size);
         let child_margin = if is_row {
             child.margin.left + child.margin.right
         } else {
@@ -563,18 +775,168 @@ fn split_into_lines<'a>(
 }
 
 /// Get the base main axis size for a child (before flex grow/shrink)
-fn get_base_main_size(child: &Element, is_row: bool) -> u16 {
+fn get_base_main_size(child: &Element, is_row: bool, main_size: u16) -> u16 {
     let size = if is_row { child.width } else { child.height };
     match size {
         Size::Fixed(n) => n,
         Size::Auto => estimate_size(child, is_row),
-        Size::Fill => 0,       // Will be distributed via flex
-        Size::Flex(_) => 0,    // Will be distributed via flex
-        Size::Percent(_) => 0, // Needs container size, treat as flex for wrapping
+        Size::Fill => 0,    // Will be distributed via flex
+        Size::Flex(_) => 0, // Will be distributed via flex
+        Size::Percent(p) => (main_size as f32 * p) as u16,
     }
 }
 
-/// Layout a single line of flex items, returns the line's cross-axis size
+/// Cross-axis size of a wrapped line: the largest natural cross size of its items.
+/// Fill/Flex/Percent items don't contribute; they stretch to the line instead.
+fn line_cross_size(line: &[&Element], is_row: bool) -> u16 {
+    line.iter()
+        .map(|child| {
+            let (size, margin, min, max) = if is_row {
+                (
+                    child.height,
+                    child.margin.top + child.margin.bottom,
+                    child.min_height,
+                    child.max_height,
+                )
+            } else {
+                (
+                    child.width,
+                    child.margin.left + child.margin.right,
+                    child.min_width,
+                    child.max_width,
+                )
+            };
+            let natural = match size {
+                Size::Fixed(n) => n,
+                Size::Auto => estimate_size(child, !is_row),
+                Size::Fill | Size::Flex(_) | Size::Percent(_) => 0,
+            };
+            let natural = min.map_or(natural, |m| natural.max(m));
+            let natural = max.map_or(natural, |m| natural.min(m));
+            natural + margin
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Compute the start offset and spacing between wrapped lines on the cross axis.
+fn distribute_lines(
+    align_content: crate::types::Justify,
+    line_sizes: &[u16],
+    cross_size: u16,
+    gap: u16,
+) -> (u16, u16) {
+    use crate::types::Justify;
+
+    let count = line_sizes.len() as u16;
+    let total = line_sizes.iter().sum::<u16>() + gap * count.saturating_sub(1);
+    let extra_space = cross_size.saturating_sub(total);
+
+    match align_content {
+        Justify::Start => (0, gap),
+        Justify::End => (extra_space, gap),
+        Justify::Center => (extra_space / 2, gap),
+        Justify::SpaceBetween => {
+            if count > 1 {
+                (0, extra_space / (count - 1) + gap)
+            } else {
+                (0, gap)
+            }
+        }
+        Justify::SpaceAround => match extra_space.checked_div(count) {
+            Some(spacing) => (spacing / 2, spacing + gap),
+            None => (0, gap),
+        },
+    }
+}
+
+/// Resolve the main-axis sizes of a line's items.
+///
+/// Free space (positive or negative) is distributed by flex_grow/flex_shrink.
+/// Items whose result violates their min/max constraint are frozen at the
+/// clamped size and the remaining free space is redistributed among the
+/// others, so constraints neither waste space nor push the line past its size.
+fn resolve_flexible_lengths(
+    line: &[&Element],
+    base_sizes: &[u16],
+    grow_factors: &[u16],
+    fixed_space: u16,
+    main_size: u16,
+    is_row: bool,
+    allow_overflow: bool,
+) -> Vec<u16> {
+    let clamp = |child: &Element, size: u16| {
+        let (min_main, max_main) = if is_row {
+            (child.min_width, child.max_width)
+        } else {
+            (child.min_height, child.max_height)
+        };
+        let size = min_main.map_or(size, |m| size.max(m));
+        max_main.map_or(size, |m| size.min(m))
+    };
+
+    let mut sizes = base_sizes.to_vec();
+    let mut frozen = vec![false; line.len()];
+
+    loop {
+        // Free space left after frozen items and the bases of flexible ones
+        let used: i32 = fixed_space as i32
+            + (0..line.len())
+                .map(|i| {
+                    if frozen[i] {
+                        sizes[i] as i32
+                    } else {
+                        base_sizes[i] as i32
+                    }
+                })
+                .sum::<i32>();
+        let remaining = main_size as i32 - used;
+
+        let (total_grow, total_shrink) =
+            (0..line.len())
+                .filter(|&i| !frozen[i])
+                .fold((0u32, 0u32), |(grow, shrink), i| {
+                    (
+                        grow + grow_factors[i] as u32,
+                        shrink + line[i].flex_shrink as u32,
+                    )
+                });
+
+        let mut violated = false;
+        for (i, child) in line.iter().enumerate() {
+            if frozen[i] {
+                continue;
+            }
+            let base = base_sizes[i];
+            let target = if remaining > 0 && total_grow > 0 {
+                // Grow: distribute extra space proportionally
+                let grow_amount = remaining as u32 * grow_factors[i] as u32 / total_grow;
+                base.saturating_add(grow_amount.min(u16::MAX as u32) as u16)
+            } else if remaining < 0 && total_shrink > 0 && !allow_overflow {
+                // Shrink: reduce size proportionally (but not if parent allows overflow)
+                let shrink_amount = (-remaining) as u32 * child.flex_shrink as u32 / total_shrink;
+                base.saturating_sub(shrink_amount.min(u16::MAX as u32) as u16)
+            } else {
+                base
+            };
+
+            let constrained = clamp(child, target);
+            sizes[i] = constrained;
+            if constrained != target {
+                frozen[i] = true;
+                violated = true;
+            }
+        }
+
+        if !violated {
+            return sizes;
+        }
+    }
+}
+
+/// Layout a single line of flex items.
+///
+/// Items are aligned within `cross_offset..cross_size` on the cross axis.
 #[allow(clippy::too_many_arguments)]
 fn layout_line(
     line: &[&Element],
@@ -587,19 +949,18 @@ fn layout_line(
     result: &mut LayoutResult,
     animation: &AnimationState,
     scroll_ctx: Option<ScrollContext>,
-) -> u16 {
+) {
     if line.is_empty() {
-        return 0;
+        return;
     }
 
     let gap_total = parent.gap * line.len().saturating_sub(1) as u16;
 
     // First pass: calculate base sizes and collect flex info
     let mut base_sizes: Vec<u16> = Vec::with_capacity(line.len());
+    let mut grow_factors: Vec<u16> = Vec::with_capacity(line.len());
     let mut margins: Vec<(u16, u16)> = Vec::with_capacity(line.len());
-    let mut total_base = 0u16;
-    let mut total_flex_grow = 0u16;
-    let mut total_flex_shrink = 0u16;
+    let mut margin_total = 0u16;
 
     for child in line {
         let (margin_before, margin_after) = if is_row {
@@ -608,6 +969,7 @@ fn layout_line(
             (child.margin.top, child.margin.bottom)
         };
         margins.push((margin_before, margin_after));
+        margin_total += margin_before + margin_after;
 
         let child_main_size = if is_row { child.width } else { child.height };
         let (base, flex_grow) = match child_main_size {
@@ -626,15 +988,9 @@ fn layout_line(
         };
 
         base_sizes.push(base);
-        total_base += base + margin_before + margin_after;
-        total_flex_grow += flex_grow;
-        total_flex_shrink += child.flex_shrink;
+        grow_factors.push(flex_grow);
     }
 
-    // Calculate remaining space (positive = grow, negative = shrink)
-    let total_with_gaps = total_base + gap_total;
-    let remaining = main_size as i32 - total_with_gaps as i32;
-
     // Check if parent allows overflow in the main axis direction.
     // For rows, main axis is horizontal (check overflow_x).
     // For columns, main axis is vertical (check overflow_y).
@@ -645,43 +1001,16 @@ fn layout_line(
     };
     let allow_main_overflow = main_overflow == Overflow::Scroll || main_overflow == Overflow::Auto;
 
-    // Second pass: apply flex grow or shrink
-    let mut final_sizes: Vec<u16> = Vec::with_capacity(line.len());
-
-    for (i, child) in line.iter().enumerate() {
-        let base = base_sizes[i];
-        let child_main_size = if is_row { child.width } else { child.height };
-
-        let flex_grow = match child_main_size {
-            Size::Fill => 1.max(child.flex_grow),
-            Size::Flex(n) => n.max(child.flex_grow),
-            _ => child.flex_grow,
-        };
-
-        let adjusted = if remaining > 0 && total_flex_grow > 0 {
-            // Grow: distribute extra space proportionally
-            let grow_amount = (remaining as u32 * flex_grow as u32 / total_flex_grow as u32) as u16;
-            base + grow_amount
-        } else if remaining < 0 && total_flex_shrink > 0 && !allow_main_overflow {
-            // Shrink: reduce size proportionally (but not if parent allows overflow)
-            let shrink_amount =
-                ((-remaining) as u32 * child.flex_shrink as u32 / total_flex_shrink as u32) as u16;
-            base.saturating_sub(shrink_amount)
-        } else {
-            base
-        };
-
-        // Apply min/max constraints
-        let (min_main, max_main) = if is_row {
-            (child.min_width, child.max_width)
-        } else {
-            (child.min_height, child.max_height)
-        };
-        let constrained = min_main.map_or(adjusted, |m| adjusted.max(m));
-        let constrained = max_main.map_or(constrained, |m| constrained.min(m));
-
-        final_sizes.push(constrained);
-    }
+    // Second pass: apply flex grow or shrink within min/max constraints
+    let final_sizes = resolve_flexible_lengths(
+        line,
+        &base_sizes,
+        &grow_factors,
+        margin_total + gap_total,
+        main_size,
+        is_row,
+        allow_main_overflow,
+    );
 
     // Recalculate total for justify spacing
     let mut total_final = 0u16;
@@ -712,9 +1041,8 @@ fn layout_line(
         }
     };
 
-    // Third pass: position children and calculate line cross size
+    // Third pass: position children
     let mut main_offset = start_offset;
-    let mut line_cross_size = 0u16;
     let available_cross = cross_size.saturating_sub(cross_offset);
 
     for (i, child) in line.iter().enumerate() {
@@ -775,6 +1103,19 @@ fn layout_line(
             }
         };
 
+        // Aspect ratio: an Auto cross size follows the resolved main size
+        let cross = match child.aspect_ratio {
+            Some(ratio) if matches!(child_cross_size, Size::Auto) => {
+                let derived = aspect_convert(main, ratio, !is_row);
+                if allow_cross_overflow {
+                    derived
+                } else {
+                    derived.min(cross_available)
+                }
+            }
+            _ => cross,
+        };
+
         // Apply min/max on cross axis
         let (min_cross, max_cross) = if is_row {
             (child.min_height, child.max_height)
@@ -784,8 +1125,6 @@ fn layout_line(
         let cross = min_cross.map_or(cross, |m| cross.max(m));
         let cross = max_cross.map_or(cross, |m| cross.min(m));
 
-        line_cross_size = line_cross_size.max(cross + cross_margin_before + cross_margin_after);
-
         // Calculate cross-axis position
         let child_cross_offset = match child_align {
             Align::Start => cross_margin_before,
@@ -831,8 +1170,6 @@ fn layout_line(
 
         main_offset += margin_before + main + margin_after + between_gap;
     }
-
-    line_cross_size
 }
 
 /// Resolve a size value to a concrete pixel value.
@@ -898,16 +1235,35 @@ fn estimate_size(element: &Element, is_width: bool) -> u16 {
 }
 
 fn estimate_size_with_hint(element: &Element, is_width: bool, available_cross: Option<u16>) -> u16 {
+    let size = estimate_natural_size(element, is_width, available_cross);
+
+    // Min/max constraints bound the estimate so parents size around the clamped child
+    let (min, max) = if is_width {
+        (element.min_width, element.max_width)
+    } else {
+        (element.min_height, element.max_height)
+    };
+    let size = min.map_or(size, |m| size.max(m));
+    max.map_or(size, |m| size.min(m))
+}
+
+/// Natural size of an element from its explicit size or content, ignoring min/max.
+fn estimate_natural_size(element: &Element, is_width: bool, available_cross: Option<u16>) -> u16 {
     // Check for explicit Fixed size first - this takes precedence over content estimation
-    let explicit_size = if is_width {
-        element.width
+    let (explicit_size, other_size) = if is_width {
+        (element.width, element.height)
     } else {
-        element.height
+        (element.height, element.width)
     };
     if let Size::Fixed(n) = explicit_size {
         return n;
     }
 
+    // Aspect-ratio elements derive this axis from a fixed size on the other one
+    if let (Some(ratio), Size::Fixed(other)) = (element.aspect_ratio, other_size) {
+        return aspect_convert(other, ratio, is_width);
+    }
+
     let border_size = if element.style.border == crate::types::Border::None {
         0
     } else {
@@ -934,6 +1290,13 @@ fn estimate_size_with_hint(element: &Element, is_width: bool, available_cross: O
 
     let content_size = match &element.content {
         Content::Text(text) => {
+            let stripped;
+            let text = if element.ansi {
+                stripped = strip_ansi(text);
+                &stripped
+            } else {
+                text
+            };
             if is_width {
                 display_width(text) as u16
             } else if element.text_wrap != TextWrap::NoWrap {
@@ -988,6 +1351,28 @@ fn estimate_size_with_hint(element: &Element, is_width: bool, available_cross: O
                     .map(|c| estimate_size(c, is_width))
                     .sum::<u16>()
                     + gap_total
+            } else if let (Wrap::Wrap, Some(avail_main)) = (element.wrap, available_cross) {
+                // Wrapping container: stack the lines it would produce at the hinted size
+                let is_row = element.direction == Direction::Row;
+                let main_padding = if is_row {
+                    element.padding.horizontal_total()
+                } else {
+                    element.padding.vertical_total()
+                };
+                let inner_main = avail_main
+                    .saturating_sub(main_padding)
+                    .saturating_sub(border_size);
+                let children: Vec<&Element> = children
+                    .iter()
+                    .filter(|c| !c.position.is_out_of_flow())
+                    .collect();
+                let lines = split_into_lines(&children, inner_main, element.gap, is_row);
+                let gap_total = element.gap * lines.len().saturating_sub(1) as u16;
+                lines
+                    .iter()
+                    .map(|line| line_cross_size(line, is_row))
+                    .sum::<u16>()
+                    + gap_total
             } else {
                 // Max along cross axis
                 children
@@ -999,6 +1384,14 @@ fn estimate_size_with_hint(element: &Element, is_width: bool, available_cross: O
         }
         Content::None => 0,
         Content::Custom(_) => 10, // arbitrary default
+        Content::Image(image) => {
+            let (cols, rows) = image.cell_size(crate::image::DEFAULT_CELL_PIXELS);
+            if is_width {
+                cols
+            } else {
+                rows
+            }
+        }
         Content::Frames { children, .. } => {
             // Size to fit the largest frame
             children
@@ -1,5 +1,5 @@
 mod flex;
 mod rect;
 
-pub use flex::{layout, LayoutResult};
+pub use flex::{layout, LayoutResult, CELL_ASPECT};
 pub use rect::Rect;
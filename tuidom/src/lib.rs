@@ -5,6 +5,7 @@ pub mod element;
 pub mod event;
 pub mod focus;
 pub mod hit;
+pub mod image;
 pub mod layout;
 pub mod render;
 pub mod scroll;
@@ -21,9 +22,11 @@ pub use element::{find_element, Content, Element};
 pub use event::{Event, Key, Modifiers, MouseButton, NavDirection, ScrollAction};
 pub use focus::{collect_focusable, FocusState};
 pub use hit::{hit_test, hit_test_any, hit_test_focusable};
+pub use image::{Image, ImageData, ImageProtocol};
 pub use layout::{LayoutResult, Rect};
+pub use render::LayerCache;
 pub use scroll::{collect_scrollable, ScrollChange, ScrollOffset, ScrollState, ScrollbarGeometry};
-pub use terminal::Terminal;
+pub use terminal::{Capabilities, ResizeDebouncer, Terminal};
 pub use text_input::{TextEditResult, TextInputData, TextInputState};
 pub use transitions::{Easing, TransitionConfig, Transitions};
 pub use types::*;
@@ -0,0 +1,147 @@
+//! Offscreen layers.
+//!
+//! An element with `layer(true)` renders its subtree into a buffer the size of
+//! its own rect, with its top-left corner at (0, 0). The layer is then
+//! composited into the parent buffer at the element's position, clipped the
+//! same way the element itself would be.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::buffer::{Buffer, Cell};
+use crate::image::ImagePlacement;
+use crate::layout::Rect;
+
+/// Layer buffers kept between frames.
+///
+/// Layers with a `layer_key` are only re-rendered when the key or their size
+/// changes. Layers that weren't rendered in a frame are dropped at the end of it.
+#[derive(Debug, Default)]
+pub struct LayerCache {
+    layers: HashMap<String, CachedLayer>,
+    /// Layers used in the current frame.
+    seen: HashSet<String>,
+}
+
+#[derive(Debug)]
+struct CachedLayer {
+    key: Option<u64>,
+    buffer: Buffer,
+}
+
+impl LayerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of layer buffers held.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Drop all cached layers, forcing them to re-render.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+        self.seen.clear();
+    }
+
+    /// Take the buffer for layer `id` out of the cache. Returns the buffer
+    /// and whether its previous contents can be reused; otherwise it is blank.
+    /// Hand it back with [`store`](Self::store) once rendered.
+    pub(super) fn take(
+        &mut self,
+        id: &str,
+        key: Option<u64>,
+        width: u16,
+        height: u16,
+    ) -> (Buffer, bool) {
+        self.seen.insert(id.to_string());
+        match self.layers.remove(id) {
+            Some(layer) if layer.buffer.width() == width && layer.buffer.height() == height => {
+                let mut buffer = layer.buffer;
+                let valid = key.is_some() && layer.key == key;
+                if !valid {
+                    buffer.clear();
+                }
+                (buffer, valid)
+            }
+            _ => (Buffer::new(width, height), false),
+        }
+    }
+
+    pub(super) fn store(&mut self, id: &str, key: Option<u64>, buffer: Buffer) {
+        self.layers
+            .insert(id.to_string(), CachedLayer { key, buffer });
+    }
+
+    /// Drop layers that weren't used since the last call.
+    pub(super) fn end_frame(&mut self) {
+        let seen = std::mem::take(&mut self.seen);
+        self.layers.retain(|id, _| seen.contains(id));
+    }
+}
+
+/// Copy `layer` into `buf` with its (0, 0) at `origin`, limited to `visible`.
+///
+/// Cells the layer never drew are skipped, and cells without a background
+/// keep the one underneath, matching how elements render directly into the
+/// buffer.
+pub(super) fn composite(layer: &Buffer, buf: &mut Buffer, origin: (i16, i16), visible: Rect) {
+    let blank = Cell::default();
+    let top = visible.y.max(0);
+    let bottom = visible.bottom().min(buf.height() as i16);
+    let left = visible.x.max(0);
+    let right = visible.right().min(buf.width() as i16);
+
+    for y in top..bottom {
+        for x in left..right {
+            let (lx, ly) = (x - origin.0, y - origin.1);
+            if lx < 0 || ly < 0 {
+                continue;
+            }
+            let Some(&src) = layer.get(lx as u16, ly as u16) else {
+                continue;
+            };
+            if src == blank {
+                continue;
+            }
+
+            let mut cell = src;
+            if cell.bg.is_none() {
+                cell.bg = buf.get(x as u16, y as u16).and_then(|c| c.bg);
+            }
+            // Interned strings live in the layer's buffer; ids are derived
+            // from the content, so re-interning yields the same id
+            cell.link = src
+                .link
+                .and_then(|id| layer.link(id))
+                .map(|url| buf.intern_link(url));
+            cell.cluster = src
+                .cluster
+                .and_then(|id| layer.grapheme(id))
+                .and_then(|g| buf.intern_grapheme(g));
+            buf.set(x as u16, y as u16, cell);
+        }
+    }
+
+    // Terminal images can't be clipped, so only fully visible ones are kept
+    for placement in layer.images() {
+        let rect = Rect::new(
+            placement.rect.x + origin.0,
+            placement.rect.y + origin.1,
+            placement.rect.width,
+            placement.rect.height,
+        );
+        let inside =
+            rect.x >= left && rect.y >= top && rect.right() <= right && rect.bottom() <= bottom;
+        if inside {
+            buf.place_image(ImagePlacement {
+                image: placement.image.clone(),
+                rect,
+            });
+        }
+    }
+}
@@ -1,20 +1,27 @@
+mod layer;
+
 use std::collections::HashMap;
 use std::time::Instant;
 
 use crate::animation::{AnimationState, PropertyValue, TransitionProperty};
 use crate::buffer::{Buffer, Cell};
 use crate::element::{Content, Element};
+use crate::image::{Image, ImageData, ImagePlacement};
 use crate::layout::{LayoutResult, Rect};
+use crate::text::ansi::AnsiText;
 use crate::text::{
-    align_offset, char_width, display_width, truncate_to_width, wrap_chars, wrap_words,
+    align_offset, char_width, display_width, grapheme_width, graphemes, truncate_to_width,
+    wrap_chars, wrap_words,
 };
-use crate::types::{Backdrop, Color, ColorContext, ColorKey, Oklch, Overflow, TextWrap};
+pub use layer::LayerCache;
+
+use crate::types::{Backdrop, Color, ColorContext, ColorKey, Oklch, Overflow, Rgb, TextWrap};
 
 /// Cache for Color → Oklch conversions during render.
 /// Avoids repeated palette conversions for the same colors within a frame.
 /// Uses ColorContext to resolve Var and Derived colors before conversion.
 struct OklchCache<'a> {
-    cache: HashMap<ColorKey, Oklch>,
+    cache: HashMap<ColorKey, (Oklch, f32)>,
     color_ctx: &'a ColorContext<'a>,
 }
 
@@ -27,16 +34,30 @@ impl<'a> OklchCache<'a> {
     }
 
     fn get(&mut self, color: &Color) -> Oklch {
+        self.get_with_alpha(color).0
+    }
+
+    /// Resolve a color to Oklch along with its opacity.
+    fn get_with_alpha(&mut self, color: &Color) -> (Oklch, f32) {
         // First resolve any Var or Derived colors through the theme
         let resolved = self.color_ctx.resolve(color);
 
         if let Some(key) = resolved.cache_key() {
-            *self.cache.entry(key).or_insert_with(|| resolved.to_oklch())
+            *self
+                .cache
+                .entry(key)
+                .or_insert_with(|| (resolved.to_oklch(), resolved.opacity()))
         } else {
             // Shouldn't happen after resolve(), but handle gracefully
-            resolved.to_oklch()
+            (resolved.to_oklch(), resolved.opacity())
         }
     }
+
+    /// Color that translucent colors blend against where the buffer is
+    /// transparent (terminal default background).
+    fn base(&mut self) -> Oklch {
+        self.get(&Color::var("background"))
+    }
 }
 
 /// A render item contains an element with its z_index, tree order, and clip rect.
@@ -55,6 +76,12 @@ struct RenderItem<'a> {
     /// When a parent has overflow scroll, its children inherit this offset
     /// so their content renders shifted within the clipping region.
     inherited_scroll: (u16, u16),
+    /// Element renders its subtree into an offscreen layer; descendants were
+    /// not collected.
+    layer: bool,
+    /// Parent's cumulative layout offset, before `origin` was subtracted.
+    /// Layers start their own render pass from it.
+    parent_layout_offset: (i16, i16),
 }
 
 /// Timing stats for render operations (in microseconds).
@@ -76,6 +103,73 @@ pub fn render_to_buffer(
     buf: &mut Buffer,
     animation: &AnimationState,
     color_ctx: &ColorContext,
+) {
+    render_to_buffer_with_layers(
+        element,
+        layout,
+        buf,
+        animation,
+        color_ctx,
+        &mut LayerCache::new(),
+    );
+}
+
+/// Like [`render_to_buffer`], keeping layer buffers in `layers` so layers
+/// with an unchanged `layer_key` aren't re-rendered next frame.
+pub fn render_to_buffer_with_layers(
+    element: &Element,
+    layout: &LayoutResult,
+    buf: &mut Buffer,
+    animation: &AnimationState,
+    color_ctx: &ColorContext,
+    layers: &mut LayerCache,
+) {
+    let mut stats = RenderStats::default();
+
+    // Create color cache for this frame (with theme resolution)
+    let mut oklch_cache = OklchCache::new(color_ctx);
+
+    render_tree(
+        element,
+        layout,
+        buf,
+        animation,
+        &mut oklch_cache,
+        layers,
+        None,
+        (0, 0),
+        &mut stats,
+    );
+    layers.end_frame();
+
+    log::debug!(
+        "  render breakdown: collect={:>6.2}µs sort={:>6.2}µs bg={:>6.2}µs border={:>6.2}µs text={:>6.2}µs scrollbar={:>6.2}µs other={:>6.2}µs elements={}",
+        stats.collect_us,
+        stats.sort_us,
+        stats.background_us,
+        stats.border_us,
+        stats.text_us,
+        stats.scrollbar_us,
+        stats.other_us,
+        stats.element_count,
+    );
+}
+
+/// Render `element` and its descendants. `layer_origin` is set when rendering
+/// a layer's subtree into its own buffer: the absolute position of the layer,
+/// which is subtracted from every rect. `root_offset` is the animation offset
+/// inherited from the element's ancestors.
+#[allow(clippy::too_many_arguments)]
+fn render_tree(
+    element: &Element,
+    layout: &LayoutResult,
+    buf: &mut Buffer,
+    animation: &AnimationState,
+    oklch_cache: &mut OklchCache,
+    layers: &mut LayerCache,
+    layer_origin: Option<(i16, i16)>,
+    root_offset: (i16, i16),
+    stats: &mut RenderStats,
 ) {
     let t0 = Instant::now();
 
@@ -89,11 +183,12 @@ pub fn render_to_buffer(
         0,
         element.z_index,
         None,
-        (0, 0), // Initial layout offset
+        root_offset,
         (0, 0), // Initial inherited scroll
         animation,
         0, // Initial scope_order (root stacking context)
         &mut next_scope_order,
+        layer_origin.unwrap_or((0, 0)),
     );
     let t1 = Instant::now();
 
@@ -103,48 +198,104 @@ pub fn render_to_buffer(
     let t2 = Instant::now();
 
     // Track per-operation timing
-    let mut stats = RenderStats {
-        collect_us: t1.duration_since(t0).as_secs_f64() * 1_000_000.0,
-        sort_us: t2.duration_since(t1).as_secs_f64() * 1_000_000.0,
-        element_count: render_list.len(),
-        ..Default::default()
-    };
-
-    // Create color cache for this frame (with theme resolution)
-    let mut oklch_cache = OklchCache::new(color_ctx);
+    stats.collect_us += t1.duration_since(t0).as_secs_f64() * 1_000_000.0;
+    stats.sort_us += t2.duration_since(t1).as_secs_f64() * 1_000_000.0;
+    stats.element_count += render_list.len();
 
     // Render in sorted order
     for item in render_list {
-        render_single_element_timed(
-            item.element,
+        // Apply backdrop BEFORE rendering this element (dims entire buffer).
+        // A layer's own backdrop was applied to the buffer it composites into.
+        if layer_origin.is_none() || item.tree_order != 0 {
+            apply_backdrop(buf, &item.element.backdrop);
+        }
+
+        if item.layer {
+            render_layer(
+                &item,
+                layout,
+                buf,
+                layer_origin.unwrap_or((0, 0)),
+                animation,
+                oklch_cache,
+                layers,
+                stats,
+            );
+        } else {
+            render_single_element_timed(
+                item.element,
+                layout,
+                buf,
+                item.clip,
+                item.layout_offset,
+                item.inherited_scroll,
+                animation,
+                stats,
+                oklch_cache,
+            );
+        }
+    }
+}
+
+/// Render a layer element's subtree into its offscreen buffer (unless the
+/// cached one is still valid) and composite it into `buf`.
+#[allow(clippy::too_many_arguments)]
+fn render_layer(
+    item: &RenderItem,
+    layout: &LayoutResult,
+    buf: &mut Buffer,
+    buf_origin: (i16, i16),
+    animation: &AnimationState,
+    oklch_cache: &mut OklchCache,
+    layers: &mut LayerCache,
+    stats: &mut RenderStats,
+) {
+    let element = item.element;
+    let Some(layout_rect) = layout.get(&element.id) else {
+        return;
+    };
+    // Position within `buf`, and in absolute coordinates for the layer's own pass
+    let origin = (
+        layout_rect.x + item.layout_offset.0,
+        layout_rect.y + item.layout_offset.1,
+    );
+    let absolute = (origin.0 + buf_origin.0, origin.1 + buf_origin.1);
+    let rect = Rect::new(origin.0, origin.1, layout_rect.width, layout_rect.height);
+    let visible = intersect_rects(rect, item.clip);
+    if visible.width == 0 || visible.height == 0 {
+        return;
+    }
+
+    let (mut layer_buf, cached) = layers.take(
+        &element.id,
+        element.layer_key,
+        layout_rect.width,
+        layout_rect.height,
+    );
+    if !cached {
+        render_tree(
+            element,
             layout,
-            buf,
-            item.clip,
-            item.layout_offset,
-            item.inherited_scroll,
+            &mut layer_buf,
             animation,
-            &mut stats,
-            &mut oklch_cache,
+            oklch_cache,
+            layers,
+            Some(absolute),
+            item.parent_layout_offset,
+            stats,
         );
     }
-
-    log::debug!(
-        "  render breakdown: collect={:>6.2}µs sort={:>6.2}µs bg={:>6.2}µs border={:>6.2}µs text={:>6.2}µs scrollbar={:>6.2}µs other={:>6.2}µs elements={}",
-        stats.collect_us,
-        stats.sort_us,
-        stats.background_us,
-        stats.border_us,
-        stats.text_us,
-        stats.scrollbar_us,
-        stats.other_us,
-        stats.element_count,
-    );
+    layer::composite(&layer_buf, buf, origin, visible);
+    layers.store(&element.id, element.layer_key, layer_buf);
 }
 
 /// Collect all elements in tree order with their effective z_index and clip rects.
 /// Children inherit their parent's z_index as a minimum (they render in the same layer or higher).
 /// Clip rects are computed based on ancestors with overflow != Visible.
 /// Stacking contexts: elements with interaction_scope=true start a new stacking context.
+/// Layers: descendants of a layer element (other than the root) are not collected;
+/// they render when the layer does. `origin` is subtracted from all positions.
+#[allow(clippy::too_many_arguments)]
 fn collect_elements<'a>(
     element: &'a Element,
     layout: &LayoutResult,
@@ -157,6 +308,7 @@ fn collect_elements<'a>(
     animation: &AnimationState,
     parent_scope_order: usize,
     next_scope_order: &mut usize,
+    origin: (i16, i16),
 ) -> usize {
     let mut order = tree_order;
 
@@ -252,12 +404,14 @@ fn collect_elements<'a>(
                 scrollbar_right,
                 scrollbar_bottom
             );
-            let inner = rect.shrink(
+            let mut inner = rect.shrink(
                 element.padding.top + border_size,
                 element.padding.right + border_size + scrollbar_right,
                 element.padding.bottom + border_size + scrollbar_bottom,
                 element.padding.left + border_size,
             );
+            inner.x -= origin.0;
+            inner.y -= origin.1;
             log::debug!(
                 "[clip] id={} rect=({},{},{},{}) inner=({},{},{},{}) scrollbar_reserve=({},{})",
                 element.id,
@@ -281,17 +435,29 @@ fn collect_elements<'a>(
         parent_clip
     };
 
+    // The root of a render pass is never a layer (it is the layer being rendered)
+    let is_layer = element.layer && tree_order > 0;
+
     list.push(RenderItem {
         element,
         scope_order,
         z_index: effective_z,
         tree_order: order,
         clip: parent_clip, // This element is clipped by parent's clip
-        layout_offset: cumulative_offset,
+        layout_offset: (
+            cumulative_offset.0 - origin.0,
+            cumulative_offset.1 - origin.1,
+        ),
         inherited_scroll: parent_inherited_scroll,
+        layer: is_layer,
+        parent_layout_offset,
     });
     order += 1;
 
+    if is_layer {
+        return order;
+    }
+
     // Accumulate scroll offset for children.
     // When this element has overflow scroll, its children inherit this scroll offset
     // so their content is rendered shifted within the clipping region.
@@ -324,6 +490,7 @@ fn collect_elements<'a>(
                     animation,
                     scope_order,
                     next_scope_order,
+                    origin,
                 );
             }
         }
@@ -345,6 +512,7 @@ fn collect_elements<'a>(
                         animation,
                         scope_order,
                         next_scope_order,
+                        origin,
                     );
                 }
             }
@@ -384,9 +552,6 @@ fn render_single_element_timed(
 ) {
     let t0 = Instant::now();
 
-    // Apply backdrop BEFORE rendering this element (dims entire buffer)
-    apply_backdrop(buf, &element.backdrop);
-
     let Some(layout_rect) = layout.get(&element.id) else {
         return;
     };
@@ -427,7 +592,7 @@ fn render_single_element_timed(
 
     // Render background if set (only within visible area)
     if let Some(bg) = background {
-        let oklch = oklch_cache.get(&bg);
+        let (oklch, alpha) = oklch_cache.get_with_alpha(&bg);
         // Debug logging for focused rows
         if element.id.contains("-scrollable-") || element.id.contains("-frozen-") {
             log::debug!(
@@ -448,7 +613,12 @@ fn render_single_element_timed(
                 oklch
             );
         }
-        fill_rect(buf, visible_rect, Some(oklch));
+        if alpha >= 1.0 {
+            fill_rect(buf, visible_rect, Some(oklch));
+        } else if alpha > 0.0 {
+            let base = oklch_cache.base();
+            blend_rect(buf, visible_rect, oklch, alpha, base);
+        }
     }
     let t2 = Instant::now();
     stats.background_us += t2.duration_since(t1).as_secs_f64() * 1_000_000.0;
@@ -500,6 +670,9 @@ fn render_single_element_timed(
             // Custom content gets the full rect; it should handle clipping internally
             custom.render(rect, buf);
         }
+        Content::Image(image) => {
+            render_image(image, rect, buf, clip);
+        }
     }
     let t4 = Instant::now();
     stats.text_us += t4.duration_since(t3).as_secs_f64() * 1_000_000.0;
@@ -527,16 +700,112 @@ fn get_interpolated_color(
     current.cloned()
 }
 
+/// Render an image's text-mode fallback and record its placement.
+///
+/// RGBA images are approximated with half blocks (two pixels per cell), other
+/// formats show their alt text. The terminal replaces the fallback with real
+/// graphics when it supports a protocol. Placements are only recorded when the
+/// image is fully visible, since graphics protocols can't clip.
+fn render_image(image: &Image, rect: Rect, buf: &mut Buffer, clip: Option<Rect>) {
+    let visible = intersect_rects(rect, clip);
+    if visible.width == 0 || visible.height == 0 {
+        return;
+    }
+
+    match image.data() {
+        ImageData::Rgba {
+            width,
+            height,
+            pixels,
+        } => {
+            let pixel = |px: u32, py: u32| -> Option<Oklch> {
+                let i = ((py * width + px) * 4) as usize;
+                let p = &pixels[i..i + 4];
+                (p[3] >= 128).then(|| Oklch::from_rgb(Rgb::new(p[0], p[1], p[2])))
+            };
+            let rows = rect.height as u32 * 2;
+            for y in visible.y.max(0)..visible.bottom().min(buf.height() as i16) {
+                for x in visible.x.max(0)..visible.right().min(buf.width() as i16) {
+                    let px = (x - rect.x) as u32 * width / rect.width as u32;
+                    let cy = (y - rect.y) as u32 * 2;
+                    let top = pixel(px, cy * height / rows);
+                    let bottom = pixel(px, (cy + 1) * height / rows);
+                    let Some(cell) = buf.get_mut(x as u16, y as u16) else {
+                        continue;
+                    };
+                    // Transparent pixels keep whatever is behind them
+                    let (ch, fg, bg) = match (top, bottom) {
+                        (Some(t), Some(b)) => ('▀', t, Some(b)),
+                        (Some(t), None) => ('▀', t, cell.bg),
+                        (None, Some(b)) => ('▄', b, cell.bg),
+                        (None, None) => continue,
+                    };
+                    *cell = Cell::new(ch).with_fg(fg).with_bg(bg);
+                }
+            }
+        }
+        ImageData::Png(_) => {
+            let alt = image.alt_text().unwrap_or("[image]");
+            let text = truncate_to_width(alt, visible.width as usize);
+            let y = rect.y;
+            if y >= visible.y && y < visible.bottom() && y >= 0 {
+                let mut x = rect.x;
+                for g in graphemes(&text) {
+                    let cluster = buf.intern_grapheme(g);
+                    if x >= visible.x && x < visible.right() && x >= 0 {
+                        if let Some(cell) = buf.get_mut(x as u16, y as u16) {
+                            cell.char = g.chars().next().unwrap_or(' ');
+                            cell.cluster = cluster;
+                            cell.wide_continuation = false;
+                        }
+                    }
+                    x += grapheme_width(g) as i16;
+                }
+            }
+        }
+    }
+
+    if visible == rect {
+        buf.place_image(ImagePlacement {
+            image: image.clone(),
+            rect,
+        });
+    }
+}
+
+/// Composite a translucent color over the cells in `rect`.
+///
+/// Unlike `fill_rect` the existing content stays visible: characters are kept
+/// and both their foreground and background are tinted toward `color`.
+/// Transparent cells blend against `base`.
+fn blend_rect(buf: &mut Buffer, rect: Rect, color: Oklch, alpha: f32, base: Oklch) {
+    for y in rect.y.max(0)..rect.bottom().min(buf.height() as i16) {
+        for x in rect.x.max(0)..rect.right().min(buf.width() as i16) {
+            if let Some(cell) = buf.get_mut(x as u16, y as u16) {
+                let under = cell.bg.unwrap_or(base);
+                cell.bg = Some(under.blend(color, alpha));
+                cell.fg = cell.fg.blend(color, alpha);
+                cell.underline_color = cell.underline_color.map(|u| u.blend(color, alpha));
+            }
+        }
+    }
+}
+
 fn fill_rect(buf: &mut Buffer, rect: Rect, bg: Option<Oklch>) {
     let Some(bg_color) = bg else { return }; // Skip if transparent
     for y in rect.y.max(0)..rect.bottom().min(buf.height() as i16) {
         for x in rect.x.max(0)..rect.right().min(buf.width() as i16) {
             if let Some(cell) = buf.get_mut(x as u16, y as u16) {
                 // Skip if cell already has correct state
-                if cell.bg == Some(bg_color) && cell.char == ' ' && !cell.wide_continuation {
+                if cell.bg == Some(bg_color)
+                    && cell.char == ' '
+                    && cell.cluster.is_none()
+                    && !cell.wide_continuation
+                {
                     continue;
                 }
                 cell.char = ' ';
+                cell.cluster = None;
                 cell.bg = Some(bg_color);
                 cell.wide_continuation = false;
             }
@@ -561,10 +830,11 @@ fn render_text(
         TransitionProperty::Foreground,
         element.style.foreground.as_ref(),
     );
-    let fg = foreground
+    let (fg, fg_alpha) = foreground
         .as_ref()
-        .map(|c| oklch_cache.get(c))
-        .unwrap_or(Oklch::new(1.0, 0.0, 0.0)); // white
+        .map(|c| oklch_cache.get_with_alpha(c))
+        .unwrap_or((Oklch::new(1.0, 0.0, 0.0), 1.0)); // white
+    let base = oklch_cache.base();
 
     // Get background color (potentially interpolated)
     let background = get_interpolated_color(
@@ -573,7 +843,17 @@ fn render_text(
         TransitionProperty::Background,
         element.style.background.as_ref(),
     );
-    let explicit_bg = background.as_ref().map(|c| oklch_cache.get(c));
+    // Translucent backgrounds were already blended into the buffer
+    let explicit_bg = background
+        .as_ref()
+        .map(|c| oklch_cache.get_with_alpha(c))
+        .filter(|(_, alpha)| *alpha >= 1.0)
+        .map(|(bg, _)| bg);
+    let underline_color = element
+        .style
+        .underline_color
+        .as_ref()
+        .map(|c| oklch_cache.get(c));
 
     let border_size = if element.style.border == crate::types::Border::None {
         0
@@ -592,6 +872,13 @@ fn render_text(
         return;
     }
 
+    let link = element.link.as_deref().map(|url| buf.intern_link(url));
+
+    // Lay out the printable text; SGR styling is looked up per cluster
+    let ansi = element.ansi.then(|| AnsiText::parse(text));
+    let text = ansi.as_ref().map_or(text, |ansi| ansi.text.as_str());
+    let mut source_pos = 0;
+
     let max_width = inner.width as usize;
     // Note: horizontal scroll is now applied to element position in render_element,
     // so we don't need to handle it here for text content
@@ -642,12 +929,17 @@ fn render_text(
         // logical_x is the offset from (inner.x + x_offset)
         let mut logical_x: i16 = 0;
 
-        // Render characters with scroll offset applied
-        for ch in line.chars() {
-            let ch_w = char_width(ch);
+        // Render grapheme clusters with scroll offset applied
+        for g in graphemes(line) {
+            // Escape-sequence styling for this cluster, if any
+            let sgr = ansi.as_ref().and_then(|ansi| {
+                locate_grapheme(text, &mut source_pos, g).and_then(|pos| ansi.style_at(pos))
+            });
+
+            let ch_w = grapheme_width(g);
 
             if ch_w == 0 {
-                // Zero-width char (combining mark, etc.) - attach to previous cell
+                // Stray zero-width cluster - nothing to draw
                 continue;
             }
 
@@ -673,14 +965,41 @@ fn render_text(
 
             // Preserve existing background if no explicit background set
             let bg = explicit_bg.or_else(|| buf.get(render_x as u16, y as u16).and_then(|c| c.bg));
+            let fg = if fg_alpha < 1.0 {
+                bg.unwrap_or(base).blend(fg, fg_alpha)
+            } else {
+                fg
+            };
+            let mut text_style = element.style.text_style;
+            let mut underline_color = underline_color;
+            let (fg, bg) = match sgr {
+                Some(sgr) => {
+                    let fg = sgr.fg.as_ref().map_or(fg, |c| oklch_cache.get(c));
+                    let bg = sgr.bg.as_ref().map(|c| oklch_cache.get(c)).or(bg);
+                    text_style = text_style.merge(&sgr.text_style);
+                    if let Some(color) = &sgr.underline_color {
+                        underline_color = Some(oklch_cache.get(color));
+                    }
+                    if sgr.reverse {
+                        (bg.unwrap_or(base), Some(fg))
+                    } else {
+                        (fg, bg)
+                    }
+                }
+                None => (fg, bg),
+            };
 
+            let cluster = buf.intern_grapheme(g);
             buf.set(
                 render_x as u16,
                 y as u16,
-                Cell::new(ch)
+                Cell::new(g.chars().next().unwrap_or(' '))
+                    .with_cluster(cluster)
                     .with_fg(fg)
                     .with_bg(bg)
-                    .with_style(element.style.text_style),
+                    .with_style(text_style)
+                    .with_underline_color(underline_color)
+                    .with_link(link),
             );
 
             // For wide chars (CJK), fill the next cell with a continuation marker
@@ -691,7 +1010,9 @@ fn render_text(
                     let mut continuation = Cell::new(' ')
                         .with_fg(fg)
                         .with_bg(bg)
-                        .with_style(element.style.text_style);
+                        .with_style(text_style)
+                        .with_underline_color(underline_color)
+                        .with_link(link);
                     continuation.wide_continuation = true;
                     buf.set(cont_x as u16, y as u16, continuation);
                 }
@@ -702,6 +1023,26 @@ fn render_text(
     }
 }
 
+/// Find the byte offset of the wrapped line's cluster `g` in the unwrapped
+/// `text`, searching from `pos`. Wrapping only drops or collapses whitespace,
+/// so anything else in between means `g` was added (e.g. an ellipsis).
+fn locate_grapheme(text: &str, pos: &mut usize, g: &str) -> Option<usize> {
+    let is_space = |s: &str| s.chars().all(char::is_whitespace);
+    let mut offset = *pos;
+    for candidate in graphemes(&text[*pos..]) {
+        let start = offset;
+        offset += candidate.len();
+        if candidate == g || (is_space(candidate) && is_space(g)) {
+            *pos = offset;
+            return Some(start);
+        }
+        if !is_space(candidate) {
+            return None;
+        }
+    }
+    None
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_text_input(
     value: &str,
@@ -737,6 +1078,11 @@ fn render_text_input(
         element.style.background.as_ref(),
     );
     let bg = background.as_ref().map(|c| oklch_cache.get(c));
+    let underline_color = element
+        .style
+        .underline_color
+        .as_ref()
+        .map(|c| oklch_cache.get(c));
 
     // Cursor style from theme
     let cursor_fg = oklch_cache.get(&Color::var("input.cursor_fg"));
@@ -775,7 +1121,8 @@ fn render_text_input(
         value
     };
 
-    let chars: Vec<char> = display_text.chars().collect();
+    // Cursor and selection are grapheme indices
+    let clusters: Vec<&str> = graphemes(display_text).collect();
     let y = inner.y;
 
     // Check off-screen or vertical clip
@@ -793,21 +1140,21 @@ fn render_text_input(
     let visible_width = inner.width as usize;
     let cursor_margin = visible_width.min(5);
 
-    // Get display width for a character (uses mask width if masked)
-    let display_char_width = |ch: char| -> usize {
+    // Get display width for a cluster (uses mask width if masked)
+    let display_char_width = |g: &str| -> usize {
         if is_placeholder {
-            char_width(ch)
+            grapheme_width(g)
         } else {
-            mask.map(char_width).unwrap_or_else(|| char_width(ch))
+            mask.map(char_width).unwrap_or_else(|| grapheme_width(g))
         }
     };
 
     let scroll_offset = if focused && !is_placeholder && visible_width > cursor_margin {
         // Calculate the display width up to the cursor (including cursor position)
-        let width_to_cursor: usize = chars
+        let width_to_cursor: usize = clusters
             .iter()
             .take(cursor)
-            .map(|&c| display_char_width(c))
+            .map(|g| display_char_width(g))
             .sum::<usize>()
             + 1; // +1 for cursor itself
 
@@ -819,11 +1166,11 @@ fn render_text_input(
             // Convert target scroll width to character offset
             let mut offset = 0;
             let mut skipped_width = 0;
-            for &ch in &chars {
+            for g in &clusters {
                 if skipped_width >= target_scroll_width {
                     break;
                 }
-                skipped_width += display_char_width(ch);
+                skipped_width += display_char_width(g);
                 offset += 1;
             }
             offset
@@ -836,28 +1183,28 @@ fn render_text_input(
 
     let mut x = inner.x;
 
-    for (i, &ch) in chars.iter().enumerate().skip(scroll_offset) {
+    for (i, &g) in clusters.iter().enumerate().skip(scroll_offset) {
         if x >= inner.right() {
             break;
         }
 
         // Determine the character to display (mask for password fields, unless placeholder)
-        let display_ch = if is_placeholder {
-            ch
-        } else {
-            mask.unwrap_or(ch)
+        let ch_w = display_char_width(g);
+        let (display_ch, cluster) = match mask {
+            Some(m) if !is_placeholder => (m, None),
+            _ => (g.chars().next().unwrap_or(' '), buf.intern_grapheme(g)),
         };
 
         // Skip if off-screen left
         if x < 0 {
-            x += char_width(display_ch) as i16;
+            x += ch_w as i16;
             continue;
         }
 
         // Check horizontal clip
         if let Some(c) = clip {
             if x < c.x {
-                x += char_width(display_ch) as i16;
+                x += ch_w as i16;
                 continue;
             }
             if x >= c.right() {
@@ -889,19 +1236,21 @@ fn render_text_input(
             x as u16,
             y as u16,
             Cell::new(display_ch)
+                .with_cluster(cluster)
                 .with_fg(char_fg)
                 .with_bg(char_bg)
-                .with_style(element.style.text_style),
+                .with_style(element.style.text_style)
+                .with_underline_color(underline_color),
         );
 
-        let ch_w = char_width(display_ch);
         if ch_w == 2 && x + 1 < inner.right() {
             let cont_x = x + 1;
             if cont_x >= 0 && clip.is_none_or(|c| cont_x >= c.x && cont_x < c.right()) {
                 let mut continuation = Cell::new(' ')
                     .with_fg(char_fg)
                     .with_bg(char_bg)
-                    .with_style(element.style.text_style);
+                    .with_style(element.style.text_style)
+                    .with_underline_color(underline_color);
                 continuation.wide_continuation = true;
                 buf.set(cont_x as u16, y as u16, continuation);
             }
@@ -912,11 +1261,11 @@ fn render_text_input(
 
     // If cursor is at end and focused, render cursor block
     // Account for scroll offset when determining if cursor is visible
-    if focused && cursor >= chars.len() {
-        let width_to_cursor: usize = chars
+    if focused && cursor >= clusters.len() {
+        let width_to_cursor: usize = clusters
             .iter()
             .skip(scroll_offset)
-            .map(|&c| display_char_width(c))
+            .map(|g| display_char_width(g))
             .sum();
         let cursor_x = inner.x + width_to_cursor as i16;
         if cursor_x >= 0
@@ -1014,6 +1363,7 @@ fn set_char(buf: &mut Buffer, x: i16, y: i16, ch: char, fg: Oklch) {
     }
     if let Some(cell) = buf.get_mut(x as u16, y as u16) {
         cell.char = ch;
+        cell.cluster = None;
         cell.fg = fg;
         // Preserve existing background
     }
@@ -1117,6 +1467,7 @@ fn render_scrollbar(
                         if let Some(cell) = buf.get_mut(x as u16, y as u16) {
                             let in_thumb = y >= thumb_start && y < thumb_end;
                             cell.char = if in_thumb { '█' } else { '░' };
+                            cell.cluster = None;
                             cell.fg = if in_thumb { thumb_color } else { track_color };
                         }
                     }
@@ -1154,6 +1505,7 @@ fn render_scrollbar(
                         if let Some(cell) = buf.get_mut(x as u16, y as u16) {
                             let in_thumb = x >= thumb_start && x < thumb_end;
                             cell.char = if in_thumb { '█' } else { '░' };
+                            cell.cluster = None;
                             cell.fg = if in_thumb { thumb_color } else { track_color };
                         }
                     }
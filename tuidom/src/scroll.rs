@@ -8,7 +8,7 @@ use crate::layout::{LayoutResult, Rect};
 use crate::types::Overflow;
 
 /// Scroll offset for a scrollable element.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct ScrollOffset {
     pub x: u16,
     pub y: u16,
@@ -185,6 +185,9 @@ pub struct ScrollState {
     last_mouse_pos: Option<(u16, u16)>,
     /// Current scrollbar drag state
     drag: Option<ScrollbarDrag>,
+    /// Scroll positions as a fraction of the scroll range, captured on resize
+    /// and applied once the new layout is known (see `apply_reflow`).
+    reflow: HashMap<String, (f32, f32)>,
 }
 
 impl ScrollState {
@@ -314,6 +317,54 @@ impl ScrollState {
             .insert(id.to_string(), (content_width, content_height));
     }
 
+    /// Remember each scroll position as a fraction of its current scroll range.
+    /// Called on `Event::Resize` with the layout from before the resize.
+    pub fn capture_reflow(&mut self, layout: &LayoutResult) {
+        self.reflow.clear();
+        for (id, offset) in &self.offsets {
+            let (Some((cw, ch)), Some((vw, vh))) =
+                (layout.content_size(id), layout.viewport_size(id))
+            else {
+                continue;
+            };
+            let fraction = |pos: u16, max: u16| {
+                if max == 0 {
+                    0.0
+                } else {
+                    (pos.min(max) as f32) / max as f32
+                }
+            };
+            self.reflow.insert(
+                id.clone(),
+                (
+                    fraction(offset.x, cw.saturating_sub(vw)),
+                    fraction(offset.y, ch.saturating_sub(vh)),
+                ),
+            );
+        }
+    }
+
+    /// Restore positions captured by `capture_reflow` against the layout at the
+    /// new size, so the same part of the content stays in view when text
+    /// rewraps. Returns true if any offset changed (the frame needs a re-render).
+    pub fn apply_reflow(&mut self, layout: &LayoutResult) -> bool {
+        let mut changed = false;
+        for (id, (fx, fy)) in std::mem::take(&mut self.reflow) {
+            let (Some((cw, ch)), Some((vw, vh))) =
+                (layout.content_size(&id), layout.viewport_size(&id))
+            else {
+                continue;
+            };
+            let x = (fx * cw.saturating_sub(vw) as f32).round() as u16;
+            let y = (fy * ch.saturating_sub(vh) as f32).round() as u16;
+            if self.get(&id) != ScrollOffset::new(x, y) {
+                self.set(&id, x, y);
+                changed = true;
+            }
+        }
+        changed
+    }
+
     /// Get the content size for a scrollable element (set during layout).
     pub fn content_size(&self, id: &str) -> Option<(u16, u16)> {
         self.content_sizes.get(id).copied()
@@ -336,6 +387,11 @@ impl ScrollState {
                     self.last_mouse_pos = Some((*x, *y));
                 }
 
+                // Layout still reflects the old size here
+                Event::Resize { .. } => {
+                    self.capture_reflow(layout);
+                }
+
                 Event::Scroll {
                     target,
                     delta_x,
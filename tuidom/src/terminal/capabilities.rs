@@ -0,0 +1,86 @@
+//! Terminal feature detection.
+//!
+//! Terminals don't reliably answer capability queries, so detection is based
+//! on the environment variables well-known terminals set. Unknown terminals
+//! get the conservative answer and the plain fallback.
+
+/// Optional terminal features tuidom can make use of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+    /// Synchronized updates (DEC mode 2026), which present a frame atomically.
+    pub synchronized_output: bool,
+}
+
+impl Capabilities {
+    /// Detect capabilities from the environment.
+    pub fn detect() -> Self {
+        Self::detect_from(|name| std::env::var(name).ok())
+    }
+
+    /// Detect capabilities using the given environment lookup.
+    pub fn detect_from(env: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            hyperlinks: detect_hyperlinks(&env),
+            synchronized_output: detect_synchronized_output(&env),
+        }
+    }
+}
+
+fn detect_hyperlinks(env: &impl Fn(&str) -> Option<String>) -> bool {
+    // Explicit override, e.g. FORCE_HYPERLINK=0 inside a multiplexer
+    if let Some(force) = env("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    let term = env("TERM").unwrap_or_default();
+    if term == "dumb" || term == "linux" {
+        return false;
+    }
+
+    let term_program = env("TERM_PROGRAM").unwrap_or_default();
+    if matches!(
+        term_program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) {
+        return true;
+    }
+
+    // VTE gained OSC 8 in 0.50 (VTE_VERSION 5000)
+    if env("VTE_VERSION")
+        .and_then(|v| v.parse::<u32>().ok())
+        .is_some_and(|v| v >= 5000)
+    {
+        return true;
+    }
+
+    env("KITTY_WINDOW_ID").is_some()
+        || env("WT_SESSION").is_some()
+        || env("KONSOLE_VERSION").is_some()
+        || ["kitty", "alacritty", "foot", "ghostty", "wezterm"]
+            .iter()
+            .any(|name| term.contains(name))
+}
+
+fn detect_synchronized_output(env: &impl Fn(&str) -> Option<String>) -> bool {
+    let term = env("TERM").unwrap_or_default();
+    let term_program = env("TERM_PROGRAM").unwrap_or_default();
+
+    matches!(
+        term_program.as_str(),
+        "iTerm.app" | "WezTerm" | "ghostty" | "vscode"
+    ) || env("KITTY_WINDOW_ID").is_some()
+        || env("WT_SESSION").is_some()
+        || env("ALACRITTY_WINDOW_ID").is_some()
+        || [
+            "kitty",
+            "foot",
+            "ghostty",
+            "wezterm",
+            "alacritty",
+            "contour",
+        ]
+        .iter()
+        .any(|name| term.contains(name))
+}
@@ -1,3 +1,6 @@
+mod capabilities;
+mod resize;
+
 use std::collections::HashMap;
 use std::io::{self, Write};
 use std::sync::Arc;
@@ -7,17 +10,24 @@ use crossterm::{
     cursor,
     event::{self, Event as CrosstermEvent},
     execute,
-    style::{Attribute, Color as CtColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    style::{
+        Attribute, Color as CtColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+        SetUnderlineColor,
+    },
     terminal,
 };
 
 use crate::animation::AnimationState;
-use crate::buffer::Buffer;
+use crate::buffer::{Buffer, Cell};
 use crate::element::Element;
+use crate::image::{ImageProtocol, DEFAULT_CELL_PIXELS, KITTY_DELETE_ALL};
 use crate::layout::{layout, LayoutResult, Rect};
-use crate::render::render_to_buffer;
-use crate::text::char_width;
-use crate::types::{ColorContext, EmptyTheme, Oklch, Rgb, Theme};
+use crate::render::{render_to_buffer_with_layers, LayerCache};
+use crate::text::{char_width, grapheme_width};
+use crate::types::{ColorContext, EmptyTheme, Oklch, Rgb, Theme, UnderlineStyle};
+
+pub use capabilities::Capabilities;
+pub use resize::{ResizeDebouncer, DEFAULT_RESIZE_DEBOUNCE};
 
 /// Cache for OKLCH → RGB conversions within a frame.
 /// Uses quantized key to allow f32 hashing.
@@ -50,6 +60,13 @@ pub struct Terminal {
     last_layout: LayoutResult,
     animation: AnimationState,
     theme: Arc<dyn Theme>,
+    image_protocol: ImageProtocol,
+    cell_pixels: (u16, u16),
+    capabilities: Capabilities,
+    /// Size frames are laid out at; only changes once a resize has settled.
+    size: (u16, u16),
+    resize: ResizeDebouncer,
+    layers: LayerCache,
 }
 
 impl Terminal {
@@ -61,7 +78,8 @@ impl Terminal {
             stdout,
             terminal::EnterAlternateScreen,
             cursor::Hide,
-            event::EnableMouseCapture
+            event::EnableMouseCapture,
+            event::EnableFocusChange
         )?;
 
         let (width, height) = terminal::size()?;
@@ -75,6 +93,12 @@ impl Terminal {
             last_layout: LayoutResult::new(),
             animation: AnimationState::new(),
             theme: Arc::new(EmptyTheme),
+            image_protocol: ImageProtocol::detect(),
+            cell_pixels: cell_pixels(),
+            capabilities: Capabilities::detect(),
+            size: (width, height),
+            resize: ResizeDebouncer::default(),
+            layers: LayerCache::new(),
         })
     }
 
@@ -82,33 +106,73 @@ impl Terminal {
         (self.current_buffer.width(), self.current_buffer.height())
     }
 
-    pub fn poll(&self, timeout: Option<Duration>) -> io::Result<Vec<CrosstermEvent>> {
+    /// Wait for input events.
+    ///
+    /// Resize events are debounced: while the terminal is being resized none
+    /// are returned, then a single `Resize` with the final size once it has
+    /// been stable for the debounce delay. Until then [`render`](Self::render)
+    /// keeps drawing at the previous size.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> io::Result<Vec<CrosstermEvent>> {
         let mut events = Vec::new();
 
         // Use short timeout when animations are active for smooth animation
-        let effective_timeout = match self.animation.next_tick_due() {
+        let mut effective_timeout = match self.animation.next_tick_due() {
             Some(tick_due) => Some(timeout.map(|t| t.min(tick_due)).unwrap_or(tick_due)),
             None => timeout,
         };
+        // Wake up when a pending resize settles
+        if let Some(left) = self.resize.time_left(Instant::now()) {
+            effective_timeout = Some(effective_timeout.map_or(left, |t| t.min(left)));
+        }
 
         let has_event = match effective_timeout {
             Some(dur) => event::poll(dur)?,
             None => {
                 // Block until event
                 events.push(event::read()?);
-                return Ok(events);
+                false
             }
         };
 
         if has_event {
             events.push(event::read()?);
+        }
+        if !events.is_empty() {
             // Drain any additional pending events
             while event::poll(Duration::ZERO)? {
                 events.push(event::read()?);
             }
         }
 
-        Ok(events)
+        Ok(self.debounce_resizes(events))
+    }
+
+    /// Hold back resize events until the size settles.
+    fn debounce_resizes(&mut self, events: Vec<CrosstermEvent>) -> Vec<CrosstermEvent> {
+        let now = Instant::now();
+        let mut events: Vec<CrosstermEvent> = events
+            .into_iter()
+            .filter(|event| match event {
+                CrosstermEvent::Resize(width, height) => {
+                    self.resize.push(*width, *height, now);
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+
+        if let Some((width, height)) = self.resize.settled(now) {
+            self.size = (width, height);
+            events.push(CrosstermEvent::Resize(width, height));
+        }
+
+        events
+    }
+
+    /// Set how long the terminal size has to stay unchanged before a resize
+    /// is reported. `Duration::ZERO` reports every resize immediately.
+    pub fn set_resize_debounce(&mut self, delay: Duration) {
+        self.resize.set_delay(delay);
     }
 
     /// Enable or disable reduced motion (accessibility).
@@ -120,6 +184,29 @@ impl Terminal {
     /// Set the theme for color variable resolution.
     pub fn set_theme(&mut self, theme: Arc<dyn Theme>) {
         self.theme = theme;
+        // Cached layers hold colors resolved against the old theme
+        self.layers.clear();
+    }
+
+    /// Override the detected image protocol.
+    /// Use `ImageProtocol::None` to always render the text fallback.
+    pub fn set_image_protocol(&mut self, protocol: ImageProtocol) {
+        self.image_protocol = protocol;
+    }
+
+    /// The protocol used to draw images.
+    pub fn image_protocol(&self) -> ImageProtocol {
+        self.image_protocol
+    }
+
+    /// Override the detected terminal capabilities.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// The terminal features in use.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
     }
 
     /// Returns true if any animations (transitions or frame animations) are currently active.
@@ -133,14 +220,26 @@ impl Terminal {
         self.animation.has_active_animations()
     }
 
+    /// Lay out and draw a frame.
+    ///
+    /// Frames use the terminal's current size, except while [`poll`](Self::poll)
+    /// is holding back a resize that hasn't settled yet. Apps that read
+    /// crossterm events themselves get every resize immediately.
     pub fn render(&mut self, root: &Element) -> io::Result<&LayoutResult> {
         let t_start = Instant::now();
 
-        // Check if terminal size changed
-        let (width, height) = terminal::size()?;
+        // Check if terminal size changed (keep the old size during a resize, see `poll`)
+        if !self.resize.is_pending() {
+            self.size = terminal::size()?;
+        }
+        let (width, height) = self.size;
         if width != self.current_buffer.width() || height != self.current_buffer.height() {
             self.current_buffer = Buffer::new(width, height);
             self.previous_buffer = Buffer::new(width, height);
+            self.cell_pixels = cell_pixels();
+            if self.image_protocol == ImageProtocol::Kitty {
+                write!(self.stdout, "{}", KITTY_DELETE_ALL)?;
+            }
         }
         let t_resize = Instant::now();
 
@@ -167,12 +266,13 @@ impl Terminal {
         let color_ctx = ColorContext::new(self.theme.as_ref());
 
         // Render to buffer with animation state for interpolated values
-        render_to_buffer(
+        render_to_buffer_with_layers(
             root,
             &self.last_layout,
             &mut self.current_buffer,
             &self.animation,
             &color_ctx,
+            &mut self.layers,
         );
         let t_render = Instant::now();
 
@@ -218,6 +318,74 @@ impl Terminal {
         &self.last_layout
     }
 
+    /// Blank the fallback under drawable images and remove stale placements.
+    ///
+    /// Images that vanished or moved since the last frame are deleted and
+    /// their cells invalidated so the text diff repaints them.
+    fn prepare_images(&mut self) -> io::Result<()> {
+        let protocol = self.image_protocol;
+        if protocol == ImageProtocol::None {
+            return Ok(());
+        }
+
+        for placement in self.current_buffer.images().to_vec() {
+            if placement.image.supported_by(protocol) {
+                let rect = placement.rect;
+                for y in rect.y.max(0)..rect.bottom() {
+                    for x in rect.x.max(0)..rect.right() {
+                        self.current_buffer.set(x as u16, y as u16, Cell::default());
+                    }
+                }
+            }
+        }
+
+        let stale: Vec<_> = self
+            .previous_buffer
+            .images()
+            .iter()
+            .filter(|old| {
+                !self
+                    .current_buffer
+                    .images()
+                    .iter()
+                    .any(|new| new.same_as(old))
+            })
+            .cloned()
+            .collect();
+        for old in stale {
+            if let Some(delete) = old.image.encode_delete(protocol) {
+                write!(self.stdout, "{}", delete)?;
+            }
+            self.previous_buffer.invalidate(old.rect);
+        }
+        Ok(())
+    }
+
+    /// Draw images that are new or moved since the last frame.
+    fn draw_images(&mut self) -> io::Result<()> {
+        let protocol = self.image_protocol;
+        for placement in self.current_buffer.images() {
+            if self
+                .previous_buffer
+                .images()
+                .iter()
+                .any(|old| old.same_as(placement))
+            {
+                continue;
+            }
+            let rect = placement.rect;
+            if let Some(seq) =
+                placement
+                    .image
+                    .encode(protocol, rect.width, rect.height, self.cell_pixels)
+            {
+                execute!(self.stdout, cursor::MoveTo(rect.x as u16, rect.y as u16))?;
+                write!(self.stdout, "{}", seq)?;
+            }
+        }
+        Ok(())
+    }
+
     fn flush_diff(&mut self) -> io::Result<()> {
         let mut rgb_cache = RgbCache::new();
         let mut last_x = u16::MAX;
@@ -226,6 +394,15 @@ impl Terminal {
         let mut last_fg = Oklch::new(1.0, 0.0, 0.0); // white
         let mut last_bg: Option<Oklch> = None; // transparent (terminal default)
         let mut last_style = crate::types::TextStyle::new();
+        let mut last_link: Option<u64> = None;
+        let mut last_underline_color: Option<Oklch> = None;
+
+        // Hold the frame back until it's complete so large diffs don't tear
+        if self.capabilities.synchronized_output {
+            write!(self.stdout, "\x1b[?2026h")?;
+        }
+
+        self.prepare_images()?;
 
         // Reset to known state at start
         execute!(self.stdout, SetAttribute(Attribute::Reset))?;
@@ -298,25 +475,82 @@ impl Terminal {
                     execute!(self.stdout, SetAttribute(Attribute::NoItalic))?;
                 }
             }
-            if cell.style.underline != last_style.underline {
+            if cell.style.underline != last_style.underline
+                || (cell.style.underline
+                    && cell.style.underline_style != last_style.underline_style)
+            {
                 if cell.style.underline {
-                    execute!(self.stdout, SetAttribute(Attribute::Underlined))?;
+                    // Extended underline SGR (4:n); unsupporting terminals draw a single line
+                    let attr = match cell.style.underline_style {
+                        UnderlineStyle::Single => Attribute::Underlined,
+                        UnderlineStyle::Double => Attribute::DoubleUnderlined,
+                        UnderlineStyle::Curly => Attribute::Undercurled,
+                        UnderlineStyle::Dotted => Attribute::Underdotted,
+                        UnderlineStyle::Dashed => Attribute::Underdashed,
+                    };
+                    execute!(self.stdout, SetAttribute(attr))?;
                 } else {
                     execute!(self.stdout, SetAttribute(Attribute::NoUnderline))?;
                 }
             }
+            if cell.underline_color != last_underline_color {
+                let color = match cell.underline_color {
+                    Some(color) => {
+                        let rgb = rgb_cache.get(color);
+                        CtColor::Rgb {
+                            r: rgb.r,
+                            g: rgb.g,
+                            b: rgb.b,
+                        }
+                    }
+                    None => CtColor::Reset,
+                };
+                execute!(self.stdout, SetUnderlineColor(color))?;
+                last_underline_color = cell.underline_color;
+            }
             last_style = cell.style;
 
-            // Write character
-            write!(self.stdout, "{}", cell.char)?;
+            // Open or close hyperlinks; without support links are plain text
+            if cell.link != last_link && self.capabilities.hyperlinks {
+                let target = cell
+                    .link
+                    .and_then(|id| Some((id, self.current_buffer.link(id)?)));
+                match target {
+                    // The id lets the terminal treat wrapped lines as one link
+                    Some((id, url)) => write!(self.stdout, "\x1b]8;id={:x};{}\x1b\\", id, url)?,
+                    None => write!(self.stdout, "\x1b]8;;\x1b\\")?,
+                }
+                last_link = cell.link;
+            }
+
+            // Write character (or the full grapheme cluster it starts)
+            match cell.cluster.and_then(|id| self.current_buffer.grapheme(id)) {
+                Some(grapheme) => {
+                    write!(self.stdout, "{}", grapheme)?;
+                    last_char_width = grapheme_width(grapheme).max(1) as u16;
+                }
+                None => {
+                    write!(self.stdout, "{}", cell.char)?;
+                    last_char_width = char_width(cell.char).max(1) as u16;
+                }
+            }
 
             last_x = x;
             last_y = y;
-            last_char_width = char_width(cell.char).max(1) as u16;
         }
 
         // Reset at end
+        if last_link.is_some() && self.capabilities.hyperlinks {
+            write!(self.stdout, "\x1b]8;;\x1b\\")?;
+        }
         execute!(self.stdout, SetAttribute(Attribute::Reset))?;
+
+        // Images go on top of the text they replace
+        self.draw_images()?;
+
+        if self.capabilities.synchronized_output {
+            write!(self.stdout, "\x1b[?2026l")?;
+        }
         self.stdout.flush()?;
         Ok(())
     }
@@ -324,8 +558,12 @@ impl Terminal {
 
 impl Drop for Terminal {
     fn drop(&mut self) {
+        if self.image_protocol == ImageProtocol::Kitty {
+            let _ = write!(self.stdout, "{}", KITTY_DELETE_ALL);
+        }
         let _ = execute!(
             self.stdout,
+            event::DisableFocusChange,
             event::DisableMouseCapture,
             cursor::Show,
             terminal::LeaveAlternateScreen
@@ -333,3 +571,13 @@ impl Drop for Terminal {
         let _ = terminal::disable_raw_mode();
     }
 }
+
+/// Query the cell size in pixels, falling back to a common default.
+fn cell_pixels() -> (u16, u16) {
+    match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => DEFAULT_CELL_PIXELS,
+    }
+}
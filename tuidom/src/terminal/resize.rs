@@ -0,0 +1,65 @@
+//! Resize debouncing.
+//!
+//! Dragging a terminal corner produces a burst of resize events. Laying out
+//! and redrawing for every intermediate size is wasted work, so resizes are
+//! held back until no new one has arrived for the debounce delay, then
+//! reported once with the final size.
+
+use std::time::{Duration, Instant};
+
+/// Default time the terminal size has to stay unchanged before a resize is reported.
+pub const DEFAULT_RESIZE_DEBOUNCE: Duration = Duration::from_millis(80);
+
+/// Collapses a burst of resize events into the last one.
+#[derive(Debug, Clone)]
+pub struct ResizeDebouncer {
+    delay: Duration,
+    pending: Option<((u16, u16), Instant)>,
+}
+
+impl Default for ResizeDebouncer {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESIZE_DEBOUNCE)
+    }
+}
+
+impl ResizeDebouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Record a resize seen at `now`, replacing any pending one.
+    pub fn push(&mut self, width: u16, height: u16, now: Instant) {
+        self.pending = Some(((width, height), now));
+    }
+
+    /// Whether a resize is waiting for the terminal to settle.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Time until the pending resize settles, if there is one.
+    pub fn time_left(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .map(|(_, at)| (at + self.delay).saturating_duration_since(now))
+    }
+
+    /// Take the pending size if it has been stable for the debounce delay.
+    pub fn settled(&mut self, now: Instant) -> Option<(u16, u16)> {
+        match self.time_left(now) {
+            Some(left) if left.is_zero() => self.pending.take().map(|(size, _)| size),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! SGR escape sequence parsing for text produced by external tools.
+//!
+//! Text elements with `ansi(true)` run their content through [`AnsiText::parse`]:
+//! SGR sequences (`ESC [ ... m`) become per-cell styling, every other escape
+//! sequence and control character (except newlines and tabs) is dropped so it
+//! can't corrupt the frame.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::types::{Color, TextStyle, UnderlineStyle};
+
+/// Styling selected by SGR sequences at some point in the text.
+///
+/// `None` colors mean "use the element's own color".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnsiStyle {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub underline_color: Option<Color>,
+    pub text_style: TextStyle,
+    /// Swap foreground and background (SGR 7).
+    pub reverse: bool,
+}
+
+/// Text with its escape sequences removed and SGR styling kept as spans.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnsiText {
+    /// The printable text.
+    pub text: String,
+    /// `(byte offset into text, style)` pairs, sorted by offset. Each style
+    /// applies until the next span starts.
+    pub spans: Vec<(usize, AnsiStyle)>,
+}
+
+impl AnsiText {
+    pub fn parse(s: &str) -> Self {
+        let mut result = AnsiText::default();
+        let mut style = AnsiStyle::default();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        if let Some(params) = parse_csi(&mut chars) {
+                            apply_sgr(&mut style, &params);
+                            result.push_style(style.clone());
+                        }
+                    }
+                    Some(']') => skip_osc(&mut chars),
+                    // nF sequences such as charset selection (`ESC ( B`): intermediate
+                    // bytes, then a final byte
+                    Some(c) if ('\x20'..='\x2f').contains(&c) => {
+                        for c in chars.by_ref() {
+                            if !('\x20'..='\x2f').contains(&c) {
+                                break;
+                            }
+                        }
+                    }
+                    // Two-character sequences (keypad modes, ...)
+                    _ => {}
+                },
+                // 8-bit CSI and OSC
+                '\u{9b}' => {
+                    if let Some(params) = parse_csi(&mut chars) {
+                        apply_sgr(&mut style, &params);
+                        result.push_style(style.clone());
+                    }
+                }
+                '\u{9d}' => skip_osc(&mut chars),
+                '\n' | '\t' => result.text.push(c),
+                // Other C0/C1 controls (CR, backspace, bell, ...) would move the
+                // cursor or act on the terminal
+                c if c.is_control() => {}
+                c => result.text.push(c),
+            }
+        }
+
+        result
+    }
+
+    /// Style in effect at `byte` in [`text`](Self::text).
+    pub fn style_at(&self, byte: usize) -> Option<&AnsiStyle> {
+        let idx = self.spans.partition_point(|(start, _)| *start <= byte);
+        idx.checked_sub(1).map(|i| &self.spans[i].1)
+    }
+
+    fn push_style(&mut self, style: AnsiStyle) {
+        let offset = self.text.len();
+        // Consecutive sequences with no text between them collapse into one span
+        if let Some(last) = self.spans.last_mut() {
+            if last.0 == offset {
+                last.1 = style;
+                return;
+            }
+            if last.1 == style {
+                return;
+            }
+        }
+        self.spans.push((offset, style));
+    }
+}
+
+/// Remove all escape sequences and control characters other than newlines
+/// and tabs, keeping only the printable text.
+pub fn strip(s: &str) -> String {
+    if !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t') {
+        return s.to_string();
+    }
+    AnsiText::parse(s).text
+}
+
+/// Consume a CSI sequence after its introducer: parameters, then a final
+/// byte in 0x40..=0x7e. Returns the parameters of an SGR sequence.
+fn parse_csi(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut params = String::new();
+    for c in chars.by_ref() {
+        if ('\x40'..='\x7e').contains(&c) {
+            return (c == 'm').then_some(params);
+        }
+        params.push(c);
+    }
+    None
+}
+
+/// Consume an OSC sequence after its introducer, up to BEL or ST
+/// (`ESC \` or 8-bit `0x9c`).
+fn skip_osc(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\x07' | '\u{9c}' => break,
+            '\x1b' if chars.peek() == Some(&'\\') => {
+                chars.next();
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn apply_sgr(style: &mut AnsiStyle, params: &str) {
+    // `ESC[m` is the same as `ESC[0m`
+    if params.is_empty() {
+        *style = AnsiStyle::default();
+        return;
+    }
+
+    let groups: Vec<&str> = params.split(';').collect();
+    let mut i = 0;
+    while i < groups.len() {
+        let group = groups[i];
+        i += 1;
+
+        // Colon sub-parameters (`4:3`, `38:2::r:g:b`) stay within one group
+        let mut sub = group.split(':').map(|p| p.parse::<u16>().unwrap_or(0));
+        let code = sub.next().unwrap_or(0);
+
+        match code {
+            0 => *style = AnsiStyle::default(),
+            1 => style.text_style.bold = true,
+            2 => style.text_style.dim = true,
+            3 => style.text_style.italic = true,
+            4 => match sub.next() {
+                Some(0) => style.text_style.underline = false,
+                Some(kind) => {
+                    style.text_style = style.text_style.underline_style(match kind {
+                        2 => UnderlineStyle::Double,
+                        3 => UnderlineStyle::Curly,
+                        4 => UnderlineStyle::Dotted,
+                        5 => UnderlineStyle::Dashed,
+                        _ => UnderlineStyle::Single,
+                    })
+                }
+                None => style.text_style = style.text_style.underline_style(UnderlineStyle::Single),
+            },
+            7 => style.reverse = true,
+            9 => style.text_style.strikethrough = true,
+            21 => style.text_style = style.text_style.underline_style(UnderlineStyle::Double),
+            22 => {
+                style.text_style.bold = false;
+                style.text_style.dim = false;
+            }
+            23 => style.text_style.italic = false,
+            24 => style.text_style.underline = false,
+            27 => style.reverse = false,
+            29 => style.text_style.strikethrough = false,
+            30..=37 => style.fg = Some(palette(code as u8 - 30)),
+            39 => style.fg = None,
+            40..=47 => style.bg = Some(palette(code as u8 - 40)),
+            49 => style.bg = None,
+            59 => style.underline_color = None,
+            90..=97 => style.fg = Some(palette(code as u8 - 90 + 8)),
+            100..=107 => style.bg = Some(palette(code as u8 - 100 + 8)),
+            38 | 48 | 58 => {
+                let color = if group.contains(':') {
+                    extended_color_sub(sub)
+                } else {
+                    extended_color(&groups, &mut i)
+                };
+                if let Some(color) = color {
+                    match code {
+                        38 => style.fg = Some(color),
+                        48 => style.bg = Some(color),
+                        _ => style.underline_color = Some(color),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `38;5;n` or `38;2;r;g;b`, consuming the following groups.
+fn extended_color(groups: &[&str], i: &mut usize) -> Option<Color> {
+    let mut next = || {
+        let value = groups.get(*i).and_then(|p| p.parse::<u16>().ok());
+        *i += 1;
+        value
+    };
+    match next()? {
+        5 => Some(palette(next()?.min(255) as u8)),
+        2 => {
+            let (r, g, b) = (next()?, next()?, next()?);
+            Some(Color::rgb(
+                r.min(255) as u8,
+                g.min(255) as u8,
+                b.min(255) as u8,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// `38:5:n`, `38:2:r:g:b` or `38:2:colorspace:r:g:b`.
+fn extended_color_sub(sub: impl Iterator<Item = u16>) -> Option<Color> {
+    let values: Vec<u16> = sub.collect();
+    match values.as_slice() {
+        [5, n, ..] => Some(palette((*n).min(255) as u8)),
+        [2, _, r, g, b, ..] | [2, r, g, b] => Some(Color::rgb(
+            (*r).min(255) as u8,
+            (*g).min(255) as u8,
+            (*b).min(255) as u8,
+        )),
+        _ => None,
+    }
+}
+
+/// The xterm 256-color palette.
+pub fn palette(index: u8) -> Color {
+    const BASE: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+
+    match index {
+        0..=15 => {
+            let (r, g, b) = BASE[index as usize];
+            Color::rgb(r, g, b)
+        }
+        16..=231 => {
+            let i = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color::rgb(level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        232..=255 => {
+            let v = 8 + (index - 232) * 10;
+            Color::rgb(v, v, v)
+        }
+    }
+}
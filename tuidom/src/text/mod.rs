@@ -1,16 +1,46 @@
+pub mod ansi;
+
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthChar;
 use unicode_width::UnicodeWidthStr;
 
 use crate::types::TextAlign;
 
+/// Width in cells, measured per grapheme cluster as it will be rendered.
 pub fn display_width(s: &str) -> usize {
-    s.width()
+    graphemes(s).map(grapheme_width).sum()
 }
 
 pub fn char_width(c: char) -> usize {
     c.width().unwrap_or(0)
 }
 
+/// Split text into extended grapheme clusters (user-perceived characters).
+///
+/// Emoji ZWJ sequences, flags and base characters with combining marks each
+/// come out as one cluster and occupy one buffer cell (two for wide ones).
+pub fn graphemes(s: &str) -> impl Iterator<Item = &str> {
+    s.graphemes(true)
+}
+
+/// Width of a single grapheme cluster in cells (0, 1 or 2).
+pub fn grapheme_width(g: &str) -> usize {
+    g.width().min(2)
+}
+
+/// Number of grapheme clusters in `s`.
+pub fn grapheme_count(s: &str) -> usize {
+    graphemes(s).count()
+}
+
+/// Byte offset of the grapheme at `index`, or `s.len()` past the end.
+pub fn grapheme_byte_index(s: &str, index: usize) -> usize {
+    s.grapheme_indices(true)
+        .nth(index)
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
+
 pub fn truncate_to_width(s: &str, max_width: usize) -> String {
     let current_width = display_width(s);
     if current_width <= max_width {
@@ -28,13 +58,13 @@ pub fn truncate_to_width(s: &str, max_width: usize) -> String {
     let mut result = String::new();
     let mut width = 0;
 
-    for ch in s.chars() {
-        let ch_width = char_width(ch);
-        if width + ch_width > target_width {
+    for g in graphemes(s) {
+        let g_width = grapheme_width(g);
+        if width + g_width > target_width {
             break;
         }
-        result.push(ch);
-        width += ch_width;
+        result.push_str(g);
+        width += g_width;
     }
 
     result.push_str(ellipsis);
@@ -134,16 +164,16 @@ pub fn wrap_chars(s: &str, max_width: usize) -> Vec<String> {
         let mut current_line = String::new();
         let mut current_width = 0;
 
-        for ch in input_line.chars() {
-            let ch_width = char_width(ch);
+        for g in graphemes(input_line) {
+            let g_width = grapheme_width(g);
 
-            if ch_width == 0 {
-                // Zero-width char (combining, etc.) - just add it
-                current_line.push(ch);
+            if g_width == 0 {
+                // Zero-width cluster (stray combining mark, etc.) - just add it
+                current_line.push_str(g);
                 continue;
             }
 
-            if current_width + ch_width > max_width {
+            if current_width + g_width > max_width {
                 // Start new line
                 if !current_line.is_empty() {
                     lines.push(current_line);
@@ -152,8 +182,8 @@ pub fn wrap_chars(s: &str, max_width: usize) -> Vec<String> {
                 current_width = 0;
             }
 
-            current_line.push(ch);
-            current_width += ch_width;
+            current_line.push_str(g);
+            current_width += g_width;
         }
 
         if !current_line.is_empty() {
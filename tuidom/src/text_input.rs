@@ -3,8 +3,12 @@ use std::collections::HashMap;
 use crate::element::{find_element, Element};
 use crate::event::{Event, Key, Modifiers};
 use crate::layout::LayoutResult;
+use crate::text::{grapheme_byte_index, grapheme_count};
 
 /// Data for a single text input: text content and cursor state.
+///
+/// Cursor and anchor positions are grapheme cluster indices, so an emoji
+/// sequence or an accented letter is stepped over and deleted as one unit.
 #[derive(Debug, Clone, Default)]
 pub struct TextInputData {
     pub text: String,
@@ -16,7 +20,7 @@ pub struct TextInputData {
 impl TextInputData {
     pub fn new(text: impl Into<String>) -> Self {
         let text = text.into();
-        let cursor = text.len();
+        let cursor = grapheme_count(&text);
         Self {
             text,
             cursor,
@@ -53,7 +57,7 @@ impl TextInputData {
     pub fn select_all(&mut self) {
         if !self.text.is_empty() {
             self.anchor = Some(0);
-            self.cursor = self.text.len();
+            self.cursor = grapheme_count(&self.text);
         }
     }
 }
@@ -87,7 +91,7 @@ impl TextInputState {
     /// Set the text value for an input, placing cursor at end.
     pub fn set(&mut self, id: &str, text: impl Into<String>) {
         let text = text.into();
-        let cursor = text.len();
+        let cursor = grapheme_count(&text);
         self.inputs.insert(
             id.to_string(),
             TextInputData {
@@ -214,97 +218,48 @@ impl TextInputState {
     /// Insert a character at cursor, replacing selection if any.
     fn insert_char(&mut self, id: &str, c: char) {
         let data = self.get_data_mut(id);
-
-        if let Some((start, end)) = data.selection() {
-            // Replace selection
-            let mut new_text =
-                String::with_capacity(data.text.len() - (end - start) + c.len_utf8());
-            new_text.push_str(&data.text[..start]);
-            new_text.push(c);
-            new_text.push_str(&data.text[end..]);
-            data.text = new_text;
-            data.cursor = start + c.len_utf8();
-            data.clear_selection();
-        } else {
-            // Insert at cursor
-            let byte_pos = char_to_byte_index(&data.text, data.cursor);
-            data.text.insert(byte_pos, c);
-            data.cursor += 1;
-        }
+        let (start, end) = data.selection().unwrap_or((data.cursor, data.cursor));
+        let mut buf = [0u8; 4];
+        replace_range(data, start, end, c.encode_utf8(&mut buf));
     }
 
-    /// Delete character before cursor or delete selection.
+    /// Delete grapheme before cursor or delete selection.
     /// Returns true if text changed.
     fn delete_back(&mut self, id: &str) -> bool {
         let data = self.get_data_mut(id);
 
         if let Some((start, end)) = data.selection() {
-            // Delete selection
-            let mut new_text = String::with_capacity(data.text.len() - (end - start));
-            new_text.push_str(&data.text[..start]);
-            new_text.push_str(&data.text[end..]);
-            data.text = new_text;
-            data.cursor = start;
-            data.clear_selection();
+            replace_range(data, start, end, "");
             true
         } else if data.cursor > 0 {
-            // Delete char before cursor
-            let char_indices: Vec<_> = data.text.char_indices().collect();
-            if data.cursor <= char_indices.len() {
-                let byte_pos = if data.cursor == char_indices.len() {
-                    data.text.len()
-                } else {
-                    char_indices[data.cursor].0
-                };
-                let prev_byte_pos = char_indices[data.cursor - 1].0;
-                data.text = format!("{}{}", &data.text[..prev_byte_pos], &data.text[byte_pos..]);
-                data.cursor -= 1;
-                true
-            } else {
-                false
-            }
+            let cursor = data.cursor.min(grapheme_count(&data.text));
+            replace_range(data, cursor.saturating_sub(1), cursor, "");
+            true
         } else {
             false
         }
     }
 
-    /// Delete character after cursor or delete selection.
+    /// Delete grapheme after cursor or delete selection.
     /// Returns true if text changed.
     fn delete_forward(&mut self, id: &str) -> bool {
         let data = self.get_data_mut(id);
 
         if let Some((start, end)) = data.selection() {
-            // Delete selection
-            let mut new_text = String::with_capacity(data.text.len() - (end - start));
-            new_text.push_str(&data.text[..start]);
-            new_text.push_str(&data.text[end..]);
-            data.text = new_text;
-            data.cursor = start;
-            data.clear_selection();
+            replace_range(data, start, end, "");
+            true
+        } else if data.cursor < grapheme_count(&data.text) {
+            replace_range(data, data.cursor, data.cursor + 1, "");
             true
         } else {
-            let char_count = data.text.chars().count();
-            if data.cursor < char_count {
-                // Delete char at cursor
-                let char_indices: Vec<_> = data.text.char_indices().collect();
-                let byte_pos = char_indices[data.cursor].0;
-                let next_byte_pos = if data.cursor + 1 == char_indices.len() {
-                    data.text.len()
-                } else {
-                    char_indices[data.cursor + 1].0
-                };
-                data.text = format!("{}{}", &data.text[..byte_pos], &data.text[next_byte_pos..]);
-                true
-            } else {
-                false
-            }
+            false
         }
     }
 
-    /// Move cursor by delta characters.
+    /// Move cursor by delta graphemes.
     fn move_cursor(&mut self, id: &str, delta: i32, extend_selection: bool) {
         let data = self.get_data_mut(id);
-        let char_count = data.text.chars().count();
+        let char_count = grapheme_count(&data.text);
 
         if extend_selection && data.anchor.is_none() {
             data.anchor = Some(data.cursor);
@@ -338,7 +293,7 @@ impl TextInputState {
     /// Move cursor to end of text.
     fn move_to_end(&mut self, id: &str, extend_selection: bool) {
         let data = self.get_data_mut(id);
-        let char_count = data.text.chars().count();
+        let char_count = grapheme_count(&data.text);
 
         if extend_selection && data.anchor.is_none() {
             data.anchor = Some(data.cursor);
@@ -363,10 +318,13 @@ pub enum TextEditResult {
     Ignored,
 }
 
-/// Convert character index to byte index in a string.
-fn char_to_byte_index(s: &str, char_idx: usize) -> usize {
-    s.char_indices()
-        .nth(char_idx)
-        .map(|(i, _)| i)
-        .unwrap_or(s.len())
+/// Replace the graphemes in `start..end` with `insert`, leaving the cursor
+/// after the inserted text and clearing the selection.
+fn replace_range(data: &mut TextInputData, start: usize, end: usize, insert: &str) {
+    let start_byte = grapheme_byte_index(&data.text, start);
+    let end_byte = grapheme_byte_index(&data.text, end);
+    data.text.replace_range(start_byte..end_byte, insert);
+    // Count rather than add: a combining mark merges into the previous cluster
+    data.cursor = grapheme_count(&data.text[..start_byte + insert.len()]);
+    data.clear_selection();
 }
@@ -63,6 +63,28 @@ impl Oklch {
         }
     }
 
+    /// Composite `over` on top of this color with the given opacity.
+    /// Mixes in Oklab so the hue doesn't swing through unrelated colors.
+    pub fn blend(self, over: Oklch, alpha: f32) -> Self {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let (a1, b1) = self.ab();
+        let (a2, b2) = over.ab();
+        let a = a1 + (a2 - a1) * alpha;
+        let b = b1 + (b2 - b1) * alpha;
+        let h = b.atan2(a).to_degrees();
+        Self {
+            l: self.l + (over.l - self.l) * alpha,
+            c: (a * a + b * b).sqrt(),
+            h: if h < 0.0 { h + 360.0 } else { h },
+        }
+    }
+
+    /// Cartesian (a, b) components of the Oklab equivalent.
+    fn ab(self) -> (f32, f32) {
+        let h = self.h.to_radians();
+        (self.c * h.cos(), self.c * h.sin())
+    }
+
     /// Convert to RGB for terminal output.
     pub fn to_rgb(self) -> Rgb {
         use palette::{IntoColor, Oklch as PaletteOklch, Srgb};
@@ -89,7 +111,7 @@ impl Oklch {
 /// Uses quantized values for f32 hashing.
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub enum ColorKey {
-    Oklch(i32, i32, i32), // quantized l, c, h
+    Oklch(i32, i32, i32, i32), // quantized l, c, h, alpha
     Rgb(u8, u8, u8),
     Var(String),
 }
@@ -193,14 +215,24 @@ impl Color {
         }
     }
 
+    /// Opacity of a concrete color, 0.0-1.0.
+    /// Var and Derived colors must be resolved first; they report 1.0.
+    pub fn opacity(&self) -> f32 {
+        match self {
+            Self::Oklch { a, .. } => a.clamp(0.0, 1.0),
+            Self::Rgb { .. } | Self::Var(_) | Self::Derived { .. } => 1.0,
+        }
+    }
+
     /// Generate a cache key for this color.
     /// Returns None for Derived colors which can't be cached without resolution.
     pub fn cache_key(&self) -> Option<ColorKey> {
         match self {
-            Self::Oklch { l, c, h, .. } => Some(ColorKey::Oklch(
+            Self::Oklch { l, c, h, a } => Some(ColorKey::Oklch(
                 (l * 1000.0) as i32,
                 (c * 10000.0) as i32,
                 (h * 10.0) as i32,
+                (a * 1000.0) as i32,
             )),
             Self::Rgb { r, g, b } => Some(ColorKey::Rgb(*r, *g, *b)),
             Self::Var(s) => Some(ColorKey::Var(s.clone())),
@@ -223,6 +255,116 @@ impl ColorOp {
     }
 }
 
+/// Error returned when parsing a [`Color`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    input: String,
+}
+
+impl std::fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid color `{}`", self.input)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+/// Parses the format written by [`Color::to_dsl`], plus `#rgb` / `#rrggbb` hex:
+/// `oklch(0.5, 0.1, 280)`, `rgb(255, 0, 0)`, `#ff0000`, a variable name such
+/// as `text.muted`, each optionally followed by `| op(value)` operations.
+impl std::str::FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_color(s).ok_or_else(|| ParseColorError {
+            input: s.to_string(),
+        })
+    }
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    let mut parts = split_top_level(s, '|').into_iter();
+    let mut color = parse_base_color(parts.next()?.trim())?;
+    for part in parts {
+        let (name, args) = parse_call(part.trim())?;
+        let amount = |index: usize| args.get(index)?.trim().parse::<f32>().ok();
+        color = match (name, args.len()) {
+            ("lighten", 1) => color.lighten(amount(0)?),
+            ("darken", 1) => color.darken(amount(0)?),
+            ("saturate", 1) => color.saturate(amount(0)?),
+            ("desaturate", 1) => color.desaturate(amount(0)?),
+            ("hue" | "hue_shift", 1) => color.hue_shift(amount(0)?),
+            ("alpha", 1) => color.alpha(amount(0)?),
+            ("mix", 2) => color.mix(parse_color(args[0])?, amount(1)?),
+            _ => return None,
+        };
+    }
+    Some(color)
+}
+
+fn parse_base_color(s: &str) -> Option<Color> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let digit = |i: usize, len: usize| u8::from_str_radix(hex.get(i..i + len)?, 16).ok();
+        return match hex.len() {
+            3 => Some(Color::rgb(
+                digit(0, 1)? * 17,
+                digit(1, 1)? * 17,
+                digit(2, 1)? * 17,
+            )),
+            6 => Some(Color::rgb(digit(0, 2)?, digit(2, 2)?, digit(4, 2)?)),
+            _ => None,
+        };
+    }
+
+    if let Some((name, args)) = parse_call(s) {
+        return match (name, args.len()) {
+            ("rgb", 3) => {
+                let channel = |i: usize| args[i].trim().parse::<u8>().ok();
+                Some(Color::rgb(channel(0)?, channel(1)?, channel(2)?))
+            }
+            ("oklch", 3 | 4) => {
+                let value = |i: usize| args[i].trim().parse::<f32>().ok();
+                let alpha = if args.len() == 4 { value(3)? } else { 1.0 };
+                Some(Color::oklcha(value(0)?, value(1)?, value(2)?, alpha))
+            }
+            _ => None,
+        };
+    }
+
+    let is_name = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-');
+    is_name.then(|| Color::var(s))
+}
+
+/// Split `name(a, b)` into its name and top-level arguments.
+fn parse_call(s: &str) -> Option<(&str, Vec<&str>)> {
+    let open = s.find('(')?;
+    let inner = s.get(open + 1..)?.strip_suffix(')')?;
+    let name = s[..open].trim();
+    Some((name, split_top_level(inner, ',')))
+}
+
+/// Split on `separator`, ignoring separators nested in parentheses.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
 fn oklch_to_rgb(l: f32, c: f32, h: f32) -> Rgb {
     use palette::{IntoColor, Oklch, Srgb};
 
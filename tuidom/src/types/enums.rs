@@ -21,6 +21,51 @@ pub enum Position {
     Static,
     Relative,
     Absolute,
+    /// Positioned relative to the viewport, regardless of ancestors.
+    Fixed,
+}
+
+impl Position {
+    /// Whether the element is taken out of normal flex flow.
+    pub const fn is_out_of_flow(&self) -> bool {
+        matches!(self, Position::Absolute | Position::Fixed)
+    }
+}
+
+/// Side of an anchor element that an anchored element is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placement {
+    Above,
+    #[default]
+    Below,
+    Left,
+    Right,
+}
+
+impl Placement {
+    /// The placement on the opposite side of the anchor.
+    pub const fn flipped(&self) -> Self {
+        match self {
+            Placement::Above => Placement::Below,
+            Placement::Below => Placement::Above,
+            Placement::Left => Placement::Right,
+            Placement::Right => Placement::Left,
+        }
+    }
+}
+
+/// Positions an out-of-flow element next to another element.
+///
+/// The element is placed on the `placement` side of the anchor and flipped
+/// to the opposite side when it would overflow the viewport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    /// ID of the element to position against.
+    pub id: String,
+    /// Preferred side of the anchor.
+    pub placement: Placement,
+    /// Gap between the anchor and the element, in cells.
+    pub offset: u16,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -85,11 +130,25 @@ pub enum Border {
     Thick,
 }
 
+/// Shape of the underline drawn when `TextStyle::underline` is set.
+///
+/// Terminals without extended underline support draw a single line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnderlineStyle {
+    #[default]
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct TextStyle {
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+    pub underline_style: UnderlineStyle,
     pub dim: bool,
     pub strikethrough: bool,
 }
@@ -110,6 +169,7 @@ impl TextStyle {
             bold: false,
             italic: false,
             underline: false,
+            underline_style: UnderlineStyle::Single,
             dim: false,
             strikethrough: false,
         }
@@ -130,6 +190,13 @@ impl TextStyle {
         self
     }
 
+    /// Underline with the given style (e.g. curly for errors).
+    pub const fn underline_style(mut self, style: UnderlineStyle) -> Self {
+        self.underline = true;
+        self.underline_style = style;
+        self
+    }
+
     pub const fn dim(mut self) -> Self {
         self.dim = true;
         self
@@ -147,6 +214,11 @@ impl TextStyle {
             bold: other.bold || self.bold,
             italic: other.italic || self.italic,
             underline: other.underline || self.underline,
+            underline_style: if other.underline {
+                other.underline_style
+            } else {
+                self.underline_style
+            },
             dim: other.dim || self.dim,
             strikethrough: other.strikethrough || self.strikethrough,
         }
@@ -4,11 +4,11 @@ mod enums;
 mod style;
 mod theme;
 
-pub use color::{Color, ColorKey, ColorOp, Oklch, Rgb};
+pub use color::{Color, ColorKey, ColorOp, Oklch, ParseColorError, Rgb};
 pub use edges::Edges;
 pub use enums::{
-    Align, Backdrop, Border, Direction, Justify, Overflow, Position, Size, TextAlign, TextStyle,
-    TextWrap, Wrap,
+    Align, Anchor, Backdrop, Border, Direction, Justify, Overflow, Placement, Position, Size,
+    TextAlign, TextStyle, TextWrap, UnderlineStyle, Wrap,
 };
 pub use style::Style;
 pub use theme::{ColorContext, DefaultTheme, EmptyTheme, Theme};
@@ -1,9 +1,11 @@
-use super::{Border, Color, TextStyle};
+use super::{Border, Color, TextStyle, UnderlineStyle};
 
 #[derive(Debug, Clone, Default)]
 pub struct Style {
     pub background: Option<Color>,
     pub foreground: Option<Color>,
+    /// Underline color. `None` uses the foreground color.
+    pub underline_color: Option<Color>,
     pub border: Border,
     pub text_style: TextStyle,
 }
@@ -48,6 +50,16 @@ impl Style {
         self
     }
 
+    pub fn underline_style(mut self, style: UnderlineStyle) -> Self {
+        self.text_style = self.text_style.underline_style(style);
+        self
+    }
+
+    pub fn underline_color(mut self, color: Color) -> Self {
+        self.underline_color = Some(color);
+        self
+    }
+
     pub fn dim(mut self) -> Self {
         self.text_style.dim = true;
         self
@@ -62,6 +74,10 @@ impl Style {
         Style {
             background: other.background.clone().or(self.background.clone()),
             foreground: other.foreground.clone().or(self.foreground.clone()),
+            underline_color: other
+                .underline_color
+                .clone()
+                .or(self.underline_color.clone()),
             border: if other.border != Border::default() {
                 other.border
             } else {
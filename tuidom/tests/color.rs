@@ -0,0 +1,63 @@
+use tuidom::Color;
+
+// =============================================================================
+// Color Parsing Tests
+// =============================================================================
+
+#[test]
+fn test_parse_color_base_forms() {
+    assert_eq!("#ff8000".parse::<Color>(), Ok(Color::rgb(255, 128, 0)));
+    assert_eq!("#f80".parse::<Color>(), Ok(Color::rgb(255, 136, 0)));
+    assert_eq!("rgb(1, 2, 3)".parse::<Color>(), Ok(Color::rgb(1, 2, 3)));
+    assert_eq!(
+        "oklch(0.5, 0.1, 280)".parse::<Color>(),
+        Ok(Color::oklch(0.5, 0.1, 280.0))
+    );
+    assert_eq!(
+        "oklch(0.5, 0.1, 280, 0.5)".parse::<Color>(),
+        Ok(Color::oklcha(0.5, 0.1, 280.0, 0.5))
+    );
+    assert_eq!("text.muted".parse::<Color>(), Ok(Color::var("text.muted")));
+}
+
+#[test]
+fn test_parse_color_operations() {
+    assert_eq!(
+        "surface | lighten(0.1) | alpha(0.5)".parse::<Color>(),
+        Ok(Color::var("surface").lighten(0.1).alpha(0.5))
+    );
+    assert_eq!(
+        "primary | mix(#000, 0.25)".parse::<Color>(),
+        Ok(Color::var("primary").mix(Color::rgb(0, 0, 0), 0.25))
+    );
+}
+
+#[test]
+fn test_parse_color_round_trips_dsl() {
+    let colors = [
+        Color::oklch(0.9, 0.02, 280.0),
+        Color::rgb(10, 20, 30),
+        Color::var("interact").darken(0.3).hue_shift(15.0),
+        Color::var("a").mix(Color::var("b").lighten(0.2), 0.5),
+    ];
+    for color in colors {
+        assert_eq!(color.to_dsl().parse::<Color>(), Ok(color));
+    }
+}
+
+#[test]
+fn test_parse_color_rejects_invalid() {
+    for input in [
+        "",
+        "#12",
+        "#gggggg",
+        "rgb(1, 2)",
+        "surface | glow(1)",
+        "a b",
+    ] {
+        assert!(
+            input.parse::<Color>().is_err(),
+            "{input:?} should not parse"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use tuidom::{
-    collect_focusable, hit_test, hit_test_any, hit_test_focusable, Element, FocusState,
-    LayoutResult, Rect,
+    collect_focusable, hit_test, hit_test_any, hit_test_focusable, Element, Event, FocusState,
+    LayoutResult, NavDirection, Rect,
 };
 
 fn create_layout(elements: &[(&str, Rect)]) -> LayoutResult {
@@ -91,6 +91,34 @@ fn test_hit_test_any() {
     );
 }
 
+#[test]
+fn test_hit_test_click_through_subtree() {
+    let root = Element::box_()
+        .id("root")
+        .child(Element::box_().id("under").clickable(true))
+        .child(
+            Element::box_()
+                .id("hud")
+                .z_index(5)
+                .click_through(true)
+                .child(Element::text("FPS").id("hud-btn").clickable(true)),
+        );
+
+    let layout = create_layout(&[
+        ("root", Rect::new(0, 0, 100, 50)),
+        ("under", Rect::new(0, 0, 100, 50)),
+        ("hud", Rect::new(0, 0, 20, 3)),
+        ("hud-btn", Rect::new(0, 0, 20, 3)),
+    ]);
+
+    // The HUD and its descendants are skipped, the element beneath wins
+    assert_eq!(hit_test(&layout, &root, 5, 1), Some("under".to_string()));
+    assert_eq!(
+        hit_test_any(&layout, &root, 5, 1),
+        Some("under".to_string())
+    );
+}
+
 #[test]
 fn test_hit_test_focusable() {
     let root = Element::box_()
@@ -437,6 +465,73 @@ fn test_focus_single_element() {
     assert_eq!(focus.focus_prev(&root), None);
 }
 
+#[test]
+fn test_focus_wrapped_row_cycles_on_main_axis() {
+    let root = Element::col()
+        .child(
+            Element::row()
+                .id("group")
+                .focus_wrap(true)
+                .child(Element::text("A").id("a").focusable(true))
+                .child(Element::text("B").id("b").focusable(true))
+                .child(Element::text("C").id("c").focusable(true)),
+        )
+        .child(Element::text("Below").id("below").focusable(true));
+
+    let mut focus = FocusState::new();
+    focus.focus("c", &root);
+
+    // Right from the last item wraps to the first
+    assert_eq!(
+        focus.focus_wrapped(NavDirection::Right, &root),
+        Some("a".to_string())
+    );
+
+    // Left from the first item wraps to the last
+    assert_eq!(
+        focus.focus_wrapped(NavDirection::Left, &root),
+        Some("c".to_string())
+    );
+
+    // Cross-axis keys are left to spatial navigation
+    assert_eq!(focus.focus_wrapped(NavDirection::Down, &root), None);
+    assert_eq!(focus.focused(), Some("c"));
+}
+
+#[test]
+fn test_focus_wrapped_outside_container() {
+    let root = Element::col()
+        .focus_wrap(false)
+        .child(Element::text("A").id("a").focusable(true))
+        .child(Element::text("B").id("b").focusable(true));
+
+    let mut focus = FocusState::new();
+    focus.focus("b", &root);
+
+    assert_eq!(focus.focus_wrapped(NavDirection::Down, &root), None);
+    assert_eq!(focus.focused(), Some("b"));
+}
+
+#[test]
+fn test_terminal_focus_events_pass_through() {
+    use crossterm::event::Event as CrosstermEvent;
+
+    let root = Element::col().child(Element::text("A").id("a").focusable(true));
+    let layout = create_layout(&[("a", Rect::new(0, 0, 10, 1))]);
+
+    let mut focus = FocusState::new();
+    focus.focus("a", &root);
+    let events = focus.process_events(
+        &[CrosstermEvent::FocusLost, CrosstermEvent::FocusGained],
+        &root,
+        &layout,
+    );
+
+    assert_eq!(events, vec![Event::FocusLost, Event::FocusGained]);
+    // Element focus is unaffected by the terminal window losing focus
+    assert_eq!(focus.focused(), Some("a"));
+}
+
 // ============================================================================
 // Collect Focusable
 // ============================================================================
@@ -0,0 +1,195 @@
+use tuidom::animation::AnimationState;
+use tuidom::{
+    Buffer, ColorContext, Element, EmptyTheme, Image, ImageProtocol, Overflow, Rect, Size,
+};
+
+fn render_to_buffer(root: &Element, width: u16, height: u16) -> Buffer {
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(root, Rect::new(0, 0, width, height), &animation);
+    let mut buf = Buffer::new(width, height);
+    let theme = EmptyTheme;
+    let color_ctx = ColorContext::new(&theme);
+    tuidom::render::render_to_buffer(root, &layout, &mut buf, &animation, &color_ctx);
+    buf
+}
+
+/// A 2x4 image: red top half, blue bottom half.
+fn red_blue() -> Image {
+    let mut pixels = Vec::new();
+    for y in 0..4 {
+        for _ in 0..2 {
+            if y < 2 {
+                pixels.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                pixels.extend_from_slice(&[0, 0, 255, 255]);
+            }
+        }
+    }
+    Image::rgba(2, 4, pixels)
+}
+
+// ============================================================================
+// Protocol Detection Tests
+// ============================================================================
+
+#[test]
+fn test_detect_kitty() {
+    let protocol = ImageProtocol::detect_from(|name| match name {
+        "TERM" => Some("xterm-kitty".into()),
+        _ => None,
+    });
+    assert_eq!(protocol, ImageProtocol::Kitty);
+}
+
+#[test]
+fn test_detect_iterm2() {
+    let protocol = ImageProtocol::detect_from(|name| match name {
+        "TERM_PROGRAM" => Some("iTerm.app".into()),
+        _ => None,
+    });
+    assert_eq!(protocol, ImageProtocol::Iterm2);
+}
+
+#[test]
+fn test_detect_sixel() {
+    let protocol = ImageProtocol::detect_from(|name| match name {
+        "TERM" => Some("foot".into()),
+        _ => None,
+    });
+    assert_eq!(protocol, ImageProtocol::Sixel);
+}
+
+#[test]
+fn test_detect_none() {
+    let protocol = ImageProtocol::detect_from(|name| match name {
+        "TERM" => Some("xterm-256color".into()),
+        _ => None,
+    });
+    assert_eq!(protocol, ImageProtocol::None);
+}
+
+// ============================================================================
+// Encoding Tests
+// ============================================================================
+
+#[test]
+fn test_encode_kitty() {
+    let image = red_blue();
+    let seq = image.encode(ImageProtocol::Kitty, 2, 1, (8, 16)).unwrap();
+    assert!(seq.starts_with("\x1b_Ga=T,f=32,s=2,v=4"));
+    assert!(seq.ends_with("\x1b\\"));
+}
+
+#[test]
+fn test_encode_iterm2() {
+    let image = red_blue();
+    let seq = image.encode(ImageProtocol::Iterm2, 2, 1, (8, 16)).unwrap();
+    assert!(seq.starts_with("\x1b]1337;File=inline=1;width=2;height=1"));
+    assert!(seq.ends_with('\x07'));
+}
+
+#[test]
+fn test_encode_sixel() {
+    let image = red_blue();
+    let seq = image.encode(ImageProtocol::Sixel, 1, 1, (4, 6)).unwrap();
+    assert!(seq.starts_with("\x1bP"));
+    assert!(seq.ends_with("\x1b\\"));
+}
+
+#[test]
+fn test_sixel_cannot_draw_png() {
+    let image = Image::png(vec![0u8; 8]);
+    assert!(!image.supported_by(ImageProtocol::Sixel));
+    assert!(image.encode(ImageProtocol::Sixel, 1, 1, (8, 16)).is_none());
+    assert!(image.encode(ImageProtocol::None, 1, 1, (8, 16)).is_none());
+}
+
+#[test]
+fn test_png_pixel_size() {
+    // PNG signature followed by an IHDR chunk for a 32x48 image
+    let mut bytes = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    bytes.extend_from_slice(&13u32.to_be_bytes());
+    bytes.extend_from_slice(b"IHDR");
+    bytes.extend_from_slice(&32u32.to_be_bytes());
+    bytes.extend_from_slice(&48u32.to_be_bytes());
+    bytes.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let image = Image::png(bytes);
+    assert_eq!(image.pixel_size(), Some((32, 48)));
+    assert_eq!(image.cell_size((8, 16)), (4, 3));
+}
+
+// ============================================================================
+// Render Tests
+// ============================================================================
+
+#[test]
+fn test_image_records_placement() {
+    let root = Element::col().id("root").child(
+        Element::image(red_blue())
+            .id("img")
+            .width(Size::Fixed(2))
+            .height(Size::Fixed(1)),
+    );
+    let buf = render_to_buffer(&root, 10, 5);
+
+    assert_eq!(buf.images().len(), 1);
+    assert_eq!(buf.images()[0].rect, Rect::new(0, 0, 2, 1));
+}
+
+#[test]
+fn test_image_half_block_fallback() {
+    let root = Element::col().id("root").child(
+        Element::image(red_blue())
+            .id("img")
+            .width(Size::Fixed(2))
+            .height(Size::Fixed(1)),
+    );
+    let buf = render_to_buffer(&root, 10, 5);
+
+    let cell = buf.get(0, 0).unwrap();
+    assert_eq!(cell.char, '▀');
+    let fg = cell.fg.to_rgb();
+    let bg = cell.bg.unwrap().to_rgb();
+    assert!(fg.r > 200 && fg.b < 50, "top should be red, got {:?}", fg);
+    assert!(
+        bg.b > 200 && bg.r < 50,
+        "bottom should be blue, got {:?}",
+        bg
+    );
+}
+
+#[test]
+fn test_png_fallback_shows_alt_text() {
+    let root = Element::col().id("root").child(
+        Element::image(Image::png(vec![0u8; 8]).alt("logo"))
+            .id("img")
+            .width(Size::Fixed(6))
+            .height(Size::Fixed(2)),
+    );
+    let buf = render_to_buffer(&root, 10, 5);
+
+    let text: String = (0..4).map(|x| buf.get(x, 0).unwrap().char).collect();
+    assert_eq!(text, "logo");
+}
+
+#[test]
+fn test_clipped_image_not_placed() {
+    // Image scrolled partially out of view can't be drawn by the terminal
+    let root = Element::col()
+        .id("root")
+        .height(Size::Fixed(2))
+        .overflow(Overflow::Hidden)
+        .child(Element::box_().id("spacer").height(Size::Fixed(1)))
+        .child(
+            Element::image(red_blue())
+                .id("img")
+                .width(Size::Fixed(2))
+                .height(Size::Fixed(2)),
+        );
+    let buf = render_to_buffer(&root, 10, 5);
+
+    assert!(buf.images().is_empty());
+    // Visible part still shows the fallback
+    assert_eq!(buf.get(0, 1).unwrap().char, '▀');
+}
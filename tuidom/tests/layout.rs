@@ -1,4 +1,7 @@
-use tuidom::{Align, AnimationState, Edges, Element, LayoutResult, Position, Rect, Size, Wrap};
+use tuidom::{
+    Align, AnimationState, Edges, Element, Justify, LayoutResult, Overflow, Placement, Position,
+    Rect, Size, Wrap,
+};
 
 fn layout_root(root: &Element, width: u16, height: u16) -> LayoutResult {
     let animation = AnimationState::new();
@@ -125,6 +128,137 @@ fn test_max_constrains_fill() {
     assert_eq!(child.width, 40, "max_width constrains Fill");
 }
 
+#[test]
+fn test_max_width_redistributes_grow() {
+    // Space a clamped item can't take goes to its flexible siblings
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(100))
+        .child(Element::box_().id("a").width(Size::Fill).max_width(30))
+        .child(Element::box_().id("b").width(Size::Fill));
+
+    let layout = layout_root(&root, 100, 10);
+
+    assert_eq!(layout.get("a").unwrap().width, 30);
+    assert_eq!(layout.get("b").unwrap().width, 70);
+    assert_eq!(layout.get("b").unwrap().x, 30);
+}
+
+#[test]
+fn test_min_width_redistributes_shrink() {
+    // Overflow a min-clamped item can't absorb is taken by its siblings
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(100))
+        .child(Element::box_().id("a").width(Size::Fixed(80)).min_width(70))
+        .child(Element::box_().id("b").width(Size::Fixed(80)));
+
+    let layout = layout_root(&root, 100, 10);
+
+    assert_eq!(layout.get("a").unwrap().width, 70);
+    assert_eq!(layout.get("b").unwrap().width, 30);
+}
+
+#[test]
+fn test_percent_with_max_and_fill() {
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(100))
+        .child(
+            Element::box_()
+                .id("a")
+                .width(Size::Percent(0.5))
+                .max_width(30),
+        )
+        .child(Element::box_().id("b").width(Size::Fill));
+
+    let layout = layout_root(&root, 100, 10);
+
+    assert_eq!(layout.get("a").unwrap().width, 30);
+    assert_eq!(layout.get("b").unwrap().width, 70);
+}
+
+#[test]
+fn test_min_height_affects_auto_parent() {
+    // An auto-sized parent reserves the child's min size
+    let root = Element::col()
+        .id("root")
+        .width(Size::Fixed(20))
+        .height(Size::Fixed(20))
+        .child(
+            Element::col()
+                .id("group")
+                .child(Element::text("x").id("content").min_height(4)),
+        )
+        .child(Element::text("below").id("below"));
+
+    let layout = layout_root(&root, 20, 20);
+
+    assert_eq!(layout.get("group").unwrap().height, 4);
+    assert_eq!(layout.get("below").unwrap().y, 4);
+}
+
+// ============================================================================
+// Aspect Ratio Tests
+// ============================================================================
+
+#[test]
+fn test_aspect_ratio_derives_height_from_width() {
+    // 16:9 at 40 columns: 40 / (16/9 * CELL_ASPECT) = 11.25 rows
+    let root = Element::col()
+        .id("root")
+        .width(Size::Fixed(100))
+        .height(Size::Fixed(50))
+        .child(
+            Element::box_()
+                .id("preview")
+                .width(Size::Fixed(40))
+                .aspect_ratio(16.0 / 9.0),
+        )
+        .child(Element::text("caption").id("caption"));
+
+    let layout = layout_root(&root, 100, 50);
+
+    assert_eq!(layout.get("preview").unwrap().height, 11);
+    assert_eq!(layout.get("caption").unwrap().y, 11);
+}
+
+#[test]
+fn test_aspect_ratio_derives_width_from_height() {
+    // A visual square of 10 rows is 20 columns wide
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(100))
+        .height(Size::Fixed(50))
+        .child(
+            Element::box_()
+                .id("square")
+                .height(Size::Fixed(10))
+                .aspect_ratio(1.0),
+        )
+        .child(Element::box_().id("next").width(Size::Fixed(5)));
+
+    let layout = layout_root(&root, 100, 50);
+
+    assert_eq!(layout.get("square").unwrap().width, 20);
+    assert_eq!(layout.get("next").unwrap().x, 20);
+}
+
+#[test]
+fn test_aspect_ratio_fits_available_space() {
+    // Fill width would need 50 rows; scale down to the 20 available instead
+    let root = Element::box_()
+        .id("root")
+        .width(Size::Fill)
+        .aspect_ratio(1.0);
+
+    let layout = layout_root(&root, 100, 20);
+    let rect = layout.get("root").unwrap();
+
+    assert_eq!(rect.height, 20);
+    assert_eq!(rect.width, 40);
+}
+
 // ============================================================================
 // Cross-Axis Alignment Tests
 // ============================================================================
@@ -501,6 +635,138 @@ fn test_absolute_all_anchors_stretches_both() {
     assert_eq!(child.height, 80, "height = 100 - 10 - 10");
 }
 
+// ============================================================================
+// Position::Fixed / Anchor Tests
+// ============================================================================
+
+#[test]
+fn test_fixed_ignores_containing_block() {
+    let root = Element::col().id("root").child(
+        Element::box_()
+            .id("container")
+            .margin(Edges::new(10, 0, 0, 20))
+            .width(Size::Fixed(30))
+            .height(Size::Fixed(30))
+            .child(
+                Element::box_()
+                    .id("child")
+                    .width(Size::Fixed(5))
+                    .height(Size::Fixed(5))
+                    .position(Position::Fixed)
+                    .left(2)
+                    .top(3),
+            ),
+    );
+
+    let layout = layout_root(&root, 100, 100);
+    let child = layout.get("child").unwrap();
+
+    assert_eq!((child.x, child.y), (2, 3), "fixed is relative to viewport");
+}
+
+fn popover(placement: Placement) -> Element {
+    Element::box_()
+        .id("popover")
+        .width(Size::Fixed(12))
+        .height(Size::Fixed(4))
+        .position(Position::Fixed)
+        .anchor("anchor", placement)
+}
+
+fn anchored_root(anchor_top: u16, popover: Element) -> Element {
+    Element::col()
+        .id("root")
+        .width(Size::Fixed(40))
+        .height(Size::Fixed(20))
+        .child(
+            Element::box_()
+                .id("anchor")
+                .margin(Edges::new(anchor_top, 0, 0, 30))
+                .width(Size::Fixed(6))
+                .height(Size::Fixed(1)),
+        )
+        .child(popover)
+}
+
+#[test]
+fn test_anchor_below() {
+    let layout = layout_root(&anchored_root(2, popover(Placement::Below)), 40, 20);
+    let popover = layout.get("popover").unwrap();
+
+    assert_eq!(popover.y, 3, "directly below the anchor");
+    // Anchor starts at x=30, but 30 + 12 overflows 40 so it's clamped
+    assert_eq!(popover.x, 28, "clamped to viewport");
+}
+
+#[test]
+fn test_anchor_flips_when_overflowing() {
+    // Anchor near the bottom: no room below, flips above
+    let layout = layout_root(&anchored_root(17, popover(Placement::Below)), 40, 20);
+    let popover_rect = layout.get("popover").unwrap();
+    assert_eq!(popover_rect.y, 13, "flipped above the anchor");
+
+    // Anchor near the top: no room above, flips below
+    let layout = layout_root(&anchored_root(1, popover(Placement::Above)), 40, 20);
+    let popover_rect = layout.get("popover").unwrap();
+    assert_eq!(popover_rect.y, 2, "flipped below the anchor");
+}
+
+#[test]
+fn test_anchor_left_with_offset() {
+    let root = anchored_root(5, popover(Placement::Left).anchor_offset(1));
+
+    let layout = layout_root(&root, 40, 20);
+    let popover = layout.get("popover").unwrap();
+
+    assert_eq!(popover.x, 17, "30 - 1 offset - 12 width");
+    assert_eq!(popover.y, 5, "aligned with anchor top");
+}
+
+#[test]
+fn test_anchor_inside_scrolled_container() {
+    // A popover layer (unanchored fixed wrapper) whose panel is anchored to
+    // a button in a container scrolled down by 5
+    let layer = Element::box_()
+        .id("layer")
+        .position(Position::Fixed)
+        .left(0)
+        .top(0)
+        .right(0)
+        .bottom(0)
+        .child(
+            Element::box_()
+                .id("panel")
+                .width(Size::Fixed(12))
+                .height(Size::Fixed(4))
+                .position(Position::Fixed)
+                .anchor("button", Placement::Below),
+        );
+    let root = Element::col()
+        .id("root")
+        .width(Size::Fixed(40))
+        .height(Size::Fixed(20))
+        .overflow(Overflow::Scroll)
+        .scroll_offset(0, 5)
+        .child(Element::box_().id("spacer").height(Size::Fixed(8)))
+        .child(
+            Element::box_()
+                .id("button")
+                .width(Size::Fixed(6))
+                .height(Size::Fixed(1)),
+        )
+        .child(Element::box_().id("rest").height(Size::Fixed(30)))
+        .child(layer);
+
+    let layout = layout_root(&root, 40, 20);
+    let button = layout.get("button").unwrap();
+    assert_eq!(button.y, 3, "8 - 5 scrolled");
+
+    let panel = layout.get("panel").unwrap();
+    assert_eq!(panel.y, button.bottom(), "below the scrolled anchor");
+    let layer = layout.get("layer").unwrap();
+    assert_eq!((layer.x, layer.y), (0, 0), "fixed wrapper doesn't scroll");
+}
+
 // ============================================================================
 // flex_grow Tests
 // ============================================================================
@@ -846,3 +1112,72 @@ fn test_nowrap_overflow() {
     assert_eq!(a.y, 0);
     assert_eq!(b.y, 0);
 }
+
+fn sized(id: &str, width: u16, height: u16) -> Element {
+    Element::box_()
+        .id(id)
+        .width(Size::Fixed(width))
+        .height(Size::Fixed(height))
+}
+
+#[test]
+fn test_wrap_align_within_line() {
+    // Items align within their own line, not the remaining container height
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(70))
+        .height(Size::Fixed(100))
+        .wrap(Wrap::Wrap)
+        .align(Align::Center)
+        .child(sized("a", 30, 20))
+        .child(sized("b", 30, 10))
+        .child(sized("c", 30, 20));
+
+    let layout = layout_root(&root, 70, 100);
+
+    assert_eq!(layout.get("a").unwrap().y, 0);
+    assert_eq!(layout.get("b").unwrap().y, 5, "centered in 20-high line");
+    assert_eq!(layout.get("c").unwrap().y, 20, "second line");
+}
+
+#[test]
+fn test_wrap_align_content_end() {
+    let root = Element::row()
+        .id("root")
+        .width(Size::Fixed(50))
+        .height(Size::Fixed(100))
+        .wrap(Wrap::Wrap)
+        .align_content(Justify::End)
+        .child(sized("a", 30, 20))
+        .child(sized("b", 30, 20));
+
+    let layout = layout_root(&root, 50, 100);
+
+    assert_eq!(layout.get("a").unwrap().y, 60);
+    assert_eq!(layout.get("b").unwrap().y, 80);
+}
+
+#[test]
+fn test_wrap_auto_height_grows_with_lines() {
+    // An auto-height wrapping row reserves space for all of its lines
+    let root = Element::col()
+        .id("root")
+        .width(Size::Fixed(40))
+        .height(Size::Fixed(20))
+        .child(
+            Element::row()
+                .id("tags")
+                .wrap(Wrap::Wrap)
+                .gap(1)
+                .child(sized("t1", 15, 1))
+                .child(sized("t2", 15, 1))
+                .child(sized("t3", 15, 1)),
+        )
+        .child(sized("below", 40, 1));
+
+    let layout = layout_root(&root, 40, 20);
+
+    assert_eq!(layout.get("tags").unwrap().height, 3);
+    assert_eq!(layout.get("t3").unwrap().y, 2);
+    assert_eq!(layout.get("below").unwrap().y, 3);
+}
@@ -1,7 +1,7 @@
 use tuidom::animation::AnimationState;
 use tuidom::{
-    Buffer, Color, ColorContext, Element, EmptyTheme, Oklch, Overflow, Position, Rect, Rgb, Size,
-    Style,
+    Buffer, Capabilities, Color, ColorContext, Element, EmptyTheme, Oklch, Overflow, Position,
+    Rect, Rgb, Size, Style, UnderlineStyle,
 };
 
 fn render_to_buffer(root: &Element, width: u16, height: u16) -> Buffer {
@@ -433,3 +433,356 @@ fn test_nested_overflow_hidden() {
         .unwrap_or(false);
     assert!(!outside_outer_is_red, "Content clipped by outer container");
 }
+
+// ============================================================================
+// Hyperlink Tests
+// ============================================================================
+
+#[test]
+fn test_link_text_cells_carry_link() {
+    let root = Element::col()
+        .id("root")
+        .child(Element::text("docs").id("a").link("https://example.com"))
+        .child(Element::text("plain").id("b"));
+    let buf = render_to_buffer(&root, 20, 5);
+
+    let id = buf.get(0, 0).unwrap().link.expect("link cell");
+    assert_eq!(buf.link(id), Some("https://example.com"));
+    assert_eq!(buf.get(3, 0).unwrap().link, Some(id));
+    // Cells past the text and other elements aren't linked
+    assert_eq!(buf.get(4, 0).unwrap().link, None);
+    assert_eq!(buf.get(0, 1).unwrap().link, None);
+}
+
+#[test]
+fn test_link_control_characters_escaped() {
+    // A URL that tries to close the hyperlink and clear the screen
+    let hostile = "https://example.com/\x1b]8;;\x07\x1b[2J\u{9b}1m\r\n";
+    let root = Element::text("docs").id("a").link(hostile);
+    let buf = render_to_buffer(&root, 10, 1);
+
+    let id = buf.get(0, 0).unwrap().link.expect("link cell");
+    let url = buf.link(id).unwrap();
+    assert_eq!(url, "https://example.com/%1B]8;;%07%1B[2J%C2%9B1m%0D%0A");
+    assert!(!url.chars().any(char::is_control));
+}
+
+#[test]
+fn test_link_id_stable_across_frames() {
+    let root = Element::text("docs").id("a").link("https://example.com");
+    let first = render_to_buffer(&root, 10, 1);
+    let second = render_to_buffer(&root, 10, 1);
+
+    assert_eq!(second.diff(&first).count(), 0);
+}
+
+#[test]
+fn test_hyperlink_detection() {
+    let detect = |vars: &'static [(&'static str, &'static str)]| {
+        Capabilities::detect_from(|name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+        .hyperlinks
+    };
+
+    assert!(detect(&[("TERM", "xterm-kitty")]));
+    assert!(detect(&[("TERM_PROGRAM", "iTerm.app")]));
+    assert!(detect(&[("VTE_VERSION", "6800")]));
+    assert!(!detect(&[("VTE_VERSION", "4600")]));
+    assert!(!detect(&[("TERM", "linux")]));
+    assert!(!detect(&[("TERM", "xterm-256color")]));
+    // Explicit override wins
+    assert!(!detect(&[
+        ("TERM", "xterm-kitty"),
+        ("FORCE_HYPERLINK", "0")
+    ]));
+    assert!(detect(&[
+        ("TERM", "xterm-256color"),
+        ("FORCE_HYPERLINK", "1")
+    ]));
+}
+
+#[test]
+fn test_synchronized_output_detection() {
+    let detect = |vars: &'static [(&'static str, &'static str)]| {
+        Capabilities::detect_from(|name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+        .synchronized_output
+    };
+
+    assert!(detect(&[("TERM", "xterm-kitty")]));
+    assert!(detect(&[("TERM", "foot")]));
+    assert!(detect(&[("TERM_PROGRAM", "WezTerm")]));
+    assert!(!detect(&[("TERM", "xterm-256color")]));
+    assert!(!detect(&[]));
+}
+
+// ============================================================================
+// Underline Tests
+// ============================================================================
+
+#[test]
+fn test_underline_style_and_color() {
+    let root = Element::text("oops").id("err").style(
+        Style::new()
+            .underline_style(UnderlineStyle::Curly)
+            .underline_color(Color::rgb(255, 0, 0)),
+    );
+    let buf = render_to_buffer(&root, 10, 1);
+
+    let cell = buf.get(0, 0).unwrap();
+    assert!(cell.style.underline);
+    assert_eq!(cell.style.underline_style, UnderlineStyle::Curly);
+    let color = to_rgb(cell.underline_color.expect("underline color"));
+    assert_eq!(color.r, 255);
+    // Cells without text don't get the underline color
+    assert_eq!(buf.get(5, 0).unwrap().underline_color, None);
+}
+
+#[test]
+fn test_underline_style_merge() {
+    let base = Style::new().underline_style(UnderlineStyle::Dotted);
+    let merged = base.merge(&Some(Style::new().bold()));
+    assert_eq!(merged.text_style.underline_style, UnderlineStyle::Dotted);
+
+    let merged = base.merge(&Some(Style::new().underline_style(UnderlineStyle::Double)));
+    assert_eq!(merged.text_style.underline_style, UnderlineStyle::Double);
+}
+
+// ============================================================================
+// Alpha Blending Tests
+// ============================================================================
+
+#[test]
+fn test_translucent_background_blends_with_content_below() {
+    // Half-transparent black overlay over a white box with text
+    let root = Element::box_()
+        .id("root")
+        .width(Size::Fixed(10))
+        .height(Size::Fixed(3))
+        .style(Style::new().background(Color::rgb(255, 255, 255)))
+        .child(Element::text("hi").id("text"))
+        .child(
+            Element::box_()
+                .id("shade")
+                .position(Position::Absolute)
+                .left(0)
+                .top(0)
+                .width(Size::Fixed(10))
+                .height(Size::Fixed(3))
+                .style(Style::new().background(Color::oklcha(0.0, 0.0, 0.0, 0.5))),
+        );
+    let buf = render_to_buffer(&root, 10, 3);
+
+    // Text beneath stays visible
+    assert_eq!(buf.get(0, 0).unwrap().char, 'h');
+
+    // Background is a mid gray, not white or black
+    let bg = to_rgb(buf.get(5, 1).unwrap().bg.expect("blended background"));
+    assert!(bg.r > 60 && bg.r < 200, "expected mid gray, got {:?}", bg);
+}
+
+#[test]
+fn test_opaque_background_replaces_content() {
+    let root = Element::box_()
+        .id("root")
+        .width(Size::Fixed(10))
+        .height(Size::Fixed(3))
+        .child(Element::text("hi").id("text"))
+        .child(
+            Element::box_()
+                .id("cover")
+                .position(Position::Absolute)
+                .left(0)
+                .top(0)
+                .width(Size::Fixed(10))
+                .height(Size::Fixed(3))
+                .style(Style::new().background(Color::rgb(0, 0, 255))),
+        );
+    let buf = render_to_buffer(&root, 10, 3);
+
+    assert_eq!(buf.get(0, 0).unwrap().char, ' ');
+}
+
+#[test]
+fn test_translucent_foreground_blends_with_background() {
+    let root = Element::text("x").id("text").style(
+        Style::new()
+            .background(Color::rgb(0, 0, 0))
+            .foreground(Color::rgb(255, 255, 255).alpha(0.5)),
+    );
+    let buf = render_to_buffer(&root, 5, 1);
+
+    let fg = to_rgb(buf.get(0, 0).unwrap().fg);
+    assert!(fg.r > 60 && fg.r < 200, "expected mid gray, got {:?}", fg);
+}
+
+#[test]
+fn test_oklch_blend_endpoints() {
+    let black = Oklch::new(0.0, 0.0, 0.0);
+    let red = Oklch::from_rgb(Rgb::new(255, 0, 0));
+
+    assert_eq!(black.blend(red, 1.0).to_rgb(), red.to_rgb());
+    assert_eq!(red.blend(black, 0.0).to_rgb(), red.to_rgb());
+}
+
+// ============================================================================
+// Layer Tests
+// ============================================================================
+
+fn row_text(buf: &Buffer, y: u16, width: u16) -> String {
+    (0..width).map(|x| buf.get(x, y).unwrap().char).collect()
+}
+
+fn layered_panel(layer: bool, text: &str) -> Element {
+    Element::col()
+        .id("root")
+        .width(Size::Fixed(12))
+        .height(Size::Fixed(4))
+        .style(Style::new().background(Color::rgb(0, 0, 80)))
+        .padding(tuidom::Edges::all(1))
+        .child(
+            Element::col()
+                .id("panel")
+                .layer(layer)
+                .width(Size::Fixed(8))
+                .height(Size::Fixed(2))
+                .child(Element::text(text).id("panel-text"))
+                .child(
+                    Element::text("bg")
+                        .id("panel-bg")
+                        .style(Style::new().background(Color::rgb(80, 0, 0))),
+                ),
+        )
+}
+
+#[test]
+fn test_layer_matches_direct_rendering() {
+    let direct = render_to_buffer(&layered_panel(false, "hello"), 12, 4);
+    let layered = render_to_buffer(&layered_panel(true, "hello"), 12, 4);
+
+    for y in 0..4 {
+        for x in 0..12 {
+            assert_eq!(
+                direct.get(x, y),
+                layered.get(x, y),
+                "cell ({}, {}) differs",
+                x,
+                y
+            );
+        }
+    }
+    assert_eq!(row_text(&layered, 1, 12), " hello      ");
+}
+
+#[test]
+fn test_layer_is_transparent_where_undrawn() {
+    let buf = render_to_buffer(&layered_panel(true, "hi"), 12, 4);
+
+    // Cell after "hi" inside the layer shows the root background
+    let cell = buf.get(4, 1).unwrap();
+    assert_eq!(to_rgb(cell.bg.unwrap()), Rgb::new(0, 0, 80));
+}
+
+#[test]
+fn test_layer_clipped_by_parent() {
+    let root = Element::col()
+        .id("root")
+        .width(Size::Fixed(10))
+        .height(Size::Fixed(2))
+        .overflow(Overflow::Hidden)
+        .child(
+            Element::col()
+                .id("layer")
+                .layer(true)
+                .width(Size::Fixed(10))
+                .height(Size::Fixed(4))
+                .child(Element::text("one").id("l1"))
+                .child(Element::text("two").id("l2"))
+                .child(Element::text("three").id("l3")),
+        );
+    let buf = render_to_buffer(&root, 10, 4);
+
+    assert_eq!(row_text(&buf, 0, 5), "one  ");
+    assert_eq!(row_text(&buf, 1, 5), "two  ");
+    assert_eq!(row_text(&buf, 2, 5), "     ");
+}
+
+#[test]
+fn test_nested_layers() {
+    let root = Element::col().id("root").child(
+        Element::col()
+            .id("outer")
+            .layer(true)
+            .padding(tuidom::Edges::all(1))
+            .child(
+                Element::col()
+                    .id("inner")
+                    .layer(true)
+                    .child(Element::text("deep").id("deep")),
+            ),
+    );
+    let buf = render_to_buffer(&root, 10, 4);
+    assert_eq!(row_text(&buf, 1, 6), " deep ");
+}
+
+fn render_with_cache(
+    root: &Element,
+    layers: &mut tuidom::LayerCache,
+    width: u16,
+    height: u16,
+) -> Buffer {
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(root, Rect::new(0, 0, width, height), &animation);
+    let mut buf = Buffer::new(width, height);
+    let theme = EmptyTheme;
+    let color_ctx = ColorContext::new(&theme);
+    tuidom::render::render_to_buffer_with_layers(
+        root, &layout, &mut buf, &animation, &color_ctx, layers,
+    );
+    buf
+}
+
+fn keyed_layer(text: &str, key: u64) -> Element {
+    Element::col().id("root").child(
+        Element::col()
+            .id("cached")
+            .layer_key(key)
+            .width(Size::Fixed(6))
+            .height(Size::Fixed(1))
+            .child(Element::text(text).id("cached-text")),
+    )
+}
+
+#[test]
+fn test_layer_key_reuses_buffer() {
+    let mut layers = tuidom::LayerCache::new();
+
+    let buf = render_with_cache(&keyed_layer("first", 1), &mut layers, 10, 2);
+    assert_eq!(row_text(&buf, 0, 6), "first ");
+    assert_eq!(layers.len(), 1);
+
+    // Same key: the cached pixels are used, the new text isn't rendered
+    let buf = render_with_cache(&keyed_layer("second", 1), &mut layers, 10, 2);
+    assert_eq!(row_text(&buf, 0, 6), "first ");
+
+    // New key: re-rendered
+    let buf = render_with_cache(&keyed_layer("second", 2), &mut layers, 10, 2);
+    assert_eq!(row_text(&buf, 0, 6), "second");
+}
+
+#[test]
+fn test_unused_layers_dropped() {
+    let mut layers = tuidom::LayerCache::new();
+    render_with_cache(&keyed_layer("x", 1), &mut layers, 10, 2);
+    assert_eq!(layers.len(), 1);
+
+    let plain = Element::col().id("root").child(Element::text("x").id("t"));
+    render_with_cache(&plain, &mut layers, 10, 2);
+    assert!(layers.is_empty());
+}
@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+use tuidom::animation::AnimationState;
+use tuidom::{Element, Event, Overflow, Rect, ResizeDebouncer, ScrollState, Size};
+
+// ============================================================================
+// Debounce Tests
+// ============================================================================
+
+#[test]
+fn test_resize_waits_for_settle() {
+    let mut debouncer = ResizeDebouncer::new(Duration::from_millis(50));
+    let start = Instant::now();
+
+    debouncer.push(80, 24, start);
+    assert!(debouncer.is_pending());
+    assert_eq!(debouncer.settled(start + Duration::from_millis(20)), None);
+    assert_eq!(
+        debouncer.time_left(start + Duration::from_millis(20)),
+        Some(Duration::from_millis(30))
+    );
+    assert_eq!(
+        debouncer.settled(start + Duration::from_millis(50)),
+        Some((80, 24))
+    );
+    assert!(!debouncer.is_pending());
+}
+
+#[test]
+fn test_resize_burst_reports_last_size() {
+    let mut debouncer = ResizeDebouncer::new(Duration::from_millis(50));
+    let start = Instant::now();
+
+    for (i, width) in [81, 85, 90, 100].into_iter().enumerate() {
+        let at = start + Duration::from_millis(i as u64 * 30);
+        debouncer.push(width, 30, at);
+        assert_eq!(debouncer.settled(at), None);
+    }
+
+    // Each new resize restarts the delay
+    assert_eq!(debouncer.settled(start + Duration::from_millis(120)), None);
+    assert_eq!(
+        debouncer.settled(start + Duration::from_millis(140)),
+        Some((100, 30))
+    );
+    assert_eq!(debouncer.settled(start + Duration::from_millis(500)), None);
+}
+
+#[test]
+fn test_resize_zero_delay_is_immediate() {
+    let mut debouncer = ResizeDebouncer::new(Duration::ZERO);
+    let now = Instant::now();
+    debouncer.push(10, 5, now);
+    assert_eq!(debouncer.settled(now), Some((10, 5)));
+}
+
+// ============================================================================
+// Scroll Reflow Tests
+// ============================================================================
+
+fn scroll_root(offset_y: u16) -> Element {
+    let text = (0..40)
+        .map(|i| format!("line {}", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Element::col()
+        .id("root")
+        .width(Size::Fill)
+        .height(Size::Fill)
+        .child(
+            Element::col()
+                .id("scroll")
+                .width(Size::Fill)
+                .height(Size::Fill)
+                .overflow_y(Overflow::Scroll)
+                .scroll_offset(0, offset_y)
+                .child(Element::text(text).id("text")),
+        )
+}
+
+#[test]
+fn test_reflow_keeps_scroll_proportional() {
+    let animation = AnimationState::new();
+    let mut scroll = ScrollState::new();
+
+    // Short terminal: large scroll range
+    let short = tuidom::layout::layout(&scroll_root(0), Rect::new(0, 0, 20, 10), &animation);
+    let (_, content_h) = short.content_size("scroll").unwrap();
+    let (_, viewport_h) = short.viewport_size("scroll").unwrap();
+    let old_max = content_h - viewport_h;
+    scroll.set("scroll", 0, old_max / 2);
+
+    scroll.process_events(
+        &[Event::Resize {
+            width: 20,
+            height: 30,
+        }],
+        &scroll_root(old_max / 2),
+        &short,
+    );
+
+    // Taller terminal: smaller scroll range
+    let tall = tuidom::layout::layout(&scroll_root(0), Rect::new(0, 0, 20, 30), &animation);
+    let (_, content_h) = tall.content_size("scroll").unwrap();
+    let (_, viewport_h) = tall.viewport_size("scroll").unwrap();
+    let new_max = content_h - viewport_h;
+    assert!(new_max < old_max);
+
+    assert!(scroll.apply_reflow(&tall));
+    let y = scroll.get("scroll").y;
+    let expected = (new_max as f32 * (old_max / 2) as f32 / old_max as f32).round() as u16;
+    assert_eq!(y, expected);
+
+    // Applied once only
+    assert!(!scroll.apply_reflow(&tall));
+}
+
+#[test]
+fn test_reflow_keeps_bottom_pinned() {
+    let animation = AnimationState::new();
+    let mut scroll = ScrollState::new();
+
+    let short = tuidom::layout::layout(&scroll_root(0), Rect::new(0, 0, 20, 10), &animation);
+    let (_, content_h) = short.content_size("scroll").unwrap();
+    let (_, viewport_h) = short.viewport_size("scroll").unwrap();
+    scroll.set("scroll", 0, content_h - viewport_h);
+    scroll.capture_reflow(&short);
+
+    let tall = tuidom::layout::layout(&scroll_root(0), Rect::new(0, 0, 20, 30), &animation);
+    let (_, content_h) = tall.content_size("scroll").unwrap();
+    let (_, viewport_h) = tall.viewport_size("scroll").unwrap();
+    scroll.apply_reflow(&tall);
+    assert_eq!(scroll.get("scroll").y, content_h - viewport_h);
+}
@@ -1,7 +1,13 @@
+use tuidom::animation::AnimationState;
+use tuidom::text::ansi::{self, AnsiText};
 use tuidom::text::{
-    align_offset, char_width, display_width, truncate_to_width, wrap_chars, wrap_words,
+    align_offset, char_width, display_width, grapheme_byte_index, grapheme_count, graphemes,
+    truncate_to_width, wrap_chars, wrap_words,
+};
+use tuidom::{
+    Buffer, Color, ColorContext, Element, EmptyTheme, Event, Key, LayoutResult, Modifiers, Rect,
+    Rgb, Size, Style, TextAlign, TextInputState, TextWrap, UnderlineStyle,
 };
-use tuidom::TextAlign;
 
 #[test]
 fn test_display_width_ascii() {
@@ -142,3 +148,250 @@ fn test_align_offset_text_wider_than_available() {
     assert_eq!(align_offset(15, 10, TextAlign::Center), 0);
     assert_eq!(align_offset(15, 10, TextAlign::Right), 0);
 }
+
+// ============================================================================
+// Grapheme Clusters
+// ============================================================================
+
+const FAMILY: &str = "👨\u{200d}👩\u{200d}👧";
+const FLAG: &str = "🇧🇪";
+const E_ACUTE: &str = "e\u{301}";
+
+#[test]
+fn test_display_width_grapheme_clusters() {
+    assert_eq!(display_width(FAMILY), 2);
+    assert_eq!(display_width(FLAG), 2);
+    assert_eq!(display_width(E_ACUTE), 1);
+    assert_eq!(display_width("👍🏽"), 2);
+}
+
+#[test]
+fn test_graphemes_keep_clusters_together() {
+    let text = format!("a{}{}{}", FAMILY, FLAG, E_ACUTE);
+    let clusters: Vec<&str> = graphemes(&text).collect();
+    assert_eq!(clusters, vec!["a", FAMILY, FLAG, E_ACUTE]);
+    assert_eq!(grapheme_count(&text), 4);
+    assert_eq!(grapheme_byte_index(&text, 1), 1);
+    assert_eq!(grapheme_byte_index(&text, 4), text.len());
+}
+
+#[test]
+fn test_truncate_does_not_split_cluster() {
+    let text = format!("{}{}x", FAMILY, FAMILY);
+    // Room for one emoji plus the ellipsis
+    assert_eq!(truncate_to_width(&text, 4), format!("{}…", FAMILY));
+    // Not enough room for the second emoji next to the ellipsis
+    assert_eq!(truncate_to_width(&text, 3), format!("{}…", FAMILY));
+}
+
+#[test]
+fn test_wrap_chars_does_not_split_cluster() {
+    let text = format!("{}{}{}", FLAG, FLAG, FLAG);
+    let lines = wrap_chars(&text, 5);
+    assert_eq!(lines, vec![format!("{}{}", FLAG, FLAG), FLAG.to_string()]);
+
+    let lines = wrap_chars(&format!("{}{}", E_ACUTE, E_ACUTE), 1);
+    assert_eq!(lines, vec![E_ACUTE, E_ACUTE]);
+}
+
+#[test]
+fn test_render_stores_cluster_in_one_cell() {
+    let root = Element::text(format!("{}!", FAMILY)).id("t");
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(&root, Rect::new(0, 0, 10, 1), &animation);
+    let mut buf = Buffer::new(10, 1);
+    let theme = EmptyTheme;
+    tuidom::render::render_to_buffer(
+        &root,
+        &layout,
+        &mut buf,
+        &animation,
+        &ColorContext::new(&theme),
+    );
+
+    let cell = buf.get(0, 0).unwrap();
+    let id = cell.cluster.expect("multi-codepoint cluster");
+    assert_eq!(buf.grapheme(id), Some(FAMILY));
+    assert!(buf.get(1, 0).unwrap().wide_continuation);
+    assert_eq!(buf.get(2, 0).unwrap().char, '!');
+    assert_eq!(buf.get(2, 0).unwrap().cluster, None);
+}
+
+// ============================================================================
+// Text Input Editing
+// ============================================================================
+
+fn press(state: &mut TextInputState, root: &Element, key: Key) {
+    let event = Event::Key {
+        target: Some("input".into()),
+        key,
+        modifiers: Modifiers::default(),
+    };
+    state.process_events(&[event], root, &LayoutResult::new());
+}
+
+#[test]
+fn test_text_input_edits_whole_clusters() {
+    let root = Element::text_input("").id("input");
+    let mut state = TextInputState::new();
+    state.set("input", format!("a{}b", FAMILY));
+    assert_eq!(state.get_data("input").unwrap().cursor, 3);
+
+    press(&mut state, &root, Key::Left);
+    press(&mut state, &root, Key::Backspace);
+    assert_eq!(state.get("input"), "ab");
+    assert_eq!(state.get_data("input").unwrap().cursor, 1);
+
+    press(&mut state, &root, Key::Delete);
+    assert_eq!(state.get("input"), "a");
+}
+
+#[test]
+fn test_text_input_combining_mark_joins_cluster() {
+    let root = Element::text_input("").id("input");
+    let mut state = TextInputState::new();
+    state.set("input", "e");
+
+    press(&mut state, &root, Key::Char('\u{301}'));
+    assert_eq!(state.get("input"), E_ACUTE);
+    assert_eq!(state.get_data("input").unwrap().cursor, 1);
+
+    press(&mut state, &root, Key::Backspace);
+    assert_eq!(state.get("input"), "");
+}
+
+#[test]
+fn test_text_input_replaces_non_ascii_selection() {
+    let root = Element::text_input("").id("input");
+    let mut state = TextInputState::new();
+    state.set("input", format!("{}é", FLAG));
+    state.get_data_mut("input").select_all();
+
+    press(&mut state, &root, Key::Char('x'));
+    assert_eq!(state.get("input"), "x");
+    assert_eq!(state.get_data("input").unwrap().cursor, 1);
+}
+
+// ============================================================================
+// ANSI Escape Sequences
+// ============================================================================
+
+#[test]
+fn test_ansi_parse_basic_colors() {
+    let parsed = AnsiText::parse("a\x1b[31mred\x1b[0m b");
+    assert_eq!(parsed.text, "ared b");
+    assert_eq!(parsed.style_at(0), None);
+    assert_eq!(parsed.style_at(1).unwrap().fg, Some(ansi::palette(1)));
+    assert_eq!(parsed.style_at(3).unwrap().fg, Some(ansi::palette(1)));
+    assert_eq!(parsed.style_at(4).unwrap().fg, None);
+}
+
+#[test]
+fn test_ansi_parse_extended_colors() {
+    let parsed = AnsiText::parse("\x1b[38;2;10;20;30;48;5;196mx\x1b[38:2::1:2:3my");
+    let first = parsed.style_at(0).unwrap();
+    assert_eq!(first.fg, Some(Color::rgb(10, 20, 30)));
+    assert_eq!(first.bg, Some(Color::rgb(255, 0, 0)));
+    let second = parsed.style_at(1).unwrap();
+    assert_eq!(second.fg, Some(Color::rgb(1, 2, 3)));
+    assert_eq!(second.bg, Some(Color::rgb(255, 0, 0)));
+}
+
+#[test]
+fn test_ansi_parse_attributes() {
+    let parsed = AnsiText::parse("\x1b[1;3;4:3;7mx\x1b[22;24;27my");
+    let first = parsed.style_at(0).unwrap();
+    assert!(first.text_style.bold);
+    assert!(first.text_style.italic);
+    assert!(first.text_style.underline);
+    assert_eq!(first.text_style.underline_style, UnderlineStyle::Curly);
+    assert!(first.reverse);
+
+    let second = parsed.style_at(1).unwrap();
+    assert!(!second.text_style.bold);
+    assert!(second.text_style.italic);
+    assert!(!second.text_style.underline);
+    assert!(!second.reverse);
+}
+
+#[test]
+fn test_ansi_strips_other_sequences() {
+    assert_eq!(
+        ansi::strip("\x1b]0;title\x07a\x1b[2Kb\x1b]8;;http://x\x1b\\c\x1b(Bd"),
+        "abcd"
+    );
+    assert_eq!(ansi::strip("plain"), "plain");
+}
+
+#[test]
+fn test_ansi_strips_control_characters() {
+    assert_eq!(ansi::strip("a\rb\x08c\x07d\x7fe"), "abcde");
+    // Newlines and tabs are kept
+    assert_eq!(ansi::strip("a\tb\nc"), "a\tb\nc");
+    // 8-bit CSI and OSC are dropped with their parameters
+    assert_eq!(ansi::strip("a\u{9b}2Jb\u{9d}0;title\u{9c}c\u{85}d"), "abcd");
+
+    let parsed = AnsiText::parse("\u{9b}31mred\r");
+    assert_eq!(parsed.text, "red");
+    assert_eq!(parsed.style_at(0).unwrap().fg, Some(ansi::palette(1)));
+}
+
+#[test]
+fn test_ansi_text_measured_without_escapes() {
+    let root = Element::row()
+        .id("root")
+        .child(Element::text("\x1b[32mok\x1b[0m").id("t").ansi(true));
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(&root, Rect::new(0, 0, 20, 1), &animation);
+    assert_eq!(layout.get("t").unwrap().width, 2);
+}
+
+#[test]
+fn test_ansi_render_styles_cells() {
+    let root = Element::text("\x1b[1;31mab\x1b[0m cd \x1b[7mef")
+        .id("t")
+        .ansi(true)
+        .text_wrap(TextWrap::WordWrap)
+        .width(Size::Fixed(5))
+        .height(Size::Fixed(2))
+        .style(Style::new().foreground(Color::rgb(0, 255, 0)));
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(&root, Rect::new(0, 0, 5, 3), &animation);
+    let mut buf = Buffer::new(5, 3);
+    let theme = EmptyTheme;
+    tuidom::render::render_to_buffer(
+        &root,
+        &layout,
+        &mut buf,
+        &animation,
+        &ColorContext::new(&theme),
+    );
+
+    // "ab cd" on the first line, "ef" wrapped onto the second
+    let row: String = (0..5).map(|x| buf.get(x, 0).unwrap().char).collect();
+    assert_eq!(row, "ab cd");
+
+    let a = buf.get(0, 0).unwrap();
+    assert!(a.style.bold);
+    assert_eq!(a.fg.to_rgb(), Rgb::new(205, 0, 0));
+
+    let c = buf.get(3, 0).unwrap();
+    assert!(!c.style.bold);
+    assert_eq!(c.fg.to_rgb(), Rgb::new(0, 255, 0));
+
+    // Reverse video: element foreground becomes the background
+    let e = buf.get(0, 1).unwrap();
+    assert_eq!(e.char, 'e');
+    assert_eq!(e.bg.unwrap().to_rgb(), Rgb::new(0, 255, 0));
+}
+
+#[test]
+fn test_ansi_off_prints_text_verbatim() {
+    let root = Element::text("\x1b[31mx").id("t");
+    let animation = AnimationState::new();
+    let layout = tuidom::layout::layout(&root, Rect::new(0, 0, 10, 1), &animation);
+    assert_eq!(
+        layout.get("t").unwrap().width as usize,
+        display_width("\x1b[31mx")
+    );
+}